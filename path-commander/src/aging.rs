@@ -0,0 +1,48 @@
+use std::time::{Duration, SystemTime};
+
+/// Directories untouched for longer than this are cleanup candidates
+pub const STALE_AFTER: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// How long ago a directory changed, a timestamp in the future counts as now
+pub fn age(modified: SystemTime, now: SystemTime) -> Duration {
+    now.duration_since(modified).unwrap_or_default()
+}
+
+pub fn is_stale(modified: SystemTime, now: SystemTime) -> bool {
+    age(modified, now) > STALE_AFTER
+}
+
+/// "today", "12 days", "5 months" or "2.3 years"
+pub fn describe_age(modified: SystemTime, now: SystemTime) -> String {
+    let days = age(modified, now).as_secs() / DAY;
+    match days {
+        0 => "today".to_string(),
+        1 => "1 day".to_string(),
+        2..=44 => format!("{} days", days),
+        45..=364 => format!("{} months", (days + 15) / 30),
+        _ => format!("{:.1} years", days as f64 / 365.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_age() {
+        let now = SystemTime::now();
+        let ago = |days: u64| now - Duration::from_secs(days * DAY);
+        assert_eq!(describe_age(now, now), "today");
+        assert_eq!(describe_age(now + Duration::from_secs(DAY), now), "today");
+        assert_eq!(describe_age(ago(1), now), "1 day");
+        assert_eq!(describe_age(ago(44), now), "44 days");
+        assert_eq!(describe_age(ago(45), now), "2 months");
+        assert_eq!(describe_age(ago(364), now), "12 months");
+        assert_eq!(describe_age(ago(840), now), "2.3 years");
+
+        assert!(!is_stale(ago(365), now));
+        assert!(is_stale(ago(366), now));
+    }
+}
@@ -1,19 +1,52 @@
 use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
 use crossterm::event::{
-    KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
 };
 use ratatui::{layout::Rect, widgets::ScrollbarState};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
+use crate::aging;
 use crate::backup::{self, PathBackup};
+use crate::change_report::{self, ChangeReport, Finding, ReportFormat, ScopeDiff};
+use crate::command_lookup::{self, Lookup};
+use crate::config::{self, AddPosition, Settings, MIN_DIALOG_PERCENT};
+use crate::control::{self, ControlServer};
+use crate::disk_usage::{self, DirectorySize};
+use crate::filesystem::{FileSystem, UncheckedFileSystem};
+use crate::history::{self, HistoryEntry, ScopeChange};
+use crate::hosts::{self, Host, HostInventory};
+use crate::installed_apps::{self, InstalledApp};
+use crate::instances::{Instance, Registration};
+use crate::live_env::{self, LiveEnvironment};
 use crate::path_analyzer::{
-    analyze_paths, analyze_paths_with_remote, normalize_path, to_unc_path, PathInfo,
+    analyze_paths_in, analyze_scopes_in, clean_entry, has_extended_prefix, normalize_path,
+    normalize_path_with_fs, path_exists_with_fs, recheck_entry, to_unc_path, ExpansionContext,
+    PathInfo, PathKind, PathStatus,
 };
+use crate::path_limits::{self, Advisory, Lengths, ScopeValue, Threshold};
 use crate::permissions;
-use crate::registry::{self, PathScope, RemoteConnection};
+use crate::policy::{self, Policy};
+use crate::preflight;
+use crate::process_detector::ProcessInfo;
+use crate::profile::{Feature, Profile};
+use crate::raw_editor::RawEditor;
+use crate::registry::{self, PathScope, RegistryBackend, RemoteConnection};
+use crate::release_notes::{self, Release};
+use crate::remote_queue::{QueuedWrite, RetryOutcome};
+use crate::security_scan::{self, ScanReport};
+use crate::services::ServiceAtRisk;
+use crate::shims::{self, ShimFinding};
+use crate::task::{self, BackgroundTask};
+use crate::template::{self, Variables};
 use crate::theme::Theme;
+use crate::toolchains::{self, Toolchain};
+use crate::ui::TextArea;
+use crate::updates::{self, Update};
+use crate::usage;
 
 /// Represents the connection mode of the application
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -24,7 +57,7 @@ pub enum ConnectionMode {
     Remote,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Panel {
     Machine,
     User,
@@ -44,6 +77,13 @@ impl Panel {
             Panel::User => PathScope::User,
         }
     }
+
+    pub fn from_scope(scope: PathScope) -> Self {
+        match scope {
+            PathScope::Machine => Panel::Machine,
+            PathScope::User => Panel::User,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -58,6 +98,32 @@ pub enum Mode {
     FilterMenu,
     ThemeSelection,
     FileBrowser,
+    PathDetails,
+    PathPrecedence,
+    PathLengths,
+    LiveEnvironment,
+    History,
+    SecurityReport,
+    ShimReport,
+    Toolchains,
+    AgingReport,
+    UsageSuggestions,
+    WhereResult,
+    MergeDuplicates,
+    NestedEntries,
+    BatchReplace,
+    BulkReview,
+    IgnoreList,
+    RawEditor,
+    HostPicker,
+    HostNotes,
+    MessageLog,
+    WhatsNew,
+    Locked,
+    /// Menu bar focused with no dropdown open yet, F9 like Midnight Commander
+    MenuBar {
+        active_menu: usize,
+    },
     Menu {
         active_menu: usize,
         selected_item: usize,
@@ -75,7 +141,22 @@ pub enum ConfirmAction {
     RestoreBackup,
     CreateSingleDirectory,
     CreateMarkedDirectories,
+    /// Create the directory of the selected dead entry, nothing marked
+    CreateSelectedDirectory,
+    /// Add the subdirectory holding the commands instead of the directory typed
+    UseSubdirectory,
+    /// Put back the PATH from before the last apply, and write it if confirmed
+    RevertLastApply,
     DisconnectRemote,
+    /// Connecting to a remote computer failed, try again
+    RetryRemote,
+    /// RemoteRegistry is stopped on the computer being connected to, start it
+    StartRemoteRegistry,
+    /// Writing the remote PATH failed during apply, it waits in the queue
+    RemoteWriteQueued,
+    PromoteEntry,
+    SizeAdvisory,
+    InstallUpdate,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -83,17 +164,698 @@ pub enum InputMode {
     AddPath,
     EditPath,
     ConnectRemote,
+    WhereCommand,
+    ReplaceFind,
+    ReplaceWith,
+    MigrateFrom,
+    MigrateTo,
+    ChangeReport,
+    IgnoreRule,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Panel contents a read-only profile restores after a key or click
+#[derive(Debug)]
+struct PanelSnapshot {
+    machine: Vec<String>,
+    user: Vec<String>,
+    remote_machine: Vec<String>,
+    undo_len: usize,
+    has_changes: bool,
+}
+
+impl Mode {
+    /// Feature of a launch profile this dialog belongs to, None if every profile has it
+    pub fn feature(self) -> Option<Feature> {
+        match self {
+            Mode::Confirm(
+                ConfirmAction::Exit
+                | ConfirmAction::DisconnectRemote
+                | ConfirmAction::InstallUpdate,
+            ) => None,
+            Mode::Confirm(
+                ConfirmAction::RetryRemote
+                | ConfirmAction::StartRemoteRegistry
+                | ConfirmAction::RemoteWriteQueued,
+            ) => Some(Feature::Remote),
+            Mode::Confirm(_)
+            | Mode::Input(
+                InputMode::AddPath
+                | InputMode::EditPath
+                | InputMode::ReplaceFind
+                | InputMode::ReplaceWith
+                | InputMode::MigrateFrom
+                | InputMode::MigrateTo
+                | InputMode::IgnoreRule,
+            )
+            | Mode::FileBrowser
+            | Mode::MergeDuplicates
+            | Mode::NestedEntries
+            | Mode::BatchReplace
+            | Mode::BulkReview
+            | Mode::IgnoreList
+            | Mode::RawEditor => Some(Feature::Edit),
+            Mode::Input(InputMode::ConnectRemote) | Mode::HostPicker | Mode::HostNotes => {
+                Some(Feature::Remote)
+            }
+            Mode::Input(InputMode::WhereCommand)
+            | Mode::WhereResult
+            | Mode::LiveEnvironment
+            | Mode::SecurityReport
+            | Mode::ShimReport
+            | Mode::Toolchains
+            | Mode::AgingReport
+            | Mode::UsageSuggestions => Some(Feature::Doctor),
+            Mode::Input(InputMode::ChangeReport) | Mode::History => Some(Feature::Reports),
+            Mode::BackupList => Some(Feature::Backup),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum FilterMode {
     None,
     Dead,
     Duplicates,
     NonNormalized,
     Valid,
+    Kind(PathKind),
+}
+
+impl FilterMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            FilterMode::None => "None (showing all)",
+            FilterMode::Dead => "Dead paths",
+            FilterMode::Duplicates => "Duplicates",
+            FilterMode::NonNormalized => "Non-normalized",
+            FilterMode::Valid => "Valid paths",
+            FilterMode::Kind(kind) => kind.label(),
+        }
+    }
+
+    /// Name in the header and panel badges, empty for None
+    pub fn short_label(self) -> &'static str {
+        match self {
+            FilterMode::None => "",
+            FilterMode::Dead => "Dead",
+            FilterMode::Duplicates => "Duplicates",
+            FilterMode::NonNormalized => "Non-normalized",
+            FilterMode::Valid => "Valid",
+            FilterMode::Kind(kind) => kind.label(),
+        }
+    }
+}
+
+/// Order entries are listed in, the PATH itself keeps its order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortMode {
+    PathOrder,
+    Name,
+    Status,
+}
+
+impl SortMode {
+    pub fn next(self) -> Self {
+        match self {
+            SortMode::PathOrder => SortMode::Name,
+            SortMode::Name => SortMode::Status,
+            SortMode::Status => SortMode::PathOrder,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::PathOrder => "PATH order",
+            SortMode::Name => "name",
+            SortMode::Status => "status",
+        }
+    }
+}
+
+/// List a remembered position belongs to, remote hosts each have their own
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PositionList {
+    Machine,
+    User,
+    Remote(String),
+}
+
+/// Selected entry and first listed row of a panel
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PanelPosition {
+    pub selected: usize,
+    pub scroll: usize,
+}
+
+/// Range being selected with V, everything from the anchor to the selected entry is marked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VisualSelection {
+    pub panel: Panel,
+    pub anchor: usize,
+    /// Marks the panel had before, restored when the range shrinks or is cancelled
+    pub marked_before: HashSet<usize>,
+}
+
+/// Whether edits in a panel can be written, shown as the tag in its title
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanelAccess {
+    Writable,
+    /// MACHINE edits that wait for elevation
+    Staged,
+    /// MACHINE without elevation, or any panel of a read-only profile
+    ReadOnly,
+    /// A remote write failed and waits to be tried again
+    Queued,
+}
+
+impl PanelAccess {
+    pub fn tag(self) -> &'static str {
+        match self {
+            PanelAccess::Writable => "",
+            PanelAccess::Staged => "[STAGED]",
+            PanelAccess::ReadOnly => "[READ-ONLY]",
+            PanelAccess::Queued => "[QUEUED]",
+        }
+    }
+}
+
+/// What a panel shows, the one source for its title, its header counts and the filter indicator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanelDescriptor {
+    /// MACHINE, USER, LOCAL MACHINE or REMOTE MACHINE
+    pub label: &'static str,
+    /// Letter before the panel's header counts
+    pub initial: char,
+    /// Computer the panel is read from, None for this one
+    pub host: Option<String>,
+    pub access: PanelAccess,
+    /// Filter limiting this panel, None when it is off or limits the other panel
+    pub filter: FilterMode,
+    /// The filter this panel had is switched off and can be brought back
+    pub filter_off: bool,
+    pub shown: usize,
+    pub total: usize,
+}
+
+impl PanelDescriptor {
+    /// "REMOTE MACHINE (SRV01)", or just the label for this computer
+    pub fn scope_name(&self) -> String {
+        match self.host {
+            Some(ref host) => format!("{} ({})", self.label, host),
+            None => self.label.to_string(),
+        }
+    }
+
+    /// "[Dead 2/38]" while filtered, "[filter off]" while the filter can be restored
+    pub fn badge(&self) -> String {
+        if self.filter != FilterMode::None {
+            format!(
+                "[{} {}/{}]",
+                self.filter.short_label(),
+                self.shown,
+                self.total
+            )
+        } else if self.filter_off {
+            "[filter off]".to_string()
+        } else {
+            String::new()
+        }
+    }
+}
+
+/// Clickable parts of a panel title
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TitleRegion {
+    /// Scope name, cycles the sort order
+    Label,
+    /// [STAGED], [READ-ONLY] or [QUEUED]
+    Tag,
+    /// Filter count, toggles the filter
+    Badge,
+}
+
+/// Parts of the header statistics, the counts filter their panel when clicked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderRegion {
+    /// Labels and separators
+    Label,
+    /// "REMOTE: " in remote mode
+    Remote,
+    /// The remote computer's name
+    Host,
+    Total(Panel),
+    Dead(Panel),
+    Duplicates(Panel),
+    NonNormalized(Panel),
+    Malformed(Panel),
+    Offline(Panel),
+}
+
+impl HeaderRegion {
+    /// Filter a click on the region applies and the panel it applies to,
+    /// a total shows the whole panel again
+    pub fn filter(self) -> Option<(FilterMode, Panel)> {
+        match self {
+            HeaderRegion::Total(panel) => Some((FilterMode::None, panel)),
+            HeaderRegion::Dead(panel) => Some((FilterMode::Dead, panel)),
+            HeaderRegion::Duplicates(panel) => Some((FilterMode::Duplicates, panel)),
+            HeaderRegion::NonNormalized(panel) => Some((FilterMode::NonNormalized, panel)),
+            _ => None,
+        }
+    }
+}
+
+/// Which copy of an entry listed in both scopes a merge keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeKeep {
+    Machine,
+    User,
+    Both,
+}
+
+impl MergeKeep {
+    pub fn next(self) -> Self {
+        match self {
+            MergeKeep::Machine => MergeKeep::User,
+            MergeKeep::User => MergeKeep::Both,
+            MergeKeep::Both => MergeKeep::Machine,
+        }
+    }
+
+    pub fn previous(self) -> Self {
+        self.next().next()
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MergeKeep::Machine => "Keep MACHINE",
+            MergeKeep::User => "Keep USER",
+            MergeKeep::Both => "Keep both",
+        }
+    }
+}
+
+/// An entry found in both MACHINE and USER, by index into each panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergePair {
+    pub machine: usize,
+    pub user: usize,
+    pub keep: MergeKeep,
+}
+
+/// How an entry inside another entry's directory is consolidated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NestedFix {
+    KeepBoth,
+    RemoveParent,
+    RemoveChild,
+}
+
+impl NestedFix {
+    pub fn next(self) -> Self {
+        match self {
+            NestedFix::KeepBoth => NestedFix::RemoveParent,
+            NestedFix::RemoveParent => NestedFix::RemoveChild,
+            NestedFix::RemoveChild => NestedFix::KeepBoth,
+        }
+    }
+
+    pub fn previous(self) -> Self {
+        self.next().next()
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NestedFix::KeepBoth => "Keep both",
+            NestedFix::RemoveParent => "Remove parent",
+            NestedFix::RemoveChild => "Remove child",
+        }
+    }
+}
+
+/// An entry whose directory is inside another entry's, e.g. C:\Tools and C:\Tools\bin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NestedPair {
+    pub parent: (PathScope, usize),
+    pub child: (PathScope, usize),
+    /// Whether each directory holds commands itself, which decides the suggestion
+    pub parent_has_commands: bool,
+    pub child_has_commands: bool,
+    pub fix: NestedFix,
+}
+
+/// An entry of the aging report, whose directory has a last-modified time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AgingRow {
+    pub panel: Panel,
+    pub index: usize,
+    pub modified: SystemTime,
+}
+
+/// A move the usage suggestions offer, applied if it is still included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsageRow {
+    pub panel: Panel,
+    pub suggestion: usage::Suggestion,
+    pub include: bool,
+}
+
+/// An entry the find-and-replace dialog would change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplaceRow {
+    pub scope: PathScope,
+    pub index: usize,
+    pub before: String,
+    pub after: String,
+    /// Whether the new directory exists
+    pub exists: bool,
+    /// Whether the replacement is staged when the dialog is confirmed
+    pub include: bool,
+}
+
+/// Dialogs that can be resized and moved, sizes are remembered in config.ini
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dialog {
+    Help,
+    FileBrowser,
+    PathDetails,
+    LiveEnvironment,
+    PathPrecedence,
+    PathLengths,
+    History,
+    SecurityReport,
+    ShimReport,
+    Toolchains,
+    AgingReport,
+    UsageSuggestions,
+    WhereResult,
+    MergeDuplicates,
+    NestedEntries,
+    BatchReplace,
+    BulkReview,
+    IgnoreList,
+    RawEditor,
+    HostNotes,
+    MessageLog,
+    WhatsNew,
+    ProcessRestartInfo,
+}
+
+impl Dialog {
+    /// Dialog shown in a mode, if it is one of the resizable ones
+    pub fn for_mode(mode: Mode) -> Option<Self> {
+        match mode {
+            Mode::Help => Some(Dialog::Help),
+            Mode::FileBrowser => Some(Dialog::FileBrowser),
+            Mode::PathDetails => Some(Dialog::PathDetails),
+            Mode::LiveEnvironment => Some(Dialog::LiveEnvironment),
+            Mode::PathPrecedence => Some(Dialog::PathPrecedence),
+            Mode::PathLengths => Some(Dialog::PathLengths),
+            Mode::History => Some(Dialog::History),
+            Mode::SecurityReport => Some(Dialog::SecurityReport),
+            Mode::ShimReport => Some(Dialog::ShimReport),
+            Mode::Toolchains => Some(Dialog::Toolchains),
+            Mode::AgingReport => Some(Dialog::AgingReport),
+            Mode::UsageSuggestions => Some(Dialog::UsageSuggestions),
+            Mode::WhereResult => Some(Dialog::WhereResult),
+            Mode::MergeDuplicates => Some(Dialog::MergeDuplicates),
+            Mode::NestedEntries => Some(Dialog::NestedEntries),
+            Mode::BatchReplace => Some(Dialog::BatchReplace),
+            Mode::BulkReview => Some(Dialog::BulkReview),
+            Mode::IgnoreList => Some(Dialog::IgnoreList),
+            Mode::RawEditor => Some(Dialog::RawEditor),
+            Mode::HostNotes => Some(Dialog::HostNotes),
+            Mode::MessageLog => Some(Dialog::MessageLog),
+            Mode::WhatsNew => Some(Dialog::WhatsNew),
+            Mode::ProcessRestartInfo => Some(Dialog::ProcessRestartInfo),
+            _ => None,
+        }
+    }
+
+    /// Name in the [dialogs] section of config.ini
+    pub fn key(self) -> &'static str {
+        match self {
+            Dialog::Help => "help",
+            Dialog::FileBrowser => "file_browser",
+            Dialog::PathDetails => "path_details",
+            Dialog::LiveEnvironment => "live_environment",
+            Dialog::PathPrecedence => "path_precedence",
+            Dialog::PathLengths => "path_lengths",
+            Dialog::History => "history",
+            Dialog::SecurityReport => "security_report",
+            Dialog::ShimReport => "shim_report",
+            Dialog::Toolchains => "toolchains",
+            Dialog::AgingReport => "aging_report",
+            Dialog::UsageSuggestions => "usage_suggestions",
+            Dialog::WhereResult => "where_result",
+            Dialog::MergeDuplicates => "merge_duplicates",
+            Dialog::NestedEntries => "nested_entries",
+            Dialog::BatchReplace => "batch_replace",
+            Dialog::BulkReview => "bulk_review",
+            Dialog::IgnoreList => "ignore_list",
+            Dialog::RawEditor => "raw_editor",
+            Dialog::HostNotes => "host_notes",
+            Dialog::MessageLog => "message_log",
+            Dialog::WhatsNew => "whats_new",
+            Dialog::ProcessRestartInfo => "process_restart",
+        }
+    }
+
+    /// Width and height in percent of the terminal
+    pub fn default_size(self) -> (u16, u16) {
+        match self {
+            Dialog::Help => (55, 50),
+            Dialog::ProcessRestartInfo => (70, 80),
+            Dialog::FileBrowser => (60, 60),
+            Dialog::PathDetails | Dialog::IgnoreList | Dialog::HostNotes => (60, 50),
+            Dialog::LiveEnvironment
+            | Dialog::PathPrecedence
+            | Dialog::PathLengths
+            | Dialog::History
+            | Dialog::SecurityReport
+            | Dialog::ShimReport
+            | Dialog::Toolchains
+            | Dialog::AgingReport
+            | Dialog::UsageSuggestions
+            | Dialog::WhereResult
+            | Dialog::MergeDuplicates
+            | Dialog::NestedEntries
+            | Dialog::BatchReplace
+            | Dialog::BulkReview
+            | Dialog::RawEditor
+            | Dialog::MessageLog
+            | Dialog::WhatsNew => (80, 80),
+        }
+    }
+}
+
+/// Commands offered in the key hint bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintAction {
+    Help,
+    Mark,
+    Delete,
+    Add,
+    Move,
+    Normalize,
+    Filter,
+    MarkAll,
+    Unmark,
+    Undo,
+    Redo,
+    Save,
+    Elevate,
+    Disconnect,
+    CreateDir,
+    Quit,
+    Cancel,
+}
+
+impl HintAction {
+    /// Key the command is bound to, a click on the hint sends the same key
+    pub fn key_event(self) -> KeyEvent {
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        match self {
+            HintAction::Help => KeyEvent::from(KeyCode::F(1)),
+            HintAction::Mark => KeyEvent::from(KeyCode::F(2)),
+            HintAction::Delete => KeyEvent::from(KeyCode::F(3)),
+            HintAction::Add => KeyEvent::from(KeyCode::F(4)),
+            HintAction::Move => KeyEvent::from(KeyCode::F(5)),
+            HintAction::Normalize => KeyEvent::new(KeyCode::F(9), KeyModifiers::SHIFT),
+            HintAction::Filter => KeyEvent::from(KeyCode::Char('/')),
+            HintAction::MarkAll => ctrl('a'),
+            // Uppercase means Shift is held, the way the handlers match it
+            HintAction::Unmark => ctrl('U'),
+            HintAction::Undo => ctrl('z'),
+            HintAction::Redo => ctrl('y'),
+            HintAction::Save => ctrl('s'),
+            HintAction::Elevate => ctrl('e'),
+            HintAction::Disconnect => ctrl('o'),
+            HintAction::CreateDir => KeyEvent::new(KeyCode::F(10), KeyModifiers::SHIFT),
+            HintAction::Quit => KeyEvent::from(KeyCode::F(10)),
+            HintAction::Cancel => KeyEvent::from(KeyCode::Esc),
+        }
+    }
+
+    /// Key as the hint bar shows it, F-keys by number like Midnight Commander
+    pub fn key_label(self) -> String {
+        let key = self.key_event();
+        match key.code {
+            KeyCode::F(n) if key.modifiers.contains(KeyModifiers::SHIFT) => format!("Shift+F{}", n),
+            KeyCode::F(n) => n.to_string(),
+            KeyCode::Esc => "ESC".to_string(),
+            KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if c.is_ascii_uppercase() {
+                    format!("Ctrl+Shift+{}", c)
+                } else {
+                    format!("Ctrl+{}", c.to_ascii_uppercase())
+                }
+            }
+            KeyCode::Char(c) => c.to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// A key hint and the columns it takes up in the hint bar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyHint {
+    pub action: HintAction,
+    pub key: String,
+    pub label: &'static str,
+    pub x: u16,
+}
+
+impl KeyHint {
+    pub fn width(&self) -> u16 {
+        (self.key.len() + self.label.len()) as u16
+    }
+}
+
+/// Commands offered for the selected entry in the quick actions row under the panels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuickAction {
+    Edit,
+    Delete,
+    Normalize,
+    Open,
+    Info,
+}
+
+impl QuickAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            QuickAction::Edit => "Edit",
+            QuickAction::Delete => "Delete",
+            QuickAction::Normalize => "Normalize",
+            QuickAction::Open => "Open",
+            QuickAction::Info => "Info",
+        }
+    }
+}
+
+/// A quick action button and the column it starts at, drawn as "[Label]"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuickButton {
+    pub action: QuickAction,
+    pub x: u16,
+}
+
+impl QuickButton {
+    pub fn width(&self) -> u16 {
+        self.action.label().len() as u16 + 2
+    }
+}
+
+/// Where a list dialog draws its items, shared by the renderer and the click handler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListLayout {
+    /// The whole dialog, borders included
+    pub area: Rect,
+    /// Rows the items are drawn in
+    pub rows: Rect,
+    /// Lines each item takes up
+    pub item_height: u16,
+    /// First item shown, so the selected one stays in view
+    pub offset: usize,
+    pub len: usize,
+}
+
+impl ListLayout {
+    fn new(area: Rect, rows: Rect, item_height: u16, len: usize, selected: usize) -> Self {
+        let visible = (rows.height / item_height).max(1) as usize;
+        Self {
+            area,
+            rows,
+            item_height,
+            offset: selected.saturating_sub(visible - 1),
+            len,
+        }
+    }
+
+    /// Item drawn at a screen position
+    pub fn item_at(&self, x: u16, y: u16) -> Option<usize> {
+        if x < self.rows.x || x >= self.rows.right() || y < self.rows.y || y >= self.rows.bottom() {
+            return None;
+        }
+        let index = self.offset + ((y - self.rows.y) / self.item_height) as usize;
+        (index < self.len).then_some(index)
+    }
+}
+
+/// Dialog title or border being dragged with the mouse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogDrag {
+    /// Title row grabbed at `from`, with the dialog's offset at that time
+    Move {
+        dialog: Dialog,
+        from: (u16, u16),
+        offset: (i16, i16),
+    },
+    /// Right and/or bottom border grabbed
+    Resize {
+        dialog: Dialog,
+        width: bool,
+        height: bool,
+    },
+}
+
+/// Status messages kept for the message log
+const MAX_STATUS_LOG: usize = 200;
+
+/// Smallest terminal the layout fits in, below it the screen shows a placeholder
+pub const MIN_TERMINAL_WIDTH: u16 = 80;
+pub const MIN_TERMINAL_HEIGHT: u16 = 24;
+
+pub fn terminal_too_small(area: Rect) -> bool {
+    area.width < MIN_TERMINAL_WIDTH || area.height < MIN_TERMINAL_HEIGHT
+}
+
+/// Problems first, so sorting by status groups what needs attention
+fn status_rank(status: PathStatus) -> u8 {
+    match status {
+        PathStatus::Insecure => 0,
+        PathStatus::Dead => 1,
+        PathStatus::DeadDuplicate => 2,
+        PathStatus::Duplicate => 3,
+        PathStatus::Malformed => 4,
+        PathStatus::OfflineDrive => 5,
+        PathStatus::Removable => 6,
+        PathStatus::NonNormalized => 7,
+        PathStatus::Valid => 8,
+        PathStatus::Unchecked => 9,
+    }
+}
+
+/// Entries { and } stop at: what F7, F8, Shift+F9 and Ctrl+L would clean up
+fn needs_cleanup(info: &PathInfo) -> bool {
+    info.is_dead() || info.is_duplicate || info.needs_normalization || info.is_malformed
 }
 
+/// Number of entries in the filter menu: five status filters, then one per PathKind
+const FILTER_MENU_ITEMS: usize = 5 + PathKind::ALL.len();
+
 /// Represents a directory entry in the file browser
 #[derive(Debug, Clone)]
 pub struct DirectoryEntry {
@@ -131,6 +893,12 @@ pub enum Operation {
         index1: usize,
         index2: usize,
     },
+    /// One entry moved several places by repeated swaps, recorded as a single step
+    MoveEntry {
+        panel: Panel,
+        from: usize,
+        to: usize,
+    },
     /// Move paths from one panel to another
     MovePaths {
         from_panel: Panel,
@@ -150,6 +918,206 @@ pub enum Operation {
         panel: Panel,
         changes: Vec<(usize, String, String)>, // (index, old_path, new_path)
     },
+    /// Edit touching both panels at once (demoting or promoting entries)
+    /// - stores both panels before and after
+    ReplacePanels {
+        machine_before: Vec<String>,
+        user_before: Vec<String>,
+        machine_after: Vec<String>,
+        user_after: Vec<String>,
+    },
+}
+
+impl Operation {
+    /// Rough memory taken by the operation: the path text it keeps
+    fn size(&self) -> usize {
+        let paths =
+            |paths: &[(usize, String)]| -> usize { paths.iter().map(|(_, path)| path.len()).sum() };
+        let lists = |lists: &[&Vec<String>]| -> usize {
+            lists
+                .iter()
+                .flat_map(|list| list.iter())
+                .map(String::len)
+                .sum()
+        };
+        let text = match self {
+            Operation::DeletePaths { deleted, .. } => paths(deleted),
+            Operation::AddPath { path, .. } => path.len(),
+            Operation::EditPath {
+                old_path, new_path, ..
+            } => old_path.len() + new_path.len(),
+            Operation::SwapPaths { .. } | Operation::MoveEntry { .. } => 0,
+            Operation::MovePaths {
+                paths_with_indices, ..
+            }
+            | Operation::CopyPaths {
+                paths_with_indices, ..
+            } => paths(paths_with_indices),
+            Operation::NormalizePaths { changes, .. } => changes
+                .iter()
+                .map(|(_, old, new)| old.len() + new.len())
+                .sum(),
+            Operation::ReplacePanels {
+                machine_before,
+                user_before,
+                machine_after,
+                user_after,
+            } => lists(&[machine_before, user_before, machine_after, user_after]),
+        };
+        std::mem::size_of::<Operation>() + text
+    }
+
+    /// The two operations as one step, if `next` continues what `self` did:
+    /// editing the same entry again, or moving the same entry further
+    fn merge(&self, next: &Operation) -> Option<Operation> {
+        match (self, next) {
+            (
+                Operation::EditPath {
+                    panel,
+                    index,
+                    old_path,
+                    new_path,
+                },
+                Operation::EditPath {
+                    panel: next_panel,
+                    index: next_index,
+                    old_path: next_old,
+                    new_path: next_new,
+                },
+            ) if panel == next_panel && index == next_index && new_path == next_old => {
+                Some(Operation::EditPath {
+                    panel: *panel,
+                    index: *index,
+                    old_path: old_path.clone(),
+                    new_path: next_new.clone(),
+                })
+            }
+            // A swap with a neighbour moves the entry one place, so a chain of them
+            // moving the same entry is one move from where it started
+            (
+                Operation::SwapPaths {
+                    panel,
+                    index1: from,
+                    index2: to,
+                }
+                | Operation::MoveEntry { panel, from, to },
+                Operation::SwapPaths {
+                    panel: next_panel,
+                    index1,
+                    index2,
+                },
+            ) if panel == next_panel && index1 == to && index1.abs_diff(*index2) == 1 => {
+                Some(Operation::MoveEntry {
+                    panel: *panel,
+                    from: *from,
+                    to: *index2,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the operation leaves the panels as they were, e.g. an entry moved back
+    fn is_noop(&self) -> bool {
+        match self {
+            Operation::EditPath {
+                old_path, new_path, ..
+            } => old_path == new_path,
+            Operation::MoveEntry { from, to, .. } => from == to,
+            _ => false,
+        }
+    }
+}
+
+/// Move the entry at `from` to `to`, shifting the ones between
+fn move_entry(paths: &mut Vec<String>, from: usize, to: usize) {
+    if from < paths.len() && to < paths.len() {
+        let path = paths.remove(from);
+        paths.insert(to, path);
+    }
+}
+
+/// Preview of moving an entry to the other scope and cleaning up its duplicates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Promotion {
+    pub from: Panel,
+    pub entry: String,
+    /// Copies removed from the source panel (index, entry), the promoted one included
+    pub removed_from_source: Vec<(usize, String)>,
+    /// A copy already in the target panel, kept in place instead of adding another
+    pub kept_in_target: Option<usize>,
+    /// Further copies in the target panel, redundant once one is kept
+    pub removed_from_target: Vec<(usize, String)>,
+}
+
+/// Workflow a startup flag opens the TUI in, for installers to link users straight to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StartupFlow {
+    /// `--add DIR`: the Add Path dialog with the directory filled in
+    Add(String),
+    /// `--prune-dead`: dead entries marked and their delete confirmation shown
+    PruneDead,
+    /// `--dedupe`: duplicate entries marked and their delete confirmation shown
+    Dedupe,
+}
+
+/// The last local apply of the session that wrote something: the backup taken before it
+/// and the scopes it actually wrote
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LastApply {
+    pub backup: PathBuf,
+    pub user: bool,
+    pub machine: bool,
+}
+
+impl LastApply {
+    /// "USER", "MACHINE" or "USER and MACHINE"
+    pub fn scopes(&self) -> &'static str {
+        match (self.user, self.machine) {
+            (true, true) => "USER and MACHINE",
+            (false, true) => "MACHINE",
+            _ => "USER",
+        }
+    }
+}
+
+/// Entries a delete-all will remove from each scope, worked out before confirming
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BulkDelete {
+    pub action: ConfirmAction,
+    /// (index, entry) pairs removed from MACHINE
+    pub machine: Vec<(usize, String)>,
+    /// (index, entry) pairs removed from USER
+    pub user: Vec<(usize, String)>,
+}
+
+impl BulkDelete {
+    pub fn len(&self) -> usize {
+        self.machine.len() + self.user.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every entry with its scope, MACHINE first
+    pub fn entries(&self) -> impl Iterator<Item = (PathScope, &String)> {
+        self.machine
+            .iter()
+            .map(|(_, entry)| (PathScope::Machine, entry))
+            .chain(self.user.iter().map(|(_, entry)| (PathScope::User, entry)))
+    }
+
+    /// Only the entries whose flag is set, flags in the order of entries()
+    fn retain_checked(mut self, checked: &[bool]) -> Self {
+        let machine_count = self.machine.len();
+        let mut flags = checked.iter();
+        self.machine
+            .retain(|_| flags.next().copied().unwrap_or(false));
+        let mut flags = checked.iter().skip(machine_count);
+        self.user.retain(|_| flags.next().copied().unwrap_or(false));
+        self
+    }
 }
 
 pub struct App {
@@ -182,21 +1150,138 @@ pub struct App {
     pub backup_selected: usize,
     pub machine_scrollbar_state: ScrollbarState,
     pub user_scrollbar_state: ScrollbarState,
+    pub machine_scroll: usize, // First listed row of the MACHINE panel
+    pub user_scroll: usize,    // First listed row of the USER panel
+    pub remote_scroll: usize,  // First listed row of the remote MACHINE panel
+    pub remote_positions: HashMap<String, PanelPosition>, // Last position per remote host
+    pub filter_positions: HashMap<(PositionList, FilterMode), PanelPosition>, // Last position per filter
+    pub bookmarks: HashMap<(PositionList, u8), PanelPosition>, // Positions saved with m1..m9
+    pending_bookmark: bool, // m was pressed, the next digit saves a bookmark
+    pub visual: Option<VisualSelection>,
     pub should_exit: bool,
     pub viewport_height: u16,
     pub pending_directory: String, // Temporarily stores path for directory creation confirmation
-    pub processes_to_restart: Vec<String>, // List of processes that need restarting to pick up PATH changes
-    pub theme: Theme,                      // Color theme for UI rendering
+    /// Entry typed in Add Path and the subdirectory with commands offered instead
+    pub pending_subdirectory: Option<(String, String)>,
+    pub pending_promotion: Option<Promotion>, // Previewed move to the other scope awaiting confirmation
+    pub pending_bulk_delete: Option<BulkDelete>, // Previewed bulk delete awaiting confirmation or review
+    pub review_checked: Vec<bool>, // Entries of the reviewed bulk delete that will go, in BulkDelete::entries order
+    pub review_selected: usize,    // Selected entry in the review list
+    pub processes_to_restart: Vec<ProcessInfo>, // List of processes that need restarting to pick up PATH changes
+    pub process_selected: usize, // Process whose details the restart dialog shows, among the listed ones
+    pub hide_services: bool,     // Leave service processes out of the restart dialog
+    pub services_at_risk: Vec<ServiceAtRisk>, // Services whose binaries are in MACHINE directories the last apply removed
+    installed_apps: Option<Vec<InstalledApp>>, // Uninstall data, read the first time a delete is confirmed
+    pub delete_owners: Vec<(String, String)>, // Marked entries inside an installed program's directory, with the program
+    pub theme: Theme,                         // Color theme for UI rendering
     pub theme_arg: Option<String>, // Original theme argument from command line (for elevation)
     pub filter_mode: FilterMode,   // Current filter mode (None, Dead, Duplicates, etc.)
-    pub filter_menu_selected: usize, // Selected item in filter menu (0-4)
+    pub last_filter: FilterMode, // Filter switched off from the panel title, restored by clicking again
+    pub filter_panel: Option<Panel>, // Panel the filter is limited to, None filters both
+    pub sort_mode: SortMode,     // Order the panels list entries in
+    pub filter_menu_selected: usize, // Selected item in filter menu (see FILTER_MENU_ITEMS)
     pub theme_list: Vec<(String, bool)>, // List of available themes (name, is_builtin)
-    pub theme_selected: usize,     // Selected theme in the theme selector
+    pub theme_selected: usize,   // Selected theme in the theme selector
     pub original_theme: Option<Theme>, // Theme before opening theme selector (for Esc cancellation)
     pub undo_stack: Vec<Operation>, // Stack of undoable operations
     pub redo_stack: Vec<Operation>, // Stack of redoable operations
+    pub availability_report: Option<String>, // Result of the file availability check in Path Details
+    pub availability_task: Option<BackgroundTask<String>>, // Availability check still running
+    pub directory_sizes: HashMap<String, Result<DirectorySize, String>>, // Measured in Path Details, by directory
+    pub size_task: Option<BackgroundTask<(String, Result<DirectorySize, String>)>>, // Directory being measured
+    pub security_task: Option<BackgroundTask<ScanReport>>, // Security scan still running
+    pub update_task: Option<BackgroundTask<Result<Option<Update>>>>, // Update check still running
+    pub update_check_quiet: bool, // The running update check only reports a newer release
+    pub update: Option<Update>,   // Newer release found by the last update check
+    pub download_task: Option<BackgroundTask<Result<String>>>, // Update download still running
+    pub remote_task: Option<BackgroundTask<RemoteRead>>, // Remote connection still being made
+    pub connecting_to: Option<String>, // Computer remote_task connects to
+    pub failed_remote: Option<(String, String)>, // Computer and error the retry dialog offers to try again
+    pub stop_registry_on_disconnect: Option<String>, // Computer whose RemoteRegistry was only started for this session
+    pub queued_remote_write: Option<QueuedWrite>, // Remote PATH a failed apply couldn't write yet
+    pub remote_write_task: Option<BackgroundTask<Result<RetryOutcome>>>, // Queued write being tried again
+    pub update_staged: Option<String>, // Version downloaded and waiting for the next start
+    pub control: Option<ControlServer>, // Local control pipe, when enabled in config.ini
+    pub security_report: Option<ScanReport>, // Result of the last security scan
+    pub security_scroll: u16,          // Scroll offset of the Security Scan dialog
+    pub shim_task: Option<BackgroundTask<Vec<ShimFinding>>>, // Shim check still running
+    pub shim_findings: Vec<ShimFinding>, // Result of the last package manager shim check
+    pub shim_scroll: u16,              // Scroll offset of the shim report
+    pub toolchain_task: Option<BackgroundTask<Vec<Toolchain>>>, // Toolchain scan still running
+    pub toolchains: Vec<Toolchain>,    // Result of the last toolchain scan
+    pub toolchain_scroll: u16,         // Scroll offset of the toolchains dialog
+    pub where_lookup: Lookup,          // Last command looked up with w
+    pub where_scroll: u16,             // Scroll offset of the lookup result
+    pub merge_pairs: Vec<MergePair>,   // Entries in both scopes, shown by the merge dialog
+    pub merge_selected: usize,         // Selected pair in the merge dialog
+    pub nested_pairs: Vec<NestedPair>, // Entries inside another entry, shown by the nested dialog
+    pub nested_selected: usize,
+    pub aging_rows: Vec<AgingRow>, // Entries oldest first, shown by the aging report
+    pub aging_selected: usize,
+    pub usage_rows: Vec<UsageRow>, // Moves suggested by how often each directory ran commands
+    pub usage_selected: usize,
+    pub usage_days: i64, // Days the usage log behind the suggestions covers
+    pub ignore_selected: usize, // Rule selected in the Ignore List dialog
+    pub replace_find: String, // Text last searched for by find and replace
+    pub replace_with: String, // Text last used as its replacement
+    pub replace_rows: Vec<ReplaceRow>, // Entries the find-and-replace dialog would change
+    pub replace_selected: usize, // Selected row in the find-and-replace dialog
+    pub replace_migration: bool, // The dialog previews a drive migration rather than a text replace
+    pub raw_editor: Option<RawEditor>, // Whole PATH value of a panel being edited as text
+    pub raw_editor_panel: Panel, // Panel the raw editor puts its entries back in
+    pub clipboard: String, // Cut or copied in a text editor, shared by all of them
+    pub host_list: Vec<Host>, // Remote computers in the Connect to picker, as last loaded
+    pub host_selected: usize, // Selected host in the picker
+    pub host_notes: Option<TextArea>, // Notes of the selected host being edited
+    pub menu_submenu: Option<usize>, // Selected item of the open submenu, if one is open
+    pub precedence_scroll: u16, // Scroll offset of the PATH precedence overlay
+    pub lengths_scroll: u16, // Scroll offset of the PATH Length dialog
+    pub live_environment: LiveEnvironment, // Process and volatile PATH shown for comparison
+    pub live_scroll: u16, // Scroll offset of the live environment dialog
+    pub size_advisory: Advisory, // Length limits the pending apply runs into
+    size_acknowledged: bool, // The size advisory was accepted for this apply
+    pub apply_user: bool, // Apply dialog: write USER changes
+    pub apply_machine: bool, // Apply dialog: write (local) MACHINE changes
+    pub apply_remote: bool, // Apply dialog: write remote MACHINE changes
+    pub delete_unavailable: bool, // Delete-all-dead dialog: include entries on unavailable drives
+    pub fs: Box<dyn FileSystem>, // Filesystem used for existence checks (faked in tests)
+    pub registry: Box<dyn RegistryBackend>, // Local PATH storage (mocked in tests)
+    pub machine_helper: Box<dyn RegistryBackend>, // MACHINE writes through the elevated helper
+    pub last_written: HashMap<PathScope, DateTime<Local>>, // When each local scope was last written
+    pub external_changes: Vec<PathScope>, // Local scopes written by someone else since we read them
+    pub instance: Option<Registration>, // This session's entry in ~/.pc/instances
+    pub other_instances: Vec<Instance>, // Other Path Commanders running on this computer
+    pub backup_dir: PathBuf, // Where backups are saved before applying changes
+    pub last_apply: Option<LastApply>, // Last local apply of this session that wrote something
+    pub pending_revert: Option<PathBackup>, // Revert Last Apply: the backup being confirmed
+    pub history_file: PathBuf, // Append-only log of applied changes
+    pub usage_file: PathBuf, // Commands run and their directories, from the usage hook
+    pub variables_file: PathBuf, // Values for ${NAME} placeholders in restored backups
+    pub settings_file: PathBuf, // config.ini, where resized dialogs save their size
+    pub hosts_file: PathBuf, // hosts.toml, the remote computers File > Connect to offers
+    pub refresh_file: Option<PathBuf>, // Created after a local apply for a `pc refresh-hook` wrapper
+    pub dialog_offsets: HashMap<Dialog, (i16, i16)>, // Dialogs moved away from the center, for this session
+    pub dialog_drag: Option<DialogDrag>,             // Title or border being dragged
+    pub history: Vec<HistoryEntry>, // Entries shown in the History dialog, newest first
+    pub history_scroll: u16,        // Scroll offset of the History dialog
+    pub status_log: Vec<(String, String)>, // Time and text of recent status messages, oldest first
+    pub message_log_scroll: u16,    // Scroll offset of the Message Log dialog
+    pub releases: Vec<Release>,     // Release notes shown by What's New, newest first
+    pub release_selected: usize,    // Release the What's New dialog shows
+    pub release_scroll: u16,        // Scroll offset within that release's notes
+    pub hovered: Option<(Panel, usize)>, // Entry under the mouse pointer, echoed in the status bar
+    pub settings: Settings,         // Options from ~/.pc/config.ini
+    pub add_position: AddPosition,  // Where the Add Path dialog inserts the new entry
+    pub add_target: Option<Panel>,  // Panel the Add Path dialog adds to, None for the active one
+    pub report_format: ReportFormat, // File type the change summary dialog writes
+    elevation_offered: bool,        // Elevation was already offered for a staged MACHINE edit
+    last_activity: std::time::Instant, // Last key or mouse event, for the idle lock
+    locked_mode: Mode,              // Mode to return to when the idle lock is lifted
+    pub policy: Policy,             // Machine-wide locked/denied prefixes
+    pub profile: Profile,           // Features the --profile this session started with allows
     last_click_time: std::time::Instant, // Time of last mouse click for double-click detection
     last_click_pos: (Panel, usize), // Panel and row of last click
+    last_list_click: Option<(Mode, usize, std::time::Instant)>, // Dialog list item clicked last, for double-clicks
     mode_enter_time: std::time::Instant, // Time when current mode was entered (for buffering protection)
     // File browser state
     pub file_browser_current_path: PathBuf, // Current directory being browsed
@@ -206,21 +1291,41 @@ pub struct App {
 }
 
 impl App {
-    pub fn new(theme: Theme, theme_arg: Option<String>) -> Result<Self> {
+    pub fn new(theme: Theme, theme_arg: Option<String>, fs: Box<dyn FileSystem>) -> Result<Self> {
+        Self::with_registry(theme, theme_arg, registry::local_backend(), fs)
+    }
+
+    /// Create a new App reading and writing PATH through the given registry backend,
+    /// checking entries against the given filesystem
+    pub fn with_registry(
+        theme: Theme,
+        theme_arg: Option<String>,
+        registry: Box<dyn RegistryBackend>,
+        fs: Box<dyn FileSystem>,
+    ) -> Result<Self> {
         let is_admin = permissions::is_admin();
 
         // Read paths from registry
-        let user_path_string = registry::read_path(PathScope::User)?;
-        let machine_path_string = registry::read_path(PathScope::Machine)?;
-
-        let user_paths = registry::parse_path(&user_path_string);
-        let machine_paths = registry::parse_path(&machine_path_string);
+        let user_paths = registry.read_paths(PathScope::User)?;
+        let machine_paths = registry.read_paths(PathScope::Machine)?;
 
         // Analyze paths
-        let user_info = analyze_paths(&user_paths, &machine_paths);
-        let machine_info = analyze_paths(&machine_paths, &user_paths);
+        let settings = Settings::load_default();
+        let (machine_info, user_info) = analyze_scopes_in(
+            &machine_paths,
+            &user_paths,
+            &*fs,
+            &expansion_context(&settings, false),
+        );
+
+        // A broken policy file is reported instead of keeping pc from starting
+        let mut status_message = permissions::get_privilege_message();
+        let policy = Policy::load(&policy::default_path()).unwrap_or_else(|e| {
+            status_message = format!("Policy ignored: {:#}", e);
+            Policy::default()
+        });
 
-        Ok(Self {
+        let mut app = Self {
             connection_mode: ConnectionMode::Local,
             remote_connection: None,
             machine_scrollbar_state: ScrollbarState::new(machine_paths.len()).position(0),
@@ -237,7 +1342,10 @@ impl App {
             remote_machine_original: Vec::new(),
             remote_machine_selected: 0,
             remote_machine_marked: HashSet::new(),
-            active_panel: Panel::Machine,
+            active_panel: settings
+                .start_panel
+                .map(Panel::from_scope)
+                .unwrap_or(Panel::Machine),
             machine_selected: 0,
             user_selected: 0,
             machine_marked: HashSet::new(),
@@ -245,43 +1353,180 @@ impl App {
             mode: Mode::Normal,
             is_admin,
             has_changes: false,
-            status_message: permissions::get_privilege_message(),
+            status_message: String::new(),
             input_buffer: String::new(),
             backup_list: Vec::new(),
             backup_selected: 0,
+            machine_scroll: 0,
+            user_scroll: 0,
+            remote_scroll: 0,
+            remote_positions: HashMap::new(),
+            filter_positions: HashMap::new(),
+            bookmarks: HashMap::new(),
+            pending_bookmark: false,
+            visual: None,
             should_exit: false,
             viewport_height: 10, // Default, will be updated based on terminal size
             pending_directory: String::new(),
+            pending_subdirectory: None,
+            pending_promotion: None,
+            pending_bulk_delete: None,
+            review_checked: Vec::new(),
+            review_selected: 0,
             processes_to_restart: Vec::new(),
+            process_selected: 0,
+            hide_services: false,
+            services_at_risk: Vec::new(),
+            installed_apps: None,
+            delete_owners: Vec::new(),
             theme,
             theme_arg,
             filter_mode: FilterMode::None,
+            last_filter: FilterMode::None,
+            filter_panel: None,
+            sort_mode: SortMode::PathOrder,
             filter_menu_selected: 0,
             theme_list: Vec::new(), // Will be populated when theme selector is opened
             theme_selected: 0,
             original_theme: None,
             undo_stack: Vec::new(),
             redo_stack: Vec::new(),
+            availability_report: None,
+            availability_task: None,
+            directory_sizes: HashMap::new(),
+            size_task: None,
+            security_task: None,
+            update_task: None,
+            remote_task: None,
+            connecting_to: None,
+            failed_remote: None,
+            stop_registry_on_disconnect: None,
+            queued_remote_write: None,
+            remote_write_task: None,
+            update_check_quiet: false,
+            update: None,
+            download_task: None,
+            update_staged: None,
+            control: None,
+            security_report: None,
+            security_scroll: 0,
+            shim_task: None,
+            shim_findings: Vec::new(),
+            shim_scroll: 0,
+            toolchain_task: None,
+            toolchains: Vec::new(),
+            toolchain_scroll: 0,
+            where_lookup: Lookup::default(),
+            where_scroll: 0,
+            merge_pairs: Vec::new(),
+            merge_selected: 0,
+            nested_pairs: Vec::new(),
+            nested_selected: 0,
+            aging_rows: Vec::new(),
+            aging_selected: 0,
+            usage_rows: Vec::new(),
+            usage_selected: 0,
+            usage_days: 0,
+            ignore_selected: 0,
+            replace_find: String::new(),
+            replace_with: String::new(),
+            replace_rows: Vec::new(),
+            replace_selected: 0,
+            replace_migration: false,
+            raw_editor: None,
+            raw_editor_panel: Panel::Machine,
+            clipboard: String::new(),
+            host_list: Vec::new(),
+            host_selected: 0,
+            host_notes: None,
+            menu_submenu: None,
+            precedence_scroll: 0,
+            lengths_scroll: 0,
+            live_environment: LiveEnvironment::default(),
+            live_scroll: 0,
+            size_advisory: Advisory::default(),
+            size_acknowledged: false,
+            apply_user: true,
+            delete_unavailable: false,
+            apply_machine: true,
+            apply_remote: true,
+            fs,
+            registry,
+            machine_helper: Box::new(crate::elevation::HelperRegistry),
+            last_written: HashMap::new(),
+            external_changes: Vec::new(),
+            instance: None,
+            other_instances: Vec::new(),
+            backup_dir: backup::get_default_backup_dir(),
+            last_apply: None,
+            pending_revert: None,
+            history_file: crate::config::get_history_file()
+                .unwrap_or_else(|_| PathBuf::from("history.jsonl")),
+            usage_file: crate::config::get_usage_file()
+                .unwrap_or_else(|_| PathBuf::from("usage.log")),
+            variables_file: crate::config::get_variables_file()
+                .unwrap_or_else(|_| PathBuf::from("variables.ini")),
+            settings_file: crate::config::get_settings_file()
+                .unwrap_or_else(|_| PathBuf::from("config.ini")),
+            hosts_file: crate::config::get_hosts_file()
+                .unwrap_or_else(|_| PathBuf::from("hosts.toml")),
+            refresh_file: std::env::var_os(crate::cli::REFRESH_FILE_VAR).map(PathBuf::from),
+            dialog_offsets: HashMap::new(),
+            dialog_drag: None,
+            history: Vec::new(),
+            history_scroll: 0,
+            status_log: Vec::new(),
+            message_log_scroll: 0,
+            releases: Vec::new(),
+            release_selected: 0,
+            release_scroll: 0,
+            hovered: None,
+            add_position: settings.add_position,
+            report_format: settings.report_format,
+            add_target: None,
+            elevation_offered: false,
+            last_activity: std::time::Instant::now(),
+            locked_mode: Mode::Normal,
+            settings,
+            policy,
+            profile: Profile::default(),
             last_click_time: std::time::Instant::now(),
             last_click_pos: (Panel::Machine, 0),
+            last_list_click: None,
             mode_enter_time: std::time::Instant::now(),
             file_browser_current_path: std::env::current_dir()
                 .unwrap_or_else(|_| PathBuf::from("C:\\")),
             file_browser_entries: Vec::new(),
             file_browser_selected: 0,
             file_browser_scrollbar_state: ScrollbarState::new(0).position(0),
-        })
+        };
+        app.set_status(&status_message);
+        if !app.fs.is_checked() {
+            app.set_status(
+                "Filesystem checks are off - entries are edited as text, none are checked",
+            );
+        }
+        app.refresh_last_written();
+        app.track_dead_entries();
+        Ok(app)
     }
 
-    /// Create a new App with a remote connection
-    pub fn new_with_remote(
-        theme: Theme,
-        theme_arg: Option<String>,
-        remote_computer: &str,
-    ) -> Result<Self> {
-        let mut app = Self::new(theme, theme_arg)?;
-        app.connect_to_remote(remote_computer)?;
-        Ok(app)
+    /// Re-read when the local USER and MACHINE storage was last written
+    pub fn refresh_last_written(&mut self) {
+        self.last_written = [PathScope::User, PathScope::Machine]
+            .into_iter()
+            .filter_map(|scope| Some((scope, self.registry.last_modified(scope)?.into())))
+            .collect();
+    }
+
+    /// Last write of the local storage shown in a panel, None for the remote panel
+    pub fn last_written_for(&self, panel: Panel) -> Option<(PathScope, DateTime<Local>)> {
+        let scope = match (self.connection_mode, panel) {
+            (ConnectionMode::Local, Panel::User) => PathScope::User,
+            (_, Panel::Machine) => PathScope::Machine,
+            (ConnectionMode::Remote, Panel::User) => return None,
+        };
+        self.last_written.get(&scope).map(|time| (scope, *time))
     }
 
     /// Restore App from an elevation state (after UAC elevation)
@@ -290,7 +1535,8 @@ impl App {
         state: crate::elevation::ElevationState,
     ) -> Result<Self> {
         // Create a new app with the theme
-        let mut app = Self::new(theme, state.theme_arg.clone())?;
+        let fs = crate::filesystem::for_session(state.no_fs_checks);
+        let mut app = Self::new(theme, state.theme_arg.clone(), fs)?;
 
         // Restore all state from elevation
         app.connection_mode = state.connection_mode;
@@ -305,8 +1551,12 @@ impl App {
         app.user_marked = state.user_marked;
         app.remote_machine_marked = state.remote_machine_marked;
         app.filter_mode = state.filter_mode;
+        app.filter_panel = state.filter_panel;
         app.input_buffer = state.input_buffer;
         app.pending_directory = state.pending_directory;
+        if let Some(ref profile) = state.profile {
+            app.profile = Profile::resolve(profile, &app.settings)?;
+        }
 
         // Restore remote connection if in remote mode
         if app.connection_mode == ConnectionMode::Remote {
@@ -328,19 +1578,28 @@ impl App {
         Ok(app)
     }
 
-    /// Connect to a remote computer
+    /// Connect to a remote computer, blocking until its registry answers
     pub fn connect_to_remote(&mut self, computer_name: &str) -> Result<()> {
-        // Establish remote connection
-        let connection = RemoteConnection::connect(computer_name)?;
+        let (connection, remote_paths) = connect_and_read(computer_name)?;
+        self.use_remote_connection(connection, remote_paths);
+        Ok(())
+    }
 
-        // Read remote MACHINE paths
-        let remote_path_string = registry::read_path_remote(PathScope::Machine, &connection)?;
-        let remote_paths = registry::parse_path(&remote_path_string);
+    /// Switch to remote mode with a connection and the remote MACHINE entries read through it
+    fn use_remote_connection(&mut self, connection: RemoteConnection, remote_paths: Vec<String>) {
+        let computer_name = connection.computer_name().to_string();
 
         // Analyze remote paths (compare with local machine paths for cross-scope duplicates)
-        let remote_info = analyze_paths(&remote_paths, &self.machine_paths);
+        let remote_info = analyze_paths_in(
+            &remote_paths,
+            &self.machine_paths,
+            Some(connection.computer_name()),
+            &*self.fs,
+            &expansion_context(&self.settings, true),
+        );
 
         // Update app state to remote mode
+        self.remember_remote_position();
         self.connection_mode = ConnectionMode::Remote;
         self.remote_machine_paths = remote_paths.clone();
         self.remote_machine_info = remote_info;
@@ -348,16 +1607,156 @@ impl App {
         self.remote_machine_selected = 0;
         self.remote_machine_marked = HashSet::new();
         self.remote_scrollbar_state = ScrollbarState::new(remote_paths.len()).position(0);
+        self.remote_scroll = 0;
+        let host = connection.computer_name().to_lowercase();
         self.remote_connection = Some(connection);
+        if let Some(position) = self.remote_positions.get(&host).copied() {
+            self.set_panel_position(Panel::User, position);
+        }
+        self.remember_recent_remote(&computer_name);
 
         // Update status message
-        self.status_message = format!(
+        self.set_status(&format!(
             "Connected to remote computer: {} | {}",
             computer_name,
             permissions::get_privilege_message()
-        );
+        ));
+        self.record_host_session(&computer_name);
+    }
 
-        Ok(())
+    /// Connect on a worker thread, the local panels stay usable until the remote one is ready
+    /// A failed attempt opens a dialog offering to try again
+    pub fn start_remote_connect(&mut self, computer_name: &str) {
+        if !self.profile_allows(Feature::Remote) {
+            return;
+        }
+        if let Some(ref host) = self.connecting_to {
+            self.set_status(&format!("Still connecting to {}", host));
+            return;
+        }
+        let host = computer_name.to_string();
+        self.remote_task = Some(BackgroundTask::spawn(
+            &format!("Connecting to {}", host),
+            move || connect_and_read(&host),
+        ));
+        self.connecting_to = Some(computer_name.to_string());
+        self.set_status(&format!("Connecting to {}", computer_name));
+    }
+
+    /// Start RemoteRegistry on the computer the preflight found it stopped on, then connect
+    /// With `temporarily` the service is stopped again on disconnect
+    fn start_registry_and_connect(&mut self, temporarily: bool) {
+        let Some((host, _)) = self.failed_remote.take() else {
+            return;
+        };
+        if temporarily {
+            self.stop_registry_on_disconnect = Some(host.clone());
+        }
+        let target = host.clone();
+        self.remote_task = Some(BackgroundTask::spawn(
+            &format!("Starting RemoteRegistry on {}", host),
+            move || {
+                preflight::start_remote_registry(&target)?;
+                connect_and_read(&target)
+            },
+        ));
+        self.set_status(&format!("Starting RemoteRegistry on {}", host));
+        self.connecting_to = Some(host);
+    }
+
+    /// Stop RemoteRegistry again where it was only started for this session
+    pub fn restore_remote_registry(&mut self) {
+        let Some(host) = self.stop_registry_on_disconnect.take() else {
+            return;
+        };
+        match preflight::stop_remote_registry(&host) {
+            Ok(()) => self.set_status(&format!("Stopped RemoteRegistry on {} again", host)),
+            Err(e) => self.set_status(&format!("Could not stop RemoteRegistry on {}: {}", host, e)),
+        }
+    }
+
+    /// Take over a finished connection attempt
+    fn finish_remote_connect(&mut self, result: RemoteRead) {
+        let Some(host) = self.connecting_to.take() else {
+            return;
+        };
+        match result {
+            Ok((connection, remote_paths)) => self.use_remote_connection(connection, remote_paths),
+            Err(e) => {
+                self.set_status(&format!("Failed to connect to {}: {}", host, e));
+                // Starting the service is offered instead of a retry that would fail the same way
+                let startable = e
+                    .downcast_ref::<preflight::Problem>()
+                    .is_some_and(|problem| problem.startable);
+                self.failed_remote = Some((host, e.to_string()));
+                // Don't pull the user out of another dialog, the status bar says what happened
+                if self.mode == Mode::Normal {
+                    self.mode = Mode::Confirm(if startable {
+                        ConfirmAction::StartRemoteRegistry
+                    } else {
+                        ConfirmAction::RetryRemote
+                    });
+                }
+            }
+        }
+    }
+
+    /// Try the failed connection again
+    fn retry_remote_connect(&mut self) {
+        if let Some((host, _)) = self.failed_remote.take() {
+            self.start_remote_connect(&host);
+        }
+    }
+
+    /// Put a host first in the recent remotes of the File menu and save the list
+    fn remember_recent_remote(&mut self, computer_name: &str) {
+        let recent = &mut self.settings.recent_remotes;
+        recent.retain(|host| !host.eq_ignore_ascii_case(computer_name));
+        recent.insert(0, computer_name.to_string());
+        recent.truncate(config::MAX_RECENT_REMOTES);
+        // Only a convenience, connecting worked either way
+        let _ = config::save_setting(&self.settings_file, "remote", "recent", &recent.join(", "));
+    }
+
+    /// Note in hosts.toml when a host was last connected to and how its PATH looked
+    /// A host that isn't in the inventory yet is added to it
+    fn record_host_session(&mut self, computer_name: &str) {
+        let health = hosts::path_health(&self.remote_machine_info);
+        let when = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+        // A file that doesn't parse is left for the user to fix, not overwritten
+        let saved = HostInventory::load(&self.hosts_file).and_then(|mut inventory| {
+            inventory.record_session(computer_name, health, when);
+            inventory.save(&self.hosts_file)
+        });
+        if let Err(e) = saved {
+            self.set_status(&format!("Host inventory not updated: {:#}", e));
+        }
+    }
+
+    /// File > Connect to: the saved hosts, or typing a name while there are none
+    pub fn open_host_picker(&mut self) {
+        match HostInventory::load(&self.hosts_file) {
+            Ok(inventory) if !inventory.hosts.is_empty() => {
+                self.host_selected = self.host_selected.min(inventory.hosts.len() - 1);
+                self.host_list = inventory.hosts;
+                self.mode = Mode::HostPicker;
+            }
+            loaded => {
+                if let Err(e) = loaded {
+                    self.set_status(&format!("{:#}", e));
+                }
+                self.mode = Mode::Input(InputMode::ConnectRemote);
+                self.input_buffer.clear();
+            }
+        }
+    }
+
+    /// Write the picker's hosts back to hosts.toml
+    fn save_host_list(&self) -> Result<()> {
+        HostInventory {
+            hosts: self.host_list.clone(),
+        }
+        .save(&self.hosts_file)
     }
 
     /// Disconnect from remote computer and return to local mode
@@ -366,7 +1765,8 @@ impl App {
             return Ok(());
         }
 
-        // Clear remote connection and data
+        // Clear remote connection and data, the remote host's unsaved edits are dropped
+        self.remember_remote_position();
         self.connection_mode = ConnectionMode::Local;
         self.remote_connection = None;
         self.remote_machine_paths.clear();
@@ -375,14 +1775,18 @@ impl App {
         self.remote_machine_selected = 0;
         self.remote_machine_marked.clear();
         self.remote_scrollbar_state = ScrollbarState::new(0).position(0);
+        self.remote_scroll = 0;
 
         // Switch back to Machine panel if on User panel (which was showing remote)
         if self.active_panel == Panel::User {
             self.active_panel = Panel::Machine;
         }
 
+        // Local edits stay pending
+        self.has_changes = self.any_pending_changes();
+
         // Update status message
-        self.status_message = permissions::get_privilege_message();
+        self.set_status(&permissions::get_privilege_message());
 
         Ok(())
     }
@@ -390,13 +1794,129 @@ impl App {
     /// Update viewport height based on terminal size
     /// Calculates visible lines in panel: terminal_height - menu(1) - header(1) - status(3) - hints(2) - borders(2)
     pub fn update_viewport_height(&mut self, terminal_height: u16) {
-        // Layout: Menu(1) + Header(1) + Content + Status(3) + Hints(2)
+        // Layout: Menu(1) + Header(1) + Content + Quick actions(0/1) + Status(3) + Hints(2)
         // Panel has top and bottom borders (2)
         // Viewport = terminal_height - 1 - 1 - 3 - 2 - 2 = terminal_height - 9
-        self.viewport_height = terminal_height.saturating_sub(9).max(1);
+        self.viewport_height = terminal_height
+            .saturating_sub(9 + self.quick_actions_height())
+            .max(1);
+    }
+
+    /// Rows the quick actions strip takes under the panels, 0 when it is turned off
+    pub fn quick_actions_height(&self) -> u16 {
+        u16::from(self.settings.quick_actions)
+    }
+
+    /// Handle a terminal event, including the global shortcuts
+    /// This is the single entry point for both the real event loop and injected (scripted) events
+    pub fn handle_event(&mut self, event: Event, terminal_size: Rect) -> Result<()> {
+        if matches!(event, Event::Key(_) | Event::Mouse(_)) {
+            self.last_activity = std::time::Instant::now();
+        }
+        if self.mode == Mode::Locked {
+            return self.handle_locked_event(event);
+        }
+        match event {
+            Event::Key(key) => {
+                // Filter duplicate events using KeyEventKind (Windows sends both Press and Release)
+                // Only process Press events to avoid double-handling the same keystroke
+                // This is the standard approach recommended by ratatui/crossterm documentation
+                if key.kind != KeyEventKind::Press {
+                    return Ok(());
+                }
+                // The keyboard takes the status bar back from the pointer
+                self.hovered = None;
+
+                // Global shortcuts
+                match (key.code, key.modifiers) {
+                    (KeyCode::Char('c'), KeyModifiers::CONTROL) => self.should_exit = true,
+                    // Shift+F10 creates directories, see handle_normal_input
+                    (KeyCode::F(10), modifiers) if !modifiers.contains(KeyModifiers::SHIFT) => {
+                        // F10 with double-tap detection for quick exit
+                        // Only handle in Normal mode; let confirm dialog handle it when in Exit confirmation
+                        if !matches!(self.mode, Mode::Confirm(ConfirmAction::Exit)) {
+                            self.handle_f10_press();
+                        } else {
+                            // In Exit confirmation dialog - pass to dialog handler
+                            self.handle_input(key)?;
+                        }
+                    }
+                    _ => self.handle_input(key)?,
+                }
+            }
+            // Handle mouse events (clicks, scrolling)
+            // Nothing to click while the too-small placeholder is shown
+            Event::Mouse(mouse) if !terminal_too_small(terminal_size) => {
+                self.handle_mouse(mouse, terminal_size)?
+            }
+            Event::Resize(_, height) => self.handle_resize(height),
+            Event::Paste(text) => self.handle_paste(&text),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Text pasted into the terminal, arrives in one piece when bracketed paste is on
+    fn handle_paste(&mut self, text: &str) {
+        self.last_activity = std::time::Instant::now();
+        match self.mode {
+            Mode::RawEditor => {
+                if let Some(editor) = self.raw_editor.as_mut().filter(|e| !e.reviewing) {
+                    editor.text.insert_str(text);
+                }
+            }
+            Mode::HostNotes => {
+                if let Some(notes) = self.host_notes.as_mut() {
+                    notes.insert_str(text);
+                }
+            }
+            // One line only, line breaks would end the input
+            Mode::Input(_) => self
+                .input_buffer
+                .extend(text.chars().filter(|c| !matches!(c, '\r' | '\n'))),
+            _ => {}
+        }
+    }
+
+    /// Reflow for a new terminal size right away instead of on the next key press
+    fn handle_resize(&mut self, height: u16) {
+        self.update_viewport_height(height);
+        self.keep_selection_visible();
+        // Dialogs are sized in percent and follow by themselves, moved ones go back to the center
+        self.dialog_offsets.clear();
+        self.dialog_drag = None;
     }
 
     pub fn handle_input(&mut self, key: KeyEvent) -> Result<()> {
+        let machine_before = self.machine_paths.clone();
+        let panels_before = self.read_only_snapshot();
+        let result = self.dispatch_input(key);
+        self.enforce_profile(panels_before);
+        self.note_staged_machine_edit(&machine_before);
+        self.keep_selection_visible();
+        result
+    }
+
+    fn dispatch_input(&mut self, key: KeyEvent) -> Result<()> {
+        // Ctrl+arrows resize whichever resizable dialog is open
+        if let Some(dialog) = Dialog::for_mode(self.mode) {
+            if key.modifiers.contains(KeyModifiers::CONTROL) {
+                let step = match key.code {
+                    KeyCode::Left => Some((-5, 0)),
+                    KeyCode::Right => Some((5, 0)),
+                    KeyCode::Up => Some((0, -5)),
+                    KeyCode::Down => Some((0, 5)),
+                    _ => None,
+                };
+                if let Some((width, height)) = step {
+                    let (w, h) = self.dialog_size(dialog);
+                    self.set_dialog_size(dialog, w as i32 + width, h as i32 + height);
+                    self.save_dialog_size(dialog);
+                    return Ok(());
+                }
+            }
+        }
+
         match self.mode {
             Mode::Normal => self.handle_normal_input(key),
             Mode::Help => self.handle_help_input(key),
@@ -408,6 +1928,29 @@ impl App {
             Mode::FileBrowser => self.handle_file_browser_input(key),
             Mode::FilterMenu => self.handle_filter_menu_input(key),
             Mode::ThemeSelection => self.handle_theme_selection_input(key),
+            Mode::PathDetails => self.handle_path_details_input(key),
+            Mode::PathPrecedence => self.handle_path_precedence_input(key),
+            Mode::PathLengths => self.handle_path_lengths_input(key),
+            Mode::LiveEnvironment => self.handle_live_environment_input(key),
+            Mode::History => self.handle_history_input(key),
+            Mode::SecurityReport => self.handle_security_report_input(key),
+            Mode::ShimReport => self.handle_shim_report_input(key),
+            Mode::Toolchains => self.handle_toolchains_input(key),
+            Mode::AgingReport => self.handle_aging_report_input(key),
+            Mode::UsageSuggestions => self.handle_usage_suggestions_input(key),
+            Mode::WhereResult => self.handle_where_result_input(key),
+            Mode::MergeDuplicates => self.handle_merge_duplicates_input(key),
+            Mode::IgnoreList => self.handle_ignore_list_input(key),
+            Mode::NestedEntries => self.handle_nested_entries_input(key),
+            Mode::BatchReplace => self.handle_batch_replace_input(key),
+            Mode::RawEditor => self.handle_raw_editor_input(key),
+            Mode::HostPicker => self.handle_host_picker_input(key),
+            Mode::HostNotes => self.handle_host_notes_input(key),
+            Mode::BulkReview => self.handle_bulk_review_input(key),
+            Mode::MessageLog => self.handle_message_log_input(key),
+            Mode::WhatsNew => self.handle_whats_new_input(key),
+            Mode::Locked => self.handle_locked_event(Event::Key(key)),
+            Mode::MenuBar { active_menu } => self.handle_menu_bar_input(key, active_menu),
             Mode::Menu {
                 active_menu,
                 selected_item,
@@ -416,6 +1959,40 @@ impl App {
     }
 
     fn handle_normal_input(&mut self, key: KeyEvent) -> Result<()> {
+        // m followed by a digit saves a bookmark, anything else cancels it
+        if std::mem::take(&mut self.pending_bookmark) {
+            if let KeyCode::Char(c @ '1'..='9') = key.code {
+                self.save_bookmark(c as u8 - b'0');
+                return Ok(());
+            }
+            self.set_status("Bookmark cancelled");
+        }
+
+        // In visual mode moving extends the range, any other key ends it and acts on the marks
+        if self.visual.is_some() {
+            match key.code {
+                KeyCode::Esc => {
+                    self.cancel_visual();
+                    return Ok(());
+                }
+                KeyCode::Char('V') => {
+                    self.keep_visual();
+                    return Ok(());
+                }
+                KeyCode::Up
+                | KeyCode::Down
+                | KeyCode::Char('j')
+                | KeyCode::Char('k')
+                | KeyCode::PageUp
+                | KeyCode::PageDown
+                | KeyCode::Home
+                | KeyCode::End
+                | KeyCode::Char('{')
+                | KeyCode::Char('}') => {}
+                _ => self.visual = None,
+            }
+        }
+
         match (key.code, key.modifiers) {
             // Navigation
             (KeyCode::Up, _) | (KeyCode::Char('k'), _) => self.move_selection(-1),
@@ -432,38 +2009,38 @@ impl App {
             }
             (KeyCode::Home, _) => self.move_selection_to(0),
             (KeyCode::End, _) => self.move_selection_to(usize::MAX),
+            (KeyCode::Char('}'), _) => self.jump_to_problem(true),
+            (KeyCode::Char('{'), _) => self.jump_to_problem(false),
             (KeyCode::Tab, _) | (KeyCode::Left, _) | (KeyCode::Right, _) => {
                 self.active_panel = self.active_panel.toggle();
             }
 
             // Selection
             (KeyCode::Char(' '), _) | (KeyCode::Insert, _) => self.toggle_mark(),
+            (KeyCode::Char('V'), KeyModifiers::NONE | KeyModifiers::SHIFT) => self.start_visual(),
 
             // Actions
             (KeyCode::F(2), _) => self.toggle_mark(),
             (KeyCode::F(3), _) => {
-                if self.has_marked_items() {
-                    self.mode = Mode::Confirm(ConfirmAction::DeleteSelected);
-                }
+                self.confirm_delete_marked();
             }
             (KeyCode::F(4), _) => self.start_add_path(),
+            (KeyCode::F(5), KeyModifiers::SHIFT) => self.copy_marked_to_other_panel()?,
             (KeyCode::F(5), _) => self.move_marked_to_other_panel()?,
             (KeyCode::F(6), _) => self.move_item_up(),
             (KeyCode::F(7), _) => {
-                self.mode = Mode::Confirm(ConfirmAction::DeleteAllDuplicates);
+                self.confirm_bulk_delete(ConfirmAction::DeleteAllDuplicates);
             }
             (KeyCode::F(8), _) => {
-                self.mode = Mode::Confirm(ConfirmAction::DeleteAllDead);
+                self.confirm_bulk_delete(ConfirmAction::DeleteAllDead);
             }
-            (KeyCode::F(9), _) => self.normalize_selected(),
-            (KeyCode::F(10), _) => {
-                // Create marked dead directories
-                if self.has_marked_dead_paths() {
-                    self.mode = Mode::Confirm(ConfirmAction::CreateMarkedDirectories);
-                } else {
-                    self.set_status("No marked dead paths to create");
-                }
+            (KeyCode::F(9), KeyModifiers::SHIFT) => self.normalize_selected(),
+            (KeyCode::F(9), _) if self.settings.f9_normalizes => self.normalize_selected(),
+            (KeyCode::F(9), _) => {
+                self.mode = Mode::MenuBar { active_menu: 0 };
+                self.set_status("Menu: ←/→ choose, Enter or ↓ opens, Esc leaves");
             }
+            (KeyCode::F(10), _) => self.confirm_create_directories(),
             (KeyCode::Char('/'), _) => {
                 // Open filter menu
                 self.mode = Mode::FilterMenu;
@@ -473,6 +2050,19 @@ impl App {
                 // Open theme selection menu
                 self.open_theme_selector()?;
             }
+            (KeyCode::Char('i'), _) => self.show_path_details(),
+            (KeyCode::Char('o'), KeyModifiers::NONE) => self.open_selected_folder(),
+            (KeyCode::Char('h'), KeyModifiers::NONE) => self.show_history()?,
+            (KeyCode::Char('p'), KeyModifiers::NONE) => self.start_promotion(),
+            (KeyCode::Char('v'), KeyModifiers::NONE) => self.show_live_environment(),
+            (KeyCode::Char('l'), KeyModifiers::NONE) => self.show_message_log(),
+            (KeyCode::Char('L'), _) => self.show_path_lengths(),
+            (KeyCode::Char('w'), KeyModifiers::NONE) => self.start_where_lookup(),
+            (KeyCode::Char('R'), _) => self.start_batch_replace(),
+            (KeyCode::Char('E'), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                self.start_raw_editor()
+            }
+            (KeyCode::Char('r'), KeyModifiers::NONE) => self.recheck_selected(),
             (KeyCode::F(1), _) | (KeyCode::Char('?'), _) => {
                 self.mode = Mode::Help;
             }
@@ -489,41 +2079,18 @@ impl App {
                 }
             }
             (KeyCode::Delete, _) => {
-                if self.has_marked_items() {
-                    self.mode = Mode::Confirm(ConfirmAction::DeleteSelected);
-                }
+                self.confirm_delete_marked();
             }
 
             // Save/Restore
-            (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
-                if self.has_changes {
-                    // Check if we need elevation for MACHINE path changes
-                    let needs_elevation = crate::elevation::needs_elevation_for_changes(
-                        self.is_admin,
-                        &self.machine_paths,
-                        &self.machine_original,
-                        &self.remote_machine_paths,
-                        &self.remote_machine_original,
-                        self.connection_mode,
-                    );
-
-                    if needs_elevation {
-                        self.mode = Mode::Confirm(ConfirmAction::RequestElevation);
-                    } else {
-                        self.mode = Mode::Confirm(ConfirmAction::ApplyChanges);
-                    }
-                } else {
-                    self.set_status("No changes to save");
-                }
-            }
-            (KeyCode::Char('b'), KeyModifiers::CONTROL) => self.create_backup()?,
+            (KeyCode::Char('s'), KeyModifiers::CONTROL) => self.request_apply(),
+            (KeyCode::Char('b'), KeyModifiers::CONTROL) => self.create_manual_backup()?,
             (KeyCode::Char('o'), KeyModifiers::CONTROL) => {
                 // Connect to or disconnect from remote computer
                 match self.connection_mode {
                     ConnectionMode::Local => {
-                        // Open connection dialog
-                        self.mode = Mode::Input(InputMode::ConnectRemote);
-                        self.input_buffer.clear();
+                        // Pick a saved host, or type a name if there are none
+                        self.open_host_picker();
                     }
                     ConnectionMode::Remote => {
                         // Confirm disconnect
@@ -534,6 +2101,9 @@ impl App {
 
             // Undo/Redo
             (KeyCode::Char('z'), KeyModifiers::CONTROL) => self.undo()?,
+            (KeyCode::Char('Z'), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                self.start_revert_last_apply()
+            }
             (KeyCode::Char('y'), KeyModifiers::CONTROL) => self.redo()?,
             (KeyCode::Char('r'), KeyModifiers::CONTROL) => self.show_backup_list()?,
 
@@ -561,6 +2131,7 @@ impl App {
                 // Shift+Ctrl+U (uppercase U means shift is pressed)
                 self.unmark_all();
             }
+            (KeyCode::Char('l'), KeyModifiers::CONTROL) => self.clean_malformed_entries(),
 
             (KeyCode::Char('e'), KeyModifiers::CONTROL)
             | (KeyCode::Char('E'), KeyModifiers::CONTROL) => {
@@ -572,6 +2143,15 @@ impl App {
                 }
             }
 
+            // Bookmarks within the active panel
+            (KeyCode::Char('m'), KeyModifiers::NONE) => {
+                self.pending_bookmark = true;
+                self.set_status("Bookmark: press 1-9 to save this position, Alt+digit jumps back");
+            }
+            (KeyCode::Char(c @ '1'..='9'), KeyModifiers::ALT) => {
+                self.jump_to_bookmark(c as u8 - b'0');
+            }
+
             // Menu activation with Alt+letter
             (KeyCode::Char(c), KeyModifiers::ALT) => {
                 self.activate_menu_by_char(c);
@@ -579,6 +2159,7 @@ impl App {
 
             _ => {}
         }
+        self.extend_visual();
         Ok(())
     }
 
@@ -602,24 +2183,130 @@ impl App {
         Ok(())
     }
 
+    fn handle_path_precedence_input(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+                self.mode = Mode::Normal;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.precedence_scroll = self.precedence_scroll.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.precedence_scroll = self.precedence_scroll.saturating_add(1);
+            }
+            KeyCode::PageUp => {
+                self.precedence_scroll =
+                    self.precedence_scroll.saturating_sub(self.viewport_height);
+            }
+            KeyCode::PageDown => {
+                self.precedence_scroll =
+                    self.precedence_scroll.saturating_add(self.viewport_height);
+            }
+            KeyCode::Home => self.precedence_scroll = 0,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_live_environment_input(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('v') => {
+                self.mode = Mode::Normal;
+            }
+            KeyCode::Char('u') => self.save_live_entries_to_user(),
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.live_scroll = self.live_scroll.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.live_scroll = self.live_scroll.saturating_add(1);
+            }
+            KeyCode::PageUp => {
+                self.live_scroll = self.live_scroll.saturating_sub(self.viewport_height);
+            }
+            KeyCode::PageDown => {
+                self.live_scroll = self.live_scroll.saturating_add(self.viewport_height);
+            }
+            KeyCode::Home => self.live_scroll = 0,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_path_details_input(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('i') => {
+                self.mode = Mode::Normal;
+            }
+            KeyCode::Char('a') | KeyCode::Char('A') => {
+                self.check_path_availability();
+            }
+            KeyCode::Char('s') | KeyCode::Char('S') => self.measure_selected_directory(true),
+            KeyCode::F(10) if self.selected_creatable_dead().is_some() => {
+                self.mode = Mode::Confirm(ConfirmAction::CreateSelectedDirectory);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
     fn handle_process_restart_info_input(&mut self, key: KeyEvent) -> Result<()> {
         match key.code {
             KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
                 self.mode = Mode::Normal;
                 self.set_status("Changes applied successfully!");
             }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.process_selected = self.process_selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let last = self.listed_processes().len().saturating_sub(1);
+                self.process_selected = (self.process_selected + 1).min(last);
+            }
+            KeyCode::Char('s') | KeyCode::Char('S') => {
+                self.hide_services = !self.hide_services;
+                self.process_selected = 0;
+            }
             _ => {}
         }
         Ok(())
     }
 
+    /// Processes the restart dialog lists, services left out when hidden
+    pub fn listed_processes(&self) -> Vec<&ProcessInfo> {
+        self.processes_to_restart
+            .iter()
+            .filter(|process| !(self.hide_services && process.is_service))
+            .collect()
+    }
+
     fn handle_confirm_input(&mut self, key: KeyEvent, action: ConfirmAction) -> Result<()> {
         match key.code {
+            KeyCode::Char(c)
+                if action == ConfirmAction::ApplyChanges
+                    && self
+                        .apply_choices()
+                        .iter()
+                        .any(|choice| choice.key == c.to_ascii_uppercase()) =>
+            {
+                self.toggle_apply_choice(c.to_ascii_uppercase());
+            }
+            KeyCode::Char('o') | KeyCode::Char('O')
+                if action == ConfirmAction::DeleteAllDead && self.unavailable_count() > 0 =>
+            {
+                self.delete_unavailable = !self.delete_unavailable;
+                self.pending_bulk_delete = Some(self.plan_bulk_delete(action));
+            }
             KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
                 // For Exit action, require F10 instead of y/Enter
                 if matches!(action, ConfirmAction::Exit) {
                     return Ok(());
                 }
+                if action == ConfirmAction::ApplyChanges
+                    && !self.apply_choices().iter().any(|choice| choice.checked)
+                {
+                    self.set_status("Nothing selected to apply");
+                    return Ok(());
+                }
                 self.mode = Mode::Normal;
                 match action {
                     ConfirmAction::Exit => {
@@ -636,10 +2323,20 @@ impl App {
                     ConfirmAction::RestoreBackup => self.restore_selected_backup()?,
                     ConfirmAction::CreateSingleDirectory => self.create_single_directory()?,
                     ConfirmAction::CreateMarkedDirectories => self.create_marked_directories()?,
+                    ConfirmAction::CreateSelectedDirectory => self.create_selected_directory()?,
+                    ConfirmAction::UseSubdirectory => self.add_pending_subdirectory(true),
+                    ConfirmAction::RevertLastApply => self.revert_last_apply(true),
+                    ConfirmAction::PromoteEntry => self.promote_entry(),
+                    ConfirmAction::SizeAdvisory => self.accept_size_advisory(),
+                    ConfirmAction::InstallUpdate => self.download_update(),
                     ConfirmAction::DisconnectRemote => {
                         self.disconnect_from_remote()?;
                         self.set_status("Disconnected from remote computer");
+                        self.restore_remote_registry();
                     }
+                    ConfirmAction::RetryRemote => self.retry_remote_connect(),
+                    ConfirmAction::StartRemoteRegistry => self.start_registry_and_connect(false),
+                    ConfirmAction::RemoteWriteQueued => self.retry_queued_write(),
                 }
             }
             KeyCode::F(10) => {
@@ -649,8 +2346,37 @@ impl App {
                     self.should_exit = true;
                 }
             }
+            // No means the entry as typed, only Esc cancels adding it
+            KeyCode::Char('n') | KeyCode::Char('N') if action == ConfirmAction::UseSubdirectory => {
+                self.mode = Mode::Normal;
+                self.add_pending_subdirectory(false);
+            }
+            KeyCode::Char('t') | KeyCode::Char('T')
+                if action == ConfirmAction::StartRemoteRegistry =>
+            {
+                self.mode = Mode::Normal;
+                self.start_registry_and_connect(true);
+            }
+            KeyCode::Char('s') | KeyCode::Char('S')
+                if action == ConfirmAction::RemoteWriteQueued =>
+            {
+                self.mode = Mode::Normal;
+                match config::get_pending_dir() {
+                    Ok(folder) => self.save_queued_write(&folder),
+                    Err(e) => self.set_status(&format!("Could not save the queued write: {}", e)),
+                }
+            }
+            // No restores the panels without writing them
+            KeyCode::Char('n') | KeyCode::Char('N') if action == ConfirmAction::RevertLastApply => {
+                self.mode = Mode::Normal;
+                self.revert_last_apply(false);
+            }
             KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
                 self.mode = Mode::Normal;
+                self.pending_subdirectory = None;
+                self.pending_revert = None;
+                self.failed_remote = None;
+                self.delete_unavailable = false;
             }
             _ => {}
         }
@@ -679,30 +2405,86 @@ impl App {
                             InputMode::ConnectRemote => {
                                 let computer_name = self.input_buffer.trim().to_string();
                                 if !computer_name.is_empty() {
-                                    match self.connect_to_remote(&computer_name) {
-                                        Ok(()) => {
-                                            self.set_status(&format!(
-                                                "Successfully connected to {}",
-                                                computer_name
-                                            ));
-                                        }
-                                        Err(e) => {
-                                            self.set_status(&format!(
-                                                "Failed to connect to {}: {}",
-                                                computer_name, e
-                                            ));
-                                        }
-                                    }
+                                    self.start_remote_connect(&computer_name);
+                                }
+                            }
+                            InputMode::WhereCommand => {
+                                let name = self.input_buffer.trim().to_string();
+                                if !name.is_empty() {
+                                    self.look_up_command(&name);
+                                }
+                            }
+                            InputMode::ReplaceFind => {
+                                let find = self.input_buffer.trim().to_string();
+                                if !find.is_empty() {
+                                    self.replace_find = find;
+                                    self.input_buffer = self.replace_with.clone();
+                                    self.mode = Mode::Input(InputMode::ReplaceWith);
+                                }
+                            }
+                            InputMode::ReplaceWith => {
+                                self.replace_with = self.input_buffer.trim().to_string();
+                                self.preview_batch_replace();
+                            }
+                            InputMode::MigrateFrom => {
+                                let from = self.input_buffer.trim().to_string();
+                                if !from.is_empty() {
+                                    self.replace_find = from;
+                                    self.input_buffer.clear();
+                                    self.mode = Mode::Input(InputMode::MigrateTo);
+                                }
+                            }
+                            InputMode::MigrateTo => {
+                                let to = self.input_buffer.trim().to_string();
+                                if !to.is_empty() {
+                                    self.replace_with = to;
+                                    self.preview_drive_migration();
                                 }
                             }
+                            InputMode::ChangeReport => {
+                                let folder = self.input_buffer.trim().trim_matches('"').to_string();
+                                if !folder.is_empty() {
+                                    self.write_change_report(Path::new(&folder));
+                                }
+                            }
+                            InputMode::IgnoreRule => {
+                                let rule = self.input_buffer.trim().trim_matches('"').to_string();
+                                self.add_ignore_rule(&rule);
+                            }
+                        }
+                        // Keep the text of a prompt the action opened
+                        if !matches!(self.mode, Mode::Input(_)) {
+                            self.input_buffer.clear();
                         }
-                        self.input_buffer.clear();
                     }
                     KeyCode::Esc => {
-                        self.mode = Mode::Normal;
+                        // A rule is typed from the Ignore List, go back there
+                        self.mode = if input_mode == InputMode::IgnoreRule {
+                            Mode::IgnoreList
+                        } else {
+                            Mode::Normal
+                        };
                         self.mode_enter_time = std::time::Instant::now();
                         self.input_buffer.clear();
                     }
+                    KeyCode::Char('g')
+                        if key.modifiers.contains(KeyModifiers::CONTROL)
+                            && self.input_conflict().is_some() =>
+                    {
+                        self.go_to_input_conflict();
+                    }
+                    KeyCode::Tab if input_mode == InputMode::AddPath => {
+                        self.add_position = self.add_position.next();
+                    }
+                    KeyCode::Tab if input_mode == InputMode::ChangeReport => {
+                        self.report_format = self.report_format.next();
+                    }
+                    KeyCode::BackTab
+                        if input_mode == InputMode::AddPath
+                            && self.connection_mode == ConnectionMode::Local =>
+                    {
+                        self.add_target = Some(self.add_panel().toggle());
+                    }
                     KeyCode::Char(c) => {
                         self.input_buffer.push(c);
                     }
@@ -719,2624 +2501,7978 @@ impl App {
         Ok(())
     }
 
-    fn handle_backup_list_input(&mut self, key: KeyEvent) -> Result<()> {
+    fn handle_history_input(&mut self, key: KeyEvent) -> Result<()> {
         match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('h') => {
+                self.mode = Mode::Normal;
+            }
             KeyCode::Up | KeyCode::Char('k') => {
-                if self.backup_selected > 0 {
-                    self.backup_selected -= 1;
-                }
+                self.history_scroll = self.history_scroll.saturating_sub(1);
             }
             KeyCode::Down | KeyCode::Char('j') => {
-                if self.backup_selected + 1 < self.backup_list.len() {
-                    self.backup_selected += 1;
-                }
+                self.history_scroll = self.history_scroll.saturating_add(1);
             }
-            KeyCode::Enter => {
-                if !self.backup_list.is_empty() {
-                    self.mode = Mode::Confirm(ConfirmAction::RestoreBackup);
-                }
+            KeyCode::PageUp => {
+                self.history_scroll = self.history_scroll.saturating_sub(self.viewport_height);
             }
-            KeyCode::Esc | KeyCode::Char('q') => {
-                self.mode = Mode::Normal;
+            KeyCode::PageDown => {
+                self.history_scroll = self.history_scroll.saturating_add(self.viewport_height);
             }
+            KeyCode::Home => self.history_scroll = 0,
             _ => {}
         }
         Ok(())
     }
 
-    fn handle_file_browser_input(&mut self, key: KeyEvent) -> Result<()> {
+    fn handle_path_lengths_input(&mut self, key: KeyEvent) -> Result<()> {
         match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('L') => {
+                self.mode = Mode::Normal;
+            }
             KeyCode::Up | KeyCode::Char('k') => {
-                if self.file_browser_selected > 0 {
-                    self.file_browser_selected -= 1;
-                    self.file_browser_scrollbar_state = self
-                        .file_browser_scrollbar_state
-                        .position(self.file_browser_selected);
-                }
+                self.lengths_scroll = self.lengths_scroll.saturating_sub(1);
             }
             KeyCode::Down | KeyCode::Char('j') => {
-                if self.file_browser_selected + 1 < self.file_browser_entries.len() {
-                    self.file_browser_selected += 1;
-                    self.file_browser_scrollbar_state = self
-                        .file_browser_scrollbar_state
-                        .position(self.file_browser_selected);
-                }
+                self.lengths_scroll = self.lengths_scroll.saturating_add(1);
             }
             KeyCode::PageUp => {
-                let jump = self.viewport_height.saturating_sub(1).max(1) as usize;
-                self.file_browser_selected = self.file_browser_selected.saturating_sub(jump);
-                self.file_browser_scrollbar_state = self
-                    .file_browser_scrollbar_state
-                    .position(self.file_browser_selected);
+                self.lengths_scroll = self.lengths_scroll.saturating_sub(self.viewport_height);
             }
             KeyCode::PageDown => {
-                let jump = self.viewport_height.saturating_sub(1).max(1) as usize;
-                let max_idx = self.file_browser_entries.len().saturating_sub(1);
-                self.file_browser_selected = (self.file_browser_selected + jump).min(max_idx);
-                self.file_browser_scrollbar_state = self
-                    .file_browser_scrollbar_state
-                    .position(self.file_browser_selected);
-            }
-            KeyCode::Home | KeyCode::Char('g') => {
-                self.file_browser_selected = 0;
-                self.file_browser_scrollbar_state = self
-                    .file_browser_scrollbar_state
-                    .position(self.file_browser_selected);
+                self.lengths_scroll = self.lengths_scroll.saturating_add(self.viewport_height);
             }
-            KeyCode::End | KeyCode::Char('G') => {
-                if !self.file_browser_entries.is_empty() {
-                    self.file_browser_selected = self.file_browser_entries.len() - 1;
-                    self.file_browser_scrollbar_state = self
-                        .file_browser_scrollbar_state
-                        .position(self.file_browser_selected);
-                }
+            KeyCode::Home => self.lengths_scroll = 0,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_message_log_input(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('l') => {
+                self.mode = Mode::Normal;
             }
-            KeyCode::Enter => {
-                // If an entry is selected, navigate into it
-                if !self.file_browser_entries.is_empty() {
-                    self.navigate_to_selected_directory();
-                }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.message_log_scroll = self.message_log_scroll.saturating_sub(1);
             }
-            KeyCode::Char(' ') => {
-                // Space key: select current directory for adding to PATH
-                self.select_current_directory_for_path()?;
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.message_log_scroll = self.message_log_scroll.saturating_add(1);
             }
-            KeyCode::Tab => {
-                // Tab: switch to manual text input mode
-                self.mode = Mode::Input(InputMode::AddPath);
-                self.mode_enter_time = std::time::Instant::now();
-                self.input_buffer = self.file_browser_current_path.to_string_lossy().to_string();
+            KeyCode::PageUp => {
+                self.message_log_scroll =
+                    self.message_log_scroll.saturating_sub(self.viewport_height);
             }
-            KeyCode::Esc | KeyCode::Char('q') => {
-                // Cancel file browser
-                self.mode = Mode::Normal;
+            KeyCode::PageDown => {
+                self.message_log_scroll =
+                    self.message_log_scroll.saturating_add(self.viewport_height);
             }
+            KeyCode::Home => self.message_log_scroll = 0,
             _ => {}
         }
         Ok(())
     }
 
-    fn handle_filter_menu_input(&mut self, key: KeyEvent) -> Result<()> {
+    fn handle_whats_new_input(&mut self, key: KeyEvent) -> Result<()> {
         match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+                self.mode = Mode::Normal;
+            }
+            // Releases are listed newest first, so left goes to a newer one
+            KeyCode::Left | KeyCode::Char('h') => {
+                self.release_selected = self.release_selected.saturating_sub(1);
+                self.release_scroll = 0;
+            }
+            KeyCode::Right | KeyCode::Char('l')
+                if self.release_selected + 1 < self.releases.len() =>
+            {
+                self.release_selected += 1;
+                self.release_scroll = 0;
+            }
             KeyCode::Up | KeyCode::Char('k') => {
-                if self.filter_menu_selected > 0 {
-                    self.filter_menu_selected -= 1;
-                }
+                self.release_scroll = self.release_scroll.saturating_sub(1);
             }
             KeyCode::Down | KeyCode::Char('j') => {
-                // We have 5 filter options (0-4): None, Dead, Duplicates, NonNormalized, Valid
-                if self.filter_menu_selected < 4 {
-                    self.filter_menu_selected += 1;
-                }
+                self.release_scroll = self.release_scroll.saturating_add(1);
             }
-            KeyCode::Enter => {
-                // Apply selected filter
-                let new_filter = match self.filter_menu_selected {
-                    0 => FilterMode::None,
-                    1 => FilterMode::Dead,
-                    2 => FilterMode::Duplicates,
-                    3 => FilterMode::NonNormalized,
-                    4 => FilterMode::Valid,
-                    _ => FilterMode::None,
-                };
-
-                // Set the filter mode directly (don't toggle)
-                self.filter_mode = new_filter;
-                let filter_name = match new_filter {
-                    FilterMode::None => "None (showing all)",
-                    FilterMode::Dead => "Dead paths",
-                    FilterMode::Duplicates => "Duplicates",
-                    FilterMode::NonNormalized => "Non-normalized",
-                    FilterMode::Valid => "Valid paths",
-                };
-                self.set_status(&format!("Filter: {}", filter_name));
-                self.mode = Mode::Normal;
+            KeyCode::PageUp => {
+                self.release_scroll = self.release_scroll.saturating_sub(self.viewport_height);
             }
-            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('/') => {
-                // Close menu without changing filter
-                self.mode = Mode::Normal;
+            KeyCode::PageDown => {
+                self.release_scroll = self.release_scroll.saturating_add(self.viewport_height);
             }
             _ => {}
         }
         Ok(())
     }
 
-    fn open_theme_selector(&mut self) -> Result<()> {
-        // Load available themes
-        self.theme_list = crate::config::list_available_themes()?;
-
-        // Find current theme in list and select it
-        let current_name = &self.theme.name;
-        self.theme_selected = self
-            .theme_list
-            .iter()
-            .position(|(name, _)| name == current_name)
-            .unwrap_or(0);
-
-        // Store original theme for Esc cancellation
-        self.original_theme = Some(self.theme.clone());
-
-        self.mode = Mode::ThemeSelection;
+    fn handle_security_report_input(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+                self.mode = Mode::Normal;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.security_scroll = self.security_scroll.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.security_scroll = self.security_scroll.saturating_add(1);
+            }
+            KeyCode::PageUp => {
+                self.security_scroll = self.security_scroll.saturating_sub(self.viewport_height);
+            }
+            KeyCode::PageDown => {
+                self.security_scroll = self.security_scroll.saturating_add(self.viewport_height);
+            }
+            KeyCode::Home => self.security_scroll = 0,
+            KeyCode::Char('d') | KeyCode::Char('D') => self.demote_flagged_entries(),
+            _ => {}
+        }
         Ok(())
     }
 
-    /// Load and apply the currently selected theme (for live preview)
-    fn apply_selected_theme(&mut self) -> Result<()> {
-        if let Some((theme_name, is_builtin)) = self.theme_list.get(self.theme_selected) {
-            let new_theme = if *is_builtin {
-                Theme::builtin(theme_name)?
-            } else {
-                // Load from custom theme file
-                if let Some(theme_path) = crate::config::get_theme_path(theme_name) {
-                    Theme::from_mc_skin(&theme_path)?
-                } else {
-                    self.set_status(&format!("Theme file not found: {}", theme_name));
-                    return Ok(());
-                }
-            };
-
-            self.theme = new_theme;
+    fn handle_shim_report_input(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+                self.mode = Mode::Normal;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.shim_scroll = self.shim_scroll.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.shim_scroll = self.shim_scroll.saturating_add(1);
+            }
+            KeyCode::PageUp => {
+                self.shim_scroll = self.shim_scroll.saturating_sub(self.viewport_height);
+            }
+            KeyCode::PageDown => {
+                self.shim_scroll = self.shim_scroll.saturating_add(self.viewport_height);
+            }
+            KeyCode::Home => self.shim_scroll = 0,
+            KeyCode::Char('r') | KeyCode::Char('R') => self.restore_shim_positions(),
+            _ => {}
         }
         Ok(())
     }
 
-    fn handle_theme_selection_input(&mut self, key: KeyEvent) -> Result<()> {
+    fn handle_where_result_input(&mut self, key: KeyEvent) -> Result<()> {
         match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+                self.mode = Mode::Normal;
+            }
             KeyCode::Up | KeyCode::Char('k') => {
-                if self.theme_selected > 0 {
-                    self.theme_selected -= 1;
-                    // Apply theme immediately for live preview
-                    self.apply_selected_theme()?;
-                }
+                self.where_scroll = self.where_scroll.saturating_sub(1);
             }
             KeyCode::Down | KeyCode::Char('j') => {
-                if self.theme_selected < self.theme_list.len().saturating_sub(1) {
-                    self.theme_selected += 1;
-                    // Apply theme immediately for live preview
-                    self.apply_selected_theme()?;
-                }
+                self.where_scroll = self.where_scroll.saturating_add(1);
             }
-            KeyCode::Enter => {
-                // Keep the currently selected theme and close
-                if let Some((theme_name, _)) = self.theme_list.get(self.theme_selected) {
-                    self.set_status(&format!("Theme changed to: {}", theme_name));
-                }
-                // Clear original theme since we're accepting the change
-                self.original_theme = None;
-                self.mode = Mode::Normal;
+            KeyCode::PageUp => {
+                self.where_scroll = self.where_scroll.saturating_sub(self.viewport_height);
             }
-            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('t') => {
-                // Restore original theme and close
-                if let Some(original) = self.original_theme.take() {
-                    self.theme = original;
-                }
+            KeyCode::PageDown => {
+                self.where_scroll = self.where_scroll.saturating_add(self.viewport_height);
+            }
+            KeyCode::Home => self.where_scroll = 0,
+            KeyCode::Char('w') | KeyCode::Char('W') => self.start_where_lookup(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_toolchains_input(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
                 self.mode = Mode::Normal;
             }
-            KeyCode::Char('r') => {
-                // Reload theme list
-                self.theme_list = crate::config::list_available_themes()?;
-                self.set_status("Theme list reloaded");
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.toolchain_scroll = self.toolchain_scroll.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.toolchain_scroll = self.toolchain_scroll.saturating_add(1);
+            }
+            KeyCode::PageUp => {
+                self.toolchain_scroll = self.toolchain_scroll.saturating_sub(self.viewport_height);
             }
+            KeyCode::PageDown => {
+                self.toolchain_scroll = self.toolchain_scroll.saturating_add(self.viewport_height);
+            }
+            KeyCode::Home => self.toolchain_scroll = 0,
+            KeyCode::Char('m') | KeyCode::Char('M') => self.mark_shadowed_toolchains(),
             _ => {}
         }
         Ok(())
     }
 
-    // Mouse event handling
-    pub fn handle_mouse(&mut self, mouse: MouseEvent, terminal_size: Rect) -> Result<()> {
-        match mouse.kind {
-            MouseEventKind::Down(MouseButton::Left) => {
-                // Handle clicks based on current mode
-                match self.mode {
-                    Mode::Confirm(_) => {
-                        self.handle_confirm_dialog_click(mouse.column, mouse.row, terminal_size)?;
-                    }
-                    Mode::Normal => {
-                        // Check if click is on menu bar (top row)
-                        if mouse.row == 0 {
-                            self.handle_menu_bar_click(mouse.column)?;
-                        }
-                        // Check if click is on key hints area (bottom 2 rows)
-                        else if mouse.row >= terminal_size.height.saturating_sub(2) {
-                            self.handle_hints_click(mouse.column, terminal_size.width)?;
-                        } else {
-                            self.handle_mouse_click(
-                                mouse.column,
-                                mouse.row,
-                                terminal_size,
-                                mouse.modifiers,
-                            )?;
-                        }
-                    }
-                    Mode::Menu {
-                        active_menu,
-                        selected_item,
-                    } => {
-                        self.handle_menu_click(
-                            mouse.column,
-                            mouse.row,
-                            active_menu,
-                            selected_item,
-                        )?;
+    fn handle_aging_report_input(&mut self, key: KeyEvent) -> Result<()> {
+        let selected = self.aging_selected;
+        let last = self.aging_rows.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.close_aging_report(),
+            KeyCode::Enter => self.go_to_aging_row(),
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.aging_selected = selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.aging_selected = (selected + 1).min(last),
+            KeyCode::PageUp => {
+                self.aging_selected = selected.saturating_sub(self.viewport_height as usize);
+            }
+            KeyCode::PageDown => {
+                self.aging_selected = (selected + self.viewport_height as usize).min(last);
+            }
+            KeyCode::Home => self.aging_selected = 0,
+            KeyCode::End => self.aging_selected = last,
+            KeyCode::Char(' ') | KeyCode::Insert => {
+                if let Some(row) = self.aging_rows.get(selected).copied() {
+                    let marked = self.marked_mut(row.panel);
+                    if !marked.remove(&row.index) {
+                        marked.insert(row.index);
                     }
-                    _ => {}
+                    self.aging_selected = (selected + 1).min(last);
                 }
             }
-            MouseEventKind::ScrollUp => {
-                // Only scroll in Normal mode
-                if self.mode == Mode::Normal {
-                    self.move_selection(1);
+            KeyCode::Char('s') | KeyCode::Char('S') => self.mark_stale_entries(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_usage_suggestions_input(&mut self, key: KeyEvent) -> Result<()> {
+        let selected = self.usage_selected;
+        let last = self.usage_rows.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.usage_rows.clear();
+                self.mode = Mode::Normal;
+            }
+            KeyCode::Enter => self.apply_usage_suggestions(),
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.usage_selected = selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.usage_selected = (selected + 1).min(last),
+            KeyCode::PageUp => {
+                self.usage_selected = selected.saturating_sub(self.viewport_height as usize);
+            }
+            KeyCode::PageDown => {
+                self.usage_selected = (selected + self.viewport_height as usize).min(last);
+            }
+            KeyCode::Home => self.usage_selected = 0,
+            KeyCode::End => self.usage_selected = last,
+            KeyCode::Char(' ') | KeyCode::Insert => {
+                if let Some(row) = self.usage_rows.get_mut(selected) {
+                    row.include = !row.include;
+                    self.usage_selected = (selected + 1).min(last);
                 }
             }
-            MouseEventKind::ScrollDown => {
-                // Only scroll in Normal mode
-                if self.mode == Mode::Normal {
-                    self.move_selection(-1);
+            KeyCode::Char('a') | KeyCode::Char('A') => {
+                let include = !self.usage_rows.iter().all(|row| row.include);
+                for row in &mut self.usage_rows {
+                    row.include = include;
                 }
             }
             _ => {}
         }
-
         Ok(())
     }
 
-    fn handle_mouse_click(
-        &mut self,
-        x: u16,
-        y: u16,
-        terminal_size: Rect,
-        modifiers: KeyModifiers,
-    ) -> Result<()> {
-        // Calculate layout (same as ui.rs render_main)
-        let header_height = 3;
-        let status_height = 3;
-        let hints_height = 2;
-
-        // Main content area
-        let content_start = header_height;
-        let content_end = terminal_size.height - status_height - hints_height;
-
-        // Check if click is in main content area
-        if y < content_start || y >= content_end {
-            return Ok(());
-        }
-
-        // Determine which panel was clicked (50/50 split)
-        let panel_width = terminal_size.width / 2;
-        let clicked_panel = if x < panel_width {
-            Panel::Machine
-        } else {
-            Panel::User
-        };
-
-        // Get panel-specific coordinates
-        let panel_x_offset = if clicked_panel == Panel::Machine {
-            0
-        } else {
-            panel_width
-        };
-        let relative_x = x - panel_x_offset;
-        let relative_y = y - content_start;
-
-        // Check if click is on scrollbar (second-to-last column, before right border)
-        let scrollbar_x = panel_width - 2;
-        if relative_x == scrollbar_x {
-            // Clicked on scrollbar - jump to position
-            let border_top: u16 = 1;
-            let border_bottom: u16 = 1;
-            let content_height = content_end - content_start;
-            let scrollbar_height =
-                content_height.saturating_sub(border_top + border_bottom) as usize;
-
-            if scrollbar_height > 0 {
-                let click_pos = relative_y.saturating_sub(border_top) as usize;
-                let paths = match clicked_panel {
-                    Panel::Machine => &self.machine_paths,
-                    Panel::User => &self.user_paths,
+    fn handle_merge_duplicates_input(&mut self, key: KeyEvent) -> Result<()> {
+        let selected = self.merge_selected;
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.merge_pairs.clear();
+                self.mode = Mode::Normal;
+                self.set_status("Merge cancelled");
+            }
+            KeyCode::Enter => self.apply_merge_duplicates(),
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.merge_selected = selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                if selected + 1 < self.merge_pairs.len() {
+                    self.merge_selected += 1;
+                }
+            }
+            KeyCode::Home => self.merge_selected = 0,
+            KeyCode::End => self.merge_selected = self.merge_pairs.len().saturating_sub(1),
+            code => {
+                let Some(pair) = self.merge_pairs.get_mut(selected) else {
+                    return Ok(());
                 };
+                pair.keep = match code {
+                    KeyCode::Left => pair.keep.previous(),
+                    KeyCode::Right | KeyCode::Char(' ') => pair.keep.next(),
+                    KeyCode::Char('m') | KeyCode::Char('M') => MergeKeep::Machine,
+                    KeyCode::Char('u') | KeyCode::Char('U') => MergeKeep::User,
+                    KeyCode::Char('b') | KeyCode::Char('B') => MergeKeep::Both,
+                    _ => return Ok(()),
+                };
+            }
+        }
+        Ok(())
+    }
 
-                if !paths.is_empty() && click_pos < scrollbar_height {
-                    // Calculate target position as percentage
-                    let target_pos = (click_pos * paths.len()) / scrollbar_height;
-                    let target_pos = target_pos.min(paths.len().saturating_sub(1));
-
-                    self.active_panel = clicked_panel;
-                    self.move_selection_to(target_pos);
+    fn handle_nested_entries_input(&mut self, key: KeyEvent) -> Result<()> {
+        let selected = self.nested_selected;
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.nested_pairs.clear();
+                self.mode = Mode::Normal;
+                self.set_status("Consolidation cancelled");
+            }
+            KeyCode::Enter => self.apply_nested_entries(),
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.nested_selected = selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                if selected + 1 < self.nested_pairs.len() {
+                    self.nested_selected += 1;
                 }
             }
-            return Ok(());
+            KeyCode::Home => self.nested_selected = 0,
+            KeyCode::End => self.nested_selected = self.nested_pairs.len().saturating_sub(1),
+            code => {
+                let Some(pair) = self.nested_pairs.get_mut(selected) else {
+                    return Ok(());
+                };
+                pair.fix = match code {
+                    KeyCode::Left => pair.fix.previous(),
+                    KeyCode::Right | KeyCode::Char(' ') => pair.fix.next(),
+                    KeyCode::Char('b') | KeyCode::Char('B') => NestedFix::KeepBoth,
+                    KeyCode::Char('p') | KeyCode::Char('P') => NestedFix::RemoveParent,
+                    KeyCode::Char('c') | KeyCode::Char('C') => NestedFix::RemoveChild,
+                    _ => return Ok(()),
+                };
+            }
         }
+        Ok(())
+    }
 
-        // Check if click is on border (first or last column of panel)
-        if relative_x == 0 || relative_x >= panel_width - 1 {
-            // Clicked on panel border - switch to this panel
-            self.active_panel = clicked_panel;
-            return Ok(());
+    fn handle_ignore_list_input(&mut self, key: KeyEvent) -> Result<()> {
+        let count = self.settings.ignored.len();
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => self.mode = Mode::Normal,
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.ignore_selected = self.ignore_selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') if self.ignore_selected + 1 < count => {
+                self.ignore_selected += 1;
+            }
+            KeyCode::Home => self.ignore_selected = 0,
+            KeyCode::End => self.ignore_selected = count.saturating_sub(1),
+            KeyCode::Insert | KeyCode::Char('a') | KeyCode::Char('A') => self.start_ignore_rule(),
+            KeyCode::Delete | KeyCode::Char('d') | KeyCode::Char('D') => {
+                self.remove_ignore_rule(self.ignore_selected)
+            }
+            _ => {}
         }
+        Ok(())
+    }
 
-        // Click is inside panel content
-        // Account for: top border (1) + title (0, included in border)
-        let border_top = 1;
-
-        // Check if click is on border row
-        if relative_y == 0 {
-            // Clicked on top border/title - switch to this panel
-            self.active_panel = clicked_panel;
+    fn handle_raw_editor_input(&mut self, key: KeyEvent) -> Result<()> {
+        let page = self.viewport_height.saturating_sub(1) as usize;
+        let Some(editor) = self.raw_editor.as_mut() else {
+            self.mode = Mode::Normal;
             return Ok(());
-        }
-
-        // Calculate list item index (0-based)
-        // relative_y - border_top = row in list
-        let list_row = relative_y.saturating_sub(border_top) as usize;
-
-        // Get current panel's paths
-        let paths = match clicked_panel {
-            Panel::Machine => &self.machine_paths,
-            Panel::User => &self.user_paths,
         };
-
-        // Check if list_row is valid
-        if list_row >= paths.len() {
-            // Clicked below last item - just switch panel
-            self.active_panel = clicked_panel;
+        if editor.reviewing {
+            match key.code {
+                KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => self.commit_raw_edit(),
+                KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => editor.reviewing = false,
+                _ => {}
+            }
             return Ok(());
         }
-
-        // Handle Ctrl+Click: Toggle mark on clicked item without changing selection
-        if modifiers.contains(KeyModifiers::CONTROL) {
-            match clicked_panel {
-                Panel::Machine => {
-                    if self.machine_marked.contains(&list_row) {
-                        self.machine_marked.remove(&list_row);
-                    } else {
-                        self.machine_marked.insert(list_row);
-                    }
-                }
-                Panel::User => {
-                    if self.user_marked.contains(&list_row) {
-                        self.user_marked.remove(&list_row);
-                    } else {
-                        self.user_marked.insert(list_row);
-                    }
+        match (key.code, key.modifiers) {
+            (KeyCode::Esc, _) => {
+                self.raw_editor = None;
+                self.mode = Mode::Normal;
+                self.set_status("Raw edit discarded");
+            }
+            (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
+                if editor.is_changed() {
+                    editor.reviewing = true;
+                } else {
+                    self.set_status("Nothing changed yet");
                 }
             }
-            return Ok(());
+            _ => {
+                editor.text.handle_key(key, page, &mut self.clipboard);
+            }
         }
+        Ok(())
+    }
 
-        // Handle Shift+Click: Range select from current selection to clicked item
-        if modifiers.contains(KeyModifiers::SHIFT) {
-            let current_selection = match clicked_panel {
-                Panel::Machine => self.machine_selected,
-                Panel::User => self.user_selected,
-            };
-
-            let start = current_selection.min(list_row);
-            let end = current_selection.max(list_row);
-
-            match clicked_panel {
-                Panel::Machine => {
-                    for i in start..=end {
-                        self.machine_marked.insert(i);
-                    }
+    fn handle_host_picker_input(&mut self, key: KeyEvent) -> Result<()> {
+        let selected = self.host_selected;
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.host_selected = selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') if selected + 1 < self.host_list.len() => {
+                self.host_selected += 1;
+            }
+            KeyCode::Enter => {
+                if let Some(host) = self.host_list.get(selected) {
+                    let address = host.address.clone();
+                    self.mode = Mode::Normal;
+                    self.start_remote_connect(&address);
                 }
-                Panel::User => {
-                    for i in start..=end {
-                        self.user_marked.insert(i);
-                    }
+            }
+            KeyCode::Char('t') | KeyCode::Char('T') => {
+                self.mode = Mode::Input(InputMode::ConnectRemote);
+                self.input_buffer.clear();
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') => {
+                if let Some(host) = self.host_list.get(selected) {
+                    self.host_notes = Some(TextArea::new(&host.notes));
+                    self.mode = Mode::HostNotes;
                 }
             }
-
-            self.active_panel = clicked_panel;
-            self.move_selection_to(list_row);
-            return Ok(());
-        }
-
-        // Normal click: Switch to this panel and select the item
-        self.active_panel = clicked_panel;
-        self.move_selection_to(list_row);
-
-        // Define checkbox area bounds (used for both double-click check and marking)
-        // Checkbox is at relative_x = 1 (border) to 5 (border + "[ ] ")
-        let checkbox_start = 1; // After left border
-        let checkbox_end = 5; // "[ ] " is 4 chars
-
-        // Check for double-click (two clicks on same item within 500ms)
-        let now = std::time::Instant::now();
-        let double_click_threshold = std::time::Duration::from_millis(500);
-        let is_same_position = self.last_click_pos == (clicked_panel, list_row);
-        let is_within_time = now.duration_since(self.last_click_time) < double_click_threshold;
-
-        if is_same_position && is_within_time && relative_x >= checkbox_end {
-            // Double-click detected outside checkbox area - edit the path
-            self.start_edit_path();
-            // Reset click tracking to prevent triple-click issues
-            self.last_click_time = std::time::Instant::now() - double_click_threshold;
-            return Ok(());
-        }
-
-        // Update click tracking for next potential double-click
-        self.last_click_time = now;
-        self.last_click_pos = (clicked_panel, list_row);
-
-        // Check if click is on checkbox area
-
-        if relative_x >= checkbox_start && relative_x < checkbox_end {
-            // Clicked on checkbox - toggle mark (without auto-advance)
-            match self.active_panel {
-                Panel::Machine => {
-                    if self.machine_marked.contains(&self.machine_selected) {
-                        self.machine_marked.remove(&self.machine_selected);
-                    } else {
-                        self.machine_marked.insert(self.machine_selected);
-                    }
+            KeyCode::Delete if selected < self.host_list.len() => {
+                let host = self.host_list.remove(selected);
+                match self.save_host_list() {
+                    Ok(()) => self.set_status(&format!("Removed {} from the hosts", host.name)),
+                    Err(e) => self.set_status(&format!("{:#}", e)),
                 }
-                Panel::User => {
-                    if self.user_marked.contains(&self.user_selected) {
-                        self.user_marked.remove(&self.user_selected);
-                    } else {
-                        self.user_marked.insert(self.user_selected);
-                    }
+                self.host_selected = selected.min(self.host_list.len().saturating_sub(1));
+                if self.host_list.is_empty() {
+                    self.mode = Mode::Normal;
                 }
             }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.mode = Mode::Normal;
+            }
+            _ => {}
         }
-
         Ok(())
     }
 
-    fn handle_hints_click(&mut self, x: u16, width: u16) -> Result<()> {
-        // Key hints are centered, so calculate the starting position
-        // MC-style format: "1Help | 2Mark | 3Del | 4Add | /Filter | Ctrl+SSave | QQuit"
-        // Note: Actual hints vary by context (filter active, items marked, normal mode)
-        // Approximate length for normal mode: ~80 characters (was 100 in old format)
-        let hint_text_len = 80;
-        let start_x = (width.saturating_sub(hint_text_len)) / 2;
-
-        // Calculate relative position
-        if x < start_x {
+    fn handle_host_notes_input(&mut self, key: KeyEvent) -> Result<()> {
+        let page = self.viewport_height.saturating_sub(1) as usize;
+        let Some(notes) = self.host_notes.as_mut() else {
+            self.mode = Mode::HostPicker;
             return Ok(());
-        }
-
-        let relative_x = x - start_x;
-
-        // Map click positions to keys (MC-style format):
-        // "1" (1) + "Help" (4) + " | " (3) = 0-7
-        // "2" (1) + "Mark" (4) + " | " (3) = 8-15
-        // "3" (1) + "Del" (3) + " | " (3) = 16-22
-        // "4" (1) + "Add" (3) + " | " (3) = 23-29
-        // "/" (1) + "Filter" (6) + " | " (3) = 30-39
-        // "Ctrl+Z" (6) + "Undo" (4) + " | " (3) = 40-52 (conditional)
-        // "Ctrl+Y" (6) + "Redo" (4) + " | " (3) = 53-65 (conditional)
-        // "Ctrl+S" (6) + "Save" (4) + " | " (3) = ~66-78
-        // "Q" (1) + "Quit" (4) = ~79-83
-
-        match relative_x {
-            0..=7 => self.mode = Mode::Help, // 1Help
-            8..=15 => self.toggle_mark(),    // 2Mark
-            16..=22 => {
-                // 3Del
-                if self.has_marked_items() {
-                    self.mode = Mode::Confirm(ConfirmAction::DeleteSelected);
-                }
-            }
-            23..=29 => self.start_add_path(), // 4Add
-            30..=39 => {
-                // /Filter - toggle filter or something
-                // This used to be F5 Move, but now it's /Filter
-                // Adjust based on context
-            }
-            40..=90 => {
-                // This region contains conditional items (Undo/Redo) and final items (Save/Quit)
-                // Due to dynamic nature of hints, we'll handle Save and Quit conservatively
-                // TODO: Make this more robust by calculating exact positions dynamically
-                if relative_x >= 70 {
-                    // Likely in the Save/Quit region
-                    if relative_x >= 79 {
-                        self.confirm_exit(); // QQuit
-                    } else {
-                        // Ctrl+SSave
-                        if self.has_changes {
-                            self.mode = Mode::Confirm(ConfirmAction::ApplyChanges);
-                        } else {
-                            self.set_status("No changes to save");
-                        }
+        };
+        match (key.code, key.modifiers) {
+            (KeyCode::Esc, _) => {
+                self.host_notes = None;
+                self.mode = Mode::HostPicker;
+            }
+            (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
+                let text = notes.lines.join("\n").trim_end().to_string();
+                self.host_notes = None;
+                self.mode = Mode::HostPicker;
+                if let Some(host) = self.host_list.get_mut(self.host_selected) {
+                    host.notes = text;
+                    let name = host.name.clone();
+                    match self.save_host_list() {
+                        Ok(()) => self.set_status(&format!("Notes for {} saved", name)),
+                        Err(e) => self.set_status(&format!("{:#}", e)),
                     }
                 }
             }
-            _ => {}
+            _ => {
+                notes.handle_key(key, page, &mut self.clipboard);
+            }
         }
-
         Ok(())
     }
 
-    fn handle_confirm_dialog_click(&mut self, x: u16, y: u16, terminal_size: Rect) -> Result<()> {
-        // Dialog is 60% width, 30% height, centered
-        let dialog_width = (terminal_size.width * 60) / 100;
-        let dialog_height = (terminal_size.height * 30) / 100;
-        let dialog_x = (terminal_size.width - dialog_width) / 2;
-        let dialog_y = (terminal_size.height - dialog_height) / 2;
-
-        // Check if click is within dialog bounds
-        if x < dialog_x || x >= dialog_x + dialog_width {
-            return Ok(());
-        }
-        if y < dialog_y || y >= dialog_y + dialog_height {
-            return Ok(());
+    fn handle_batch_replace_input(&mut self, key: KeyEvent) -> Result<()> {
+        let selected = self.replace_selected;
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.replace_rows.clear();
+                self.mode = Mode::Normal;
+                self.set_status("Replace cancelled");
+            }
+            KeyCode::Enter => self.apply_batch_replace(),
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.replace_selected = selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') if selected + 1 < self.replace_rows.len() => {
+                self.replace_selected += 1;
+            }
+            KeyCode::PageUp => {
+                self.replace_selected = selected.saturating_sub(self.viewport_height as usize);
+            }
+            KeyCode::PageDown => {
+                self.replace_selected = (selected + self.viewport_height as usize)
+                    .min(self.replace_rows.len().saturating_sub(1));
+            }
+            KeyCode::Home => self.replace_selected = 0,
+            KeyCode::End => self.replace_selected = self.replace_rows.len().saturating_sub(1),
+            KeyCode::Char(' ') | KeyCode::Insert => {
+                if let Some(row) = self.replace_rows.get_mut(selected) {
+                    row.include = !row.include;
+                }
+            }
+            // Leave out the rows whose new directory is missing, or take them all back
+            KeyCode::Char('x') | KeyCode::Char('X') => {
+                let skip_missing = self
+                    .replace_rows
+                    .iter()
+                    .any(|row| row.include && !row.exists);
+                for row in &mut self.replace_rows {
+                    row.include = row.exists || !skip_missing;
+                }
+            }
+            _ => {}
         }
+        Ok(())
+    }
 
-        // Calculate relative position within dialog
-        let relative_y = y - dialog_y;
-
-        // "Yes / No" is on line 3 of the dialog (after empty line, message, empty line)
-        // Dialog has border, so content starts at y=1
-        // Line 0: border
-        // Line 1: empty
-        // Line 2: message
-        // Line 3: empty
-        // Line 4: "Yes / No"
-        if relative_y == 4 {
-            let relative_x = x - dialog_x;
-            let center_x = dialog_width / 2;
-
-            // "Yes / No" is centered
-            // "Y" is approximately at center - 4
-            // "N" is approximately at center + 3
-            let yes_x = center_x.saturating_sub(4);
-            let no_x = center_x + 3;
-
-            if relative_x >= yes_x.saturating_sub(2) && relative_x <= yes_x + 2 {
-                // Clicked on "Yes"
-                if let Mode::Confirm(action) = self.mode {
-                    self.mode = Mode::Normal;
-                    match action {
-                        ConfirmAction::Exit => {
-                            self.should_exit = true;
-                        }
-                        ConfirmAction::DeleteSelected => self.delete_marked()?,
-                        ConfirmAction::DeleteAllDead => self.delete_all_dead()?,
-                        ConfirmAction::DeleteAllDuplicates => self.delete_all_duplicates()?,
-                        ConfirmAction::ApplyChanges => self.apply_changes()?,
-                        ConfirmAction::RequestElevation => {
-                            self.request_elevation()?;
-                        }
-                        ConfirmAction::RestoreBackup => self.restore_selected_backup()?,
-                        ConfirmAction::CreateSingleDirectory => self.create_single_directory()?,
-                        ConfirmAction::CreateMarkedDirectories => {
-                            self.create_marked_directories()?
-                        }
-                        ConfirmAction::DisconnectRemote => {
-                            self.disconnect_from_remote()?;
-                            self.set_status("Disconnected from remote computer");
-                        }
-                    }
+    fn handle_backup_list_input(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                if self.backup_selected > 0 {
+                    self.backup_selected -= 1;
                 }
-            } else if relative_x >= no_x.saturating_sub(2) && relative_x <= no_x + 2 {
-                // Clicked on "No"
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                if self.backup_selected + 1 < self.backup_list.len() {
+                    self.backup_selected += 1;
+                }
+            }
+            KeyCode::Enter => {
+                if !self.backup_list.is_empty() {
+                    self.mode = Mode::Confirm(ConfirmAction::RestoreBackup);
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
                 self.mode = Mode::Normal;
             }
+            _ => {}
         }
-
         Ok(())
     }
 
-    // Navigation helpers
-    fn move_selection(&mut self, delta: i32) {
-        match self.active_panel {
-            Panel::Machine => {
-                let new_pos = (self.machine_selected as i32 + delta)
-                    .max(0)
-                    .min(self.machine_paths.len().saturating_sub(1) as i32);
-                self.machine_selected = new_pos as usize;
-                self.machine_scrollbar_state =
-                    self.machine_scrollbar_state.position(self.machine_selected);
+    fn handle_file_browser_input(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                if self.file_browser_selected > 0 {
+                    self.file_browser_selected -= 1;
+                    self.file_browser_scrollbar_state = self
+                        .file_browser_scrollbar_state
+                        .position(self.file_browser_selected);
+                }
             }
-            Panel::User => {
-                let new_pos = (self.user_selected as i32 + delta)
-                    .max(0)
-                    .min(self.user_paths.len().saturating_sub(1) as i32);
-                self.user_selected = new_pos as usize;
-                self.user_scrollbar_state = self.user_scrollbar_state.position(self.user_selected);
+            KeyCode::Down | KeyCode::Char('j') => {
+                if self.file_browser_selected + 1 < self.file_browser_entries.len() {
+                    self.file_browser_selected += 1;
+                    self.file_browser_scrollbar_state = self
+                        .file_browser_scrollbar_state
+                        .position(self.file_browser_selected);
+                }
             }
-        }
-    }
-
-    fn move_selection_to(&mut self, pos: usize) {
-        match self.active_panel {
-            Panel::Machine => {
-                self.machine_selected = pos.min(self.machine_paths.len().saturating_sub(1));
-                self.machine_scrollbar_state =
-                    self.machine_scrollbar_state.position(self.machine_selected);
+            KeyCode::PageUp => {
+                let jump = self.viewport_height.saturating_sub(1).max(1) as usize;
+                self.file_browser_selected = self.file_browser_selected.saturating_sub(jump);
+                self.file_browser_scrollbar_state = self
+                    .file_browser_scrollbar_state
+                    .position(self.file_browser_selected);
             }
-            Panel::User => {
-                self.user_selected = pos.min(self.user_paths.len().saturating_sub(1));
-                self.user_scrollbar_state = self.user_scrollbar_state.position(self.user_selected);
+            KeyCode::PageDown => {
+                let jump = self.viewport_height.saturating_sub(1).max(1) as usize;
+                let max_idx = self.file_browser_entries.len().saturating_sub(1);
+                self.file_browser_selected = (self.file_browser_selected + jump).min(max_idx);
+                self.file_browser_scrollbar_state = self
+                    .file_browser_scrollbar_state
+                    .position(self.file_browser_selected);
             }
-        }
-    }
-
-    fn toggle_mark(&mut self) {
-        match self.active_panel {
-            Panel::Machine => {
-                if self.machine_marked.contains(&self.machine_selected) {
-                    self.machine_marked.remove(&self.machine_selected);
-                } else {
-                    self.machine_marked.insert(self.machine_selected);
+            KeyCode::Home | KeyCode::Char('g') => {
+                self.file_browser_selected = 0;
+                self.file_browser_scrollbar_state = self
+                    .file_browser_scrollbar_state
+                    .position(self.file_browser_selected);
+            }
+            KeyCode::End | KeyCode::Char('G') => {
+                if !self.file_browser_entries.is_empty() {
+                    self.file_browser_selected = self.file_browser_entries.len() - 1;
+                    self.file_browser_scrollbar_state = self
+                        .file_browser_scrollbar_state
+                        .position(self.file_browser_selected);
                 }
-                self.move_selection(1);
             }
-            Panel::User => {
-                if self.user_marked.contains(&self.user_selected) {
-                    self.user_marked.remove(&self.user_selected);
-                } else {
-                    self.user_marked.insert(self.user_selected);
+            KeyCode::Enter => {
+                // If an entry is selected, navigate into it
+                if !self.file_browser_entries.is_empty() {
+                    self.navigate_to_selected_directory();
                 }
-                self.move_selection(1);
             }
+            KeyCode::Char(' ') => {
+                // Space key: select current directory for adding to PATH
+                self.select_current_directory_for_path()?;
+            }
+            KeyCode::Tab => {
+                // Tab: switch to manual text input mode
+                self.mode = Mode::Input(InputMode::AddPath);
+                self.mode_enter_time = std::time::Instant::now();
+                self.input_buffer = self.file_browser_current_path.to_string_lossy().to_string();
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                // Cancel file browser
+                self.mode = Mode::Normal;
+            }
+            _ => {}
         }
+        Ok(())
     }
 
-    pub fn has_marked_items(&self) -> bool {
-        !self.machine_marked.is_empty() || !self.user_marked.is_empty()
-    }
+    fn handle_filter_menu_input(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                if self.filter_menu_selected > 0 {
+                    self.filter_menu_selected -= 1;
+                }
+            }
+            // Status filters (None, Dead, Duplicates, NonNormalized, Valid), then kinds
+            KeyCode::Down | KeyCode::Char('j')
+                if self.filter_menu_selected + 1 < FILTER_MENU_ITEMS =>
+            {
+                self.filter_menu_selected += 1;
+            }
+            KeyCode::Enter => {
+                // Apply selected filter
+                let new_filter = match self.filter_menu_selected {
+                    0 => FilterMode::None,
+                    1 => FilterMode::Dead,
+                    2 => FilterMode::Duplicates,
+                    3 => FilterMode::NonNormalized,
+                    4 => FilterMode::Valid,
+                    n if n < FILTER_MENU_ITEMS => FilterMode::Kind(PathKind::ALL[n - 5]),
+                    _ => FilterMode::None,
+                };
 
-    pub fn has_marked_dead_paths(&self) -> bool {
-        // Check if any marked items in the active panel are dead paths
-        match self.active_panel {
-            Panel::Machine => self
-                .machine_marked
-                .iter()
-                .any(|&idx| idx < self.machine_info.len() && !self.machine_info[idx].exists),
-            Panel::User => self
-                .user_marked
-                .iter()
-                .any(|&idx| idx < self.user_info.len() && !self.user_info[idx].exists),
+                // Set the filter mode directly (don't toggle)
+                self.set_filter(new_filter, None);
+                self.set_status(&format!("Filter: {}", new_filter.label()));
+                self.mode = Mode::Normal;
+            }
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('/') => {
+                // Close menu without changing filter
+                self.mode = Mode::Normal;
+            }
+            _ => {}
         }
+        Ok(())
     }
 
-    // Path modification
-    fn delete_marked(&mut self) -> Result<()> {
-        let mut deleted_count = 0;
+    fn open_theme_selector(&mut self) -> Result<()> {
+        // Load available themes
+        self.theme_list = crate::config::list_available_themes()?;
 
-        // Record machine deletions for undo
-        let mut machine_deleted = Vec::new();
-        for (idx, path) in self.machine_paths.iter().enumerate() {
-            if self.machine_marked.contains(&idx) {
-                machine_deleted.push((idx, path.clone()));
-            }
-        }
+        // Find current theme in list and select it
+        let current_name = &self.theme.name;
+        self.theme_selected = self
+            .theme_list
+            .iter()
+            .position(|(name, _)| name == current_name)
+            .unwrap_or(0);
 
-        // Delete from machine paths
-        let mut new_machine = Vec::new();
-        for (idx, path) in self.machine_paths.iter().enumerate() {
-            if !self.machine_marked.contains(&idx) {
-                new_machine.push(path.clone());
-            } else {
-                deleted_count += 1;
-            }
-        }
-        self.machine_paths = new_machine;
-        self.machine_marked.clear();
+        // Store original theme for Esc cancellation
+        self.original_theme = Some(self.theme.clone());
 
-        // Record user deletions for undo
-        let mut user_deleted = Vec::new();
-        for (idx, path) in self.user_paths.iter().enumerate() {
-            if self.user_marked.contains(&idx) {
-                user_deleted.push((idx, path.clone()));
-            }
-        }
+        self.mode = Mode::ThemeSelection;
+        Ok(())
+    }
 
-        // Delete from user paths
-        let mut new_user = Vec::new();
-        for (idx, path) in self.user_paths.iter().enumerate() {
-            if !self.user_marked.contains(&idx) {
-                new_user.push(path.clone());
-            } else {
-                deleted_count += 1;
-            }
-        }
-        self.user_paths = new_user;
-        self.user_marked.clear();
+    /// Load and apply the currently selected theme (for live preview)
+    fn apply_selected_theme(&mut self) -> Result<()> {
+        if let Some((theme_name, is_builtin)) = self.theme_list.get(self.theme_selected) {
+            let new_theme = if *is_builtin {
+                Theme::builtin(theme_name)?
+            } else {
+                // Load from custom theme file
+                if let Some(theme_path) = crate::config::get_theme_path(theme_name) {
+                    Theme::from_mc_skin(&theme_path)?
+                } else {
+                    self.set_status(&format!("Theme file not found: {}", theme_name));
+                    return Ok(());
+                }
+            };
 
-        // Clear redo stack and record undo operations
-        self.clear_redo_stack();
-        if !machine_deleted.is_empty() {
-            self.undo_stack.push(Operation::DeletePaths {
-                panel: Panel::Machine,
-                deleted: machine_deleted,
-            });
-        }
-        if !user_deleted.is_empty() {
-            self.undo_stack.push(Operation::DeletePaths {
-                panel: Panel::User,
-                deleted: user_deleted,
-            });
+            self.theme = new_theme;
         }
-
-        self.reanalyze();
-        self.has_changes = true;
-        self.set_status(&format!("Deleted {} path(s)", deleted_count));
         Ok(())
     }
 
-    fn delete_all_dead(&mut self) -> Result<()> {
-        // Record machine dead paths for undo
-        let mut machine_deleted = Vec::new();
-        for (idx, path) in self.machine_paths.iter().enumerate() {
-            if !crate::path_analyzer::path_exists(path) {
-                machine_deleted.push((idx, path.clone()));
+    fn handle_theme_selection_input(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                if self.theme_selected > 0 {
+                    self.theme_selected -= 1;
+                    // Apply theme immediately for live preview
+                    self.apply_selected_theme()?;
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                if self.theme_selected < self.theme_list.len().saturating_sub(1) {
+                    self.theme_selected += 1;
+                    // Apply theme immediately for live preview
+                    self.apply_selected_theme()?;
+                }
+            }
+            KeyCode::Enter => {
+                // Keep the currently selected theme and close
+                if let Some((theme_name, _)) = self.theme_list.get(self.theme_selected) {
+                    self.set_status(&format!("Theme changed to: {}", theme_name));
+                }
+                // Clear original theme since we're accepting the change
+                self.original_theme = None;
+                self.mode = Mode::Normal;
+            }
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('t') => {
+                // Restore original theme and close
+                if let Some(original) = self.original_theme.take() {
+                    self.theme = original;
+                }
+                self.mode = Mode::Normal;
+            }
+            KeyCode::Char('r') => {
+                // Reload theme list
+                self.theme_list = crate::config::list_available_themes()?;
+                self.set_status("Theme list reloaded");
             }
+            _ => {}
         }
+        Ok(())
+    }
 
-        // Record user dead paths for undo
-        let mut user_deleted = Vec::new();
-        for (idx, path) in self.user_paths.iter().enumerate() {
-            if !crate::path_analyzer::path_exists(path) {
-                user_deleted.push((idx, path.clone()));
+    // Mouse event handling
+    pub fn handle_mouse(&mut self, mouse: MouseEvent, terminal_size: Rect) -> Result<()> {
+        // The mouse ends a visual selection, leaving its range marked
+        self.visual = None;
+        let machine_before = self.machine_paths.clone();
+        let panels_before = self.read_only_snapshot();
+        let result = self.dispatch_mouse(mouse, terminal_size);
+        self.enforce_profile(panels_before);
+        self.note_staged_machine_edit(&machine_before);
+        self.keep_selection_visible();
+        result
+    }
+
+    fn dispatch_mouse(&mut self, mouse: MouseEvent, terminal_size: Rect) -> Result<()> {
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) if Dialog::for_mode(self.mode).is_some() => {
+                self.start_dialog_drag(mouse.column, mouse.row, terminal_size);
+                if self.dialog_drag.is_none() {
+                    self.handle_list_click(mouse.column, mouse.row, terminal_size)?;
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                self.drag_dialog(mouse.column, mouse.row, terminal_size);
+            }
+            MouseEventKind::Up(MouseButton::Left) => {
+                // Sizes are saved once the border is let go
+                if let Some(DialogDrag::Resize { dialog, .. }) = self.dialog_drag.take() {
+                    self.save_dialog_size(dialog);
+                }
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                // Handle clicks based on current mode
+                match self.mode {
+                    Mode::Confirm(_) => {
+                        self.handle_confirm_dialog_click(mouse.column, mouse.row, terminal_size)?;
+                    }
+                    Mode::Normal => {
+                        // Check if click is on menu bar (top row)
+                        if mouse.row == 0 {
+                            self.handle_menu_bar_click(mouse.column)?;
+                        }
+                        // The statistics under it filter the panels
+                        else if mouse.row == 1 {
+                            self.handle_header_click(mouse.column);
+                        }
+                        // Check if click is on key hints area (bottom 2 rows)
+                        else if mouse.row >= terminal_size.height.saturating_sub(2) {
+                            self.handle_hints_click(mouse.column, terminal_size.width)?;
+                        }
+                        // The status bar above them opens the full messages
+                        else if mouse.row >= terminal_size.height.saturating_sub(5) {
+                            self.show_message_log();
+                        }
+                        // The quick actions strip sits right above the status bar
+                        else if mouse.row
+                            >= terminal_size
+                                .height
+                                .saturating_sub(5 + self.quick_actions_height())
+                        {
+                            self.handle_quick_actions_click(mouse.column);
+                        } else {
+                            self.handle_mouse_click(
+                                mouse.column,
+                                mouse.row,
+                                terminal_size,
+                                mouse.modifiers,
+                            )?;
+                        }
+                    }
+                    Mode::BackupList
+                    | Mode::FilterMenu
+                    | Mode::ThemeSelection
+                    | Mode::HostPicker => {
+                        self.handle_list_click(mouse.column, mouse.row, terminal_size)?;
+                    }
+                    Mode::MenuBar { .. } if mouse.row == 0 => {
+                        self.handle_menu_bar_click(mouse.column)?;
+                    }
+                    Mode::MenuBar { .. } => self.mode = Mode::Normal,
+                    Mode::Menu {
+                        active_menu,
+                        selected_item,
+                    } => {
+                        self.handle_menu_click(
+                            mouse.column,
+                            mouse.row,
+                            active_menu,
+                            selected_item,
+                            terminal_size.width,
+                        )?;
+                    }
+                    _ => {}
+                }
+            }
+            MouseEventKind::Moved => {
+                self.hovered = if self.mode == Mode::Normal {
+                    self.entry_at(mouse.column, mouse.row, terminal_size)
+                } else {
+                    None
+                };
+            }
+            MouseEventKind::ScrollUp => {
+                if self.mode == Mode::Normal {
+                    self.move_selection(1);
+                } else if self.list_layout(terminal_size).is_some() {
+                    // List dialogs move their selection like the arrow keys
+                    self.dispatch_input(KeyEvent::from(KeyCode::Up))?;
+                }
+            }
+            MouseEventKind::ScrollDown => {
+                if self.mode == Mode::Normal {
+                    self.move_selection(-1);
+                } else if self.list_layout(terminal_size).is_some() {
+                    self.dispatch_input(KeyEvent::from(KeyCode::Down))?;
+                }
             }
+            _ => {}
         }
 
-        let machine_before = self.machine_paths.len();
-        let user_before = self.user_paths.len();
+        Ok(())
+    }
 
-        self.machine_paths
-            .retain(|p| crate::path_analyzer::path_exists(p));
-        self.user_paths
-            .retain(|p| crate::path_analyzer::path_exists(p));
+    /// Preferred size of a dialog in percent of the terminal
+    pub fn dialog_size(&self, dialog: Dialog) -> (u16, u16) {
+        self.settings
+            .dialog_sizes
+            .get(dialog.key())
+            .copied()
+            .unwrap_or_else(|| dialog.default_size())
+    }
 
-        let deleted =
-            (machine_before - self.machine_paths.len()) + (user_before - self.user_paths.len());
+    fn set_dialog_size(&mut self, dialog: Dialog, width: i32, height: i32) {
+        let clamp = |percent: i32| percent.clamp(MIN_DIALOG_PERCENT as i32, 100) as u16;
+        self.settings
+            .dialog_sizes
+            .insert(dialog.key().to_string(), (clamp(width), clamp(height)));
+    }
 
-        // Clear redo stack and record undo operations
-        self.clear_redo_stack();
-        if !machine_deleted.is_empty() {
-            self.undo_stack.push(Operation::DeletePaths {
-                panel: Panel::Machine,
-                deleted: machine_deleted,
-            });
-        }
-        if !user_deleted.is_empty() {
-            self.undo_stack.push(Operation::DeletePaths {
-                panel: Panel::User,
-                deleted: user_deleted,
-            });
+    fn save_dialog_size(&mut self, dialog: Dialog) {
+        let (width, height) = self.dialog_size(dialog);
+        match config::save_setting(
+            &self.settings_file,
+            "dialogs",
+            dialog.key(),
+            &format!("{}x{}", width, height),
+        ) {
+            Ok(()) => self.set_status(&format!("Dialog size {}% x {}% saved", width, height)),
+            Err(e) => self.set_status(&format!("Could not save dialog size: {:#}", e)),
         }
-
-        self.reanalyze();
-        self.has_changes = true;
-        self.set_status(&format!("Deleted {} dead path(s)", deleted));
-        Ok(())
     }
 
-    fn delete_all_duplicates(&mut self) -> Result<()> {
-        let mut seen = HashSet::new();
-        let mut deleted = 0;
+    /// Where a dialog is drawn: its size, centered, then moved by the mouse
+    pub fn dialog_area(&self, dialog: Dialog, area: Rect) -> Rect {
+        let (width, height) = self.dialog_size(dialog);
+        let rect = crate::ui::centered_rect(width, height, area);
+        let (dx, dy) = self
+            .dialog_offsets
+            .get(&dialog)
+            .copied()
+            .unwrap_or_default();
+        // Keep the whole dialog on screen
+        let x =
+            (rect.x as i32 + dx as i32).clamp(area.x as i32, (area.right() - rect.width) as i32);
+        let y =
+            (rect.y as i32 + dy as i32).clamp(area.y as i32, (area.bottom() - rect.height) as i32);
+        Rect {
+            x: x as u16,
+            y: y as u16,
+            ..rect
+        }
+    }
 
-        // Identify duplicates in machine paths for undo
-        let mut machine_deleted = Vec::new();
-        for (idx, path) in self.machine_paths.iter().enumerate() {
-            let normalized = normalize_path(path).to_lowercase();
-            if seen.contains(&normalized) {
-                machine_deleted.push((idx, path.clone()));
-            } else {
-                seen.insert(normalized);
+    /// The list dialog open right now and where its items are, None in other modes
+    pub fn list_layout(&self, terminal_size: Rect) -> Option<ListLayout> {
+        use crate::ui::content_sized_rect;
+        let inside = |area: Rect| Rect {
+            x: area.x + 1,
+            y: area.y + 1,
+            width: area.width.saturating_sub(2),
+            height: area.height.saturating_sub(2),
+        };
+        let layout = match self.mode {
+            Mode::HostPicker => {
+                // Two lines per host: name and address, then its last session and notes
+                let len = self.host_list.len();
+                let area = content_sized_rect(60, len.min(8) as u16 * 2, terminal_size);
+                ListLayout::new(area, inside(area), 2, len, self.host_selected)
+            }
+            Mode::BackupList => {
+                // Auto-sized: up to 15 backups, ~35 chars wide for the file name
+                let len = self.backup_list.len();
+                let area = content_sized_rect(40, len.min(15) as u16 + 2, terminal_size);
+                ListLayout::new(area, inside(area), 1, len, self.backup_selected)
+            }
+            Mode::FilterMenu => {
+                // Two lines per filter: name, then description
+                let area = content_sized_rect(45, FILTER_MENU_ITEMS as u16 * 2 + 1, terminal_size);
+                let selected = self.filter_menu_selected;
+                ListLayout::new(area, inside(area), 2, FILTER_MENU_ITEMS, selected)
+            }
+            Mode::ThemeSelection => {
+                let len = self.theme_list.len();
+                let longest = self
+                    .theme_list
+                    .iter()
+                    .map(|(name, _)| name.len())
+                    .max()
+                    .unwrap_or(20)
+                    .min(40);
+                let area =
+                    content_sized_rect(longest as u16 + 10, len.min(15) as u16 + 2, terminal_size);
+                ListLayout::new(area, inside(area), 1, len, self.theme_selected)
+            }
+            Mode::FileBrowser => {
+                // Current path (2 rows) above the list, key hints (2 rows) below it
+                let area = self.dialog_area(Dialog::FileBrowser, terminal_size);
+                let inner = inside(area);
+                let rows = Rect {
+                    y: inner.y + 2,
+                    height: inner.height.saturating_sub(4),
+                    ..inner
+                };
+                let len = self.file_browser_entries.len();
+                ListLayout::new(area, rows, 1, len, self.file_browser_selected)
             }
+            _ => return None,
+        };
+        Some(layout)
+    }
+
+    /// Click selects a list item, a second click on it within 500ms opens it like Enter
+    fn handle_list_click(&mut self, x: u16, y: u16, terminal_size: Rect) -> Result<()> {
+        let Some(index) = self
+            .list_layout(terminal_size)
+            .and_then(|layout| layout.item_at(x, y))
+        else {
+            return Ok(());
+        };
+        let now = std::time::Instant::now();
+        let double_click = self.last_list_click.is_some_and(|(mode, item, time)| {
+            mode == self.mode
+                && item == index
+                && now.duration_since(time) < std::time::Duration::from_millis(500)
+        });
+        if double_click {
+            self.last_list_click = None;
+            return self.dispatch_input(KeyEvent::from(KeyCode::Enter));
         }
+        self.last_list_click = Some((self.mode, index, now));
+        self.select_list_item(index)
+    }
 
-        // Keep first occurrence of each path (case-insensitive, normalized)
-        seen.clear();
-        let mut new_machine = Vec::new();
-        for path in &self.machine_paths {
-            let normalized = normalize_path(path).to_lowercase();
-            if seen.insert(normalized) {
-                new_machine.push(path.clone());
-            } else {
-                deleted += 1;
+    fn select_list_item(&mut self, index: usize) -> Result<()> {
+        match self.mode {
+            Mode::BackupList => self.backup_selected = index,
+            Mode::HostPicker => self.host_selected = index,
+            Mode::FilterMenu => self.filter_menu_selected = index,
+            Mode::ThemeSelection if index != self.theme_selected => {
+                self.theme_selected = index;
+                // Same live preview as the arrow keys
+                self.apply_selected_theme()?;
+            }
+            Mode::FileBrowser => {
+                self.file_browser_selected = index;
+                self.file_browser_scrollbar_state =
+                    self.file_browser_scrollbar_state.position(index);
             }
+            _ => {}
         }
-        self.machine_paths = new_machine;
+        Ok(())
+    }
 
-        // Identify duplicates in user paths for undo
-        let mut user_deleted = Vec::new();
-        for (idx, path) in self.user_paths.iter().enumerate() {
-            let normalized = normalize_path(path).to_lowercase();
-            if seen.contains(&normalized) {
-                user_deleted.push((idx, path.clone()));
-            } else {
-                seen.insert(normalized);
-            }
+    /// Grab a dialog by its title row to move it, or by its right/bottom border to resize it
+    fn start_dialog_drag(&mut self, x: u16, y: u16, terminal_size: Rect) {
+        let Some(dialog) = Dialog::for_mode(self.mode) else {
+            return;
+        };
+        let rect = self.dialog_area(dialog, terminal_size);
+        if x < rect.x || x >= rect.right() || y < rect.y || y >= rect.bottom() {
+            return;
         }
+        let width = x == rect.right() - 1;
+        let height = y == rect.bottom() - 1;
+        self.dialog_drag = if width || height {
+            Some(DialogDrag::Resize {
+                dialog,
+                width,
+                height,
+            })
+        } else if y == rect.y {
+            Some(DialogDrag::Move {
+                dialog,
+                from: (x, y),
+                offset: self
+                    .dialog_offsets
+                    .get(&dialog)
+                    .copied()
+                    .unwrap_or_default(),
+            })
+        } else {
+            None
+        };
+    }
 
-        let mut new_user = Vec::new();
-        for path in &self.user_paths {
-            let normalized = normalize_path(path).to_lowercase();
-            if seen.insert(normalized) {
-                new_user.push(path.clone());
-            } else {
-                deleted += 1;
+    fn drag_dialog(&mut self, x: u16, y: u16, terminal_size: Rect) {
+        match self.dialog_drag {
+            Some(DialogDrag::Move {
+                dialog,
+                from,
+                offset,
+            }) => {
+                let dx = offset.0 as i32 + x as i32 - from.0 as i32;
+                let dy = offset.1 as i32 + y as i32 - from.1 as i32;
+                self.dialog_offsets.insert(dialog, (dx as i16, dy as i16));
+            }
+            Some(DialogDrag::Resize {
+                dialog,
+                width,
+                height,
+            }) => {
+                // The dialog stays centered on the same point, so one border moves by twice as much
+                let rect = self.dialog_area(dialog, terminal_size);
+                let percent = |pos: u16, start: u16, len: u16, total: u16| {
+                    let center = start as i32 + len as i32 / 2;
+                    ((pos as i32 - center).abs() * 2 + 1) * 100 / total.max(1) as i32
+                };
+                let (w, h) = self.dialog_size(dialog);
+                let w = if width {
+                    percent(x, rect.x, rect.width, terminal_size.width)
+                } else {
+                    w as i32
+                };
+                let h = if height {
+                    percent(y, rect.y, rect.height, terminal_size.height)
+                } else {
+                    h as i32
+                };
+                self.set_dialog_size(dialog, w, h);
             }
+            None => {}
         }
-        self.user_paths = new_user;
+    }
 
-        // Clear redo stack and record undo operations
-        self.clear_redo_stack();
-        if !machine_deleted.is_empty() {
-            self.undo_stack.push(Operation::DeletePaths {
-                panel: Panel::Machine,
-                deleted: machine_deleted,
-            });
-        }
-        if !user_deleted.is_empty() {
-            self.undo_stack.push(Operation::DeletePaths {
-                panel: Panel::User,
-                deleted: user_deleted,
-            });
+    /// Entry listed at a screen position, None outside the entry rows of both panels
+    fn entry_at(&self, x: u16, y: u16, terminal_size: Rect) -> Option<(Panel, usize)> {
+        // Same layout as handle_mouse_click: header, panels, then quick actions, status bar and key hints
+        let content_start = 3;
+        let content_end = terminal_size
+            .height
+            .saturating_sub(3 + 2 + self.quick_actions_height());
+        let panel_width = terminal_size.width / 2;
+        let (panel, relative_x) = if x < panel_width {
+            (Panel::Machine, x)
+        } else {
+            (Panel::User, x - panel_width)
+        };
+
+        // Borders, the title row and the scrollbar are not entries
+        if y <= content_start
+            || y + 1 >= content_end
+            || relative_x == 0
+            || relative_x + 2 >= panel_width
+        {
+            return None;
         }
+        let row = (y - content_start - 1) as usize + self.panel_position(panel).scroll;
+        let index = *self.display_indices(panel).get(row)?;
+        Some((panel, index))
+    }
 
-        self.reanalyze();
-        self.has_changes = true;
-        self.set_status(&format!("Deleted {} duplicate path(s)", deleted));
-        Ok(())
+    /// Full entry and status of the row under the mouse pointer, shown instead of the message
+    pub fn hover_text(&self) -> Option<String> {
+        let (panel, index) = self.hovered?;
+        let (paths, info, _) = self.panel_entries(panel);
+        Some(format!(
+            "{} - {}",
+            paths.get(index)?,
+            info.get(index)?.status.description()
+        ))
     }
 
-    fn normalize_selected(&mut self) {
-        let mut normalized_count = 0;
-        let mut changes = Vec::new();
+    fn handle_mouse_click(
+        &mut self,
+        x: u16,
+        y: u16,
+        terminal_size: Rect,
+        modifiers: KeyModifiers,
+    ) -> Result<()> {
+        // Calculate layout (same as ui.rs render_main)
+        let header_height = 3;
+        let status_height = 3;
+        let hints_height = 2;
 
-        match self.active_panel {
-            Panel::Machine => {
-                for idx in &self.machine_marked {
-                    if let Some(path) = self.machine_paths.get_mut(*idx) {
-                        let normalized = normalize_path(path);
-                        if &normalized != path {
-                            changes.push((*idx, path.clone(), normalized.clone()));
-                            *path = normalized;
-                            normalized_count += 1;
-                        }
-                    }
-                }
-                self.machine_marked.clear();
+        // Main content area
+        let content_start = header_height;
+        let content_end =
+            terminal_size.height - status_height - hints_height - self.quick_actions_height();
 
-                // Clear redo stack and record undo operation for machine panel
-                if !changes.is_empty() {
-                    self.clear_redo_stack();
-                    self.undo_stack.push(Operation::NormalizePaths {
-                        panel: Panel::Machine,
-                        changes,
-                    });
-                }
-            }
-            Panel::User => {
-                for idx in &self.user_marked {
-                    if let Some(path) = self.user_paths.get_mut(*idx) {
-                        let normalized = normalize_path(path);
-                        if &normalized != path {
-                            changes.push((*idx, path.clone(), normalized.clone()));
-                            *path = normalized;
-                            normalized_count += 1;
-                        }
-                    }
-                }
-                self.user_marked.clear();
+        // Check if click is in main content area
+        if y < content_start || y >= content_end {
+            return Ok(());
+        }
 
-                // Clear redo stack and record undo operation for user panel
-                if !changes.is_empty() {
-                    self.clear_redo_stack();
-                    self.undo_stack.push(Operation::NormalizePaths {
-                        panel: Panel::User,
-                        changes,
-                    });
+        // Determine which panel was clicked (50/50 split)
+        let panel_width = terminal_size.width / 2;
+        let clicked_panel = if x < panel_width {
+            Panel::Machine
+        } else {
+            Panel::User
+        };
+
+        // Get panel-specific coordinates
+        let panel_x_offset = if clicked_panel == Panel::Machine {
+            0
+        } else {
+            panel_width
+        };
+        let relative_x = x - panel_x_offset;
+        let relative_y = y - content_start;
+
+        // Check if click is on scrollbar (second-to-last column, before right border)
+        let scrollbar_x = panel_width - 2;
+        if relative_x == scrollbar_x {
+            // Clicked on scrollbar - jump to position
+            let border_top: u16 = 1;
+            let border_bottom: u16 = 1;
+            let content_height = content_end - content_start;
+            let scrollbar_height =
+                content_height.saturating_sub(border_top + border_bottom) as usize;
+
+            if scrollbar_height > 0 {
+                let click_pos = relative_y.saturating_sub(border_top) as usize;
+                let paths = match clicked_panel {
+                    Panel::Machine => &self.machine_paths,
+                    Panel::User => &self.user_paths,
+                };
+
+                if !paths.is_empty() && click_pos < scrollbar_height {
+                    // Calculate target position as percentage
+                    let target_pos = (click_pos * paths.len()) / scrollbar_height;
+                    let target_pos = target_pos.min(paths.len().saturating_sub(1));
+
+                    self.active_panel = clicked_panel;
+                    self.move_selection_to(target_pos);
                 }
             }
+            return Ok(());
         }
 
-        if normalized_count > 0 {
-            self.reanalyze();
-            self.has_changes = true;
-            self.set_status(&format!("Normalized {} path(s)", normalized_count));
+        // Check if click is on border (first or last column of panel)
+        if relative_x == 0 || relative_x >= panel_width - 1 {
+            // Clicked on panel border - switch to this panel
+            self.active_panel = clicked_panel;
+            return Ok(());
         }
-    }
 
-    fn move_marked_to_other_panel(&mut self) -> Result<()> {
-        let from_panel = self.active_panel;
-        let to_panel = from_panel.toggle();
+        // Click is inside panel content
+        // Account for: top border (1) + title (0, included in border)
+        let border_top = 1;
 
-        // In remote mode, copy instead of move (don't delete from source)
-        let is_copy_mode = self.connection_mode == ConnectionMode::Remote;
+        // Check if click is on border row
+        if relative_y == 0 {
+            // Clicked on top border/title - switch to this panel
+            self.active_panel = clicked_panel;
+            self.handle_title_click(clicked_panel, relative_x);
+            return Ok(());
+        }
 
-        let (from_paths, to_paths, from_marked) = match from_panel {
-            Panel::Machine => (
-                &mut self.machine_paths,
-                &mut self.user_paths,
-                &mut self.machine_marked,
-            ),
-            Panel::User => (
-                &mut self.user_paths,
-                &mut self.machine_paths,
-                &mut self.user_marked,
-            ),
+        // Calculate list row (0-based), counted from the first entry listed
+        // relative_y - border_top = row on screen
+        let list_row = relative_y.saturating_sub(border_top) as usize
+            + self.panel_position(clicked_panel).scroll;
+
+        // Rows follow the filtered and sorted order the panel lists
+        let order = self.display_indices(clicked_panel);
+        let Some(&index) = order.get(list_row) else {
+            // Clicked below last item - just switch panel
+            self.active_panel = clicked_panel;
+            return Ok(());
         };
 
-        if from_marked.is_empty() {
+        // Handle Ctrl+Click: Toggle mark on clicked item without changing selection
+        if modifiers.contains(KeyModifiers::CONTROL) {
+            match clicked_panel {
+                Panel::Machine => {
+                    if self.machine_marked.contains(&index) {
+                        self.machine_marked.remove(&index);
+                    } else {
+                        self.machine_marked.insert(index);
+                    }
+                }
+                Panel::User => {
+                    if self.user_marked.contains(&index) {
+                        self.user_marked.remove(&index);
+                    } else {
+                        self.user_marked.insert(index);
+                    }
+                }
+            }
             return Ok(());
         }
 
-        // Record for undo: paths with their original indices
-        let mut paths_with_indices = Vec::new();
-        let mut indices: Vec<_> = from_marked.iter().copied().collect();
-        indices.sort_unstable(); // Sort in ascending order for recording
+        // Handle Shift+Click: Range select from current selection to clicked item
+        if modifiers.contains(KeyModifiers::SHIFT) {
+            let current_selection = match clicked_panel {
+                Panel::Machine => self.machine_selected,
+                Panel::User => self.user_selected,
+            };
 
-        for idx in &indices {
-            if let Some(path) = from_paths.get(*idx) {
-                paths_with_indices.push((*idx, path.clone()));
+            let current_row = order
+                .iter()
+                .position(|&idx| idx == current_selection)
+                .unwrap_or(list_row);
+            let start = current_row.min(list_row);
+            let end = current_row.max(list_row);
+
+            match clicked_panel {
+                Panel::Machine => {
+                    for &i in &order[start..=end] {
+                        self.machine_marked.insert(i);
+                    }
+                }
+                Panel::User => {
+                    for &i in &order[start..=end] {
+                        self.user_marked.insert(i);
+                    }
+                }
             }
+
+            self.active_panel = clicked_panel;
+            self.move_selection_to(index);
+            return Ok(());
         }
 
-        let mut moved = Vec::new();
-        indices.sort_unstable_by(|a, b| b.cmp(a)); // Reverse order to maintain indices during removal
+        // Normal click: Switch to this panel and select the item
+        self.active_panel = clicked_panel;
+        self.move_selection_to(index);
 
-        for idx in indices {
-            if let Some(path) = from_paths.get(idx) {
-                moved.push(path.clone());
-            }
+        // Define checkbox area bounds (used for both double-click check and marking)
+        // Checkbox is at relative_x = 1 (border) to 5 (border + "[ ] ")
+        let checkbox_start = 1; // After left border
+        let checkbox_end = 5; // "[ ] " is 4 chars
+
+        // Check for double-click (two clicks on same item within 500ms)
+        let now = std::time::Instant::now();
+        let double_click_threshold = std::time::Duration::from_millis(500);
+        let is_same_position = self.last_click_pos == (clicked_panel, index);
+        let is_within_time = now.duration_since(self.last_click_time) < double_click_threshold;
+
+        if is_same_position && is_within_time && relative_x >= checkbox_end {
+            // Double-click detected outside checkbox area - edit the path
+            self.start_edit_path();
+            // Reset click tracking to prevent triple-click issues
+            self.last_click_time = std::time::Instant::now() - double_click_threshold;
+            return Ok(());
         }
 
-        // In copy mode (remote), don't remove from source
-        if !is_copy_mode {
-            // Remove from source (in reverse order)
-            let mut new_from = Vec::new();
-            for (idx, path) in from_paths.iter().enumerate() {
-                if !from_marked.contains(&idx) {
-                    new_from.push(path.clone());
+        // Update click tracking for next potential double-click
+        self.last_click_time = now;
+        self.last_click_pos = (clicked_panel, index);
+
+        // Check if click is on checkbox area
+
+        if relative_x >= checkbox_start && relative_x < checkbox_end {
+            // Clicked on checkbox - toggle mark (without auto-advance)
+            match self.active_panel {
+                Panel::Machine => {
+                    if self.machine_marked.contains(&self.machine_selected) {
+                        self.machine_marked.remove(&self.machine_selected);
+                    } else {
+                        self.machine_marked.insert(self.machine_selected);
+                    }
+                }
+                Panel::User => {
+                    if self.user_marked.contains(&self.user_selected) {
+                        self.user_marked.remove(&self.user_selected);
+                    } else {
+                        self.user_marked.insert(self.user_selected);
+                    }
                 }
             }
-            *from_paths = new_from;
         }
 
-        // Add to destination
-        to_paths.extend(moved.iter().cloned());
-
-        let count = moved.len();
-        from_marked.clear();
+        Ok(())
+    }
 
-        // Clear redo stack and record undo operation
-        if !paths_with_indices.is_empty() {
-            self.clear_redo_stack();
-            if is_copy_mode {
-                self.undo_stack.push(Operation::CopyPaths {
-                    from_panel,
-                    to_panel,
-                    paths_with_indices,
-                });
-            } else {
-                self.undo_stack.push(Operation::MovePaths {
-                    from_panel,
-                    to_panel,
-                    paths_with_indices,
-                });
+    /// Clicks on a panel title: the scope name cycles the sort order, the badge toggles the filter
+    fn handle_title_click(&mut self, panel: Panel, relative_x: u16) {
+        // The title starts right after the top-left corner
+        let mut start = 1;
+        for (region, text) in self.panel_title(panel) {
+            let width = text.chars().count() as u16;
+            if relative_x >= start && relative_x < start + width {
+                match region {
+                    TitleRegion::Label => self.cycle_sort_mode(),
+                    TitleRegion::Badge => self.toggle_filter(),
+                    TitleRegion::Tag => {}
+                }
+                return;
             }
+            start += width;
         }
+    }
 
-        self.reanalyze();
-        self.has_changes = true;
-
-        let action_verb = if is_copy_mode { "Copied" } else { "Moved" };
-        let target = if self.connection_mode == ConnectionMode::Remote {
-            if to_panel == Panel::User {
-                self.remote_connection
-                    .as_ref()
-                    .map(|c| format!("remote ({})", c.computer_name()))
-                    .unwrap_or_else(|| "remote".to_string())
+    /// Everything a panel's title and the header say about it
+    pub fn panel_descriptor(&self, panel: Panel) -> PanelDescriptor {
+        let remote = self.connection_mode == ConnectionMode::Remote;
+        let (label, initial) = match (remote, panel) {
+            (false, Panel::Machine) => ("MACHINE", 'M'),
+            (false, Panel::User) => ("USER", 'U'),
+            (true, Panel::Machine) => ("LOCAL MACHINE", 'L'),
+            (true, Panel::User) => ("REMOTE MACHINE", 'R'),
+        };
+        let host = match (remote, panel) {
+            (true, Panel::User) => self
+                .remote_connection
+                .as_ref()
+                .map(|conn| conn.computer_name().to_string()),
+            _ => None,
+        };
+        let access = if self.profile.is_read_only() {
+            PanelAccess::ReadOnly
+        } else if remote && panel == Panel::User {
+            // Whether the remote computer accepts the write is only known once it is tried
+            if self.queued_remote_write.is_some() {
+                PanelAccess::Queued
             } else {
-                "local".to_string()
+                PanelAccess::Writable
             }
+        } else if panel == Panel::Machine && self.machine_staged() {
+            PanelAccess::Staged
+        } else if panel == Panel::Machine && !self.is_admin {
+            PanelAccess::ReadOnly
         } else {
-            to_panel.scope().as_str().to_string()
+            PanelAccess::Writable
         };
+        let (paths, info, _) = self.panel_entries(panel);
+        let filter = self.panel_filter(panel);
+        PanelDescriptor {
+            label,
+            initial,
+            host,
+            access,
+            filter,
+            filter_off: self.filter_mode == FilterMode::None
+                && self.last_filter != FilterMode::None
+                && self.filter_panel.is_none_or(|scope| scope == panel),
+            shown: self.get_filtered_indices(panel, info).len(),
+            total: paths.len(),
+        }
+    }
 
-        self.set_status(&format!("{} {} path(s) to {}", action_verb, count, target));
-        Ok(())
+    /// Title of a panel split into its clickable parts
+    pub fn panel_title(&self, panel: Panel) -> Vec<(TitleRegion, String)> {
+        let descriptor = self.panel_descriptor(panel);
+        let label = match self.sort_mode {
+            SortMode::PathOrder => format!(" {} ", descriptor.scope_name()),
+            sort => format!(" {} (by {}) ", descriptor.scope_name(), sort.label()),
+        };
+        // Each part brings its own trailing space so missing ones leave no gap
+        let spaced = |text: String| {
+            if text.is_empty() {
+                text
+            } else {
+                format!("{} ", text)
+            }
+        };
+        vec![
+            (TitleRegion::Label, label),
+            (
+                TitleRegion::Tag,
+                spaced(descriptor.access.tag().to_string()),
+            ),
+            (TitleRegion::Badge, spaced(descriptor.badge())),
+        ]
     }
 
-    fn move_item_up(&mut self) {
-        match self.active_panel {
-            Panel::Machine => {
-                if self.machine_selected > 0 {
-                    let idx1 = self.machine_selected;
-                    let idx2 = self.machine_selected - 1;
-
-                    self.machine_paths.swap(idx1, idx2);
-                    self.machine_selected -= 1;
+    /// Statistics line under the menu bar split into its clickable parts
+    pub fn header_stats(&self) -> Vec<(HeaderRegion, String)> {
+        let stats = self.get_statistics();
+        let mut parts = Vec::new();
+        if let Some(ref connection) = self.remote_connection {
+            parts.push((HeaderRegion::Remote, "REMOTE: ".to_string()));
+            parts.push((HeaderRegion::Host, connection.computer_name().to_string()));
+            parts.push((HeaderRegion::Label, " ".to_string()));
+        }
 
-                    // Clear redo stack and record undo operation
-                    self.clear_redo_stack();
-                    self.undo_stack.push(Operation::SwapPaths {
-                        panel: Panel::Machine,
-                        index1: idx1,
-                        index2: idx2,
-                    });
+        let machine_initial = self.panel_descriptor(Panel::Machine).initial;
+        let user_initial = self.panel_descriptor(Panel::User).initial;
+        let mut counts =
+            |label: &str, region: fn(Panel) -> HeaderRegion, machine: usize, user: usize| {
+                parts.push((HeaderRegion::Label, label.to_string()));
+                parts.push((
+                    region(Panel::Machine),
+                    format!("{}:{} ", machine_initial, machine),
+                ));
+                parts.push((region(Panel::User), format!("{}:{}", user_initial, user)));
+            };
+        counts(
+            "Total: ",
+            HeaderRegion::Total,
+            stats.machine_total,
+            stats.user_total,
+        );
+        counts(
+            " │ Dead: ",
+            HeaderRegion::Dead,
+            stats.machine_dead,
+            stats.user_dead,
+        );
+        counts(
+            " │ Duplicates: ",
+            HeaderRegion::Duplicates,
+            stats.machine_duplicates,
+            stats.user_duplicates,
+        );
+        counts(
+            " │ Non-norm: ",
+            HeaderRegion::NonNormalized,
+            stats.machine_non_normalized,
+            stats.user_non_normalized,
+        );
+        // Only show malformed counts when there is something to clean up
+        if stats.machine_malformed + stats.user_malformed > 0 {
+            counts(
+                " │ Malformed: ",
+                HeaderRegion::Malformed,
+                stats.machine_malformed,
+                stats.user_malformed,
+            );
+        }
+        // Only show offline counts when a mapped drive is disconnected
+        if stats.machine_offline + stats.user_offline > 0 {
+            counts(
+                " │ Offline: ",
+                HeaderRegion::Offline,
+                stats.machine_offline,
+                stats.user_offline,
+            );
+        }
+        parts
+    }
 
-                    self.has_changes = true;
-                    self.reanalyze();
+    /// Clicks on the header statistics: a count filters its panel to those entries and
+    /// focuses it, clicking the same count again or the panel's total shows it all
+    fn handle_header_click(&mut self, x: u16) {
+        let mut start = 0;
+        for (region, text) in self.header_stats() {
+            let width = text.chars().count() as u16;
+            if x >= start && x < start + width {
+                let Some((filter, panel)) = region.filter() else {
+                    return;
+                };
+                let filter = if self.filter_mode == filter && self.filter_panel == Some(panel) {
+                    FilterMode::None
+                } else {
+                    filter
+                };
+                if filter == FilterMode::None && self.filter_mode != FilterMode::None {
+                    self.last_filter = self.filter_mode;
                 }
-            }
-            Panel::User => {
-                if self.user_selected > 0 {
-                    let idx1 = self.user_selected;
-                    let idx2 = self.user_selected - 1;
-
-                    self.user_paths.swap(idx1, idx2);
-                    self.user_selected -= 1;
-
-                    // Clear redo stack and record undo operation
-                    self.clear_redo_stack();
-                    self.undo_stack.push(Operation::SwapPaths {
-                        panel: Panel::User,
-                        index1: idx1,
-                        index2: idx2,
-                    });
-
-                    self.has_changes = true;
-                    self.reanalyze();
+                self.set_filter(filter, Some(panel));
+                self.active_panel = panel;
+                let scope = panel.scope().as_str();
+                if filter == FilterMode::None {
+                    self.set_status(&format!("Filter off, showing all of {}", scope));
+                } else {
+                    self.set_status(&format!("Filter on {}: {}", scope, filter.label()));
                 }
+                return;
             }
+            start += width;
         }
     }
 
-    fn start_add_path(&mut self) {
-        // Open file browser instead of text input
-        self.mode = Mode::FileBrowser;
-        self.file_browser_selected = 0;
-        // Start from current directory or C:\ if current dir unavailable
-        self.file_browser_current_path =
-            std::env::current_dir().unwrap_or_else(|_| PathBuf::from("C:\\"));
-        self.read_current_directory();
+    /// Next sort order for both panels, the selection stays on the same entry
+    fn cycle_sort_mode(&mut self) {
+        self.sort_mode = self.sort_mode.next();
+        self.set_status(&format!("Sorted by {}", self.sort_mode.label()));
     }
 
-    /// Get available drive letters on Windows
-    fn get_available_drives() -> Vec<DirectoryEntry> {
-        let mut drives = Vec::new();
-
-        // Add network drives option
-        drives.push(DirectoryEntry {
-            name: "Network...".to_string(),
-            path: PathBuf::from("NETWORK"),
-            is_parent: false,
-            is_drive: true,
-        });
-
-        // Check drives A-Z
-        for letter in b'A'..=b'Z' {
-            let drive_letter = letter as char;
-            let drive_path = PathBuf::from(format!("{}:\\", drive_letter));
+    /// Switch the filter off, or back to the one switched off last
+    fn toggle_filter(&mut self) {
+        if self.filter_mode != FilterMode::None {
+            self.last_filter = self.filter_mode;
+            self.set_filter(FilterMode::None, self.filter_panel);
+            self.set_status("Filter off, click [filter off] to restore it");
+        } else if self.last_filter != FilterMode::None {
+            self.set_filter(self.last_filter, self.filter_panel);
+            self.set_status(&format!("Filter: {}", self.filter_mode.label()));
+        }
+    }
 
-            // Check if the drive exists
-            if drive_path.exists() {
-                drives.push(DirectoryEntry {
-                    name: format!("{}:", drive_letter),
-                    path: drive_path,
-                    is_parent: false,
-                    is_drive: true,
-                });
-            }
+    /// Commands the hint bar offers right now, with their labels
+    fn hint_actions(&self) -> Vec<(HintAction, &'static str)> {
+        if self.mode != Mode::Normal {
+            return vec![(HintAction::Cancel, "Cancel")];
         }
 
-        drives
+        // Context-sensitive hints based on application state
+        let filter_active = self.filter_mode != FilterMode::None;
+        let marked = !filter_active && self.machine_marked.len() + self.user_marked.len() > 0;
+        let mut hints = if filter_active {
+            // When filter is active - show filter-related operations
+            vec![
+                (HintAction::Help, "Help"),
+                (HintAction::Filter, "Clear"),
+                (HintAction::MarkAll, "MarkAll"),
+                (HintAction::Delete, "Del"),
+            ]
+        } else if marked {
+            // When items are marked - show bulk operations
+            vec![
+                (HintAction::Help, "Help"),
+                (HintAction::Delete, "Delete"),
+                (HintAction::Move, "Move"),
+                (HintAction::Normalize, "Normalize"),
+            ]
+        } else {
+            // Default hints with the more discoverable features
+            vec![
+                (HintAction::Help, "Help"),
+                (HintAction::Mark, "Mark"),
+                (HintAction::Delete, "Del"),
+                (HintAction::Add, "Add"),
+                (HintAction::Filter, "Filter"),
+            ]
+        };
+        if self.can_undo() {
+            hints.push((HintAction::Undo, "Undo"));
+        }
+        if self.can_redo() {
+            hints.push((HintAction::Redo, "Redo"));
+        }
+        if marked {
+            hints.push((HintAction::Unmark, "Unmark"));
+        }
+        hints.push((HintAction::Save, "Save"));
+        if !self.is_admin {
+            hints.push((HintAction::Elevate, "Elevate"));
+        }
+        if !filter_active && !marked && self.remote_connection.is_some() {
+            hints.push((HintAction::Disconnect, "Disconnect"));
+        }
+        if !marked && self.selected_creatable_dead().is_some() {
+            hints.push((HintAction::CreateDir, "MkDir"));
+        }
+        hints.push((HintAction::Quit, "Quit"));
+        hints
     }
 
-    /// Check if a path is a drive root (e.g., C:\, D:\)
-    fn is_drive_root(path: &std::path::Path) -> bool {
-        let path_str = path.to_string_lossy();
-        // Match patterns like "C:\", "D:\", etc.
-        path_str.len() == 3
-            && path_str.chars().nth(1) == Some(':')
-            && path_str.chars().nth(2) == Some('\\')
+    /// The hint bar as rendered: hints spread evenly over the width, with their columns
+    /// The renderer and the click handler both use this, so clicks land where the text is
+    pub fn key_hints(&self, width: u16) -> Vec<KeyHint> {
+        let mut hints: Vec<KeyHint> = self
+            .hint_actions()
+            .into_iter()
+            .map(|(action, label)| KeyHint {
+                action,
+                key: action.key_label(),
+                label,
+                x: 0,
+            })
+            .collect();
+        let content: u16 = hints.iter().map(|hint| hint.width()).sum();
+        let gap = match hints.len() {
+            0 | 1 => 0,
+            n => width.saturating_sub(content) / (n as u16 - 1),
+        };
+        let mut x = 0;
+        for hint in &mut hints {
+            hint.x = x;
+            x += hint.width() + gap;
+        }
+        hints
     }
 
-    /// Read and populate entries for the current directory in file browser
-    fn read_current_directory(&mut self) {
-        self.file_browser_entries.clear();
-
-        // Check if we're at a drive root or in "drives view"
-        let path_str = self.file_browser_current_path.to_string_lossy().to_string();
-
-        if path_str == "DRIVES" {
-            // Show all available drives
-            self.file_browser_entries = Self::get_available_drives();
-        } else {
-            // Add parent directory entry
-            if Self::is_drive_root(&self.file_browser_current_path) {
-                // At drive root, parent goes to drives list
-                self.file_browser_entries.push(DirectoryEntry {
-                    name: "..".to_string(),
-                    path: PathBuf::from("DRIVES"),
-                    is_parent: true,
-                    is_drive: false,
-                });
-            } else if self.file_browser_current_path.parent().is_some() {
-                // Normal parent directory
-                self.file_browser_entries.push(DirectoryEntry {
-                    name: "..".to_string(),
-                    path: self
-                        .file_browser_current_path
-                        .parent()
-                        .unwrap()
-                        .to_path_buf(),
-                    is_parent: true,
-                    is_drive: false,
-                });
+    fn handle_hints_click(&mut self, x: u16, width: u16) -> Result<()> {
+        let Some(hint) = self
+            .key_hints(width)
+            .into_iter()
+            .find(|hint| (hint.x..hint.x + hint.width()).contains(&x))
+        else {
+            return Ok(());
+        };
+        match hint.action {
+            // F10 is handled before the modes see it
+            HintAction::Quit => {
+                self.handle_f10_press();
+                Ok(())
             }
+            action => self.dispatch_input(action.key_event()),
+        }
+    }
 
-            // Read directory entries (only if not in drives view)
-            if let Ok(entries) = std::fs::read_dir(&self.file_browser_current_path) {
-                let mut dirs: Vec<DirectoryEntry> = entries
-                    .filter_map(|entry| entry.ok())
-                    .filter(|entry| {
-                        // Only include directories
-                        entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false)
-                    })
-                    .filter_map(|entry| {
-                        let path = entry.path();
-                        let name = path.file_name()?.to_string_lossy().to_string();
-                        Some(DirectoryEntry {
-                            name,
-                            path,
-                            is_parent: false,
-                            is_drive: false,
-                        })
-                    })
-                    .collect();
-
-                // Sort directories alphabetically (case-insensitive)
-                dirs.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-
-                self.file_browser_entries.extend(dirs);
+    /// What the quick actions row offers for the selected entry
+    /// Delete and Normalize act on the marks once there are any, so they leave the row then
+    fn quick_actions(&self) -> Vec<QuickAction> {
+        let Some((_, info)) = self.selected_path_info() else {
+            return Vec::new();
+        };
+        let mut actions = Vec::new();
+        if !self.profile.is_read_only() {
+            actions.push(QuickAction::Edit);
+            if !self.has_marked_items() {
+                actions.push(QuickAction::Delete);
+                if info.needs_normalization {
+                    actions.push(QuickAction::Normalize);
+                }
             }
         }
-
-        // Ensure selection is valid
-        if self.file_browser_selected >= self.file_browser_entries.len()
-            && !self.file_browser_entries.is_empty()
-        {
-            self.file_browser_selected = self.file_browser_entries.len() - 1;
+        if info.exists && !self.selected_is_remote() {
+            actions.push(QuickAction::Open);
         }
+        actions.push(QuickAction::Info);
+        actions
+    }
 
-        // Update scrollbar state
-        self.file_browser_scrollbar_state = ScrollbarState::new(self.file_browser_entries.len())
-            .position(self.file_browser_selected);
+    /// The quick actions row as rendered, buttons one space apart after a leading space
+    /// The renderer and the click handler both use this, like key_hints
+    pub fn quick_buttons(&self) -> Vec<QuickButton> {
+        let mut x = 1;
+        self.quick_actions()
+            .into_iter()
+            .map(|action| {
+                let button = QuickButton { action, x };
+                x += button.width() + 1;
+                button
+            })
+            .collect()
     }
 
-    /// Navigate to the selected directory in file browser
-    fn navigate_to_selected_directory(&mut self) {
-        if self.file_browser_entries.is_empty() {
-            return;
+    fn handle_quick_actions_click(&mut self, x: u16) {
+        if let Some(button) = self
+            .quick_buttons()
+            .into_iter()
+            .find(|button| (button.x..button.x + button.width()).contains(&x))
+        {
+            self.run_quick_action(button.action);
         }
+    }
 
-        if let Some(entry) = self.file_browser_entries.get(self.file_browser_selected) {
-            // Check if user selected the network option
-            if entry.path.to_string_lossy() == "NETWORK" {
-                // Switch to manual input mode for network path
-                self.mode = Mode::Input(InputMode::AddPath);
-                self.mode_enter_time = std::time::Instant::now();
-                self.input_buffer = "\\\\".to_string(); // Start with UNC prefix
-                return;
+    fn run_quick_action(&mut self, action: QuickAction) {
+        match action {
+            QuickAction::Edit => self.start_edit_path(),
+            // Offered only while nothing is marked, so marking the entry targets it alone
+            QuickAction::Delete => {
+                self.mark_selected();
+                self.confirm_delete_marked();
+            }
+            QuickAction::Normalize => {
+                self.mark_selected();
+                self.normalize_selected();
             }
-
-            self.file_browser_current_path = entry.path.clone();
-            self.file_browser_selected = 0; // Reset selection when entering new directory
-            self.read_current_directory();
+            QuickAction::Open => self.open_selected_folder(),
+            QuickAction::Info => self.show_path_details(),
         }
     }
 
-    /// Select the current directory in file browser and add it to PATH
-    fn select_current_directory_for_path(&mut self) -> Result<()> {
-        // Don't allow adding the "DRIVES" pseudo-directory
-        let path_str = self.file_browser_current_path.to_string_lossy().to_string();
-        if path_str == "DRIVES" {
-            self.set_status("Cannot add drives list to PATH. Navigate to a directory first.");
-            return Ok(());
+    /// Options > Quick Actions Row: show or hide the strip, remembered in config.ini
+    fn toggle_quick_actions(&mut self) {
+        self.settings.quick_actions = !self.settings.quick_actions;
+        let shown = self.settings.quick_actions;
+        match config::save_setting(
+            &self.settings_file,
+            "display",
+            "quick_actions",
+            if shown { "true" } else { "false" },
+        ) {
+            Ok(()) if shown => self.set_status("Quick actions shown under the panels"),
+            Ok(()) => self.set_status("Quick actions hidden"),
+            Err(e) => self.set_status(&format!("Could not save quick actions: {:#}", e)),
         }
-
-        // Set input buffer and add path
-        self.input_buffer = path_str;
-        self.mode = Mode::Normal;
-        self.add_path_from_input()?;
-        self.input_buffer.clear();
-
-        Ok(())
     }
 
-    fn start_edit_path(&mut self) {
-        let current_path = match self.active_panel {
-            Panel::Machine => self.machine_paths.get(self.machine_selected),
-            Panel::User => self.user_paths.get(self.user_selected),
+    fn mark_selected(&mut self) {
+        match self.active_panel {
+            Panel::Machine => self.machine_marked.insert(self.machine_selected),
+            Panel::User => self.user_marked.insert(self.user_selected),
         };
-
-        if let Some(path) = current_path {
-            self.input_buffer = path.clone();
-            self.mode = Mode::Input(InputMode::EditPath);
-            self.mode_enter_time = std::time::Instant::now();
-        }
     }
 
-    fn add_path_from_input(&mut self) -> Result<()> {
-        if self.input_buffer.is_empty() {
+    fn handle_confirm_dialog_click(&mut self, x: u16, y: u16, terminal_size: Rect) -> Result<()> {
+        // Dialog is 60% width, 30% height, centered
+        let dialog_width = (terminal_size.width * 60) / 100;
+        let dialog_height = (terminal_size.height * 30) / 100;
+        let dialog_x = (terminal_size.width - dialog_width) / 2;
+        let dialog_y = (terminal_size.height - dialog_height) / 2;
+
+        // Check if click is within dialog bounds
+        if x < dialog_x || x >= dialog_x + dialog_width {
             return Ok(());
         }
-
-        // Check if directory exists
-        let expanded = normalize_path(&self.input_buffer);
-        if !std::path::Path::new(&expanded).exists() {
-            // Directory doesn't exist - check if we can create it
-            if Self::can_create_directory(&self.input_buffer) {
-                // Store the path and ask for confirmation
-                self.pending_directory = self.input_buffer.clone();
-                self.mode = Mode::Confirm(ConfirmAction::CreateSingleDirectory);
-                return Ok(());
-            } else {
-                // Can't create (network path, invalid chars, etc.) - add anyway as dead path
-                self.set_status("Warning: Path cannot be auto-created (network or invalid). Adding as dead path.");
-            }
+        if y < dialog_y || y >= dialog_y + dialog_height {
+            return Ok(());
         }
 
-        // Directory exists or can't be created - add it
-        let new_path = self.input_buffer.clone();
-        let (panel, index) = match self.active_panel {
-            Panel::Machine => {
-                let idx = self.machine_paths.len();
-                self.machine_paths.push(new_path.clone());
-                (Panel::Machine, idx)
-            }
-            Panel::User => {
-                let idx = self.user_paths.len();
-                self.user_paths.push(new_path.clone());
-                (Panel::User, idx)
-            }
-        };
+        // Calculate relative position within dialog
+        let relative_y = y - dialog_y;
 
-        // Clear redo stack and record undo operation
-        self.clear_redo_stack();
-        self.undo_stack.push(Operation::AddPath {
-            panel,
-            index,
-            path: new_path,
-        });
+        // "Yes / No" is on line 3 of the dialog (after empty line, message, empty line)
+        // Dialog has border, so content starts at y=1
+        // Line 0: border
+        // Line 1: empty
+        // Line 2: message
+        // Line 3: empty
+        // Line 4: "Yes / No"
+        if relative_y == 4 {
+            let relative_x = x - dialog_x;
+            let center_x = dialog_width / 2;
 
-        self.reanalyze();
-        self.has_changes = true;
-        self.set_status("Path added");
-        Ok(())
-    }
+            // "Yes / No" is centered
+            // "Y" is approximately at center - 4
+            // "N" is approximately at center + 3
+            let yes_x = center_x.saturating_sub(4);
+            let no_x = center_x + 3;
 
-    fn update_path_from_input(&mut self) -> Result<()> {
-        if self.input_buffer.is_empty() {
-            return Ok(());
+            if relative_x >= yes_x.saturating_sub(2) && relative_x <= yes_x + 2 {
+                // Clicked on "Yes"
+                if let Mode::Confirm(action) = self.mode {
+                    self.mode = Mode::Normal;
+                    match action {
+                        ConfirmAction::Exit => {
+                            self.should_exit = true;
+                        }
+                        ConfirmAction::DeleteSelected => self.delete_marked()?,
+                        ConfirmAction::DeleteAllDead => self.delete_all_dead()?,
+                        ConfirmAction::DeleteAllDuplicates => self.delete_all_duplicates()?,
+                        ConfirmAction::ApplyChanges => self.apply_changes()?,
+                        ConfirmAction::RequestElevation => {
+                            self.request_elevation()?;
+                        }
+                        ConfirmAction::RestoreBackup => self.restore_selected_backup()?,
+                        ConfirmAction::CreateSingleDirectory => self.create_single_directory()?,
+                        ConfirmAction::CreateMarkedDirectories => {
+                            self.create_marked_directories()?
+                        }
+                        ConfirmAction::CreateSelectedDirectory => {
+                            self.create_selected_directory()?
+                        }
+                        ConfirmAction::UseSubdirectory => self.add_pending_subdirectory(true),
+                        ConfirmAction::RevertLastApply => self.revert_last_apply(true),
+                        ConfirmAction::PromoteEntry => self.promote_entry(),
+                        ConfirmAction::SizeAdvisory => self.accept_size_advisory(),
+                        ConfirmAction::InstallUpdate => self.download_update(),
+                        ConfirmAction::DisconnectRemote => {
+                            self.disconnect_from_remote()?;
+                            self.set_status("Disconnected from remote computer");
+                            self.restore_remote_registry();
+                        }
+                        ConfirmAction::RetryRemote => self.retry_remote_connect(),
+                        ConfirmAction::StartRemoteRegistry => {
+                            self.start_registry_and_connect(false)
+                        }
+                        ConfirmAction::RemoteWriteQueued => self.retry_queued_write(),
+                    }
+                }
+            } else if relative_x >= no_x.saturating_sub(2) && relative_x <= no_x + 2 {
+                // Clicked on "No"
+                if self.mode == Mode::Confirm(ConfirmAction::UseSubdirectory) {
+                    self.mode = Mode::Normal;
+                    self.add_pending_subdirectory(false);
+                }
+                if self.mode == Mode::Confirm(ConfirmAction::RevertLastApply) {
+                    self.mode = Mode::Normal;
+                    self.revert_last_apply(false);
+                }
+                self.mode = Mode::Normal;
+            }
         }
 
-        let new_path = self.input_buffer.clone();
+        Ok(())
+    }
 
+    // Navigation helpers
+    fn move_selection(&mut self, delta: i32) {
+        // A sorted or filtered panel moves through the entries as they are listed
+        if self.sort_mode != SortMode::PathOrder
+            || self.panel_filter(self.active_panel) != FilterMode::None
+        {
+            let order = self.display_indices(self.active_panel);
+            let (_, _, selected) = self.panel_entries(self.active_panel);
+            if let Some(row) = order.iter().position(|&idx| idx == selected) {
+                let row = (row as i32 + delta).max(0).min(order.len() as i32 - 1);
+                self.move_selection_to(order[row as usize]);
+                return;
+            }
+        }
         match self.active_panel {
             Panel::Machine => {
-                if let Some(path) = self.machine_paths.get_mut(self.machine_selected) {
-                    let old_path = path.clone();
-                    *path = new_path.clone();
-
-                    // Clear redo stack and record undo operation
-                    self.clear_redo_stack();
-                    self.undo_stack.push(Operation::EditPath {
-                        panel: Panel::Machine,
-                        index: self.machine_selected,
-                        old_path,
-                        new_path,
-                    });
-                }
+                let new_pos = (self.machine_selected as i32 + delta)
+                    .max(0)
+                    .min(self.machine_paths.len().saturating_sub(1) as i32);
+                self.machine_selected = new_pos as usize;
+                self.machine_scrollbar_state =
+                    self.machine_scrollbar_state.position(self.machine_selected);
             }
             Panel::User => {
-                if let Some(path) = self.user_paths.get_mut(self.user_selected) {
-                    let old_path = path.clone();
-                    *path = new_path.clone();
-
-                    // Clear redo stack and record undo operation
-                    self.clear_redo_stack();
-                    self.undo_stack.push(Operation::EditPath {
-                        panel: Panel::User,
-                        index: self.user_selected,
-                        old_path,
-                        new_path,
-                    });
-                }
+                let new_pos = (self.user_selected as i32 + delta)
+                    .max(0)
+                    .min(self.user_paths.len().saturating_sub(1) as i32);
+                self.user_selected = new_pos as usize;
+                self.user_scrollbar_state = self.user_scrollbar_state.position(self.user_selected);
             }
         }
-
-        self.reanalyze();
-        self.has_changes = true;
-        self.set_status("Path updated");
-        Ok(())
     }
 
-    // Backup/Restore
-    fn create_backup(&mut self) -> Result<()> {
-        let user_path = registry::join_paths(&self.user_original);
-        let machine_path = registry::join_paths(&self.machine_original);
-
-        let backup = PathBackup::new(
-            user_path,
-            machine_path,
-            self.user_original.clone(),
-            self.machine_original.clone(),
-        );
-
-        let backup_dir = backup::get_default_backup_dir();
-        let filepath = backup.save(&backup_dir)?;
-
-        self.set_status(&format!("Backup saved: {}", filepath.display()));
-        Ok(())
+    fn move_selection_to(&mut self, pos: usize) {
+        match self.active_panel {
+            Panel::Machine => {
+                self.machine_selected = pos.min(self.machine_paths.len().saturating_sub(1));
+                self.machine_scrollbar_state =
+                    self.machine_scrollbar_state.position(self.machine_selected);
+            }
+            Panel::User => {
+                self.user_selected = pos.min(self.user_paths.len().saturating_sub(1));
+                self.user_scrollbar_state = self.user_scrollbar_state.position(self.user_selected);
+            }
+        }
     }
 
-    fn show_backup_list(&mut self) -> Result<()> {
-        let backup_dir = backup::get_default_backup_dir();
-        self.backup_list = backup::list_backups(&backup_dir)?;
-        self.backup_selected = 0;
-
-        if self.backup_list.is_empty() {
-            self.set_status("No backups found");
-        } else {
-            self.mode = Mode::BackupList;
+    /// List the position of a panel is remembered for
+    fn position_list(&self, panel: Panel) -> PositionList {
+        match (self.connection_mode, panel) {
+            (_, Panel::Machine) => PositionList::Machine,
+            (ConnectionMode::Local, Panel::User) => PositionList::User,
+            (ConnectionMode::Remote, Panel::User) => PositionList::Remote(
+                self.remote_connection
+                    .as_ref()
+                    .map(|c| c.computer_name().to_lowercase())
+                    .unwrap_or_default(),
+            ),
         }
-        Ok(())
     }
 
-    fn restore_selected_backup(&mut self) -> Result<()> {
-        if self.backup_selected < self.backup_list.len() {
-            let backup_path = &self.backup_list[self.backup_selected];
-            let backup = PathBackup::load(backup_path)?;
+    /// Selected entry and first listed row of a panel
+    pub fn panel_position(&self, panel: Panel) -> PanelPosition {
+        let (_, _, selected) = self.panel_entries(panel);
+        let scroll = match (self.connection_mode, panel) {
+            (_, Panel::Machine) => self.machine_scroll,
+            (ConnectionMode::Local, Panel::User) => self.user_scroll,
+            (ConnectionMode::Remote, Panel::User) => self.remote_scroll,
+        };
+        PanelPosition { selected, scroll }
+    }
 
-            self.user_paths = backup.user_paths;
-            self.machine_paths = backup.machine_paths;
+    /// Restore a remembered position, clamped to the entries the panel has now
+    fn set_panel_position(&mut self, panel: Panel, position: PanelPosition) {
+        let (paths, _, _) = self.panel_entries(panel);
+        let selected = position.selected.min(paths.len().saturating_sub(1));
+        let (target, scroll, scrollbar) = match (self.connection_mode, panel) {
+            (_, Panel::Machine) => (
+                &mut self.machine_selected,
+                &mut self.machine_scroll,
+                &mut self.machine_scrollbar_state,
+            ),
+            (ConnectionMode::Local, Panel::User) => (
+                &mut self.user_selected,
+                &mut self.user_scroll,
+                &mut self.user_scrollbar_state,
+            ),
+            (ConnectionMode::Remote, Panel::User) => (
+                &mut self.remote_machine_selected,
+                &mut self.remote_scroll,
+                &mut self.remote_scrollbar_state,
+            ),
+        };
+        *target = selected;
+        *scroll = position.scroll;
+        *scrollbar = scrollbar.position(selected);
+        self.keep_selection_visible();
+    }
 
-            self.reanalyze();
-            self.has_changes = true;
-            self.set_status("Backup restored (not yet applied)");
+    /// Scroll both panels just enough that their selected entry is listed
+    fn keep_selection_visible(&mut self) {
+        let rows = self.viewport_height.max(1) as usize;
+        for panel in [Panel::Machine, Panel::User] {
+            let order = self.display_indices(panel);
+            let position = self.panel_position(panel);
+            let row = order
+                .iter()
+                .position(|&idx| idx == position.selected)
+                .unwrap_or(0);
+            let scroll = position
+                .scroll
+                .min(row)
+                .max((row + 1).saturating_sub(rows))
+                .min(order.len().saturating_sub(rows));
+            match (self.connection_mode, panel) {
+                (_, Panel::Machine) => self.machine_scroll = scroll,
+                (ConnectionMode::Local, Panel::User) => self.user_scroll = scroll,
+                (ConnectionMode::Remote, Panel::User) => self.remote_scroll = scroll,
+            }
         }
-        Ok(())
     }
 
-    // Apply changes to registry
-    fn apply_changes(&mut self) -> Result<()> {
-        // Save current state as backup first
-        self.create_backup()?;
-
-        match self.connection_mode {
-            ConnectionMode::Local => {
-                // Apply user paths
-                let user_path = registry::join_paths(&self.user_paths);
-                registry::write_path(PathScope::User, &user_path)?;
-
-                // Apply machine paths (if admin)
-                if self.is_admin {
-                    let machine_path = registry::join_paths(&self.machine_paths);
-                    registry::write_path(PathScope::Machine, &machine_path)?;
-                }
+    /// Remember where the remote panel was, so reconnecting to the host returns there
+    fn remember_remote_position(&mut self) {
+        if self.connection_mode != ConnectionMode::Remote {
+            return;
+        }
+        if let PositionList::Remote(host) = self.position_list(Panel::User) {
+            let position = self.panel_position(Panel::User);
+            self.remote_positions.insert(host, position);
+        }
+    }
 
-                // Update originals
-                self.user_original = self.user_paths.clone();
-                self.machine_original = self.machine_paths.clone();
+    /// Switch filters, for one panel or both, each filter remembers where the panels
+    /// were when it was left
+    fn set_filter(&mut self, filter: FilterMode, scope: Option<Panel>) {
+        for panel in [Panel::Machine, Panel::User] {
+            let key = (self.position_list(panel), self.panel_filter(panel));
+            self.filter_positions
+                .insert(key, self.panel_position(panel));
+        }
+        self.filter_mode = filter;
+        self.filter_panel = scope;
+        for panel in [Panel::Machine, Panel::User] {
+            let key = (self.position_list(panel), self.panel_filter(panel));
+            if let Some(position) = self.filter_positions.get(&key).copied() {
+                self.set_panel_position(panel, position);
+            }
+            // A hidden selection would make keys act on an entry nobody sees
+            let order = self.display_indices(panel);
+            let selected = self.panel_position(panel).selected;
+            if !order.is_empty() && !order.contains(&selected) {
+                self.set_panel_position(
+                    panel,
+                    PanelPosition {
+                        selected: order[0],
+                        scroll: 0,
+                    },
+                );
             }
-            ConnectionMode::Remote => {
-                // In remote mode, only write to local MACHINE and remote MACHINE
-                // (USER paths are not shown/editable in remote mode)
+        }
+    }
 
-                // Apply local machine paths (if admin)
-                if self.is_admin {
-                    let machine_path = registry::join_paths(&self.machine_paths);
-                    registry::write_path(PathScope::Machine, &machine_path)?;
-                }
+    /// Select the next or previous problem entry in the listed order, wrapping around
+    /// Unlike a filter this keeps the healthy entries around it in view
+    fn jump_to_problem(&mut self, forward: bool) {
+        let panel = self.active_panel;
+        let order = self.display_indices(panel);
+        let (_, info, selected) = self.panel_entries(panel);
+        let problems: Vec<usize> = (0..order.len())
+            .filter(|&row| info.get(order[row]).is_some_and(needs_cleanup))
+            .collect();
+        if problems.is_empty() {
+            self.set_status("No dead, duplicate or non-normalized entries in this panel");
+            return;
+        }
 
-                // Apply remote machine paths (if connected and admin)
-                if self.is_admin {
-                    if let Some(ref connection) = self.remote_connection {
-                        let remote_path = registry::join_paths(&self.remote_machine_paths);
-                        registry::write_path_remote(PathScope::Machine, &remote_path, connection)?;
-                    }
-                }
+        let current = order.iter().position(|&idx| idx == selected);
+        let found = if forward {
+            problems
+                .iter()
+                .position(|&row| current.is_none_or(|current| row > current))
+        } else {
+            problems
+                .iter()
+                .rposition(|&row| current.is_none_or(|current| row < current))
+        };
+        let wrapped = found.is_none();
+        let n = found.unwrap_or(if forward { 0 } else { problems.len() - 1 });
+        let index = order[problems[n]];
+        let status = info[index].status;
 
-                // Update originals
-                self.machine_original = self.machine_paths.clone();
-                self.remote_machine_original = self.remote_machine_paths.clone();
-            }
-        }
+        let scroll = self.panel_position(panel).scroll;
+        self.set_panel_position(
+            panel,
+            PanelPosition {
+                selected: index,
+                scroll,
+            },
+        );
+        self.set_status(&format!(
+            "Problem {}/{}: {}{}",
+            n + 1,
+            problems.len(),
+            status.description(),
+            if wrapped { " (wrapped around)" } else { "" }
+        ));
+    }
 
-        self.has_changes = false;
+    fn save_bookmark(&mut self, slot: u8) {
+        let key = (self.position_list(self.active_panel), slot);
+        let position = self.panel_position(self.active_panel);
+        self.bookmarks.insert(key, position);
+        self.set_status(&format!(
+            "Bookmark {} set at entry {}, Alt+{} jumps back",
+            slot,
+            position.selected + 1,
+            slot
+        ));
+    }
 
-        // Note: Undo/redo stacks are NOT cleared on save, allowing users to undo changes even after saving
+    fn jump_to_bookmark(&mut self, slot: u8) {
+        let key = (self.position_list(self.active_panel), slot);
+        match self.bookmarks.get(&key).copied() {
+            Some(position) => {
+                self.set_panel_position(self.active_panel, position);
+                self.set_status(&format!("Bookmark {}", slot));
+            }
+            None => self.set_status(&format!(
+                "No bookmark {} in this panel, set one with m{}",
+                slot, slot
+            )),
+        }
+    }
 
-        // Detect running processes that won't pick up the new PATH
-        match crate::process_detector::detect_running_processes() {
-            Ok(processes) => {
-                if !processes.is_empty() {
-                    self.processes_to_restart = processes;
-                    self.mode = Mode::ProcessRestartInfo;
+    fn toggle_mark(&mut self) {
+        match self.active_panel {
+            Panel::Machine => {
+                if self.machine_marked.contains(&self.machine_selected) {
+                    self.machine_marked.remove(&self.machine_selected);
                 } else {
-                    // No non-responsive processes detected
-                    self.set_status(
-                        "Changes applied! All running processes should pick up the new PATH.",
-                    );
+                    self.machine_marked.insert(self.machine_selected);
                 }
+                self.move_selection(1);
             }
-            Err(e) => {
-                // Process detection failed, but changes were still applied successfully
-                self.set_status(&format!(
-                    "Changes applied! (Process detection failed: {})",
-                    e
-                ));
+            Panel::User => {
+                if self.user_marked.contains(&self.user_selected) {
+                    self.user_marked.remove(&self.user_selected);
+                } else {
+                    self.user_marked.insert(self.user_selected);
+                }
+                self.move_selection(1);
             }
         }
-
-        Ok(())
     }
 
-    pub fn confirm_exit(&mut self) {
-        if self.has_changes {
-            self.mode = Mode::Confirm(ConfirmAction::Exit);
-        } else {
-            self.should_exit = true;
+    fn start_visual(&mut self) {
+        let (paths, _, _) = self.panel_entries(self.active_panel);
+        if paths.is_empty() {
+            return;
         }
+        let (marked, selected) = match self.active_panel {
+            Panel::Machine => (&self.machine_marked, self.machine_selected),
+            Panel::User => (&self.user_marked, self.user_selected),
+        };
+        self.visual = Some(VisualSelection {
+            panel: self.active_panel,
+            anchor: selected,
+            marked_before: marked.clone(),
+        });
     }
 
-    /// Handle F10 key press - shows exit confirmation dialog
-    pub fn handle_f10_press(&mut self) {
-        // Always show confirmation dialog (user must press F10 again in dialog to confirm)
-        self.mode = Mode::Confirm(ConfirmAction::Exit);
-    }
-
-    /// Check if a directory can be created (not network path, valid chars, etc.)
-    fn can_create_directory(path: &str) -> bool {
-        if path.is_empty() {
-            return false;
+    /// Mark the rows from the anchor to the selected entry, as the panel lists them
+    fn extend_visual(&mut self) {
+        let Some(visual) = self.visual.as_ref() else {
+            return;
+        };
+        let selected = match visual.panel {
+            Panel::Machine => self.machine_selected,
+            Panel::User => self.user_selected,
+        };
+        let order = self.display_indices(visual.panel);
+        let range = match (
+            order.iter().position(|&idx| idx == visual.anchor),
+            order.iter().position(|&idx| idx == selected),
+        ) {
+            (Some(anchor), Some(row)) => order[anchor.min(row)..=anchor.max(row)].to_vec(),
+            _ => vec![selected],
+        };
+        // The range is added to the marks from before, an action applies to all of them
+        let mut marked = visual.marked_before.clone();
+        marked.extend(&range);
+        let added = marked.len() - visual.marked_before.len();
+        let before = marked.len() - added;
+        let panel = visual.panel;
+        match panel {
+            Panel::Machine => self.machine_marked = marked,
+            Panel::User => self.user_marked = marked,
         }
+        let also = if before > 0 {
+            format!(" added to {} already marked", before)
+        } else {
+            String::new()
+        };
+        self.set_status(&format!(
+            "-- VISUAL -- {} entr{}{}, move to extend, Esc cancels, any action applies to the marks",
+            range.len(),
+            if range.len() == 1 { "y" } else { "ies" },
+            also
+        ));
+    }
 
-        // Check if it's a network path (starts with \\ or //)
-        if path.starts_with("\\\\") || path.starts_with("//") {
-            return false;
+    fn cancel_visual(&mut self) {
+        let Some(visual) = self.visual.take() else {
+            return;
+        };
+        match visual.panel {
+            Panel::Machine => self.machine_marked = visual.marked_before,
+            Panel::User => self.user_marked = visual.marked_before,
         }
+        self.set_status("Visual selection cancelled");
+    }
 
-        // Check for invalid characters (basic check)
-        let invalid_chars = ['<', '>', '|', '\"', '?', '*'];
-        if path.chars().any(|c| invalid_chars.contains(&c)) {
-            return false;
+    /// V again leaves the range marked, to add more with Space or another V
+    fn keep_visual(&mut self) {
+        let Some(visual) = self.visual.take() else {
+            return;
+        };
+        let marked = match visual.panel {
+            Panel::Machine => &self.machine_marked,
+            Panel::User => &self.user_marked,
+        };
+        let added = marked.difference(&visual.marked_before).count();
+        self.set_status(&format!(
+            "Marked {} entr{} from the visual selection",
+            added,
+            if added == 1 { "y" } else { "ies" }
+        ));
+    }
+
+    /// Ask before deleting the marked entries, naming programs installed in them
+    fn confirm_delete_marked(&mut self) {
+        if !self.has_marked_items() {
+            return;
         }
 
-        // Path should have a drive letter on Windows or be a relative path
-        // Simple check: if it has a colon, it should be at position 1 (like C:)
-        if let Some(colon_pos) = path.find(':') {
-            if colon_pos != 1 {
-                return false;
-            }
+        let mut marked: Vec<String> = self
+            .machine_marked
+            .iter()
+            .filter_map(|&idx| self.machine_paths.get(idx).cloned())
+            .collect();
+        // In remote mode the USER panel holds the remote machine, whose programs are unknown here
+        if self.connection_mode == ConnectionMode::Local {
+            marked.extend(
+                self.user_marked
+                    .iter()
+                    .filter_map(|&idx| self.user_paths.get(idx).cloned()),
+            );
         }
 
-        true
+        let apps = self
+            .installed_apps
+            .get_or_insert_with(|| installed_apps::installed_apps().unwrap_or_default());
+        self.delete_owners = marked
+            .into_iter()
+            .filter_map(|entry| {
+                let owner = installed_apps::owners(apps, &entry).first()?.label();
+                Some((entry, owner))
+            })
+            .collect();
+
+        let sorted = |marked: &HashSet<usize>, paths: &[String]| -> Vec<(usize, String)> {
+            let mut entries: Vec<(usize, String)> = marked
+                .iter()
+                .filter_map(|&idx| Some((idx, paths.get(idx)?.clone())))
+                .collect();
+            entries.sort();
+            entries
+        };
+        let plan = BulkDelete {
+            action: ConfirmAction::DeleteSelected,
+            machine: sorted(&self.machine_marked, &self.machine_paths),
+            user: sorted(&self.user_marked, &self.user_paths),
+        };
+        if plan.len() > self.review_threshold() {
+            self.start_review(plan);
+        } else {
+            self.mode = Mode::Confirm(ConfirmAction::DeleteSelected);
+        }
     }
 
-    /// Create a directory and its parent directories
-    /// If remote_computer is provided, creates the directory on the remote computer using UNC path
-    fn create_directory_with_remote(path: &str, remote_computer: Option<&str>) -> Result<()> {
-        use std::fs;
-        use std::path::Path;
-
-        if path.is_empty() {
-            return Err(anyhow::anyhow!("Path is empty"));
-        }
+    pub fn has_marked_items(&self) -> bool {
+        !self.machine_marked.is_empty() || !self.user_marked.is_empty()
+    }
 
-        // Expand environment variables
-        let expanded = normalize_path(path);
+    /// The selected entry, if it is dead and its directory can be created here
+    pub fn selected_creatable_dead(&self) -> Option<&String> {
+        let (path, info) = self.selected_path_info()?;
+        (info.is_dead() && Self::can_create_directory(path)).then_some(path)
+    }
 
-        // Determine the actual path to create
-        let actual_path = if let Some(computer_name) = remote_computer {
-            // Convert to UNC path for remote creation
-            to_unc_path(&expanded, computer_name)
-                .ok_or_else(|| anyhow::anyhow!("Cannot convert to UNC path: {}", expanded))?
+    /// Shift+F10: create the marked dead directories, or the selected one if none are marked
+    fn confirm_create_directories(&mut self) {
+        if self.has_marked_dead_paths() {
+            self.mode = Mode::Confirm(ConfirmAction::CreateMarkedDirectories);
+        } else if self.selected_creatable_dead().is_some() {
+            self.mode = Mode::Confirm(ConfirmAction::CreateSelectedDirectory);
         } else {
-            expanded
-        };
+            self.set_status("No marked dead paths to create");
+        }
+    }
 
-        let path_obj = Path::new(&actual_path);
+    pub fn has_marked_dead_paths(&self) -> bool {
+        // Check if any marked items in the active panel are dead paths
+        match self.active_panel {
+            Panel::Machine => self
+                .machine_marked
+                .iter()
+                .any(|&idx| idx < self.machine_info.len() && self.machine_info[idx].is_dead()),
+            Panel::User => self
+                .user_marked
+                .iter()
+                .any(|&idx| idx < self.user_info.len() && self.user_info[idx].is_dead()),
+        }
+    }
 
-        // Create directory with parents
-        fs::create_dir_all(path_obj).map_err(|e| {
-            if remote_computer.is_some() {
-                anyhow::anyhow!(
-                    "Failed to create remote directory '{}': {}. \
-                    Ensure C$ administrative shares are enabled and accessible.",
-                    actual_path,
-                    e
-                )
-            } else {
-                anyhow::anyhow!("Failed to create directory '{}': {}", actual_path, e)
+    /// Entries, analysis and selection shown in a panel
+    /// In Remote mode, the User panel shows the remote machine's paths
+    pub fn panel_entries(&self, panel: Panel) -> (&Vec<String>, &Vec<PathInfo>, usize) {
+        match (self.connection_mode, panel) {
+            (_, Panel::Machine) => (
+                &self.machine_paths,
+                &self.machine_info,
+                self.machine_selected,
+            ),
+            (ConnectionMode::Local, Panel::User) => {
+                (&self.user_paths, &self.user_info, self.user_selected)
             }
-        })?;
+            (ConnectionMode::Remote, Panel::User) => (
+                &self.remote_machine_paths,
+                &self.remote_machine_info,
+                self.remote_machine_selected,
+            ),
+        }
+    }
 
-        Ok(())
+    /// Get the selected entry and its analysis for the active panel
+    pub fn selected_path_info(&self) -> Option<(&String, &PathInfo)> {
+        let (paths, info, selected) = self.panel_entries(self.active_panel);
+        Some((paths.get(selected)?, info.get(selected)?))
     }
 
-    /// Create the pending directory and add the path
-    fn create_single_directory(&mut self) -> Result<()> {
-        if self.pending_directory.is_empty() {
-            return Ok(());
+    /// Entries of an inactive panel equivalent to the selected entry of the active one
+    /// Used to highlight cross-scope duplicates while browsing
+    pub fn counterpart_indices(&self, panel: Panel) -> HashSet<usize> {
+        if panel == self.active_panel {
+            return HashSet::new();
+        }
+        let Some((_, selected)) = self.selected_path_info() else {
+            return HashSet::new();
+        };
+        if selected.normalized.is_empty() {
+            return HashSet::new();
         }
 
-        // Determine if we're creating on remote computer
-        // In remote mode, User panel (right) is actually the remote machine
-        let remote_computer =
-            if self.connection_mode == ConnectionMode::Remote && self.active_panel == Panel::User {
-                self.remote_connection.as_ref().map(|c| c.computer_name())
-            } else {
-                None
-            };
-
-        match Self::create_directory_with_remote(&self.pending_directory, remote_computer) {
-            Ok(()) => {
-                // Directory created successfully - now add the path
-                match self.active_panel {
-                    Panel::Machine => {
-                        self.machine_paths.push(self.pending_directory.clone());
-                    }
-                    Panel::User => {
-                        if self.connection_mode == ConnectionMode::Remote {
-                            self.remote_machine_paths
-                                .push(self.pending_directory.clone());
-                        } else {
-                            self.user_paths.push(self.pending_directory.clone());
-                        }
-                    }
-                }
+        // Same comparison the analyzer uses to flag duplicates
+        let key = selected.normalized.to_lowercase();
+        let (_, info, _) = self.panel_entries(panel);
+        info.iter()
+            .enumerate()
+            .filter(|(_, other)| other.normalized.to_lowercase() == key)
+            .map(|(idx, _)| idx)
+            .collect()
+    }
 
-                self.reanalyze();
-                self.has_changes = true;
-                self.set_status(&format!(
-                    "Created directory and added: {}",
-                    self.pending_directory
-                ));
-                self.pending_directory.clear();
-                Ok(())
-            }
-            Err(e) => {
-                self.set_status(&format!("Failed to create directory: {}", e));
-                self.pending_directory.clear();
-                Err(e)
+    /// Open the details dialog for the selected entry
+    fn show_path_details(&mut self) {
+        if self.selected_path_info().is_some() {
+            self.availability_report = None;
+            self.availability_task = None;
+            self.mode = Mode::PathDetails;
+            if self.settings.directory_sizes {
+                self.measure_selected_directory(false);
             }
+        } else {
+            self.set_status("No path selected");
         }
     }
 
-    /// Create all marked dead directories
-    fn create_marked_directories(&mut self) -> Result<()> {
-        let mut created_count = 0;
-        let mut skipped_count = 0;
-        let mut failed_paths = Vec::new();
-
-        // Determine if we're creating on remote computer
-        let remote_computer =
-            if self.connection_mode == ConnectionMode::Remote && self.active_panel == Panel::User {
-                self.remote_connection.as_ref().map(|c| c.computer_name())
-            } else {
-                None
-            };
+    /// Whether the selected entry lives on the remote computer rather than this one
+    fn selected_is_remote(&self) -> bool {
+        self.connection_mode == ConnectionMode::Remote && self.active_panel == Panel::User
+    }
 
-        // Collect all marked dead paths
-        let marked_paths: Vec<(usize, String)> = match self.active_panel {
-            Panel::Machine => self
-                .machine_marked
-                .iter()
-                .filter_map(|&idx| {
-                    if idx < self.machine_paths.len() && idx < self.machine_info.len() {
-                        if !self.machine_info[idx].exists {
-                            Some((idx, self.machine_paths[idx].clone()))
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                })
-                .collect(),
-            Panel::User => {
-                if self.connection_mode == ConnectionMode::Remote {
-                    // In remote mode, User panel shows remote machine paths
-                    self.remote_machine_marked
-                        .iter()
-                        .filter_map(|&idx| {
-                            if idx < self.remote_machine_paths.len()
-                                && idx < self.remote_machine_info.len()
-                            {
-                                if !self.remote_machine_info[idx].exists {
-                                    Some((idx, self.remote_machine_paths[idx].clone()))
-                                } else {
-                                    None
-                                }
-                            } else {
-                                None
-                            }
-                        })
-                        .collect()
-                } else {
-                    self.user_marked
-                        .iter()
-                        .filter_map(|&idx| {
-                            if idx < self.user_paths.len() && idx < self.user_info.len() {
-                                if !self.user_info[idx].exists {
-                                    Some((idx, self.user_paths[idx].clone()))
-                                } else {
-                                    None
-                                }
-                            } else {
-                                None
-                            }
-                        })
-                        .collect()
-                }
-            }
+    /// o: show the selected directory in Explorer (or the desktop's file manager)
+    fn open_selected_folder(&mut self) {
+        let Some((path, info)) = self.selected_path_info() else {
+            self.set_status("No path selected");
+            return;
+        };
+        if self.selected_is_remote() {
+            self.set_status("Directories on the remote computer can't be opened from here");
+            return;
+        }
+        if !info.exists {
+            let message = format!("{} does not exist", path);
+            self.set_status(&message);
+            return;
+        }
+        let folder = if info.normalized.is_empty() {
+            path.clone()
+        } else {
+            info.normalized.clone()
         };
+        match open_folder(&folder) {
+            Ok(()) => self.set_status(&format!("Opened {}", folder)),
+            Err(e) => self.set_status(&format!("Could not open {}: {}", folder, e)),
+        }
+    }
 
-        // Try to create each directory
-        for (_idx, path) in marked_paths {
-            if !Self::can_create_directory(&path) {
-                skipped_count += 1;
-                continue;
+    /// Check the selected entry against the filesystem again, without reanalyzing
+    /// everything, e.g. after creating its directory or reconnecting its share
+    fn recheck_selected(&mut self) {
+        let remote = match (self.connection_mode, self.active_panel) {
+            (ConnectionMode::Remote, Panel::User) => self
+                .remote_connection
+                .as_ref()
+                .map(|conn| conn.computer_name().to_string()),
+            _ => None,
+        };
+        let context = expansion_context(&self.settings, remote.is_some());
+        let (paths, infos, idx) = match (self.connection_mode, self.active_panel) {
+            (_, Panel::Machine) => (
+                &self.machine_paths,
+                &mut self.machine_info,
+                self.machine_selected,
+            ),
+            (ConnectionMode::Local, Panel::User) => {
+                (&self.user_paths, &mut self.user_info, self.user_selected)
             }
+            (ConnectionMode::Remote, Panel::User) => (
+                &self.remote_machine_paths,
+                &mut self.remote_machine_info,
+                self.remote_machine_selected,
+            ),
+        };
+        let (Some(path), Some(before)) = (paths.get(idx), infos.get(idx).map(|i| i.status)) else {
+            self.set_status("No path selected");
+            return;
+        };
+        let path = path.clone();
 
-            match Self::create_directory_with_remote(&path, remote_computer) {
-                Ok(()) => created_count += 1,
-                Err(_) => {
-                    failed_paths.push(path);
-                }
-            }
+        recheck_entry(infos, idx, remote.as_deref(), &*self.fs, &context);
+        let after = infos[idx].status;
+        if after == before {
+            self.set_status(&format!("Rechecked {}: {}", path, after.description()));
+        } else {
+            self.set_status(&format!(
+                "Rechecked {}: {} (was {})",
+                path,
+                after.description(),
+                before.description()
+            ));
         }
+    }
 
-        // Reanalyze to update dead path status
-        if created_count > 0 {
-            self.reanalyze();
-            self.has_changes = true;
+    /// Check how many files in the selected OneDrive folder are online-only placeholders
+    fn check_path_availability(&mut self) {
+        let Some((_, info)) = self.selected_path_info() else {
+            return;
+        };
+        if !info.is_onedrive || !self.fs.is_checked() {
+            return;
         }
 
-        // Show status message
-        let mut msg = format!("Created {} directories", created_count);
-        if skipped_count > 0 {
-            msg.push_str(&format!(", skipped {} (network/invalid)", skipped_count));
-        }
-        if !failed_paths.is_empty() {
-            msg.push_str(&format!(", failed {} paths", failed_paths.len()));
-        }
-        self.set_status(&msg);
+        // Large synced folders take a while to list, so count on a worker thread
+        let expanded = crate::path_analyzer::expand_environment_variables(&info.normalized);
+        self.availability_report = None;
+        self.availability_task = Some(BackgroundTask::spawn(
+            "Checking file availability",
+            move || match crate::onedrive::check_availability(Path::new(&expanded)) {
+                Ok(availability) if availability.online_only == 0 => {
+                    format!("All {} file(s) are available offline", availability.files)
+                }
+                Ok(availability) => format!(
+                    "{} of {} file(s) are online-only and will download on first use",
+                    availability.online_only, availability.files
+                ),
+                Err(e) => format!("Could not check availability: {}", e),
+            },
+        ));
+    }
 
-        Ok(())
+    /// Where the selected entry's directory is read from, its UNC path on a remote computer
+    fn selected_directory(&self) -> Option<String> {
+        let (_, info) = self.selected_path_info()?;
+        let expanded = crate::path_analyzer::expand_environment_variables(&info.normalized);
+        let remote = self
+            .remote_connection
+            .as_ref()
+            .filter(|_| self.selected_is_remote())
+            .map(|conn| conn.computer_name());
+        Some(crate::path_analyzer::lookup_path(&expanded, remote))
     }
 
-    fn reanalyze(&mut self) {
-        match self.connection_mode {
-            ConnectionMode::Local => {
-                self.user_info = analyze_paths(&self.user_paths, &self.machine_paths);
-                self.machine_info = analyze_paths(&self.machine_paths, &self.user_paths);
+    /// Size of the selected directory if it was measured this session
+    pub fn selected_directory_size(&self) -> Option<&Result<DirectorySize, String>> {
+        self.directory_sizes
+            .get(&self.selected_directory()?.to_lowercase())
+    }
 
-                // Update scrollbar content lengths
-                self.machine_scrollbar_state = self
-                    .machine_scrollbar_state
-                    .content_length(self.machine_paths.len());
-                self.user_scrollbar_state = self
-                    .user_scrollbar_state
-                    .content_length(self.user_paths.len());
+    /// Add up the files at the top level of the selected directory on a worker thread
+    /// A size measured before is kept unless `refresh` asks for it again
+    fn measure_selected_directory(&mut self, refresh: bool) {
+        let Some((_, info)) = self.selected_path_info() else {
+            return;
+        };
+        if !info.exists || info.is_app_aliases || !self.fs.is_checked() {
+            return;
+        }
+        let Some(dir) = self.selected_directory() else {
+            return;
+        };
+        let key = dir.to_lowercase();
+        if self.size_task.is_some() || (!refresh && self.directory_sizes.contains_key(&key)) {
+            return;
+        }
 
-                // Adjust selection if out of bounds
-                if self.machine_selected >= self.machine_paths.len()
-                    && !self.machine_paths.is_empty()
-                {
-                    self.machine_selected = self.machine_paths.len() - 1;
-                    self.machine_scrollbar_state =
-                        self.machine_scrollbar_state.position(self.machine_selected);
-                }
-                if self.user_selected >= self.user_paths.len() && !self.user_paths.is_empty() {
-                    self.user_selected = self.user_paths.len() - 1;
-                    self.user_scrollbar_state =
-                        self.user_scrollbar_state.position(self.user_selected);
-                }
-            }
-            ConnectionMode::Remote => {
-                // In remote mode: analyze local machine vs remote machine paths
-                // Local paths are analyzed normally (no remote computer name)
-                self.machine_info = analyze_paths(&self.machine_paths, &self.remote_machine_paths);
+        // A network share or a folder of thousands of files takes a while to list
+        self.size_task = Some(BackgroundTask::spawn(
+            "Measuring directory size",
+            move || {
+                let size = disk_usage::measure(Path::new(&dir)).map_err(|e| e.to_string());
+                (key, size)
+            },
+        ));
+    }
 
-                // Remote paths need UNC path validation - pass the remote computer name
-                let remote_computer_name = self
-                    .remote_connection
-                    .as_ref()
-                    .map(|conn| conn.computer_name());
-                self.remote_machine_info = analyze_paths_with_remote(
-                    &self.remote_machine_paths,
-                    &self.machine_paths,
-                    remote_computer_name,
-                );
+    /// Check PATH directories for write access by all users and unsigned executables
+    fn start_security_scan(&mut self) {
+        if !self.profile_allows(Feature::Doctor) {
+            return;
+        }
+        if self.refuse_without_fs_checks("Security scan") {
+            return;
+        }
+        if self.connection_mode == ConnectionMode::Remote {
+            self.set_status("Security scan only checks this computer's PATH");
+            return;
+        }
+        if self.security_task.is_some() {
+            self.set_status("Security scan already running");
+            return;
+        }
 
-                // Update scrollbar content lengths
-                self.machine_scrollbar_state = self
-                    .machine_scrollbar_state
-                    .content_length(self.machine_paths.len());
-                self.remote_scrollbar_state = self
-                    .remote_scrollbar_state
-                    .content_length(self.remote_machine_paths.len());
+        // Scan the PATH as currently edited, including unapplied changes
+        let entries: Vec<(PathScope, usize, String)> = self
+            .machine_paths
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (PathScope::Machine, i, p.clone()))
+            .chain(
+                self.user_paths
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| (PathScope::User, i, p.clone())),
+            )
+            .collect();
+        self.security_task = Some(BackgroundTask::spawn("Scanning PATH security", move || {
+            security_scan::scan(&entries, &security_scan::SystemProbe)
+        }));
+    }
 
-                // Adjust selection if out of bounds
-                if self.machine_selected >= self.machine_paths.len()
-                    && !self.machine_paths.is_empty()
-                {
-                    self.machine_selected = self.machine_paths.len() - 1;
-                    self.machine_scrollbar_state =
-                        self.machine_scrollbar_state.position(self.machine_selected);
-                }
-                if self.remote_machine_selected >= self.remote_machine_paths.len()
-                    && !self.remote_machine_paths.is_empty()
-                {
-                    self.remote_machine_selected = self.remote_machine_paths.len() - 1;
-                    self.remote_scrollbar_state = self
-                        .remote_scrollbar_state
-                        .position(self.remote_machine_selected);
+    /// Show a finished scan, or note it in the status bar if another dialog is open
+    fn finish_security_scan(&mut self, report: ScanReport) {
+        if self.mode == Mode::Normal {
+            self.security_scroll = 0;
+            self.mode = Mode::SecurityReport;
+        } else {
+            self.set_status(&format!(
+                "Security scan finished: {} risky director{}",
+                report.findings.len(),
+                if report.findings.len() == 1 {
+                    "y"
+                } else {
+                    "ies"
                 }
-            }
+            ));
         }
+        self.security_report = Some(report);
     }
 
-    /// Request UAC elevation and restart the application with administrator privileges
-    fn request_elevation(&mut self) -> Result<()> {
-        // Build elevation state from current app state
-        let elevation_state = crate::elevation::ElevationState {
-            connection_mode: self.connection_mode,
-            remote_computer_name: self
-                .remote_connection
-                .as_ref()
-                .map(|c| c.computer_name().to_string()),
-            machine_paths: self.machine_paths.clone(),
-            user_paths: self.user_paths.clone(),
-            remote_machine_paths: self.remote_machine_paths.clone(),
-            active_panel: self.active_panel,
-            machine_selected: self.machine_selected,
-            user_selected: self.user_selected,
-            remote_machine_selected: self.remote_machine_selected,
-            machine_marked: self.machine_marked.clone(),
-            user_marked: self.user_marked.clone(),
-            remote_machine_marked: self.remote_machine_marked.clone(),
-            filter_mode: self.filter_mode,
-            input_buffer: self.input_buffer.clone(),
-            pending_directory: self.pending_directory.clone(),
-            theme_arg: self.theme_arg.clone(),
-        };
+    /// Check where package and version manager shim directories are in PATH and what shadows them
+    fn start_shim_check(&mut self) {
+        if !self.profile_allows(Feature::Doctor) {
+            return;
+        }
+        if self.refuse_without_fs_checks("Shim check") {
+            return;
+        }
+        if self.connection_mode == ConnectionMode::Remote {
+            self.set_status("Shim check only looks at this computer's PATH");
+            return;
+        }
+        if self.shim_task.is_some() {
+            self.set_status("Shim check already running");
+            return;
+        }
 
-        // Get current executable path
-        let current_exe = std::env::current_exe()
-            .context("Failed to get current executable path")?
-            .to_string_lossy()
-            .to_string();
+        // Check the PATH as currently edited, in search order
+        let entries: Vec<(PathScope, usize, String)> = self
+            .machine_paths
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (PathScope::Machine, i, p.clone()))
+            .chain(
+                self.user_paths
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| (PathScope::User, i, p.clone())),
+            )
+            .collect();
+        self.shim_task = Some(BackgroundTask::spawn(
+            "Checking shim directories",
+            move || shims::scan(&entries),
+        ));
+    }
 
-        // Request elevation (this will trigger UAC and restart the app)
-        match crate::elevation::request_elevation(&elevation_state, &current_exe) {
-            Ok(()) => {
-                // Elevation successful - the new elevated process is starting
-                // Exit this instance
-                self.should_exit = true;
-                self.set_status("Restarting with elevated privileges...");
-                Ok(())
-            }
-            Err(e) => {
-                // Elevation failed or was cancelled
-                self.set_status(&format!(
-                    "Elevation failed: {}. Continuing without elevation.",
-                    e
-                ));
-                Ok(())
-            }
+    /// Show a finished shim check, or note it in the status bar if another dialog is open
+    fn finish_shim_check(&mut self, findings: Vec<ShimFinding>) {
+        if findings.is_empty() {
+            self.set_status("No shim or version manager directories found");
+        } else if self.mode == Mode::Normal {
+            self.shim_scroll = 0;
+            self.mode = Mode::ShimReport;
+        } else {
+            let conflicting = findings
+                .iter()
+                .filter(|finding| !finding.problems().is_empty())
+                .count();
+            self.set_status(&format!(
+                "Shim check finished: {} director{} with conflicts",
+                conflicting,
+                if conflicting == 1 { "y" } else { "ies" }
+            ));
         }
+        self.shim_findings = findings;
     }
 
-    fn set_status(&mut self, message: &str) {
-        self.status_message = message.to_string();
+    /// Find well-known tools in PATH and the version of each copy
+    fn start_toolchain_scan(&mut self) {
+        if !self.profile_allows(Feature::Doctor) {
+            return;
+        }
+        if self.refuse_without_fs_checks("Toolchain scan") {
+            return;
+        }
+        if self.connection_mode == ConnectionMode::Remote {
+            self.set_status("Toolchain scan only looks at this computer's PATH");
+            return;
+        }
+        if self.toolchain_task.is_some() {
+            self.set_status("Toolchain scan already running");
+            return;
+        }
+
+        let entries: Vec<(PathScope, usize, String)> = self
+            .machine_paths
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (PathScope::Machine, i, p.clone()))
+            .chain(
+                self.user_paths
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| (PathScope::User, i, p.clone())),
+            )
+            .collect();
+        self.toolchain_task = Some(BackgroundTask::spawn(
+            "Finding toolchains on PATH",
+            move || toolchains::scan(&entries),
+        ));
     }
 
-    // Bulk selection functions
-    fn mark_all_visible(&mut self) {
-        let count = match self.active_panel {
-            Panel::Machine => {
-                let filtered = self.get_filtered_indices(&self.machine_info);
-                for idx in filtered {
-                    self.machine_marked.insert(idx);
+    /// Show a finished toolchain scan, or note it in the status bar if another dialog is open
+    fn finish_toolchain_scan(&mut self, toolchains: Vec<Toolchain>) {
+        if toolchains.is_empty() {
+            self.set_status("No well-known toolchains found on PATH");
+        } else if self.mode == Mode::Normal {
+            self.toolchain_scroll = 0;
+            self.mode = Mode::Toolchains;
+        } else {
+            let repeated = toolchains
+                .iter()
+                .filter(|toolchain| toolchain.installs.len() > 1)
+                .count();
+            self.set_status(&format!(
+                "Toolchain scan finished: {} tool{} found more than once",
+                repeated,
+                if repeated == 1 { "" } else { "s" }
+            ));
+        }
+        self.toolchains = toolchains;
+    }
+
+    /// Mark the directories of copies that never run because an earlier copy wins,
+    /// leaving out directories whose copy of some other tool is the one that runs
+    fn mark_shadowed_toolchains(&mut self) {
+        let winners: HashSet<(PathScope, usize)> = self
+            .toolchains
+            .iter()
+            .filter_map(|toolchain| toolchain.installs.first())
+            .map(|install| (install.scope, install.index))
+            .collect();
+        let shadowed: HashSet<(PathScope, usize)> = self
+            .toolchains
+            .iter()
+            .flat_map(|toolchain| toolchain.installs.iter().skip(1))
+            .map(|install| (install.scope, install.index))
+            .filter(|entry| !winners.contains(entry))
+            .collect();
+        if shadowed.is_empty() {
+            self.set_status("No directory only holds copies that never run");
+            return;
+        }
+
+        self.machine_marked.clear();
+        self.user_marked.clear();
+        for (scope, index) in &shadowed {
+            match scope {
+                PathScope::Machine if *index < self.machine_paths.len() => {
+                    self.machine_marked.insert(*index);
                 }
-                self.machine_marked.len()
-            }
-            Panel::User => {
-                let filtered = self.get_filtered_indices(&self.user_info);
-                for idx in filtered {
-                    self.user_marked.insert(idx);
+                PathScope::User if *index < self.user_paths.len() => {
+                    self.user_marked.insert(*index);
                 }
-                self.user_marked.len()
+                _ => {}
             }
-        };
+        }
+        self.mode = Mode::Normal;
         self.set_status(&format!(
-            "Marked {} visible paths in {} scope",
-            count,
-            self.active_panel.scope().as_str()
+            "Marked {} director{} with copies that never run, review them and press F3 to delete",
+            shadowed.len(),
+            if shadowed.len() == 1 { "y" } else { "ies" }
         ));
     }
 
-    fn mark_all_both_scopes(&mut self) {
-        let machine_filtered = self.get_filtered_indices(&self.machine_info);
-        for idx in machine_filtered {
-            self.machine_marked.insert(idx);
+    /// Entries of both panels whose directory has a last-modified time, oldest first
+    pub fn aging_rows(&self) -> Vec<AgingRow> {
+        let mut rows: Vec<AgingRow> = [Panel::Machine, Panel::User]
+            .into_iter()
+            .flat_map(|panel| {
+                let (_, info, _) = self.panel_entries(panel);
+                info.iter().enumerate().filter_map(move |(index, info)| {
+                    info.modified.map(|modified| AgingRow {
+                        panel,
+                        index,
+                        modified,
+                    })
+                })
+            })
+            .collect();
+        rows.sort_by_key(|row| row.modified);
+        rows
+    }
+
+    /// Open the report of entries by how long ago their directory last changed
+    fn start_aging_report(&mut self) {
+        if !self.profile_allows(Feature::Doctor) {
+            return;
         }
-        let user_filtered = self.get_filtered_indices(&self.user_info);
-        for idx in user_filtered {
-            self.user_marked.insert(idx);
+        if self.refuse_without_fs_checks("Aging report") {
+            return;
         }
-        let total = self.machine_marked.len() + self.user_marked.len();
-        self.set_status(&format!("Marked {} paths in both scopes", total));
+        self.aging_rows = self.aging_rows();
+        if self.aging_rows.is_empty() {
+            self.set_status("No entry has a directory to date");
+            return;
+        }
+        self.aging_selected = 0;
+        self.mode = Mode::AgingReport;
     }
 
-    fn mark_all_duplicates(&mut self) {
-        let count = match self.active_panel {
-            Panel::Machine => {
-                for (idx, info) in self.machine_info.iter().enumerate() {
-                    if info.is_duplicate {
-                        self.machine_marked.insert(idx);
-                    }
-                }
-                self.machine_marked.len()
-            }
-            Panel::User => {
-                for (idx, info) in self.user_info.iter().enumerate() {
-                    if info.is_duplicate {
-                        self.user_marked.insert(idx);
-                    }
-                }
-                self.user_marked.len()
-            }
-        };
-        self.set_status(&format!("Marked {} duplicate paths", count));
+    /// Close the report, marks made in it stay
+    fn close_aging_report(&mut self) {
+        self.aging_rows.clear();
+        self.mode = Mode::Normal;
     }
 
-    fn mark_all_dead(&mut self) {
-        let count = match self.active_panel {
-            Panel::Machine => {
-                for (idx, info) in self.machine_info.iter().enumerate() {
-                    if !info.exists {
-                        self.machine_marked.insert(idx);
-                    }
-                }
-                self.machine_marked.len()
-            }
-            Panel::User => {
-                for (idx, info) in self.user_info.iter().enumerate() {
-                    if !info.exists {
-                        self.user_marked.insert(idx);
-                    }
-                }
-                self.user_marked.len()
-            }
+    /// Close the report with the selected row's entry selected in its panel
+    fn go_to_aging_row(&mut self) {
+        let Some(row) = self.aging_rows.get(self.aging_selected).copied() else {
+            return;
         };
-        self.set_status(&format!("Marked {} dead paths", count));
+        self.close_aging_report();
+        self.go_to_entry(row.panel, row.index);
     }
 
-    fn mark_all_non_normalized(&mut self) {
-        let count = match self.active_panel {
-            Panel::Machine => {
-                for (idx, info) in self.machine_info.iter().enumerate() {
-                    if info.needs_normalization {
-                        self.machine_marked.insert(idx);
-                    }
-                }
-                self.machine_marked.len()
-            }
-            Panel::User => {
-                for (idx, info) in self.user_info.iter().enumerate() {
-                    if info.needs_normalization {
-                        self.user_marked.insert(idx);
-                    }
-                }
-                self.user_marked.len()
-            }
-        };
-        self.set_status(&format!("Marked {} non-normalized paths", count));
-    }
+    /// Focus a panel and select one of its entries, clearing a filter that hides it
+    fn go_to_entry(&mut self, panel: Panel, index: usize) {
+        self.active_panel = panel;
+        // Filtered out entries can't be selected
+        if !self.display_indices(panel).contains(&index) {
+            self.filter_mode = FilterMode::None;
+        }
+        let scroll = self.panel_position(panel).scroll;
+        self.set_panel_position(
+            panel,
+            PanelPosition {
+                selected: index,
+                scroll,
+            },
+        );
+    }
 
-    fn unmark_all(&mut self) {
-        let total = self.machine_marked.len() + self.user_marked.len();
-        self.machine_marked.clear();
-        self.user_marked.clear();
-        self.set_status(&format!("Unmarked {} paths", total));
+    /// Mark every entry whose directory has not changed in over a year and close the report
+    fn mark_stale_entries(&mut self) {
+        let now = SystemTime::now();
+        let stale: Vec<AgingRow> = self
+            .aging_rows
+            .iter()
+            .filter(|row| aging::is_stale(row.modified, now))
+            .copied()
+            .collect();
+        if stale.is_empty() {
+            self.set_status("No directory has gone untouched for over a year");
+            return;
+        }
+
+        for row in &stale {
+            self.marked_mut(row.panel).insert(row.index);
+        }
+        self.aging_rows.clear();
+        self.mode = Mode::Normal;
+        self.set_status(&format!(
+            "Marked {} entr{} untouched for over a year, review them and press F3 to delete",
+            stale.len(),
+            if stale.len() == 1 { "y" } else { "ies" }
+        ));
     }
 
-    /// Get filtered indices based on current filter mode
-    pub fn get_filtered_indices(&self, info: &[PathInfo]) -> Vec<usize> {
-        match self.filter_mode {
-            FilterMode::None => (0..info.len()).collect(),
-            FilterMode::Dead => info
-                .iter()
-                .enumerate()
-                .filter(|(_, i)| !i.exists)
-                .map(|(idx, _)| idx)
-                .collect(),
-            FilterMode::Duplicates => info
-                .iter()
-                .enumerate()
-                .filter(|(_, i)| i.is_duplicate)
-                .map(|(idx, _)| idx)
-                .collect(),
-            FilterMode::NonNormalized => info
-                .iter()
-                .enumerate()
-                .filter(|(_, i)| i.needs_normalization)
-                .map(|(idx, _)| idx)
-                .collect(),
-            FilterMode::Valid => info
+    /// Moves for both local panels from the usage log, promotions first
+    pub fn usage_rows(&self, log: &usage::UsageLog) -> Vec<UsageRow> {
+        let mut rows: Vec<UsageRow> = [Panel::Machine, Panel::User]
+            .into_iter()
+            .flat_map(|panel| {
+                let (_, info, _) = self.panel_entries(panel);
+                let dirs: Vec<String> = info
+                    .iter()
+                    .map(|info| {
+                        crate::path_analyzer::expand_environment_variables(&info.normalized)
+                    })
+                    .collect();
+                let pinned: Vec<bool> = info
+                    .iter()
+                    .map(|info| crate::path_analyzer::is_system_entry(&info.normalized))
+                    .collect();
+                usage::suggest(&dirs, &pinned, log)
+                    .into_iter()
+                    .map(move |suggestion| UsageRow {
+                        panel,
+                        suggestion,
+                        include: true,
+                    })
+            })
+            .collect();
+        rows.sort_by_key(|row| row.suggestion.action == usage::Move::Demote);
+        rows
+    }
+
+    /// Open the reorder suggestions made from the log the PowerShell usage hook writes
+    fn start_usage_suggestions(&mut self) {
+        if !self.profile_allows(Feature::Doctor) {
+            return;
+        }
+        if self.connection_mode == ConnectionMode::Remote {
+            self.set_status("Usage suggestions only cover this computer's PATH");
+            return;
+        }
+        let log = match usage::UsageLog::load(&self.usage_file) {
+            Ok(log) => log,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.set_status("No usage recorded yet, add `pc usage-hook | Out-String | Invoke-Expression` to your PowerShell $PROFILE");
+                return;
+            }
+            Err(e) => {
+                self.set_status(&format!("Could not read the usage log: {}", e));
+                return;
+            }
+        };
+        if log.days() < usage::MIN_LOG_DAYS {
+            self.set_status(&format!(
+                "The usage log covers {} day{}, suggestions start after {}",
+                log.days(),
+                if log.days() == 1 { "" } else { "s" },
+                usage::MIN_LOG_DAYS
+            ));
+            return;
+        }
+        self.usage_rows = self.usage_rows(&log);
+        if self.usage_rows.is_empty() {
+            self.set_status(
+                "Every directory that runs commands is already ahead of the unused ones",
+            );
+            return;
+        }
+        self.usage_days = log.days();
+        self.usage_selected = 0;
+        self.mode = Mode::UsageSuggestions;
+    }
+
+    /// Make the included moves, both panels as one undo step
+    fn apply_usage_suggestions(&mut self) {
+        if !self.profile_allows(Feature::Edit) {
+            return;
+        }
+        let chosen = |panel: Panel| -> Vec<usage::Suggestion> {
+            self.usage_rows
                 .iter()
-                .enumerate()
-                .filter(|(_, i)| i.exists && !i.is_duplicate && !i.needs_normalization)
-                .map(|(idx, _)| idx)
-                .collect(),
+                .filter(|row| row.include && row.panel == panel)
+                .map(|row| row.suggestion)
+                .collect()
+        };
+        let machine_after = usage::reorder(&self.machine_paths, &chosen(Panel::Machine));
+        let user_after = usage::reorder(&self.user_paths, &chosen(Panel::User));
+        let moved = self.usage_rows.iter().filter(|row| row.include).count();
+        self.usage_rows.clear();
+        self.mode = Mode::Normal;
+        if machine_after == self.machine_paths && user_after == self.user_paths {
+            self.set_status("No suggestion was included, nothing moved");
+            return;
         }
+
+        self.clear_redo_stack();
+        let operation = Operation::ReplacePanels {
+            machine_before: std::mem::replace(&mut self.machine_paths, machine_after.clone()),
+            user_before: std::mem::replace(&mut self.user_paths, user_after.clone()),
+            machine_after,
+            user_after,
+        };
+        self.record_undo(operation);
+        // Marks refer to positions that just changed
+        self.machine_marked.clear();
+        self.user_marked.clear();
+        self.has_changes = true;
+        self.reanalyze();
+        self.set_status(&format!(
+            "Moved {} entr{} by how often they run commands (Ctrl+Z to undo)",
+            moved,
+            if moved == 1 { "y" } else { "ies" }
+        ));
     }
 
-    pub fn get_statistics(&self) -> Statistics {
-        let machine_dead = self.machine_info.iter().filter(|i| !i.exists).count();
-        let user_dead = self.user_info.iter().filter(|i| !i.exists).count();
+    /// Marks of a panel, the remote MACHINE's in remote mode
+    fn marked_mut(&mut self, panel: Panel) -> &mut HashSet<usize> {
+        match (self.connection_mode, panel) {
+            (_, Panel::Machine) => &mut self.machine_marked,
+            (ConnectionMode::Local, Panel::User) => &mut self.user_marked,
+            (ConnectionMode::Remote, Panel::User) => &mut self.remote_machine_marked,
+        }
+    }
 
-        let machine_duplicates = self.machine_info.iter().filter(|i| i.is_duplicate).count();
-        let user_duplicates = self.user_info.iter().filter(|i| i.is_duplicate).count();
+    /// Whether an entry is marked, the remote MACHINE's in remote mode
+    pub fn is_marked(&self, panel: Panel, index: usize) -> bool {
+        match (self.connection_mode, panel) {
+            (_, Panel::Machine) => self.machine_marked.contains(&index),
+            (ConnectionMode::Local, Panel::User) => self.user_marked.contains(&index),
+            (ConnectionMode::Remote, Panel::User) => self.remote_machine_marked.contains(&index),
+        }
+    }
 
-        let machine_non_normalized = self
-            .machine_info
-            .iter()
-            .filter(|i| i.needs_normalization)
-            .count();
-        let user_non_normalized = self
-            .user_info
+    /// Move shim directories ahead of the entries in their scope that shadow them,
+    /// and the alias folder after the real installs it hides
+    fn restore_shim_positions(&mut self) {
+        let machine_after =
+            shims::restore_order(&self.machine_paths, PathScope::Machine, &self.shim_findings);
+        let user_after =
+            shims::restore_order(&self.user_paths, PathScope::User, &self.shim_findings);
+        if machine_after == self.machine_paths && user_after == self.user_paths {
+            self.set_status("Shim directories are already in the recommended position");
+            return;
+        }
+        let moved = self
+            .shim_findings
             .iter()
-            .filter(|i| i.needs_normalization)
+            .filter(|finding| finding.is_fixable())
             .count();
 
-        Statistics {
-            machine_total: self.machine_paths.len(),
-            user_total: self.user_paths.len(),
-            machine_dead,
-            user_dead,
-            machine_duplicates,
-            user_duplicates,
-            machine_non_normalized,
-            user_non_normalized,
+        self.clear_redo_stack();
+        let operation = Operation::ReplacePanels {
+            machine_before: std::mem::replace(&mut self.machine_paths, machine_after.clone()),
+            user_before: std::mem::replace(&mut self.user_paths, user_after.clone()),
+            machine_after,
+            user_after,
+        };
+        self.record_undo(operation);
+        // Marks refer to positions that just changed
+        self.machine_marked.clear();
+        self.user_marked.clear();
+        self.has_changes = true;
+        self.reanalyze();
+
+        self.mode = Mode::Normal;
+        self.set_status(&format!(
+            "Moved {} director{} to the recommended position (Ctrl+Z to undo)",
+            moved,
+            if moved == 1 { "y" } else { "ies" }
+        ));
+    }
+
+    /// Work out what promoting (or demoting) the selected entry to the other scope does
+    /// Copies are matched the way the analyzer finds duplicates
+    pub fn plan_promotion(&self) -> Option<Promotion> {
+        let from = self.active_panel;
+        let (entry, info) = self.selected_path_info()?;
+        let key = info.normalized.to_lowercase();
+        let copies = |infos: &[PathInfo], paths: &[String]| -> Vec<(usize, String)> {
+            infos
+                .iter()
+                .enumerate()
+                .filter(|(_, other)| other.normalized.to_lowercase() == key)
+                .filter_map(|(idx, _)| paths.get(idx).map(|p| (idx, p.clone())))
+                .collect()
+        };
+        let (source, target) = match from {
+            Panel::Machine => (
+                copies(&self.machine_info, &self.machine_paths),
+                copies(&self.user_info, &self.user_paths),
+            ),
+            Panel::User => (
+                copies(&self.user_info, &self.user_paths),
+                copies(&self.machine_info, &self.machine_paths),
+            ),
+        };
+
+        let mut target = target.into_iter();
+        let kept_in_target = target.next().map(|(idx, _)| idx);
+        Some(Promotion {
+            from,
+            entry: entry.to_string(),
+            removed_from_source: source,
+            kept_in_target,
+            removed_from_target: target.collect(),
+        })
+    }
+
+    /// Preview moving the selected entry to the other scope
+    fn start_promotion(&mut self) {
+        if self.connection_mode == ConnectionMode::Remote {
+            self.set_status("Promote only works between USER and MACHINE in local mode");
+            return;
+        }
+        match self.plan_promotion() {
+            Some(plan) => {
+                self.pending_promotion = Some(plan);
+                self.mode = Mode::Confirm(ConfirmAction::PromoteEntry);
+            }
+            None => self.set_status("No path selected"),
         }
     }
 
-    /// Calculate the total PATH length for a given scope
-    /// Returns the total character count including semicolon separators
-    /// Windows PATH limit is 2047 characters
-    pub fn calculate_path_length(&self, panel: Panel) -> usize {
-        let paths = match (self.connection_mode, panel) {
-            (ConnectionMode::Local, Panel::Machine) => &self.machine_paths,
-            (ConnectionMode::Local, Panel::User) => &self.user_paths,
-            (ConnectionMode::Remote, Panel::Machine) => &self.machine_paths,
-            (ConnectionMode::Remote, Panel::User) => &self.remote_machine_paths,
+    /// Carry out the previewed promotion as one undoable edit
+    fn promote_entry(&mut self) {
+        let Some(plan) = self.pending_promotion.take() else {
+            return;
+        };
+        let remove = |paths: &[String], removed: &[(usize, String)]| -> Vec<String> {
+            paths
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| !removed.iter().any(|(r, _)| r == idx))
+                .map(|(_, p)| p.clone())
+                .collect()
         };
 
-        if paths.is_empty() {
-            return 0;
+        let (source, target) = match plan.from {
+            Panel::Machine => (&self.machine_paths, &self.user_paths),
+            Panel::User => (&self.user_paths, &self.machine_paths),
+        };
+        let source_after = remove(source, &plan.removed_from_source);
+        let mut target_after = remove(target, &plan.removed_from_target);
+        if plan.kept_in_target.is_none() {
+            target_after.push(plan.entry.clone());
         }
+        let (machine_after, user_after) = match plan.from {
+            Panel::Machine => (source_after, target_after),
+            Panel::User => (target_after, source_after),
+        };
+
+        self.clear_redo_stack();
+        let operation = Operation::ReplacePanels {
+            machine_before: std::mem::replace(&mut self.machine_paths, machine_after.clone()),
+            user_before: std::mem::replace(&mut self.user_paths, user_after.clone()),
+            machine_after,
+            user_after,
+        };
+        self.record_undo(operation);
+        self.machine_marked.clear();
+        self.user_marked.clear();
+        self.has_changes = true;
+        self.reanalyze();
 
-        // Calculate total length: sum of all path lengths + (n-1) semicolons
-        let total_chars: usize = paths.iter().map(|p| p.len()).sum();
-        let separators = paths.len().saturating_sub(1);
-        total_chars + separators
+        let to = plan.from.toggle().scope();
+        let verb = if to == PathScope::Machine {
+            "Promoted"
+        } else {
+            "Demoted"
+        };
+        let duplicates = plan.removed_from_source.len() - 1 + plan.removed_from_target.len();
+        self.set_status(&format!(
+            "{} {} to {}, removed {} duplicate(s)",
+            verb,
+            plan.entry,
+            to.as_str(),
+            duplicates
+        ));
     }
 
-    /// Undo the last operation by popping from the undo stack and reversing it
-    pub fn undo(&mut self) -> Result<()> {
-        if let Some(operation) = self.undo_stack.pop() {
-            // Push to redo stack before reversing
-            self.redo_stack.push(operation.clone());
+    /// Move the entries flagged by the last security scan to the end of their panel
+    /// Less drastic than deleting them: the tools stay reachable, but can no longer
+    /// shadow commands in directories searched after them
+    fn demote_flagged_entries(&mut self) {
+        let Some(report) = &self.security_report else {
+            return;
+        };
+        let flagged = |scope: PathScope| -> Vec<&str> {
+            report
+                .findings
+                .iter()
+                .filter(|f| f.scope == scope)
+                .map(|f| f.path.as_str())
+                .collect()
+        };
+        let machine_flagged = flagged(PathScope::Machine);
+        let user_flagged = flagged(PathScope::User);
+        let machine_after = demoted(&self.machine_paths, &machine_flagged);
+        let user_after = demoted(&self.user_paths, &user_flagged);
 
-            // Reverse the operation without recording it
-            match operation {
-                Operation::DeletePaths { panel, deleted } => {
-                    // Restore deleted paths at their original indices
-                    let paths = match panel {
-                        Panel::Machine => &mut self.machine_paths,
-                        Panel::User => &mut self.user_paths,
-                    };
+        if machine_after == self.machine_paths && user_after == self.user_paths {
+            self.set_status("Flagged entries are already at the end");
+            return;
+        }
+        let count = self
+            .machine_paths
+            .iter()
+            .filter(|p| machine_flagged.contains(&p.as_str()))
+            .count()
+            + self
+                .user_paths
+                .iter()
+                .filter(|p| user_flagged.contains(&p.as_str()))
+                .count();
 
-                    // Sort by index to insert in correct order
-                    let mut sorted_deleted = deleted;
-                    sorted_deleted.sort_by_key(|(idx, _)| *idx);
+        self.clear_redo_stack();
+        let operation = Operation::ReplacePanels {
+            machine_before: std::mem::replace(&mut self.machine_paths, machine_after.clone()),
+            user_before: std::mem::replace(&mut self.user_paths, user_after.clone()),
+            machine_after,
+            user_after,
+        };
+        self.record_undo(operation);
+        // Marks refer to positions that just changed
+        self.machine_marked.clear();
+        self.user_marked.clear();
+        self.has_changes = true;
+        self.reanalyze();
 
-                    for (idx, path) in sorted_deleted {
-                        paths.insert(idx, path);
-                    }
-                }
+        self.mode = Mode::Normal;
+        self.set_status(&format!(
+            "Moved {} flagged entr{} to the end (Ctrl+Z to undo)",
+            count,
+            if count == 1 { "y" } else { "ies" }
+        ));
+    }
 
-                Operation::AddPath { panel, index, .. } => {
-                    // Remove the added path
-                    let paths = match panel {
-                        Panel::Machine => &mut self.machine_paths,
-                        Panel::User => &mut self.user_paths,
-                    };
+    /// Entries listed in both MACHINE and USER, the first copy in each scope
+    /// Keeping the MACHINE copy is the default: it is searched first anyway, and only USER changes
+    pub fn cross_scope_duplicates(&self) -> Vec<MergePair> {
+        let key = |path: &String| normalize_path_with_fs(path, &*self.fs).to_lowercase();
+        let mut machine_first: HashMap<String, usize> = HashMap::new();
+        for (idx, path) in self.machine_paths.iter().enumerate() {
+            if !path.trim().is_empty() {
+                machine_first.entry(key(path)).or_insert(idx);
+            }
+        }
 
-                    if index < paths.len() {
-                        paths.remove(index);
-                    }
-                }
+        let mut pairs = Vec::new();
+        for (idx, path) in self.user_paths.iter().enumerate() {
+            let ignored = self.user_info.get(idx).is_some_and(|info| info.is_ignored);
+            if path.trim().is_empty() || ignored {
+                continue;
+            }
+            // Later USER copies are plain duplicates within USER, F7 handles those
+            if let Some(machine) = machine_first.remove(&key(path)) {
+                pairs.push(MergePair {
+                    machine,
+                    user: idx,
+                    keep: MergeKeep::Machine,
+                });
+            }
+        }
+        pairs
+    }
 
-                Operation::EditPath {
-                    panel,
-                    index,
-                    old_path,
-                    ..
-                } => {
-                    // Restore the old path value
-                    let paths = match panel {
-                        Panel::Machine => &mut self.machine_paths,
-                        Panel::User => &mut self.user_paths,
-                    };
+    /// Open the merge dialog for entries listed in both scopes
+    fn start_merge_duplicates(&mut self) {
+        if self.connection_mode == ConnectionMode::Remote {
+            self.set_status("Remote MACHINE and local USER PATH are not one environment to merge");
+            return;
+        }
+        self.merge_pairs = self.cross_scope_duplicates();
+        if self.merge_pairs.is_empty() {
+            self.set_status("No entry is listed in both MACHINE and USER");
+            return;
+        }
+        self.merge_selected = 0;
+        self.mode = Mode::MergeDuplicates;
+    }
 
-                    if let Some(path) = paths.get_mut(index) {
-                        *path = old_path;
-                    }
-                }
+    /// Remove the copies the merge dialog does not keep, as one undoable step
+    fn apply_merge_duplicates(&mut self) {
+        let pairs = std::mem::take(&mut self.merge_pairs);
+        self.mode = Mode::Normal;
+        let drop_machine: HashSet<usize> = pairs
+            .iter()
+            .filter(|pair| pair.keep == MergeKeep::User)
+            .map(|pair| pair.machine)
+            .collect();
+        let drop_user: HashSet<usize> = pairs
+            .iter()
+            .filter(|pair| pair.keep == MergeKeep::Machine)
+            .map(|pair| pair.user)
+            .collect();
+        if drop_machine.is_empty() && drop_user.is_empty() {
+            self.set_status("Nothing merged, every entry keeps both copies");
+            return;
+        }
+        let kept = |paths: &[String], dropped: &HashSet<usize>| -> Vec<String> {
+            paths
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| !dropped.contains(idx))
+                .map(|(_, path)| path.clone())
+                .collect()
+        };
+        let machine_after = kept(&self.machine_paths, &drop_machine);
+        let user_after = kept(&self.user_paths, &drop_user);
 
-                Operation::SwapPaths {
-                    panel,
-                    index1,
-                    index2,
-                } => {
-                    // Swap back (same operation reverses itself)
-                    let paths = match panel {
-                        Panel::Machine => &mut self.machine_paths,
-                        Panel::User => &mut self.user_paths,
-                    };
+        self.clear_redo_stack();
+        let machine_before = std::mem::replace(&mut self.machine_paths, machine_after.clone());
+        let operation = Operation::ReplacePanels {
+            machine_before: machine_before.clone(),
+            user_before: std::mem::replace(&mut self.user_paths, user_after.clone()),
+            machine_after,
+            user_after,
+        };
+        self.record_undo(operation);
+        self.machine_marked.clear();
+        self.user_marked.clear();
+        self.has_changes = true;
+        self.reanalyze();
 
-                    if index1 < paths.len() && index2 < paths.len() {
-                        paths.swap(index1, index2);
-                    }
+        self.set_status(&format!(
+            "Merged {} entr{}: removed {} from MACHINE, {} from USER (Ctrl+Z to undo)",
+            pairs.len(),
+            if pairs.len() == 1 { "y" } else { "ies" },
+            drop_machine.len(),
+            drop_user.len()
+        ));
+        self.note_staged_machine_edit(&machine_before);
+    }
+
+    /// Entries inside another entry's directory, in both scopes
+    /// The suggestion keeps the directory that holds the commands: an install folder listed
+    /// next to its bin usually has nothing to run itself
+    pub fn nested_entries(&self) -> Vec<NestedPair> {
+        let entries: Vec<(PathScope, usize)> = (0..self.machine_info.len())
+            .map(|idx| (PathScope::Machine, idx))
+            .chain((0..self.user_info.len()).map(|idx| (PathScope::User, idx)))
+            .collect();
+        let dirs: Vec<String> = self
+            .machine_info
+            .iter()
+            .chain(&self.user_info)
+            .map(|info| crate::path_analyzer::expand_environment_variables(&info.normalized))
+            .collect();
+        let extensions = crate::command_lookup::extensions();
+        let has_commands =
+            |pos: usize| crate::command_lookup::has_commands(Path::new(&dirs[pos]), &extensions);
+
+        crate::path_analyzer::find_nested(&dirs)
+            .into_iter()
+            .map(|(parent, child)| {
+                let parent_has_commands = has_commands(parent);
+                let child_has_commands = has_commands(child);
+                let fix = match (parent_has_commands, child_has_commands) {
+                    (false, true) => NestedFix::RemoveParent,
+                    (true, false) => NestedFix::RemoveChild,
+                    _ => NestedFix::KeepBoth,
+                };
+                NestedPair {
+                    parent: entries[parent],
+                    child: entries[child],
+                    parent_has_commands,
+                    child_has_commands,
+                    fix,
                 }
+            })
+            .collect()
+    }
 
-                Operation::MovePaths {
-                    from_panel,
-                    to_panel: _,
-                    paths_with_indices,
-                } => {
-                    // Move paths back from to_panel to from_panel at their original indices
-                    let (from_paths, to_paths) = match from_panel {
-                        Panel::Machine => (&mut self.machine_paths, &mut self.user_paths),
-                        Panel::User => (&mut self.user_paths, &mut self.machine_paths),
-                    };
+    /// Whether any local entry is a parent of the directory, and whether any is inside it
+    pub fn nesting_of(&self, expanded: &str) -> (bool, bool) {
+        let mut has_parent = false;
+        let mut has_child = false;
+        for info in self.machine_info.iter().chain(&self.user_info) {
+            let other = crate::path_analyzer::expand_environment_variables(&info.normalized);
+            has_parent |= crate::path_analyzer::is_inside(expanded, &other);
+            has_child |= crate::path_analyzer::is_inside(&other, expanded);
+        }
+        (has_parent, has_child)
+    }
 
-                    // Remove from destination panel (they were appended at the end)
-                    // We need to remove the last N items where N = paths_with_indices.len()
-                    let count = paths_with_indices.len();
-                    let new_len = to_paths.len().saturating_sub(count);
-                    to_paths.truncate(new_len);
+    /// Open the dialog listing entries inside another entry's directory
+    fn start_nested_entries(&mut self) {
+        if self.connection_mode == ConnectionMode::Remote {
+            self.set_status("Nested entries are only checked on this computer's PATH");
+            return;
+        }
+        if self.refuse_without_fs_checks("The nested entries check") {
+            return;
+        }
+        self.nested_pairs = self.nested_entries();
+        if self.nested_pairs.is_empty() {
+            self.set_status("No entry is inside another entry's directory");
+            return;
+        }
+        self.nested_selected = 0;
+        self.mode = Mode::NestedEntries;
+    }
 
-                    // Restore to source panel at original indices
-                    let mut sorted_paths = paths_with_indices;
-                    sorted_paths.sort_by_key(|(idx, _)| *idx);
+    /// Remove the entries the nested dialog chose, as one undoable step
+    fn apply_nested_entries(&mut self) {
+        let pairs = std::mem::take(&mut self.nested_pairs);
+        self.mode = Mode::Normal;
+        let removed: HashSet<(PathScope, usize)> = pairs
+            .iter()
+            .filter_map(|pair| match pair.fix {
+                NestedFix::KeepBoth => None,
+                NestedFix::RemoveParent => Some(pair.parent),
+                NestedFix::RemoveChild => Some(pair.child),
+            })
+            .collect();
+        if removed.is_empty() {
+            self.set_status("Nothing consolidated, every entry is kept");
+            return;
+        }
+        let kept = |paths: &[String], scope: PathScope| -> Vec<String> {
+            paths
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| !removed.contains(&(scope, *idx)))
+                .map(|(_, path)| path.clone())
+                .collect()
+        };
+        let machine_after = kept(&self.machine_paths, PathScope::Machine);
+        let user_after = kept(&self.user_paths, PathScope::User);
 
-                    for (idx, path) in sorted_paths {
-                        from_paths.insert(idx, path);
-                    }
-                }
+        self.clear_redo_stack();
+        let machine_before = std::mem::replace(&mut self.machine_paths, machine_after.clone());
+        let operation = Operation::ReplacePanels {
+            machine_before: machine_before.clone(),
+            user_before: std::mem::replace(&mut self.user_paths, user_after.clone()),
+            machine_after,
+            user_after,
+        };
+        self.record_undo(operation);
+        self.machine_marked.clear();
+        self.user_marked.clear();
+        self.has_changes = true;
+        self.reanalyze();
 
-                Operation::CopyPaths {
-                    from_panel: _,
-                    to_panel,
-                    paths_with_indices,
-                } => {
-                    // Undo copy: remove the copied paths from to_panel
-                    // Note: paths_with_indices contains the indices from the FROM panel,
-                    // but we need to remove from the TO panel where they were appended
-                    let to_paths = match to_panel {
-                        Panel::Machine => &mut self.machine_paths,
-                        Panel::User => &mut self.user_paths,
-                    };
+        self.set_status(&format!(
+            "Removed {} nested entr{} (Ctrl+Z to undo)",
+            removed.len(),
+            if removed.len() == 1 { "y" } else { "ies" }
+        ));
+        self.note_staged_machine_edit(&machine_before);
+    }
 
-                    // Paths were appended to the end, so remove the last N paths
-                    let count = paths_with_indices.len();
-                    let new_len = to_paths.len().saturating_sub(count);
-                    to_paths.truncate(new_len);
-                }
+    /// Ask for the text to find in every entry, prefilled with the last one
+    fn start_batch_replace(&mut self) {
+        if self.connection_mode == ConnectionMode::Remote {
+            self.set_status("Find and replace checks the new directories on this computer only");
+            return;
+        }
+        self.input_buffer = self.replace_find.clone();
+        self.mode = Mode::Input(InputMode::ReplaceFind);
+        self.mode_enter_time = std::time::Instant::now();
+    }
 
-                Operation::NormalizePaths { panel, changes } => {
-                    // Restore old (non-normalized) values
-                    let paths = match panel {
-                        Panel::Machine => &mut self.machine_paths,
-                        Panel::User => &mut self.user_paths,
-                    };
+    /// Entries of both panels containing the find text, with the text replaced
+    pub fn batch_replace_rows(&self, find: &str, with: &str) -> Vec<ReplaceRow> {
+        self.replacement_rows(|path| replace_ignore_case(path, find, with))
+    }
 
-                    for (idx, old_path, _) in changes {
-                        if let Some(path) = paths.get_mut(idx) {
-                            *path = old_path;
-                        }
-                    }
-                }
+    /// Entries of both panels the replacement changes, checked for the new directory
+    fn replacement_rows(&self, replace: impl Fn(&str) -> Option<String>) -> Vec<ReplaceRow> {
+        let scopes = [
+            (PathScope::Machine, &self.machine_paths),
+            (PathScope::User, &self.user_paths),
+        ];
+        let mut rows = Vec::new();
+        for (scope, paths) in scopes {
+            for (index, before) in paths.iter().enumerate() {
+                let Some(after) = replace(before) else {
+                    continue;
+                };
+                let exists = !after.trim().is_empty()
+                    && path_exists_with_fs(
+                        &normalize_path_with_fs(&after, &*self.fs),
+                        None,
+                        &*self.fs,
+                    );
+                rows.push(ReplaceRow {
+                    scope,
+                    index,
+                    before: before.clone(),
+                    after,
+                    exists,
+                    include: true,
+                });
             }
+        }
+        rows
+    }
 
-            self.reanalyze();
-            self.has_changes = true;
-            self.set_status("Undo successful");
-            Ok(())
-        } else {
-            self.set_status("Nothing to undo");
-            Ok(())
+    /// Open the preview of replacing the find text with the replacement
+    fn preview_batch_replace(&mut self) {
+        let (find, with) = (self.replace_find.clone(), self.replace_with.clone());
+        self.replace_rows = self.batch_replace_rows(&find, &with);
+        if self.replace_rows.is_empty() {
+            self.set_status(&format!("No entry contains \"{}\"", find));
+            return;
         }
+        self.replace_migration = false;
+        self.replace_selected = 0;
+        self.mode = Mode::BatchReplace;
     }
 
-    /// Check if there are operations available to undo
-    pub fn can_undo(&self) -> bool {
-        !self.undo_stack.is_empty()
+    /// Drive roots and UNC shares the entries live on, in the order first seen
+    pub fn entry_roots(&self) -> Vec<String> {
+        let mut roots: Vec<String> = Vec::new();
+        for path in self.machine_paths.iter().chain(&self.user_paths) {
+            if let Some(root) = path_root(path.trim().trim_matches('"')) {
+                if !roots.iter().any(|r| r.eq_ignore_ascii_case(&root)) {
+                    roots.push(root);
+                }
+            }
+        }
+        roots
     }
 
-    /// Redo the last undone operation by popping from the redo stack and re-applying it
-    pub fn redo(&mut self) -> Result<()> {
-        if let Some(operation) = self.redo_stack.pop() {
-            // Re-apply the operation and push back to undo stack
-            self.undo_stack.push(operation.clone());
+    /// Ask for the drive or folder the tools moved from, starting at the selected entry's root
+    fn start_drive_migration(&mut self) {
+        if self.connection_mode == ConnectionMode::Remote {
+            self.set_status("Drive migration checks the new directories on this computer only");
+            return;
+        }
+        self.input_buffer = self
+            .selected_path_info()
+            .and_then(|(path, _)| path_root(path.trim().trim_matches('"')))
+            .unwrap_or_default();
+        self.mode = Mode::Input(InputMode::MigrateFrom);
+        self.mode_enter_time = std::time::Instant::now();
+    }
 
-            // Apply the operation
-            match operation {
-                Operation::DeletePaths { panel, deleted } => {
-                    // Re-delete the paths
-                    let paths = match panel {
-                        Panel::Machine => &mut self.machine_paths,
-                        Panel::User => &mut self.user_paths,
-                    };
+    /// Entries under the old root moved to the new one
+    /// Only the entries whose directory exists at the new location are selected
+    pub fn drive_migration_rows(&self, from: &str, to: &str) -> Vec<ReplaceRow> {
+        let mut rows = self.replacement_rows(|path| replace_root(path, from, to));
+        for row in &mut rows {
+            row.include = row.exists;
+        }
+        rows
+    }
 
-                    // Sort indices in reverse to delete from end to start
-                    let mut indices: Vec<_> = deleted.iter().map(|(idx, _)| *idx).collect();
-                    indices.sort_unstable_by(|a, b| b.cmp(a));
+    /// Open the migration preview in the find-and-replace dialog
+    fn preview_drive_migration(&mut self) {
+        let (from, to) = (self.replace_find.clone(), self.replace_with.clone());
+        self.replace_rows = self.drive_migration_rows(&from, &to);
+        if self.replace_rows.is_empty() {
+            self.set_status(&format!("No entry is under {}", from));
+            return;
+        }
+        self.replace_migration = true;
+        self.replace_selected = 0;
+        self.mode = Mode::BatchReplace;
+    }
 
-                    for idx in indices {
-                        if idx < paths.len() {
-                            paths.remove(idx);
-                        }
-                    }
-                }
+    /// Stage the replacements chosen in the dialog as one undoable step
+    fn apply_batch_replace(&mut self) {
+        let rows = std::mem::take(&mut self.replace_rows);
+        self.mode = Mode::Normal;
+        let chosen: Vec<&ReplaceRow> = rows.iter().filter(|row| row.include).collect();
+        if chosen.is_empty() {
+            self.set_status("Nothing replaced, every entry was left out");
+            return;
+        }
+        let mut machine_after = self.machine_paths.clone();
+        let mut user_after = self.user_paths.clone();
+        for row in &chosen {
+            let paths = match row.scope {
+                PathScope::Machine => &mut machine_after,
+                PathScope::User => &mut user_after,
+            };
+            if let Some(path) = paths.get_mut(row.index) {
+                *path = row.after.clone();
+            }
+        }
 
-                Operation::AddPath {
-                    panel,
-                    index: _,
-                    path,
-                } => {
-                    // Re-add the path
-                    let paths = match panel {
-                        Panel::Machine => &mut self.machine_paths,
-                        Panel::User => &mut self.user_paths,
-                    };
-                    paths.push(path);
-                }
+        self.clear_redo_stack();
+        let machine_before = std::mem::replace(&mut self.machine_paths, machine_after.clone());
+        let operation = Operation::ReplacePanels {
+            machine_before: machine_before.clone(),
+            user_before: std::mem::replace(&mut self.user_paths, user_after.clone()),
+            machine_after,
+            user_after,
+        };
+        self.record_undo(operation);
+        self.machine_marked.clear();
+        self.user_marked.clear();
+        self.has_changes = true;
+        self.reanalyze();
 
-                Operation::EditPath {
-                    panel,
-                    index,
-                    old_path: _,
-                    new_path,
-                } => {
-                    // Re-apply the edit
-                    let paths = match panel {
-                        Panel::Machine => &mut self.machine_paths,
-                        Panel::User => &mut self.user_paths,
-                    };
+        let missing = chosen.iter().filter(|row| !row.exists).count();
+        let manual = rows.iter().filter(|row| !row.include).count();
+        let mut status = format!(
+            "Replaced \"{}\" in {} entr{}",
+            self.replace_find,
+            chosen.len(),
+            if chosen.len() == 1 { "y" } else { "ies" }
+        );
+        if missing > 0 {
+            status.push_str(&format!(", {} to a missing directory", missing));
+        }
+        if self.replace_migration && manual > 0 {
+            status.push_str(&format!(", {} left to move by hand", manual));
+        }
+        status.push_str(" (Ctrl+Z to undo)");
+        self.set_status(&status);
+        self.note_staged_machine_edit(&machine_before);
+    }
 
-                    if let Some(path) = paths.get_mut(index) {
-                        *path = new_path;
-                    }
-                }
+    /// E: edit the active panel's whole PATH value as text
+    fn start_raw_editor(&mut self) {
+        if self.connection_mode == ConnectionMode::Remote {
+            self.set_status("The raw editor works in local mode only");
+            return;
+        }
+        let (paths, _, _) = self.panel_entries(self.active_panel);
+        self.raw_editor = Some(RawEditor::new(paths));
+        self.raw_editor_panel = self.active_panel;
+        self.mode = Mode::RawEditor;
+    }
 
-                Operation::SwapPaths {
-                    panel,
-                    index1,
-                    index2,
-                } => {
-                    // Re-swap (same as undo)
-                    let paths = match panel {
-                        Panel::Machine => &mut self.machine_paths,
-                        Panel::User => &mut self.user_paths,
-                    };
+    /// Size of the edited value against the configured limit, as the status bar counts it
+    pub fn raw_editor_length(&self) -> Option<String> {
+        let editor = self.raw_editor.as_ref()?;
+        let threshold = self.length_threshold();
+        let length = Lengths::of(&editor.entries(), &expansion_context(&self.settings, false))
+            .measured(threshold.measure);
+        Some(format!("{}/{}", length, threshold.limit))
+    }
 
-                    if index1 < paths.len() && index2 < paths.len() {
-                        paths.swap(index1, index2);
-                    }
-                }
+    /// What putting the text back would change, e.g. "USER +1 -2 (reordered)"
+    pub fn raw_editor_change(&self) -> Option<ScopeChange> {
+        let editor = self.raw_editor.as_ref()?;
+        ScopeChange::between(
+            self.raw_editor_panel.scope(),
+            editor.before(),
+            &editor.entries(),
+        )
+    }
 
-                Operation::MovePaths {
-                    from_panel,
-                    to_panel: _,
-                    paths_with_indices,
-                } => {
-                    // Re-do: move paths from from_panel to to_panel (destination is implicit from from_panel)
-                    let (from_paths, to_paths) = match from_panel {
-                        Panel::Machine => (&mut self.machine_paths, &mut self.user_paths),
-                        Panel::User => (&mut self.user_paths, &mut self.machine_paths),
-                    };
+    /// Replace the panel's entries with the edited text as one undoable step
+    fn commit_raw_edit(&mut self) {
+        self.mode = Mode::Normal;
+        let summary = self
+            .raw_editor_change()
+            .map(|change| change.summary())
+            .unwrap_or_default();
+        let Some(editor) = self.raw_editor.take() else {
+            return;
+        };
+        let entries = editor.entries();
+        let (mut machine_after, mut user_after) =
+            (self.machine_paths.clone(), self.user_paths.clone());
+        match self.raw_editor_panel {
+            Panel::Machine => machine_after = entries,
+            Panel::User => user_after = entries,
+        }
 
-                    // Remove from source panel (sorted in reverse)
-                    let mut indices: Vec<_> =
-                        paths_with_indices.iter().map(|(idx, _)| *idx).collect();
-                    indices.sort_unstable_by(|a, b| b.cmp(a));
+        self.clear_redo_stack();
+        let machine_before = std::mem::replace(&mut self.machine_paths, machine_after.clone());
+        let operation = Operation::ReplacePanels {
+            machine_before: machine_before.clone(),
+            user_before: std::mem::replace(&mut self.user_paths, user_after.clone()),
+            machine_after,
+            user_after,
+        };
+        self.record_undo(operation);
+        self.machine_marked.clear();
+        self.user_marked.clear();
+        self.has_changes = true;
+        self.reanalyze();
 
-                    for idx in indices {
-                        if idx < from_paths.len() {
-                            from_paths.remove(idx);
-                        }
-                    }
+        self.set_status(&format!("Raw edit staged: {} (Ctrl+Z to undo)", summary));
+        self.note_staged_machine_edit(&machine_before);
+    }
 
-                    // Add to destination panel
-                    for (_, path) in paths_with_indices {
-                        to_paths.push(path);
-                    }
+    /// The PATH a new process sees, in search order: MACHINE entries first, then USER
+    /// Later entries naming an already searched directory have no effect
+    pub fn effective_path(&self) -> Vec<EffectiveEntry> {
+        let mut first_seen: std::collections::HashMap<String, (PathScope, usize)> =
+            std::collections::HashMap::new();
+        let scopes = [
+            (PathScope::Machine, &self.machine_info),
+            (PathScope::User, &self.user_info),
+        ];
+
+        let mut entries = Vec::new();
+        for (scope, infos) in scopes {
+            for (index, info) in infos.iter().enumerate() {
+                let key = info.normalized.to_lowercase();
+                let shadowed_by = first_seen.get(&key).copied();
+                if shadowed_by.is_none() {
+                    first_seen.insert(key, (scope, index));
                 }
+                entries.push(EffectiveEntry {
+                    scope,
+                    index,
+                    shadowed_by,
+                });
+            }
+        }
+        entries
+    }
 
-                Operation::CopyPaths {
-                    from_panel: _,
-                    to_panel,
-                    paths_with_indices,
-                } => {
-                    // Redo copy: add the copied paths back to to_panel
-                    let to_paths = match to_panel {
-                        Panel::Machine => &mut self.machine_paths,
-                        Panel::User => &mut self.user_paths,
-                    };
+    /// The background task currently running, if any (shown with a spinner)
+    /// Returns the task's label and current spinner frame
+    pub fn busy_task(&self) -> Option<(&str, char)> {
+        if let Some(task) = &self.availability_task {
+            return Some((task.label.as_str(), self.spinner_of(task)));
+        }
+        if let Some(task) = &self.size_task {
+            return Some((task.label.as_str(), self.spinner_of(task)));
+        }
+        if let Some(task) = &self.security_task {
+            return Some((task.label.as_str(), self.spinner_of(task)));
+        }
+        if let Some(task) = &self.shim_task {
+            return Some((task.label.as_str(), self.spinner_of(task)));
+        }
+        if let Some(task) = &self.toolchain_task {
+            return Some((task.label.as_str(), self.spinner_of(task)));
+        }
+        if let Some(task) = &self.download_task {
+            return Some((task.label.as_str(), self.spinner_of(task)));
+        }
+        if let Some(task) = &self.remote_task {
+            return Some((task.label.as_str(), self.spinner_of(task)));
+        }
+        if let Some(task) = &self.remote_write_task {
+            return Some((task.label.as_str(), self.spinner_of(task)));
+        }
+        self.update_task
+            .as_ref()
+            .map(|task| (task.label.as_str(), self.spinner_of(task)))
+    }
 
-                    for (_, path) in paths_with_indices {
-                        to_paths.push(path);
-                    }
-                }
+    /// Spinner frame of a running task, a still mark when motion is reduced
+    pub fn spinner_of<T: Send + 'static>(&self, running: &BackgroundTask<T>) -> char {
+        if self.settings.reduced_motion {
+            task::STILL_SPINNER
+        } else {
+            running.spinner()
+        }
+    }
 
-                Operation::NormalizePaths { panel, changes } => {
-                    // Re-apply normalizations
-                    let paths = match panel {
-                        Panel::Machine => &mut self.machine_paths,
-                        Panel::User => &mut self.user_paths,
-                    };
+    /// Collect results of finished background tasks, returns true if one finished
+    pub fn poll_background_tasks(&mut self) -> bool {
+        let mut finished = false;
 
-                    for (idx, _, new_path) in changes {
-                        if let Some(path) = paths.get_mut(idx) {
-                            *path = new_path;
-                        }
-                    }
-                }
+        if let Some(task) = &self.availability_task {
+            if let Some(report) = task.try_finish() {
+                self.availability_report = Some(report);
+                self.availability_task = None;
+                finished = true;
+            } else if task.is_abandoned() {
+                self.availability_report = Some("Could not check availability".to_string());
+                self.availability_task = None;
+                finished = true;
             }
+        }
 
-            self.reanalyze();
-            self.has_changes = true;
-            self.set_status("Redo successful");
-            Ok(())
-        } else {
-            self.set_status("Nothing to redo");
-            Ok(())
+        if let Some(task) = &self.size_task {
+            if let Some((key, size)) = task.try_finish() {
+                self.directory_sizes.insert(key, size);
+                self.size_task = None;
+                finished = true;
+            } else if task.is_abandoned() {
+                self.size_task = None;
+                finished = true;
+            }
         }
-    }
 
-    /// Check if there are operations available to redo
-    pub fn can_redo(&self) -> bool {
-        !self.redo_stack.is_empty()
-    }
+        if let Some(task) = &self.security_task {
+            if let Some(report) = task.try_finish() {
+                self.security_task = None;
+                self.finish_security_scan(report);
+                finished = true;
+            } else if task.is_abandoned() {
+                self.security_task = None;
+                self.set_status("Security scan failed");
+                finished = true;
+            }
+        }
 
-    /// Clear the redo stack - called whenever a new operation is performed
-    fn clear_redo_stack(&mut self) {
-        self.redo_stack.clear();
-    }
+        if let Some(task) = &self.shim_task {
+            if let Some(findings) = task.try_finish() {
+                self.shim_task = None;
+                self.finish_shim_check(findings);
+                finished = true;
+            } else if task.is_abandoned() {
+                self.shim_task = None;
+                self.set_status("Shim check failed");
+                finished = true;
+            }
+        }
 
-    /// Activate menu by accelerator character
-    fn activate_menu_by_char(&mut self, c: char) {
-        let menus = crate::menu::get_menus(self.connection_mode);
-        let c_lower = c.to_lowercase().next().unwrap_or(c);
+        if let Some(task) = &self.toolchain_task {
+            if let Some(toolchains) = task.try_finish() {
+                self.toolchain_task = None;
+                self.finish_toolchain_scan(toolchains);
+                finished = true;
+            } else if task.is_abandoned() {
+                self.toolchain_task = None;
+                self.set_status("Toolchain scan failed");
+                finished = true;
+            }
+        }
 
-        for (i, menu) in menus.iter().enumerate() {
-            if menu.accelerator == c_lower {
-                self.mode = Mode::Menu {
-                    active_menu: i,
-                    selected_item: 0,
-                };
-                return;
+        if let Some(task) = &self.update_task {
+            if let Some(result) = task.try_finish() {
+                self.update_task = None;
+                self.finish_update_check(result);
+                finished = true;
+            } else if task.is_abandoned() {
+                self.update_task = None;
+                self.finish_update_check(Err(anyhow::anyhow!("the check stopped unexpectedly")));
+                finished = true;
             }
         }
-    }
 
-    /// Handle click on menu bar
-    fn handle_menu_bar_click(&mut self, column: u16) -> Result<()> {
-        let menus = crate::menu::get_menus(self.connection_mode);
-        let mut x_offset = 1; // Start with 1 for initial space
+        if let Some(task) = &self.download_task {
+            if let Some(result) = task.try_finish() {
+                self.download_task = None;
+                self.finish_update_download(result);
+                finished = true;
+            } else if task.is_abandoned() {
+                self.download_task = None;
+                self.set_status("Update download failed");
+                finished = true;
+            }
+        }
 
-        for (i, menu) in menus.iter().enumerate() {
-            let menu_start = x_offset;
-            let menu_end = x_offset + menu.name.len() as u16 + 2; // +2 for spaces around menu name
+        if let Some(task) = &self.remote_write_task {
+            if let Some(result) = task.try_finish() {
+                self.remote_write_task = None;
+                self.finish_queued_write(result);
+                finished = true;
+            } else if task.is_abandoned() {
+                self.remote_write_task = None;
+                self.finish_queued_write(Err(anyhow::anyhow!("the write stopped unexpectedly")));
+                finished = true;
+            }
+        }
 
-            if column >= menu_start && column < menu_end {
-                // Clicked on this menu
-                self.mode = Mode::Menu {
-                    active_menu: i,
-                    selected_item: 0,
-                };
-                return Ok(());
+        if let Some(task) = &self.remote_task {
+            if let Some(result) = task.try_finish() {
+                self.remote_task = None;
+                self.finish_remote_connect(result);
+                finished = true;
+            } else if task.is_abandoned() {
+                self.remote_task = None;
+                self.finish_remote_connect(Err(anyhow::anyhow!(
+                    "the connection attempt stopped unexpectedly"
+                )));
+                finished = true;
             }
+        }
 
-            x_offset = menu_end;
+        finished
+    }
+
+    /// Periodic work between events, returns true if anything on screen changed
+    pub fn on_tick(&mut self) -> bool {
+        let finished = self.poll_background_tasks();
+        self.poll_remote_queue(std::time::Instant::now());
+        // A finished scan opens its report
+        self.enforce_profile(None);
+        let controlled = self.poll_control();
+        let coordinated = self.poll_instances();
+        let media_changed = self.refresh_media_status();
+        let locked = self.check_idle_lock(std::time::Instant::now());
+        // A running task animates its spinner
+        let animating = self.busy_task().is_some() && !self.settings.reduced_motion;
+        finished || controlled || coordinated || media_changed || locked || animating
+    }
+
+    /// Lock an elevated session that has been idle too long, true if it just locked
+    pub fn check_idle_lock(&mut self, now: std::time::Instant) -> bool {
+        let Some(limit) = self.settings.lock_after else {
+            return false;
+        };
+        if !self.is_admin
+            || self.mode == Mode::Locked
+            || now.duration_since(self.last_activity) < limit
+        {
+            return false;
         }
+        self.locked_mode = self.mode;
+        self.mode = Mode::Locked;
+        true
+    }
 
+    /// While locked only Enter (unlock) and Ctrl+C (quit) do anything
+    fn handle_locked_event(&mut self, event: Event) -> Result<()> {
+        let Event::Key(key) = event else {
+            return Ok(());
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(());
+        }
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => self.should_exit = true,
+            (KeyCode::Enter, _) => {
+                self.mode = self.locked_mode;
+                self.mode_enter_time = std::time::Instant::now();
+                self.set_status(
+                    "Unlocked - running as administrator, MACHINE edits affect every user",
+                );
+            }
+            _ => {}
+        }
         Ok(())
     }
 
-    /// Handle click in menu dropdown
-    fn handle_menu_click(
-        &mut self,
-        column: u16,
-        row: u16,
-        active_menu: usize,
-        _selected_item: usize,
-    ) -> Result<()> {
-        let menus = crate::menu::get_menus(self.connection_mode);
+    // Path modification
+    fn delete_marked(&mut self) -> Result<()> {
+        let mut deleted_count = 0;
 
-        if active_menu >= menus.len() {
-            return Ok(());
+        // Record machine deletions for undo
+        let mut machine_deleted = Vec::new();
+        for (idx, path) in self.machine_paths.iter().enumerate() {
+            if self.machine_marked.contains(&idx) {
+                machine_deleted.push((idx, path.clone()));
+            }
         }
 
-        let menu = &menus[active_menu];
+        // Delete from machine paths
+        let mut new_machine = Vec::new();
+        for (idx, path) in self.machine_paths.iter().enumerate() {
+            if !self.machine_marked.contains(&idx) {
+                new_machine.push(path.clone());
+            } else {
+                deleted_count += 1;
+            }
+        }
+        self.machine_paths = new_machine;
+        self.machine_marked.clear();
 
-        // Calculate menu position
-        let mut x_offset = 1;
-        for menu in menus.iter().take(active_menu) {
-            x_offset += menu.name.len() as u16 + 2;
+        // Record user deletions for undo
+        let mut user_deleted = Vec::new();
+        for (idx, path) in self.user_paths.iter().enumerate() {
+            if self.user_marked.contains(&idx) {
+                user_deleted.push((idx, path.clone()));
+            }
         }
 
-        // Calculate menu width
-        let mut menu_width = menu.name.len();
-        for item in &menu.items {
-            let item_text_len =
-                item.label.len() + item.shortcut.as_ref().map(|s| s.len() + 2).unwrap_or(0);
-            menu_width = menu_width.max(item_text_len);
+        // Delete from user paths
+        let mut new_user = Vec::new();
+        for (idx, path) in self.user_paths.iter().enumerate() {
+            if !self.user_marked.contains(&idx) {
+                new_user.push(path.clone());
+            } else {
+                deleted_count += 1;
+            }
         }
-        menu_width += 4;
+        self.user_paths = new_user;
+        self.user_marked.clear();
 
-        let menu_x = x_offset;
-        let menu_y = 1; // Below menu bar
-        let menu_height = menu.items.len() as u16 + 2; // +2 for borders
+        // Clear redo stack and record undo operations
+        self.clear_redo_stack();
+        if !machine_deleted.is_empty() {
+            self.record_undo(Operation::DeletePaths {
+                panel: Panel::Machine,
+                deleted: machine_deleted,
+            });
+        }
+        if !user_deleted.is_empty() {
+            self.record_undo(Operation::DeletePaths {
+                panel: Panel::User,
+                deleted: user_deleted,
+            });
+        }
 
-        // Check if click is within menu bounds
-        if column >= menu_x
-            && column < menu_x + menu_width as u16
-            && row >= menu_y
-            && row < menu_y + menu_height
-        {
-            // Calculate which item was clicked (accounting for border)
-            let clicked_item = (row - menu_y - 1) as usize; // -1 for top border
+        self.reanalyze();
+        self.has_changes = true;
+        self.set_status(&format!("Deleted {} path(s)", deleted_count));
+        Ok(())
+    }
 
-            if clicked_item < menu.items.len() {
-                let item = &menu.items[clicked_item];
-                if item.enabled {
-                    self.execute_menu_action(item.action)?;
-                }
-            }
+    /// Work out what a delete-all would remove and ask, or say there is nothing to remove
+    fn confirm_bulk_delete(&mut self, action: ConfirmAction) {
+        self.delete_unavailable = false;
+        let plan = self.plan_bulk_delete(action);
+        // Entries on unavailable drives can still be included from the dialog
+        let unavailable = action == ConfirmAction::DeleteAllDead && self.unavailable_count() > 0;
+        if plan.is_empty() && !unavailable {
+            let recent = self.recently_dead_count();
+            let message = match action {
+                ConfirmAction::DeleteAllDead if recent > 0 => format!(
+                    "No dead paths to delete, kept {}",
+                    self.recently_dead_note(recent)
+                ),
+                ConfirmAction::DeleteAllDead => "No dead paths to delete".to_string(),
+                _ => "No duplicate paths to delete".to_string(),
+            };
+            self.set_status(&message);
+            return;
+        }
+        if plan.len() > self.review_threshold() {
+            self.start_review(plan);
         } else {
-            // Clicked outside menu, close it
-            self.mode = Mode::Normal;
+            self.pending_bulk_delete = Some(plan);
+            self.mode = Mode::Confirm(action);
         }
+    }
 
-        Ok(())
+    /// Bulk deletes of more entries than this are reviewed instead of confirmed
+    fn review_threshold(&self) -> usize {
+        self.settings
+            .review_threshold
+            .unwrap_or(config::DEFAULT_REVIEW_THRESHOLD)
     }
 
-    /// Handle keyboard input in menu mode
-    fn handle_menu_input(
-        &mut self,
-        key: KeyEvent,
-        active_menu: usize,
-        selected_item: usize,
-    ) -> Result<()> {
-        let menus = crate::menu::get_menus(self.connection_mode);
+    /// List every entry of a bulk delete with a checkbox, all checked
+    fn start_review(&mut self, plan: BulkDelete) {
+        self.review_checked = vec![true; plan.len()];
+        self.review_selected = 0;
+        self.pending_bulk_delete = Some(plan);
+        self.mode = Mode::BulkReview;
+    }
 
+    fn handle_bulk_review_input(&mut self, key: KeyEvent) -> Result<()> {
+        let count = self.review_checked.len();
         match key.code {
-            KeyCode::Esc => {
-                // Close menu and return to normal mode
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.pending_bulk_delete = None;
                 self.mode = Mode::Normal;
+                self.set_status("Delete cancelled");
             }
-            KeyCode::Left => {
-                // Move to previous menu
-                if active_menu > 0 {
-                    self.mode = Mode::Menu {
-                        active_menu: active_menu - 1,
-                        selected_item: 0,
-                    };
+            KeyCode::Enter => {
+                self.mode = Mode::Normal;
+                if let Some(plan) = self.pending_bulk_delete.take() {
+                    let plan = plan.retain_checked(&self.review_checked);
+                    if plan.is_empty() {
+                        self.set_status("Nothing checked, nothing deleted");
+                    } else {
+                        self.run_bulk_delete(plan);
+                    }
                 }
             }
-            KeyCode::Right => {
-                // Move to next menu
-                if active_menu + 1 < menus.len() {
-                    self.mode = Mode::Menu {
-                        active_menu: active_menu + 1,
-                        selected_item: 0,
-                    };
-                }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.review_selected = self.review_selected.saturating_sub(1);
             }
-            KeyCode::Up => {
-                // Move to previous item in menu
-                if selected_item > 0 {
-                    self.mode = Mode::Menu {
-                        active_menu,
-                        selected_item: selected_item - 1,
-                    };
-                }
+            KeyCode::Down | KeyCode::Char('j') if self.review_selected + 1 < count => {
+                self.review_selected += 1;
             }
-            KeyCode::Down => {
-                // Move to next item in menu
-                if active_menu < menus.len() && selected_item + 1 < menus[active_menu].items.len() {
-                    self.mode = Mode::Menu {
-                        active_menu,
-                        selected_item: selected_item + 1,
-                    };
+            KeyCode::Home => self.review_selected = 0,
+            KeyCode::End => self.review_selected = count.saturating_sub(1),
+            KeyCode::Char(' ') | KeyCode::Insert => {
+                if let Some(checked) = self.review_checked.get_mut(self.review_selected) {
+                    *checked = !*checked;
                 }
-            }
-            KeyCode::Enter => {
-                // Execute selected menu action
-                if active_menu < menus.len() {
-                    let menu = &menus[active_menu];
-                    if selected_item < menu.items.len() {
-                        let item = &menu.items[selected_item];
-                        if item.enabled {
-                            self.execute_menu_action(item.action)?;
-                        }
-                    }
+                if self.review_selected + 1 < count {
+                    self.review_selected += 1;
                 }
             }
+            KeyCode::Char('a') | KeyCode::Char('A') => {
+                // Uncheck all when everything is checked, check all otherwise
+                let all = !self.review_checked.iter().all(|&checked| checked);
+                self.review_checked
+                    .iter_mut()
+                    .for_each(|checked| *checked = all);
+            }
             _ => {}
         }
         Ok(())
     }
 
-    /// Execute a menu action
-    fn execute_menu_action(&mut self, action: crate::menu::MenuAction) -> Result<()> {
-        use crate::menu::MenuAction;
+    /// Entries DeleteAllDead or DeleteAllDuplicates removes from each scope
+    fn plan_bulk_delete(&self, action: ConfirmAction) -> BulkDelete {
+        let (machine, user) = if action == ConfirmAction::DeleteAllDead {
+            // Entries on offline drives are kept unless the dialog includes them
+            (
+                self.collect_dead_entries(&self.machine_paths, &self.machine_info),
+                self.collect_dead_entries(&self.user_paths, &self.user_info),
+            )
+        } else {
+            // Keep the first occurrence of each path (case-insensitive, normalized),
+            // MACHINE first so USER copies of MACHINE entries go
+            // Ignored entries are left out altogether
+            let mut seen = HashSet::new();
+            let mut duplicates = |paths: &[String], info: &[PathInfo]| -> Vec<(usize, String)> {
+                paths
+                    .iter()
+                    .enumerate()
+                    .filter(|(idx, _)| !info.get(*idx).is_some_and(|i| i.is_ignored))
+                    .filter(|(_, path)| {
+                        !seen.insert(normalize_path_with_fs(path, &*self.fs).to_lowercase())
+                    })
+                    .map(|(idx, path)| (idx, path.clone()))
+                    .collect()
+            };
+            let machine = duplicates(&self.machine_paths, &self.machine_info);
+            (machine, duplicates(&self.user_paths, &self.user_info))
+        };
+        BulkDelete {
+            action,
+            machine,
+            user,
+        }
+    }
 
-        // Close the menu first
-        self.mode = Mode::Normal;
+    /// Carry out the previewed delete-all, planning it afresh if none is pending
+    fn take_bulk_delete(&mut self, action: ConfirmAction) -> BulkDelete {
+        match self.pending_bulk_delete.take() {
+            Some(plan) if plan.action == action => plan,
+            _ => self.plan_bulk_delete(action),
+        }
+    }
+
+    /// Remove a planned delete-all as undoable edits, returning how many entries went
+    fn remove_planned(&mut self, plan: BulkDelete) -> usize {
+        let deleted = plan.len();
+        Self::remove_indices(&mut self.machine_paths, &plan.machine);
+        Self::remove_indices(&mut self.user_paths, &plan.user);
+
+        // Clear redo stack and record undo operations
+        self.clear_redo_stack();
+        if !plan.machine.is_empty() {
+            self.record_undo(Operation::DeletePaths {
+                panel: Panel::Machine,
+                deleted: plan.machine,
+            });
+        }
+        if !plan.user.is_empty() {
+            self.record_undo(Operation::DeletePaths {
+                panel: Panel::User,
+                deleted: plan.user,
+            });
+        }
+
+        self.reanalyze();
+        self.has_changes = true;
+        deleted
+    }
+
+    /// Remove a planned bulk delete and report it the way its command does
+    fn run_bulk_delete(&mut self, plan: BulkDelete) {
+        let action = plan.action;
+        let kept_unavailable = if self.delete_unavailable {
+            0
+        } else {
+            self.unavailable_count()
+        };
+        self.delete_unavailable = false;
+        let kept_recent = self.recently_dead_count();
+        let deleted = self.remove_planned(plan);
+        // Marks point at positions that have moved
+        self.machine_marked.clear();
+        self.user_marked.clear();
 
         match action {
-            // File menu
-            MenuAction::RunAsAdministrator => {
-                if !self.is_admin {
-                    self.mode = Mode::Confirm(ConfirmAction::RequestElevation);
-                } else {
-                    self.set_status("Already running as administrator");
+            ConfirmAction::DeleteAllDead => {
+                let mut kept = Vec::new();
+                if kept_unavailable > 0 {
+                    kept.push(format!("{} on unavailable drives", kept_unavailable));
                 }
-            }
-            MenuAction::Exit => {
-                self.confirm_exit();
-            }
-
-            // Command menu
-            MenuAction::AddPath => {
-                self.start_add_path();
-            }
-            MenuAction::EditPath => {
-                let has_paths = match self.active_panel {
-                    Panel::Machine => !self.machine_paths.is_empty(),
-                    Panel::User => !self.user_paths.is_empty(),
-                };
-                if has_paths {
-                    self.start_edit_path();
+                if kept_recent > 0 {
+                    kept.push(self.recently_dead_note(kept_recent));
                 }
-            }
-            MenuAction::DeleteMarked => {
-                if self.has_marked_items() {
-                    self.mode = Mode::Confirm(ConfirmAction::DeleteSelected);
+                if kept.is_empty() {
+                    self.set_status(&format!("Deleted {} dead path(s)", deleted));
+                } else {
+                    self.set_status(&format!(
+                        "Deleted {} dead path(s), kept {}",
+                        deleted,
+                        kept.join(" and ")
+                    ));
                 }
             }
-            MenuAction::MarkItem => {
-                self.toggle_mark();
-            }
-            MenuAction::UnmarkAll => {
-                self.unmark_all();
-            }
-            MenuAction::MoveMarked => {
-                self.move_marked_to_other_panel()?;
-            }
-            MenuAction::MoveItemUp => {
-                self.move_item_up();
-            }
-            MenuAction::NormalizeSelected => {
-                self.normalize_selected();
-            }
-            MenuAction::DeleteAllDead => {
-                self.mode = Mode::Confirm(ConfirmAction::DeleteAllDead);
-            }
-            MenuAction::DeleteAllDuplicates => {
-                self.mode = Mode::Confirm(ConfirmAction::DeleteAllDuplicates);
+            ConfirmAction::DeleteAllDuplicates => {
+                self.set_status(&format!("Deleted {} duplicate path(s)", deleted));
             }
-            MenuAction::CreateMarkedDirectories => {
-                if self.has_marked_dead_paths() {
-                    self.mode = Mode::Confirm(ConfirmAction::CreateMarkedDirectories);
-                } else {
-                    self.set_status("No marked dead paths to create");
-                }
+            _ => {
+                self.set_status(&format!("Deleted {} path(s)", deleted));
             }
+        }
+    }
 
-            // Options menu
-            MenuAction::SelectTheme => {
-                self.open_theme_selector()?;
+    fn delete_all_dead(&mut self) -> Result<()> {
+        let plan = self.take_bulk_delete(ConfirmAction::DeleteAllDead);
+        self.run_bulk_delete(plan);
+        Ok(())
+    }
+
+    /// Count missing entries that live on offline mapped drives or ejected removable media
+    pub fn unavailable_count(&self) -> usize {
+        self.machine_info
+            .iter()
+            .chain(self.user_info.iter())
+            .filter(|i| i.is_unavailable())
+            .count()
+    }
+
+    /// "2 dead for less than 7 days", for status messages
+    pub fn recently_dead_note(&self, count: usize) -> String {
+        format!(
+            "{} dead for less than {} day{}",
+            count,
+            self.settings.dead_grace_days,
+            if self.settings.dead_grace_days == 1 {
+                ""
+            } else {
+                "s"
             }
-            MenuAction::ApplyFilter => {
-                self.mode = Mode::FilterMenu;
-                self.filter_menu_selected = 0;
+        )
+    }
+
+    /// Count dead entries kept because they have not been dead for the grace period yet
+    pub fn recently_dead_count(&self) -> usize {
+        self.machine_info
+            .iter()
+            .chain(self.user_info.iter())
+            .filter(|i| i.is_dead() && !self.past_dead_grace(i))
+            .count()
+    }
+
+    /// Whether a dead entry has been missing for `[dead] grace_days`, always true without one
+    /// Entries not seen dead at startup have no date yet and wait like new ones
+    fn past_dead_grace(&self, info: &PathInfo) -> bool {
+        let days = self.settings.dead_grace_days;
+        days == 0
+            || self
+                .settings
+                .dead_since
+                .get(&info.normalized.to_lowercase())
+                .is_some_and(|since| {
+                    (Local::now().date_naive() - *since).num_days() >= i64::from(days)
+                })
+    }
+
+    /// Features that read directories say so instead of running with --no-fs-checks
+    fn refuse_without_fs_checks(&mut self, feature: &str) -> bool {
+        if self.fs.is_checked() {
+            return false;
+        }
+        self.set_status(&format!(
+            "{} reads the disk, which --no-fs-checks turned off",
+            feature
+        ));
+        true
+    }
+
+    /// Remember when each dead local entry was first seen missing, forgetting entries that
+    /// came back or left PATH, and save the dates in config.ini when they changed
+    /// Nothing is known without filesystem checks, so the dates are left alone
+    pub fn track_dead_entries(&mut self) {
+        if self.connection_mode != ConnectionMode::Local || !self.fs.is_checked() {
+            return;
+        }
+        let today = Local::now().date_naive();
+        let dead_since: HashMap<String, chrono::NaiveDate> = self
+            .machine_info
+            .iter()
+            .chain(self.user_info.iter())
+            .filter(|info| info.is_dead())
+            .map(|info| {
+                let key = info.normalized.to_lowercase();
+                let since = self.settings.dead_since.get(&key).copied().unwrap_or(today);
+                (key, since)
+            })
+            .collect();
+        if dead_since == self.settings.dead_since {
+            return;
+        }
+        let mut entries: Vec<(String, String)> = dead_since
+            .iter()
+            .map(|(path, since)| (path.clone(), since.format("%Y-%m-%d").to_string()))
+            .collect();
+        entries.sort();
+        self.settings.dead_since = dead_since;
+        if let Err(e) = config::save_section(&self.settings_file, "dead_since", &entries) {
+            self.set_status(&format!("Dead entry dates not saved: {}", e));
+        }
+    }
+
+    /// Collect (index, path) pairs of dead entries past the grace period
+    /// Entries on offline mapped drives or ejected removable media are not considered dead,
+    /// they are only collected once the dialog includes them with O
+    fn collect_dead_entries(&self, paths: &[String], info: &[PathInfo]) -> Vec<(usize, String)> {
+        paths
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| {
+                info.get(*idx).is_some_and(|i| {
+                    (i.is_dead() && self.past_dead_grace(i))
+                        || (self.delete_unavailable && i.is_unavailable())
+                })
+            })
+            .map(|(idx, path)| (idx, path.clone()))
+            .collect()
+    }
+
+    /// Remove the given (index, path) pairs from a path list
+    fn remove_indices(paths: &mut Vec<String>, removed: &[(usize, String)]) {
+        let indices: HashSet<usize> = removed.iter().map(|(idx, _)| *idx).collect();
+        let mut idx = 0;
+        paths.retain(|_| {
+            let keep = !indices.contains(&idx);
+            idx += 1;
+            keep
+        });
+    }
+
+    fn delete_all_duplicates(&mut self) -> Result<()> {
+        let plan = self.take_bulk_delete(ConfirmAction::DeleteAllDuplicates);
+        self.run_bulk_delete(plan);
+        Ok(())
+    }
+
+    fn normalize_selected(&mut self) {
+        let mut normalized_count = 0;
+        let mut stripped_prefix_count = 0;
+        let mut changes = Vec::new();
+
+        match self.active_panel {
+            Panel::Machine => {
+                for idx in &self.machine_marked {
+                    if let Some(path) = self.machine_paths.get_mut(*idx) {
+                        let normalized = normalize_path_with_fs(path, &*self.fs);
+                        if &normalized != path {
+                            if has_extended_prefix(path) && !has_extended_prefix(&normalized) {
+                                stripped_prefix_count += 1;
+                            }
+                            changes.push((*idx, path.clone(), normalized.clone()));
+                            *path = normalized;
+                            normalized_count += 1;
+                        }
+                    }
+                }
+                self.machine_marked.clear();
+
+                // Clear redo stack and record undo operation for machine panel
+                if !changes.is_empty() {
+                    self.clear_redo_stack();
+                    self.record_undo(Operation::NormalizePaths {
+                        panel: Panel::Machine,
+                        changes,
+                    });
+                }
             }
-            MenuAction::ConnectRemote => {
-                if self.connection_mode == ConnectionMode::Local {
-                    self.mode = Mode::Input(InputMode::ConnectRemote);
-                    self.input_buffer.clear();
+            Panel::User => {
+                for idx in &self.user_marked {
+                    if let Some(path) = self.user_paths.get_mut(*idx) {
+                        let normalized = normalize_path_with_fs(path, &*self.fs);
+                        if &normalized != path {
+                            if has_extended_prefix(path) && !has_extended_prefix(&normalized) {
+                                stripped_prefix_count += 1;
+                            }
+                            changes.push((*idx, path.clone(), normalized.clone()));
+                            *path = normalized;
+                            normalized_count += 1;
+                        }
+                    }
+                }
+                self.user_marked.clear();
+
+                // Clear redo stack and record undo operation for user panel
+                if !changes.is_empty() {
+                    self.clear_redo_stack();
+                    self.record_undo(Operation::NormalizePaths {
+                        panel: Panel::User,
+                        changes,
+                    });
                 }
             }
-            MenuAction::DisconnectRemote => {
-                if self.connection_mode == ConnectionMode::Remote {
-                    self.mode = Mode::Confirm(ConfirmAction::DisconnectRemote);
+        }
+
+        if normalized_count > 0 {
+            self.reanalyze();
+            self.has_changes = true;
+            if stripped_prefix_count > 0 {
+                self.set_status(&format!(
+                    r"Normalized {} path(s) - warning: removed \\?\ prefix from {} (long path access may break)",
+                    normalized_count, stripped_prefix_count
+                ));
+            } else {
+                self.set_status(&format!("Normalized {} path(s)", normalized_count));
+            }
+        }
+    }
+
+    /// Clean up malformed entries in both scopes: drop empty segments and
+    /// strip stray whitespace and unneeded quotes from the rest
+    fn clean_malformed_entries(&mut self) {
+        let mut cleaned_count = 0;
+
+        for panel in [Panel::Machine, Panel::User] {
+            let paths = match panel {
+                Panel::Machine => &mut self.machine_paths,
+                Panel::User => &mut self.user_paths,
+            };
+
+            let mut changes = Vec::new();
+            let mut deleted = Vec::new();
+            for (idx, path) in paths.iter_mut().enumerate() {
+                if !crate::path_analyzer::is_malformed_entry(path) {
+                    continue;
+                }
+                let cleaned = clean_entry(path);
+                if cleaned.is_empty() {
+                    deleted.push((idx, path.clone()));
+                } else if &cleaned != path {
+                    changes.push((idx, path.clone(), cleaned.clone()));
+                    *path = cleaned;
                 }
             }
-            MenuAction::CreateBackup => {
-                self.create_backup()?;
+
+            // Remove empty segments after edits so recorded indices stay valid
+            Self::remove_indices(paths, &deleted);
+
+            cleaned_count += changes.len() + deleted.len();
+
+            // Record edits first, then deletions, so undo restores indices before values
+            if !changes.is_empty() || !deleted.is_empty() {
+                self.clear_redo_stack();
             }
-            MenuAction::RestoreBackup => {
-                self.show_backup_list()?;
+            if !changes.is_empty() {
+                self.undo_stack
+                    .push(Operation::NormalizePaths { panel, changes });
+            }
+            if !deleted.is_empty() {
+                self.undo_stack
+                    .push(Operation::DeletePaths { panel, deleted });
             }
+        }
 
-            // Help menu
-            MenuAction::KeyboardShortcuts => {
-                self.mode = Mode::Help;
+        if cleaned_count > 0 {
+            self.machine_marked.clear();
+            self.user_marked.clear();
+            self.reanalyze();
+            self.has_changes = true;
+            self.set_status(&format!("Cleaned up {} malformed entries", cleaned_count));
+        } else {
+            self.set_status("No malformed entries found");
+        }
+    }
+
+    fn move_marked_to_other_panel(&mut self) -> Result<()> {
+        // In remote mode, copy instead of move (don't delete from source)
+        self.transfer_marked_to_other_panel(self.connection_mode == ConnectionMode::Remote)
+    }
+
+    /// Copy marked entries to the other panel, keeping the originals
+    fn copy_marked_to_other_panel(&mut self) -> Result<()> {
+        self.transfer_marked_to_other_panel(true)
+    }
+
+    fn transfer_marked_to_other_panel(&mut self, is_copy_mode: bool) -> Result<()> {
+        let from_panel = self.active_panel;
+        let to_panel = from_panel.toggle();
+
+        let (from_paths, to_paths, from_marked) = match from_panel {
+            Panel::Machine => (
+                &mut self.machine_paths,
+                &mut self.user_paths,
+                &mut self.machine_marked,
+            ),
+            Panel::User => (
+                &mut self.user_paths,
+                &mut self.machine_paths,
+                &mut self.user_marked,
+            ),
+        };
+
+        if from_marked.is_empty() {
+            return Ok(());
+        }
+
+        // Record for undo: paths with their original indices
+        let mut paths_with_indices = Vec::new();
+        let mut indices: Vec<_> = from_marked.iter().copied().collect();
+        indices.sort_unstable(); // Sort in ascending order for recording
+
+        for idx in &indices {
+            if let Some(path) = from_paths.get(*idx) {
+                paths_with_indices.push((*idx, path.clone()));
             }
-            MenuAction::About => {
-                self.mode = Mode::About;
+        }
+
+        let mut moved = Vec::new();
+        indices.sort_unstable_by(|a, b| b.cmp(a)); // Reverse order to maintain indices during removal
+
+        for idx in indices {
+            if let Some(path) = from_paths.get(idx) {
+                moved.push(path.clone());
+            }
+        }
+
+        // In copy mode (remote), don't remove from source
+        if !is_copy_mode {
+            // Remove from source (in reverse order)
+            let mut new_from = Vec::new();
+            for (idx, path) in from_paths.iter().enumerate() {
+                if !from_marked.contains(&idx) {
+                    new_from.push(path.clone());
+                }
+            }
+            *from_paths = new_from;
+        }
+
+        // Add to destination
+        to_paths.extend(moved.iter().cloned());
+
+        let count = moved.len();
+        from_marked.clear();
+
+        // Clear redo stack and record undo operation
+        if !paths_with_indices.is_empty() {
+            self.clear_redo_stack();
+            if is_copy_mode {
+                self.record_undo(Operation::CopyPaths {
+                    from_panel,
+                    to_panel,
+                    paths_with_indices,
+                });
+            } else {
+                self.record_undo(Operation::MovePaths {
+                    from_panel,
+                    to_panel,
+                    paths_with_indices,
+                });
             }
         }
 
+        self.reanalyze();
+        self.has_changes = true;
+
+        let action_verb = if is_copy_mode { "Copied" } else { "Moved" };
+        let target = if self.connection_mode == ConnectionMode::Remote {
+            if to_panel == Panel::User {
+                self.remote_connection
+                    .as_ref()
+                    .map(|c| format!("remote ({})", c.computer_name()))
+                    .unwrap_or_else(|| "remote".to_string())
+            } else {
+                "local".to_string()
+            }
+        } else {
+            to_panel.scope().as_str().to_string()
+        };
+
+        self.set_status(&format!("{} {} path(s) to {}", action_verb, count, target));
         Ok(())
     }
-}
-
-pub struct Statistics {
-    pub machine_total: usize,
-    pub user_total: usize,
-    pub machine_dead: usize,
-    pub user_dead: usize,
-    pub machine_duplicates: usize,
-    pub user_duplicates: usize,
-    pub machine_non_normalized: usize,
-    pub user_non_normalized: usize,
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    fn move_item_up(&mut self) {
+        match self.active_panel {
+            Panel::Machine => {
+                if self.machine_selected > 0 {
+                    let idx1 = self.machine_selected;
+                    let idx2 = self.machine_selected - 1;
 
-    // Helper function to create a test App without registry access
-    fn create_test_app(machine_paths: Vec<String>, user_paths: Vec<String>) -> App {
-        let machine_info = analyze_paths(&machine_paths, &user_paths);
-        let user_info = analyze_paths(&user_paths, &machine_paths);
+                    self.machine_paths.swap(idx1, idx2);
+                    self.machine_selected -= 1;
 
-        App {
-            connection_mode: ConnectionMode::Local,
-            remote_connection: None,
+                    // Clear redo stack and record undo operation
+                    self.clear_redo_stack();
+                    self.record_undo(Operation::SwapPaths {
+                        panel: Panel::Machine,
+                        index1: idx1,
+                        index2: idx2,
+                    });
+
+                    self.has_changes = true;
+                    self.reanalyze();
+                }
+            }
+            Panel::User => {
+                if self.user_selected > 0 {
+                    let idx1 = self.user_selected;
+                    let idx2 = self.user_selected - 1;
+
+                    self.user_paths.swap(idx1, idx2);
+                    self.user_selected -= 1;
+
+                    // Clear redo stack and record undo operation
+                    self.clear_redo_stack();
+                    self.record_undo(Operation::SwapPaths {
+                        panel: Panel::User,
+                        index1: idx1,
+                        index2: idx2,
+                    });
+
+                    self.has_changes = true;
+                    self.reanalyze();
+                }
+            }
+        }
+    }
+
+    fn start_add_path(&mut self) {
+        // Remote panels are different hosts, not scopes
+        self.add_target = match self.connection_mode {
+            ConnectionMode::Local => self.settings.add_scope.map(Panel::from_scope),
+            ConnectionMode::Remote => None,
+        };
+        // Open file browser instead of text input
+        self.mode = Mode::FileBrowser;
+        self.file_browser_selected = 0;
+        // Start from current directory or C:\ if current dir unavailable
+        self.file_browser_current_path =
+            std::env::current_dir().unwrap_or_else(|_| PathBuf::from("C:\\"));
+        self.read_current_directory();
+    }
+
+    /// Panel the Add Path dialog adds to
+    pub fn add_panel(&self) -> Panel {
+        self.add_target.unwrap_or(self.active_panel)
+    }
+
+    /// Focus and add to the given scope, as chosen with --scope
+    pub fn use_scope(&mut self, scope: PathScope) {
+        self.active_panel = Panel::from_scope(scope);
+        self.settings.start_panel = Some(scope);
+        self.settings.add_scope = Some(scope);
+    }
+
+    /// Open the workflow a startup flag asked for
+    pub fn start_flow(&mut self, flow: StartupFlow) {
+        if !self.profile_allows(Feature::Edit) {
+            return;
+        }
+        let action = match flow {
+            StartupFlow::Add(dir) => {
+                self.add_target = self.settings.add_scope.map(Panel::from_scope);
+                // cmd.exe hands a quoted directory ending in \ over with a stray quote
+                self.input_buffer = dir.trim().trim_matches('"').to_string();
+                self.mode = Mode::Input(InputMode::AddPath);
+                self.mode_enter_time = std::time::Instant::now();
+                return;
+            }
+            StartupFlow::PruneDead => ConfirmAction::DeleteAllDead,
+            StartupFlow::Dedupe => ConfirmAction::DeleteAllDuplicates,
+        };
+        let plan = self.plan_bulk_delete(action);
+        self.machine_marked = plan.machine.iter().map(|(idx, _)| *idx).collect();
+        self.user_marked = plan.user.iter().map(|(idx, _)| *idx).collect();
+        self.confirm_bulk_delete(action);
+    }
+
+    /// Expand per-user variables for another profile, as given by `--profile-root`
+    pub fn use_profile_root(&mut self, profile_root: &str) {
+        self.settings.profile_root = Some(profile_root.to_string());
+        self.reanalyze();
+    }
+
+    /// Get available drive letters on Windows
+    fn get_available_drives() -> Vec<DirectoryEntry> {
+        let mut drives = Vec::new();
+
+        // Add network drives option
+        drives.push(DirectoryEntry {
+            name: "Network...".to_string(),
+            path: PathBuf::from("NETWORK"),
+            is_parent: false,
+            is_drive: true,
+        });
+
+        // Check drives A-Z
+        for letter in b'A'..=b'Z' {
+            let drive_letter = letter as char;
+            let drive_path = PathBuf::from(format!("{}:\\", drive_letter));
+
+            // Check if the drive exists
+            if drive_path.exists() {
+                drives.push(DirectoryEntry {
+                    name: format!("{}:", drive_letter),
+                    path: drive_path,
+                    is_parent: false,
+                    is_drive: true,
+                });
+            }
+        }
+
+        drives
+    }
+
+    /// Check if a path is a drive root (e.g., C:\, D:\)
+    fn is_drive_root(path: &std::path::Path) -> bool {
+        let path_str = path.to_string_lossy();
+        // Match patterns like "C:\", "D:\", etc.
+        path_str.len() == 3
+            && path_str.chars().nth(1) == Some(':')
+            && path_str.chars().nth(2) == Some('\\')
+    }
+
+    /// Read and populate entries for the current directory in file browser
+    fn read_current_directory(&mut self) {
+        self.file_browser_entries.clear();
+
+        // Check if we're at a drive root or in "drives view"
+        let path_str = self.file_browser_current_path.to_string_lossy().to_string();
+
+        if path_str == "DRIVES" {
+            // Show all available drives
+            self.file_browser_entries = Self::get_available_drives();
+        } else {
+            // Add parent directory entry
+            if Self::is_drive_root(&self.file_browser_current_path) {
+                // At drive root, parent goes to drives list
+                self.file_browser_entries.push(DirectoryEntry {
+                    name: "..".to_string(),
+                    path: PathBuf::from("DRIVES"),
+                    is_parent: true,
+                    is_drive: false,
+                });
+            } else if self.file_browser_current_path.parent().is_some() {
+                // Normal parent directory
+                self.file_browser_entries.push(DirectoryEntry {
+                    name: "..".to_string(),
+                    path: self
+                        .file_browser_current_path
+                        .parent()
+                        .unwrap()
+                        .to_path_buf(),
+                    is_parent: true,
+                    is_drive: false,
+                });
+            }
+
+            // Read directory entries (only if not in drives view)
+            if let Ok(entries) = std::fs::read_dir(&self.file_browser_current_path) {
+                let mut dirs: Vec<DirectoryEntry> = entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| {
+                        // Only include directories
+                        entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false)
+                    })
+                    .filter_map(|entry| {
+                        let path = entry.path();
+                        let name = path.file_name()?.to_string_lossy().to_string();
+                        Some(DirectoryEntry {
+                            name,
+                            path,
+                            is_parent: false,
+                            is_drive: false,
+                        })
+                    })
+                    .collect();
+
+                // Sort directories alphabetically (case-insensitive)
+                dirs.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+
+                self.file_browser_entries.extend(dirs);
+            }
+        }
+
+        // Ensure selection is valid
+        if self.file_browser_selected >= self.file_browser_entries.len()
+            && !self.file_browser_entries.is_empty()
+        {
+            self.file_browser_selected = self.file_browser_entries.len() - 1;
+        }
+
+        // Update scrollbar state
+        self.file_browser_scrollbar_state = ScrollbarState::new(self.file_browser_entries.len())
+            .position(self.file_browser_selected);
+    }
+
+    /// Navigate to the selected directory in file browser
+    fn navigate_to_selected_directory(&mut self) {
+        if self.file_browser_entries.is_empty() {
+            return;
+        }
+
+        if let Some(entry) = self.file_browser_entries.get(self.file_browser_selected) {
+            // Check if user selected the network option
+            if entry.path.to_string_lossy() == "NETWORK" {
+                // Switch to manual input mode for network path
+                self.mode = Mode::Input(InputMode::AddPath);
+                self.mode_enter_time = std::time::Instant::now();
+                self.input_buffer = "\\\\".to_string(); // Start with UNC prefix
+                return;
+            }
+
+            self.file_browser_current_path = entry.path.clone();
+            self.file_browser_selected = 0; // Reset selection when entering new directory
+            self.read_current_directory();
+        }
+    }
+
+    /// Select the current directory in file browser and add it to PATH
+    fn select_current_directory_for_path(&mut self) -> Result<()> {
+        // Don't allow adding the "DRIVES" pseudo-directory
+        let path_str = self.file_browser_current_path.to_string_lossy().to_string();
+        if path_str == "DRIVES" {
+            self.set_status("Cannot add drives list to PATH. Navigate to a directory first.");
+            return Ok(());
+        }
+
+        // Set input buffer and add path
+        self.input_buffer = path_str;
+        self.mode = Mode::Normal;
+        self.add_path_from_input()?;
+        self.input_buffer.clear();
+
+        Ok(())
+    }
+
+    fn start_edit_path(&mut self) {
+        let current_path = match self.active_panel {
+            Panel::Machine => self.machine_paths.get(self.machine_selected),
+            Panel::User => self.user_paths.get(self.user_selected),
+        };
+
+        if let Some(path) = current_path {
+            self.input_buffer = path.clone();
+            self.mode = Mode::Input(InputMode::EditPath);
+            self.mode_enter_time = std::time::Instant::now();
+        }
+    }
+
+    fn add_path_from_input(&mut self) -> Result<()> {
+        if self.input_buffer.is_empty() {
+            return Ok(());
+        }
+
+        // Check if directory exists
+        let normalized = normalize_path_with_fs(&self.input_buffer, &*self.fs);
+        if !path_exists_with_fs(&normalized, None, &*self.fs) {
+            // Directory doesn't exist - check if we can create it
+            if Self::can_create_directory(&self.input_buffer) {
+                // Store the path and ask for confirmation
+                self.pending_directory = self.input_buffer.clone();
+                self.mode = Mode::Confirm(ConfirmAction::CreateSingleDirectory);
+                return Ok(());
+            } else {
+                // Can't create (network path, invalid chars, etc.) - add anyway as dead path
+                self.set_status("Warning: Path cannot be auto-created (network or invalid). Adding as dead path.");
+            }
+        }
+
+        // Nothing to run in it, but in a subdirectory such as bin
+        if let Some(suggested) = self.suggest_subdirectory(&self.input_buffer) {
+            self.pending_subdirectory = Some((self.input_buffer.clone(), suggested));
+            self.mode = Mode::Confirm(ConfirmAction::UseSubdirectory);
+            return Ok(());
+        }
+
+        // Directory exists or can't be created - add it
+        self.add_entry(self.input_buffer.clone());
+        Ok(())
+    }
+
+    /// For an existing local directory without commands, the entry of its nearest
+    /// subdirectory that has some, written the way the directory was typed
+    fn suggest_subdirectory(&self, typed: &str) -> Option<String> {
+        let typed = typed.trim().trim_matches('"');
+        if !self.fs.is_checked() || typed.starts_with("\\\\") || typed.starts_with("//") {
+            return None;
+        }
+        let expanded = crate::path_analyzer::expand_environment_variables(&normalize_path_with_fs(
+            typed, &*self.fs,
+        ));
+        let dir = Path::new(&expanded);
+        let extensions = crate::command_lookup::extensions();
+        if !dir.is_dir() || crate::command_lookup::has_commands(dir, &extensions) {
+            return None;
+        }
+        let found = crate::command_lookup::suggest_command_dir(dir, &extensions)?;
+        let relative = found.strip_prefix(dir).ok()?;
+        Some(format!(
+            "{}{}{}",
+            typed.trim_end_matches(['\\', '/']),
+            std::path::MAIN_SEPARATOR,
+            relative.to_string_lossy()
+        ))
+    }
+
+    /// Answer to the subdirectory suggestion: add the subdirectory, or the entry as typed
+    fn add_pending_subdirectory(&mut self, use_subdirectory: bool) {
+        if let Some((typed, suggested)) = self.pending_subdirectory.take() {
+            self.add_entry(if use_subdirectory { suggested } else { typed });
+        }
+    }
+
+    /// Add an entry where the Add Path dialog puts it, as one undo step
+    fn add_entry(&mut self, new_path: String) {
+        let panel = self.add_panel();
+        // Show the panel the entry went to
+        self.active_panel = panel;
+        let index = match panel {
+            Panel::Machine => insert_at(
+                &mut self.machine_paths,
+                &mut self.machine_selected,
+                &mut self.machine_marked,
+                self.add_position,
+                new_path.clone(),
+            ),
+            Panel::User => insert_at(
+                &mut self.user_paths,
+                &mut self.user_selected,
+                &mut self.user_marked,
+                self.add_position,
+                new_path.clone(),
+            ),
+        };
+
+        // Clear redo stack and record undo operation
+        self.clear_redo_stack();
+        self.record_undo(Operation::AddPath {
+            panel,
+            index,
+            path: new_path,
+        });
+
+        self.reanalyze();
+        self.has_changes = true;
+        self.set_status("Path added");
+    }
+
+    /// An existing entry the Add or Edit Path input would duplicate, the target panel first
+    /// Compared as text, so typing never waits on a slow drive
+    pub fn input_conflict(&self) -> Option<(Panel, usize)> {
+        let (target, editing) = match self.mode {
+            Mode::Input(InputMode::AddPath) => (self.add_panel(), None),
+            Mode::Input(InputMode::EditPath) => {
+                let panel = self.active_panel;
+                (panel, Some((panel, self.panel_entries(panel).2)))
+            }
+            _ => return None,
+        };
+        if self.input_buffer.trim().is_empty() {
+            return None;
+        }
+        let key = normalize_path_with_fs(&self.input_buffer, &UncheckedFileSystem).to_lowercase();
+        // The remote panel belongs to another computer, a copy there is no duplicate
+        let mut panels = vec![target];
+        if self.connection_mode == ConnectionMode::Local {
+            panels.push(target.toggle());
+        }
+        panels.into_iter().find_map(|panel| {
+            let (_, infos, _) = self.panel_entries(panel);
+            infos
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| editing != Some((panel, *index)))
+                .find(|(_, info)| info.normalized.to_lowercase() == key)
+                .map(|(index, _)| (panel, index))
+        })
+    }
+
+    /// Ctrl+G in Add or Edit Path: drop the input and select the entry it duplicates
+    fn go_to_input_conflict(&mut self) {
+        let Some((panel, index)) = self.input_conflict() else {
+            return;
+        };
+        self.mode = Mode::Normal;
+        self.mode_enter_time = std::time::Instant::now();
+        self.input_buffer.clear();
+        self.go_to_entry(panel, index);
+        self.set_status("Went to the existing entry, nothing was changed");
+    }
+
+    fn update_path_from_input(&mut self) -> Result<()> {
+        if self.input_buffer.is_empty() {
+            return Ok(());
+        }
+
+        let new_path = self.input_buffer.clone();
+
+        match self.active_panel {
+            Panel::Machine => {
+                if let Some(path) = self.machine_paths.get_mut(self.machine_selected) {
+                    let old_path = path.clone();
+                    *path = new_path.clone();
+
+                    // Clear redo stack and record undo operation
+                    self.clear_redo_stack();
+                    self.record_undo(Operation::EditPath {
+                        panel: Panel::Machine,
+                        index: self.machine_selected,
+                        old_path,
+                        new_path,
+                    });
+                }
+            }
+            Panel::User => {
+                if let Some(path) = self.user_paths.get_mut(self.user_selected) {
+                    let old_path = path.clone();
+                    *path = new_path.clone();
+
+                    // Clear redo stack and record undo operation
+                    self.clear_redo_stack();
+                    self.record_undo(Operation::EditPath {
+                        panel: Panel::User,
+                        index: self.user_selected,
+                        old_path,
+                        new_path,
+                    });
+                }
+            }
+        }
+
+        self.reanalyze();
+        self.has_changes = true;
+        self.set_status("Path updated");
+        Ok(())
+    }
+
+    // Backup/Restore
+    /// Ctrl+B, unless the launch profile leaves backups out
+    fn create_manual_backup(&mut self) -> Result<()> {
+        if self.profile_allows(Feature::Backup) {
+            self.create_backup()?;
+        }
+        Ok(())
+    }
+
+    /// Save the PATH as last loaded or applied, returns the file written
+    fn create_backup(&mut self) -> Result<PathBuf> {
+        let user_path = registry::join_paths(&self.user_original);
+        let machine_path = registry::join_paths(&self.machine_original);
+
+        let backup = PathBackup::new(
+            user_path,
+            machine_path,
+            self.user_original.clone(),
+            self.machine_original.clone(),
+        );
+
+        let filepath = backup.save(&self.backup_dir)?;
+
+        self.set_status(&format!("Backup saved: {}", filepath.display()));
+        Ok(filepath)
+    }
+
+    /// Offer to go back to the PATH as it was before the last apply, read from the
+    /// backup that apply saved
+    fn start_revert_last_apply(&mut self) {
+        if self.connection_mode == ConnectionMode::Remote {
+            self.set_status("Revert Last Apply works in local mode only");
+            return;
+        }
+        let Some(last) = self.last_apply.clone() else {
+            self.set_status("Nothing has been applied in this session");
+            return;
+        };
+        match PathBackup::load(&last.backup) {
+            Ok(mut backup) => {
+                // A scope the apply left out keeps its edits, pending or not
+                if !last.user {
+                    backup.user_paths = self.user_paths.clone();
+                }
+                if !last.machine {
+                    backup.machine_paths = self.machine_paths.clone();
+                }
+                if backup.user_paths == self.user_paths
+                    && backup.machine_paths == self.machine_paths
+                {
+                    self.set_status("The panels already hold the PATH from before the last apply");
+                    return;
+                }
+                self.pending_revert = Some(backup);
+                self.mode = Mode::Confirm(ConfirmAction::RevertLastApply);
+            }
+            Err(e) => self.set_status(&format!(
+                "Backup of the last apply could not be read: {}",
+                e
+            )),
+        }
+    }
+
+    /// Put the PATH from before the last apply in the panels it wrote as one undoable edit,
+    /// then open the apply dialog if it should be written back to the registry
+    fn revert_last_apply(&mut self, write: bool) {
+        let Some(backup) = self.pending_revert.take() else {
+            return;
+        };
+        let machine_before = self.machine_paths.clone();
+        self.clear_redo_stack();
+        let operation = Operation::ReplacePanels {
+            machine_before: std::mem::replace(
+                &mut self.machine_paths,
+                backup.machine_paths.clone(),
+            ),
+            user_before: std::mem::replace(&mut self.user_paths, backup.user_paths.clone()),
+            machine_after: backup.machine_paths,
+            user_after: backup.user_paths,
+        };
+        self.record_undo(operation);
+        // Marks refer to positions that just changed
+        self.machine_marked.clear();
+        self.user_marked.clear();
+        self.has_changes = true;
+        self.reanalyze();
+
+        self.set_status("Restored the PATH from before the last apply (Ctrl+Z to undo)");
+        self.note_staged_machine_edit(&machine_before);
+        if write {
+            self.request_apply();
+        }
+    }
+
+    /// Open the list of entries analysis never flags
+    fn start_ignore_list(&mut self) {
+        self.ignore_selected = 0;
+        self.mode = Mode::IgnoreList;
+    }
+
+    /// Ask for an entry or prefix to ignore, the selected entry prefilled
+    fn start_ignore_rule(&mut self) {
+        self.input_buffer = self
+            .selected_path_info()
+            .map(|(path, _)| path.clone())
+            .unwrap_or_default();
+        self.mode = Mode::Input(InputMode::IgnoreRule);
+        self.mode_enter_time = std::time::Instant::now();
+    }
+
+    /// Put an entry or prefix on the ignore list and analyze again
+    pub fn add_ignore_rule(&mut self, rule: &str) {
+        self.mode = Mode::IgnoreList;
+        if rule.is_empty() || rule.contains(';') {
+            self.set_status("Type one entry, or a prefix ending in *");
+            return;
+        }
+        if let Some(idx) = self
+            .settings
+            .ignored
+            .iter()
+            .position(|existing| existing.eq_ignore_ascii_case(rule))
+        {
+            self.ignore_selected = idx;
+            self.set_status(&format!("{} is already on the ignore list", rule));
+            return;
+        }
+        self.settings.ignored.push(rule.to_string());
+        self.ignore_selected = self.settings.ignored.len() - 1;
+        self.save_ignore_list(&format!(
+            "Ignoring {}, it is no longer reported as dead or duplicate",
+            rule
+        ));
+    }
+
+    /// Take a rule off the ignore list and analyze again
+    pub fn remove_ignore_rule(&mut self, idx: usize) {
+        if idx >= self.settings.ignored.len() {
+            return;
+        }
+        let rule = self.settings.ignored.remove(idx);
+        self.ignore_selected = idx.min(self.settings.ignored.len().saturating_sub(1));
+        self.save_ignore_list(&format!("No longer ignoring {}", rule));
+    }
+
+    /// Write the ignore list to config.ini and re-check every entry against it
+    fn save_ignore_list(&mut self, done: &str) {
+        self.reanalyze();
+        match config::save_setting(
+            &self.settings_file,
+            "ignore",
+            "entries",
+            &self.settings.ignored.join("; "),
+        ) {
+            Ok(()) => self.set_status(done),
+            Err(e) => self.set_status(&format!("{} (not saved: {})", done, e)),
+        }
+    }
+
+    /// Ask for the folder a change summary goes to, the one used last time preselected
+    fn start_change_report(&mut self) {
+        if self.connection_mode == ConnectionMode::Remote {
+            self.set_status("Change summaries cover this computer's PATH only");
+            return;
+        }
+        if let Err(e) = self.change_report() {
+            self.set_status(&e.to_string());
+            return;
+        }
+        self.input_buffer = match &self.settings.report_folder {
+            Some(folder) => folder.clone(),
+            None => config::get_reports_dir()
+                .map(|dir| dir.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        };
+        self.mode = Mode::Input(InputMode::ChangeReport);
+        self.mode_enter_time = std::time::Instant::now();
+    }
+
+    /// The edits not applied yet, or if there are none the last apply of this session,
+    /// with what the analysis still finds wrong in the result
+    pub fn change_report(&self) -> Result<ChangeReport> {
+        let (machine_before, user_before, state) =
+            if self.scope_changed(PathScope::Machine) || self.scope_changed(PathScope::User) {
+                (
+                    self.machine_original.clone(),
+                    self.user_original.clone(),
+                    "Planned, not applied yet".to_string(),
+                )
+            } else if let Some(last) = &self.last_apply {
+                let backup = PathBackup::load(&last.backup)?;
+                let state = format!("Applied, backup taken {}", backup.display_time());
+                (backup.machine_paths, backup.user_paths, state)
+            } else {
+                anyhow::bail!("No changes to report, edit or apply something first");
+            };
+
+        let findings = [
+            (PathScope::Machine, &self.machine_info),
+            (PathScope::User, &self.user_info),
+        ]
+        .into_iter()
+        .flat_map(|(scope, infos)| {
+            infos
+                .iter()
+                .filter(|info| info.status != PathStatus::Valid)
+                .map(move |info| Finding {
+                    scope,
+                    entry: info.original.clone(),
+                    problem: info.status.description().to_string(),
+                })
+        })
+        .collect();
+
+        Ok(ChangeReport {
+            computer: history::local_computer_name(),
+            operator: change_report::operator_name(),
+            generated: Local::now(),
+            state,
+            scopes: vec![
+                ScopeDiff {
+                    scope: PathScope::Machine,
+                    before: machine_before,
+                    after: self.machine_paths.clone(),
+                },
+                ScopeDiff {
+                    scope: PathScope::User,
+                    before: user_before,
+                    after: self.user_paths.clone(),
+                },
+            ],
+            findings,
+            warnings: self.check_path_sizes().warnings,
+        })
+    }
+
+    /// Save the change summary in the folder, remembering folder and format for next time
+    fn write_change_report(&mut self, folder: &Path) {
+        let format = self.report_format;
+        let written = self.change_report().and_then(|report| {
+            std::fs::create_dir_all(folder)
+                .with_context(|| format!("Failed to create {}", folder.display()))?;
+            let file = folder.join(report.file_name(format));
+            std::fs::write(&file, report.render(format))
+                .with_context(|| format!("Failed to write {}", file.display()))?;
+            Ok(file)
+        });
+        match written {
+            Ok(file) => {
+                let folder = folder.to_string_lossy().to_string();
+                // Only a convenience, the report is written either way
+                let _ = config::save_setting(&self.settings_file, "reports", "folder", &folder);
+                let _ =
+                    config::save_setting(&self.settings_file, "reports", "format", format.as_str());
+                self.settings.report_folder = Some(folder);
+                self.settings.report_format = format;
+                self.set_status(&format!("Change summary written to {}", file.display()));
+            }
+            Err(e) => self.set_status(&format!("Could not write the change summary: {}", e)),
+        }
+    }
+
+    fn show_backup_list(&mut self) -> Result<()> {
+        self.backup_list = backup::list_backups(&self.backup_dir)?;
+        self.backup_selected = 0;
+
+        if self.backup_list.is_empty() {
+            self.set_status("No backups found");
+        } else {
+            self.mode = Mode::BackupList;
+        }
+        Ok(())
+    }
+
+    fn restore_selected_backup(&mut self) -> Result<()> {
+        if self.backup_selected < self.backup_list.len() {
+            let backup_path = &self.backup_list[self.backup_selected];
+            let backup = PathBackup::load(backup_path)?;
+
+            // Backups shared between machines may use ${NAME} placeholders
+            let variables = Variables::load(&self.variables_file)?;
+            let entries: Vec<String> = backup
+                .user_paths
+                .iter()
+                .chain(&backup.machine_paths)
+                .cloned()
+                .collect();
+            let mut resolved = match template::resolve_all(&entries, &variables) {
+                Ok(resolved) => resolved,
+                Err(e) => {
+                    self.set_status(&format!(
+                        "Backup not restored: {} (values are read from {})",
+                        e,
+                        self.variables_file.display()
+                    ));
+                    return Ok(());
+                }
+            };
+
+            self.machine_paths = resolved.split_off(backup.user_paths.len());
+            self.user_paths = resolved;
+
+            self.reanalyze();
+            self.has_changes = true;
+            self.set_status("Backup restored (not yet applied)");
+        }
+        Ok(())
+    }
+
+    /// Whether a scope has edits that have not been applied yet (Local mode)
+    fn scope_changed(&self, scope: PathScope) -> bool {
+        match scope {
+            PathScope::User => self.user_paths != self.user_original,
+            PathScope::Machine => self.machine_paths != self.machine_original,
+        }
+    }
+
+    /// Unelevated local sessions write MACHINE through a helper if configured
+    pub fn uses_machine_helper(&self) -> bool {
+        !self.is_admin
+            && self.settings.elevated_helper
+            && self.connection_mode == ConnectionMode::Local
+    }
+
+    /// MACHINE edits that are pending but cannot be applied without elevation
+    pub fn machine_staged(&self) -> bool {
+        !self.is_admin && self.scope_changed(PathScope::Machine)
+    }
+
+    /// Tell the user an edit only staged a MACHINE change, offering elevation once if configured
+    fn note_staged_machine_edit(&mut self, machine_before: &[String]) {
+        if self.is_admin || self.machine_paths == machine_before {
+            return;
+        }
+        if !self.scope_changed(PathScope::Machine) {
+            self.set_status("MACHINE is back to its saved state");
+            return;
+        }
+        if self.settings.auto_elevate && !self.elevation_offered && self.mode == Mode::Normal {
+            self.elevation_offered = true;
+            self.mode = Mode::Confirm(ConfirmAction::RequestElevation);
+        }
+        // Keep what the edit itself reported
+        let notice = if self.uses_machine_helper() {
+            "MACHINE change staged, Ctrl+S writes it after a UAC prompt"
+        } else {
+            "MACHINE change staged, applying it needs administrator rights (Ctrl+E)"
+        };
+        if self.status_message.is_empty() || self.status_message.contains(notice) {
+            self.set_status(notice);
+        } else {
+            self.set_status(&format!("{} - {}", self.status_message, notice));
+        }
+    }
+
+    /// Whether the launch profile has a feature, telling the user if it does not
+    fn profile_allows(&mut self, feature: Feature) -> bool {
+        if self.profile.allows(feature) {
+            return true;
+        }
+        self.set_status(&self.profile.denied(feature));
+        false
+    }
+
+    /// Panels before a key or click, kept only when the profile is read-only
+    fn read_only_snapshot(&self) -> Option<PanelSnapshot> {
+        self.profile.is_read_only().then(|| PanelSnapshot {
+            machine: self.machine_paths.clone(),
+            user: self.user_paths.clone(),
+            remote_machine: self.remote_machine_paths.clone(),
+            undo_len: self.undo_stack.len(),
+            has_changes: self.has_changes,
+        })
+    }
+
+    /// Close a dialog the profile does not have, and take back any edit a read-only
+    /// profile let through
+    fn enforce_profile(&mut self, before: Option<PanelSnapshot>) {
+        if let Some(feature) = self.mode.feature() {
+            if !self.profile.allows(feature) {
+                self.mode = Mode::Normal;
+                self.input_buffer.clear();
+                self.set_status(&self.profile.denied(feature));
+            }
+        }
+        let Some(before) = before else {
+            return;
+        };
+        if before.machine == self.machine_paths
+            && before.user == self.user_paths
+            && before.remote_machine == self.remote_machine_paths
+        {
+            return;
+        }
+        self.machine_paths = before.machine;
+        self.user_paths = before.user;
+        self.remote_machine_paths = before.remote_machine;
+        self.undo_stack.truncate(before.undo_len);
+        self.has_changes = before.has_changes;
+        self.reanalyze();
+        self.set_status(&self.profile.denied(Feature::Edit));
+    }
+
+    /// Whether the connected remote host has edits that have not been applied yet
+    pub fn remote_changed(&self) -> bool {
+        self.remote_machine_paths != self.remote_machine_original
+    }
+
+    /// Whether any host or scope still has unapplied edits
+    fn any_pending_changes(&self) -> bool {
+        match self.connection_mode {
+            ConnectionMode::Local => {
+                self.scope_changed(PathScope::User) || self.scope_changed(PathScope::Machine)
+            }
+            ConnectionMode::Remote => {
+                self.scope_changed(PathScope::Machine) || self.remote_changed()
+            }
+        }
+    }
+
+    /// Checkboxes of the apply dialog: USER and MACHINE locally, one per host in remote mode
+    pub fn apply_choices(&self) -> Vec<ApplyChoice> {
+        match self.connection_mode {
+            ConnectionMode::Local => vec![
+                ApplyChoice {
+                    key: 'U',
+                    label: "USER".to_string(),
+                    checked: self.apply_user,
+                    changed: self.scope_changed(PathScope::User),
+                    writable: true,
+                },
+                ApplyChoice {
+                    key: 'M',
+                    label: if self.uses_machine_helper() {
+                        "MACHINE (UAC prompt)".to_string()
+                    } else {
+                        "MACHINE".to_string()
+                    },
+                    checked: self.apply_machine,
+                    changed: self.scope_changed(PathScope::Machine),
+                    writable: self.is_admin || self.uses_machine_helper(),
+                },
+            ],
+            ConnectionMode::Remote => {
+                let host = self
+                    .remote_connection
+                    .as_ref()
+                    .map(|c| c.computer_name().to_string())
+                    .unwrap_or_default();
+                vec![
+                    ApplyChoice {
+                        key: 'L',
+                        label: "LOCAL MACHINE".to_string(),
+                        checked: self.apply_machine,
+                        changed: self.scope_changed(PathScope::Machine),
+                        writable: self.is_admin,
+                    },
+                    ApplyChoice {
+                        key: 'R',
+                        label: format!("REMOTE MACHINE ({})", host),
+                        checked: self.apply_remote,
+                        changed: self.remote_changed(),
+                        writable: self.is_admin,
+                    },
+                ]
+            }
+        }
+    }
+
+    /// Pending edits that the machine policy does not allow, checked per computer
+    pub fn policy_violations(&self) -> Vec<String> {
+        if self.policy.is_empty() {
+            return Vec::new();
+        }
+
+        // Moving an entry between USER and MACHINE does not remove it
+        let local_before: Vec<&String> = self
+            .machine_original
+            .iter()
+            .chain(&self.user_original)
+            .collect();
+        let local_after: Vec<&String> = self.machine_paths.iter().chain(&self.user_paths).collect();
+        let mut violations = self.policy.violations(&local_before, &local_after);
+
+        if self.connection_mode == ConnectionMode::Remote {
+            let remote_before: Vec<&String> = self.remote_machine_original.iter().collect();
+            let remote_after: Vec<&String> = self.remote_machine_paths.iter().collect();
+            violations.extend(self.policy.violations(&remote_before, &remote_after));
+        }
+        violations
+    }
+
+    /// Open the apply dialog, or the elevation prompt when only elevated changes are pending
+    fn request_apply(&mut self) {
+        if !self.has_changes {
+            self.set_status("No changes to save");
+            return;
+        }
+
+        let violations = self.policy_violations();
+        if !violations.is_empty() {
+            self.set_status(&format!("Blocked by policy: {}", violations.join("; ")));
+            return;
+        }
+
+        // Long values get truncated by other tools later, say so before writing them
+        if !self.size_acknowledged {
+            let advisory = self.check_path_sizes();
+            if !advisory.is_empty() {
+                self.size_advisory = advisory;
+                self.mode = Mode::Confirm(ConfirmAction::SizeAdvisory);
+                return;
+            }
+        }
+
+        // Check if we need elevation for MACHINE path changes
+        let needs_elevation = crate::elevation::needs_elevation_for_changes(
+            self.is_admin,
+            &self.machine_paths,
+            &self.machine_original,
+            &self.remote_machine_paths,
+            &self.remote_machine_original,
+            self.connection_mode,
+        );
+
+        match self.connection_mode {
+            ConnectionMode::Local => {
+                let user_changed = self.scope_changed(PathScope::User);
+                let helper = self.uses_machine_helper();
+                if needs_elevation && !user_changed && !helper {
+                    self.mode = Mode::Confirm(ConfirmAction::RequestElevation);
+                    return;
+                }
+
+                // USER edits can be applied on their own while MACHINE waits for elevation
+                self.apply_user = user_changed;
+                self.apply_machine =
+                    self.scope_changed(PathScope::Machine) && (self.is_admin || helper);
+                self.mode = Mode::Confirm(ConfirmAction::ApplyChanges);
+            }
+            ConnectionMode::Remote => {
+                if needs_elevation {
+                    self.mode = Mode::Confirm(ConfirmAction::RequestElevation);
+                    return;
+                }
+
+                // Each host's edits are applied separately, only changed hosts start checked
+                self.apply_machine = self.scope_changed(PathScope::Machine);
+                self.apply_remote = self.remote_changed();
+                self.mode = Mode::Confirm(ConfirmAction::ApplyChanges);
+            }
+        }
+    }
+
+    /// Continue to the apply dialog despite the size advisory
+    fn accept_size_advisory(&mut self) {
+        self.size_acknowledged = true;
+        self.request_apply();
+        self.size_acknowledged = false;
+    }
+
+    /// Check the values an apply would write against the PATH length limits
+    pub fn check_path_sizes(&self) -> Advisory {
+        let local = expansion_context(&self.settings, false);
+        match self.connection_mode {
+            ConnectionMode::Local => path_limits::advise(
+                &[
+                    ScopeValue {
+                        label: "MACHINE",
+                        before: &self.machine_original,
+                        after: &self.machine_paths,
+                        info: &self.machine_info,
+                        context: &local,
+                    },
+                    ScopeValue {
+                        label: "USER",
+                        before: &self.user_original,
+                        after: &self.user_paths,
+                        info: &self.user_info,
+                        context: &local,
+                    },
+                ],
+                true,
+                self.length_threshold(),
+            ),
+            // The two machines have separate environments
+            ConnectionMode::Remote => path_limits::advise(
+                &[
+                    ScopeValue {
+                        label: "LOCAL MACHINE",
+                        before: &self.machine_original,
+                        after: &self.machine_paths,
+                        info: &self.machine_info,
+                        context: &local,
+                    },
+                    ScopeValue {
+                        label: "REMOTE MACHINE",
+                        before: &self.remote_machine_original,
+                        after: &self.remote_machine_paths,
+                        info: &self.remote_machine_info,
+                        context: &expansion_context(&self.settings, true),
+                    },
+                ],
+                false,
+                self.length_threshold(),
+            ),
+        }
+    }
+
+    /// Length PATH values are warned about, from `[limits]` in config.ini
+    pub fn length_threshold(&self) -> Threshold {
+        Threshold::from_settings(&self.settings)
+    }
+
+    /// Expanded length of MACHINE and USER together, what new local processes get
+    /// None in remote mode, where the panels are two separate computers
+    pub fn combined_expanded_length(&self) -> Option<usize> {
+        if self.connection_mode != ConnectionMode::Local {
+            return None;
+        }
+        let context = expansion_context(&self.settings, false);
+        Some(path_limits::combined_length(&[
+            (&self.machine_paths, &context),
+            (&self.user_paths, &context),
+        ]))
+    }
+
+    /// Ask for a command name to look up in the edited PATH
+    fn start_where_lookup(&mut self) {
+        if self.connection_mode == ConnectionMode::Remote {
+            self.set_status("Command lookup only searches this computer's PATH");
+            return;
+        }
+        if self.refuse_without_fs_checks("Command lookup") {
+            return;
+        }
+        self.input_buffer = self.where_lookup.name.clone();
+        self.mode = Mode::Input(InputMode::WhereCommand);
+        self.mode_enter_time = std::time::Instant::now();
+    }
+
+    /// Resolve a command like `where` does, against the edited PATH and the applied one
+    pub fn look_up_command(&mut self, name: &str) {
+        let entries = |machine: &[String], user: &[String]| -> Vec<(PathScope, usize, String)> {
+            machine
+                .iter()
+                .enumerate()
+                .map(|(i, p)| (PathScope::Machine, i, p.clone()))
+                .chain(
+                    user.iter()
+                        .enumerate()
+                        .map(|(i, p)| (PathScope::User, i, p.clone())),
+                )
+                .collect()
+        };
+        let extensions = command_lookup::extensions();
+        self.where_lookup = Lookup {
+            name: name.to_string(),
+            edited: command_lookup::resolve(
+                name,
+                &entries(&self.machine_paths, &self.user_paths),
+                &extensions,
+            ),
+            applied: command_lookup::resolve(
+                name,
+                &entries(&self.machine_original, &self.user_original),
+                &extensions,
+            ),
+        };
+        self.where_scroll = 0;
+        self.mode = Mode::WhereResult;
+    }
+
+    /// Open the dialog explaining which length limits apply
+    fn show_path_lengths(&mut self) {
+        self.lengths_scroll = 0;
+        self.mode = Mode::PathLengths;
+    }
+
+    /// Toggle an apply dialog checkbox by its key
+    fn toggle_apply_choice(&mut self, key: char) {
+        let Some(choice) = self.apply_choices().into_iter().find(|c| c.key == key) else {
+            return;
+        };
+        if !choice.changed {
+            self.set_status(&format!("No {} changes to apply", choice.label));
+            return;
+        }
+        if !choice.writable {
+            self.set_status(&format!(
+                "{} changes need administrator privileges (Ctrl+E)",
+                choice.label
+            ));
+            return;
+        }
+
+        match key {
+            'U' => self.apply_user = !self.apply_user,
+            'R' => self.apply_remote = !self.apply_remote,
+            _ => self.apply_machine = !self.apply_machine,
+        }
+    }
+
+    /// Compare the PATH of this process and logon session with the saved one
+    pub fn show_live_environment(&mut self) {
+        if self.connection_mode != ConnectionMode::Local {
+            self.set_status("The live environment is only shown for the local computer");
+            return;
+        }
+        self.live_environment = LiveEnvironment::capture();
+        self.live_scroll = 0;
+        self.mode = Mode::LiveEnvironment;
+    }
+
+    /// Saved USER and MACHINE entries, which is what newly started programs get
+    pub fn saved_entries(&self) -> Vec<&String> {
+        self.machine_original
+            .iter()
+            .chain(self.user_original.iter())
+            .collect()
+    }
+
+    /// Entries of the live PATH that are not saved and not yet added in either panel
+    pub fn unsaved_live_entries(&self) -> Vec<String> {
+        let panels: Vec<&String> = self
+            .machine_paths
+            .iter()
+            .chain(self.user_paths.iter())
+            .collect();
+        let mut live = self.live_environment.process.clone();
+        live.extend(self.live_environment.volatile.iter().flatten().cloned());
+        let unsaved: Vec<String> = live_env::missing_from(&live, &self.saved_entries())
+            .into_iter()
+            .cloned()
+            .collect();
+        live_env::missing_from(&unsaved, &panels)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Add the live-only entries to USER so new terminals get them too
+    fn save_live_entries_to_user(&mut self) {
+        let entries = self.unsaved_live_entries();
+        if entries.is_empty() {
+            self.set_status("Every live PATH entry is already in USER or MACHINE");
+            return;
+        }
+
+        let mut user_after = self.user_paths.clone();
+        user_after.extend(entries.iter().cloned());
+        self.clear_redo_stack();
+        let operation = Operation::ReplacePanels {
+            machine_before: self.machine_paths.clone(),
+            user_before: std::mem::replace(&mut self.user_paths, user_after.clone()),
+            machine_after: self.machine_paths.clone(),
+            user_after,
+        };
+        self.record_undo(operation);
+        self.has_changes = true;
+        self.reanalyze();
+        self.mode = Mode::Normal;
+        self.set_status(&format!(
+            "Added {} live PATH entr{} to USER, press Ctrl+S to save",
+            entries.len(),
+            if entries.len() == 1 { "y" } else { "ies" }
+        ));
+    }
+
+    /// Open the History dialog with the recorded applies
+    fn show_history(&mut self) -> Result<()> {
+        self.history = history::load(&self.history_file)?;
+        if self.history.is_empty() {
+            self.set_status("No changes have been applied yet");
+        } else {
+            self.history_scroll = 0;
+            self.mode = Mode::History;
+        }
+        Ok(())
+    }
+
+    /// Append an apply to the history and, if enabled, the Windows event log
+    /// Returns a note for the status bar if either failed
+    fn record_history(&self, computer: &str, changes: Vec<ScopeChange>) -> Option<String> {
+        if changes.is_empty() {
+            return None;
+        }
+        let entry = HistoryEntry::new(computer, changes);
+
+        let mut problems = Vec::new();
+        if let Err(e) = history::append(&self.history_file, &entry) {
+            problems.push(format!("History not recorded: {}", e));
+        }
+        if self.settings.event_log {
+            if let Err(e) = crate::event_log::report_apply(&entry) {
+                problems.push(format!("Event log not written: {}", e));
+            }
+        }
+
+        if problems.is_empty() {
+            None
+        } else {
+            Some(format!(" ({})", problems.join("; ")))
+        }
+    }
+
+    /// Let the shell wrapper that started us know it should reload PATH
+    fn notify_refresh_hook(&self) {
+        if let Some(ref file) = self.refresh_file {
+            // The wrapper only checks that the file exists
+            let _ = std::fs::write(file, Local::now().to_rfc3339());
+        }
+    }
+
+    // Apply changes to registry
+    fn apply_changes(&mut self) -> Result<()> {
+        // Held until the end so another instance can't write in between
+        let _lock = match self.instance.as_ref().map(Registration::lock_apply) {
+            Some(Err(e)) => {
+                self.set_status(&format!("Not applied: {}, try again in a moment", e));
+                return Ok(());
+            }
+            Some(Ok(lock)) => Some(lock),
+            None => None,
+        };
+
+        // Save current state as backup first, Revert Last Apply goes back to it
+        let backup_file = self.create_backup()?;
+        if self.connection_mode == ConnectionMode::Remote {
+            // The backup only holds this computer's PATH
+            self.last_apply = None;
+        }
+
+        let mut history_note = None;
+        // MACHINE entries this apply removed, the only PATH services see
+        let mut machine_removed = Vec::new();
+        // Whether this apply queued the remote write instead of making it
+        let mut queued_now = false;
+        match self.connection_mode {
+            ConnectionMode::Local => {
+                let mut changes = Vec::new();
+
+                // Apply only the scopes chosen in the apply dialog
+                let mut written = LastApply {
+                    backup: backup_file,
+                    user: false,
+                    machine: false,
+                };
+                if self.apply_user {
+                    self.registry
+                        .write_paths(PathScope::User, &self.user_paths)?;
+                    written.user = true;
+                    changes.extend(ScopeChange::between(
+                        PathScope::User,
+                        &self.user_original,
+                        &self.user_paths,
+                    ));
+                    self.user_original = self.user_paths.clone();
+                }
+
+                // Apply machine paths (if admin, or through the elevated helper)
+                let mut machine_result = Ok(());
+                if self.apply_machine && (self.is_admin || self.uses_machine_helper()) {
+                    let backend = if self.is_admin {
+                        &mut self.registry
+                    } else {
+                        &mut self.machine_helper
+                    };
+                    machine_result = backend.write_paths(PathScope::Machine, &self.machine_paths);
+                    if machine_result.is_ok() {
+                        let change = ScopeChange::between(
+                            PathScope::Machine,
+                            &self.machine_original,
+                            &self.machine_paths,
+                        );
+                        if let Some(ref change) = change {
+                            machine_removed = change.removed.clone();
+                        }
+                        changes.extend(change);
+                        self.machine_original = self.machine_paths.clone();
+                        written.machine = true;
+                    }
+                }
+
+                // Only an apply that wrote something can be reverted, and only what it wrote,
+                // and only then does the shell wrapper have a new PATH to load
+                if written.user || written.machine {
+                    self.last_apply = Some(written);
+                    self.notify_refresh_hook();
+                }
+
+                // Record what was written even if a later scope failed
+                history_note = self.record_history(&history::local_computer_name(), changes);
+                // Our own write is not a change from outside, even if MACHINE failed
+                self.refresh_last_written();
+                machine_result?;
+            }
+            ConnectionMode::Remote => {
+                // In remote mode, only write to local MACHINE and remote MACHINE
+                // (USER paths are not shown/editable in remote mode)
+
+                // Apply local machine paths (if admin)
+                if self.apply_machine && self.is_admin {
+                    self.registry
+                        .write_paths(PathScope::Machine, &self.machine_paths)?;
+                    let changes = ScopeChange::between(
+                        PathScope::Machine,
+                        &self.machine_original,
+                        &self.machine_paths,
+                    );
+                    history_note = self.record_history(
+                        &history::local_computer_name(),
+                        changes.into_iter().collect(),
+                    );
+                    self.machine_original = self.machine_paths.clone();
+                }
+
+                // Apply remote machine paths (if connected and admin)
+                if self.apply_remote && self.is_admin {
+                    if let Some(ref connection) = self.remote_connection {
+                        let host = connection.computer_name().to_string();
+                        let remote_path = registry::join_paths(&self.remote_machine_paths);
+                        // A network blip shouldn't undo the local write, the remote one waits
+                        match registry::write_path_remote(
+                            PathScope::Machine,
+                            &remote_path,
+                            connection,
+                        ) {
+                            Ok(()) => {
+                                history_note = self
+                                    .finish_remote_write(&host, self.remote_machine_paths.clone())
+                                    .or(history_note);
+                            }
+                            Err(e) => {
+                                queued_now = true;
+                                self.queued_remote_write = Some(QueuedWrite::new(
+                                    &host,
+                                    self.remote_machine_original.clone(),
+                                    self.remote_machine_paths.clone(),
+                                    e.to_string(),
+                                    std::time::Instant::now(),
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        self.refresh_last_written();
+        // What we just wrote replaced the outside change
+        self.external_changes.retain(|scope| match scope {
+            PathScope::User => !self.apply_user,
+            PathScope::Machine => !self.apply_machine,
+        });
+
+        // Edits of a scope or host that was left out stay pending
+        self.has_changes = self.any_pending_changes();
+        let pending_note = format!(
+            "{}{}",
+            if self.has_changes {
+                " Unselected changes are still pending."
+            } else {
+                ""
+            },
+            history_note.unwrap_or_default()
+        );
+
+        // Note: Undo/redo stacks are NOT cleared on save, allowing users to undo changes even after saving
+
+        // Services running from a removed directory may have relied on it being on PATH
+        self.services_at_risk = if machine_removed.is_empty() {
+            Vec::new()
+        } else {
+            crate::services::installed_services()
+                .map(|services| crate::services::at_risk(&services, &machine_removed))
+                .unwrap_or_default()
+        };
+
+        // Detect running processes that won't pick up the new PATH
+        match crate::process_detector::detect_running_processes() {
+            Ok(processes) => {
+                if !processes.is_empty() || !self.services_at_risk.is_empty() {
+                    self.processes_to_restart = processes;
+                    self.process_selected = 0;
+                    self.mode = Mode::ProcessRestartInfo;
+                } else {
+                    // No non-responsive processes detected
+                    self.set_status(&format!(
+                        "Changes applied! All running processes should pick up the new PATH.{}",
+                        pending_note
+                    ));
+                }
+            }
+            Err(e) => {
+                // Process detection failed, but changes were still applied successfully
+                if !self.services_at_risk.is_empty() {
+                    self.processes_to_restart.clear();
+                    self.mode = Mode::ProcessRestartInfo;
+                }
+                self.set_status(&format!(
+                    "Changes applied! (Process detection failed: {}){}",
+                    e, pending_note
+                ));
+            }
+        }
+
+        // The remote write that failed comes first, what to do with it is the open question
+        if let (true, Some(queued)) = (queued_now, &self.queued_remote_write) {
+            self.set_status(&format!(
+                "Local changes applied, the write to {} is queued: {}",
+                queued.host, queued.error
+            ));
+            self.mode = Mode::Confirm(ConfirmAction::RemoteWriteQueued);
+        }
+
+        Ok(())
+    }
+
+    /// A remote write went through: record it and drop whatever was queued for that host
+    /// Returns the history note, like record_history
+    fn finish_remote_write(&mut self, host: &str, written: Vec<String>) -> Option<String> {
+        if self
+            .queued_remote_write
+            .as_ref()
+            .is_some_and(|queued| queued.host.eq_ignore_ascii_case(host))
+        {
+            self.queued_remote_write = None;
+        }
+        // The panel is only ours to update while still connected to that host
+        let connected = self
+            .remote_connection
+            .as_ref()
+            .is_some_and(|connection| connection.computer_name().eq_ignore_ascii_case(host));
+        if !connected {
+            return None;
+        }
+        let changes =
+            ScopeChange::between(PathScope::Machine, &self.remote_machine_original, &written);
+        self.remote_machine_original = written;
+        self.has_changes = self.any_pending_changes();
+        self.record_host_session(host);
+        self.record_history(host, changes.into_iter().collect())
+    }
+
+    /// Try the queued remote write on a fresh connection, the old one may not survive the outage
+    /// Only written if the remote PATH is still the one the apply started from
+    fn retry_queued_write(&mut self) {
+        let Some(ref queued) = self.queued_remote_write else {
+            return;
+        };
+        if self.remote_write_task.is_some() {
+            return;
+        }
+        let host = queued.host.clone();
+        let base = queued.base.clone();
+        let value = registry::join_paths(&queued.paths);
+        self.remote_write_task = Some(BackgroundTask::spawn(
+            &format!("Writing PATH to {}", host),
+            move || {
+                let connection = RemoteConnection::connect(&host)?;
+                let current = registry::parse_path(&registry::read_path_remote(
+                    PathScope::Machine,
+                    &connection,
+                )?);
+                if current != base {
+                    return Ok(RetryOutcome::Changed(current));
+                }
+                registry::write_path_remote(PathScope::Machine, &value, &connection)?;
+                Ok(RetryOutcome::Written)
+            },
+        ));
+    }
+
+    /// Start the next automatic attempt once its backoff has passed
+    fn poll_remote_queue(&mut self, now: std::time::Instant) {
+        if self
+            .queued_remote_write
+            .as_ref()
+            .is_some_and(|queued| queued.is_due(now))
+        {
+            self.retry_queued_write();
+        }
+    }
+
+    fn finish_queued_write(&mut self, result: Result<RetryOutcome>) {
+        let Some(queued) = self.queued_remote_write.clone() else {
+            return;
+        };
+        let now = std::time::Instant::now();
+        match result {
+            Ok(RetryOutcome::Changed(_)) => {
+                if let Some(queued) = self.queued_remote_write.as_mut() {
+                    queued.conflicted();
+                }
+                self.set_status(&format!(
+                    "PATH on {} changed during the outage, the queued write was not made: reconnect to review it, or save it with S",
+                    queued.host
+                ));
+                if self.mode == Mode::Normal {
+                    self.mode = Mode::Confirm(ConfirmAction::RemoteWriteQueued);
+                }
+            }
+            Ok(RetryOutcome::Written) => {
+                let note = self
+                    .finish_remote_write(&queued.host, queued.paths)
+                    .unwrap_or_default();
+                if self.mode == Mode::Confirm(ConfirmAction::RemoteWriteQueued) {
+                    self.mode = Mode::Normal;
+                }
+                self.set_status(&format!(
+                    "Queued PATH written to {} on attempt {}{}",
+                    queued.host,
+                    queued.attempts + 1,
+                    note
+                ));
+            }
+            Err(e) => {
+                if let Some(queued) = self.queued_remote_write.as_mut() {
+                    queued.failed_again(e.to_string(), now);
+                }
+                let next = self
+                    .queued_remote_write
+                    .as_ref()
+                    .map(|queued| queued.describe_next(now))
+                    .unwrap_or_default();
+                self.set_status(&format!(
+                    "Writing PATH to {} failed again: {} ({})",
+                    queued.host, e, next
+                ));
+            }
+        }
+    }
+
+    /// Save the queued write as a .reg file to import on the remote computer, and stop retrying
+    fn save_queued_write(&mut self, folder: &Path) {
+        let Some(queued) = self.queued_remote_write.clone() else {
+            return;
+        };
+        let written = std::fs::create_dir_all(folder)
+            .with_context(|| format!("Failed to create {}", folder.display()))
+            .and_then(|()| {
+                let file = folder.join(queued.file_name());
+                std::fs::write(&file, queued.to_reg())
+                    .with_context(|| format!("Failed to write {}", file.display()))?;
+                Ok(file)
+            });
+        match written {
+            Ok(file) => {
+                self.queued_remote_write = None;
+                self.set_status(&format!(
+                    "Saved to {}, run reg import with it on {} to apply it",
+                    file.display(),
+                    queued.host
+                ));
+            }
+            Err(e) => self.set_status(&format!("Could not save the queued write: {}", e)),
+        }
+    }
+
+    pub fn confirm_exit(&mut self) {
+        if self.has_changes {
+            self.mode = Mode::Confirm(ConfirmAction::Exit);
+        } else {
+            self.should_exit = true;
+        }
+    }
+
+    /// Handle F10 key press - shows exit confirmation dialog
+    pub fn handle_f10_press(&mut self) {
+        // Always show confirmation dialog (user must press F10 again in dialog to confirm)
+        self.mode = Mode::Confirm(ConfirmAction::Exit);
+    }
+
+    /// Check if a directory can be created (not network path, valid chars, etc.)
+    fn can_create_directory(path: &str) -> bool {
+        if path.is_empty() {
+            return false;
+        }
+
+        // Check if it's a network path (starts with \\ or //)
+        if path.starts_with("\\\\") || path.starts_with("//") {
+            return false;
+        }
+
+        // Check for invalid characters (basic check)
+        let invalid_chars = ['<', '>', '|', '\"', '?', '*'];
+        if path.chars().any(|c| invalid_chars.contains(&c)) {
+            return false;
+        }
+
+        // Path should have a drive letter on Windows or be a relative path
+        // Simple check: if it has a colon, it should be at position 1 (like C:)
+        if let Some(colon_pos) = path.find(':') {
+            if colon_pos != 1 {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Create a directory and its parent directories
+    /// If remote_computer is provided, creates the directory on the remote computer using UNC path
+    fn create_directory_with_remote(path: &str, remote_computer: Option<&str>) -> Result<()> {
+        use std::fs;
+        use std::path::Path;
+
+        if path.is_empty() {
+            return Err(anyhow::anyhow!("Path is empty"));
+        }
+
+        // Expand environment variables
+        let expanded = normalize_path(path);
+
+        // Determine the actual path to create
+        let actual_path = if let Some(computer_name) = remote_computer {
+            // Convert to UNC path for remote creation
+            to_unc_path(&expanded, computer_name)
+                .ok_or_else(|| anyhow::anyhow!("Cannot convert to UNC path: {}", expanded))?
+        } else {
+            expanded
+        };
+
+        let path_obj = Path::new(&actual_path);
+
+        // Create directory with parents
+        fs::create_dir_all(path_obj).map_err(|e| {
+            if remote_computer.is_some() {
+                anyhow::anyhow!(
+                    "Failed to create remote directory '{}': {}. \
+                    Ensure C$ administrative shares are enabled and accessible.",
+                    actual_path,
+                    e
+                )
+            } else {
+                anyhow::anyhow!("Failed to create directory '{}': {}", actual_path, e)
+            }
+        })?;
+
+        Ok(())
+    }
+
+    /// Create the pending directory and add the path
+    fn create_single_directory(&mut self) -> Result<()> {
+        if self.pending_directory.is_empty() {
+            return Ok(());
+        }
+
+        // Determine if we're creating on remote computer
+        // In remote mode, User panel (right) is actually the remote machine
+        let remote_computer =
+            if self.connection_mode == ConnectionMode::Remote && self.active_panel == Panel::User {
+                self.remote_connection.as_ref().map(|c| c.computer_name())
+            } else {
+                None
+            };
+
+        match Self::create_directory_with_remote(&self.pending_directory, remote_computer) {
+            Ok(()) => {
+                // Directory created successfully - now add the path
+                let path = self.pending_directory.clone();
+                self.active_panel = self.add_panel();
+                match self.active_panel {
+                    Panel::Machine => {
+                        insert_at(
+                            &mut self.machine_paths,
+                            &mut self.machine_selected,
+                            &mut self.machine_marked,
+                            self.add_position,
+                            path,
+                        );
+                    }
+                    Panel::User => {
+                        if self.connection_mode == ConnectionMode::Remote {
+                            insert_at(
+                                &mut self.remote_machine_paths,
+                                &mut self.remote_machine_selected,
+                                &mut self.remote_machine_marked,
+                                self.add_position,
+                                path,
+                            );
+                        } else {
+                            insert_at(
+                                &mut self.user_paths,
+                                &mut self.user_selected,
+                                &mut self.user_marked,
+                                self.add_position,
+                                path,
+                            );
+                        }
+                    }
+                }
+
+                self.reanalyze();
+                self.has_changes = true;
+                self.set_status(&format!(
+                    "Created directory and added: {}",
+                    self.pending_directory
+                ));
+                self.pending_directory.clear();
+                Ok(())
+            }
+            Err(e) => {
+                self.set_status(&format!("Failed to create directory: {}", e));
+                self.pending_directory.clear();
+                Err(e)
+            }
+        }
+    }
+
+    /// Create the directory of the selected dead entry, leaving PATH as it is
+    fn create_selected_directory(&mut self) -> Result<()> {
+        let Some(path) = self.selected_creatable_dead().cloned() else {
+            return Ok(());
+        };
+        let remote_computer =
+            if self.connection_mode == ConnectionMode::Remote && self.active_panel == Panel::User {
+                self.remote_connection.as_ref().map(|c| c.computer_name())
+            } else {
+                None
+            };
+
+        match Self::create_directory_with_remote(&path, remote_computer) {
+            Ok(()) => {
+                self.reanalyze();
+                self.set_status(&format!("Created directory: {}", path));
+            }
+            Err(e) => self.set_status(&format!("Failed to create directory: {}", e)),
+        }
+        Ok(())
+    }
+
+    /// Create all marked dead directories
+    fn create_marked_directories(&mut self) -> Result<()> {
+        let mut created_count = 0;
+        let mut skipped_count = 0;
+        let mut failed_paths = Vec::new();
+
+        // Determine if we're creating on remote computer
+        let remote_computer =
+            if self.connection_mode == ConnectionMode::Remote && self.active_panel == Panel::User {
+                self.remote_connection.as_ref().map(|c| c.computer_name())
+            } else {
+                None
+            };
+
+        // Collect all marked dead paths
+        let marked_paths: Vec<(usize, String)> = match self.active_panel {
+            Panel::Machine => self
+                .machine_marked
+                .iter()
+                .filter_map(|&idx| {
+                    if idx < self.machine_paths.len() && idx < self.machine_info.len() {
+                        if self.machine_info[idx].is_dead() {
+                            Some((idx, self.machine_paths[idx].clone()))
+                        } else {
+                            None
+                        }
+                    } else {
+                        None
+                    }
+                })
+                .collect(),
+            Panel::User => {
+                if self.connection_mode == ConnectionMode::Remote {
+                    // In remote mode, User panel shows remote machine paths
+                    self.remote_machine_marked
+                        .iter()
+                        .filter_map(|&idx| {
+                            if idx < self.remote_machine_paths.len()
+                                && idx < self.remote_machine_info.len()
+                            {
+                                if self.remote_machine_info[idx].is_dead() {
+                                    Some((idx, self.remote_machine_paths[idx].clone()))
+                                } else {
+                                    None
+                                }
+                            } else {
+                                None
+                            }
+                        })
+                        .collect()
+                } else {
+                    self.user_marked
+                        .iter()
+                        .filter_map(|&idx| {
+                            if idx < self.user_paths.len() && idx < self.user_info.len() {
+                                if self.user_info[idx].is_dead() {
+                                    Some((idx, self.user_paths[idx].clone()))
+                                } else {
+                                    None
+                                }
+                            } else {
+                                None
+                            }
+                        })
+                        .collect()
+                }
+            }
+        };
+
+        // Try to create each directory
+        for (_idx, path) in marked_paths {
+            if !Self::can_create_directory(&path) {
+                skipped_count += 1;
+                continue;
+            }
+
+            match Self::create_directory_with_remote(&path, remote_computer) {
+                Ok(()) => created_count += 1,
+                Err(_) => {
+                    failed_paths.push(path);
+                }
+            }
+        }
+
+        // Reanalyze to update dead path status
+        if created_count > 0 {
+            self.reanalyze();
+            self.has_changes = true;
+        }
+
+        // Show status message
+        let mut msg = format!("Created {} directories", created_count);
+        if skipped_count > 0 {
+            msg.push_str(&format!(", skipped {} (network/invalid)", skipped_count));
+        }
+        if !failed_paths.is_empty() {
+            msg.push_str(&format!(", failed {} paths", failed_paths.len()));
+        }
+        self.set_status(&msg);
+
+        Ok(())
+    }
+
+    fn reanalyze(&mut self) {
+        match self.connection_mode {
+            ConnectionMode::Local => {
+                (self.machine_info, self.user_info) = analyze_scopes_in(
+                    &self.machine_paths,
+                    &self.user_paths,
+                    &*self.fs,
+                    &expansion_context(&self.settings, false),
+                );
+
+                // Update scrollbar content lengths
+                self.machine_scrollbar_state = self
+                    .machine_scrollbar_state
+                    .content_length(self.machine_paths.len());
+                self.user_scrollbar_state = self
+                    .user_scrollbar_state
+                    .content_length(self.user_paths.len());
+
+                // Adjust selection if out of bounds
+                if self.machine_selected >= self.machine_paths.len()
+                    && !self.machine_paths.is_empty()
+                {
+                    self.machine_selected = self.machine_paths.len() - 1;
+                    self.machine_scrollbar_state =
+                        self.machine_scrollbar_state.position(self.machine_selected);
+                }
+                if self.user_selected >= self.user_paths.len() && !self.user_paths.is_empty() {
+                    self.user_selected = self.user_paths.len() - 1;
+                    self.user_scrollbar_state =
+                        self.user_scrollbar_state.position(self.user_selected);
+                }
+            }
+            ConnectionMode::Remote => {
+                // In remote mode: analyze local machine vs remote machine paths
+                // Local paths are analyzed normally (no remote computer name)
+                self.machine_info = analyze_paths_in(
+                    &self.machine_paths,
+                    &self.remote_machine_paths,
+                    None,
+                    &*self.fs,
+                    &expansion_context(&self.settings, false),
+                );
+
+                // Remote paths need UNC path validation - pass the remote computer name
+                let remote_computer_name = self
+                    .remote_connection
+                    .as_ref()
+                    .map(|conn| conn.computer_name());
+                self.remote_machine_info = analyze_paths_in(
+                    &self.remote_machine_paths,
+                    &self.machine_paths,
+                    remote_computer_name,
+                    &*self.fs,
+                    &expansion_context(&self.settings, true),
+                );
+
+                // Update scrollbar content lengths
+                self.machine_scrollbar_state = self
+                    .machine_scrollbar_state
+                    .content_length(self.machine_paths.len());
+                self.remote_scrollbar_state = self
+                    .remote_scrollbar_state
+                    .content_length(self.remote_machine_paths.len());
+
+                // Adjust selection if out of bounds
+                if self.machine_selected >= self.machine_paths.len()
+                    && !self.machine_paths.is_empty()
+                {
+                    self.machine_selected = self.machine_paths.len() - 1;
+                    self.machine_scrollbar_state =
+                        self.machine_scrollbar_state.position(self.machine_selected);
+                }
+                if self.remote_machine_selected >= self.remote_machine_paths.len()
+                    && !self.remote_machine_paths.is_empty()
+                {
+                    self.remote_machine_selected = self.remote_machine_paths.len() - 1;
+                    self.remote_scrollbar_state = self
+                        .remote_scrollbar_state
+                        .position(self.remote_machine_selected);
+                }
+            }
+        }
+    }
+
+    /// Re-check entries on removable media and reanalyze if any were inserted or ejected
+    /// Called periodically from the main loop; returns true if anything changed
+    pub fn refresh_media_status(&mut self) -> bool {
+        // Only refresh while idle so dialogs and edits aren't disturbed
+        if self.connection_mode != ConnectionMode::Local || self.mode != Mode::Normal {
+            return false;
+        }
+
+        let changed = self
+            .machine_info
+            .iter()
+            .chain(self.user_info.iter())
+            .filter(|info| info.is_removable_drive)
+            .any(|info| path_exists_with_fs(&info.normalized, None, &*self.fs) != info.exists);
+
+        if changed {
+            self.reanalyze();
+            self.set_status("Removable media changed - paths re-checked");
+        }
+        changed
+    }
+
+    /// Request UAC elevation and restart the application with administrator privileges
+    fn request_elevation(&mut self) -> Result<()> {
+        // Build elevation state from current app state
+        let elevation_state = crate::elevation::ElevationState {
+            connection_mode: self.connection_mode,
+            remote_computer_name: self
+                .remote_connection
+                .as_ref()
+                .map(|c| c.computer_name().to_string()),
+            machine_paths: self.machine_paths.clone(),
+            user_paths: self.user_paths.clone(),
+            remote_machine_paths: self.remote_machine_paths.clone(),
+            active_panel: self.active_panel,
+            machine_selected: self.machine_selected,
+            user_selected: self.user_selected,
+            remote_machine_selected: self.remote_machine_selected,
+            machine_marked: self.machine_marked.clone(),
+            user_marked: self.user_marked.clone(),
+            remote_machine_marked: self.remote_machine_marked.clone(),
+            filter_mode: self.filter_mode,
+            filter_panel: self.filter_panel,
+            input_buffer: self.input_buffer.clone(),
+            pending_directory: self.pending_directory.clone(),
+            theme_arg: self.theme_arg.clone(),
+            profile: Some(self.profile.name.clone()).filter(|name| !name.is_empty()),
+            no_fs_checks: !self.fs.is_checked(),
+        };
+
+        // Get current executable path
+        let current_exe = std::env::current_exe()
+            .context("Failed to get current executable path")?
+            .to_string_lossy()
+            .to_string();
+
+        // Request elevation (this will trigger UAC and restart the app)
+        match crate::elevation::request_elevation(&elevation_state, &current_exe) {
+            Ok(()) => {
+                // Elevation successful - the new elevated process is starting
+                // Exit this instance
+                self.should_exit = true;
+                self.set_status("Restarting with elevated privileges...");
+                Ok(())
+            }
+            Err(e) => {
+                // Elevation failed or was cancelled
+                self.set_status(&format!(
+                    "Elevation failed: {}. Continuing without elevation.",
+                    e
+                ));
+                Ok(())
+            }
+        }
+    }
+
+    /// Show a message in the status bar and keep it in the message log
+    fn set_status(&mut self, message: &str) {
+        self.status_message = message.to_string();
+        if !message.is_empty() {
+            let time = Local::now().format("%H:%M:%S").to_string();
+            self.status_log.push((time, message.to_string()));
+            let excess = self.status_log.len().saturating_sub(MAX_STATUS_LOG);
+            self.status_log.drain(..excess);
+        }
+    }
+
+    /// Open the message log, newest message first
+    fn show_message_log(&mut self) {
+        self.message_log_scroll = 0;
+        self.mode = Mode::MessageLog;
+    }
+
+    /// Open the release notes at the running version
+    fn show_whats_new(&mut self) {
+        self.releases = release_notes::releases();
+        self.release_selected = release_notes::current_index(&self.releases);
+        self.release_scroll = 0;
+        self.mode = Mode::WhatsNew;
+    }
+
+    /// Show the release notes the first time this version runs, remembered in config.ini
+    pub fn show_whats_new_once(&mut self) {
+        let current = release_notes::CURRENT_VERSION;
+        if self.settings.last_seen_version.as_deref() == Some(current) {
+            return;
+        }
+        self.settings.last_seen_version = Some(current.to_string());
+        let _ = config::save_setting(&self.settings_file, "version", "last_seen", current);
+        self.show_whats_new();
+    }
+
+    // Bulk selection functions
+    fn mark_all_visible(&mut self) {
+        let count = match self.active_panel {
+            Panel::Machine => {
+                let filtered = self.get_filtered_indices(Panel::Machine, &self.machine_info);
+                for idx in filtered {
+                    self.machine_marked.insert(idx);
+                }
+                self.machine_marked.len()
+            }
+            Panel::User => {
+                let filtered = self.get_filtered_indices(Panel::User, &self.user_info);
+                for idx in filtered {
+                    self.user_marked.insert(idx);
+                }
+                self.user_marked.len()
+            }
+        };
+        self.set_status(&format!(
+            "Marked {} visible paths in {} scope",
+            count,
+            self.active_panel.scope().as_str()
+        ));
+    }
+
+    fn mark_all_both_scopes(&mut self) {
+        let machine_filtered = self.get_filtered_indices(Panel::Machine, &self.machine_info);
+        for idx in machine_filtered {
+            self.machine_marked.insert(idx);
+        }
+        let user_filtered = self.get_filtered_indices(Panel::User, &self.user_info);
+        for idx in user_filtered {
+            self.user_marked.insert(idx);
+        }
+        let total = self.machine_marked.len() + self.user_marked.len();
+        self.set_status(&format!("Marked {} paths in both scopes", total));
+    }
+
+    fn mark_all_duplicates(&mut self) {
+        let count = match self.active_panel {
+            Panel::Machine => {
+                for (idx, info) in self.machine_info.iter().enumerate() {
+                    if info.is_duplicate {
+                        self.machine_marked.insert(idx);
+                    }
+                }
+                self.machine_marked.len()
+            }
+            Panel::User => {
+                for (idx, info) in self.user_info.iter().enumerate() {
+                    if info.is_duplicate {
+                        self.user_marked.insert(idx);
+                    }
+                }
+                self.user_marked.len()
+            }
+        };
+        self.set_status(&format!("Marked {} duplicate paths", count));
+    }
+
+    fn mark_all_dead(&mut self) {
+        let count = match self.active_panel {
+            Panel::Machine => {
+                for (idx, info) in self.machine_info.iter().enumerate() {
+                    if info.is_dead() {
+                        self.machine_marked.insert(idx);
+                    }
+                }
+                self.machine_marked.len()
+            }
+            Panel::User => {
+                for (idx, info) in self.user_info.iter().enumerate() {
+                    if info.is_dead() {
+                        self.user_marked.insert(idx);
+                    }
+                }
+                self.user_marked.len()
+            }
+        };
+        self.set_status(&format!("Marked {} dead paths", count));
+    }
+
+    fn mark_all_non_normalized(&mut self) {
+        let count = match self.active_panel {
+            Panel::Machine => {
+                for (idx, info) in self.machine_info.iter().enumerate() {
+                    if info.needs_normalization {
+                        self.machine_marked.insert(idx);
+                    }
+                }
+                self.machine_marked.len()
+            }
+            Panel::User => {
+                for (idx, info) in self.user_info.iter().enumerate() {
+                    if info.needs_normalization {
+                        self.user_marked.insert(idx);
+                    }
+                }
+                self.user_marked.len()
+            }
+        };
+        self.set_status(&format!("Marked {} non-normalized paths", count));
+    }
+
+    fn unmark_all(&mut self) {
+        let total = self.machine_marked.len() + self.user_marked.len();
+        self.machine_marked.clear();
+        self.user_marked.clear();
+        self.set_status(&format!("Unmarked {} paths", total));
+    }
+
+    /// Indices a panel lists, filtered and then sorted
+    pub fn display_indices(&self, panel: Panel) -> Vec<usize> {
+        let (paths, info, _) = self.panel_entries(panel);
+        let mut indices = self.get_filtered_indices(panel, info);
+        match self.sort_mode {
+            SortMode::PathOrder => {}
+            SortMode::Name => indices.sort_by_cached_key(|&idx| {
+                paths.get(idx).map(|p| p.to_lowercase()).unwrap_or_default()
+            }),
+            SortMode::Status => indices.sort_by_key(|&idx| status_rank(info[idx].status)),
+        }
+        indices
+    }
+
+    /// Filter a panel lists its entries with, None when it is limited to the other panel
+    pub fn panel_filter(&self, panel: Panel) -> FilterMode {
+        match self.filter_panel {
+            Some(scope) if scope != panel => FilterMode::None,
+            _ => self.filter_mode,
+        }
+    }
+
+    /// Get filtered indices based on the panel's filter mode
+    pub fn get_filtered_indices(&self, panel: Panel, info: &[PathInfo]) -> Vec<usize> {
+        match self.panel_filter(panel) {
+            FilterMode::None => (0..info.len()).collect(),
+            FilterMode::Dead => info
+                .iter()
+                .enumerate()
+                .filter(|(_, i)| i.is_dead())
+                .map(|(idx, _)| idx)
+                .collect(),
+            FilterMode::Duplicates => info
+                .iter()
+                .enumerate()
+                .filter(|(_, i)| i.is_duplicate)
+                .map(|(idx, _)| idx)
+                .collect(),
+            FilterMode::NonNormalized => info
+                .iter()
+                .enumerate()
+                .filter(|(_, i)| i.needs_normalization)
+                .map(|(idx, _)| idx)
+                .collect(),
+            FilterMode::Valid => info
+                .iter()
+                .enumerate()
+                .filter(|(_, i)| i.exists && !i.is_duplicate && !i.needs_normalization)
+                .map(|(idx, _)| idx)
+                .collect(),
+            FilterMode::Kind(kind) => info
+                .iter()
+                .enumerate()
+                .filter(|(_, i)| i.kind == kind)
+                .map(|(idx, _)| idx)
+                .collect(),
+        }
+    }
+
+    pub fn get_statistics(&self) -> Statistics {
+        let (machine_paths, machine_info, _) = self.panel_entries(Panel::Machine);
+        let (user_paths, user_info, _) = self.panel_entries(Panel::User);
+        let count = |info: &[PathInfo], test: fn(&PathInfo) -> bool| {
+            info.iter().filter(|i| test(i)).count()
+        };
+
+        Statistics {
+            machine_total: machine_paths.len(),
+            user_total: user_paths.len(),
+            machine_dead: count(machine_info, PathInfo::is_dead),
+            user_dead: count(user_info, PathInfo::is_dead),
+            machine_duplicates: count(machine_info, |i| i.is_duplicate),
+            user_duplicates: count(user_info, |i| i.is_duplicate),
+            machine_non_normalized: count(machine_info, |i| i.needs_normalization),
+            user_non_normalized: count(user_info, |i| i.needs_normalization),
+            machine_malformed: count(machine_info, |i| i.is_malformed),
+            user_malformed: count(user_info, |i| i.is_malformed),
+            machine_offline: count(machine_info, |i| i.is_offline_drive),
+            user_offline: count(user_info, |i| i.is_offline_drive),
+        }
+    }
+
+    /// Stored and expanded length of a panel's PATH value, separators and quotes included
+    pub fn path_lengths(&self, panel: Panel) -> Lengths {
+        let (paths, remote) = match (self.connection_mode, panel) {
+            (ConnectionMode::Local, Panel::Machine) => (&self.machine_paths, false),
+            (ConnectionMode::Local, Panel::User) => (&self.user_paths, false),
+            (ConnectionMode::Remote, Panel::Machine) => (&self.machine_paths, false),
+            (ConnectionMode::Remote, Panel::User) => (&self.remote_machine_paths, true),
+        };
+        Lengths::of(paths, &expansion_context(&self.settings, remote))
+    }
+
+    /// Undo the last operation by popping from the undo stack and reversing it
+    pub fn undo(&mut self) -> Result<()> {
+        if let Some(operation) = self.undo_stack.pop() {
+            // Push to redo stack before reversing
+            self.redo_stack.push(operation.clone());
+
+            // Reverse the operation without recording it
+            match operation {
+                Operation::DeletePaths { panel, deleted } => {
+                    // Restore deleted paths at their original indices
+                    let paths = match panel {
+                        Panel::Machine => &mut self.machine_paths,
+                        Panel::User => &mut self.user_paths,
+                    };
+
+                    // Sort by index to insert in correct order
+                    let mut sorted_deleted = deleted;
+                    sorted_deleted.sort_by_key(|(idx, _)| *idx);
+
+                    for (idx, path) in sorted_deleted {
+                        paths.insert(idx, path);
+                    }
+                }
+
+                Operation::AddPath { panel, index, .. } => {
+                    // Remove the added path
+                    let paths = match panel {
+                        Panel::Machine => &mut self.machine_paths,
+                        Panel::User => &mut self.user_paths,
+                    };
+
+                    if index < paths.len() {
+                        paths.remove(index);
+                    }
+                }
+
+                Operation::EditPath {
+                    panel,
+                    index,
+                    old_path,
+                    ..
+                } => {
+                    // Restore the old path value
+                    let paths = match panel {
+                        Panel::Machine => &mut self.machine_paths,
+                        Panel::User => &mut self.user_paths,
+                    };
+
+                    if let Some(path) = paths.get_mut(index) {
+                        *path = old_path;
+                    }
+                }
+
+                Operation::SwapPaths {
+                    panel,
+                    index1,
+                    index2,
+                } => {
+                    // Swap back (same operation reverses itself)
+                    let paths = match panel {
+                        Panel::Machine => &mut self.machine_paths,
+                        Panel::User => &mut self.user_paths,
+                    };
+
+                    if index1 < paths.len() && index2 < paths.len() {
+                        paths.swap(index1, index2);
+                    }
+                }
+
+                Operation::MoveEntry { panel, from, to } => {
+                    let paths = match panel {
+                        Panel::Machine => &mut self.machine_paths,
+                        Panel::User => &mut self.user_paths,
+                    };
+                    move_entry(paths, to, from);
+                }
+
+                Operation::MovePaths {
+                    from_panel,
+                    to_panel: _,
+                    paths_with_indices,
+                } => {
+                    // Move paths back from to_panel to from_panel at their original indices
+                    let (from_paths, to_paths) = match from_panel {
+                        Panel::Machine => (&mut self.machine_paths, &mut self.user_paths),
+                        Panel::User => (&mut self.user_paths, &mut self.machine_paths),
+                    };
+
+                    // Remove from destination panel (they were appended at the end)
+                    // We need to remove the last N items where N = paths_with_indices.len()
+                    let count = paths_with_indices.len();
+                    let new_len = to_paths.len().saturating_sub(count);
+                    to_paths.truncate(new_len);
+
+                    // Restore to source panel at original indices
+                    let mut sorted_paths = paths_with_indices;
+                    sorted_paths.sort_by_key(|(idx, _)| *idx);
+
+                    for (idx, path) in sorted_paths {
+                        from_paths.insert(idx, path);
+                    }
+                }
+
+                Operation::CopyPaths {
+                    from_panel: _,
+                    to_panel,
+                    paths_with_indices,
+                } => {
+                    // Undo copy: remove the copied paths from to_panel
+                    // Note: paths_with_indices contains the indices from the FROM panel,
+                    // but we need to remove from the TO panel where they were appended
+                    let to_paths = match to_panel {
+                        Panel::Machine => &mut self.machine_paths,
+                        Panel::User => &mut self.user_paths,
+                    };
+
+                    // Paths were appended to the end, so remove the last N paths
+                    let count = paths_with_indices.len();
+                    let new_len = to_paths.len().saturating_sub(count);
+                    to_paths.truncate(new_len);
+                }
+
+                Operation::NormalizePaths { panel, changes } => {
+                    // Restore old (non-normalized) values
+                    let paths = match panel {
+                        Panel::Machine => &mut self.machine_paths,
+                        Panel::User => &mut self.user_paths,
+                    };
+
+                    for (idx, old_path, _) in changes {
+                        if let Some(path) = paths.get_mut(idx) {
+                            *path = old_path;
+                        }
+                    }
+                }
+
+                Operation::ReplacePanels {
+                    machine_before,
+                    user_before,
+                    ..
+                } => {
+                    self.machine_paths = machine_before;
+                    self.user_paths = user_before;
+                }
+            }
+
+            self.reanalyze();
+            self.has_changes = true;
+            self.set_status(&format!("Undo successful ({} left)", self.undo_stack.len()));
+            Ok(())
+        } else {
+            self.set_status("Nothing to undo");
+            Ok(())
+        }
+    }
+
+    /// Check if there are operations available to undo
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    /// Redo the last undone operation by popping from the redo stack and re-applying it
+    pub fn redo(&mut self) -> Result<()> {
+        if let Some(operation) = self.redo_stack.pop() {
+            // Re-apply the operation and push back to undo stack
+            self.undo_stack.push(operation.clone());
+            self.prune_undo_stack();
+
+            // Apply the operation
+            match operation {
+                Operation::DeletePaths { panel, deleted } => {
+                    // Re-delete the paths
+                    let paths = match panel {
+                        Panel::Machine => &mut self.machine_paths,
+                        Panel::User => &mut self.user_paths,
+                    };
+
+                    // Sort indices in reverse to delete from end to start
+                    let mut indices: Vec<_> = deleted.iter().map(|(idx, _)| *idx).collect();
+                    indices.sort_unstable_by(|a, b| b.cmp(a));
+
+                    for idx in indices {
+                        if idx < paths.len() {
+                            paths.remove(idx);
+                        }
+                    }
+                }
+
+                Operation::AddPath { panel, index, path } => {
+                    // Re-add the path where it was added
+                    let paths = match panel {
+                        Panel::Machine => &mut self.machine_paths,
+                        Panel::User => &mut self.user_paths,
+                    };
+                    paths.insert(index.min(paths.len()), path);
+                }
+
+                Operation::EditPath {
+                    panel,
+                    index,
+                    old_path: _,
+                    new_path,
+                } => {
+                    // Re-apply the edit
+                    let paths = match panel {
+                        Panel::Machine => &mut self.machine_paths,
+                        Panel::User => &mut self.user_paths,
+                    };
+
+                    if let Some(path) = paths.get_mut(index) {
+                        *path = new_path;
+                    }
+                }
+
+                Operation::SwapPaths {
+                    panel,
+                    index1,
+                    index2,
+                } => {
+                    // Re-swap (same as undo)
+                    let paths = match panel {
+                        Panel::Machine => &mut self.machine_paths,
+                        Panel::User => &mut self.user_paths,
+                    };
+
+                    if index1 < paths.len() && index2 < paths.len() {
+                        paths.swap(index1, index2);
+                    }
+                }
+
+                Operation::MoveEntry { panel, from, to } => {
+                    let paths = match panel {
+                        Panel::Machine => &mut self.machine_paths,
+                        Panel::User => &mut self.user_paths,
+                    };
+                    move_entry(paths, from, to);
+                }
+
+                Operation::MovePaths {
+                    from_panel,
+                    to_panel: _,
+                    paths_with_indices,
+                } => {
+                    // Re-do: move paths from from_panel to to_panel (destination is implicit from from_panel)
+                    let (from_paths, to_paths) = match from_panel {
+                        Panel::Machine => (&mut self.machine_paths, &mut self.user_paths),
+                        Panel::User => (&mut self.user_paths, &mut self.machine_paths),
+                    };
+
+                    // Remove from source panel (sorted in reverse)
+                    let mut indices: Vec<_> =
+                        paths_with_indices.iter().map(|(idx, _)| *idx).collect();
+                    indices.sort_unstable_by(|a, b| b.cmp(a));
+
+                    for idx in indices {
+                        if idx < from_paths.len() {
+                            from_paths.remove(idx);
+                        }
+                    }
+
+                    // Add to destination panel
+                    for (_, path) in paths_with_indices {
+                        to_paths.push(path);
+                    }
+                }
+
+                Operation::CopyPaths {
+                    from_panel: _,
+                    to_panel,
+                    paths_with_indices,
+                } => {
+                    // Redo copy: add the copied paths back to to_panel
+                    let to_paths = match to_panel {
+                        Panel::Machine => &mut self.machine_paths,
+                        Panel::User => &mut self.user_paths,
+                    };
+
+                    for (_, path) in paths_with_indices {
+                        to_paths.push(path);
+                    }
+                }
+
+                Operation::NormalizePaths { panel, changes } => {
+                    // Re-apply normalizations
+                    let paths = match panel {
+                        Panel::Machine => &mut self.machine_paths,
+                        Panel::User => &mut self.user_paths,
+                    };
+
+                    for (idx, _, new_path) in changes {
+                        if let Some(path) = paths.get_mut(idx) {
+                            *path = new_path;
+                        }
+                    }
+                }
+
+                Operation::ReplacePanels {
+                    machine_after,
+                    user_after,
+                    ..
+                } => {
+                    self.machine_paths = machine_after;
+                    self.user_paths = user_after;
+                }
+            }
+
+            self.reanalyze();
+            self.has_changes = true;
+            self.set_status(&format!("Redo successful ({} left)", self.redo_stack.len()));
+            Ok(())
+        } else {
+            self.set_status("Nothing to redo");
+            Ok(())
+        }
+    }
+
+    /// Check if there are operations available to redo
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Clear the redo stack - called whenever a new operation is performed
+    fn clear_redo_stack(&mut self) {
+        self.redo_stack.clear();
+    }
+
+    /// Record a new operation for undo, folded into the last one when it continues it
+    /// (editing the same entry again, moving the same entry further)
+    fn record_undo(&mut self, operation: Operation) {
+        let merged = self
+            .undo_stack
+            .last()
+            .and_then(|last| last.merge(&operation));
+        match merged {
+            Some(merged) => {
+                self.undo_stack.pop();
+                // Moved back where it started: nothing left to undo
+                if !merged.is_noop() {
+                    self.undo_stack.push(merged);
+                }
+            }
+            None => self.undo_stack.push(operation),
+        }
+        self.prune_undo_stack();
+    }
+
+    /// Drop the oldest operations beyond the configured depth or the memory limit
+    /// The newest one is always kept so the last edit can be undone
+    fn prune_undo_stack(&mut self) {
+        let depth = self.undo_depth();
+        let excess = self.undo_stack.len().saturating_sub(depth);
+        self.undo_stack.drain(..excess);
+
+        let mut size: usize = self.undo_stack.iter().map(Operation::size).sum();
+        let mut dropped = 0;
+        while size > config::UNDO_MEMORY_LIMIT && dropped + 1 < self.undo_stack.len() {
+            size -= self.undo_stack[dropped].size();
+            dropped += 1;
+        }
+        self.undo_stack.drain(..dropped);
+    }
+
+    /// Most operations kept for undo
+    pub fn undo_depth(&self) -> usize {
+        self.settings
+            .undo_depth
+            .unwrap_or(config::DEFAULT_UNDO_DEPTH)
+    }
+
+    /// Menus with the recent remotes filled in and items enabled for the current state
+    pub fn menus(&self) -> Vec<crate::menu::Menu> {
+        let mut menus = crate::menu::get_menus(self.connection_mode, &self.settings.recent_remotes);
+        crate::menu::apply_key_settings(&mut menus, &self.settings);
+        let has_selection = match self.active_panel {
+            Panel::Machine => !self.machine_paths.is_empty(),
+            Panel::User => !self.user_paths.is_empty(),
+        };
+        crate::menu::update_menu_enabled_states(
+            &mut menus,
+            self.is_admin,
+            self.has_marked_items(),
+            self.has_marked_dead_paths() || self.selected_creatable_dead().is_some(),
+            has_selection,
+            self.connection_mode == ConnectionMode::Remote,
+            self.has_changes,
+        );
+        crate::menu::apply_profile(&mut menus, &self.profile);
+        menus
+    }
+
+    /// Write the shortcuts the menus list, as currently bound, to ~/.pc/keyboard-shortcuts.md
+    fn export_shortcuts(&mut self) {
+        let sheet = crate::cheat_sheet::markdown(&self.menus(), &self.settings);
+        let written = config::get_shortcuts_file().and_then(|file| {
+            if let Some(dir) = file.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&file, sheet)
+                .with_context(|| format!("Failed to write {}", file.display()))?;
+            Ok(file)
+        });
+        match written {
+            Ok(file) => {
+                self.set_status(&format!("Keyboard shortcuts written to {}", file.display()))
+            }
+            Err(e) => self.set_status(&format!("Could not export shortcuts: {}", e)),
+        }
+    }
+
+    /// Serve the local control pipe, reporting in the status bar if it cannot be opened
+    pub fn start_control_pipe(&mut self) {
+        match ControlServer::start() {
+            Ok(server) => self.control = Some(server),
+            Err(e) => self.set_status(&format!("Control pipe not started: {}", e)),
+        }
+    }
+
+    /// Announce this session to other instances and warn if one is already running
+    pub fn register_instance(&mut self) {
+        // Coordination is advisory, without the folder we just don't know about the others
+        let Ok(registration) =
+            config::get_instances_dir().and_then(|dir| Registration::register(&dir, self.is_admin))
+        else {
+            return;
+        };
+        self.other_instances = registration.others();
+        self.instance = Some(registration);
+        if let Some(other) = self.other_instances.first() {
+            self.set_status(&format!(
+                "Another Path Commander is running ({}), applying from both can overwrite changes",
+                other.describe()
+            ));
+        }
+    }
+
+    /// Publish our pending edits, pick up other instances and PATH written behind our back
+    /// Returns true if there was something new to show
+    fn poll_instances(&mut self) -> bool {
+        let mut noticed = self.check_external_changes();
+        let pending = self.has_changes;
+        let remote = self
+            .remote_connection
+            .as_ref()
+            .map(|c| c.computer_name().to_string());
+        let Some(ref mut registration) = self.instance else {
+            return noticed;
+        };
+        registration.update(pending, remote.as_deref());
+        let others = registration.others();
+        let started: Vec<String> = others
+            .iter()
+            .filter(|other| !self.other_instances.iter().any(|o| o.pid == other.pid))
+            .map(Instance::describe)
+            .collect();
+        self.other_instances = others;
+        if !started.is_empty() {
+            self.set_status(&format!(
+                "Another Path Commander started ({}), applying from both can overwrite changes",
+                started.join(", ")
+            ));
+            noticed = true;
+        }
+        noticed
+    }
+
+    /// Note local scopes written since we last read or wrote them, true if there are new ones
+    pub fn check_external_changes(&mut self) -> bool {
+        let mut changed = Vec::new();
+        for scope in [PathScope::User, PathScope::Machine] {
+            let Some(modified) = self.registry.last_modified(scope) else {
+                continue;
+            };
+            let modified: DateTime<Local> = modified.into();
+            let newer = match self.last_written.get(&scope) {
+                Some(seen) => modified > *seen,
+                None => true,
+            };
+            if !newer {
+                continue;
+            }
+            self.last_written.insert(scope, modified);
+            if !self.external_changes.contains(&scope) {
+                self.external_changes.push(scope);
+            }
+            changed.push(scope.as_str());
+        }
+        if changed.is_empty() {
+            return false;
+        }
+        self.set_status(&format!(
+            "{} PATH was changed outside this session, applying will overwrite that change",
+            changed.join(" and ")
+        ));
+        true
+    }
+
+    /// Reasons to look twice before applying, shown in the apply dialog
+    pub fn apply_warnings(&self) -> Vec<String> {
+        let mut warnings: Vec<String> = self
+            .external_changes
+            .iter()
+            .map(|scope| format!("{} was changed outside this session", scope.as_str()))
+            .collect();
+        warnings.extend(
+            self.other_instances
+                .iter()
+                .filter(|other| other.pending)
+                .map(|other| format!("{} has unapplied edits", other.describe())),
+        );
+        warnings
+    }
+
+    /// Answer commands that arrived on the control pipe, returns true if there were any
+    fn poll_control(&mut self) -> bool {
+        let mut handled = false;
+        while let Some(request) = self.control.as_ref().and_then(ControlServer::try_next) {
+            let response = self.run_control_command(&request.command);
+            request.respond(response);
+            handled = true;
+        }
+        handled
+    }
+
+    /// Stage what a script asked for, the user still reviews and applies it
+    pub fn run_control_command(&mut self, command: &control::Command) -> control::Response {
+        let scope_panel = |scope: &Option<String>| match scope.as_deref() {
+            None => Some(Panel::User),
+            Some(scope) => PathScope::parse(scope).map(Panel::from_scope),
+        };
+        let edits = matches!(
+            command,
+            control::Command::Add { .. } | control::Command::Remove { .. }
+        );
+        if (edits || *command == control::Command::Apply) && self.profile.is_read_only() {
+            return control::Response::failed(self.profile.denied(Feature::Edit));
+        }
+        if edits && self.connection_mode == ConnectionMode::Remote {
+            return control::Response::failed("Only local PATH values can be edited this way");
+        }
+        // Indices shown in an open dialog must not shift under the user
+        if edits && self.mode != Mode::Normal {
+            return control::Response::failed("A dialog is open, try again later");
+        }
+
+        match command {
+            control::Command::Query => control::Response {
+                ok: true,
+                machine: Some(self.machine_paths.clone()),
+                user: Some(self.user_paths.clone()),
+                pending: Some(self.has_changes),
+                ..control::Response::default()
+            },
+            control::Command::Add { path, scope } => {
+                let Some(panel) = scope_panel(scope) else {
+                    return control::Response::failed("Scope must be machine or user");
+                };
+                let path = path.trim();
+                if path.is_empty() {
+                    return control::Response::failed("Path is empty");
+                }
+                let key = normalize_path_with_fs(path, &*self.fs).to_lowercase();
+                let (paths, selected, marked) = match panel {
+                    Panel::Machine => (
+                        &mut self.machine_paths,
+                        &mut self.machine_selected,
+                        &mut self.machine_marked,
+                    ),
+                    Panel::User => (
+                        &mut self.user_paths,
+                        &mut self.user_selected,
+                        &mut self.user_marked,
+                    ),
+                };
+                if paths.iter().any(|existing| {
+                    normalize_path_with_fs(existing, &*self.fs).to_lowercase() == key
+                }) {
+                    return control::Response::done(format!(
+                        "{} is already in {}",
+                        path,
+                        panel.scope().as_str()
+                    ));
+                }
+                let index = insert_at(paths, selected, marked, AddPosition::End, path.to_string());
+                // Marked so the user sees what a script added before applying it
+                marked.insert(index);
+                self.active_panel = panel;
+
+                self.clear_redo_stack();
+                self.record_undo(Operation::AddPath {
+                    panel,
+                    index,
+                    path: path.to_string(),
+                });
+                self.reanalyze();
+                self.has_changes = true;
+                let message = format!(
+                    "{} added to {} by a script, review it and press Ctrl+S to apply",
+                    path,
+                    panel.scope().as_str()
+                );
+                self.set_status(&message);
+                control::Response::done(message)
+            }
+            control::Command::Remove { path, scope } => {
+                let Some(panel) = scope_panel(scope) else {
+                    return control::Response::failed("Scope must be machine or user");
+                };
+                let key = normalize_path_with_fs(path.trim(), &*self.fs).to_lowercase();
+                let paths = match panel {
+                    Panel::Machine => &self.machine_paths,
+                    Panel::User => &self.user_paths,
+                };
+                let found: Vec<(usize, String)> = paths
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, existing)| {
+                        normalize_path_with_fs(existing, &*self.fs).to_lowercase() == key
+                    })
+                    .map(|(idx, existing)| (idx, existing.clone()))
+                    .collect();
+                if found.is_empty() {
+                    return control::Response::failed(format!(
+                        "{} is not in {}",
+                        path,
+                        panel.scope().as_str()
+                    ));
+                }
+                let (machine, user) = match panel {
+                    Panel::Machine => (found, Vec::new()),
+                    Panel::User => (Vec::new(), found),
+                };
+                let removed = self.remove_planned(BulkDelete {
+                    action: ConfirmAction::DeleteSelected,
+                    machine,
+                    user,
+                });
+                // Marks would point at different entries now
+                match panel {
+                    Panel::Machine => self.machine_marked.clear(),
+                    Panel::User => self.user_marked.clear(),
+                }
+                let message = format!(
+                    "{} removed from {} by a script ({} entr{}), review and press Ctrl+S to apply",
+                    path,
+                    panel.scope().as_str(),
+                    removed,
+                    if removed == 1 { "y" } else { "ies" }
+                );
+                self.set_status(&message);
+                control::Response::done(message)
+            }
+            control::Command::Apply if !self.has_changes => {
+                control::Response::failed("Nothing to apply")
+            }
+            control::Command::Apply if self.mode != Mode::Normal => {
+                control::Response::failed("A dialog is open, try again later")
+            }
+            control::Command::Apply => {
+                // The same checks as Ctrl+S: policy, length limits, elevation
+                self.request_apply();
+                match self.mode {
+                    Mode::Confirm(ConfirmAction::ApplyChanges) => control::Response::done(
+                        "Apply dialog opened, waiting for the user to confirm",
+                    ),
+                    Mode::Confirm(ConfirmAction::SizeAdvisory) => control::Response::done(
+                        "PATH length advisory opened, waiting for the user to continue",
+                    ),
+                    Mode::Confirm(ConfirmAction::RequestElevation) => control::Response::done(
+                        "Elevation prompt opened, waiting for the user to confirm",
+                    ),
+                    _ => control::Response::failed(self.status_message.clone()),
+                }
+            }
+        }
+    }
+
+    /// Look for a newer release on a worker thread
+    /// A quiet check, the one at startup, only speaks up when it finds a newer release
+    pub fn check_for_updates(&mut self, quiet: bool) {
+        if self.update_task.is_some() {
+            if !quiet {
+                self.set_status("Update check already running");
+            }
+            return;
+        }
+        self.update_check_quiet = quiet;
+        self.update_task = Some(BackgroundTask::spawn(
+            "Checking for updates",
+            updates::latest,
+        ));
+    }
+
+    fn finish_update_check(&mut self, result: Result<Option<Update>>) {
+        match result {
+            Ok(Some(update)) if update.is_newer() => {
+                self.set_status(&format!(
+                    "Path Commander v{} is available (Help → Install Update)",
+                    update.version
+                ));
+                self.update = Some(update);
+            }
+            _ if self.update_check_quiet => {}
+            Ok(_) => self.set_status(&format!(
+                "Path Commander v{} is up to date",
+                release_notes::CURRENT_VERSION
+            )),
+            Err(e) => self.set_status(&format!("Update check failed: {}", e)),
+        }
+    }
+
+    /// Ask before downloading the update the last check found
+    fn confirm_install_update(&mut self) {
+        if let Some(version) = &self.update_staged {
+            let message = format!("v{} is installed the next time pc starts", version);
+            self.set_status(&message);
+        } else if self.download_task.is_some() {
+            self.set_status("Update download already running");
+        } else if self.update.is_none() {
+            self.set_status("No update found yet, use Help → Check for Updates first");
+        } else if !self.settings.check_updates {
+            // A download is only installed at startup while updates are on
+            self.set_status("Updates are off, set check = true under [updates] in config.ini");
+        } else {
+            self.mode = Mode::Confirm(ConfirmAction::InstallUpdate);
+        }
+    }
+
+    /// Download and verify the update on a worker thread, it is swapped in on the next start
+    fn download_update(&mut self) {
+        let Some(update) = self.update.clone() else {
+            return;
+        };
+        let staging = match config::get_update_dir() {
+            Ok(staging) => staging,
+            Err(e) => {
+                self.set_status(&format!("Update not installed: {}", e));
+                return;
+            }
+        };
+        self.download_task = Some(BackgroundTask::spawn("Downloading update", move || {
+            updates::download(&update, &staging)
+        }));
+    }
+
+    fn finish_update_download(&mut self, result: Result<String>) {
+        match result {
+            Ok(version) => {
+                self.set_status(&format!(
+                    "v{} downloaded and verified, it is installed the next time pc starts",
+                    version
+                ));
+                self.update_staged = Some(version);
+            }
+            Err(e) => self.set_status(&format!("Update not installed: {}", e)),
+        }
+    }
+
+    /// Open a menu's dropdown with no submenu showing
+    fn open_menu(&mut self, active_menu: usize, selected_item: usize) {
+        self.menu_submenu = None;
+        self.mode = Mode::Menu {
+            active_menu,
+            selected_item,
+        };
+    }
+
+    /// Activate menu by accelerator character
+    fn activate_menu_by_char(&mut self, c: char) {
+        let menus = self.menus();
+        let c_lower = c.to_lowercase().next().unwrap_or(c);
+
+        for (i, menu) in menus.iter().enumerate() {
+            if menu.accelerator == c_lower {
+                self.open_menu(i, 0);
+                return;
+            }
+        }
+    }
+
+    /// Handle keyboard input while the menu bar is focused without a dropdown
+    fn handle_menu_bar_input(&mut self, key: KeyEvent, active_menu: usize) -> Result<()> {
+        let menus = self.menus();
+        match key.code {
+            KeyCode::Left => {
+                self.mode = Mode::MenuBar {
+                    active_menu: (active_menu + menus.len() - 1) % menus.len(),
+                };
+            }
+            KeyCode::Right => {
+                self.mode = Mode::MenuBar {
+                    active_menu: (active_menu + 1) % menus.len(),
+                };
+            }
+            KeyCode::Enter | KeyCode::Down | KeyCode::Up => self.open_menu(active_menu, 0),
+            KeyCode::Esc | KeyCode::F(9) | KeyCode::F(10) => self.mode = Mode::Normal,
+            KeyCode::Char(c) => self.activate_menu_by_char(c),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Handle click on menu bar
+    fn handle_menu_bar_click(&mut self, column: u16) -> Result<()> {
+        let menus = self.menus();
+        let mut x_offset = 1; // Start with 1 for initial space
+
+        for (i, menu) in menus.iter().enumerate() {
+            let menu_start = x_offset;
+            let menu_end = x_offset + menu.name.len() as u16 + 2; // +2 for spaces around menu name
+
+            if column >= menu_start && column < menu_end {
+                // Clicked on this menu
+                self.open_menu(i, 0);
+                return Ok(());
+            }
+
+            x_offset = menu_end;
+        }
+
+        Ok(())
+    }
+
+    /// Handle click in menu dropdown
+    fn handle_menu_click(
+        &mut self,
+        column: u16,
+        row: u16,
+        active_menu: usize,
+        selected_item: usize,
+        screen_width: u16,
+    ) -> Result<()> {
+        let menus = self.menus();
+        let Some(menu) = menus.get(active_menu) else {
+            return Ok(());
+        };
+
+        // An open submenu is drawn on top, so it gets the click first
+        if let Some(parent) = self.menu_submenu.and(menu.items.get(selected_item)) {
+            let area = crate::menu::submenu_area(&menus, active_menu, selected_item, screen_width);
+            if let Some(clicked) = crate::menu::item_at(area, parent.submenu.len(), column, row) {
+                let item = &parent.submenu[clicked];
+                if item.enabled {
+                    self.execute_menu_action(item.action)?;
+                }
+                return Ok(());
+            }
+        }
+
+        let area = crate::menu::dropdown_area(&menus, active_menu, screen_width);
+        match crate::menu::item_at(area, menu.items.len(), column, row) {
+            Some(clicked) => {
+                let item = &menu.items[clicked];
+                if item.enabled && item.has_submenu() {
+                    self.open_menu(active_menu, clicked);
+                    self.menu_submenu = Some(0);
+                } else if item.enabled {
+                    self.execute_menu_action(item.action)?;
+                }
+            }
+            // Clicked outside menu, close it
+            None => self.mode = Mode::Normal,
+        }
+
+        Ok(())
+    }
+
+    /// Handle keyboard input in menu mode
+    /// Right opens the selected item's submenu, Left and Esc close it again
+    fn handle_menu_input(
+        &mut self,
+        key: KeyEvent,
+        active_menu: usize,
+        selected_item: usize,
+    ) -> Result<()> {
+        let menus = self.menus();
+        let Some(menu) = menus.get(active_menu) else {
+            self.mode = Mode::Normal;
+            return Ok(());
+        };
+        let item = menu.items.get(selected_item);
+        let submenu = self
+            .menu_submenu
+            .zip(item)
+            .map(|(sub, item)| (sub, &item.submenu));
+
+        match (key.code, submenu) {
+            (KeyCode::Esc | KeyCode::Left, Some(_)) => self.menu_submenu = None,
+            (KeyCode::Esc, None) => {
+                // Close menu and return to normal mode
+                self.mode = Mode::Normal;
+            }
+            (KeyCode::Left, None) if active_menu > 0 => {
+                // Move to previous menu
+                self.open_menu(active_menu - 1, 0);
+            }
+            (KeyCode::Right | KeyCode::Enter, None)
+                if item.is_some_and(|item| item.has_submenu() && item.enabled) =>
+            {
+                self.menu_submenu = Some(0);
+            }
+            (KeyCode::Right, _) if active_menu + 1 < menus.len() => {
+                // Move to next menu
+                self.open_menu(active_menu + 1, 0);
+            }
+            (KeyCode::Up, Some((sub, _))) => self.menu_submenu = Some(sub.saturating_sub(1)),
+            (KeyCode::Down, Some((sub, items))) if sub + 1 < items.len() => {
+                self.menu_submenu = Some(sub + 1);
+            }
+            (KeyCode::Up, None) if selected_item > 0 => {
+                // Move to previous item in menu
+                self.open_menu(active_menu, selected_item - 1);
+            }
+            (KeyCode::Down, None) if selected_item + 1 < menu.items.len() => {
+                // Move to next item in menu
+                self.open_menu(active_menu, selected_item + 1);
+            }
+            (KeyCode::Enter, Some((sub, items))) => {
+                if let Some(item) = items.get(sub).filter(|item| item.enabled) {
+                    self.execute_menu_action(item.action)?;
+                }
+            }
+            (KeyCode::Enter, None) => {
+                // Execute selected menu action
+                if let Some(item) = item.filter(|item| item.enabled) {
+                    self.execute_menu_action(item.action)?;
+                }
+            }
+            (KeyCode::Char(c), Some((_, items))) if !key.modifiers.contains(KeyModifiers::ALT) => {
+                let pressed = crate::menu::item_by_accelerator(items, c);
+                if let Some(item) = pressed.map(|sub| &items[sub]).filter(|item| item.enabled) {
+                    self.execute_menu_action(item.action)?;
+                }
+            }
+            (KeyCode::Char(c), None) if !key.modifiers.contains(KeyModifiers::ALT) => {
+                // Underlined letter runs the item, or opens its submenu
+                if let Some(pressed) = crate::menu::item_by_accelerator(&menu.items, c) {
+                    let item = &menu.items[pressed];
+                    if item.enabled && item.has_submenu() {
+                        self.open_menu(active_menu, pressed);
+                        self.menu_submenu = Some(0);
+                    } else if item.enabled {
+                        self.execute_menu_action(item.action)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Execute a menu action
+    fn execute_menu_action(&mut self, action: crate::menu::MenuAction) -> Result<()> {
+        use crate::menu::MenuAction;
+
+        // Close the menu first
+        self.mode = Mode::Normal;
+        self.menu_submenu = None;
+
+        match action {
+            MenuAction::Submenu | MenuAction::NoRecentRemotes => {}
+
+            // File menu
+            MenuAction::RunAsAdministrator => {
+                if !self.is_admin {
+                    self.mode = Mode::Confirm(ConfirmAction::RequestElevation);
+                } else {
+                    self.set_status("Already running as administrator");
+                }
+            }
+            MenuAction::ConnectRecent(index) => {
+                if let Some(host) = self.settings.recent_remotes.get(index).cloned() {
+                    self.start_remote_connect(&host);
+                }
+            }
+            MenuAction::Exit => {
+                self.confirm_exit();
+            }
+
+            // Command menu
+            MenuAction::AddPath => {
+                self.start_add_path();
+            }
+            MenuAction::EditPath => {
+                let has_paths = match self.active_panel {
+                    Panel::Machine => !self.machine_paths.is_empty(),
+                    Panel::User => !self.user_paths.is_empty(),
+                };
+                if has_paths {
+                    self.start_edit_path();
+                }
+            }
+            MenuAction::DeleteMarked => {
+                self.confirm_delete_marked();
+            }
+            MenuAction::MarkItem => {
+                self.toggle_mark();
+            }
+            MenuAction::MarkAllVisible => self.mark_all_visible(),
+            MenuAction::MarkAllDead => self.mark_all_dead(),
+            MenuAction::MarkAllDuplicates => self.mark_all_duplicates(),
+            MenuAction::MarkAllNonNormalized => self.mark_all_non_normalized(),
+            MenuAction::UnmarkAll => {
+                self.unmark_all();
+            }
+            MenuAction::MoveMarked => {
+                self.move_marked_to_other_panel()?;
+            }
+            MenuAction::CopyMarked => {
+                self.copy_marked_to_other_panel()?;
+            }
+            MenuAction::PromoteEntry => self.start_promotion(),
+            MenuAction::MoveItemUp => {
+                self.move_item_up();
+            }
+            MenuAction::NormalizeSelected => {
+                self.normalize_selected();
+            }
+            MenuAction::PathDetails => {
+                self.show_path_details();
+            }
+            MenuAction::CleanMalformed => {
+                self.clean_malformed_entries();
+            }
+            MenuAction::DeleteAllDead => {
+                self.confirm_bulk_delete(ConfirmAction::DeleteAllDead);
+            }
+            MenuAction::DeleteAllDuplicates => {
+                self.confirm_bulk_delete(ConfirmAction::DeleteAllDuplicates);
+            }
+            MenuAction::SecurityScan => self.start_security_scan(),
+            MenuAction::ShimCheck => self.start_shim_check(),
+            MenuAction::Toolchains => self.start_toolchain_scan(),
+            MenuAction::AgingReport => self.start_aging_report(),
+            MenuAction::UsageSuggestions => self.start_usage_suggestions(),
+            MenuAction::MergeDuplicates => self.start_merge_duplicates(),
+            MenuAction::NestedEntries => self.start_nested_entries(),
+            MenuAction::BatchReplace => self.start_batch_replace(),
+            MenuAction::EditRawValue => self.start_raw_editor(),
+            MenuAction::DriveMigration => self.start_drive_migration(),
+            MenuAction::CreateMarkedDirectories => self.confirm_create_directories(),
+
+            // Options menu
+            MenuAction::SelectTheme => {
+                self.open_theme_selector()?;
+            }
+            MenuAction::ApplyFilter => {
+                self.mode = Mode::FilterMenu;
+                self.filter_menu_selected = 0;
+            }
+            MenuAction::ConnectRemote => {
+                if self.connection_mode == ConnectionMode::Local {
+                    self.open_host_picker();
+                }
+            }
+            MenuAction::DisconnectRemote => {
+                if self.connection_mode == ConnectionMode::Remote {
+                    self.mode = Mode::Confirm(ConfirmAction::DisconnectRemote);
+                }
+            }
+            MenuAction::CreateBackup => self.create_manual_backup()?,
+            MenuAction::ApplyHistory => self.show_history()?,
+            MenuAction::RestoreBackup => {
+                self.show_backup_list()?;
+            }
+            MenuAction::RevertLastApply => self.start_revert_last_apply(),
+            MenuAction::ChangeReport => self.start_change_report(),
+            MenuAction::IgnoreList => self.start_ignore_list(),
+            MenuAction::ToggleQuickActions => self.toggle_quick_actions(),
+
+            // Help menu
+            MenuAction::KeyboardShortcuts => {
+                self.mode = Mode::Help;
+            }
+            MenuAction::PathPrecedence => {
+                if self.connection_mode == ConnectionMode::Local {
+                    self.precedence_scroll = 0;
+                    self.mode = Mode::PathPrecedence;
+                }
+            }
+            MenuAction::LiveEnvironment => self.show_live_environment(),
+            MenuAction::PathLengths => self.show_path_lengths(),
+            MenuAction::WhereCommand => self.start_where_lookup(),
+            MenuAction::MessageLog => self.show_message_log(),
+            MenuAction::ExportShortcuts => self.export_shortcuts(),
+            MenuAction::WhatsNew => self.show_whats_new(),
+            MenuAction::CheckUpdates => self.check_for_updates(false),
+            MenuAction::InstallUpdate => self.confirm_install_update(),
+            MenuAction::About => {
+                self.mode = Mode::About;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// One checkbox of the apply dialog, see `App::apply_choices`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplyChoice {
+    /// Key that toggles it, also the first letter of the label
+    pub key: char,
+    pub label: String,
+    pub checked: bool,
+    /// Has edits that have not been applied yet
+    pub changed: bool,
+    /// Can be written with the current privileges
+    pub writable: bool,
+}
+
+/// One entry of the effective PATH, see `App::effective_path`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EffectiveEntry {
+    pub scope: PathScope,
+    pub index: usize,
+    /// The earlier entry for the same directory, which makes this one redundant
+    pub shadowed_by: Option<(PathScope, usize)>,
+}
+
+/// Counts per panel, the user_ ones are the remote MACHINE's in remote mode
+pub struct Statistics {
+    pub machine_total: usize,
+    pub user_total: usize,
+    pub machine_dead: usize,
+    pub user_dead: usize,
+    pub machine_duplicates: usize,
+    pub user_duplicates: usize,
+    pub machine_non_normalized: usize,
+    pub user_non_normalized: usize,
+    pub machine_malformed: usize,
+    pub user_malformed: usize,
+    pub machine_offline: usize,
+    pub user_offline: usize,
+}
+
+/// Insert a new entry at the chosen position and select it
+/// Marks stay on the entries they were on; returns the new entry's index
+fn insert_at(
+    paths: &mut Vec<String>,
+    selected: &mut usize,
+    marked: &mut HashSet<usize>,
+    position: AddPosition,
+    path: String,
+) -> usize {
+    let index = match position {
+        AddPosition::Top => 0,
+        AddPosition::BeforeSelection => (*selected).min(paths.len()),
+        AddPosition::End => paths.len(),
+    };
+    paths.insert(index, path);
+    *marked = marked
+        .iter()
+        .map(|&i| if i >= index { i + 1 } else { i })
+        .collect();
+    *selected = index;
+    index
+}
+
+/// Entries in the same order, except that flagged ones come last
+fn demoted(paths: &[String], flagged: &[&str]) -> Vec<String> {
+    let (risky, rest): (Vec<String>, Vec<String>) = paths
+        .iter()
+        .cloned()
+        .partition(|p| flagged.contains(&p.as_str()));
+    rest.into_iter().chain(risky).collect()
+}
+
+/// Every occurrence of `find` replaced, ignoring ASCII case like Windows paths do
+/// None if the text does not contain it
+fn replace_ignore_case(text: &str, find: &str, with: &str) -> Option<String> {
+    if find.is_empty() {
+        return None;
+    }
+    let mut result = String::new();
+    let mut rest = text;
+    let mut found = false;
+    while !rest.is_empty() {
+        let hit = rest
+            .get(..find.len())
+            .is_some_and(|head| head.eq_ignore_ascii_case(find));
+        if hit {
+            result.push_str(with);
+            rest = &rest[find.len()..];
+            found = true;
+        } else {
+            let next = rest.chars().next().map_or(1, char::len_utf8);
+            result.push_str(&rest[..next]);
+            rest = &rest[next..];
+        }
+    }
+    found.then_some(result)
+}
+
+/// The path moved from one root to another, e.g. D:\Tools\bin from D:\ to E:\Apps
+/// None unless the path is the root itself or inside it
+fn replace_root(path: &str, from: &str, to: &str) -> Option<String> {
+    let from = from.trim_end_matches('\\');
+    if from.is_empty() {
+        return None;
+    }
+    let head = path.get(..from.len())?;
+    let rest = &path[from.len()..];
+    if !head.eq_ignore_ascii_case(from) || !(rest.is_empty() || rest.starts_with('\\')) {
+        return None;
+    }
+    if rest.is_empty() {
+        return Some(to.to_string());
+    }
+    Some(format!("{}{}", to.trim_end_matches('\\'), rest))
+}
+
+/// Drive root (C:\) or UNC share (\\server\share) of an absolute path
+fn path_root(path: &str) -> Option<String> {
+    if let Some(unc) = path.strip_prefix(r"\\") {
+        let mut parts = unc.split('\\').filter(|part| !part.is_empty());
+        let (server, share) = (parts.next()?, parts.next()?);
+        return Some(format!(r"\\{}\{}", server, share));
+    }
+    let bytes = path.as_bytes();
+    (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+        .then(|| format!("{}:\\", bytes[0].to_ascii_uppercase() as char))
+}
+
+/// Open a directory in the file manager without waiting for it
+fn open_folder(folder: &str) -> std::io::Result<()> {
+    let program = if cfg!(windows) {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    std::process::Command::new(program)
+        .arg(folder)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+/// A remote connection and the MACHINE entries read through it
+type RemoteRead = Result<(RemoteConnection, Vec<String>)>;
+
+/// Connect to a computer's registry and read its MACHINE PATH, the slow part of going remote
+fn connect_and_read(computer_name: &str) -> RemoteRead {
+    // A missing precondition is reported with its fix instead of an opaque error code
+    preflight::check(computer_name)?;
+    let connection = RemoteConnection::connect(computer_name)?;
+    let value = registry::read_path_remote(PathScope::Machine, &connection)?;
+    Ok((connection, registry::parse_path(&value)))
+}
+
+/// Whose profile per-user variables are expanded for, in the local panels or on the remote computer
+pub fn expansion_context(settings: &Settings, remote: bool) -> ExpansionContext {
+    let mut context = match &settings.profile_root {
+        Some(root) => ExpansionContext::for_profile(root),
+        None if remote => ExpansionContext::remote_default(),
+        None => ExpansionContext::default(),
+    };
+    context.ignored = settings.ignored.clone();
+    context
+}
+
+#[cfg(test)]
+impl App {
+    /// Create an App over fixed paths with a mock registry and the given filesystem,
+    /// without reading anything from the machine
+    pub(crate) fn for_test(
+        machine_paths: Vec<String>,
+        user_paths: Vec<String>,
+        fs: Box<dyn FileSystem>,
+    ) -> App {
+        let (machine_info, user_info) =
+            crate::path_analyzer::analyze_scopes_with_fs(&machine_paths, &user_paths, &*fs);
+        let registry = Box::new(crate::registry::MockRegistry::new(
+            &registry::join_paths(&user_paths),
+            &registry::join_paths(&machine_paths),
+        ));
+
+        App {
+            connection_mode: ConnectionMode::Local,
+            remote_connection: None,
             machine_paths: machine_paths.clone(),
             user_paths: user_paths.clone(),
             machine_info,
@@ -3363,21 +10499,137 @@ mod tests {
             backup_selected: 0,
             machine_scrollbar_state: ScrollbarState::default(),
             user_scrollbar_state: ScrollbarState::default(),
+            machine_scroll: 0,
+            user_scroll: 0,
+            remote_scroll: 0,
+            remote_positions: HashMap::new(),
+            filter_positions: HashMap::new(),
+            bookmarks: HashMap::new(),
+            pending_bookmark: false,
+            visual: None,
             should_exit: false,
             viewport_height: 20,
             pending_directory: String::new(),
+            pending_subdirectory: None,
+            pending_promotion: None,
+            pending_bulk_delete: None,
+            review_checked: Vec::new(),
+            review_selected: 0,
             processes_to_restart: Vec::new(),
+            process_selected: 0,
+            hide_services: false,
+            services_at_risk: Vec::new(),
+            installed_apps: None,
+            delete_owners: Vec::new(),
             theme: Theme::default(),
             theme_arg: None,
             filter_mode: FilterMode::None,
+            last_filter: FilterMode::None,
+            filter_panel: None,
+            sort_mode: SortMode::PathOrder,
             filter_menu_selected: 0,
             theme_list: Vec::new(),
             theme_selected: 0,
             original_theme: None,
             undo_stack: Vec::new(),
             redo_stack: Vec::new(),
+            availability_report: None,
+            availability_task: None,
+            directory_sizes: HashMap::new(),
+            size_task: None,
+            security_task: None,
+            update_task: None,
+            remote_task: None,
+            connecting_to: None,
+            failed_remote: None,
+            stop_registry_on_disconnect: None,
+            queued_remote_write: None,
+            remote_write_task: None,
+            update_check_quiet: false,
+            update: None,
+            download_task: None,
+            update_staged: None,
+            control: None,
+            security_report: None,
+            security_scroll: 0,
+            shim_task: None,
+            shim_findings: Vec::new(),
+            shim_scroll: 0,
+            toolchain_task: None,
+            toolchains: Vec::new(),
+            toolchain_scroll: 0,
+            where_lookup: Lookup::default(),
+            where_scroll: 0,
+            merge_pairs: Vec::new(),
+            merge_selected: 0,
+            nested_pairs: Vec::new(),
+            nested_selected: 0,
+            aging_rows: Vec::new(),
+            aging_selected: 0,
+            usage_rows: Vec::new(),
+            usage_selected: 0,
+            usage_days: 0,
+            ignore_selected: 0,
+            replace_find: String::new(),
+            replace_with: String::new(),
+            replace_rows: Vec::new(),
+            replace_selected: 0,
+            replace_migration: false,
+            raw_editor: None,
+            raw_editor_panel: Panel::Machine,
+            clipboard: String::new(),
+            host_list: Vec::new(),
+            host_selected: 0,
+            host_notes: None,
+            menu_submenu: None,
+            precedence_scroll: 0,
+            lengths_scroll: 0,
+            live_environment: LiveEnvironment::default(),
+            live_scroll: 0,
+            size_advisory: Advisory::default(),
+            size_acknowledged: false,
+            apply_user: true,
+            delete_unavailable: false,
+            apply_machine: true,
+            apply_remote: true,
+            fs,
+            registry,
+            machine_helper: Box::new(registry::MockRegistry::default()),
+            last_written: HashMap::new(),
+            external_changes: Vec::new(),
+            instance: None,
+            other_instances: Vec::new(),
+            backup_dir: std::env::temp_dir().join("pc-test-backups"),
+            last_apply: None,
+            pending_revert: None,
+            history_file: std::env::temp_dir().join("pc-test-history.jsonl"),
+            usage_file: std::env::temp_dir().join("pc-test-usage.log"),
+            variables_file: std::env::temp_dir().join("pc-test-variables.ini"),
+            settings_file: std::env::temp_dir().join("pc-test-config.ini"),
+            hosts_file: std::env::temp_dir().join("pc-test-hosts.toml"),
+            refresh_file: None,
+            dialog_offsets: HashMap::new(),
+            dialog_drag: None,
+            history: Vec::new(),
+            history_scroll: 0,
+            status_log: Vec::new(),
+            message_log_scroll: 0,
+            releases: Vec::new(),
+            release_selected: 0,
+            release_scroll: 0,
+            hovered: None,
+            settings: Settings::default(),
+            add_position: AddPosition::default(),
+            report_format: ReportFormat::default(),
+            add_target: None,
+            elevation_offered: false,
+            last_activity: std::time::Instant::now(),
+            locked_mode: Mode::Normal,
+            policy: Policy::default(),
+            profile: Profile::default(),
             last_click_time: std::time::Instant::now(),
             last_click_pos: (Panel::Machine, 0),
+            last_list_click: None,
             mode_enter_time: std::time::Instant::now(),
             file_browser_current_path: PathBuf::from("C:\\"),
             file_browser_entries: Vec::new(),
@@ -3385,296 +10637,3545 @@ mod tests {
             file_browser_scrollbar_state: ScrollbarState::new(0).position(0),
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::{MemoryFileSystem, RealFileSystem};
+    use crate::registry::MockRegistry;
+
+    // Helper function to create a test App without registry access
+    fn create_test_app(machine_paths: Vec<String>, user_paths: Vec<String>) -> App {
+        create_test_app_with_fs(machine_paths, user_paths, Box::new(RealFileSystem))
+    }
+
+    // Same as create_test_app, but checking existence against the given (usually fake) filesystem
+    fn create_test_app_with_fs(
+        machine_paths: Vec<String>,
+        user_paths: Vec<String>,
+        fs: Box<dyn FileSystem>,
+    ) -> App {
+        App::for_test(machine_paths, user_paths, fs)
+    }
+
+    #[test]
+    fn test_panel_toggle() {
+        assert_eq!(Panel::User.toggle(), Panel::Machine);
+        assert_eq!(Panel::Machine.toggle(), Panel::User);
+    }
+
+    #[test]
+    fn test_panel_scope() {
+        assert_eq!(Panel::User.scope(), PathScope::User);
+        assert_eq!(Panel::Machine.scope(), PathScope::Machine);
+    }
+
+    #[test]
+    fn test_app_initial_state() {
+        let app = create_test_app(
+            vec![r"C:\Windows".to_string()],
+            vec![r"C:\Users\Test".to_string()],
+        );
+
+        assert_eq!(app.machine_paths.len(), 1);
+        assert_eq!(app.user_paths.len(), 1);
+        assert_eq!(app.active_panel, Panel::User);
+        assert_eq!(app.machine_selected, 0);
+        assert_eq!(app.user_selected, 0);
+        assert!(!app.has_changes);
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn test_move_selection_down() {
+        let mut app = create_test_app(
+            vec![],
+            vec![
+                r"C:\Path1".to_string(),
+                r"C:\Path2".to_string(),
+                r"C:\Path3".to_string(),
+            ],
+        );
+
+        assert_eq!(app.user_selected, 0);
+
+        app.move_selection(1);
+        assert_eq!(app.user_selected, 1);
+
+        app.move_selection(1);
+        assert_eq!(app.user_selected, 2);
+
+        // Should not go beyond last item
+        app.move_selection(1);
+        assert_eq!(app.user_selected, 2);
+    }
+
+    #[test]
+    fn test_move_selection_up() {
+        let mut app = create_test_app(
+            vec![],
+            vec![
+                r"C:\Path1".to_string(),
+                r"C:\Path2".to_string(),
+                r"C:\Path3".to_string(),
+            ],
+        );
+
+        app.user_selected = 2;
+
+        app.move_selection(-1);
+        assert_eq!(app.user_selected, 1);
+
+        app.move_selection(-1);
+        assert_eq!(app.user_selected, 0);
+
+        // Should not go below 0
+        app.move_selection(-1);
+        assert_eq!(app.user_selected, 0);
+    }
+
+    #[test]
+    fn test_move_selection_to() {
+        let mut app = create_test_app(
+            vec![],
+            vec![
+                r"C:\Path1".to_string(),
+                r"C:\Path2".to_string(),
+                r"C:\Path3".to_string(),
+            ],
+        );
+
+        app.move_selection_to(2);
+        assert_eq!(app.user_selected, 2);
+
+        app.move_selection_to(0);
+        assert_eq!(app.user_selected, 0);
+    }
+
+    #[test]
+    fn test_toggle_mark() {
+        let mut app = create_test_app(
+            vec![],
+            vec![
+                r"C:\Path1".to_string(),
+                r"C:\Path2".to_string(),
+                r"C:\Path3".to_string(),
+            ],
+        );
+
+        // Mark first item (toggle_mark also moves selection down)
+        app.user_selected = 0;
+        app.toggle_mark();
+        assert!(app.user_marked.contains(&0));
+        assert_eq!(app.user_selected, 1); // Selection moved down
+
+        // Go back and unmark first item
+        app.user_selected = 0;
+        app.toggle_mark();
+        assert!(!app.user_marked.contains(&0));
+
+        // Test on machine panel
+        app.active_panel = Panel::Machine;
+        app.machine_paths = vec![r"C:\Windows".to_string(), r"C:\Temp".to_string()];
+        app.machine_selected = 0;
+        app.toggle_mark();
+        assert!(app.machine_marked.contains(&0));
+        assert_eq!(app.machine_selected, 1); // Selection moved down
+    }
+
+    #[test]
+    fn test_has_marked_items() {
+        let mut app = create_test_app(
+            vec![r"C:\Windows".to_string()],
+            vec![r"C:\Path1".to_string()],
+        );
+
+        assert!(!app.has_marked_items());
+
+        app.user_marked.insert(0);
+        assert!(app.has_marked_items());
+
+        app.user_marked.clear();
+        app.active_panel = Panel::Machine;
+        app.machine_marked.insert(0);
+        assert!(app.has_marked_items());
+    }
+
+    #[test]
+    fn test_visual_selection_marks_a_range() {
+        let user: Vec<String> = (0..5).map(|i| format!(r"C:\Tools\{}", i)).collect();
+        let mut app = create_test_app(vec![], user);
+        app.active_panel = Panel::User;
+        app.user_marked.insert(4);
+        let press = |app: &mut App, code: KeyCode| app.handle_input(KeyEvent::from(code)).unwrap();
+
+        // Moving extends the range from the anchor, moving back shrinks it
+        app.user_selected = 1;
+        press(&mut app, KeyCode::Char('V'));
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.user_marked, HashSet::from([1, 2, 3, 4]));
+        assert!(app
+            .status_message
+            .starts_with("-- VISUAL -- 3 entries added to 1 already marked"));
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.user_marked, HashSet::from([1, 2, 4]));
+
+        // Esc brings back the marks from before
+        press(&mut app, KeyCode::Esc);
+        assert!(app.visual.is_none());
+        assert_eq!(app.user_marked, HashSet::from([4]));
+
+        // Upwards works too, and any action applies to the range and ends visual mode
+        press(&mut app, KeyCode::Char('V'));
+        press(&mut app, KeyCode::Home);
+        assert_eq!(app.user_marked, HashSet::from([0, 1, 2, 4]));
+        press(&mut app, KeyCode::Delete);
+        assert!(app.visual.is_none());
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::DeleteSelected));
+        app.mode = Mode::Normal;
+
+        // V again keeps the range marked
+        app.user_marked.clear();
+        press(&mut app, KeyCode::Char('V'));
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Char('V'));
+        assert!(app.visual.is_none());
+        assert_eq!(app.user_marked, HashSet::from([0, 1]));
+        assert_eq!(
+            app.status_message,
+            "Marked 2 entries from the visual selection"
+        );
+
+        // The wheel moves the selection without extending, so it ends visual mode too
+        press(&mut app, KeyCode::Char('V'));
+        app.handle_mouse(
+            MouseEvent {
+                kind: MouseEventKind::ScrollDown,
+                column: 0,
+                row: 0,
+                modifiers: KeyModifiers::NONE,
+            },
+            Rect::new(0, 0, 120, 30),
+        )
+        .unwrap();
+        assert!(app.visual.is_none());
+        assert_eq!(app.user_marked, HashSet::from([0, 1]));
+    }
+
+    #[test]
+    fn test_has_marked_dead_paths() {
+        let mut app = create_test_app_with_fs(
+            vec![],
+            vec![
+                r"C:\Windows".to_string(),
+                r"C:\NonExistentPath123456".to_string(),
+            ],
+            Box::new(MemoryFileSystem::new().with_dir(r"C:\Windows")),
+        );
+        app.reanalyze();
+
+        // No marked items yet
+        assert!(!app.has_marked_dead_paths());
+
+        // Mark a valid path
+        app.user_marked.insert(0);
+        assert!(!app.has_marked_dead_paths());
+
+        // Mark a dead path
+        app.user_marked.insert(1);
+        assert!(app.has_marked_dead_paths());
+    }
+
+    #[test]
+    fn test_create_selected_dead_directory() {
+        let root = tempfile::tempdir().unwrap();
+        let missing = root.path().join("tool").join("bin");
+        let missing_entry = missing.to_string_lossy().to_string();
+        let mut app = create_test_app(
+            vec![],
+            vec![
+                root.path().to_string_lossy().to_string(),
+                missing_entry.clone(),
+            ],
+        );
+        app.active_panel = Panel::User;
+        assert!(app.selected_creatable_dead().is_none());
+        assert!(!app
+            .hint_actions()
+            .iter()
+            .any(|(action, _)| *action == HintAction::CreateDir));
+
+        // Offered on the dead entry without marking it
+        app.user_selected = 1;
+        assert!(app
+            .hint_actions()
+            .contains(&(HintAction::CreateDir, "MkDir")));
+        app.handle_input(HintAction::CreateDir.key_event()).unwrap();
+        assert_eq!(
+            app.mode,
+            Mode::Confirm(ConfirmAction::CreateSelectedDirectory)
+        );
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+
+        assert!(missing.is_dir());
+        assert_eq!(app.user_info[1].status, PathStatus::Valid);
+        assert_eq!(app.user_paths.len(), 2);
+        assert!(app.user_marked.is_empty());
+        assert!(app.selected_creatable_dead().is_none());
+    }
+
+    #[test]
+    fn test_mode_transitions() {
+        let mut app = create_test_app(vec![], vec![]);
+
+        assert_eq!(app.mode, Mode::Normal);
+
+        app.mode = Mode::Help;
+        assert_eq!(app.mode, Mode::Help);
+
+        app.mode = Mode::Confirm(ConfirmAction::Exit);
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::Exit));
+
+        app.mode = Mode::Input(InputMode::AddPath);
+        assert_eq!(app.mode, Mode::Input(InputMode::AddPath));
+
+        app.mode = Mode::BackupList;
+        assert_eq!(app.mode, Mode::BackupList);
+    }
+
+    #[test]
+    fn test_can_create_directory() {
+        // Valid paths
+        assert!(App::can_create_directory(r"C:\NewFolder"));
+        assert!(App::can_create_directory(r"C:\Path\To\NewFolder"));
+
+        // Network paths should not be creatable
+        assert!(!App::can_create_directory(r"\\server\share\folder"));
+
+        // Paths with invalid characters
+        assert!(!App::can_create_directory(r"C:\Invalid|Path"));
+        assert!(!App::can_create_directory(r"C:\Invalid<Path"));
+        assert!(!App::can_create_directory(r"C:\Invalid>Path"));
+    }
+
+    #[test]
+    fn test_get_statistics() {
+        let app = create_test_app_with_fs(
+            vec![r"C:\Windows".to_string(), r"C:\NonExistent1".to_string()],
+            vec![
+                r"C:\Users".to_string(),
+                r"C:\windows".to_string(), // Duplicate of machine path
+                r"C:\NonExistent2".to_string(),
+            ],
+            Box::new(
+                MemoryFileSystem::new()
+                    .with_dir(r"C:\Windows")
+                    .with_dir(r"C:\Users"),
+            ),
+        );
+
+        let stats = app.get_statistics();
+
+        assert_eq!(stats.machine_total, 2);
+        assert_eq!(stats.user_total, 3);
+        assert_eq!(stats.machine_dead, 1); // C:\NonExistent1
+        assert_eq!(stats.user_dead, 1); // C:\NonExistent2
+                                        // Duplicates: C:\windows is duplicate of C:\Windows
+        assert!(stats.machine_duplicates > 0 || stats.user_duplicates > 0);
+    }
+
+    #[test]
+    fn test_confirm_exit() {
+        let mut app = create_test_app(vec![], vec![]);
+
+        // When there are no changes, should exit directly
+        app.confirm_exit();
+        assert!(app.should_exit);
+
+        // When there are changes, should confirm first
+        let mut app = create_test_app(vec![], vec![]);
+        app.has_changes = true;
+        app.confirm_exit();
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::Exit));
+    }
+
+    #[test]
+    fn test_delete_marked() {
+        let mut app = create_test_app(
+            vec![],
+            vec![
+                r"C:\Path1".to_string(),
+                r"C:\Path2".to_string(),
+                r"C:\Path3".to_string(),
+            ],
+        );
+
+        // Mark items 0 and 2
+        app.user_marked.insert(0);
+        app.user_marked.insert(2);
+
+        let result = app.delete_marked();
+        assert!(result.is_ok());
+
+        // Should have 1 path remaining (the middle one)
+        assert_eq!(app.user_paths.len(), 1);
+        assert_eq!(app.user_paths[0], r"C:\Path2");
+
+        // Marked set should be cleared
+        assert!(app.user_marked.is_empty());
+
+        // Should have changes
+        assert!(app.has_changes);
+    }
+
+    #[test]
+    fn test_delete_confirmation_names_installed_programs() {
+        let mut app = create_test_app(
+            vec![r"C:\Program Files\nodejs\".to_string()],
+            vec![r"C:\Tools".to_string()],
+        );
+        app.installed_apps = Some(vec![InstalledApp {
+            name: "Node.js".to_string(),
+            version: Some("20.1.0".to_string()),
+            location: r"C:\Program Files\nodejs".to_string(),
+        }]);
+
+        app.machine_marked.insert(0);
+        app.user_marked.insert(0);
+        app.handle_input(KeyEvent::new(KeyCode::Delete, KeyModifiers::NONE))
+            .unwrap();
+
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::DeleteSelected));
+        assert_eq!(
+            app.delete_owners,
+            vec![(
+                r"C:\Program Files\nodejs\".to_string(),
+                "Node.js 20.1.0".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_normalize_selected() {
+        // Test with an absolute path that should be collapsed to an env var
+        let systemroot = std::env::var("SYSTEMROOT").unwrap_or_default();
+        if systemroot.is_empty() {
+            return; // Skip test if SYSTEMROOT is not set
+        }
+
+        let absolute_path = format!(r"{}\System32", systemroot);
+        let mut app = create_test_app(vec![], vec![absolute_path.clone()]);
+        app.reanalyze();
+
+        app.user_selected = 0;
+        // normalize_selected works on marked paths, so mark it first
+        app.user_marked.insert(0);
+        app.normalize_selected();
+
+        // Path should be normalized to use environment variables
+        assert!(
+            app.user_paths[0].contains('%'),
+            "Expected normalized path to contain env var, got: {}",
+            app.user_paths[0]
+        );
+        assert!(app.has_changes);
+    }
+
+    #[test]
+    fn test_clean_malformed_entries() {
+        let mut app = create_test_app(
+            vec![r"C:\Windows".to_string(), "".to_string()],
+            vec![
+                r" C:\Tools ".to_string(),
+                r#""C:\Program Files\App""#.to_string(),
+                r#""C:\Odd;Name""#.to_string(),
+                "".to_string(),
+            ],
+        );
+
+        app.clean_malformed_entries();
+
+        assert_eq!(app.machine_paths, vec![r"C:\Windows".to_string()]);
+        assert_eq!(
+            app.user_paths,
+            vec![
+                r"C:\Tools".to_string(),
+                r"C:\Program Files\App".to_string(),
+                r#""C:\Odd;Name""#.to_string(),
+            ]
+        );
+        assert!(app.has_changes);
+
+        // Undo everything (edits and deletions for both panels)
+        while app.can_undo() {
+            app.undo().unwrap();
+        }
+        assert_eq!(
+            app.machine_paths,
+            vec![r"C:\Windows".to_string(), "".to_string()]
+        );
+        assert_eq!(app.user_paths[0], r" C:\Tools ");
+        assert_eq!(app.user_paths[3], "");
+    }
+
+    #[test]
+    fn test_delete_all_dead_keeps_offline_drives() {
+        let mut app = create_test_app_with_fs(
+            vec![],
+            vec![
+                r"C:\Windows".to_string(),
+                r"C:\NonExistent123".to_string(),
+                r"Z:\tools".to_string(),
+            ],
+            Box::new(MemoryFileSystem::new().with_dir(r"C:\Windows")),
+        );
+
+        // Pretend Z: is a disconnected mapped drive
+        app.user_info[2].exists = false;
+        app.user_info[2].is_offline_drive = true;
+
+        app.delete_all_dead().unwrap();
+
+        assert_eq!(
+            app.user_paths,
+            vec![r"C:\Windows".to_string(), r"Z:\tools".to_string()]
+        );
+        assert!(app.has_changes);
+    }
+
+    #[test]
+    fn test_delete_all_dead_includes_offline_drives_when_asked() {
+        let mut app = create_test_app_with_fs(
+            vec![],
+            vec![r"C:\Windows".to_string(), r"Z:\tools".to_string()],
+            Box::new(MemoryFileSystem::new().with_dir(r"C:\Windows")),
+        );
+        app.user_info[1].exists = false;
+        app.user_info[1].is_offline_drive = true;
+
+        // Nothing is dead, but the dialog still opens to offer the offline entry
+        app.confirm_bulk_delete(ConfirmAction::DeleteAllDead);
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::DeleteAllDead));
+        assert!(app.pending_bulk_delete.as_ref().unwrap().is_empty());
+
+        app.handle_input(KeyEvent::from(KeyCode::Char('o')))
+            .unwrap();
+        assert!(app.delete_unavailable);
+        assert_eq!(app.pending_bulk_delete.as_ref().unwrap().len(), 1);
+
+        app.handle_input(KeyEvent::from(KeyCode::Char('y')))
+            .unwrap();
+        assert_eq!(app.user_paths, vec![r"C:\Windows".to_string()]);
+        assert_eq!(app.status_message, "Deleted 1 dead path(s)");
+        assert!(!app.delete_unavailable);
+    }
+
+    #[test]
+    fn test_delete_all_dead_waits_for_grace_period() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = create_test_app_with_fs(
+            vec![],
+            vec![
+                r"C:\Windows".to_string(),
+                r"C:\Gone".to_string(),
+                r"D:\Vault\bin".to_string(),
+            ],
+            Box::new(MemoryFileSystem::new().with_dir(r"C:\Windows")),
+        );
+        app.settings_file = dir.path().join("config.ini");
+        app.settings.dead_grace_days = 7;
+        let today = Local::now().date_naive();
+        app.settings.dead_since = HashMap::from([
+            (r"c:\gone".to_string(), today - chrono::Duration::days(30)),
+            // Found again since, forgotten
+            (r"c:\windows".to_string(), today - chrono::Duration::days(3)),
+        ]);
+
+        // The unmounted vault is first seen missing today
+        app.track_dead_entries();
+        assert_eq!(app.settings.dead_since.get(r"d:\vault\bin"), Some(&today));
+        assert_eq!(app.settings.dead_since.len(), 2);
+        let saved = Settings::load(&app.settings_file).unwrap();
+        assert_eq!(saved.dead_since, app.settings.dead_since);
+
+        app.delete_all_dead().unwrap();
+        assert_eq!(
+            app.user_paths,
+            vec![r"C:\Windows".to_string(), r"D:\Vault\bin".to_string()]
+        );
+        assert_eq!(
+            app.status_message,
+            "Deleted 1 dead path(s), kept 1 dead for less than 7 days"
+        );
+
+        app.confirm_bulk_delete(ConfirmAction::DeleteAllDead);
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(
+            app.status_message,
+            "No dead paths to delete, kept 1 dead for less than 7 days"
+        );
+    }
+
+    #[test]
+    fn test_delete_all_duplicates_previewed_before_confirming() {
+        let mut app = create_test_app(
+            vec![r"C:\Windows".to_string(), r"C:\windows\".to_string()],
+            vec![
+                r"C:\Tools".to_string(),
+                r"C:\Windows".to_string(),
+                r"C:\Tools".to_string(),
+            ],
+        );
+        let press = |app: &mut App, code| {
+            app.handle_input(KeyEvent::new(code, KeyModifiers::NONE))
+                .unwrap()
+        };
+
+        press(&mut app, KeyCode::F(7));
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::DeleteAllDuplicates));
+        let plan = app.pending_bulk_delete.clone().unwrap();
+        assert_eq!(plan.machine, vec![(1, r"C:\windows\".to_string())]);
+        assert_eq!(
+            plan.user,
+            vec![(1, r"C:\Windows".to_string()), (2, r"C:\Tools".to_string())]
+        );
+
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.machine_paths, vec![r"C:\Windows".to_string()]);
+        assert_eq!(app.user_paths, vec![r"C:\Tools".to_string()]);
+        assert!(app.pending_bulk_delete.is_none());
+
+        // Nothing left to remove, so there is nothing to confirm
+        press(&mut app, KeyCode::F(7));
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn test_bulk_delete_over_threshold_is_reviewed() {
+        let mut app = create_test_app(vec![], (1..=4).map(|n| format!(r"C:\Gone{}", n)).collect());
+        app.settings.review_threshold = Some(3);
+        let press = |app: &mut App, code| {
+            app.handle_input(KeyEvent::new(code, KeyModifiers::NONE))
+                .unwrap()
+        };
+
+        press(&mut app, KeyCode::F(8));
+        assert_eq!(app.mode, Mode::BulkReview);
+        assert_eq!(app.review_checked, vec![true; 4]);
+
+        // Keep the second entry, Space moves on to the next one
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Char(' '));
+        assert_eq!(app.review_selected, 2);
+        press(&mut app, KeyCode::Enter);
+
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.user_paths, vec![r"C:\Gone2".to_string()]);
+        assert_eq!(app.status_message, "Deleted 3 dead path(s)");
+
+        // Unchecking everything deletes nothing
+        app.user_marked.extend([0]);
+        app.settings.review_threshold = Some(0);
+        press(&mut app, KeyCode::Delete);
+        assert_eq!(app.mode, Mode::BulkReview);
+        press(&mut app, KeyCode::Char('a'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.user_paths, vec![r"C:\Gone2".to_string()]);
+    }
+
+    #[test]
+    fn test_startup_flows() {
+        let fs = MemoryFileSystem::new().with_dir(r"C:\Windows");
+        let user = vec![
+            r"C:\Windows".to_string(),
+            r"C:\Gone".to_string(),
+            r"c:\windows".to_string(),
+        ];
+        let mut app = create_test_app_with_fs(vec![], user, Box::new(fs));
+
+        app.start_flow(StartupFlow::PruneDead);
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::DeleteAllDead));
+        assert_eq!(app.user_marked, HashSet::from([1]));
+        app.handle_input(KeyEvent::from(KeyCode::Char('y')))
+            .unwrap();
+        assert_eq!(app.user_paths, vec![r"C:\Windows", r"c:\windows"]);
+        assert!(app.user_marked.is_empty());
+
+        app.start_flow(StartupFlow::Dedupe);
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::DeleteAllDuplicates));
+        assert_eq!(app.user_marked, HashSet::from([1]));
+
+        app.mode = Mode::Normal;
+        app.start_flow(StartupFlow::Add(r#"C:\Tools\bin""#.to_string()));
+        assert_eq!(app.mode, Mode::Input(InputMode::AddPath));
+        assert_eq!(app.input_buffer, r"C:\Tools\bin");
+    }
+
+    #[test]
+    fn test_refresh_media_status() {
+        let mut app = create_test_app_with_fs(
+            vec![],
+            vec![r"C:\Windows".to_string()],
+            Box::new(MemoryFileSystem::new().with_dir(r"C:\Windows")),
+        );
+
+        // Pretend the entry was on ejected media that has just been inserted
+        app.user_info[0].exists = false;
+        app.user_info[0].is_removable_drive = true;
+        app.user_info[0].is_media_missing = true;
+
+        // Nothing happens while a dialog is open
+        app.mode = Mode::Help;
+        assert!(!app.refresh_media_status());
+
+        app.mode = Mode::Normal;
+        assert!(app.refresh_media_status());
+        assert!(app.user_info[0].exists);
+
+        // Already up to date
+        assert!(!app.refresh_media_status());
+    }
+
+    #[test]
+    fn test_directory_size_is_measured_once() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("sdk.exe"), vec![0u8; 2048]).unwrap();
+        let mut app = create_test_app(vec![], vec![dir.path().display().to_string()]);
+        app.active_panel = Panel::User;
+        let wait = |app: &mut App| {
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+            while !app.poll_background_tasks() {
+                assert!(std::time::Instant::now() < deadline);
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            }
+        };
+
+        // Off by default, S in the dialog measures it
+        app.handle_input(KeyEvent::from(KeyCode::Char('i')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::PathDetails);
+        assert!(app.size_task.is_none());
+        app.handle_input(KeyEvent::from(KeyCode::Char('s')))
+            .unwrap();
+        assert_eq!(
+            app.busy_task().map(|(label, _)| label),
+            Some("Measuring directory size")
+        );
+        wait(&mut app);
+        let size = app.selected_directory_size().unwrap().as_ref().unwrap();
+        assert_eq!((size.bytes, size.files), (2048, 1));
+
+        // Opening the dialog again with the setting on reuses the cached size
+        app.settings.directory_sizes = true;
+        app.handle_input(KeyEvent::from(KeyCode::Esc)).unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Char('i')))
+            .unwrap();
+        assert!(app.size_task.is_none());
+        std::fs::write(dir.path().join("more.dll"), vec![0u8; 1024]).unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Char('s')))
+            .unwrap();
+        wait(&mut app);
+        assert_eq!(
+            app.selected_directory_size()
+                .unwrap()
+                .as_ref()
+                .unwrap()
+                .bytes,
+            3072
+        );
+    }
+
+    #[test]
+    fn test_check_path_availability() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("tool.exe"), b"MZ").unwrap();
+        let mut app = create_test_app(vec![], vec![dir.path().display().to_string()]);
+
+        // Only OneDrive folders are checked
+        app.check_path_availability();
+        assert!(app.availability_report.is_none());
+
+        app.user_info[0].is_onedrive = true;
+        app.check_path_availability();
+        assert_eq!(
+            app.busy_task().map(|(label, _)| label),
+            Some("Checking file availability")
+        );
+
+        // The count arrives from the worker thread
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !app.poll_background_tasks() {
+            assert!(std::time::Instant::now() < deadline);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert!(app.busy_task().is_none());
+        assert_eq!(
+            app.availability_report.as_deref(),
+            Some("All 1 file(s) are available offline")
+        );
+    }
+
+    #[test]
+    fn test_counterpart_indices() {
+        let mut app = create_test_app_with_fs(
+            vec![r"C:\Shared".to_string(), r"C:\Machine".to_string()],
+            vec![
+                r"C:\Tools".to_string(),
+                r"c:\shared\".to_string(),
+                r"C:\SHARED".to_string(),
+            ],
+            Box::new(MemoryFileSystem::new().with_dir(r"C:\Shared")),
+        );
+
+        // Selected MACHINE entry is matched in the USER panel only
+        app.active_panel = Panel::Machine;
+        app.machine_selected = 0;
+        assert_eq!(app.counterpart_indices(Panel::User), HashSet::from([1, 2]));
+        assert!(app.counterpart_indices(Panel::Machine).is_empty());
+
+        // Follows the selection
+        app.machine_selected = 1;
+        assert!(app.counterpart_indices(Panel::User).is_empty());
+
+        app.active_panel = Panel::User;
+        app.user_selected = 2;
+        assert_eq!(app.counterpart_indices(Panel::Machine), HashSet::from([0]));
+    }
+
+    #[test]
+    fn test_effective_path() {
+        let app = create_test_app_with_fs(
+            vec![r"C:\Shared".to_string(), r"C:\Machine".to_string()],
+            vec![
+                r"C:\Tools".to_string(),
+                r"c:\shared\".to_string(),
+                r"C:\Tools".to_string(),
+            ],
+            Box::new(
+                MemoryFileSystem::new()
+                    .with_dir(r"C:\Shared")
+                    .with_dir(r"C:\Machine")
+                    .with_dir(r"C:\Tools"),
+            ),
+        );
+
+        let entries = app.effective_path();
+        let order: Vec<_> = entries.iter().map(|e| (e.scope, e.index)).collect();
+        assert_eq!(
+            order,
+            vec![
+                (PathScope::Machine, 0),
+                (PathScope::Machine, 1),
+                (PathScope::User, 0),
+                (PathScope::User, 1),
+                (PathScope::User, 2),
+            ]
+        );
+        assert_eq!(entries[0].shadowed_by, None);
+        assert_eq!(entries[2].shadowed_by, None);
+        // MACHINE always wins over USER, and the first USER copy over later ones
+        assert_eq!(entries[3].shadowed_by, Some((PathScope::Machine, 0)));
+        assert_eq!(entries[4].shadowed_by, Some((PathScope::User, 0)));
+    }
+
+    #[test]
+    fn test_filter_by_kind() {
+        let mut app = create_test_app(
+            vec![],
+            vec![
+                r"C:\Tools".to_string(),
+                r"%SystemRoot%\System32".to_string(),
+                r"\\server\share\bin".to_string(),
+            ],
+        );
+
+        app.filter_mode = FilterMode::Kind(PathKind::EnvVar);
+        assert_eq!(
+            app.get_filtered_indices(Panel::User, &app.user_info),
+            vec![1]
+        );
+
+        app.filter_mode = FilterMode::Kind(PathKind::Unc);
+        assert_eq!(
+            app.get_filtered_indices(Panel::User, &app.user_info),
+            vec![2]
+        );
+
+        // The kind filters come after the five status filters in the menu
+        app.mode = Mode::FilterMenu;
+        app.filter_menu_selected = FILTER_MENU_ITEMS - 1;
+        app.handle_filter_menu_input(KeyEvent::from(KeyCode::Enter))
+            .unwrap();
+        assert_eq!(app.filter_mode, FilterMode::Kind(PathKind::EnvVar));
+    }
+
+    #[test]
+    fn test_key_hint_clicks_hit_rendered_text() {
+        let mut app = create_test_app(vec![r"C:\Tools".to_string()], vec![]);
+        let hints = app.key_hints(120);
+        let keys: Vec<String> = hints.iter().map(|h| h.key.clone() + h.label).collect();
+        assert_eq!(
+            keys,
+            [
+                "1Help",
+                "2Mark",
+                "3Del",
+                "4Add",
+                "/Filter",
+                "Ctrl+SSave",
+                "Ctrl+EElevate",
+                "10Quit"
+            ]
+        );
+        assert_eq!(hints[0].x, 0);
+        let last = hints.last().unwrap();
+        assert!(last.x + last.width() <= 120);
+
+        let click = |app: &mut App, column| {
+            app.handle_mouse(
+                MouseEvent {
+                    kind: MouseEventKind::Down(MouseButton::Left),
+                    column,
+                    row: 23,
+                    modifiers: KeyModifiers::NONE,
+                },
+                Rect::new(0, 0, 120, 24),
+            )
+            .unwrap()
+        };
+        // Last column of "/Filter", then the gap right after it
+        let filter = &hints[4];
+        click(&mut app, filter.x + filter.width());
+        assert_eq!(app.mode, Mode::Normal);
+        click(&mut app, filter.x + filter.width() - 1);
+        assert_eq!(app.mode, Mode::FilterMenu);
+
+        app.mode = Mode::Normal;
+        click(&mut app, last.x);
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::Exit));
+
+        // Keys are shown the way they are bound
+        assert_eq!(HintAction::Unmark.key_label(), "Ctrl+Shift+U");
+    }
+
+    #[test]
+    fn test_list_dialogs_follow_the_mouse() {
+        let mut app = create_test_app(vec![], vec![]);
+        let size = Rect::new(0, 0, 120, 30);
+        let mouse = |app: &mut App, kind, column, row| {
+            app.handle_mouse(
+                MouseEvent {
+                    kind,
+                    column,
+                    row,
+                    modifiers: KeyModifiers::NONE,
+                },
+                size,
+            )
+            .unwrap()
+        };
+        let click = MouseEventKind::Down(MouseButton::Left);
+
+        app.backup_list = ["a.json", "b.json", "c.json"].map(PathBuf::from).to_vec();
+        app.mode = Mode::BackupList;
+        let rows = app.list_layout(size).unwrap().rows;
+        mouse(&mut app, click, rows.x + 2, rows.y + 2);
+        assert_eq!(app.backup_selected, 2);
+        // Below the last backup is not an item
+        mouse(&mut app, click, rows.x + 2, rows.y + 3);
+        assert_eq!(app.backup_selected, 2);
+        mouse(&mut app, MouseEventKind::ScrollUp, 0, 0);
+        assert_eq!(app.backup_selected, 1);
+        // A double-click opens the item like Enter
+        mouse(&mut app, click, rows.x, rows.y + 1);
+        assert_eq!(app.mode, Mode::BackupList);
+        mouse(&mut app, click, rows.x, rows.y + 1);
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::RestoreBackup));
+
+        // Filters take two lines each
+        app.mode = Mode::FilterMenu;
+        app.filter_menu_selected = 0;
+        let rows = app.list_layout(size).unwrap().rows;
+        mouse(&mut app, click, rows.x, rows.y + 3);
+        assert_eq!(app.filter_menu_selected, 1);
+        mouse(&mut app, click, rows.x, rows.y + 2);
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.filter_mode, FilterMode::Dead);
+
+        // The file browser list is scrolled to keep the selected entry in view
+        app.file_browser_entries = (0..40)
+            .map(|i| DirectoryEntry {
+                name: format!("dir{}", i),
+                path: PathBuf::from(format!(r"C:\dir{}", i)),
+                is_parent: false,
+                is_drive: false,
+            })
+            .collect();
+        app.file_browser_selected = 39;
+        app.mode = Mode::FileBrowser;
+        let layout = app.list_layout(size).unwrap();
+        assert_eq!(layout.offset, 40 - layout.rows.height as usize);
+        mouse(&mut app, click, layout.rows.x + 1, layout.rows.y);
+        assert_eq!(app.file_browser_selected, layout.offset);
+        mouse(&mut app, MouseEventKind::ScrollDown, 0, 0);
+        assert_eq!(app.file_browser_selected, layout.offset + 1);
+    }
+
+    #[test]
+    fn test_quick_actions_row() {
+        let mut app = create_test_app_with_fs(
+            vec![r"C:\Windows".to_string(), r"C:\Gone".to_string()],
+            vec![],
+            Box::new(MemoryFileSystem::new().with_dir(r"C:\Windows")),
+        );
+        app.reanalyze();
+        app.active_panel = Panel::Machine;
+        let dir = tempfile::tempdir().unwrap();
+        app.settings_file = dir.path().join("config.ini");
+
+        app.update_viewport_height(30);
+        assert_eq!(app.viewport_height, 21);
+        app.execute_menu_action(crate::menu::MenuAction::ToggleQuickActions)
+            .unwrap();
+        assert!(app.settings.quick_actions);
+        assert!(Settings::load(&app.settings_file).unwrap().quick_actions);
+        app.update_viewport_height(30);
+        assert_eq!(app.viewport_height, 20);
+
+        let labels = |app: &App| -> Vec<&str> {
+            app.quick_buttons()
+                .iter()
+                .map(|button| button.action.label())
+                .collect()
+        };
+        assert_eq!(labels(&app), ["Edit", "Delete", "Open", "Info"]);
+        // A dead entry can't be opened, marked entries are deleted from the hint bar
+        app.machine_selected = 1;
+        assert_eq!(labels(&app), ["Edit", "Delete", "Info"]);
+        app.machine_marked.insert(0);
+        assert_eq!(labels(&app), ["Edit", "Info"]);
+        app.machine_marked.clear();
+
+        // The row sits right above the status bar
+        let click = |app: &mut App, column, row| {
+            app.handle_mouse(
+                MouseEvent {
+                    kind: MouseEventKind::Down(MouseButton::Left),
+                    column,
+                    row,
+                    modifiers: KeyModifiers::NONE,
+                },
+                Rect::new(0, 0, 120, 30),
+            )
+            .unwrap()
+        };
+        let buttons = app.quick_buttons();
+        click(&mut app, buttons[2].x, 24);
+        assert_eq!(app.mode, Mode::PathDetails);
+
+        app.mode = Mode::Normal;
+        click(&mut app, buttons[1].x + buttons[1].width() - 1, 24);
+        assert!(matches!(app.mode, Mode::Confirm(_)));
+        assert_eq!(app.machine_marked, HashSet::from([1]));
+    }
+
+    #[test]
+    fn test_panel_title_clicks() {
+        let mut app = create_test_app(
+            vec![],
+            vec![r"C:".to_string(), r"C:".to_string(), r"C:\c".to_string()],
+        );
+        let size = Rect::new(0, 0, 80, 24);
+        let click = |app: &mut App, x, y| {
+            app.handle_mouse_click(x, y, size, KeyModifiers::NONE)
+                .unwrap()
+        };
+
+        // " USER" starts right after the corner of the right panel
+        click(&mut app, 42, 3);
+        assert_eq!(app.active_panel, Panel::User);
+        assert_eq!(app.sort_mode, SortMode::Name);
+        assert_eq!(app.display_indices(Panel::User), vec![1, 0, 2]);
+
+        // Rows and arrow keys follow the sorted order
+        click(&mut app, 50, 4);
+        assert_eq!(app.user_selected, 1);
+        app.move_selection(1);
+        assert_eq!(app.user_selected, 0);
+
+        // The badge after " USER (by name) " switches the filter off and back on
+        app.filter_mode = FilterMode::Valid;
+        assert_eq!(app.panel_title(Panel::User)[2].1, "[Valid 0/3] ");
+        click(&mut app, 58, 3);
+        assert_eq!(app.filter_mode, FilterMode::None);
+        assert_eq!(app.panel_title(Panel::User)[2].1, "[filter off] ");
+        click(&mut app, 58, 3);
+        assert_eq!(app.filter_mode, FilterMode::Valid);
+    }
+
+    #[test]
+    fn test_panel_descriptors_in_remote_mode() {
+        let mut app = create_test_app(
+            vec![r"C:\Local".to_string()],
+            vec![r"C:\User".to_string(), r"C:\User\bin".to_string()],
+        );
+        app.connection_mode = ConnectionMode::Remote;
+        app.remote_machine_paths = vec![
+            r"C:\Remote".to_string(),
+            r"C:\Remote".to_string(),
+            r"C:\Other".to_string(),
+        ];
+        app.reanalyze();
+
+        // The right panel describes the remote MACHINE, not the local USER under it
+        let remote = app.panel_descriptor(Panel::User);
+        assert_eq!(remote.label, "REMOTE MACHINE");
+        assert_eq!((remote.shown, remote.total), (3, 3));
+        assert_eq!(remote.access, PanelAccess::Writable);
+        assert_eq!(app.panel_descriptor(Panel::Machine).label, "LOCAL MACHINE");
+        let stats = app.get_statistics();
+        assert_eq!((stats.user_total, stats.user_duplicates), (3, 2));
+        assert_eq!(app.header_stats()[1].1, "L:1 ");
+        assert_eq!(app.header_stats()[2].1, "R:3");
+
+        // Each panel counts against its own filter
+        app.filter_mode = FilterMode::Duplicates;
+        app.filter_panel = Some(Panel::User);
+        assert_eq!(app.panel_title(Panel::User)[2].1, "[Duplicates 2/3] ");
+        assert_eq!(app.panel_title(Panel::Machine)[2].1, "");
+
+        // A write waiting to be retried, and a profile that cannot write at all
+        app.queued_remote_write = Some(QueuedWrite::new(
+            "SRV01",
+            vec![],
+            vec![],
+            String::new(),
+            std::time::Instant::now(),
+        ));
+        assert_eq!(app.panel_title(Panel::User)[1].1, "[QUEUED] ");
+        app.is_admin = true;
+        assert_eq!(app.panel_title(Panel::Machine)[1].1, "");
+        app.profile = Profile::resolve("helpdesk", &Settings::default()).unwrap();
+        assert_eq!(
+            app.panel_descriptor(Panel::Machine).access,
+            PanelAccess::ReadOnly
+        );
+    }
+
+    #[test]
+    fn test_header_stat_clicks() {
+        let fs = MemoryFileSystem::new().with_dir(r"C:\Windows");
+        let mut app = create_test_app_with_fs(
+            vec![r"C:\Windows".to_string(), r"C:\Gone".to_string()],
+            vec![r"C:\Missing".to_string(), r"C:\Windows".to_string()],
+            Box::new(fs),
+        );
+        app.active_panel = Panel::User;
+        // First column of a region of the header line
+        let column = |app: &App, wanted: HeaderRegion| {
+            let parts = app.header_stats();
+            let end = parts
+                .iter()
+                .position(|(region, _)| *region == wanted)
+                .unwrap();
+            parts[..end]
+                .iter()
+                .map(|(_, text)| text.chars().count() as u16)
+                .sum::<u16>()
+        };
+        let click = |app: &mut App, column| {
+            app.handle_mouse(
+                MouseEvent {
+                    kind: MouseEventKind::Down(MouseButton::Left),
+                    column,
+                    row: 1,
+                    modifiers: KeyModifiers::NONE,
+                },
+                Rect::new(0, 0, 120, 24),
+            )
+            .unwrap()
+        };
+
+        // "Dead: M:1" filters MACHINE only and focuses it
+        let dead = column(&app, HeaderRegion::Dead(Panel::Machine));
+        click(&mut app, dead);
+        assert_eq!(app.active_panel, Panel::Machine);
+        assert_eq!(app.panel_filter(Panel::Machine), FilterMode::Dead);
+        assert_eq!(app.panel_filter(Panel::User), FilterMode::None);
+        assert_eq!(app.display_indices(Panel::Machine), vec![1]);
+        assert_eq!(app.display_indices(Panel::User), vec![0, 1]);
+        assert_eq!(app.machine_selected, 1);
+        assert_eq!(app.panel_title(Panel::User)[2].1, "");
+
+        // Another count moves the filter, the same count again switches it off
+        let duplicates = column(&app, HeaderRegion::Duplicates(Panel::User));
+        click(&mut app, duplicates);
+        assert_eq!(app.active_panel, Panel::User);
+        assert_eq!(app.panel_filter(Panel::Machine), FilterMode::None);
+        assert_eq!(app.panel_filter(Panel::User), FilterMode::Duplicates);
+        click(&mut app, duplicates);
+        assert_eq!(app.filter_mode, FilterMode::None);
+        assert_eq!(app.panel_title(Panel::User)[2].1, "[filter off] ");
+        assert_eq!(app.panel_title(Panel::Machine)[2].1, "");
+
+        // Labels do nothing, a total shows the panel unfiltered
+        click(&mut app, dead);
+        click(&mut app, 0);
+        assert_eq!(app.filter_mode, FilterMode::Dead);
+        let total = column(&app, HeaderRegion::Total(Panel::Machine));
+        click(&mut app, total);
+        assert_eq!(app.filter_mode, FilterMode::None);
+    }
+
+    #[test]
+    fn test_resize_and_move_dialog() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = create_test_app(vec![], vec![]);
+        app.settings_file = dir.path().join("config.ini");
+        app.mode = Mode::Help;
+
+        // Ctrl+arrows resize and save the size
+        app.handle_input(KeyEvent::new(KeyCode::Right, KeyModifiers::CONTROL))
+            .unwrap();
+        app.handle_input(KeyEvent::new(KeyCode::Down, KeyModifiers::CONTROL))
+            .unwrap();
+        assert_eq!(app.dialog_size(Dialog::Help), (60, 55));
+        assert_eq!(
+            std::fs::read_to_string(&app.settings_file).unwrap(),
+            "[dialogs]\nhelp = 60x55\n"
+        );
+        assert_eq!(app.mode, Mode::Help);
+
+        let size = Rect::new(0, 0, 100, 40);
+        let mouse = |kind, column, row| MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        };
+
+        // Dragging the title moves the dialog
+        let area = app.dialog_area(Dialog::Help, size);
+        app.handle_mouse(
+            mouse(MouseEventKind::Down(MouseButton::Left), area.x + 5, area.y),
+            size,
+        )
+        .unwrap();
+        app.handle_mouse(
+            mouse(
+                MouseEventKind::Drag(MouseButton::Left),
+                area.x + 2,
+                area.y + 3,
+            ),
+            size,
+        )
+        .unwrap();
+        app.handle_mouse(
+            mouse(
+                MouseEventKind::Up(MouseButton::Left),
+                area.x + 2,
+                area.y + 3,
+            ),
+            size,
+        )
+        .unwrap();
+        let moved = app.dialog_area(Dialog::Help, size);
+        assert_eq!((moved.x, moved.y), (area.x - 3, area.y + 3));
+        assert_eq!((moved.width, moved.height), (area.width, area.height));
+
+        // Dragging the right border resizes it around the same center
+        let center = moved.x + moved.width / 2;
+        app.handle_mouse(
+            mouse(
+                MouseEventKind::Down(MouseButton::Left),
+                moved.right() - 1,
+                moved.y + 4,
+            ),
+            size,
+        )
+        .unwrap();
+        app.handle_mouse(
+            mouse(
+                MouseEventKind::Drag(MouseButton::Left),
+                center + 40,
+                moved.y + 4,
+            ),
+            size,
+        )
+        .unwrap();
+        app.handle_mouse(
+            mouse(
+                MouseEventKind::Up(MouseButton::Left),
+                center + 40,
+                moved.y + 4,
+            ),
+            size,
+        )
+        .unwrap();
+        assert_eq!(app.dialog_size(Dialog::Help), (81, 55));
+        assert!(std::fs::read_to_string(&app.settings_file)
+            .unwrap()
+            .contains("help = 81x55"));
+    }
+
+    #[test]
+    fn test_add_path_with_memory_fs() {
+        let fs = MemoryFileSystem::new().with_dir(r"D:\Tools");
+        let mut app = create_test_app_with_fs(vec![], vec![], Box::new(fs));
+        app.active_panel = Panel::User;
+
+        // Existing directory is added straight away
+        app.input_buffer = r"D:\Tools".to_string();
+        app.add_path_from_input().unwrap();
+        assert_eq!(app.user_paths, vec![r"D:\Tools".to_string()]);
+        assert!(app.user_info[0].exists);
+
+        // Missing directory asks to create it first
+        app.input_buffer = r"D:\New".to_string();
+        app.add_path_from_input().unwrap();
+        assert_eq!(
+            app.mode,
+            Mode::Confirm(ConfirmAction::CreateSingleDirectory)
+        );
+        assert_eq!(app.user_paths.len(), 1);
+    }
+
+    #[test]
+    fn test_add_and_edit_warn_about_duplicates() {
+        let fs = MemoryFileSystem::new()
+            .with_dir(r"C:\Windows")
+            .with_dir(r"D:\Tools");
+        let mut app = create_test_app_with_fs(
+            vec![r"C:\Windows".to_string()],
+            vec![r"D:\Tools".to_string(), r"D:\Other".to_string()],
+            Box::new(fs),
+        );
+        app.active_panel = Panel::User;
+        app.mode = Mode::Input(InputMode::AddPath);
+
+        // Case and a trailing slash don't hide a copy, the target panel is searched first
+        app.input_buffer = r"d:\tools\".to_string();
+        assert_eq!(app.input_conflict(), Some((Panel::User, 0)));
+        app.input_buffer = r"C:\WINDOWS".to_string();
+        assert_eq!(app.input_conflict(), Some((Panel::Machine, 0)));
+        app.input_buffer = r"D:\Tool".to_string();
+        assert_eq!(app.input_conflict(), None);
+
+        // An entry being edited is no copy of itself
+        app.user_selected = 1;
+        app.mode = Mode::Input(InputMode::EditPath);
+        app.input_buffer = r"D:\Other".to_string();
+        assert_eq!(app.input_conflict(), None);
+        app.input_buffer = r"D:\Tools".to_string();
+        assert_eq!(app.input_conflict(), Some((Panel::User, 0)));
+
+        // Ctrl+G selects the existing entry and leaves PATH alone
+        app.input_buffer = r"C:\Windows".to_string();
+        app.handle_input(KeyEvent::new(KeyCode::Char('g'), KeyModifiers::CONTROL))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.active_panel, Panel::Machine);
+        assert_eq!(app.machine_selected, 0);
+        assert!(app.input_buffer.is_empty());
+        assert_eq!(app.user_paths[1], r"D:\Other");
+        assert!(!app.has_changes);
+    }
+
+    #[test]
+    fn test_add_suggests_subdirectory_with_commands() {
+        let root = tempfile::tempdir().unwrap();
+        let tool = root.path().join("SomeTool");
+        std::fs::create_dir_all(tool.join("bin")).unwrap();
+        std::fs::create_dir_all(tool.join("docs")).unwrap();
+        std::fs::write(tool.join("bin").join("tool.exe"), "").unwrap();
+        let typed = tool.to_string_lossy().to_string();
+        let bin = tool.join("bin").to_string_lossy().to_string();
+        let mut app = create_test_app(vec![], vec![]);
+        app.active_panel = Panel::User;
+
+        app.input_buffer = typed.clone();
+        app.add_path_from_input().unwrap();
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::UseSubdirectory));
+        assert_eq!(app.pending_subdirectory, Some((typed.clone(), bin.clone())));
+        assert!(app.user_paths.is_empty());
+
+        // One key takes the suggestion
+        app.handle_input(KeyEvent::from(KeyCode::Char('y')))
+            .unwrap();
+        assert_eq!(app.user_paths, vec![bin.clone()]);
+        assert!(app.pending_subdirectory.is_none());
+
+        // N adds the directory as typed, Esc adds nothing
+        app.input_buffer = typed.clone();
+        app.add_path_from_input().unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Char('n')))
+            .unwrap();
+        assert_eq!(app.user_paths, vec![bin.clone(), typed.clone()]);
+        app.input_buffer = typed.clone();
+        app.add_path_from_input().unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Esc)).unwrap();
+        assert_eq!(app.user_paths.len(), 2);
+
+        // A directory with commands of its own is added straight away
+        app.input_buffer = bin.clone();
+        app.add_path_from_input().unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.user_paths.len(), 3);
+    }
+
+    #[test]
+    fn test_copy_marked_to_other_panel() {
+        let mut app = create_test_app(
+            vec![r"C:\Machine".to_string()],
+            vec![r"C:\Tools".to_string()],
+        );
+        app.is_admin = true;
+        app.active_panel = Panel::User;
+        app.user_marked.insert(0);
+
+        app.handle_input(KeyEvent::new(KeyCode::F(5), KeyModifiers::SHIFT))
+            .unwrap();
+        assert_eq!(app.user_paths, vec![r"C:\Tools"]);
+        assert_eq!(app.machine_paths, vec![r"C:\Machine", r"C:\Tools"]);
+        assert!(app.user_marked.is_empty());
+        assert_eq!(app.status_message, "Copied 1 path(s) to MACHINE");
+        assert!(matches!(
+            app.undo_stack.last(),
+            Some(Operation::CopyPaths { .. })
+        ));
+
+        app.undo().unwrap();
+        assert_eq!(app.machine_paths, vec![r"C:\Machine"]);
+        assert_eq!(app.user_paths, vec![r"C:\Tools"]);
+    }
+
+    #[test]
+    fn test_status_messages_are_logged() {
+        let mut app = create_test_app(vec![], vec![]);
+        app.set_status("first");
+        app.set_status("");
+        app.set_status("second");
+        let messages: Vec<&str> = app.status_log.iter().map(|(_, m)| m.as_str()).collect();
+        assert_eq!(messages, ["first", "second"]);
+
+        for i in 0..MAX_STATUS_LOG {
+            app.set_status(&format!("message {}", i));
+        }
+        assert_eq!(app.status_log.len(), MAX_STATUS_LOG);
+        assert_eq!(app.status_log[0].1, "message 0");
+
+        app.handle_input(KeyEvent::new(KeyCode::Char('l'), KeyModifiers::NONE))
+            .unwrap();
+        assert_eq!(app.mode, Mode::MessageLog);
+        app.handle_input(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn test_hovered_entry_echoed_without_selecting() {
+        let mut app = create_test_app(
+            vec![
+                r"C:\Windows".to_string(),
+                r"C:\Missing\Very\Long\Directory".to_string(),
+            ],
+            vec![r"C:\Tools".to_string()],
+        );
+        let size = Rect::new(0, 0, 80, 24);
+        let moved = |app: &mut App, column, row| {
+            app.handle_mouse(
+                MouseEvent {
+                    kind: MouseEventKind::Moved,
+                    column,
+                    row,
+                    modifiers: KeyModifiers::NONE,
+                },
+                size,
+            )
+            .unwrap()
+        };
+
+        let active = app.active_panel;
+
+        // Second MACHINE row, below the top border
+        moved(&mut app, 10, 5);
+        assert_eq!(app.hovered, Some((Panel::Machine, 1)));
+        assert_eq!(
+            app.hover_text().unwrap(),
+            r"C:\Missing\Very\Long\Directory - Dead (path does not exist)"
+        );
+        assert_eq!(app.machine_selected, 0);
+        assert_eq!(app.active_panel, active);
+
+        // Borders and empty rows echo nothing
+        moved(&mut app, 10, 3);
+        assert_eq!(app.hovered, None);
+        moved(&mut app, 50, 6);
+        assert_eq!(app.hovered, None);
+
+        moved(&mut app, 50, 4);
+        assert_eq!(app.hovered, Some((Panel::User, 0)));
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Down)), size)
+            .unwrap();
+        assert_eq!(app.hovered, None);
+    }
+
+    #[test]
+    fn test_process_restart_selection_and_services() {
+        let mut app = create_test_app(vec![], vec![]);
+        let process = |name: &str, is_service| ProcessInfo {
+            name: name.to_string(),
+            pid: 1,
+            started: None,
+            has_window: false,
+            is_service,
+        };
+        app.processes_to_restart = vec![
+            process("cmd.exe", true),
+            process("Code.exe", false),
+            process("pwsh.exe", false),
+        ];
+        app.mode = Mode::ProcessRestartInfo;
+        let press = |app: &mut App, code| {
+            app.handle_input(KeyEvent::new(code, KeyModifiers::NONE))
+                .unwrap()
+        };
+
+        for _ in 0..5 {
+            press(&mut app, KeyCode::Down);
+        }
+        assert_eq!(app.process_selected, 2);
+
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.process_selected, 0);
+        let listed: Vec<&str> = app
+            .listed_processes()
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(listed, ["Code.exe", "pwsh.exe"]);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.process_selected, 1);
+
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn test_add_path_position() {
+        let fs = MemoryFileSystem::new()
+            .with_dir(r"D:\A")
+            .with_dir(r"D:\B")
+            .with_dir(r"D:\C")
+            .with_dir(r"D:\New");
+        let user = vec![
+            r"D:\A".to_string(),
+            r"D:\B".to_string(),
+            r"D:\C".to_string(),
+        ];
+        let mut app = create_test_app_with_fs(vec![], user, Box::new(fs));
+        app.active_panel = Panel::User;
+        app.user_selected = 2;
+        app.user_marked.insert(2);
+
+        // Tab cycles End -> Top -> Before selection while typing
+        app.mode = Mode::Input(InputMode::AddPath);
+        app.handle_input(KeyEvent::from(KeyCode::Tab)).unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Tab)).unwrap();
+        assert_eq!(app.add_position, AddPosition::BeforeSelection);
+
+        app.input_buffer = r"D:\New".to_string();
+        app.add_path_from_input().unwrap();
+        assert_eq!(app.user_paths, vec![r"D:\A", r"D:\B", r"D:\New", r"D:\C"]);
+        // The new entry is selected and the mark stays on D:\C
+        assert_eq!(app.user_selected, 2);
+        assert_eq!(app.user_marked, HashSet::from([3]));
+
+        app.add_position = AddPosition::Top;
+        app.input_buffer = r"D:\New".to_string();
+        app.add_path_from_input().unwrap();
+        assert_eq!(app.user_paths[0], r"D:\New");
+
+        // Redo puts the entry back at the same position
+        app.undo().unwrap();
+        assert_eq!(app.user_paths, vec![r"D:\A", r"D:\B", r"D:\New", r"D:\C"]);
+        app.redo().unwrap();
+        assert_eq!(app.user_paths[0], r"D:\New");
+        assert_eq!(app.user_paths.len(), 5);
+    }
+
+    #[test]
+    fn test_idle_elevated_session_locks() {
+        use std::time::{Duration, Instant};
+
+        let mut app = create_test_app(vec![r"C:\A".to_string()], vec![]);
+        app.settings.lock_after = Some(Duration::from_secs(600));
+        app.mode = Mode::Help;
+        let later = Instant::now() + Duration::from_secs(601);
+
+        // Only elevated sessions lock
+        assert!(!app.check_idle_lock(later));
+        app.is_admin = true;
+        assert!(!app.check_idle_lock(Instant::now()));
+        assert!(app.check_idle_lock(later));
+        assert_eq!(app.mode, Mode::Locked);
+
+        // Other keys and clicks are swallowed, Enter unlocks where it left off
+        let key = |code| Event::Key(KeyEvent::from(code));
+        app.handle_event(key(KeyCode::Delete), Rect::new(0, 0, 80, 24))
+            .unwrap();
+        app.handle_event(key(KeyCode::Esc), Rect::new(0, 0, 80, 24))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Locked);
+        app.handle_event(key(KeyCode::Enter), Rect::new(0, 0, 80, 24))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Help);
+        assert!(app.status_message.contains("administrator"));
+
+        // Input resets the idle timer
+        assert!(!app.check_idle_lock(Instant::now() + Duration::from_secs(599)));
+    }
+
+    #[test]
+    fn test_unelevated_machine_edit_is_staged() {
+        let machine = vec![
+            r"C:\A".to_string(),
+            r"C:\B".to_string(),
+            r"C:\C".to_string(),
+        ];
+        let mut app = create_test_app(machine, vec![]);
+        app.active_panel = Panel::Machine;
+        app.machine_selected = 2;
+        app.settings.auto_elevate = true;
+        assert!(!app.machine_staged());
+
+        // The first staged edit offers elevation, later ones only say so
+        app.handle_input(KeyEvent::from(KeyCode::F(6))).unwrap();
+        assert!(app.machine_staged());
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::RequestElevation));
+        assert!(app.status_message.contains("needs administrator rights"));
+        app.handle_input(KeyEvent::from(KeyCode::Char('n')))
+            .unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::F(6))).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.status_message.contains("needs administrator rights"));
+
+        // Back where it started, nothing is staged (both moves are one undo step)
+        app.handle_input(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL))
+            .unwrap();
+        assert!(!app.machine_staged());
+        assert_eq!(app.status_message, "MACHINE is back to its saved state");
+
+        // Administrators are not interrupted
+        app.is_admin = true;
+        app.handle_input(KeyEvent::from(KeyCode::F(6))).unwrap();
+        assert!(!app.machine_staged());
+        assert!(!app.status_message.contains("administrator"));
+    }
+
+    #[test]
+    fn test_add_scope_overrides_active_panel() {
+        let fs = MemoryFileSystem::new().with_dir(r"D:\Tools");
+        let mut app = create_test_app_with_fs(vec![], vec![], Box::new(fs));
+        app.active_panel = Panel::Machine;
+        app.settings.add_scope = Some(PathScope::User);
+
+        app.start_add_path();
+        app.mode = Mode::Input(InputMode::AddPath);
+        assert_eq!(app.add_panel(), Panel::User);
+        app.input_buffer = r"D:\Tools".to_string();
+        app.add_path_from_input().unwrap();
+        assert_eq!(app.user_paths, vec![r"D:\Tools"]);
+        assert!(app.machine_paths.is_empty());
+        // Focus follows the new entry
+        assert_eq!(app.active_panel, Panel::User);
+
+        // Shift+Tab picks the other scope for this add only
+        app.start_add_path();
+        app.mode = Mode::Input(InputMode::AddPath);
+        app.handle_input(KeyEvent::from(KeyCode::BackTab)).unwrap();
+        assert_eq!(app.add_panel(), Panel::Machine);
+        app.input_buffer = r"D:\Tools".to_string();
+        app.add_path_from_input().unwrap();
+        assert_eq!(app.machine_paths, vec![r"D:\Tools"]);
+
+        // --scope also decides the focus
+        app.use_scope(PathScope::Machine);
+        assert_eq!(app.active_panel, Panel::Machine);
+        assert_eq!(app.settings.add_scope, Some(PathScope::Machine));
+    }
+
+    #[test]
+    fn test_with_registry_reads_paths() {
+        let registry = MockRegistry::new(r"C:\User1;C:\User2", r"C:\Machine1");
+        let app = App::with_registry(
+            Theme::default(),
+            None,
+            Box::new(registry),
+            Box::new(RealFileSystem),
+        )
+        .unwrap();
+
+        assert_eq!(
+            app.user_paths,
+            vec![r"C:\User1".to_string(), r"C:\User2".to_string()]
+        );
+        assert_eq!(app.machine_paths, vec![r"C:\Machine1".to_string()]);
+        assert_eq!(app.user_info.len(), 2);
+        assert!(!app.has_changes);
+    }
+
+    #[test]
+    fn test_with_registry_read_denied() {
+        let registry = MockRegistry::new("", "").deny_read(PathScope::Machine);
+        let err = App::with_registry(
+            Theme::default(),
+            None,
+            Box::new(registry),
+            Box::new(RealFileSystem),
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("MACHINE"));
+    }
+
+    #[test]
+    fn test_apply_changes_writes_registry() {
+        let backups = tempfile::tempdir().unwrap();
+        let mut app = create_test_app(vec![r"C:\Machine".to_string()], vec![]);
+        app.backup_dir = backups.path().to_path_buf();
+        app.is_admin = true;
+
+        app.user_paths.push(r"C:\Tools".to_string());
+        app.machine_paths.push(r"C:\Shared".to_string());
+        app.has_changes = true;
+        app.apply_changes().unwrap();
+
+        assert_eq!(
+            app.registry.read_paths(PathScope::User).unwrap(),
+            vec![r"C:\Tools".to_string()]
+        );
+        assert_eq!(
+            app.registry.read_paths(PathScope::Machine).unwrap(),
+            vec![r"C:\Machine".to_string(), r"C:\Shared".to_string()]
+        );
+        assert_eq!(app.user_original, app.user_paths);
+        assert!(!app.has_changes);
+
+        // A backup of the previous state was saved first
+        assert_eq!(backup::list_backups(backups.path()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_apply_changes_access_denied() {
+        let backups = tempfile::tempdir().unwrap();
+        let mut app = create_test_app(vec![r"C:\Machine".to_string()], vec![]);
+        app.backup_dir = backups.path().to_path_buf();
+        app.registry =
+            Box::new(MockRegistry::new("", r"C:\Machine").deny_write(PathScope::Machine));
+        app.is_admin = true;
+
+        app.machine_paths.push(r"C:\Shared".to_string());
+        app.has_changes = true;
+        let err = app.apply_changes().unwrap_err();
+
+        assert!(err.to_string().contains("admin rights"));
+        assert_eq!(app.machine_original, vec![r"C:\Machine".to_string()]);
+        assert!(app.has_changes);
+
+        // Without admin rights MACHINE is never written and stays pending
+        app.registry = Box::new(MockRegistry::new("", "").deny_write(PathScope::Machine));
+        app.is_admin = false;
+        app.apply_changes().unwrap();
+        assert!(app.has_changes);
+        assert_eq!(app.machine_original, vec![r"C:\Machine".to_string()]);
+    }
+
+    #[test]
+    fn test_apply_machine_through_elevated_helper() {
+        let backups = tempfile::tempdir().unwrap();
+        let mut app = create_test_app(vec![r"C:\Machine".to_string()], vec![]);
+        app.backup_dir = backups.path().to_path_buf();
+        app.history_file = backups.path().join("history.jsonl");
+        app.registry = Box::new(MockRegistry::new("", r"C:\Machine"));
+        app.machine_helper = Box::new(MockRegistry::new("", r"C:\Machine"));
+        app.settings.elevated_helper = true;
+        app.machine_paths.push(r"C:\Shared".to_string());
+        app.has_changes = true;
+
+        // No restart: the apply dialog offers MACHINE right away
+        app.request_apply();
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::ApplyChanges));
+        let machine = &app.apply_choices()[1];
+        assert!(machine.checked && machine.writable);
+        assert_eq!(machine.label, "MACHINE (UAC prompt)");
+
+        app.apply_changes().unwrap();
+        assert_eq!(
+            app.machine_helper.read_paths(PathScope::Machine).unwrap(),
+            vec![r"C:\Machine".to_string(), r"C:\Shared".to_string()]
+        );
+        // The session's own backend was not used for MACHINE
+        assert_eq!(
+            app.registry.read_paths(PathScope::Machine).unwrap(),
+            vec![r"C:\Machine".to_string()]
+        );
+        assert!(!app.is_admin);
+        assert!(!app.has_changes);
+    }
+
+    #[test]
+    fn test_revert_last_apply() {
+        let backups = tempfile::tempdir().unwrap();
+        let mut app = create_test_app(
+            vec![r"C:\Machine".to_string()],
+            vec![r"C:\User".to_string()],
+        );
+        app.backup_dir = backups.path().to_path_buf();
+        app.history_file = backups.path().join("history.jsonl");
+        app.registry = Box::new(MockRegistry::new(r"C:\User", r"C:\Machine"));
+        app.is_admin = true;
+
+        app.handle_input(KeyEvent::from(KeyCode::Char('Z')))
+            .unwrap();
+        assert_eq!(
+            app.status_message,
+            "Nothing has been applied in this session"
+        );
+
+        app.user_paths.push(r"C:\New".to_string());
+        app.machine_paths.clear();
+        app.has_changes = true;
+        app.apply_changes().unwrap();
+        app.mode = Mode::Normal;
+        assert_eq!(
+            app.registry.read_paths(PathScope::User).unwrap(),
+            vec![r"C:\User".to_string(), r"C:\New".to_string()]
+        );
+
+        // N puts the old PATH in the panels without touching the registry
+        app.handle_input(KeyEvent::from(KeyCode::Char('Z')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::RevertLastApply));
+        app.handle_input(KeyEvent::from(KeyCode::Char('n')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.user_paths, vec![r"C:\User".to_string()]);
+        assert_eq!(app.machine_paths, vec![r"C:\Machine".to_string()]);
+        assert!(app.has_changes);
+        assert_eq!(
+            app.registry.read_paths(PathScope::Machine).unwrap().len(),
+            0
+        );
+
+        // It is an ordinary edit that undo takes back
+        app.undo().unwrap();
+        assert_eq!(app.user_paths.len(), 2);
+        assert!(app.machine_paths.is_empty());
+
+        // Y goes on to the apply dialog to write it
+        app.handle_input(KeyEvent::from(KeyCode::Char('Z')))
+            .unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Char('y')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::ApplyChanges));
+        app.apply_changes().unwrap();
+        assert_eq!(
+            app.registry.read_paths(PathScope::Machine).unwrap(),
+            vec![r"C:\Machine".to_string()]
+        );
+        assert!(!app.has_changes);
+    }
+
+    #[test]
+    fn test_revert_last_apply_only_reverts_what_it_wrote() {
+        let backups = tempfile::tempdir().unwrap();
+        let mut app = create_test_app(
+            vec![r"C:\Machine".to_string()],
+            vec![r"C:\User".to_string()],
+        );
+        app.backup_dir = backups.path().to_path_buf();
+        app.history_file = backups.path().join("history.jsonl");
+        app.registry =
+            Box::new(MockRegistry::new(r"C:\User", r"C:\Machine").deny_write(PathScope::User));
+        app.is_admin = true;
+
+        // An apply that wrote nothing leaves nothing to revert
+        app.user_paths.push(r"C:\New".to_string());
+        app.has_changes = true;
+        assert!(app.apply_changes().is_err());
+        assert!(app.last_apply.is_none());
+
+        // A USER-only apply keeps the MACHINE edits still pending
+        app.registry = Box::new(MockRegistry::new(r"C:\User", r"C:\Machine"));
+        app.machine_paths.push(r"C:\Pending".to_string());
+        app.apply_user = true;
+        app.apply_machine = false;
+        app.apply_changes().unwrap();
+        app.mode = Mode::Normal;
+        let last = app.last_apply.clone().unwrap();
+        assert!(last.user && !last.machine);
+        assert_eq!(last.scopes(), "USER");
+
+        app.handle_input(KeyEvent::from(KeyCode::Char('Z')))
+            .unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Char('n')))
+            .unwrap();
+        assert_eq!(app.user_paths, vec![r"C:\User".to_string()]);
+        assert_eq!(
+            app.machine_paths,
+            vec![r"C:\Machine".to_string(), r"C:\Pending".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_ignore_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = create_test_app_with_fs(
+            vec![],
+            vec![
+                r"C:\Windows".to_string(),
+                r"C:\agent\_work\7\bin".to_string(),
+                r"C:\Windows".to_string(),
+            ],
+            Box::new(MemoryFileSystem::new().with_dir(r"C:\Windows")),
+        );
+        app.settings_file = dir.path().join("config.ini");
+        app.active_panel = Panel::User;
+        app.user_selected = 2;
+        let press = |app: &mut App, code| app.handle_input(KeyEvent::from(code)).unwrap();
+
+        app.execute_menu_action(crate::menu::MenuAction::IgnoreList)
+            .unwrap();
+        assert_eq!(app.mode, Mode::IgnoreList);
+        // The selected entry is offered
+        press(&mut app, KeyCode::Char('a'));
+        assert_eq!(app.mode, Mode::Input(InputMode::IgnoreRule));
+        assert_eq!(app.input_buffer, r"C:\Windows");
+        app.mode_enter_time -= std::time::Duration::from_secs(1);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.mode, Mode::IgnoreList);
+        assert!(app.user_info.iter().all(|info| !info.is_duplicate));
+
+        app.add_ignore_rule(r"C:\agent\_work\*");
+        assert_eq!(
+            app.status_message,
+            r"Ignoring C:\agent\_work\*, it is no longer reported as dead or duplicate"
+        );
+        app.add_ignore_rule(r"c:\windows");
+        assert_eq!(
+            app.status_message,
+            r"c:\windows is already on the ignore list"
+        );
+        assert_eq!(
+            Settings::load(&app.settings_file).unwrap().ignored,
+            vec![r"C:\Windows".to_string(), r"C:\agent\_work\*".to_string()]
+        );
+
+        // Bulk cleanups leave ignored entries alone
+        app.confirm_bulk_delete(ConfirmAction::DeleteAllDead);
+        assert_eq!(app.status_message, "No dead paths to delete");
+        app.confirm_bulk_delete(ConfirmAction::DeleteAllDuplicates);
+        assert_eq!(app.status_message, "No duplicate paths to delete");
+
+        app.mode = Mode::IgnoreList;
+        app.ignore_selected = 0;
+        press(&mut app, KeyCode::Delete);
+        assert_eq!(app.status_message, r"No longer ignoring C:\Windows");
+        assert!(app.user_info[0].is_duplicate);
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn test_change_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = create_test_app(
+            vec![r"C:\Machine".to_string()],
+            vec![r"C:\User".to_string()],
+        );
+        app.backup_dir = dir.path().join("backups");
+        app.history_file = dir.path().join("history.jsonl");
+        app.settings_file = dir.path().join("config.ini");
+        app.registry = Box::new(MockRegistry::new(r"C:\User", r"C:\Machine"));
+        app.is_admin = true;
+
+        app.execute_menu_action(crate::menu::MenuAction::ChangeReport)
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(
+            app.status_message,
+            "No changes to report, edit or apply something first"
+        );
+
+        // Pending edits are reported against the PATH as loaded
+        app.user_paths.push(r"C:\New".to_string());
+        app.has_changes = true;
+        app.reanalyze();
+        let report = app.change_report().unwrap();
+        assert_eq!(report.state, "Planned, not applied yet");
+        assert_eq!(report.scopes[1].before, vec![r"C:\User".to_string()]);
+        assert!(report
+            .findings
+            .iter()
+            .any(|f| f.entry == r"C:\New" && f.scope == PathScope::User));
+
+        app.execute_menu_action(crate::menu::MenuAction::ChangeReport)
+            .unwrap();
+        assert_eq!(app.mode, Mode::Input(InputMode::ChangeReport));
+        app.input_buffer = dir.path().join("reports").to_string_lossy().to_string();
+        app.handle_input(KeyEvent::from(KeyCode::Tab)).unwrap();
+        assert_eq!(app.report_format, ReportFormat::Html);
+        app.mode_enter_time -= std::time::Duration::from_secs(1);
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert!(app.status_message.starts_with("Change summary written to "));
+        let files: Vec<_> = std::fs::read_dir(dir.path().join("reports"))
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.path())
+            .collect();
+        assert_eq!(files.len(), 1);
+        assert!(std::fs::read_to_string(&files[0])
+            .unwrap()
+            .contains("<li>USER +1 -0</li>"));
+        // The folder and format are offered again next time
+        let saved = Settings::load(&app.settings_file).unwrap();
+        assert_eq!(saved.report_format, ReportFormat::Html);
+        assert_eq!(app.settings.report_folder, saved.report_folder);
+
+        // After applying, the report covers the last apply
+        app.apply_changes().unwrap();
+        app.mode = Mode::Normal;
+        let report = app.change_report().unwrap();
+        assert!(report.state.starts_with("Applied, backup taken "));
+        assert_eq!(report.scopes[1].after.len(), 2);
+    }
+
+    #[test]
+    fn test_size_advisory_before_apply() {
+        let mut app = create_test_app(vec![], vec![r"C:\User".to_string()]);
+        for i in 0..200 {
+            app.user_paths
+                .push(format!(r"C:\Program Files\Tool{:03}\bin", i));
+        }
+        app.has_changes = true;
+        app.reanalyze();
+
+        app.request_apply();
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::SizeAdvisory));
+        assert!(app.size_advisory.warnings[0].starts_with("USER PATH is"));
+        assert!(app
+            .size_advisory
+            .suggestions
+            .iter()
+            .any(|s| s.starts_with("Move entries from USER to MACHINE")));
+
+        // Going ahead leads to the usual apply dialog
+        app.handle_input(KeyEvent::from(KeyCode::Char('y')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::ApplyChanges));
+
+        // Backing out leaves the edits pending
+        app.mode = Mode::Normal;
+        app.request_apply();
+        app.handle_input(KeyEvent::from(KeyCode::Char('n')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.has_changes);
+    }
+
+    #[test]
+    fn test_save_live_entries_to_user() {
+        let mut app = create_test_app(
+            vec![r"C:\Windows".to_string()],
+            vec![r"C:\User".to_string()],
+        );
+        app.handle_input(KeyEvent::from(KeyCode::Char('v')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::LiveEnvironment);
+
+        app.live_environment = LiveEnvironment {
+            process: vec![
+                r"c:\windows\".to_string(),
+                r"C:\Terminal\bin".to_string(),
+                r"C:\User".to_string(),
+            ],
+            volatile: Some(vec![
+                r"C:\Session".to_string(),
+                r"C:\terminal\bin".to_string(),
+            ]),
+        };
+        assert_eq!(
+            app.unsaved_live_entries(),
+            vec![r"C:\Terminal\bin".to_string(), r"C:\Session".to_string()]
+        );
+
+        app.handle_input(KeyEvent::from(KeyCode::Char('u')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(
+            app.user_paths,
+            vec![r"C:\User", r"C:\Terminal\bin", r"C:\Session"]
+        );
+        assert!(app.has_changes);
+        // Added entries are not offered again
+        assert!(app.unsaved_live_entries().is_empty());
+
+        app.undo().unwrap();
+        assert_eq!(app.user_paths, vec![r"C:\User".to_string()]);
+    }
+
+    #[test]
+    fn test_last_written_refreshes_after_apply() {
+        let backups = tempfile::tempdir().unwrap();
+        let mut app = create_test_app(vec![], vec![r"C:\User".to_string()]);
+        app.backup_dir = backups.path().to_path_buf();
+        app.history_file = backups.path().join("history.jsonl");
+        let installed = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        app.registry =
+            Box::new(MockRegistry::new(r"C:\User", "").modified_at(PathScope::Machine, installed));
+        app.refresh_last_written();
+        assert_eq!(
+            app.last_written_for(Panel::Machine),
+            Some((PathScope::Machine, installed.into()))
+        );
+        assert_eq!(app.last_written_for(Panel::User), None);
+
+        app.user_paths.push(r"C:\Tools".to_string());
+        app.has_changes = true;
+        app.apply_changes().unwrap();
+        let (scope, written) = app.last_written_for(Panel::User).unwrap();
+        assert_eq!(scope, PathScope::User);
+        assert!(written > DateTime::<Local>::from(installed));
+
+        // The remote panel has no local storage behind it
+        app.connection_mode = ConnectionMode::Remote;
+        assert_eq!(app.last_written_for(Panel::User), None);
+    }
+
+    #[test]
+    fn test_external_change_warns_before_apply() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = create_test_app(vec![], vec![r"C:\User".to_string()]);
+        app.backup_dir = dir.path().to_path_buf();
+        app.history_file = dir.path().join("history.jsonl");
+        app.instance = Some(Registration::register(&dir.path().join("instances"), false).unwrap());
+        let read = std::time::SystemTime::now() - std::time::Duration::from_secs(60);
+        app.registry =
+            Box::new(MockRegistry::new(r"C:\User", "").modified_at(PathScope::User, read));
+        app.refresh_last_written();
+        assert!(!app.check_external_changes());
+
+        // An installer appends to USER while we have it open
+        app.registry
+            .write_paths(
+                PathScope::User,
+                &[r"C:\User".to_string(), r"C:\Installer".to_string()],
+            )
+            .unwrap();
+        assert!(app.on_tick());
+        assert_eq!(app.external_changes, vec![PathScope::User]);
+        assert_eq!(
+            app.status_message,
+            "USER PATH was changed outside this session, applying will overwrite that change"
+        );
+        // Only reported once
+        assert!(!app.check_external_changes());
+
+        app.user_paths.push(r"C:\Tools".to_string());
+        app.has_changes = true;
+        app.request_apply();
+        assert_eq!(
+            app.apply_warnings(),
+            vec!["USER was changed outside this session".to_string()]
+        );
+        app.mode_enter_time -= std::time::Duration::from_secs(1);
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert!(app.external_changes.is_empty());
+        assert!(!app.check_external_changes());
+        // The lock is released once the apply is done
+        assert!(!dir.path().join("instances").join("apply.lock").exists());
+    }
+
+    #[test]
+    fn test_apply_creates_refresh_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = create_test_app(vec![], vec![r"C:\User".to_string()]);
+        app.backup_dir = dir.path().to_path_buf();
+        app.history_file = dir.path().join("history.jsonl");
+        app.refresh_file = Some(dir.path().join("refresh.txt"));
+
+        // A MACHINE-only apply that failed wrote nothing for the wrapper to reload
+        app.is_admin = true;
+        app.registry = Box::new(MockRegistry::new(r"C:\User", "").deny_write(PathScope::Machine));
+        app.machine_paths.push(r"C:\Shared".to_string());
+        app.has_changes = true;
+        app.apply_user = false;
+        app.apply_machine = true;
+        assert!(app.apply_changes().is_err());
+        assert!(!dir.path().join("refresh.txt").exists());
+
+        app.registry = Box::new(MockRegistry::new(r"C:\User", ""));
+        app.user_paths.push(r"C:\Tools".to_string());
+        app.apply_user = true;
+        app.apply_changes().unwrap();
+        assert!(dir.path().join("refresh.txt").exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_security_scan_opens_report() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let open = tempfile::tempdir().unwrap();
+        let closed = tempfile::tempdir().unwrap();
+        std::fs::set_permissions(open.path(), std::fs::Permissions::from_mode(0o777)).unwrap();
+        let mut app = create_test_app(
+            vec![closed.path().display().to_string()],
+            vec![open.path().display().to_string()],
+        );
+
+        app.start_security_scan();
+        assert_eq!(
+            app.busy_task().map(|(label, _)| label),
+            Some("Scanning PATH security")
+        );
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !app.poll_background_tasks() {
+            assert!(std::time::Instant::now() < deadline);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert_eq!(app.mode, Mode::SecurityReport);
+        let report = app.security_report.as_ref().unwrap();
+        assert_eq!(report.scanned, 2);
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].scope, PathScope::User);
+
+        app.handle_input(KeyEvent::from(KeyCode::Esc)).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+    }
 
     #[test]
-    fn test_panel_toggle() {
-        assert_eq!(Panel::User.toggle(), Panel::Machine);
-        assert_eq!(Panel::Machine.toggle(), Panel::User);
+    fn test_demote_flagged_entries() {
+        use crate::security_scan::{Finding, ScanReport};
+
+        let finding = |scope, index, path: &str| Finding {
+            scope,
+            index,
+            path: path.to_string(),
+            writable_by: vec!["Everyone".to_string()],
+            unsigned: vec![],
+        };
+        let machine = vec![
+            r"C:\Python27".to_string(),
+            r"C:\Windows\System32".to_string(),
+            r"C:\Windows".to_string(),
+        ];
+        let user = vec![r"C:\Public".to_string(), r"C:\Tools".to_string()];
+        let mut app = create_test_app(machine.clone(), user.clone());
+        app.security_report = Some(ScanReport {
+            scanned: 5,
+            findings: vec![
+                finding(PathScope::Machine, 0, r"C:\Python27"),
+                finding(PathScope::User, 0, r"C:\Public"),
+            ],
+            ..Default::default()
+        });
+        app.mode = Mode::SecurityReport;
+
+        app.handle_input(KeyEvent::from(KeyCode::Char('d')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.has_changes);
+        assert_eq!(
+            app.machine_paths,
+            vec![r"C:\Windows\System32", r"C:\Windows", r"C:\Python27"]
+        );
+        assert_eq!(app.user_paths, vec![r"C:\Tools", r"C:\Public"]);
+        assert!(app.status_message.starts_with("Moved 2 flagged entries"));
+
+        // Nothing left to move
+        app.mode = Mode::SecurityReport;
+        app.handle_input(KeyEvent::from(KeyCode::Char('d')))
+            .unwrap();
+        assert_eq!(app.status_message, "Flagged entries are already at the end");
+
+        // One undo restores both panels
+        app.undo().unwrap();
+        assert_eq!(app.machine_paths, machine);
+        assert_eq!(app.user_paths, user);
+        app.redo().unwrap();
+        assert_eq!(app.user_paths, vec![r"C:\Tools", r"C:\Public"]);
     }
 
     #[test]
-    fn test_panel_scope() {
-        assert_eq!(Panel::User.scope(), PathScope::User);
-        assert_eq!(Panel::Machine.scope(), PathScope::Machine);
+    fn test_mark_shadowed_toolchains() {
+        use crate::toolchains::{Install, Toolchain};
+
+        let machine = vec![
+            r"C:\jdk17\bin".to_string(),
+            r"C:\msys64\usr\bin".to_string(),
+        ];
+        let user = vec![r"C:\jdk8\bin".to_string(), r"C:\Perl\bin".to_string()];
+        let mut app = create_test_app(machine, user);
+        let install = |scope, index| Install {
+            scope,
+            index,
+            path: String::new(),
+            version: None,
+        };
+        app.toolchains = vec![
+            Toolchain {
+                label: "Java",
+                installs: vec![install(PathScope::Machine, 0), install(PathScope::User, 0)],
+            },
+            Toolchain {
+                label: "Perl",
+                installs: vec![install(PathScope::User, 1), install(PathScope::Machine, 1)],
+            },
+            Toolchain {
+                label: "GCC",
+                installs: vec![install(PathScope::Machine, 1)],
+            },
+        ];
+        app.mode = Mode::Toolchains;
+
+        app.handle_input(KeyEvent::from(KeyCode::Char('m')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        // msys64 loses Perl but its GCC is the one that runs
+        assert!(app.machine_marked.is_empty());
+        assert_eq!(app.user_marked, HashSet::from([0]));
+        assert!(app.status_message.starts_with("Marked 1 directory"));
+        assert!(!app.has_changes);
     }
 
     #[test]
-    fn test_app_initial_state() {
-        let app = create_test_app(
+    fn test_no_fs_checks_leaves_disk_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = create_test_app_with_fs(
             vec![r"C:\Windows".to_string()],
-            vec![r"C:\Users\Test".to_string()],
+            vec![r"Z:\Hung\bin".to_string(), r"C:\Gone".to_string()],
+            Box::new(crate::filesystem::UncheckedFileSystem),
         );
+        app.settings_file = dir.path().join("config.ini");
+        let since = Local::now().date_naive() - chrono::Duration::days(30);
+        app.settings.dead_since = HashMap::from([(r"c:\gone".to_string(), since)]);
 
-        assert_eq!(app.machine_paths.len(), 1);
-        assert_eq!(app.user_paths.len(), 1);
-        assert_eq!(app.active_panel, Panel::User);
-        assert_eq!(app.machine_selected, 0);
-        assert_eq!(app.user_selected, 0);
-        assert!(!app.has_changes);
-        assert_eq!(app.mode, Mode::Normal);
+        // Entries are neutral and nothing counts as dead, so F7 finds nothing to remove
+        assert!(app
+            .user_info
+            .iter()
+            .all(|info| info.status == PathStatus::Unchecked));
+        assert!(app
+            .collect_dead_entries(&app.user_paths, &app.user_info)
+            .is_empty());
+
+        // A missing directory was never looked at, so its first-seen date is kept
+        app.track_dead_entries();
+        assert_eq!(app.settings.dead_since.get(r"c:\gone"), Some(&since));
+        assert!(!app.settings_file.exists());
+
+        // Diagnostics that read directories refuse instead of hanging
+        app.execute_menu_action(crate::menu::MenuAction::SecurityScan)
+            .unwrap();
+        assert!(app.security_task.is_none());
+        assert_eq!(
+            app.status_message,
+            "Security scan reads the disk, which --no-fs-checks turned off"
+        );
+
+        // Edits still work as text
+        app.user_marked.insert(1);
+        app.delete_marked().unwrap();
+        assert_eq!(app.user_paths, vec![r"Z:\Hung\bin".to_string()]);
+        assert_eq!(app.user_info[0].status, PathStatus::Unchecked);
     }
 
     #[test]
-    fn test_move_selection_down() {
+    fn test_usage_suggestions_reorder_by_runs() {
+        let dir = tempfile::tempdir().unwrap();
         let mut app = create_test_app(
-            vec![],
+            vec![r"C:\Windows".to_string(), r"C:\Hot".to_string()],
             vec![
-                r"C:\Path1".to_string(),
-                r"C:\Path2".to_string(),
-                r"C:\Path3".to_string(),
+                r"D:\Unused".to_string(),
+                r"D:\Tools".to_string(),
+                r"D:\Idle".to_string(),
             ],
         );
+        app.usage_file = dir.path().join("usage.log");
 
-        assert_eq!(app.user_selected, 0);
-
-        app.move_selection(1);
-        assert_eq!(app.user_selected, 1);
+        // Nothing recorded yet points at the hook
+        app.execute_menu_action(crate::menu::MenuAction::UsageSuggestions)
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.status_message.contains("pc usage-hook"));
 
-        app.move_selection(1);
-        assert_eq!(app.user_selected, 2);
+        let mut log = "2026-09-01\tC:\\Windows\tcmd.exe\n".to_string();
+        for _ in 0..12 {
+            log.push_str("2026-09-30\tC:\\Hot\\\thot.exe\n");
+        }
+        log.push_str("2026-09-30\tD:\\Tools\ttool.exe\n");
+        std::fs::write(&app.usage_file, log).unwrap();
+        app.execute_menu_action(crate::menu::MenuAction::UsageSuggestions)
+            .unwrap();
+        assert_eq!(app.mode, Mode::UsageSuggestions);
+        assert_eq!(app.usage_days, 30);
+
+        // MACHINE keeps its system directory first, USER only demotes its unused entry
+        let rows: Vec<(Panel, usize, usage::Move)> = app
+            .usage_rows
+            .iter()
+            .map(|row| (row.panel, row.suggestion.index, row.suggestion.action))
+            .collect();
+        assert_eq!(rows, vec![(Panel::User, 0, usage::Move::Demote)]);
 
-        // Should not go beyond last item
-        app.move_selection(1);
-        assert_eq!(app.user_selected, 2);
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(
+            app.user_paths,
+            vec![
+                r"D:\Tools".to_string(),
+                r"D:\Idle".to_string(),
+                r"D:\Unused".to_string()
+            ]
+        );
+        assert!(app.has_changes);
+        app.undo().unwrap();
+        assert_eq!(app.user_paths[0], r"D:\Unused");
     }
 
     #[test]
-    fn test_move_selection_up() {
-        let mut app = create_test_app(
-            vec![],
+    fn test_aging_report_marks_stale_entries() {
+        let now = SystemTime::now();
+        let days_ago = |days: u64| now - std::time::Duration::from_secs(days * 24 * 60 * 60);
+        let fs = MemoryFileSystem::new()
+            .with_modified_dir(r"C:\Old\bin", days_ago(900))
+            .with_modified_dir(r"C:\Fresh", days_ago(3))
+            .with_modified_dir(r"C:\Older", days_ago(2000));
+        let mut app = create_test_app_with_fs(
+            vec![r"C:\Old\bin".to_string()],
             vec![
-                r"C:\Path1".to_string(),
-                r"C:\Path2".to_string(),
-                r"C:\Path3".to_string(),
+                r"C:\Fresh".to_string(),
+                r"C:\Gone".to_string(),
+                r"C:\Older".to_string(),
             ],
+            Box::new(fs),
         );
 
-        app.user_selected = 2;
+        // Oldest first, entries without a directory are left out
+        app.execute_menu_action(crate::menu::MenuAction::AgingReport)
+            .unwrap();
+        assert_eq!(app.mode, Mode::AgingReport);
+        let rows: Vec<(Panel, usize)> = app
+            .aging_rows
+            .iter()
+            .map(|row| (row.panel, row.index))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![(Panel::User, 2), (Panel::Machine, 0), (Panel::User, 0)]
+        );
 
-        app.move_selection(-1);
-        assert_eq!(app.user_selected, 1);
+        // Space marks the fresh one by hand, S adds every directory over a year old
+        app.handle_input(KeyEvent::from(KeyCode::End)).unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Char(' ')))
+            .unwrap();
+        assert!(app.is_marked(Panel::User, 0));
+        app.handle_input(KeyEvent::from(KeyCode::Char('s')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.machine_marked, HashSet::from([0]));
+        assert_eq!(app.user_marked, HashSet::from([0, 2]));
+        assert!(app.status_message.starts_with("Marked 2 entries untouched"));
+
+        // Enter goes to the entry in its panel
+        app.execute_menu_action(crate::menu::MenuAction::AgingReport)
+            .unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Down)).unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.active_panel, Panel::Machine);
+        assert_eq!(app.machine_selected, 0);
+        assert!(app.aging_rows.is_empty());
+    }
 
-        app.move_selection(-1);
-        assert_eq!(app.user_selected, 0);
+    #[test]
+    fn test_recheck_selected_entry() {
+        let mut app = create_test_app_with_fs(
+            vec![r"C:\Machine".to_string()],
+            vec![r"C:\Tools".to_string(), r"C:\Later".to_string()],
+            Box::new(MemoryFileSystem::new().with_dir(r"C:\Tools")),
+        );
+        app.active_panel = Panel::User;
+        app.user_selected = 1;
+        assert_eq!(app.user_info[1].status, PathStatus::Dead);
 
-        // Should not go below 0
-        app.move_selection(-1);
-        assert_eq!(app.user_selected, 0);
+        // Created outside Path Commander
+        app.fs = Box::new(
+            MemoryFileSystem::new()
+                .with_dir(r"C:\Tools")
+                .with_dir(r"C:\Later"),
+        );
+        app.handle_input(KeyEvent::from(KeyCode::Char('r')))
+            .unwrap();
+        assert_eq!(app.user_info[1].status, PathStatus::Valid);
+        assert!(app.user_info[1].exists);
+        assert_eq!(
+            app.status_message,
+            r"Rechecked C:\Later: Valid (was Dead (path does not exist))"
+        );
+        // Only the selected entry was looked at again
+        assert_eq!(app.machine_info[0].status, PathStatus::Dead);
+        assert!(!app.has_changes);
     }
 
     #[test]
-    fn test_move_selection_to() {
-        let mut app = create_test_app(
-            vec![],
-            vec![
-                r"C:\Path1".to_string(),
-                r"C:\Path2".to_string(),
-                r"C:\Path3".to_string(),
+    fn test_look_up_command_with_unapplied_edits() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = |name: &str| {
+            let dir = root.path().join(name);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("tool.bat"), "").unwrap();
+            dir.to_string_lossy().to_string()
+        };
+        let old = dir("old");
+        let new = dir("new");
+        let mut app = create_test_app(vec![old.clone()], vec![new.clone()]);
+
+        app.handle_input(KeyEvent::from(KeyCode::Char('w')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Input(InputMode::WhereCommand));
+        app.input_buffer = "tool.bat".to_string();
+        // Past the grace period for buffered Enter keys
+        app.mode_enter_time -= std::time::Duration::from_secs(1);
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert_eq!(app.mode, Mode::WhereResult);
+        assert_eq!(app.where_lookup.edited.len(), 2);
+        assert!(!app.where_lookup.changes_winner());
+
+        // Removing the old directory is not applied yet
+        app.machine_paths.clear();
+        app.look_up_command("tool.bat");
+        assert_eq!(app.where_lookup.edited[0].scope, PathScope::User);
+        assert_eq!(app.where_lookup.applied[0].scope, PathScope::Machine);
+        assert!(app.where_lookup.changes_winner());
+    }
+
+    #[test]
+    fn test_restore_shim_positions() {
+        use crate::shims::{Conflict, ShimFinding, ShimManager};
+
+        let machine = vec![r"C:\Program Files\Git\cmd".to_string()];
+        let user = vec![
+            r"C:\Tools".to_string(),
+            r"C:\Node".to_string(),
+            r"C:\Users\alice\scoop\shims".to_string(),
+        ];
+        let mut app = create_test_app(machine.clone(), user.clone());
+        let conflict = |scope, index, path: &str| Conflict {
+            scope,
+            index,
+            path: path.to_string(),
+            commands: vec!["node".to_string()],
+        };
+        app.shim_findings = vec![ShimFinding {
+            manager: ShimManager::Scoop,
+            entry: Some((PathScope::User, 2, user[2].clone())),
+            dir: user[2].clone(),
+            shadowed_by: vec![
+                conflict(PathScope::Machine, 0, &machine[0]),
+                conflict(PathScope::User, 1, &user[1]),
             ],
+            shadows: vec![],
+        }];
+        app.mode = Mode::ShimReport;
+
+        app.handle_input(KeyEvent::from(KeyCode::Char('r')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.has_changes);
+        // MACHINE is searched first anyway, so only the USER conflict is fixed
+        assert_eq!(app.machine_paths, machine);
+        assert_eq!(
+            app.user_paths,
+            vec![r"C:\Tools", r"C:\Users\alice\scoop\shims", r"C:\Node"]
+        );
+        assert!(app.status_message.starts_with("Moved 1 directory"));
+
+        app.mode = Mode::ShimReport;
+        app.handle_input(KeyEvent::from(KeyCode::Char('r')))
+            .unwrap();
+        assert_eq!(
+            app.status_message,
+            "Shim directories are already in the recommended position"
         );
 
-        app.move_selection_to(2);
-        assert_eq!(app.user_selected, 2);
+        app.undo().unwrap();
+        assert_eq!(app.user_paths, user);
+    }
 
-        app.move_selection_to(0);
-        assert_eq!(app.user_selected, 0);
+    #[test]
+    fn test_merge_cross_scope_duplicates() {
+        let machine = vec![
+            r"C:\Windows".to_string(),
+            r"C:\Tools".to_string(),
+            r"C:\Git\cmd".to_string(),
+        ];
+        let user = vec![
+            r"c:\tools\".to_string(),
+            r"C:\Users\me\bin".to_string(),
+            r"C:\Git\cmd".to_string(),
+            r"C:\Windows".to_string(),
+        ];
+        let mut app = create_test_app(machine.clone(), user.clone());
+        app.is_admin = true;
+
+        app.start_merge_duplicates();
+        assert_eq!(app.mode, Mode::MergeDuplicates);
+        let pairs: Vec<(usize, usize)> = app
+            .merge_pairs
+            .iter()
+            .map(|p| (p.machine, p.user))
+            .collect();
+        assert_eq!(pairs, vec![(1, 0), (2, 2), (0, 3)]);
+
+        // Keep C:\Tools in MACHINE (default), C:\Git\cmd in USER, both copies of C:\Windows
+        app.handle_input(KeyEvent::from(KeyCode::Down)).unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Char('u')))
+            .unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Down)).unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Right)).unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Right)).unwrap();
+        assert_eq!(app.merge_pairs[2].keep, MergeKeep::Both);
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.machine_paths, vec![r"C:\Windows", r"C:\Tools"]);
+        assert_eq!(
+            app.user_paths,
+            vec![r"C:\Users\me\bin", r"C:\Git\cmd", r"C:\Windows"]
+        );
+        assert!(app.status_message.starts_with("Merged 3 entries"));
+
+        // The whole merge is one undo step
+        app.undo().unwrap();
+        assert_eq!(app.machine_paths, machine);
+        assert_eq!(app.user_paths, user);
     }
 
     #[test]
-    fn test_toggle_mark() {
-        let mut app = create_test_app(
-            vec![],
+    fn test_consolidate_nested_entries() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = |path: &str, command: Option<&str>| {
+            let dir = root.path().join(path);
+            std::fs::create_dir_all(&dir).unwrap();
+            if let Some(command) = command {
+                std::fs::write(dir.join(command), "").unwrap();
+            }
+            dir.to_string_lossy().to_string()
+        };
+        let tool = dir("Tool", None);
+        let tool_bin = dir("Tool/bin", Some("tool.exe"));
+        let sdk = dir("Sdk", Some("sdk.cmd"));
+        let sdk_extras = dir("Sdk/extras", Some("extra.cmd"));
+        let other = dir("ToolBox", Some("box.exe"));
+        let machine = vec![tool.clone(), sdk.clone()];
+        let user = vec![tool_bin.clone(), sdk_extras.clone(), other.clone()];
+        let mut app = create_test_app(machine.clone(), user.clone());
+        app.is_admin = true;
+
+        app.start_nested_entries();
+        assert_eq!(app.mode, Mode::NestedEntries);
+        let found: Vec<_> = app
+            .nested_pairs
+            .iter()
+            .map(|pair| (pair.parent, pair.child, pair.fix))
+            .collect();
+        assert_eq!(
+            found,
             vec![
-                r"C:\Path1".to_string(),
-                r"C:\Path2".to_string(),
-                r"C:\Path3".to_string(),
-            ],
+                (
+                    (PathScope::Machine, 0),
+                    (PathScope::User, 0),
+                    NestedFix::RemoveParent
+                ),
+                (
+                    (PathScope::Machine, 1),
+                    (PathScope::User, 1),
+                    NestedFix::KeepBoth
+                ),
+            ]
+        );
+        let (has_parent, has_child) = app.nesting_of(&tool);
+        assert!(!has_parent && has_child);
+
+        // Drop the SDK extras too, then apply as one undo step
+        app.handle_input(KeyEvent::from(KeyCode::Down)).unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Char('c')))
+            .unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.machine_paths, vec![sdk.clone()]);
+        assert_eq!(app.user_paths, vec![tool_bin.clone(), other.clone()]);
+        assert_eq!(
+            app.status_message,
+            "Removed 2 nested entries (Ctrl+Z to undo)"
         );
 
-        // Mark first item (toggle_mark also moves selection down)
-        app.user_selected = 0;
-        app.toggle_mark();
-        assert!(app.user_marked.contains(&0));
-        assert_eq!(app.user_selected, 1); // Selection moved down
+        app.undo().unwrap();
+        assert_eq!(app.machine_paths, machine);
+        assert_eq!(app.user_paths, user);
+    }
 
-        // Go back and unmark first item
-        app.user_selected = 0;
-        app.toggle_mark();
-        assert!(!app.user_marked.contains(&0));
+    #[test]
+    fn test_replace_ignore_case() {
+        assert_eq!(
+            replace_ignore_case(r"d:\tools\bin;D:\Tools", r"D:\Tools", r"E:\Tools"),
+            Some(r"E:\Tools\bin;E:\Tools".to_string())
+        );
+        assert_eq!(
+            replace_ignore_case(r"C:\Ünïcode\bin", "bin", "lib"),
+            Some(r"C:\Ünïcode\lib".to_string())
+        );
+        assert_eq!(replace_ignore_case(r"C:\Tools", r"D:\", r"E:\"), None);
+        assert_eq!(replace_ignore_case(r"C:\Tools", "", "x"), None);
+    }
 
-        // Test on machine panel
-        app.active_panel = Panel::Machine;
-        app.machine_paths = vec![r"C:\Windows".to_string(), r"C:\Temp".to_string()];
-        app.machine_selected = 0;
-        app.toggle_mark();
-        assert!(app.machine_marked.contains(&0));
-        assert_eq!(app.machine_selected, 1); // Selection moved down
+    #[test]
+    fn test_batch_replace_is_one_undo_step() {
+        let machine = vec![r"D:\Tools\bin".to_string(), r"C:\Windows".to_string()];
+        let user = vec![r"d:\tools\go\bin".to_string(), r"D:\Other".to_string()];
+        let fs = MemoryFileSystem::new().with_dir(r"E:\Tools\bin");
+        let mut app = create_test_app_with_fs(machine.clone(), user.clone(), Box::new(fs));
+        app.is_admin = true;
+
+        app.handle_input(KeyEvent::from(KeyCode::Char('R')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Input(InputMode::ReplaceFind));
+        // Past the grace period for buffered Enter keys
+        app.mode_enter_time -= std::time::Duration::from_secs(1);
+        app.input_buffer = r"D:\Tools".to_string();
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert_eq!(app.mode, Mode::Input(InputMode::ReplaceWith));
+        app.mode_enter_time -= std::time::Duration::from_secs(1);
+        app.input_buffer = r"E:\Tools".to_string();
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+
+        assert_eq!(app.mode, Mode::BatchReplace);
+        let rows: Vec<(PathScope, usize, &str, bool)> = app
+            .replace_rows
+            .iter()
+            .map(|row| (row.scope, row.index, row.after.as_str(), row.exists))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (PathScope::Machine, 0, r"E:\Tools\bin", true),
+                (PathScope::User, 0, r"E:\Tools\go\bin", false),
+            ]
+        );
+
+        // X leaves out the missing target, Enter stages the rest
+        app.handle_input(KeyEvent::from(KeyCode::Char('x')))
+            .unwrap();
+        assert!(!app.replace_rows[1].include);
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.machine_paths, vec![r"E:\Tools\bin", r"C:\Windows"]);
+        assert_eq!(app.user_paths, user);
+        assert!(app
+            .status_message
+            .starts_with(r#"Replaced "D:\Tools" in 1 entry"#));
+
+        app.undo().unwrap();
+        assert_eq!(app.machine_paths, machine);
     }
 
     #[test]
-    fn test_has_marked_items() {
-        let mut app = create_test_app(
-            vec![r"C:\Windows".to_string()],
-            vec![r"C:\Path1".to_string()],
+    fn test_raw_editor_stages_the_text_as_one_undo_step() {
+        let user = vec![r"C:\Tools".to_string(), r"C:\Old".to_string()];
+        let mut app = create_test_app(vec![], user.clone());
+        app.active_panel = Panel::User;
+        let key = |app: &mut App, code| app.handle_input(KeyEvent::from(code)).unwrap();
+
+        key(&mut app, KeyCode::Char('E'));
+        assert_eq!(app.mode, Mode::RawEditor);
+        // Nothing to review before the text changes
+        app.handle_input(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL))
+            .unwrap();
+        assert!(!app.raw_editor.as_ref().unwrap().reviewing);
+
+        // Swap the second entry for two typed on one line
+        key(&mut app, KeyCode::Down);
+        key(&mut app, KeyCode::End);
+        for _ in 0..r"C:\Old".len() {
+            key(&mut app, KeyCode::Backspace);
+        }
+        for c in r"D:\Go\bin;C:\Tools".chars() {
+            key(&mut app, KeyCode::Char(c));
+        }
+        let editor = app.raw_editor.as_ref().unwrap();
+        assert_eq!(editor.problems(), ["Entry 3 repeats entry 1"]);
+        assert_eq!(
+            app.raw_editor_change().unwrap().summary(),
+            "USER +1 -1 (reordered)"
         );
 
-        assert!(!app.has_marked_items());
+        app.handle_input(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL))
+            .unwrap();
+        assert!(app.raw_editor.as_ref().unwrap().reviewing);
+        // N goes back to the text, Enter in the review puts it in the panel
+        key(&mut app, KeyCode::Char('n'));
+        assert!(!app.raw_editor.as_ref().unwrap().reviewing);
+        app.handle_input(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL))
+            .unwrap();
+        key(&mut app, KeyCode::Enter);
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.user_paths, [r"C:\Tools", r"D:\Go\bin", r"C:\Tools"]);
+        assert!(app.has_changes);
+        assert_eq!(
+            app.status_message,
+            "Raw edit staged: USER +1 -1 (reordered) (Ctrl+Z to undo)"
+        );
 
-        app.user_marked.insert(0);
-        assert!(app.has_marked_items());
+        app.undo().unwrap();
+        assert_eq!(app.user_paths, user);
 
-        app.user_marked.clear();
-        app.active_panel = Panel::Machine;
-        app.machine_marked.insert(0);
-        assert!(app.has_marked_items());
+        // Esc throws the text away
+        key(&mut app, KeyCode::Char('E'));
+        key(&mut app, KeyCode::Char('x'));
+        key(&mut app, KeyCode::Esc);
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.user_paths, user);
     }
 
     #[test]
-    fn test_has_marked_dead_paths() {
-        let mut app = create_test_app(
+    #[cfg(not(windows))]
+    fn test_remote_connect_failure_offers_retry() {
+        let mut app = create_test_app(vec![r"C:\Windows".to_string()], vec![]);
+        let wait = |app: &mut App| {
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+            while !app.poll_background_tasks() {
+                assert!(std::time::Instant::now() < deadline);
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            }
+        };
+
+        // The attempt runs on a worker, the local panels stay in place meanwhile
+        app.start_remote_connect("SRV01");
+        assert_eq!(app.connecting_to.as_deref(), Some("SRV01"));
+        assert_eq!(
+            app.busy_task().map(|(label, _)| label),
+            Some("Connecting to SRV01")
+        );
+        assert_eq!(app.connection_mode, ConnectionMode::Local);
+        app.start_remote_connect("SRV02");
+        assert_eq!(app.status_message, "Still connecting to SRV01");
+
+        // Remote mode needs Windows, so here the attempt always fails
+        wait(&mut app);
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::RetryRemote));
+        assert!(app.connecting_to.is_none());
+        assert_eq!(app.failed_remote.as_ref().unwrap().0, "SRV01");
+        assert!(app
+            .status_message
+            .starts_with("Failed to connect to SRV01: Cannot connect"));
+
+        // Y tries again, N gives up and stays local
+        app.handle_input(KeyEvent::from(KeyCode::Char('y')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.connecting_to.as_deref(), Some("SRV01"));
+        wait(&mut app);
+        app.handle_input(KeyEvent::from(KeyCode::Char('n')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.failed_remote.is_none());
+        assert_eq!(app.connection_mode, ConnectionMode::Local);
+        assert_eq!(app.machine_paths, [r"C:\Windows"]);
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn test_queued_remote_write_retries_then_saves() {
+        let mut app = create_test_app(vec![], vec![]);
+        let wait = |app: &mut App| {
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+            while !app.poll_background_tasks() {
+                assert!(std::time::Instant::now() < deadline);
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            }
+        };
+        let now = std::time::Instant::now();
+        app.queued_remote_write = Some(QueuedWrite::new(
+            "SRV01",
             vec![],
-            vec![
-                r"C:\Windows".to_string(),
-                r"C:\NonExistentPath123456".to_string(),
-            ],
+            vec![r"C:\Tools".to_string()],
+            "The network path was not found.".to_string(),
+            now,
+        ));
+        app.mode = Mode::Confirm(ConfirmAction::RemoteWriteQueued);
+
+        // Y retries right away on a new connection, which fails off Windows
+        app.handle_input(KeyEvent::from(KeyCode::Char('y')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(
+            app.busy_task().map(|(label, _)| label),
+            Some("Writing PATH to SRV01")
         );
-        app.reanalyze();
+        wait(&mut app);
+        assert_eq!(app.queued_remote_write.as_ref().unwrap().attempts, 2);
+        assert!(app
+            .status_message
+            .starts_with("Writing PATH to SRV01 failed again: Cannot connect"));
+        assert!(app.status_message.ends_with("(attempt 3 of 5 in 4s)"));
+
+        // The next attempt waits for its backoff
+        app.poll_remote_queue(std::time::Instant::now());
+        assert!(app.remote_write_task.is_none());
+        app.poll_remote_queue(std::time::Instant::now() + std::time::Duration::from_secs(5));
+        assert!(app.remote_write_task.is_some());
+        wait(&mut app);
+        assert_eq!(app.queued_remote_write.as_ref().unwrap().attempts, 3);
+
+        // Saving it for later ends the retries
+        let dir = tempfile::tempdir().unwrap();
+        app.save_queued_write(dir.path());
+        assert!(app.queued_remote_write.is_none());
+        let saved: Vec<_> = std::fs::read_dir(dir.path()).unwrap().flatten().collect();
+        assert_eq!(saved.len(), 1);
+        let reg = std::fs::read_to_string(saved[0].path()).unwrap();
+        assert!(reg.contains("\"Path\"=hex(2):43,00,3a,00,5c,00"));
+        assert!(app
+            .status_message
+            .ends_with("run reg import with it on SRV01 to apply it"));
+    }
 
-        // No marked items yet
-        assert!(!app.has_marked_dead_paths());
+    #[test]
+    fn test_queued_remote_write_stops_when_the_remote_changed() {
+        let mut app = create_test_app(vec![], vec![]);
+        let now = std::time::Instant::now();
+        app.queued_remote_write = Some(QueuedWrite::new(
+            "SRV01",
+            vec![r"C:\Windows".to_string()],
+            vec![r"C:\Windows".to_string(), r"C:\Tools".to_string()],
+            "The network path was not found.".to_string(),
+            now,
+        ));
 
-        // Mark a valid path
-        app.user_marked.insert(0);
-        assert!(!app.has_marked_dead_paths());
+        // Someone edited PATH on the host during the outage
+        app.finish_queued_write(Ok(RetryOutcome::Changed(vec![
+            r"C:\Windows".to_string(),
+            r"C:\Agent".to_string(),
+        ])));
+        let queued = app.queued_remote_write.as_ref().unwrap();
+        assert_eq!(queued.next_retry, None);
+        assert!(queued.error.contains("was not overwritten"));
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::RemoteWriteQueued));
+        assert!(app
+            .status_message
+            .starts_with("PATH on SRV01 changed during the outage"));
+
+        // No more automatic attempts
+        app.poll_remote_queue(now + std::time::Duration::from_secs(3600));
+        assert!(app.remote_write_task.is_none());
+    }
+
+    #[test]
+    fn test_stopped_remote_registry_is_offered_to_start() {
+        let mut app = create_test_app(vec![], vec![]);
+        let stopped = preflight::Problem {
+            what: "The RemoteRegistry service is stopped on SRV01".to_string(),
+            fix: "It can be started from here".to_string(),
+            startable: true,
+        };
+        app.connecting_to = Some("SRV01".to_string());
+        app.finish_remote_connect(Err(stopped.into()));
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::StartRemoteRegistry));
+
+        // T starts it for this session only, then connects
+        app.handle_input(KeyEvent::from(KeyCode::Char('t')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(
+            app.busy_task().map(|(label, _)| label),
+            Some("Starting RemoteRegistry on SRV01")
+        );
+        assert_eq!(app.stop_registry_on_disconnect.as_deref(), Some("SRV01"));
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !app.poll_background_tasks() {
+            assert!(std::time::Instant::now() < deadline);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        // Other platforms can't start it, which is an ordinary failure to retry
+        #[cfg(not(windows))]
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::RetryRemote));
+
+        app.restore_remote_registry();
+        assert!(app.stop_registry_on_disconnect.is_none());
+        #[cfg(not(windows))]
+        assert!(app
+            .status_message
+            .starts_with("Could not stop RemoteRegistry on SRV01"));
+    }
+
+    #[test]
+    fn test_host_picker_connects_and_keeps_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = create_test_app(vec![], vec![]);
+        app.hosts_file = dir.path().join("hosts.toml");
+        let key = |app: &mut App, code: KeyCode| app.handle_input(KeyEvent::from(code)).unwrap();
+
+        // Without saved hosts Ctrl+O goes straight to typing a name
+        app.handle_input(KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Input(InputMode::ConnectRemote));
+        key(&mut app, KeyCode::Esc);
+
+        // A session adds the host, later ones refresh it
+        app.record_host_session("SRV01");
+        app.record_host_session("BUILD-01");
+        let inventory = HostInventory::load(&app.hosts_file).unwrap();
+        assert_eq!(inventory.hosts.len(), 2);
+        assert_eq!(
+            inventory.hosts[0].last_health.as_deref(),
+            Some("0 entries, no problems")
+        );
+
+        app.open_host_picker();
+        assert_eq!(app.mode, Mode::HostPicker);
+        key(&mut app, KeyCode::Down);
+        key(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.mode, Mode::HostNotes);
+        app.handle_paste("Reboot after\r\nchanges");
+        app.handle_input(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL))
+            .unwrap();
+        assert_eq!(app.mode, Mode::HostPicker);
+        assert_eq!(app.status_message, "Notes for BUILD-01 saved");
+        let inventory = HostInventory::load(&app.hosts_file).unwrap();
+        assert_eq!(inventory.hosts[1].notes, "Reboot after\nchanges");
+
+        key(&mut app, KeyCode::Up);
+        key(&mut app, KeyCode::Delete);
+        assert_eq!(HostInventory::load(&app.hosts_file).unwrap().hosts.len(), 1);
+        key(&mut app, KeyCode::Enter);
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.connecting_to.as_deref(), Some("BUILD-01"));
+    }
+
+    #[test]
+    fn test_undo_history_coalesces_and_is_bounded() {
+        let user: Vec<String> = ["A", "B", "C", "D"].map(String::from).to_vec();
+        let mut app = create_test_app(vec![], user.clone());
+        app.active_panel = Panel::User;
+
+        // Moving D to the top takes three swaps but one undo step
+        app.user_selected = 3;
+        for _ in 0..3 {
+            app.move_item_up();
+        }
+        assert_eq!(app.user_paths, ["D", "A", "B", "C"]);
+        assert_eq!(app.undo_stack.len(), 1);
+        app.undo().unwrap();
+        assert_eq!(app.user_paths, user);
+        app.redo().unwrap();
+        assert_eq!(app.user_paths, ["D", "A", "B", "C"]);
+        app.undo().unwrap();
+
+        // Moving an entry back where it was leaves nothing to undo
+        app.user_selected = 1;
+        app.move_item_up();
+        app.user_paths.swap(0, 1);
+        app.record_undo(Operation::SwapPaths {
+            panel: Panel::User,
+            index1: 0,
+            index2: 1,
+        });
+        assert_eq!(app.user_paths, user);
+        assert!(!app.can_undo());
+
+        // Editing the same entry twice is one step back to the original
+        app.clear_redo_stack();
+        for new_path in ["A2", "A3"] {
+            let old_path = app.user_paths[0].clone();
+            app.user_paths[0] = new_path.to_string();
+            app.record_undo(Operation::EditPath {
+                panel: Panel::User,
+                index: 0,
+                old_path,
+                new_path: new_path.to_string(),
+            });
+        }
+        assert_eq!(app.undo_stack.len(), 1);
+        app.undo().unwrap();
+        assert_eq!(app.user_paths, user);
+
+        // The oldest steps are dropped beyond the configured depth
+        app.settings.undo_depth = Some(2);
+        for index in 0..3 {
+            app.record_undo(Operation::AddPath {
+                panel: Panel::User,
+                index,
+                path: format!("E{}", index),
+            });
+        }
+        assert_eq!(app.undo_stack.len(), 2);
+        assert!(matches!(
+            app.undo_stack[0],
+            Operation::AddPath { index: 1, .. }
+        ));
+
+        // And while over the memory limit, keeping at least the newest
+        app.settings.undo_depth = None;
+        let big = "x".repeat(config::UNDO_MEMORY_LIMIT);
+        app.record_undo(Operation::AddPath {
+            panel: Panel::User,
+            index: 0,
+            path: big,
+        });
+        assert_eq!(app.undo_stack.len(), 1);
+    }
 
-        // Mark a dead path
-        app.user_marked.insert(1);
-        assert!(app.has_marked_dead_paths());
+    #[test]
+    fn test_replace_root() {
+        assert_eq!(
+            replace_root(r"d:\Tools\bin", r"D:\", r"E:\Apps"),
+            Some(r"E:\Apps\Tools\bin".to_string())
+        );
+        assert_eq!(
+            replace_root(r"D:\", r"D:\", r"E:\"),
+            Some(r"E:\".to_string())
+        );
+        // Only whole folder names
+        assert_eq!(
+            replace_root(r"D:\Tools2\bin", r"D:\Tools", r"E:\Tools"),
+            None
+        );
+        assert_eq!(replace_root(r"C:\D:\x", r"D:\", r"E:\"), None);
+
+        assert_eq!(path_root(r"d:\tools"), Some(r"D:\".to_string()));
+        assert_eq!(
+            path_root(r"\\server\share\bin"),
+            Some(r"\\server\share".to_string())
+        );
+        assert_eq!(path_root(r"%SystemRoot%\system32"), None);
     }
 
     #[test]
-    fn test_mode_transitions() {
-        let mut app = create_test_app(vec![], vec![]);
+    fn test_drive_migration_selects_existing_counterparts() {
+        let machine = vec![r"D:\Tools\bin".to_string(), r"C:\Windows".to_string()];
+        let user = vec![r"D:\Go\bin".to_string()];
+        let fs = MemoryFileSystem::new().with_dir(r"E:\Tools\bin");
+        let mut app = create_test_app_with_fs(machine, user.clone(), Box::new(fs));
+        app.is_admin = true;
+        assert_eq!(app.entry_roots(), vec![r"D:\", r"C:\"]);
+
+        app.execute_menu_action(crate::menu::MenuAction::DriveMigration)
+            .unwrap();
+        assert_eq!(app.mode, Mode::Input(InputMode::MigrateFrom));
+        app.mode_enter_time -= std::time::Duration::from_secs(1);
+        app.input_buffer = r"D:\".to_string();
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+        app.mode_enter_time -= std::time::Duration::from_secs(1);
+        app.input_buffer = r"E:\".to_string();
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+
+        assert_eq!(app.mode, Mode::BatchReplace);
+        assert!(app.replace_migration);
+        let rows: Vec<(&str, bool)> = app
+            .replace_rows
+            .iter()
+            .map(|row| (row.after.as_str(), row.include))
+            .collect();
+        assert_eq!(rows, vec![(r"E:\Tools\bin", true), (r"E:\Go\bin", false)]);
+
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert_eq!(app.machine_paths, vec![r"E:\Tools\bin", r"C:\Windows"]);
+        assert_eq!(app.user_paths, user);
+        assert!(app.status_message.contains("1 left to move by hand"));
+    }
 
-        assert_eq!(app.mode, Mode::Normal);
+    #[test]
+    fn test_promote_entry_drops_duplicates() {
+        let machine = vec![
+            r"C:\Windows".to_string(),
+            r"c:\tools\".to_string(),
+            r"C:\Tools".to_string(),
+        ];
+        let user = vec![
+            r"C:\Tools".to_string(),
+            r"C:\Go\bin".to_string(),
+            r"C:\TOOLS".to_string(),
+        ];
+        let mut app = create_test_app(machine.clone(), user.clone());
+        app.is_admin = true;
+        app.active_panel = Panel::User;
 
-        app.mode = Mode::Help;
-        assert_eq!(app.mode, Mode::Help);
+        // Already in MACHINE: the first copy there stays, every other one goes
+        app.handle_input(KeyEvent::from(KeyCode::Char('p')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::PromoteEntry));
+        let plan = app.pending_promotion.clone().unwrap();
+        assert_eq!(plan.kept_in_target, Some(1));
+        assert_eq!(plan.removed_from_source.len(), 2);
+        assert_eq!(plan.removed_from_target, vec![(2, r"C:\Tools".to_string())]);
+
+        app.handle_input(KeyEvent::from(KeyCode::Char('y')))
+            .unwrap();
+        assert_eq!(app.machine_paths, vec![r"C:\Windows", r"c:\tools\"]);
+        assert_eq!(app.user_paths, vec![r"C:\Go\bin"]);
+        assert_eq!(
+            app.status_message,
+            r"Promoted C:\Tools to MACHINE, removed 2 duplicate(s)"
+        );
 
-        app.mode = Mode::Confirm(ConfirmAction::Exit);
-        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::Exit));
+        // Not in USER yet: demoting appends it there
+        app.active_panel = Panel::Machine;
+        app.machine_selected = 0;
+        app.handle_input(KeyEvent::from(KeyCode::Char('p')))
+            .unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Char('y')))
+            .unwrap();
+        assert_eq!(app.machine_paths, vec![r"c:\tools\"]);
+        assert_eq!(app.user_paths, vec![r"C:\Go\bin", r"C:\Windows"]);
+
+        // Each move is a single undo step
+        app.undo().unwrap();
+        app.undo().unwrap();
+        assert_eq!(app.machine_paths, machine);
+        assert_eq!(app.user_paths, user);
+        app.redo().unwrap();
+        assert_eq!(app.user_paths, vec![r"C:\Go\bin"]);
+    }
 
-        app.mode = Mode::Input(InputMode::AddPath);
-        assert_eq!(app.mode, Mode::Input(InputMode::AddPath));
+    #[test]
+    fn test_restore_templated_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let backup = PathBackup::new(
+            String::new(),
+            String::new(),
+            vec![r"${TOOLS_ROOT}\bin".to_string()],
+            vec![r"C:\Windows".to_string(), r"${SDK}\bin".to_string()],
+        );
+        let mut app = create_test_app(vec![r"C:\Windows".to_string()], vec![]);
+        app.backup_list = vec![backup.save(dir.path()).unwrap()];
+        app.variables_file = dir.path().join("variables.ini");
 
-        app.mode = Mode::BackupList;
-        assert_eq!(app.mode, Mode::BackupList);
+        // Nothing changes while a placeholder has no value here
+        std::fs::write(&app.variables_file, "[variables]\nTOOLS_ROOT = D:\\Tools\n").unwrap();
+        app.restore_selected_backup().unwrap();
+        assert!(!app.has_changes);
+        assert!(app
+            .status_message
+            .starts_with("Backup not restored: unresolved placeholders: ${SDK}"));
+
+        std::fs::write(
+            &app.variables_file,
+            "[variables]\nTOOLS_ROOT = D:\\Tools\nSDK = E:\\SDK\n",
+        )
+        .unwrap();
+        app.restore_selected_backup().unwrap();
+        assert!(app.has_changes);
+        assert_eq!(app.user_paths, vec![r"D:\Tools\bin"]);
+        assert_eq!(app.machine_paths, vec![r"C:\Windows", r"E:\SDK\bin"]);
     }
 
     #[test]
-    fn test_can_create_directory() {
-        // Valid paths
-        assert!(App::can_create_directory(r"C:\NewFolder"));
-        assert!(App::can_create_directory(r"C:\Path\To\NewFolder"));
+    fn test_apply_records_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = create_test_app(vec![r"C:\Machine".to_string()], vec![]);
+        app.backup_dir = dir.path().join("backups");
+        app.history_file = dir.path().join("history.jsonl");
+        app.registry = Box::new(MockRegistry::new("", r"C:\Machine"));
+        app.is_admin = true;
+
+        // Nothing applied yet
+        app.show_history().unwrap();
+        assert_eq!(app.mode, Mode::Normal);
 
-        // Network paths should not be creatable
-        assert!(!App::can_create_directory(r"\\server\share\folder"));
+        app.user_paths.push(r"C:\Tools".to_string());
+        app.has_changes = true;
+        app.apply_changes().unwrap();
 
-        // Paths with invalid characters
-        assert!(!App::can_create_directory(r"C:\Invalid|Path"));
-        assert!(!App::can_create_directory(r"C:\Invalid<Path"));
-        assert!(!App::can_create_directory(r"C:\Invalid>Path"));
+        app.machine_paths.clear();
+        app.has_changes = true;
+        app.apply_changes().unwrap();
+
+        app.mode = Mode::Normal;
+        app.handle_input(KeyEvent::from(KeyCode::Char('h')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::History);
+        assert_eq!(app.history.len(), 2);
+
+        // Newest first, each apply only lists the scopes it changed
+        let latest = &app.history[0];
+        assert_eq!(latest.changes.len(), 1);
+        assert_eq!(latest.changes[0].scope, "MACHINE");
+        assert_eq!(latest.changes[0].removed, vec![r"C:\Machine".to_string()]);
+        assert_eq!(
+            app.history[1].changes[0].added,
+            vec![r"C:\Tools".to_string()]
+        );
     }
 
     #[test]
-    fn test_get_statistics() {
-        let app = create_test_app(
-            vec![r"C:\Windows".to_string(), r"C:\NonExistent1".to_string()],
+    fn test_policy_blocks_apply() {
+        let mut app = create_test_app(
             vec![
-                r"C:\Users".to_string(),
-                r"C:\windows".to_string(), // Duplicate of machine path
-                r"C:\NonExistent2".to_string(),
+                r"C:\Windows".to_string(),
+                r"C:\Windows\System32".to_string(),
             ],
+            vec![],
         );
+        app.is_admin = true;
+        app.policy = Policy {
+            locked: vec![r"C:\Windows".to_string()],
+            denied: vec![r"C:\Users\Public".to_string()],
+        };
 
-        let stats = app.get_statistics();
-
-        assert_eq!(stats.machine_total, 2);
-        assert_eq!(stats.user_total, 3);
-        assert_eq!(stats.machine_dead, 1); // C:\NonExistent1
-        assert_eq!(stats.user_dead, 1); // C:\NonExistent2
-                                        // Duplicates: C:\windows is duplicate of C:\Windows
-        assert!(stats.machine_duplicates > 0 || stats.user_duplicates > 0);
+        // Moving a locked entry to the other scope is allowed
+        let moved = app.machine_paths.remove(1);
+        app.user_paths.push(moved);
+        app.has_changes = true;
+        assert!(app.policy_violations().is_empty());
+        app.handle_input(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::ApplyChanges));
+
+        // Removing it or adding a denied entry is not
+        app.mode = Mode::Normal;
+        app.user_paths = vec![r"C:\Users\Public\bin".to_string()];
+        assert_eq!(app.policy_violations().len(), 2);
+        app.handle_input(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.status_message.starts_with("Blocked by policy"));
+        assert!(app
+            .status_message
+            .contains(r"C:\Windows\System32 is locked"));
     }
 
     #[test]
-    fn test_confirm_exit() {
-        let mut app = create_test_app(vec![], vec![]);
-
-        // When there are no changes, should exit directly
-        app.confirm_exit();
-        assert!(app.should_exit);
+    fn test_control_apply_is_blocked_by_policy() {
+        let mut app = create_test_app(vec![r"C:\Windows".to_string()], vec![]);
+        app.policy = Policy {
+            locked: vec![r"C:\Windows".to_string()],
+            denied: vec![],
+        };
+        let response = app.run_control_command(&control::Command::Remove {
+            path: r"C:\Windows".to_string(),
+            scope: Some("machine".to_string()),
+        });
+        assert!(response.ok);
 
-        // When there are changes, should confirm first
-        let mut app = create_test_app(vec![], vec![]);
-        app.has_changes = true;
-        app.confirm_exit();
-        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::Exit));
+        let response = app.run_control_command(&control::Command::Apply);
+        assert!(!response.ok);
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(response
+            .message
+            .is_some_and(|message| message.starts_with("Blocked by policy")));
     }
 
     #[test]
-    fn test_delete_marked() {
+    fn test_launch_profile_gates_features() {
+        let backups = tempfile::tempdir().unwrap();
         let mut app = create_test_app(
-            vec![],
-            vec![
-                r"C:\Path1".to_string(),
-                r"C:\Path2".to_string(),
-                r"C:\Path3".to_string(),
-            ],
+            vec![r"C:\Windows".to_string(), r"C:\Tools".to_string()],
+            vec![r"C:\User".to_string()],
         );
+        app.is_admin = true;
+        app.backup_dir = backups.path().to_path_buf();
+        app.profile = Profile::resolve("helpdesk", &Settings::default()).unwrap();
+        let denied = |feature: &str| format!("Not available in the helpdesk profile: {}", feature);
 
-        // Mark items 0 and 2
-        app.user_marked.insert(0);
-        app.user_marked.insert(2);
+        // Edit dialogs do not open
+        app.handle_input(KeyEvent::from(KeyCode::F(4))).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.status_message, denied("editing"));
+        app.handle_input(KeyEvent::from(KeyCode::F(8))).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
 
-        let result = app.delete_marked();
-        assert!(result.is_ok());
+        // An edit made straight from a key is taken back
+        app.machine_selected = 1;
+        app.handle_input(KeyEvent::from(KeyCode::F(6))).unwrap();
+        assert_eq!(app.machine_paths[0], r"C:\Windows");
+        assert!(!app.has_changes);
+        assert!(!app.can_undo());
+        assert_eq!(app.status_message, denied("editing"));
+
+        app.handle_input(KeyEvent::new(KeyCode::Char('b'), KeyModifiers::CONTROL))
+            .unwrap();
+        assert_eq!(app.status_message, denied("backups"));
+        assert_eq!(std::fs::read_dir(backups.path()).unwrap().count(), 0);
+        app.handle_input(KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.status_message, denied("remote computers"));
 
-        // Should have 1 path remaining (the middle one)
-        assert_eq!(app.user_paths.len(), 1);
-        assert_eq!(app.user_paths[0], r"C:\Path2");
+        // Diagnostics are what helpdesk is for
+        app.handle_input(KeyEvent::from(KeyCode::Char('w')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Input(InputMode::WhereCommand));
+        app.mode = Mode::Normal;
 
-        // Marked set should be cleared
-        assert!(app.user_marked.is_empty());
+        let enabled = |app: &App, action: crate::menu::MenuAction| {
+            app.menus()
+                .iter()
+                .flat_map(|menu| menu.items.iter())
+                .any(|item| item.action == action && item.enabled)
+        };
+        assert!(!enabled(&app, crate::menu::MenuAction::AddPath));
+        assert!(!enabled(&app, crate::menu::MenuAction::ApplyHistory));
+        assert!(enabled(&app, crate::menu::MenuAction::SecurityScan));
 
-        // Should have changes
-        assert!(app.has_changes);
+        let response = app.run_control_command(&control::Command::Add {
+            path: r"C:\New".to_string(),
+            scope: None,
+        });
+        assert!(!response.ok);
+        assert_eq!(app.user_paths.len(), 1);
     }
 
     #[test]
-    fn test_normalize_selected() {
-        // Test with an absolute path that should be collapsed to an env var
-        let systemroot = std::env::var("SYSTEMROOT").unwrap_or_default();
-        if systemroot.is_empty() {
-            return; // Skip test if SYSTEMROOT is not set
-        }
+    fn test_apply_only_selected_scope() {
+        let backups = tempfile::tempdir().unwrap();
+        let mut app = create_test_app(vec![r"C:\Machine".to_string()], vec![]);
+        app.backup_dir = backups.path().to_path_buf();
+        app.registry = Box::new(MockRegistry::new("", r"C:\Machine"));
+        app.is_admin = true;
+
+        app.user_paths.push(r"C:\Tools".to_string());
+        app.machine_paths.push(r"C:\Shared".to_string());
+        app.has_changes = true;
 
-        let absolute_path = format!(r"{}\System32", systemroot);
-        let mut app = create_test_app(vec![], vec![absolute_path.clone()]);
-        app.reanalyze();
+        // Both scopes start checked, uncheck MACHINE and apply
+        app.handle_input(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::ApplyChanges));
+        assert!(app.apply_user && app.apply_machine);
+        app.handle_input(KeyEvent::from(KeyCode::Char('m')))
+            .unwrap();
+        assert!(!app.apply_machine);
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+
+        assert_eq!(
+            app.registry.read_paths(PathScope::User).unwrap(),
+            vec![r"C:\Tools".to_string()]
+        );
+        assert_eq!(
+            app.registry.read_paths(PathScope::Machine).unwrap(),
+            vec![r"C:\Machine".to_string()]
+        );
+        assert!(app.has_changes);
 
-        app.user_selected = 0;
-        // normalize_selected works on marked paths, so mark it first
-        app.user_marked.insert(0);
-        app.normalize_selected();
+        // The remaining MACHINE edit is the only one left to apply
+        app.mode = Mode::Normal;
+        app.handle_input(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL))
+            .unwrap();
+        assert!(!app.apply_user && app.apply_machine);
+
+        // Nothing checked keeps the dialog open
+        app.handle_input(KeyEvent::from(KeyCode::Char('m')))
+            .unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::ApplyChanges));
+    }
 
-        // Path should be normalized to use environment variables
-        assert!(
-            app.user_paths[0].contains('%'),
-            "Expected normalized path to contain env var, got: {}",
-            app.user_paths[0]
+    #[test]
+    fn test_pending_changes_per_host() {
+        let backups = tempfile::tempdir().unwrap();
+        let mut app = create_test_app(vec![r"C:\Machine".to_string()], vec![]);
+        app.backup_dir = backups.path().to_path_buf();
+        app.registry = Box::new(MockRegistry::new("", r"C:\Machine"));
+        app.is_admin = true;
+        app.connection_mode = ConnectionMode::Remote;
+        app.remote_machine_paths = vec![r"C:\Remote".to_string()];
+        app.remote_machine_original = app.remote_machine_paths.clone();
+
+        // Only the local host was edited
+        app.machine_paths.push(r"C:\Shared".to_string());
+        app.has_changes = true;
+        app.handle_input(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::ApplyChanges));
+        let choices = app.apply_choices();
+        assert_eq!(choices.len(), 2);
+        assert!(choices[0].checked && choices[0].changed);
+        assert!(!choices[1].checked && !choices[1].changed);
+
+        // The unchanged remote host cannot be checked
+        app.handle_input(KeyEvent::from(KeyCode::Char('r')))
+            .unwrap();
+        assert!(!app.apply_remote);
+
+        // Remote edits left unapplied stay pending
+        app.mode = Mode::Normal;
+        app.remote_machine_paths.push(r"C:\RemoteTools".to_string());
+        app.handle_input(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL))
+            .unwrap();
+        assert!(app.apply_machine && app.apply_remote);
+        app.handle_input(KeyEvent::from(KeyCode::Char('R')))
+            .unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert_eq!(
+            app.registry.read_paths(PathScope::Machine).unwrap(),
+            app.machine_paths
         );
+        assert!(app.remote_changed());
         assert!(app.has_changes);
+
+        // Disconnecting drops only that host's edits
+        app.disconnect_from_remote().unwrap();
+        assert!(!app.has_changes);
+    }
+
+    #[test]
+    fn test_apply_user_only_without_admin() {
+        let mut app = create_test_app(vec![r"C:\Machine".to_string()], vec![]);
+        app.is_admin = false;
+        app.machine_paths.push(r"C:\Shared".to_string());
+        app.has_changes = true;
+
+        // Only MACHINE edits pending: elevation is the only way forward
+        app.handle_input(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::RequestElevation));
+
+        // With USER edits ready they can be applied without elevating
+        app.mode = Mode::Normal;
+        app.user_paths.push(r"C:\Tools".to_string());
+        app.handle_input(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::ApplyChanges));
+        assert!(app.apply_user && !app.apply_machine);
+
+        // MACHINE cannot be checked without admin rights
+        app.handle_input(KeyEvent::from(KeyCode::Char('m')))
+            .unwrap();
+        assert!(!app.apply_machine);
+    }
+
+    #[test]
+    fn test_show_path_details() {
+        let mut app = create_test_app(vec![], vec![r"\\?\C:\NonExistent".to_string()]);
+
+        app.show_path_details();
+        assert_eq!(app.mode, Mode::PathDetails);
+        let (path, info) = app.selected_path_info().unwrap();
+        assert_eq!(path, r"\\?\C:\NonExistent");
+        assert!(info.has_extended_prefix);
+
+        // Nothing to show for an empty panel
+        app.mode = Mode::Normal;
+        app.active_panel = Panel::Machine;
+        app.show_path_details();
+        assert_eq!(app.mode, Mode::Normal);
     }
 
     #[test]
@@ -3701,6 +14202,215 @@ mod tests {
         assert_eq!(app.input_buffer, r"C:\ExistingPath");
     }
 
+    #[test]
+    fn test_jump_to_problem_wraps_around() {
+        let fs = MemoryFileSystem::new()
+            .with_dir(r"C:\Windows")
+            .with_dir(r"C:\Tools");
+        let user = vec![
+            r"C:\Windows".to_string(),
+            r"C:\Missing".to_string(),
+            r"C:\Tools".to_string(),
+            r"C:\Windows".to_string(),
+        ];
+        let mut app = create_test_app_with_fs(vec![], user, Box::new(fs));
+        app.active_panel = Panel::User;
+
+        // C:\Windows is listed twice, so both copies are on the way
+        app.handle_input(KeyEvent::from(KeyCode::Char('}')))
+            .unwrap();
+        assert_eq!(app.user_selected, 1);
+        assert_eq!(
+            app.status_message,
+            "Problem 2/3: Dead (path does not exist)"
+        );
+        app.handle_input(KeyEvent::from(KeyCode::Char('}')))
+            .unwrap();
+        assert_eq!(app.user_selected, 3);
+        app.handle_input(KeyEvent::from(KeyCode::Char('}')))
+            .unwrap();
+        assert_eq!(app.user_selected, 0);
+        assert!(app.status_message.starts_with("Problem 1/3: Duplicate"));
+        assert!(app.status_message.ends_with("(wrapped around)"));
+        app.handle_input(KeyEvent::from(KeyCode::Char('{')))
+            .unwrap();
+        assert_eq!(app.user_selected, 3);
+
+        app.active_panel = Panel::Machine;
+        app.handle_input(KeyEvent::from(KeyCode::Char('{')))
+            .unwrap();
+        assert!(app.status_message.starts_with("No dead, duplicate"));
+    }
+
+    #[test]
+    fn test_f9_focuses_menu_bar() {
+        let mut app = create_test_app(vec![], vec![r"C:\Tools".to_string()]);
+        app.active_panel = Panel::User;
+
+        app.handle_input(KeyEvent::from(KeyCode::F(9))).unwrap();
+        assert_eq!(app.mode, Mode::MenuBar { active_menu: 0 });
+        app.handle_input(KeyEvent::from(KeyCode::Left)).unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Left)).unwrap();
+        assert_eq!(app.mode, Mode::MenuBar { active_menu: 2 });
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert_eq!(
+            app.mode,
+            Mode::Menu {
+                active_menu: 2,
+                selected_item: 0
+            }
+        );
+        app.handle_input(KeyEvent::from(KeyCode::Esc)).unwrap();
+
+        // Shift+F9 normalizes, which clears the marks, and the setting gives F9 back to it
+        app.user_marked.insert(0);
+        app.handle_input(KeyEvent::new(KeyCode::F(9), KeyModifiers::SHIFT))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.user_marked.is_empty());
+        app.settings.f9_normalizes = true;
+        app.user_marked.insert(0);
+        app.handle_input(KeyEvent::from(KeyCode::F(9))).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.user_marked.is_empty());
+    }
+
+    #[test]
+    fn test_menu_accelerators_run_items() {
+        let fs = MemoryFileSystem::new().with_dir(r"C:\Windows");
+        let user = vec![r"C:\Windows".to_string(), r"C:\Missing".to_string()];
+        let mut app = create_test_app_with_fs(vec![], user, Box::new(fs));
+        app.active_panel = Panel::User;
+
+        // Every list gets letters of its own, underlined where they appear
+        let command = app.menus().remove(1);
+        let mut letters: Vec<char> = command.items.iter().flat_map(|i| i.accelerator).collect();
+        letters.sort();
+        letters.dedup();
+        assert_eq!(letters.len(), command.items.len());
+        assert_eq!(command.items[0].accelerator_range(), Some(0..1));
+
+        // Command > Mark > All Dead, by letters alone
+        app.mode = Mode::Menu {
+            active_menu: 1,
+            selected_item: 0,
+        };
+        app.handle_input(KeyEvent::from(KeyCode::Char('m')))
+            .unwrap();
+        assert_eq!(app.menu_submenu, Some(0));
+        app.handle_input(KeyEvent::from(KeyCode::Char('D')))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.user_marked, HashSet::from([1]));
+    }
+
+    #[test]
+    fn test_submenus_and_recent_remotes() {
+        let fs = MemoryFileSystem::new().with_dir(r"C:\Windows");
+        let user = vec![r"C:\Windows".to_string(), r"C:\Missing".to_string()];
+        let mut app = create_test_app_with_fs(vec![], user, Box::new(fs));
+        app.active_panel = Panel::User;
+
+        // Command > Mark > All Dead
+        let mark = app.menus()[1]
+            .items
+            .iter()
+            .position(|item| item.label == "Mark")
+            .unwrap();
+        app.mode = Mode::Menu {
+            active_menu: 1,
+            selected_item: mark,
+        };
+        app.handle_input(KeyEvent::from(KeyCode::Right)).unwrap();
+        assert_eq!(app.menu_submenu, Some(0));
+        app.handle_input(KeyEvent::from(KeyCode::Esc)).unwrap();
+        assert_eq!(app.menu_submenu, None);
+        assert!(matches!(app.mode, Mode::Menu { .. }));
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Down)).unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Down)).unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.user_marked, HashSet::from([1]));
+
+        // Reconnecting moves a host to the top without listing it twice
+        let dir = tempfile::tempdir().unwrap();
+        app.settings_file = dir.path().join("config.ini");
+        app.remember_recent_remote("server01");
+        app.remember_recent_remote("build-agent");
+        app.remember_recent_remote("SERVER01");
+        assert_eq!(app.settings.recent_remotes, vec!["SERVER01", "build-agent"]);
+        let menus = app.menus();
+        let recent: Vec<String> = menus[0]
+            .items
+            .iter()
+            .find(|item| item.label == "Recent Remotes")
+            .unwrap()
+            .submenu
+            .iter()
+            .map(|item| item.label.clone())
+            .collect();
+        assert_eq!(recent, vec!["SERVER01", "build-agent"]);
+        assert_eq!(
+            config::Settings::load(&app.settings_file)
+                .unwrap()
+                .recent_remotes,
+            app.settings.recent_remotes
+        );
+    }
+
+    #[test]
+    fn test_positions_follow_filters_and_bookmarks() {
+        let user: Vec<String> = (0..50).map(|i| format!(r"C:\Tools\{:02}", i)).collect();
+        let mut fs = MemoryFileSystem::new();
+        for path in user.iter().step_by(2) {
+            fs = fs.with_dir(path);
+        }
+        let mut app = create_test_app_with_fs(vec![], user, Box::new(fs));
+        app.active_panel = Panel::User;
+        app.viewport_height = 10;
+
+        // The list scrolls to keep the selection in view
+        app.handle_input(KeyEvent::from(KeyCode::End)).unwrap();
+        assert_eq!(
+            app.panel_position(Panel::User),
+            PanelPosition {
+                selected: 49,
+                scroll: 40
+            }
+        );
+        app.handle_input(KeyEvent::from(KeyCode::Char('m')))
+            .unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Char('5')))
+            .unwrap();
+        app.handle_input(KeyEvent::from(KeyCode::Home)).unwrap();
+        assert_eq!(app.panel_position(Panel::User), PanelPosition::default());
+        app.handle_input(KeyEvent::new(KeyCode::Char('5'), KeyModifiers::ALT))
+            .unwrap();
+        assert_eq!(app.panel_position(Panel::User).selected, 49);
+        assert_eq!(app.panel_position(Panel::User).scroll, 40);
+
+        // Each filter comes back to where it was left
+        app.toggle_filter();
+        app.last_filter = FilterMode::Dead;
+        app.toggle_filter();
+        app.handle_input(KeyEvent::from(KeyCode::Up)).unwrap();
+        assert_eq!(app.panel_position(Panel::User).selected, 47);
+        app.toggle_filter();
+        assert_eq!(app.filter_mode, FilterMode::None);
+        assert_eq!(app.panel_position(Panel::User).selected, 49);
+        app.toggle_filter();
+        assert_eq!(app.panel_position(Panel::User).selected, 47);
+
+        // Unset bookmarks say how to set them
+        app.handle_input(KeyEvent::new(KeyCode::Char('2'), KeyModifiers::ALT))
+            .unwrap();
+        assert_eq!(
+            app.status_message,
+            "No bookmark 2 in this panel, set one with m2"
+        );
+    }
+
     #[test]
     fn test_update_viewport_height() {
         let mut app = create_test_app(vec![], vec![]);
@@ -3713,6 +14423,34 @@ mod tests {
         assert_eq!(app.viewport_height, 41); // 50 - 9
     }
 
+    #[test]
+    fn test_resize_event_reflows() {
+        let mut app = create_test_app(vec![], vec![]);
+        app.mode = Mode::Help;
+        app.dialog_offsets.insert(Dialog::Help, (10, 5));
+
+        app.handle_event(Event::Resize(100, 40), Rect::new(0, 0, 80, 24))
+            .unwrap();
+        assert_eq!(app.viewport_height, 31);
+        assert!(app.dialog_offsets.is_empty());
+        assert_eq!(app.mode, Mode::Help);
+
+        // Clicks are ignored while the terminal is too small to lay out the screen
+        app.mode = Mode::Normal;
+        let click = MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 2,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+        };
+        app.handle_event(Event::Mouse(click), Rect::new(0, 0, 60, 20))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        app.handle_event(Event::Mouse(click), Rect::new(0, 0, 80, 24))
+            .unwrap();
+        assert!(matches!(app.mode, Mode::Menu { .. }));
+    }
+
     #[test]
     fn test_reanalyze_adjusts_selection() {
         let mut app = create_test_app(
@@ -3787,13 +14525,13 @@ mod tests {
     fn test_calculate_path_length() {
         // Test empty paths
         let app = create_test_app(vec![], vec![]);
-        assert_eq!(app.calculate_path_length(Panel::Machine), 0);
-        assert_eq!(app.calculate_path_length(Panel::User), 0);
+        assert_eq!(app.path_lengths(Panel::Machine).stored, 0);
+        assert_eq!(app.path_lengths(Panel::User).stored, 0);
 
         // Test single path (no separators)
         let app = create_test_app(vec![r"C:\Windows".to_string()], vec![]);
-        assert_eq!(app.calculate_path_length(Panel::Machine), 10); // "C:\Windows".len()
-        assert_eq!(app.calculate_path_length(Panel::User), 0);
+        assert_eq!(app.path_lengths(Panel::Machine).stored, 10); // "C:\Windows".len()
+        assert_eq!(app.path_lengths(Panel::User).stored, 0);
 
         // Test multiple paths with separators
         // "C:\Windows" (10) + ";" (1) + "C:\Program Files" (16) = 27
@@ -3801,7 +14539,7 @@ mod tests {
             vec![r"C:\Windows".to_string(), r"C:\Program Files".to_string()],
             vec![],
         );
-        assert_eq!(app.calculate_path_length(Panel::Machine), 27);
+        assert_eq!(app.path_lengths(Panel::Machine).stored, 27);
 
         // Test both panels with different lengths
         // Machine: "C:\A" (4) + ";" + "C:\B" (4) = 9
@@ -3814,15 +14552,172 @@ mod tests {
                 r"C:\Third".to_string(),
             ],
         );
-        assert_eq!(app.calculate_path_length(Panel::Machine), 9);
-        assert_eq!(app.calculate_path_length(Panel::User), 32);
+        assert_eq!(app.path_lengths(Panel::Machine).stored, 9);
+        assert_eq!(app.path_lengths(Panel::User).stored, 32);
 
         // Test path that exceeds Windows limit
         // Create a path string that's longer than 2047 characters
         let long_path = "C:\\".to_string() + &"VeryLongDirectoryName".repeat(100);
         let app = create_test_app(vec![long_path.clone()], vec![]);
-        let length = app.calculate_path_length(Panel::Machine);
+        let length = app.path_lengths(Panel::Machine).stored;
         assert!(length > 2047, "Path length {} should exceed 2047", length);
         assert_eq!(length, long_path.len());
     }
+
+    #[test]
+    fn test_path_lengths_expanded() {
+        let mut app = create_test_app(
+            vec![r"C:\Windows".to_string()],
+            vec![r"%USERPROFILE%\bin".to_string(), r"C:\Tools".to_string()],
+        );
+        app.settings.profile_root = Some(r"C:\Users\alexandria".to_string());
+        // "%USERPROFILE%\bin;C:\Tools" and "C:\Users\alexandria\bin;C:\Tools"
+        assert_eq!(
+            app.path_lengths(Panel::User),
+            Lengths {
+                stored: 26,
+                expanded: 32,
+            }
+        );
+        assert_eq!(app.combined_expanded_length(), Some(10 + 1 + 32));
+
+        app.handle_input(KeyEvent::new(KeyCode::Char('L'), KeyModifiers::SHIFT))
+            .unwrap();
+        assert_eq!(app.mode, Mode::PathLengths);
+        app.handle_input(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn test_whats_new_shown_once_per_version() {
+        let mut app = create_test_app(vec![], vec![]);
+        let dir = tempfile::tempdir().unwrap();
+        app.settings_file = dir.path().join("config.ini");
+
+        app.show_whats_new_once();
+        assert_eq!(app.mode, Mode::WhatsNew);
+        assert_eq!(
+            app.releases[app.release_selected].version,
+            release_notes::CURRENT_VERSION
+        );
+        assert_eq!(
+            config::Settings::load(&app.settings_file)
+                .unwrap()
+                .last_seen_version
+                .as_deref(),
+            Some(release_notes::CURRENT_VERSION)
+        );
+
+        // Left moves to a newer release and never past the first
+        let selected = app.release_selected;
+        for _ in 0..=selected {
+            app.handle_input(KeyEvent::new(KeyCode::Left, KeyModifiers::NONE))
+                .unwrap();
+        }
+        assert_eq!(app.release_selected, 0);
+        for _ in 0..app.releases.len() {
+            app.handle_input(KeyEvent::new(KeyCode::Right, KeyModifiers::NONE))
+                .unwrap();
+        }
+        assert_eq!(app.release_selected, app.releases.len() - 1);
+        app.handle_input(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+
+        // The same version does not show the notes again
+        app.show_whats_new_once();
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn test_install_update_needs_a_found_update() {
+        let mut app = create_test_app(vec![], vec![]);
+        app.execute_menu_action(crate::menu::MenuAction::InstallUpdate)
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.status_message.contains("Check for Updates"));
+
+        // The quiet check at startup keeps "up to date" out of the status bar
+        app.set_status("");
+        app.update_check_quiet = true;
+        app.finish_update_check(Ok(None));
+        assert_eq!(app.status_message, "");
+
+        let update = Update {
+            version: "99.0.0".to_string(),
+            page_url: "https://example.com/v99".to_string(),
+            executable_url: "https://example.com/pc.exe".to_string(),
+            checksum_url: None,
+        };
+        app.finish_update_check(Ok(Some(update.clone())));
+        assert_eq!(app.update, Some(update));
+        assert!(app.status_message.contains("v99.0.0 is available"));
+
+        // Nothing is downloaded that startup would not install
+        app.settings.check_updates = false;
+        app.execute_menu_action(crate::menu::MenuAction::InstallUpdate)
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.status_message.starts_with("Updates are off"));
+
+        app.settings.check_updates = true;
+        app.execute_menu_action(crate::menu::MenuAction::InstallUpdate)
+            .unwrap();
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::InstallUpdate));
+        app.handle_input(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+
+        // Once downloaded there is nothing left to confirm
+        app.update_staged = Some("99.0.0".to_string());
+        app.execute_menu_action(crate::menu::MenuAction::InstallUpdate)
+            .unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.status_message.contains("next time pc starts"));
+    }
+
+    #[test]
+    fn test_control_commands_stage_edits_for_review() {
+        let mut app = create_test_app(
+            vec![r"C:\Windows".to_string()],
+            vec![r"C:\Tools".to_string()],
+        );
+        let add = |path: &str, scope: Option<&str>| control::Command::Add {
+            path: path.to_string(),
+            scope: scope.map(str::to_string),
+        };
+
+        let response = app.run_control_command(&add(r"C:\Installer\bin", None));
+        assert!(response.ok);
+        assert_eq!(app.user_paths, vec![r"C:\Tools", r"C:\Installer\bin"]);
+        assert_eq!(app.user_marked, HashSet::from([1]));
+        assert_eq!(app.active_panel, Panel::User);
+        // Staged only, the registry is untouched until the user applies
+        assert!(app.has_changes);
+
+        // Adding it twice keeps one copy
+        let response = app.run_control_command(&add(r"c:\installer\bin\", Some("user")));
+        assert!(response.ok);
+        assert_eq!(app.user_paths.len(), 2);
+        assert!(!app.run_control_command(&add(r"C:\X", Some("everyone"))).ok);
+
+        let response = app.run_control_command(&control::Command::Remove {
+            path: r"C:\Windows".to_string(),
+            scope: Some("machine".to_string()),
+        });
+        assert!(response.ok);
+        assert!(app.machine_paths.is_empty());
+        app.undo().unwrap();
+        assert_eq!(app.machine_paths, vec![r"C:\Windows"]);
+
+        let query = app.run_control_command(&control::Command::Query);
+        assert_eq!(query.user, Some(app.user_paths.clone()));
+        assert_eq!(query.pending, Some(true));
+
+        // Apply only opens the dialog, and edits wait while it is open
+        assert!(app.run_control_command(&control::Command::Apply).ok);
+        assert_eq!(app.mode, Mode::Confirm(ConfirmAction::ApplyChanges));
+        assert!(!app.run_control_command(&add(r"C:\Later", None)).ok);
+    }
 }
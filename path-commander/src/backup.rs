@@ -1,9 +1,7 @@
 use anyhow::{Context, Result};
-use chrono::Local;
+use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
 
-#[cfg(test)]
-use chrono::DateTime;
 use std::fs::{self, File};
 use std::io::{BufReader, BufWriter};
 use std::path::{Path, PathBuf};
@@ -64,17 +62,20 @@ impl PathBackup {
         Ok(backup)
     }
 
-    /// Get a formatted display string for this backup
-    #[cfg(test)]
-    pub fn display_info(&self) -> String {
-        let dt = DateTime::parse_from_rfc3339(&self.timestamp)
+    /// When the backup was taken, as shown to the user
+    pub fn display_time(&self) -> String {
+        DateTime::parse_from_rfc3339(&self.timestamp)
             .ok()
             .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
-            .unwrap_or_else(|| self.timestamp.clone());
+            .unwrap_or_else(|| self.timestamp.clone())
+    }
 
+    /// Get a formatted display string for this backup
+    #[cfg(test)]
+    pub fn display_info(&self) -> String {
         format!(
             "Backup from {}\nUSER paths: {}\nMACHINE paths: {}",
-            dt,
+            self.display_time(),
             self.user_paths.len(),
             self.machine_paths.len()
         )
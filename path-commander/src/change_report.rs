@@ -0,0 +1,374 @@
+use chrono::{DateTime, Local};
+
+use crate::history::ScopeChange;
+use crate::registry::PathScope;
+
+/// File type of a change summary
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    pub const ALL: [ReportFormat; 2] = [ReportFormat::Markdown, ReportFormat::Html];
+
+    pub fn next(self) -> Self {
+        match self {
+            ReportFormat::Markdown => ReportFormat::Html,
+            ReportFormat::Html => ReportFormat::Markdown,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "Markdown",
+            ReportFormat::Html => "HTML",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "md",
+            ReportFormat::Html => "html",
+        }
+    }
+
+    /// Value in config.ini, case-insensitive
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "markdown" | "md" => Some(ReportFormat::Markdown),
+            "html" => Some(ReportFormat::Html),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "markdown",
+            ReportFormat::Html => "html",
+        }
+    }
+}
+
+/// An entry the health check has something to say about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub scope: PathScope,
+    pub entry: String,
+    pub problem: String,
+}
+
+/// One scope before and after the change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopeDiff {
+    pub scope: PathScope,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+/// What a change ticket wants to know: where, who, what changed and what is still wrong
+#[derive(Debug, Clone)]
+pub struct ChangeReport {
+    pub computer: String,
+    pub operator: String,
+    pub generated: DateTime<Local>,
+    /// "Planned (not applied yet)" or "Applied ..."
+    pub state: String,
+    pub scopes: Vec<ScopeDiff>,
+    /// Problems left in the PATH after the change
+    pub findings: Vec<Finding>,
+    /// Length limit warnings for the PATH after the change
+    pub warnings: Vec<String>,
+}
+
+/// Who runs Path Commander, DOMAIN\user where Windows says
+pub fn operator_name() -> String {
+    let user = std::env::var("USERNAME")
+        .or_else(|_| std::env::var("USER"))
+        .unwrap_or_else(|_| "unknown".to_string());
+    match std::env::var("USERDOMAIN") {
+        Ok(domain) if !domain.is_empty() => format!("{}\\{}", domain, user),
+        _ => user,
+    }
+}
+
+impl ChangeReport {
+    /// Name of the file the report is saved as, e.g. path-change-PC01-20260310-140500.md
+    pub fn file_name(&self, format: ReportFormat) -> String {
+        let computer: String = self
+            .computer
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!(
+            "path-change-{}-{}.{}",
+            computer,
+            self.generated.format("%Y%m%d-%H%M%S"),
+            format.extension()
+        )
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Markdown => self.markdown(),
+            ReportFormat::Html => self.html(),
+        }
+    }
+
+    /// Changed scopes with what was added, removed or reordered
+    fn changes(&self) -> Vec<(&ScopeDiff, ScopeChange)> {
+        self.scopes
+            .iter()
+            .filter_map(|diff| {
+                ScopeChange::between(diff.scope, &diff.before, &diff.after)
+                    .map(|change| (diff, change))
+            })
+            .collect()
+    }
+
+    fn markdown(&self) -> String {
+        let mut out = String::from("# PATH Change Summary\n\n");
+        out.push_str(&format!("- **Computer:** {}\n", self.computer));
+        out.push_str(&format!("- **Operator:** {}\n", self.operator));
+        out.push_str(&format!(
+            "- **Generated:** {}\n",
+            self.generated.format("%Y-%m-%d %H:%M:%S %:z")
+        ));
+        out.push_str(&format!("- **State:** {}\n", self.state));
+
+        let changes = self.changes();
+        out.push_str("\n## Summary\n\n");
+        for (_, change) in &changes {
+            out.push_str(&format!("- {}\n", change.summary()));
+        }
+
+        for (diff, change) in &changes {
+            out.push_str(&format!("\n## {}\n\n", diff.scope.as_str()));
+            for (title, entries) in [("Added", &change.added), ("Removed", &change.removed)] {
+                if entries.is_empty() {
+                    continue;
+                }
+                out.push_str(&format!("{}:\n\n", title));
+                for entry in entries {
+                    out.push_str(&format!("- `{}`\n", entry));
+                }
+                out.push('\n');
+            }
+            if change.reordered {
+                out.push_str("The remaining entries were reordered.\n\n");
+            }
+            out.push_str("```diff\n");
+            for line in diff_lines(&diff.before, &diff.after) {
+                out.push_str(&line);
+                out.push('\n');
+            }
+            out.push_str("```\n");
+        }
+
+        out.push_str("\n## Health Check\n\n");
+        if self.findings.is_empty() && self.warnings.is_empty() {
+            out.push_str("No problems found.\n");
+        }
+        for warning in &self.warnings {
+            out.push_str(&format!("- {}\n", warning));
+        }
+        if !self.findings.is_empty() {
+            out.push_str("\n| Scope | Entry | Problem |\n|-------|-------|---------|\n");
+            for finding in &self.findings {
+                out.push_str(&format!(
+                    "| {} | `{}` | {} |\n",
+                    finding.scope.as_str(),
+                    finding.entry.replace('|', "\\|"),
+                    finding.problem
+                ));
+            }
+        }
+        out
+    }
+
+    fn html(&self) -> String {
+        let mut out = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>PATH Change Summary</title>\n<style>\n\
+             body { font-family: Segoe UI, sans-serif; margin: 2em; }\n\
+             table { border-collapse: collapse; }\n\
+             th, td { border: 1px solid #999; padding: 0.2em 0.6em; text-align: left; }\n\
+             pre { background: #f4f4f4; padding: 0.6em; }\n\
+             .added { color: #060; } .removed { color: #a00; }\n\
+             </style>\n</head>\n<body>\n<h1>PATH Change Summary</h1>\n<table>\n",
+        );
+        for (label, value) in [
+            ("Computer", self.computer.clone()),
+            ("Operator", self.operator.clone()),
+            (
+                "Generated",
+                self.generated.format("%Y-%m-%d %H:%M:%S %:z").to_string(),
+            ),
+            ("State", self.state.clone()),
+        ] {
+            out.push_str(&format!(
+                "<tr><th>{}</th><td>{}</td></tr>\n",
+                label,
+                escape(&value)
+            ));
+        }
+        out.push_str("</table>\n");
+
+        let changes = self.changes();
+        out.push_str("<h2>Summary</h2>\n<ul>\n");
+        for (_, change) in &changes {
+            out.push_str(&format!("<li>{}</li>\n", escape(&change.summary())));
+        }
+        out.push_str("</ul>\n");
+
+        for (diff, change) in &changes {
+            out.push_str(&format!("<h2>{}</h2>\n", diff.scope.as_str()));
+            for (title, entries) in [("Added", &change.added), ("Removed", &change.removed)] {
+                if entries.is_empty() {
+                    continue;
+                }
+                out.push_str(&format!("<p>{}:</p>\n<ul>\n", title));
+                for entry in entries {
+                    out.push_str(&format!("<li><code>{}</code></li>\n", escape(entry)));
+                }
+                out.push_str("</ul>\n");
+            }
+            if change.reordered {
+                out.push_str("<p>The remaining entries were reordered.</p>\n");
+            }
+            out.push_str("<pre>\n");
+            for line in diff_lines(&diff.before, &diff.after) {
+                let class = match line.chars().next() {
+                    Some('+') => "added",
+                    Some('-') => "removed",
+                    _ => "",
+                };
+                out.push_str(&format!(
+                    "<span class=\"{}\">{}</span>\n",
+                    class,
+                    escape(&line)
+                ));
+            }
+            out.push_str("</pre>\n");
+        }
+
+        out.push_str("<h2>Health Check</h2>\n");
+        if self.findings.is_empty() && self.warnings.is_empty() {
+            out.push_str("<p>No problems found.</p>\n");
+        }
+        if !self.warnings.is_empty() {
+            out.push_str("<ul>\n");
+            for warning in &self.warnings {
+                out.push_str(&format!("<li>{}</li>\n", escape(warning)));
+            }
+            out.push_str("</ul>\n");
+        }
+        if !self.findings.is_empty() {
+            out.push_str("<table>\n<tr><th>Scope</th><th>Entry</th><th>Problem</th></tr>\n");
+            for finding in &self.findings {
+                out.push_str(&format!(
+                    "<tr><td>{}</td><td><code>{}</code></td><td>{}</td></tr>\n",
+                    finding.scope.as_str(),
+                    escape(&finding.entry),
+                    escape(&finding.problem)
+                ));
+            }
+            out.push_str("</table>\n");
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+}
+
+/// The entries after the change in order, "+" for added ones, then "-" for the removed ones
+fn diff_lines(before: &[String], after: &[String]) -> Vec<String> {
+    let mut lines: Vec<String> = after
+        .iter()
+        .map(|entry| {
+            let mark = if before.contains(entry) { ' ' } else { '+' };
+            format!("{} {}", mark, entry)
+        })
+        .collect();
+    lines.extend(
+        before
+            .iter()
+            .filter(|entry| !after.contains(entry))
+            .map(|entry| format!("- {}", entry)),
+    );
+    lines
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn sample() -> ChangeReport {
+        let paths = |entries: &[&str]| entries.iter().map(|e| e.to_string()).collect();
+        ChangeReport {
+            computer: "PC 01".to_string(),
+            operator: r"CORP\alice".to_string(),
+            generated: Local.with_ymd_and_hms(2026, 3, 10, 14, 5, 0).unwrap(),
+            state: "Planned (not applied yet)".to_string(),
+            scopes: vec![
+                ScopeDiff {
+                    scope: PathScope::Machine,
+                    before: paths(&[r"C:\Windows"]),
+                    after: paths(&[r"C:\Windows"]),
+                },
+                ScopeDiff {
+                    scope: PathScope::User,
+                    before: paths(&[r"C:\Old", r"C:\Tools"]),
+                    after: paths(&[r"C:\Tools", r"C:\<New>"]),
+                },
+            ],
+            findings: vec![Finding {
+                scope: PathScope::User,
+                entry: r"C:\<New>".to_string(),
+                problem: "Dead (path does not exist)".to_string(),
+            }],
+            warnings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_markdown_report() {
+        let report = sample();
+        assert_eq!(
+            report.file_name(ReportFormat::Markdown),
+            "path-change-PC_01-20260310-140500.md"
+        );
+        let text = report.render(ReportFormat::Markdown);
+        assert!(text.contains(r"- **Operator:** CORP\alice"));
+        assert!(text.contains("- USER +1 -1\n"));
+        // Unchanged scopes are left out
+        assert!(!text.contains("## MACHINE"));
+        assert!(text.contains("```diff\n  C:\\Tools\n+ C:\\<New>\n- C:\\Old\n```"));
+        assert!(text.contains("| USER | `C:\\<New>` | Dead (path does not exist) |"));
+    }
+
+    #[test]
+    fn test_html_report_is_escaped() {
+        let text = sample().render(ReportFormat::Html);
+        assert!(text.contains("<tr><th>Computer</th><td>PC 01</td></tr>"));
+        assert!(text.contains("<span class=\"added\">+ C:\\&lt;New&gt;</span>"));
+        assert!(!text.contains("<New>"));
+        assert_eq!(ReportFormat::parse("HTML"), Some(ReportFormat::Html));
+        assert_eq!(ReportFormat::parse("pdf"), None);
+    }
+}
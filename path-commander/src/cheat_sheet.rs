@@ -0,0 +1,80 @@
+use crate::config::Settings;
+use crate::menu::{Menu, MenuItem};
+
+/// Markdown cheat sheet of the shortcuts the menus list, as bound with the given settings
+pub fn markdown(menus: &[Menu], settings: &Settings) -> String {
+    let mut out = String::from("# Path Commander Keyboard Shortcuts\n\n");
+
+    out.push_str("## Menu Bar\n\n| Keys | Command |\n|------|---------|\n");
+    if !settings.f9_normalizes {
+        out.push_str("| F9 | Focus the menu bar |\n");
+    }
+    for menu in menus {
+        out.push_str(&format!(
+            "| Alt+{} | Open the {} menu |\n",
+            menu.accelerator.to_ascii_uppercase(),
+            menu.name
+        ));
+    }
+
+    for menu in menus {
+        let mut rows = Vec::new();
+        collect_rows(&menu.items, "", &mut rows);
+        if rows.is_empty() {
+            continue;
+        }
+        out.push_str(&format!(
+            "\n## {}\n\n| Keys | Command |\n|------|---------|\n",
+            menu.name
+        ));
+        for (keys, label) in rows {
+            out.push_str(&format!("| {} | {} |\n", keys, label));
+        }
+    }
+    out
+}
+
+/// (keys, label) of items with a shortcut, submenu items prefixed with their submenu
+fn collect_rows(items: &[MenuItem], prefix: &str, rows: &mut Vec<(String, String)>) {
+    for item in items {
+        if item.has_submenu() {
+            collect_rows(&item.submenu, &format!("{}{} → ", prefix, item.label), rows);
+        } else if let Some(shortcut) = &item.shortcut {
+            // Markdown tables split cells on |
+            rows.push((
+                shortcut.replace('|', "\\|"),
+                format!("{}{}", prefix, item.label),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::ConnectionMode;
+    use crate::menu::{apply_key_settings, get_menus};
+
+    #[test]
+    fn test_markdown_follows_menus_and_remaps() {
+        let settings = Settings::default();
+        let menus = get_menus(ConnectionMode::Local, &[]);
+        let sheet = markdown(&menus, &settings);
+        assert!(sheet.contains("| F9 | Focus the menu bar |"));
+        assert!(sheet.contains("| Alt+C | Open the Command menu |"));
+        assert!(sheet.contains("| Ctrl+Shift+D | Mark → All Dead |"));
+        assert!(sheet.contains("| Shift+F9 | Normalize Selected |"));
+        // Items without a key are left out
+        assert!(!sheet.contains("Security Scan"));
+
+        let settings = Settings {
+            f9_normalizes: true,
+            ..Settings::default()
+        };
+        let mut menus = get_menus(ConnectionMode::Local, &[]);
+        apply_key_settings(&mut menus, &settings);
+        let sheet = markdown(&menus, &settings);
+        assert!(!sheet.contains("Focus the menu bar"));
+        assert!(sheet.contains("| F9/Shift+F9 | Normalize Selected |"));
+    }
+}
@@ -0,0 +1,306 @@
+use anyhow::Result;
+use crossterm::style::{Color, Stylize};
+use std::io::IsTerminal;
+
+use crate::config::Settings;
+use crate::filesystem::FileSystem;
+use crate::path_analyzer::{analyze_scopes_in, expand_environment_variables, PathInfo, PathStatus};
+use crate::registry::{join_paths, PathScope, RegistryBackend};
+
+/// How command output is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStyle {
+    Color,
+    Plain,
+}
+
+impl OutputStyle {
+    /// Plain when asked for, when stdout is piped or captured, or on a dumb terminal
+    pub fn detect(plain: bool) -> Self {
+        if plain || !rich_terminal() {
+            OutputStyle::Plain
+        } else {
+            OutputStyle::Color
+        }
+    }
+}
+
+/// Whether stdout can take colors and a full-screen UI
+pub fn rich_terminal() -> bool {
+    std::io::stdout().is_terminal()
+        && std::env::var_os("NO_COLOR").is_none()
+        && std::env::var("TERM").map_or(true, |term| term != "dumb")
+}
+
+/// `pc list`: print every entry with its position and status, MACHINE first
+pub fn run_list(
+    registry: &dyn RegistryBackend,
+    scope: Option<PathScope>,
+    settings: &Settings,
+    fs: &dyn FileSystem,
+    style: OutputStyle,
+) -> Result<()> {
+    let machine = registry.read_paths(PathScope::Machine)?;
+    let user = registry.read_paths(PathScope::User)?;
+    // Analyze both together so duplicates across scopes are found
+    let (machine_info, user_info) = analyze_scopes_in(
+        &machine,
+        &user,
+        fs,
+        &crate::app::expansion_context(settings, false),
+    );
+
+    let mut scopes = Vec::new();
+    if scope != Some(PathScope::User) {
+        scopes.push((PathScope::Machine, &machine[..], &machine_info[..]));
+    }
+    if scope != Some(PathScope::Machine) {
+        scopes.push((PathScope::User, &user[..], &user_info[..]));
+    }
+    print!("{}", format_list(&scopes, style));
+    Ok(())
+}
+
+/// Shells `pc env` writes a PATH assignment for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum EnvShell {
+    Powershell,
+    Cmd,
+    Bash,
+}
+
+impl EnvShell {
+    /// bash when SHELL is set (Git Bash, WSL, Unix), PowerShell otherwise
+    /// cmd.exe sets nothing that PowerShell does not inherit, so it has to be asked for
+    pub fn detect() -> Self {
+        if std::env::var_os("SHELL").is_some() {
+            EnvShell::Bash
+        } else {
+            EnvShell::Powershell
+        }
+    }
+}
+
+/// `pc env`: print the PATH a new terminal would get, as a statement for the current shell
+pub fn run_env(registry: &dyn RegistryBackend, shell: EnvShell) -> Result<()> {
+    let mut entries = registry.read_paths(PathScope::Machine)?;
+    entries.extend(registry.read_paths(PathScope::User)?);
+    let expanded: Vec<String> = entries
+        .iter()
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| expand_environment_variables(entry))
+        .collect();
+    println!("{}", format_env(shell, &expanded, cfg!(windows)));
+    Ok(())
+}
+
+/// Variable a refresh hook wrapper sets to a file name, pc creates the file after an apply
+pub const REFRESH_FILE_VAR: &str = "PC_REFRESH_FILE";
+
+/// `pc refresh-hook`: a wrapper that runs pc and reloads PATH into the calling shell after
+/// an apply, which no child process can do for its parent
+pub fn refresh_hook(shell: EnvShell) -> String {
+    let script = match shell {
+        EnvShell::Powershell => {
+            "# Path Commander refresh hook for PowerShell\n\
+             # Add to $PROFILE: pc refresh-hook powershell | Out-String | Invoke-Expression\n\
+             function pc {\n    \
+                 $exe = (Get-Command pc -CommandType Application | Select-Object -First 1).Source\n    \
+                 $marker = Join-Path ([System.IO.Path]::GetTempPath()) \"pc-refresh-$PID.txt\"\n    \
+                 Remove-Item $marker -ErrorAction SilentlyContinue\n    \
+                 $env:{refresh_var} = $marker\n    \
+                 try { & $exe @args } finally { Remove-Item Env:{refresh_var} -ErrorAction SilentlyContinue }\n    \
+                 if (Test-Path $marker) {\n        \
+                     & $exe env powershell | Invoke-Expression\n        \
+                     Remove-Item $marker\n        \
+                     Write-Host 'PATH of this session refreshed'\n    \
+                 }\n\
+             }\n"
+        }
+        EnvShell::Cmd => {
+            "@echo off\n\
+             rem Path Commander refresh hook for cmd.exe\n\
+             rem Save as pcr.cmd in a directory on PATH and start pc through it,\n\
+             rem or alias it with: doskey pc=pcr.cmd $*\n\
+             set \"{refresh_var}=%TEMP%\\pc-refresh-%RANDOM%.txt\"\n\
+             if exist \"%{refresh_var}%\" del \"%{refresh_var}%\"\n\
+             pc.exe %*\n\
+             if exist \"%{refresh_var}%\" (\n    \
+                 for /f \"delims=\" %%i in ('pc.exe env cmd') do %%i\n    \
+                 del \"%{refresh_var}%\"\n    \
+                 echo PATH of this session refreshed\n\
+             )\n\
+             set \"{refresh_var}=\"\n"
+        }
+        EnvShell::Bash => {
+            "# Path Commander refresh hook for bash\n\
+             # Add to ~/.bashrc: eval \"$(pc refresh-hook bash)\"\n\
+             pc() {\n    \
+                 local marker status\n    \
+                 marker=\"$(mktemp -u)\"\n    \
+                 {refresh_var}=\"$marker\" command pc \"$@\"\n    \
+                 status=$?\n    \
+                 if [ -e \"$marker\" ]; then\n        \
+                     eval \"$(command pc env bash)\"\n        \
+                     rm -f \"$marker\"\n        \
+                     echo 'PATH of this session refreshed'\n    \
+                 fi\n    \
+                 return $status\n\
+             }\n"
+        }
+    };
+    script.replace("{refresh_var}", REFRESH_FILE_VAR)
+}
+
+/// Assignment of the combined, expanded PATH
+/// `windows` means bash needs the entries converted to /c/... form by cygpath
+fn format_env(shell: EnvShell, entries: &[String], windows: bool) -> String {
+    match shell {
+        EnvShell::Powershell => {
+            format!("$env:PATH = '{}'", join_paths(entries).replace('\'', "''"))
+        }
+        // Quoting the whole assignment keeps & and ) in entries from being run
+        EnvShell::Cmd => format!("SET \"PATH={}\"", join_paths(entries)),
+        EnvShell::Bash => {
+            let quote = |value: &str| format!("'{}'", value.replace('\'', r"'\''"));
+            if windows {
+                format!(
+                    "export PATH=\"$(cygpath -up {})\"",
+                    quote(&join_paths(entries))
+                )
+            } else {
+                format!("export PATH={}", quote(&entries.join(":")))
+            }
+        }
+    }
+}
+
+/// One line per entry: scope, 1-based position, status and the entry as stored
+fn format_list(scopes: &[(PathScope, &[String], &[PathInfo])], style: OutputStyle) -> String {
+    let mut output = String::new();
+    for (scope, paths, info) in scopes {
+        for (idx, path) in paths.iter().enumerate() {
+            let status = info.get(idx).map(|i| i.status).unwrap_or(PathStatus::Valid);
+            let label = format!("{:<10}", status_label(status));
+            let label = match style {
+                OutputStyle::Plain => label,
+                OutputStyle::Color => label.with(status_color(status)).to_string(),
+            };
+            let entry = if path.trim().is_empty() {
+                "<empty segment>"
+            } else {
+                path
+            };
+            output.push_str(&format!(
+                "{:<7} {:>3}  {}  {}\n",
+                scope.as_str(),
+                idx + 1,
+                label,
+                entry
+            ));
+        }
+    }
+    output
+}
+
+/// Single word for a status, so the columns stay easy to cut and grep
+fn status_label(status: PathStatus) -> &'static str {
+    match status {
+        PathStatus::Valid => "valid",
+        PathStatus::Dead => "dead",
+        PathStatus::Duplicate => "duplicate",
+        PathStatus::NonNormalized => "normalize",
+        PathStatus::DeadDuplicate => "dead+dup",
+        PathStatus::Malformed => "malformed",
+        PathStatus::OfflineDrive => "offline",
+        PathStatus::Removable => "removable",
+        PathStatus::Insecure => "insecure",
+        PathStatus::Unchecked => "unchecked",
+    }
+}
+
+/// Same colors the panels use with the default theme
+fn status_color(status: PathStatus) -> Color {
+    match status {
+        PathStatus::Valid => Color::Green,
+        PathStatus::Dead | PathStatus::DeadDuplicate => Color::Red,
+        PathStatus::Duplicate => Color::Yellow,
+        PathStatus::NonNormalized => Color::Cyan,
+        PathStatus::Malformed => Color::Magenta,
+        PathStatus::OfflineDrive => Color::Grey,
+        PathStatus::Removable => Color::Blue,
+        PathStatus::Insecure => Color::DarkRed,
+        PathStatus::Unchecked => Color::Reset,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::MemoryFileSystem;
+    use crate::path_analyzer::analyze_scopes_with_fs;
+
+    #[test]
+    fn test_format_list() {
+        let fs = MemoryFileSystem::new().with_dir(r"C:\Windows");
+        let machine = vec![r"C:\Windows".to_string()];
+        let user = vec![r"C:\Missing".to_string(), String::new()];
+        let (machine_info, user_info) = analyze_scopes_with_fs(&machine, &user, &fs);
+        let scopes = [
+            (PathScope::Machine, &machine[..], &machine_info[..]),
+            (PathScope::User, &user[..], &user_info[..]),
+        ];
+
+        assert_eq!(
+            format_list(&scopes, OutputStyle::Plain),
+            "MACHINE   1  valid       C:\\Windows\n\
+             USER      1  dead        C:\\Missing\n\
+             USER      2  malformed   <empty segment>\n"
+        );
+        // Colors are ANSI escapes around the status only
+        let colored = format_list(&scopes[..1], OutputStyle::Color);
+        assert!(colored.contains("\u{1b}["));
+        assert!(colored.ends_with("C:\\Windows\n"));
+    }
+
+    #[test]
+    fn test_refresh_hook() {
+        let powershell = refresh_hook(EnvShell::Powershell);
+        assert!(powershell.contains("$env:PC_REFRESH_FILE = $marker"));
+        assert!(powershell.contains("& $exe env powershell | Invoke-Expression"));
+        assert!(refresh_hook(EnvShell::Cmd)
+            .contains("for /f \"delims=\" %%i in ('pc.exe env cmd') do %%i"));
+        assert!(
+            refresh_hook(EnvShell::Bash).contains("PC_REFRESH_FILE=\"$marker\" command pc \"$@\"")
+        );
+        for shell in [EnvShell::Powershell, EnvShell::Cmd, EnvShell::Bash] {
+            assert!(!refresh_hook(shell).contains("{refresh_var}"));
+        }
+    }
+
+    #[test]
+    fn test_format_env() {
+        let entries = vec![
+            r"C:\Windows".to_string(),
+            r"C:\Users\o'brien\bin".to_string(),
+        ];
+
+        assert_eq!(
+            format_env(EnvShell::Powershell, &entries, true),
+            r"$env:PATH = 'C:\Windows;C:\Users\o''brien\bin'"
+        );
+        assert_eq!(
+            format_env(EnvShell::Cmd, &entries, true),
+            r#"SET "PATH=C:\Windows;C:\Users\o'brien\bin""#
+        );
+        assert_eq!(
+            format_env(EnvShell::Bash, &entries, true),
+            r#"export PATH="$(cygpath -up 'C:\Windows;C:\Users\o'\''brien\bin')""#
+        );
+        let unix = vec!["/usr/bin".to_string(), "/home/me/bin".to_string()];
+        assert_eq!(
+            format_env(EnvShell::Bash, &unix, false),
+            "export PATH='/usr/bin:/home/me/bin'"
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use std::path::{Path, PathBuf};
+
+use crate::path_analyzer::expand_environment_variables;
+use crate::registry::PathScope;
+
+/// Extensions cmd.exe tries when PATHEXT is not set
+const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD;.VBS;.VBE;.JS;.JSE;.WSF;.WSH;.MSC";
+
+/// How many levels below an added directory its commands are looked for
+const SUGGEST_DEPTH: usize = 3;
+
+/// Directories looked into at most, so adding a drive root does not walk the whole drive
+const SUGGEST_LIMIT: usize = 500;
+
+/// A file a command name resolves to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub scope: PathScope,
+    pub index: usize,
+    /// Full path of the file
+    pub file: String,
+}
+
+/// A command looked up in the edited PATH and in the PATH as last applied
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Lookup {
+    pub name: String,
+    /// Matches in MACHINE then USER as edited, including changes not applied yet
+    pub edited: Vec<Match>,
+    /// Matches in the PATH as loaded or last applied
+    pub applied: Vec<Match>,
+}
+
+impl Lookup {
+    /// Whether applying the edits changes which file runs
+    pub fn changes_winner(&self) -> bool {
+        self.edited.first().map(|m| m.file.to_lowercase())
+            != self.applied.first().map(|m| m.file.to_lowercase())
+    }
+}
+
+/// Extensions tried for a name without one, in order, from PATHEXT
+pub fn extensions() -> Vec<String> {
+    let pathext = std::env::var("PATHEXT").unwrap_or_else(|_| DEFAULT_PATHEXT.to_string());
+    parse_pathext(&pathext)
+}
+
+fn parse_pathext(pathext: &str) -> Vec<String> {
+    pathext
+        .split(';')
+        .map(str::trim)
+        .filter(|ext| ext.starts_with('.') && ext.len() > 1)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// File names a command can be found under: itself if it already has one of the
+/// extensions, otherwise the name with each extension (and as typed outside Windows)
+fn candidates(name: &str, extensions: &[String]) -> Vec<String> {
+    let lower = name.to_lowercase();
+    if extensions.iter().any(|ext| lower.ends_with(ext.as_str())) {
+        return vec![name.to_string()];
+    }
+    let mut names: Vec<String> = extensions
+        .iter()
+        .map(|ext| format!("{}{}", name, ext))
+        .collect();
+    if !cfg!(windows) {
+        names.insert(0, name.to_string());
+    }
+    names
+}
+
+/// Whether a file can be run by name from PATH: one of the extensions, or marked
+/// executable outside Windows
+fn is_command(file: &Path, extensions: &[String]) -> bool {
+    let name = file
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if extensions.iter().any(|ext| name.ends_with(ext.as_str())) {
+        return true;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = file.metadata() {
+            return metadata.permissions().mode() & 0o111 != 0;
+        }
+    }
+    false
+}
+
+/// Whether a directory directly holds anything runnable
+pub fn has_commands(dir: &Path, extensions: &[String]) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return false;
+    };
+    entries
+        .flatten()
+        .any(|entry| entry.path().is_file() && is_command(&entry.path(), extensions))
+}
+
+/// For a directory without commands, the nearest subdirectory that has some, e.g.
+/// `bin` of an install folder; a `bin` wins over others at the same depth
+pub fn suggest_command_dir(dir: &Path, extensions: &[String]) -> Option<PathBuf> {
+    let mut level = vec![dir.to_path_buf()];
+    let mut visited = 0;
+    for _ in 0..SUGGEST_DEPTH {
+        let mut children = Vec::new();
+        for parent in &level {
+            let Ok(entries) = std::fs::read_dir(parent) else {
+                continue;
+            };
+            let mut dirs: Vec<PathBuf> = entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect();
+            dirs.sort();
+            children.extend(dirs);
+        }
+        children.truncate(SUGGEST_LIMIT.saturating_sub(visited));
+        visited += children.len();
+
+        let found: Vec<&PathBuf> = children
+            .iter()
+            .filter(|child| has_commands(child, extensions))
+            .collect();
+        let bin = found.iter().find(|child| {
+            child
+                .file_name()
+                .is_some_and(|name| name.eq_ignore_ascii_case("bin"))
+        });
+        if let Some(child) = bin.or(found.first()) {
+            return Some(child.to_path_buf());
+        }
+        if children.is_empty() {
+            break;
+        }
+        level = children;
+    }
+    None
+}
+
+/// Every file the command resolves to across the entries (scope, index, path as stored),
+/// in the order `where` lists them: the first one is what runs
+/// Entries naming an already searched directory are skipped like Windows does
+pub fn resolve(
+    name: &str,
+    entries: &[(PathScope, usize, String)],
+    extensions: &[String],
+) -> Vec<Match> {
+    let name = name.trim().trim_matches('"');
+    // A path is not looked up in PATH
+    if name.is_empty() || name.contains(['\\', '/', ':']) {
+        return Vec::new();
+    }
+
+    let names = candidates(name, extensions);
+    let mut searched = std::collections::HashSet::new();
+    let mut matches = Vec::new();
+    for (scope, index, path) in entries {
+        let expanded = expand_environment_variables(path.trim().trim_matches('"'));
+        let dir = expanded.trim_end_matches(['\\', '/']);
+        if dir.is_empty() || !searched.insert(dir.to_lowercase()) {
+            continue;
+        }
+        for candidate in &names {
+            let file = Path::new(dir).join(candidate);
+            if file.is_file() {
+                matches.push(Match {
+                    scope: *scope,
+                    index: *index,
+                    file: file.to_string_lossy().to_string(),
+                });
+            }
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pathext() {
+        assert_eq!(
+            parse_pathext(".COM;.EXE; .Bat;;cmd"),
+            vec![".com", ".exe", ".bat"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_in_search_order() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = |name: &str, files: &[&str]| {
+            let dir = root.path().join(name);
+            std::fs::create_dir_all(&dir).unwrap();
+            for file in files {
+                std::fs::write(dir.join(file), "").unwrap();
+            }
+            dir.to_string_lossy().to_string()
+        };
+        let old = dir("old", &["python.exe"]);
+        let new = dir("new", &["python.bat", "python.exe"]);
+        let entries = vec![
+            (PathScope::Machine, 0, old.clone()),
+            (PathScope::User, 0, new.clone()),
+            (PathScope::User, 1, old.clone()),
+        ];
+        let extensions = vec![".exe".to_string(), ".bat".to_string()];
+
+        let found = resolve("python", &entries, &extensions);
+        let files: Vec<_> = found
+            .iter()
+            .map(|m| {
+                (
+                    m.scope,
+                    m.index,
+                    Path::new(&m.file).file_name().unwrap().to_owned(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                (PathScope::Machine, 0, "python.exe".into()),
+                (PathScope::User, 0, "python.exe".into()),
+                (PathScope::User, 0, "python.bat".into()),
+            ]
+        );
+        // An extension given narrows it down
+        assert_eq!(resolve("python.bat", &entries, &extensions).len(), 1);
+        assert!(resolve("missing", &entries, &extensions).is_empty());
+        assert!(resolve(r"C:\python", &entries, &extensions).is_empty());
+    }
+
+    #[test]
+    fn test_suggest_command_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let file = |path: &str| {
+            let file = root.path().join(path);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, "").unwrap();
+        };
+        file("tool/readme.txt");
+        file("tool/lib/helper.exe");
+        file("tool/bin/tool.exe");
+        file("deep/a/b/run.cmd");
+        file("empty/docs/manual.txt");
+        let extensions = vec![".exe".to_string(), ".cmd".to_string()];
+
+        let tool = root.path().join("tool");
+        assert!(!has_commands(&tool, &extensions));
+        assert!(has_commands(&tool.join("lib"), &extensions));
+        // bin is preferred over another directory with commands at the same depth
+        assert_eq!(
+            suggest_command_dir(&tool, &extensions),
+            Some(tool.join("bin"))
+        );
+        assert_eq!(
+            suggest_command_dir(&root.path().join("deep"), &extensions),
+            Some(root.path().join("deep").join("a").join("b"))
+        );
+        assert_eq!(
+            suggest_command_dir(&root.path().join("empty"), &extensions),
+            None
+        );
+    }
+}
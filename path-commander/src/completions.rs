@@ -0,0 +1,157 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::env::{Bash, EnvCompleter, Powershell};
+
+/// Shells `pc completions` writes a script for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    /// PowerShell, add the output to $PROFILE
+    Powershell,
+    /// cmd.exe through clink, save the output as pc.lua in a clink scripts directory
+    Clink,
+    /// bash, including Git Bash and WSL
+    Bash,
+}
+
+/// Environment variable that turns a run of pc into a completion request
+pub const COMPLETE_VAR: &str = "COMPLETE";
+
+/// Completion script for a shell. The script asks pc for the candidates each time,
+/// so new options and files in ~/.pc show up without regenerating it
+pub fn generate(shell: Shell) -> Result<String> {
+    let mut script = Vec::new();
+    match shell {
+        Shell::Powershell => {
+            script.extend_from_slice(
+                b"# Path Commander completions for PowerShell\n\
+                  # Add to $PROFILE: pc completions powershell | Out-String | Invoke-Expression\n",
+            );
+            Powershell.write_registration(COMPLETE_VAR, "pc", "pc", "pc", &mut script)?;
+        }
+        Shell::Bash => {
+            script.extend_from_slice(
+                b"# Path Commander completions for bash\n\
+                  # Add to ~/.bashrc: eval \"$(pc completions bash)\"\n",
+            );
+            Bash.write_registration(COMPLETE_VAR, "pc", "pc", "pc", &mut script)?;
+        }
+        Shell::Clink => script.extend_from_slice(clink().as_bytes()),
+    }
+    Ok(String::from_utf8(script)?)
+}
+
+/// clink has no adapter in clap_complete, so this asks pc the way the PowerShell script does
+fn clink() -> String {
+    format!(
+        "-- Path Commander completions for cmd.exe through clink\n\
+         -- Save as pc.lua in a clink scripts directory (clink info lists them)\n\
+         local generator = clink.generator(1)\n\
+         \n\
+         function generator:generate(line_state, builder)\n    \
+             if line_state:getwordcount() < 2 or path.getbasename(line_state:getword(1)):lower() ~= \"pc\" then\n        \
+                 return false\n    \
+             end\n    \
+             local words = {{}}\n    \
+             for i = 1, line_state:getwordcount() do\n        \
+                 table.insert(words, '\"' .. line_state:getword(i) .. '\"')\n    \
+             end\n    \
+             local pipe = io.popen(\"set {}=powershell&& pc -- \" .. table.concat(words, \" \") .. \" 2>nul\")\n    \
+             if not pipe then\n        \
+                 return false\n    \
+             end\n    \
+             for line in pipe:lines() do\n        \
+                 builder:addmatch(line:match(\"^[^\\t]*\"))\n    \
+             end\n    \
+             pipe:close()\n    \
+             return true\n\
+         end\n",
+        COMPLETE_VAR
+    )
+}
+
+/// Theme names for --theme, read from ~/.pc/themes while completing
+pub fn themes() -> Vec<CompletionCandidate> {
+    crate::config::list_available_themes()
+        .map(|themes| {
+            themes
+                .into_iter()
+                .map(|(name, _)| CompletionCandidate::new(name))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Profile names for --profile, the built-in ones and those in config.ini
+pub fn profiles() -> Vec<CompletionCandidate> {
+    crate::profile::Profile::names(&crate::config::Settings::load_default())
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+/// Values for --scope, which parses through PathScope rather than a value enum
+pub fn scopes() -> Vec<CompletionCandidate> {
+    vec![
+        CompletionCandidate::new("user"),
+        CompletionCandidate::new("machine"),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+    use std::ffi::OsString;
+
+    fn complete(line: &[&str]) -> Vec<String> {
+        let args: Vec<OsString> = line.iter().map(OsString::from).collect();
+        let index = args.len() - 1;
+        clap_complete::engine::complete(&mut crate::Args::command(), args, index, None)
+            .unwrap()
+            .iter()
+            .map(|candidate| candidate.get_value().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_completes_from_cli_definition() {
+        assert_eq!(complete(&["pc", "--scope", ""]), ["user", "machine"]);
+        assert_eq!(complete(&["pc", "list", "--scope", "m"]), ["machine"]);
+        // Global flags follow the positional values
+        assert_eq!(
+            complete(&["pc", "completions", ""])[..3],
+            ["powershell", "clink", "bash"]
+        );
+
+        let profiles = complete(&["pc", "--profile", ""]);
+        for name in ["admin", "auditor", "helpdesk"] {
+            assert!(profiles.iter().any(|word| word == name), "{}", name);
+        }
+
+        let subcommands = complete(&["pc", ""]);
+        for name in ["list", "env", "refresh-hook", "completions"] {
+            assert!(subcommands.iter().any(|word| word == name), "{}", name);
+        }
+
+        let flags = complete(&["pc", "--"]);
+        assert!(flags.iter().any(|word| word == "--plain"));
+        assert!(flags.iter().any(|word| word == "--theme"));
+        // Hidden internals are not offered
+        assert!(!flags.iter().any(|word| word == "--apply-machine"));
+    }
+
+    #[test]
+    fn test_scripts_call_back_into_pc() {
+        let powershell = generate(Shell::Powershell).unwrap();
+        assert!(powershell.contains("Register-ArgumentCompleter -Native -CommandName pc"));
+        assert!(powershell.contains("$env:COMPLETE = \"powershell\""));
+
+        let bash = generate(Shell::Bash).unwrap();
+        assert!(bash.contains("COMPLETE=\"bash\""));
+
+        let clink = generate(Shell::Clink).unwrap();
+        assert!(clink.contains("clink.generator(1)"));
+        assert!(clink.contains("set COMPLETE=powershell&& pc -- "));
+    }
+}
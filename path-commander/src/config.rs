@@ -1,5 +1,11 @@
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::change_report::ReportFormat;
+use crate::profile::{self, Feature};
+use crate::registry::PathScope;
 
 /// Get the Path Commander configuration directory (~/.pc)
 ///
@@ -43,6 +49,603 @@ pub fn get_backups_dir() -> Result<PathBuf> {
     Ok(config_dir.join("backups"))
 }
 
+/// Get the path of the append-only history of applied changes
+pub fn get_history_file() -> Result<PathBuf> {
+    let config_dir = get_config_dir()?;
+    Ok(config_dir.join("history.jsonl"))
+}
+
+/// Get the path of the log the usage hook appends to (~/.pc/usage.log)
+pub fn get_usage_file() -> Result<PathBuf> {
+    let config_dir = get_config_dir()?;
+    Ok(config_dir.join("usage.log"))
+}
+
+/// Get the path of the settings file (~/.pc/config.ini)
+pub fn get_settings_file() -> Result<PathBuf> {
+    let config_dir = get_config_dir()?;
+    Ok(config_dir.join("config.ini"))
+}
+
+/// Get the path of the machine-specific values for backup placeholders (~/.pc/variables.ini)
+pub fn get_variables_file() -> Result<PathBuf> {
+    let config_dir = get_config_dir()?;
+    Ok(config_dir.join("variables.ini"))
+}
+
+/// Get the path the keyboard shortcut cheat sheet is exported to (~/.pc/keyboard-shortcuts.md)
+pub fn get_shortcuts_file() -> Result<PathBuf> {
+    let config_dir = get_config_dir()?;
+    Ok(config_dir.join("keyboard-shortcuts.md"))
+}
+
+/// Get the folder change summary reports are saved in unless another is chosen (~/.pc/reports)
+pub fn get_reports_dir() -> Result<PathBuf> {
+    let config_dir = get_config_dir()?;
+    Ok(config_dir.join("reports"))
+}
+
+/// Get the directory remote writes saved for later go to (~/.pc/pending)
+pub fn get_pending_dir() -> Result<PathBuf> {
+    let config_dir = get_config_dir()?;
+    Ok(config_dir.join("pending"))
+}
+
+/// Get the path of the inventory of remote computers (~/.pc/hosts.toml)
+pub fn get_hosts_file() -> Result<PathBuf> {
+    let config_dir = get_config_dir()?;
+    Ok(config_dir.join("hosts.toml"))
+}
+
+/// Get the directory running instances announce themselves in (~/.pc/instances)
+pub fn get_instances_dir() -> Result<PathBuf> {
+    let config_dir = get_config_dir()?;
+    Ok(config_dir.join("instances"))
+}
+
+/// Get the directory a downloaded update waits in until the next start (~/.pc/update)
+pub fn get_update_dir() -> Result<PathBuf> {
+    let config_dir = get_config_dir()?;
+    Ok(config_dir.join("update"))
+}
+
+/// Where F4 inserts a new entry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AddPosition {
+    Top,
+    BeforeSelection,
+    #[default]
+    End,
+}
+
+impl AddPosition {
+    pub const ALL: [AddPosition; 3] = [
+        AddPosition::Top,
+        AddPosition::BeforeSelection,
+        AddPosition::End,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AddPosition::Top => "Top",
+            AddPosition::BeforeSelection => "Before selection",
+            AddPosition::End => "End",
+        }
+    }
+
+    /// The next choice, wrapping around
+    pub fn next(&self) -> Self {
+        let idx = Self::ALL.iter().position(|p| p == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().replace(['-', ' '], "_").as_str() {
+            "top" | "start" => Some(AddPosition::Top),
+            "before_selection" | "before" => Some(AddPosition::BeforeSelection),
+            "end" | "bottom" => Some(AddPosition::End),
+            _ => None,
+        }
+    }
+}
+
+/// Which length of a PATH value the warning threshold is compared with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LengthMeasure {
+    /// As written to the registry, %VARIABLES% unexpanded
+    #[default]
+    Stored,
+    /// As new processes get it, after Windows expands the variables
+    Expanded,
+}
+
+impl LengthMeasure {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LengthMeasure::Stored => "stored",
+            LengthMeasure::Expanded => "expanded",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "stored" | "raw" | "unexpanded" => Some(LengthMeasure::Stored),
+            "expanded" => Some(LengthMeasure::Expanded),
+            _ => None,
+        }
+    }
+}
+
+/// User settings from ~/.pc/config.ini
+///
+/// ```ini
+/// [audit]
+/// event_log = true
+///
+/// [elevation]
+/// # Offer to restart as administrator on the first MACHINE edit
+/// auto_request = true
+/// # Write MACHINE through a one-off elevated helper instead of restarting
+/// helper = true
+/// # Lock an elevated session after this many idle minutes
+/// lock_after_minutes = 10
+///
+/// [startup]
+/// # Panel focused at startup: user or machine
+/// panel = user
+///
+/// [editing]
+/// # top, before_selection or end
+/// add_position = before_selection
+/// # Scope the Add Path dialog targets, whichever panel is active
+/// add_scope = user
+/// # Bulk deletes of more entries than this get a review list instead of yes/no
+/// review_threshold = 10
+/// # Steps Ctrl+Z can go back, the oldest are dropped beyond this
+/// undo_depth = 200
+///
+/// [analysis]
+/// # Expand %USERPROFILE%, %APPDATA%, ... for this profile instead of your own
+/// profile_root = C:\Users\alice
+///
+/// [dialogs]
+/// # Width x height in percent of the terminal, saved when a dialog is resized
+/// help = 70x60
+///
+/// [display]
+/// # No dialog shadows or spinner animation, for screen readers, recorders and slow terminals
+/// reduced_motion = true
+/// # Clickable Edit, Delete, Normalize, Open and Info for the selected entry under the panels
+/// quick_actions = true
+/// # Measure the selected directory when Path Details opens, S measures it either way
+/// directory_sizes = true
+///
+/// [keys]
+/// # F9 focuses the menu bar, set to normalize to keep the older binding
+/// # Shift+F9 normalizes either way
+/// f9 = normalize
+///
+/// [limits]
+/// # Warn about PATH values longer than this, 2047 if unset
+/// path_length = 4095
+/// # Length compared with it: stored (as in the registry) or expanded (as new processes get it)
+/// measure = expanded
+///
+/// [version]
+/// # Last version whose release notes were shown, saved at startup
+/// last_seen = 0.6.2
+///
+/// [control]
+/// # Let local scripts and installers stage edits through \\.\pipe\path-commander
+/// pipe = true
+///
+/// [updates]
+/// # Look for a newer release on GitHub at startup, nothing is sent anywhere unless set
+/// check = true
+///
+/// [remote]
+/// # Hosts in File > Recent Remotes, most recent first, saved on every connect
+/// recent = server01, build-agent
+///
+/// [reports]
+/// # Folder and format (markdown or html) of change summaries, saved when one is written
+/// folder = \\fileserver\changes
+/// format = html
+///
+/// [ignore]
+/// # Never reported as dead or duplicate, and left alone by bulk cleanups
+/// # Separated by ; like PATH, a trailing * covers everything below
+/// entries = C:\agent\_work\*; %LOCALAPPDATA%\Microsoft\WindowsApps
+///
+/// [dead]
+/// # Leave entries out of Delete All Dead until they have been missing this many days
+/// grace_days = 7
+///
+/// [dead_since]
+/// # Kept up to date by Path Commander: when each entry was first seen missing
+/// d:\vault\bin = 2026-03-10
+///
+/// [profile.helpdesk]
+/// # Features `pc --profile helpdesk` turns on: edit, doctor, reports, remote, backup or all
+/// # Without edit nothing can be changed; admin, auditor and helpdesk are built in
+/// features = doctor, remote
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Settings {
+    /// Write an event to the Windows Application log on every apply
+    pub event_log: bool,
+    /// Position preselected in the Add Path dialog
+    pub add_position: AddPosition,
+    /// Panel focused at startup, MACHINE if unset
+    pub start_panel: Option<PathScope>,
+    /// Scope preselected in the Add Path dialog, the active panel if unset
+    pub add_scope: Option<PathScope>,
+    /// Offer elevation as soon as a MACHINE edit is staged without administrator rights
+    pub auto_elevate: bool,
+    /// Apply MACHINE edits through an elevated helper process, keeping this session unelevated
+    pub elevated_helper: bool,
+    /// Idle time after which an elevated session locks, never if unset
+    pub lock_after: Option<std::time::Duration>,
+    /// Profile that per-user variables in PATH are expanded for, the current user's if unset
+    pub profile_root: Option<String>,
+    /// Preferred dialog sizes in percent of the terminal, by dialog name
+    pub dialog_sizes: HashMap<String, (u16, u16)>,
+    /// Remote hosts connected to lately, most recent first
+    pub recent_remotes: Vec<String>,
+    /// F9 normalizes the selection instead of focusing the menu bar
+    pub f9_normalizes: bool,
+    /// PATH length warned about, the Environment Variables dialog limit if unset
+    pub length_limit: Option<usize>,
+    /// Whether the stored or expanded length is compared with the limit
+    pub length_measure: LengthMeasure,
+    /// Draw dialogs without shadows and keep spinners still
+    pub reduced_motion: bool,
+    /// Show the quick actions row for the selected entry under the panels
+    pub quick_actions: bool,
+    /// Measure the selected directory's size as soon as Path Details opens
+    pub directory_sizes: bool,
+    /// Version whose What's New was last shown, None before the first run
+    pub last_seen_version: Option<String>,
+    /// Ask GitHub for a newer release at startup
+    pub check_updates: bool,
+    /// Serve the local control pipe for scripts and installers
+    pub control_pipe: bool,
+    /// Bulk deletes of more entries than this are reviewed entry by entry, DEFAULT_REVIEW_THRESHOLD if unset
+    pub review_threshold: Option<usize>,
+    /// Most operations kept for undo, DEFAULT_UNDO_DEPTH if unset
+    pub undo_depth: Option<usize>,
+    /// Folder change summaries are saved in, ~/.pc/reports if unset
+    pub report_folder: Option<String>,
+    /// File type of change summaries
+    pub report_format: ReportFormat,
+    /// Launch profiles from `[profile.NAME]` sections, by lowercase name
+    pub profiles: HashMap<String, Vec<Feature>>,
+    /// Entries and prefixes analysis never flags, as typed in the Ignore List dialog
+    pub ignored: Vec<String>,
+    /// Days an entry must have been dead before Delete All Dead removes it, 0 for no wait
+    pub dead_grace_days: u32,
+    /// When each dead entry was first seen missing, by lowercase normalized path
+    pub dead_since: HashMap<String, NaiveDate>,
+}
+
+impl Settings {
+    /// Load settings, a missing file means all defaults
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read settings: {}", path.display()))
+            }
+        };
+        Ok(Self::parse(&content))
+    }
+
+    /// Load ~/.pc/config.ini, falling back to defaults if it cannot be read
+    pub fn load_default() -> Self {
+        get_settings_file()
+            .and_then(|path| Self::load(&path))
+            .unwrap_or_default()
+    }
+
+    fn parse(content: &str) -> Self {
+        let ini = parse_ini(content).unwrap_or_default();
+        let flag = |section: &str, key: &str| {
+            ini.get(section)
+                .and_then(|values| values.get(key))
+                .map(|value| matches!(value.to_lowercase().as_str(), "true" | "yes" | "on" | "1"))
+                .unwrap_or(false)
+        };
+
+        let value = |section: &str, key: &str| {
+            ini.get(section)
+                .and_then(|values| values.get(key))
+                .map(|value| value.as_str())
+        };
+
+        Self {
+            event_log: flag("audit", "event_log"),
+            add_position: value("editing", "add_position")
+                .and_then(AddPosition::parse)
+                .unwrap_or_default(),
+            start_panel: value("startup", "panel").and_then(PathScope::parse),
+            add_scope: value("editing", "add_scope").and_then(PathScope::parse),
+            auto_elevate: flag("elevation", "auto_request"),
+            elevated_helper: flag("elevation", "helper"),
+            lock_after: value("elevation", "lock_after_minutes")
+                .and_then(|minutes| minutes.parse::<u64>().ok())
+                .filter(|minutes| *minutes > 0)
+                .map(|minutes| std::time::Duration::from_secs(minutes * 60)),
+            profile_root: value("analysis", "profile_root")
+                .filter(|root| !root.is_empty())
+                .map(str::to_string),
+            dialog_sizes: ini
+                .get("dialogs")
+                .map(|sizes| {
+                    sizes
+                        .iter()
+                        .filter_map(|(name, size)| {
+                            Some((name.to_lowercase(), parse_dialog_size(size)?))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            recent_remotes: value("remote", "recent")
+                .map(|hosts| {
+                    hosts
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|host| !host.is_empty())
+                        .take(MAX_RECENT_REMOTES)
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            f9_normalizes: value("keys", "f9")
+                .is_some_and(|f9| f9.eq_ignore_ascii_case("normalize")),
+            length_limit: value("limits", "path_length")
+                .and_then(|length| length.parse::<usize>().ok())
+                .filter(|length| *length > 0),
+            length_measure: value("limits", "measure")
+                .and_then(LengthMeasure::parse)
+                .unwrap_or_default(),
+            reduced_motion: flag("display", "reduced_motion"),
+            quick_actions: flag("display", "quick_actions"),
+            directory_sizes: flag("display", "directory_sizes"),
+            last_seen_version: value("version", "last_seen")
+                .filter(|version| !version.is_empty())
+                .map(str::to_string),
+            check_updates: flag("updates", "check"),
+            control_pipe: flag("control", "pipe"),
+            review_threshold: value("editing", "review_threshold")
+                .and_then(|count| count.parse::<usize>().ok()),
+            undo_depth: value("editing", "undo_depth")
+                .and_then(|count| count.parse::<usize>().ok())
+                .filter(|&count| count > 0),
+            report_folder: value("reports", "folder")
+                .filter(|folder| !folder.is_empty())
+                .map(str::to_string),
+            report_format: value("reports", "format")
+                .and_then(ReportFormat::parse)
+                .unwrap_or_default(),
+            profiles: ini
+                .iter()
+                .filter_map(|(section, values)| {
+                    let name = section.to_lowercase().strip_prefix("profile.")?.to_string();
+                    let features = values.get("features").map(String::as_str)?;
+                    Some((name, profile::parse_features(features)))
+                })
+                .collect(),
+            ignored: value("ignore", "entries")
+                .map(|entries| {
+                    entries
+                        .split(';')
+                        .map(str::trim)
+                        .filter(|entry| !entry.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            dead_grace_days: value("dead", "grace_days")
+                .and_then(|days| days.parse::<u32>().ok())
+                .unwrap_or(0),
+            dead_since: ini
+                .get("dead_since")
+                .map(|entries| {
+                    entries
+                        .iter()
+                        .filter_map(|(path, date)| {
+                            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+                            Some((path.to_lowercase(), date))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Bulk deletes of more entries than this get a review list unless configured otherwise
+pub const DEFAULT_REVIEW_THRESHOLD: usize = 5;
+
+/// Operations kept for undo unless configured otherwise
+pub const DEFAULT_UNDO_DEPTH: usize = 100;
+
+/// Memory the undo history may take, in bytes of stored path text, whatever the depth
+pub const UNDO_MEMORY_LIMIT: usize = 8 * 1024 * 1024;
+
+/// Hosts kept in File > Recent Remotes
+pub const MAX_RECENT_REMOTES: usize = 8;
+
+/// Smallest dialog size in percent, so a dialog cannot be shrunk out of sight
+pub const MIN_DIALOG_PERCENT: u16 = 20;
+
+/// Parse "70x60" as width and height in percent
+fn parse_dialog_size(value: &str) -> Option<(u16, u16)> {
+    let (width, height) = value
+        .to_lowercase()
+        .split_once('x')
+        .map(|(w, h)| (w.trim().parse::<u16>(), h.trim().parse::<u16>()))?;
+    let clamp = |percent: u16| percent.clamp(MIN_DIALOG_PERCENT, 100);
+    Some((clamp(width.ok()?), clamp(height.ok()?)))
+}
+
+/// Set one value in a settings file, keeping the rest of it including comments
+pub fn save_setting(path: &Path, section: &str, key: &str, value: &str) -> Result<()> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read settings: {}", path.display()))
+        }
+    };
+    std::fs::write(path, set_ini_value(&content, section, key, value))
+        .with_context(|| format!("Failed to write settings: {}", path.display()))
+}
+
+/// Replace everything in `[section]` of the settings file with `entries`
+pub fn save_section(path: &Path, section: &str, entries: &[(String, String)]) -> Result<()> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read settings: {}", path.display()))
+        }
+    };
+    std::fs::write(path, set_ini_section(&content, section, entries))
+        .with_context(|| format!("Failed to write settings: {}", path.display()))
+}
+
+/// Replace the keys of `[section]`, adding the section if missing and dropping it if emptied
+fn set_ini_section(content: &str, section: &str, entries: &[(String, String)]) -> String {
+    let body: Vec<String> = entries
+        .iter()
+        .map(|(key, value)| format!("{} = {}", key, value))
+        .collect();
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let header = |line: &str| {
+        let line = line.trim();
+        (line.starts_with('[') && line.ends_with(']'))
+            .then(|| line[1..line.len() - 1].trim().to_string())
+    };
+
+    let Some(start) = lines
+        .iter()
+        .position(|line| header(line).is_some_and(|name| name.eq_ignore_ascii_case(section)))
+    else {
+        if body.is_empty() {
+            return content.to_string();
+        }
+        if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+            lines.push(String::new());
+        }
+        lines.push(format!("[{}]", section));
+        lines.extend(body);
+        return lines.join("\n") + "\n";
+    };
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| header(line).is_some())
+        .map_or(lines.len(), |offset| start + 1 + offset);
+    // Keep the blank lines before the next section
+    let last = lines[start + 1..end]
+        .iter()
+        .rposition(|line| !line.trim().is_empty())
+        .map_or(start, |offset| start + 1 + offset);
+    if body.is_empty() {
+        // Take the header and the blank lines after the section with it
+        lines.drain(start..end);
+    } else {
+        lines.splice(start + 1..last + 1, body);
+    }
+    if lines.is_empty() {
+        String::new()
+    } else {
+        lines.join("\n") + "\n"
+    }
+}
+
+/// Replace `key` in `[section]`, adding the key or the section if missing
+fn set_ini_value(content: &str, section: &str, key: &str, value: &str) -> String {
+    let entry = format!("{} = {}", key, value);
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let header = |line: &str| {
+        let line = line.trim();
+        (line.starts_with('[') && line.ends_with(']'))
+            .then(|| line[1..line.len() - 1].trim().to_string())
+    };
+
+    let Some(start) = lines
+        .iter()
+        .position(|line| header(line).is_some_and(|name| name.eq_ignore_ascii_case(section)))
+    else {
+        if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+            lines.push(String::new());
+        }
+        lines.push(format!("[{}]", section));
+        lines.push(entry);
+        return lines.join("\n") + "\n";
+    };
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| header(line).is_some())
+        .map_or(lines.len(), |offset| start + 1 + offset);
+
+    let existing = lines[start + 1..end].iter().position(|line| {
+        line.split_once('=')
+            .is_some_and(|(name, _)| name.trim().eq_ignore_ascii_case(key))
+    });
+    match existing {
+        Some(offset) => lines[start + 1 + offset] = entry,
+        None => {
+            // After the section's last setting, ahead of the blank lines before the next section
+            let last = lines[start + 1..end]
+                .iter()
+                .rposition(|line| !line.trim().is_empty())
+                .map_or(start, |offset| start + 1 + offset);
+            lines.insert(last + 1, entry);
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Parse INI file content into sections and key-value pairs
+pub fn parse_ini(content: &str) -> Result<HashMap<String, HashMap<String, String>>> {
+    let mut result: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut current_section = String::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        // Skip empty lines and comments
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        // Section header
+        if line.starts_with('[') && line.ends_with(']') {
+            current_section = line[1..line.len() - 1].trim().to_string();
+            result.entry(current_section.clone()).or_default();
+            continue;
+        }
+
+        // Key-value pair
+        if let Some(eq_pos) = line.find('=') {
+            let key = line[..eq_pos].trim().to_string();
+            let value = line[eq_pos + 1..].trim().to_string();
+            if !current_section.is_empty() {
+                result
+                    .entry(current_section.clone())
+                    .or_default()
+                    .insert(key, value);
+            }
+        }
+    }
+
+    Ok(result)
+}
+
 /// Get the GNU Midnight Commander skins directory path (Windows)
 ///
 /// Returns the MC skins directory if it exists, or None if MC is not installed
@@ -264,4 +867,166 @@ mod tests {
         let backups_dir = get_backups_dir().unwrap();
         assert!(backups_dir.to_string_lossy().ends_with("backups"));
     }
+
+    #[test]
+    fn test_settings_parse() {
+        assert_eq!(Settings::parse(""), Settings::default());
+        assert!(Settings::parse("[audit]\nevent_log = yes\n").event_log);
+        assert!(!Settings::parse("[audit]\nevent_log = false\n").event_log);
+        // Keys outside their section are ignored
+        assert!(!Settings::parse("event_log = true\n").event_log);
+        assert!(Settings::parse("[elevation]\nauto_request = on\n").auto_elevate);
+        assert!(Settings::parse("[display]\nreduced_motion = true\n").reduced_motion);
+        assert!(Settings::parse("[display]\nquick_actions = yes\n").quick_actions);
+        assert!(Settings::parse("[display]\ndirectory_sizes = on\n").directory_sizes);
+        assert!(Settings::parse("[updates]\ncheck = yes\n").check_updates);
+        assert!(Settings::parse("[control]\npipe = on\n").control_pipe);
+        assert_eq!(
+            Settings::parse("[elevation]\nlock_after_minutes = 10\n").lock_after,
+            Some(std::time::Duration::from_secs(600))
+        );
+        assert_eq!(
+            Settings::parse("[elevation]\nlock_after_minutes = 0\n").lock_after,
+            None
+        );
+        assert_eq!(
+            Settings::parse("[analysis]\nprofile_root = D:\\Users\\alice\n").profile_root,
+            Some(r"D:\Users\alice".to_string())
+        );
+
+        assert_eq!(
+            Settings::parse("[editing]\nadd_position = Before-Selection\n").add_position,
+            AddPosition::BeforeSelection
+        );
+        // Unknown values keep the default
+        assert_eq!(
+            Settings::parse("[editing]\nadd_position = middle\n").add_position,
+            AddPosition::End
+        );
+
+        assert_eq!(
+            Settings::parse("[editing]\nreview_threshold = 0\n").review_threshold,
+            Some(0)
+        );
+        assert_eq!(
+            Settings::parse("[editing]\nreview_threshold = many\n").review_threshold,
+            None
+        );
+        assert_eq!(
+            Settings::parse("[editing]\nundo_depth = 20\n").undo_depth,
+            Some(20)
+        );
+        // No history at all is not a depth
+        assert_eq!(
+            Settings::parse("[editing]\nundo_depth = 0\n").undo_depth,
+            None
+        );
+
+        let reports = Settings::parse("[reports]\nfolder = D:\\Changes\nformat = HTML\n");
+        assert_eq!(reports.report_folder, Some(r"D:\Changes".to_string()));
+        assert_eq!(reports.report_format, ReportFormat::Html);
+        assert_eq!(
+            Settings::parse("[reports]\nformat = pdf\n").report_format,
+            ReportFormat::Markdown
+        );
+
+        let profiles =
+            Settings::parse("[Profile.Helpdesk]\nfeatures = doctor, remote\n[profile.empty]\n");
+        assert_eq!(
+            profiles.profiles.get("helpdesk"),
+            Some(&vec![Feature::Doctor, Feature::Remote])
+        );
+        // A section without a feature list defines nothing
+        assert_eq!(profiles.profiles.len(), 1);
+
+        let dead = Settings::parse(
+            "[dead]\ngrace_days = 7\n[dead_since]\nD:\\Vault\\bin = 2026-03-10\nE:\\x = soon\n",
+        );
+        assert_eq!(dead.dead_grace_days, 7);
+        assert_eq!(
+            dead.dead_since.get(r"d:\vault\bin"),
+            NaiveDate::from_ymd_opt(2026, 3, 10).as_ref()
+        );
+        assert_eq!(dead.dead_since.len(), 1);
+        assert_eq!(
+            Settings::parse("[ignore]\nentries = C:\\agent\\_work\\*;; %TEMP% \n").ignored,
+            vec![r"C:\agent\_work\*".to_string(), "%TEMP%".to_string()]
+        );
+
+        let scopes = Settings::parse("[startup]\npanel = User\n\n[editing]\nadd_scope = system\n");
+        assert_eq!(scopes.start_panel, Some(PathScope::User));
+        assert_eq!(scopes.add_scope, Some(PathScope::Machine));
+        assert_eq!(
+            Settings::parse("[startup]\npanel = left\n").start_panel,
+            None
+        );
+
+        let sizes = Settings::parse("[dialogs]\nHelp = 70x60\nhistory = 5x200\nabout = wide\n");
+        assert_eq!(sizes.dialog_sizes.get("help"), Some(&(70, 60)));
+        assert_eq!(sizes.dialog_sizes.get("history"), Some(&(20, 100)));
+        assert_eq!(sizes.dialog_sizes.get("about"), None);
+
+        assert_eq!(
+            Settings::parse("[remote]\nrecent = server01, ,build-agent\n").recent_remotes,
+            vec!["server01".to_string(), "build-agent".to_string()]
+        );
+        assert!(Settings::parse("[keys]\nf9 = Normalize\n").f9_normalizes);
+        assert!(!Settings::parse("[keys]\nf9 = menu\n").f9_normalizes);
+
+        let limits = Settings::parse("[limits]\npath_length = 4095\nmeasure = Expanded\n");
+        assert_eq!(limits.length_limit, Some(4095));
+        assert_eq!(limits.length_measure, LengthMeasure::Expanded);
+        let invalid = Settings::parse("[limits]\npath_length = 0\nmeasure = both\n");
+        assert_eq!(invalid.length_limit, None);
+        assert_eq!(invalid.length_measure, LengthMeasure::Stored);
+
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            Settings::load(&dir.path().join("config.ini")).unwrap(),
+            Settings::default()
+        );
+    }
+
+    #[test]
+    fn test_set_ini_value() {
+        let content = "# Path Commander\n[audit]\nevent_log = true\n\n[dialogs]\nhelp = 55x50\n\n[startup]\npanel = user\n";
+        assert_eq!(
+            set_ini_value(content, "dialogs", "help", "70x60"),
+            "# Path Commander\n[audit]\nevent_log = true\n\n[dialogs]\nhelp = 70x60\n\n[startup]\npanel = user\n"
+        );
+        assert_eq!(
+            set_ini_value(content, "Dialogs", "history", "90x90"),
+            "# Path Commander\n[audit]\nevent_log = true\n\n[dialogs]\nhelp = 55x50\nhistory = 90x90\n\n[startup]\npanel = user\n"
+        );
+        assert_eq!(
+            set_ini_value("[audit]\nevent_log = true\n", "dialogs", "help", "70x60"),
+            "[audit]\nevent_log = true\n\n[dialogs]\nhelp = 70x60\n"
+        );
+        assert_eq!(
+            set_ini_value("", "dialogs", "help", "70x60"),
+            "[dialogs]\nhelp = 70x60\n"
+        );
+    }
+
+    #[test]
+    fn test_set_ini_section() {
+        let content = "[dead_since]\nc:\\old = 2026-01-01\n\n[startup]\npanel = user\n";
+        let entries = [
+            (r"c:\new".to_string(), "2026-03-10".to_string()),
+            (r"d:\x".to_string(), "2026-03-11".to_string()),
+        ];
+        assert_eq!(
+            set_ini_section(content, "dead_since", &entries),
+            "[dead_since]\nc:\\new = 2026-03-10\nd:\\x = 2026-03-11\n\n[startup]\npanel = user\n"
+        );
+        assert_eq!(
+            set_ini_section(content, "dead_since", &[]),
+            "[startup]\npanel = user\n"
+        );
+        assert_eq!(
+            set_ini_section("[startup]\npanel = user\n", "dead_since", &entries[..1]),
+            "[startup]\npanel = user\n\n[dead_since]\nc:\\new = 2026-03-10\n"
+        );
+        assert_eq!(set_ini_section("", "dead_since", &[]), "");
+    }
 }
@@ -0,0 +1,263 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+/// Pipe the running instance listens on, only reachable from this computer
+#[cfg_attr(not(windows), allow(dead_code))]
+pub const PIPE_NAME: &str = r"\\.\pipe\path-commander";
+
+/// How long a client waits for the UI to pick up its command
+#[cfg_attr(not(windows), allow(dead_code))]
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A command sent by a client, one JSON object per connection on a single line:
+///
+/// ```text
+/// {"command": "add", "path": "C:\\Tools\\bin", "scope": "user"}
+/// {"command": "remove", "path": "C:\\Old\\bin", "scope": "machine"}
+/// {"command": "apply"}
+/// {"command": "query"}
+/// ```
+///
+/// Nothing is written to the registry from here: additions and removals are staged
+/// as ordinary undoable edits and `apply` only opens the apply dialog for the user
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "command", rename_all = "lowercase")]
+pub enum Command {
+    /// Stage an entry at the end of a scope, USER unless given
+    Add { path: String, scope: Option<String> },
+    /// Stage removing every copy of an entry from a scope, USER unless given
+    Remove { path: String, scope: Option<String> },
+    /// Open the apply dialog so the user can review and write the staged edits
+    Apply,
+    /// Report both scopes as currently edited
+    Query,
+}
+
+/// Answer to a command, fields that do not apply are left out
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub machine: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<Vec<String>>,
+    /// Edits staged but not applied yet
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending: Option<bool>,
+}
+
+impl Response {
+    pub fn done(message: impl Into<String>) -> Self {
+        Self {
+            ok: true,
+            message: Some(message.into()),
+            ..Self::default()
+        }
+    }
+
+    pub fn failed(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            message: Some(message.into()),
+            ..Self::default()
+        }
+    }
+}
+
+/// A command waiting for the UI thread, which answers it through `respond`
+pub struct Request {
+    pub command: Command,
+    reply: Sender<Response>,
+}
+
+impl Request {
+    pub fn respond(self, response: Response) {
+        // The client may have given up waiting
+        let _ = self.reply.send(response);
+    }
+}
+
+/// Run one request line through the UI thread and render its answer
+#[cfg_attr(not(windows), allow(dead_code))]
+fn answer(line: &str, requests: &Sender<Request>, timeout: Duration) -> String {
+    let response = match serde_json::from_str::<Command>(line.trim()) {
+        Ok(command) => {
+            let (reply, replies) = mpsc::channel();
+            if requests.send(Request { command, reply }).is_err() {
+                Response::failed("Path Commander is closing")
+            } else {
+                replies
+                    .recv_timeout(timeout)
+                    .unwrap_or_else(|_| Response::failed("Path Commander did not answer in time"))
+            }
+        }
+        Err(e) => Response::failed(format!("Invalid command: {}", e)),
+    };
+    serde_json::to_string(&response).unwrap_or_default()
+}
+
+/// Listens on the control pipe on a worker thread and hands commands to the UI
+pub struct ControlServer {
+    requests: Receiver<Request>,
+}
+
+impl ControlServer {
+    /// The next command waiting, if any
+    pub fn try_next(&self) -> Option<Request> {
+        self.requests.try_recv().ok()
+    }
+
+    /// Create the pipe and start serving clients one at a time
+    #[cfg(windows)]
+    pub fn start() -> Result<Self> {
+        let (sender, requests) = mpsc::channel();
+        // Create the first instance here so a second Path Commander fails visibly
+        let first = windows_pipe::create(true)?;
+        std::thread::spawn(move || {
+            let mut pipe = Some(first);
+            loop {
+                let current = match pipe.take() {
+                    Some(pipe) => pipe,
+                    None => match windows_pipe::create(false) {
+                        Ok(pipe) => pipe,
+                        Err(_) => return,
+                    },
+                };
+                // A client that hung up early only loses its own answer
+                let _ = windows_pipe::serve(current, &sender);
+            }
+        });
+        Ok(Self { requests })
+    }
+
+    /// Named pipes are a Windows feature
+    #[cfg(not(windows))]
+    pub fn start() -> Result<Self> {
+        anyhow::bail!("the control pipe is only available on Windows")
+    }
+}
+
+#[cfg(windows)]
+mod windows_pipe {
+    use super::{answer, Request, PIPE_NAME, REPLY_TIMEOUT};
+    use anyhow::{anyhow, Result};
+    use std::fs::File;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::windows::io::{FromRawHandle, RawHandle};
+    use std::sync::mpsc::Sender;
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::ERROR_PIPE_CONNECTED;
+    use windows::Win32::Storage::FileSystem::{
+        FILE_FLAGS_AND_ATTRIBUTES, FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX,
+    };
+    use windows::Win32::System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
+        PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+    };
+
+    /// A new instance of the pipe, the first one fails if another process owns the name
+    /// The default security lets only this user, administrators and SYSTEM write to it
+    pub fn create(first: bool) -> Result<File> {
+        let name = crate::registry::to_wide_string(PIPE_NAME);
+        let mut open_mode = PIPE_ACCESS_DUPLEX;
+        if first {
+            open_mode |= FILE_FLAG_FIRST_PIPE_INSTANCE;
+        }
+        let handle = unsafe {
+            CreateNamedPipeW(
+                PCWSTR(name.as_ptr()),
+                FILE_FLAGS_AND_ATTRIBUTES(open_mode.0),
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                4096,
+                4096,
+                0,
+                None,
+            )
+        };
+        if handle.is_invalid() {
+            return Err(anyhow!(
+                "{} is in use, is another Path Commander running?",
+                PIPE_NAME
+            ));
+        }
+        Ok(unsafe { File::from_raw_handle(handle.0 as RawHandle) })
+    }
+
+    /// Wait for a client, answer its command and hang up
+    pub fn serve(pipe: File, requests: &Sender<Request>) -> Result<()> {
+        use std::os::windows::io::AsRawHandle;
+
+        let handle = windows::Win32::Foundation::HANDLE(pipe.as_raw_handle());
+        if let Err(e) = unsafe { ConnectNamedPipe(handle, None) } {
+            // The client connected between creating the pipe and waiting for it
+            if e.code() != ERROR_PIPE_CONNECTED.to_hresult() {
+                return Err(e.into());
+            }
+        }
+
+        let mut line = String::new();
+        BufReader::new(&pipe).read_line(&mut line)?;
+        let reply = answer(&line, requests, REPLY_TIMEOUT);
+        (&pipe).write_all(format!("{}\n", reply).as_bytes())?;
+        // Let the client read the answer before the handle closes
+        pipe.sync_all()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        let add: Command =
+            serde_json::from_str(r#"{"command": "add", "path": "C:\\Tools", "scope": "machine"}"#)
+                .unwrap();
+        assert_eq!(
+            add,
+            Command::Add {
+                path: r"C:\Tools".to_string(),
+                scope: Some("machine".to_string()),
+            }
+        );
+        assert_eq!(
+            serde_json::from_str::<Command>(r#"{"command": "query"}"#).unwrap(),
+            Command::Query
+        );
+        assert!(serde_json::from_str::<Command>(r#"{"command": "add"}"#).is_err());
+        assert!(serde_json::from_str::<Command>(r#"{"command": "format"}"#).is_err());
+    }
+
+    #[test]
+    fn test_answer_round_trip() {
+        let (sender, requests) = mpsc::channel::<Request>();
+        let ui = std::thread::spawn(move || {
+            let request = requests.recv().unwrap();
+            assert_eq!(request.command, Command::Apply);
+            request.respond(Response::done("Apply dialog opened"));
+        });
+        assert_eq!(
+            answer(
+                "{\"command\": \"apply\"}\n",
+                &sender,
+                Duration::from_secs(5)
+            ),
+            r#"{"ok":true,"message":"Apply dialog opened"}"#
+        );
+        ui.join().unwrap();
+
+        let reply = answer("not json", &sender, Duration::from_secs(5));
+        assert!(reply.starts_with(r#"{"ok":false,"message":"Invalid command"#));
+        // Nobody left to answer
+        assert_eq!(
+            answer(r#"{"command": "query"}"#, &sender, Duration::from_secs(5)),
+            r#"{"ok":false,"message":"Path Commander is closing"}"#
+        );
+    }
+}
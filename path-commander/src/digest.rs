@@ -0,0 +1,17 @@
+use anyhow::Result;
+
+/// Lowercase hex SHA-256 of the data
+#[cfg(windows)]
+pub fn sha256_hex(data: &[u8]) -> Result<String> {
+    use windows::Win32::Security::Cryptography::{BCryptHash, BCRYPT_SHA256_ALG_HANDLE};
+
+    let mut digest = [0u8; 32];
+    unsafe { BCryptHash(BCRYPT_SHA256_ALG_HANDLE, None, data, &mut digest) }.ok()?;
+    Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Hashing goes through Windows CNG, only updates and the elevated helper need it
+#[cfg(not(windows))]
+pub fn sha256_hex(_data: &[u8]) -> Result<String> {
+    anyhow::bail!("SHA-256 is only available on Windows")
+}
@@ -0,0 +1,105 @@
+use std::io;
+use std::path::Path;
+
+/// Directories at least this big get a note that removing the entry frees nothing
+pub const LARGE_DIRECTORY: u64 = 1024 * 1024 * 1024;
+
+/// Size of the files directly in a directory, subdirectories are counted but not measured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirectorySize {
+    pub bytes: u64,
+    pub files: usize,
+    pub subdirectories: usize,
+}
+
+impl DirectorySize {
+    /// "412.0 MB in 37 files, 4 subdirectories not counted"
+    pub fn describe(&self) -> String {
+        let mut text = format!(
+            "{} in {} file{}",
+            format_bytes(self.bytes),
+            self.files,
+            if self.files == 1 { "" } else { "s" }
+        );
+        if self.subdirectories > 0 {
+            text.push_str(&format!(
+                ", {} subdirector{} not counted",
+                self.subdirectories,
+                if self.subdirectories == 1 { "y" } else { "ies" }
+            ));
+        }
+        text
+    }
+}
+
+/// Add up the files at the top level of a directory
+/// Links are counted by their own size, so a junction to a big tree costs nothing
+pub fn measure(dir: &Path) -> io::Result<DirectorySize> {
+    let mut size = DirectorySize::default();
+    for entry in std::fs::read_dir(dir)? {
+        let Ok(metadata) = entry.and_then(|entry| entry.metadata()) else {
+            // Removed while listing, or access denied to this one file
+            continue;
+        };
+        if metadata.is_dir() {
+            size.subdirectories += 1;
+        } else {
+            size.files += 1;
+            size.bytes += metadata.len();
+        }
+    }
+    Ok(size)
+}
+
+/// Bytes in binary units with one decimal: "512 B", "1.5 KB", "2.0 GB"
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(LARGE_DIRECTORY * 2), "2.0 GB");
+        assert_eq!(format_bytes(LARGE_DIRECTORY * 1024 * 1024 * 3), "3072.0 TB");
+    }
+
+    #[test]
+    fn test_measure_top_level_only() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("tool.exe"), vec![0u8; 3000]).unwrap();
+        std::fs::write(dir.path().join("tool.dll"), vec![0u8; 72]).unwrap();
+        std::fs::create_dir(dir.path().join("lib")).unwrap();
+        std::fs::write(dir.path().join("lib").join("big.bin"), vec![0u8; 9000]).unwrap();
+
+        let size = measure(dir.path()).unwrap();
+        assert_eq!(
+            size,
+            DirectorySize {
+                bytes: 3072,
+                files: 2,
+                subdirectories: 1
+            }
+        );
+        assert_eq!(
+            size.describe(),
+            "3.0 KB in 2 files, 1 subdirectory not counted"
+        );
+        assert!(measure(&dir.path().join("missing")).is_err());
+    }
+}
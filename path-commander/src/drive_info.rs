@@ -0,0 +1,129 @@
+#[cfg(windows)]
+use std::sync::Mutex;
+#[cfg(windows)]
+use windows::core::{PCWSTR, PWSTR};
+#[cfg(windows)]
+use windows::Win32::Foundation::{ERROR_CONNECTION_UNAVAIL, NO_ERROR};
+#[cfg(windows)]
+use windows::Win32::NetworkManagement::WNet::WNetGetConnectionW;
+#[cfg(windows)]
+use windows::Win32::Storage::FileSystem::GetDriveTypeW;
+#[cfg(windows)]
+use windows::Win32::System::WindowsProgramming::{DRIVE_CDROM, DRIVE_NO_ROOT_DIR, DRIVE_REMOVABLE};
+
+#[cfg(windows)]
+use crate::registry::to_wide_string;
+
+/// Network mapping state of a drive letter
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(not(windows), allow(dead_code))] // Only Windows maps drive letters
+pub enum MappedDrive {
+    /// Local drive (or no drive at all)
+    NotMapped,
+    /// Mapped to the given share and currently connected
+    Connected(String),
+    /// Remembered mapping to the given share that is currently disconnected
+    Disconnected(String),
+}
+
+/// Extract the (uppercase) drive letter from a path like `X:\tools`
+pub fn drive_letter(path: &str) -> Option<char> {
+    let trimmed = path.trim().trim_start_matches('"');
+    let mut chars = trimmed.chars();
+    let letter = chars.next()?;
+    if letter.is_ascii_alphabetic() && chars.next() == Some(':') {
+        Some(letter.to_ascii_uppercase())
+    } else {
+        None
+    }
+}
+
+/// Query the network mapping of a drive letter via WNetGetConnection
+#[cfg(windows)]
+pub fn mapped_drive(letter: char) -> MappedDrive {
+    let local_name = to_wide_string(&format!("{}:", letter));
+    let mut buffer = vec![0u16; 1024];
+    let mut length = buffer.len() as u32;
+
+    let result = unsafe {
+        WNetGetConnectionW(
+            PCWSTR(local_name.as_ptr()),
+            PWSTR(buffer.as_mut_ptr()),
+            &mut length,
+        )
+    };
+
+    let end = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    let remote_name = String::from_utf16_lossy(&buffer[..end]);
+
+    match result {
+        NO_ERROR => MappedDrive::Connected(remote_name),
+        ERROR_CONNECTION_UNAVAIL => MappedDrive::Disconnected(remote_name),
+        _ => MappedDrive::NotMapped,
+    }
+}
+
+/// Drive letters seen as removable during this session. An ejected USB stick
+/// loses its drive letter entirely, so we remember what it used to be.
+#[cfg(windows)]
+static SEEN_REMOVABLE: Mutex<Vec<char>> = Mutex::new(Vec::new());
+
+/// Check if a drive letter belongs to removable media (USB stick, card reader, optical drive)
+#[cfg(windows)]
+pub fn is_removable_drive(letter: char) -> bool {
+    let root = to_wide_string(&format!("{}:\\", letter));
+    let drive_type = unsafe { GetDriveTypeW(PCWSTR(root.as_ptr())) };
+
+    let mut seen = SEEN_REMOVABLE.lock().unwrap_or_else(|e| e.into_inner());
+    match drive_type {
+        DRIVE_REMOVABLE | DRIVE_CDROM => {
+            if !seen.contains(&letter) {
+                seen.push(letter);
+            }
+            true
+        }
+        DRIVE_NO_ROOT_DIR => seen.contains(&letter),
+        _ => false,
+    }
+}
+
+/// Drive letters don't exist outside Windows
+#[cfg(not(windows))]
+pub fn mapped_drive(_letter: char) -> MappedDrive {
+    MappedDrive::NotMapped
+}
+
+#[cfg(not(windows))]
+pub fn is_removable_drive(_letter: char) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drive_letter() {
+        assert_eq!(drive_letter(r"X:\tools"), Some('X'));
+        assert_eq!(drive_letter(r"x:\tools"), Some('X'));
+        assert_eq!(drive_letter(r#""Z:\quoted""#), Some('Z'));
+        assert_eq!(drive_letter(r"\\server\share"), None);
+        assert_eq!(drive_letter(r"%SYSTEMROOT%"), None);
+        assert_eq!(drive_letter(""), None);
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_local_drive_not_mapped() {
+        // The system drive is never a network mapping
+        let system_drive = std::env::var("SYSTEMDRIVE").unwrap_or_else(|_| "C:".to_string());
+        let letter = drive_letter(&system_drive).unwrap();
+        assert_eq!(mapped_drive(letter), MappedDrive::NotMapped);
+        assert!(!is_removable_drive(letter));
+
+        use crate::filesystem::FileSystem;
+        let fs = crate::filesystem::RealFileSystem;
+        assert!(!fs.is_offline_mapped_drive(letter));
+        assert!(fs.is_drive_ready(letter));
+    }
+}
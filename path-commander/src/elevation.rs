@@ -1,12 +1,17 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+#[cfg(windows)]
 use windows::core::PCWSTR;
+#[cfg(windows)]
 use windows::Win32::UI::Shell::ShellExecuteW;
+#[cfg(windows)]
 use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, SW_NORMAL};
 
 use crate::app::{ConnectionMode, FilterMode, Panel};
+use crate::digest::sha256_hex;
+use crate::registry::{self, PathScope, RegistryBackend};
 
 /// Serializable state for elevation persistence
 #[derive(Debug, Serialize, Deserialize)]
@@ -31,64 +36,210 @@ pub struct ElevationState {
 
     // Other state
     pub filter_mode: FilterMode,
+    /// Panel the filter is limited to, missing in state saved by older versions
+    #[serde(default)]
+    pub filter_panel: Option<Panel>,
     pub input_buffer: String,
     pub pending_directory: String,
 
     // Theme to restore
     pub theme_arg: Option<String>,
+    /// Launch profile to keep after the restart, missing in state saved by older versions
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Started with --no-fs-checks, missing in state saved by older versions
+    #[serde(default)]
+    pub no_fs_checks: bool,
 }
 
 impl ElevationState {
     /// Save state to a temporary JSON file
+    #[cfg_attr(not(windows), allow(dead_code))]
     pub fn save(&self) -> Result<PathBuf> {
-        let temp_dir = std::env::temp_dir();
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let random = rand::random::<u32>();
-        let filename = format!("pc_elevation_{}_{}.json", timestamp, random);
-        let path = temp_dir.join(filename);
-
         let json =
             serde_json::to_string_pretty(self).context("Failed to serialize elevation state")?;
+        write_temp_file("pc_elevation", &json)
+    }
 
-        std::fs::write(&path, json).context("Failed to write elevation state file")?;
+    /// Load state from a temporary JSON file
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = read_temp_file(path)?;
+        serde_json::from_str(&json).context("Failed to deserialize elevation state")
+    }
+}
 
-        Ok(path)
+/// MACHINE entries handed to the elevated write helper
+/// (`pc --apply-machine FILE --payload-sha256 HEX`)
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MachinePayload {
+    pub machine_paths: Vec<String>,
+}
+
+impl MachinePayload {
+    /// Returns the file and the SHA-256 of what was written to it
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub fn save(&self) -> Result<(PathBuf, String)> {
+        let json = serde_json::to_string(self).context("Failed to serialize MACHINE payload")?;
+        let sha256 = sha256_hex(json.as_bytes())?;
+        Ok((write_temp_file("pc_apply_machine", &json)?, sha256))
     }
 
-    /// Load state from a temporary JSON file
-    pub fn load(path: &PathBuf) -> Result<Self> {
-        // Validate file age (reject if older than 5 minutes for security)
-        let metadata =
-            std::fs::metadata(path).context("Failed to read elevation state file metadata")?;
-        let created = metadata
-            .created()
-            .or_else(|_| metadata.modified())
-            .context("Failed to get file timestamp")?;
-        let age = std::time::SystemTime::now()
-            .duration_since(created)
-            .unwrap_or(std::time::Duration::from_secs(0));
-
-        if age.as_secs() > 300 {
-            anyhow::bail!("Elevation state file is too old (>5 minutes). Ignoring for security.");
+    /// The file sits in the user's temp directory, so it is only trusted if it still holds
+    /// exactly what `save` wrote
+    pub fn load(path: &Path, sha256: &str) -> Result<Self> {
+        let json = read_temp_file(path)?;
+        if sha256_hex(json.as_bytes())? != sha256.to_ascii_lowercase() {
+            anyhow::bail!("MACHINE payload changed after it was approved");
+        }
+        serde_json::from_str(&json).context("Failed to deserialize MACHINE payload")
+    }
+}
+
+/// Exit code of the helper when the payload is missing, expired or not the approved one
+pub const HELPER_REJECTED: i32 = 2;
+
+/// Exit code of the helper when the registry refused the write
+pub const HELPER_WRITE_FAILED: i32 = 3;
+
+/// Write a JSON file with a unique name to the temp directory
+fn write_temp_file(prefix: &str, json: &str) -> Result<PathBuf> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let random = rand::random::<u32>();
+    let filename = format!("{}_{}_{}.json", prefix, timestamp, random);
+    let path = std::env::temp_dir().join(filename);
+
+    std::fs::write(&path, json).context("Failed to write elevation state file")?;
+
+    Ok(path)
+}
+
+/// Read and remove a file written by write_temp_file
+fn read_temp_file(path: &Path) -> Result<String> {
+    // Validate file age (reject if older than 5 minutes for security)
+    let metadata =
+        std::fs::metadata(path).context("Failed to read elevation state file metadata")?;
+    let created = metadata
+        .created()
+        .or_else(|_| metadata.modified())
+        .context("Failed to get file timestamp")?;
+    let age = std::time::SystemTime::now()
+        .duration_since(created)
+        .unwrap_or(std::time::Duration::from_secs(0));
+
+    if age.as_secs() > 300 {
+        anyhow::bail!("Elevation state file is too old (>5 minutes). Ignoring for security.");
+    }
+
+    // Read file
+    let json = std::fs::read_to_string(path).context("Failed to read elevation state file")?;
+
+    // Clean up temp file
+    std::fs::remove_file(path).ok();
+
+    Ok(json)
+}
+
+/// Body of the elevated helper process: write MACHINE from the payload, returns the exit code
+/// Nothing is written to the user's files from here, the exit code is the only report
+pub fn run_machine_helper(payload_file: &Path, sha256: &str) -> i32 {
+    let Ok(payload) = MachinePayload::load(payload_file, sha256) else {
+        return HELPER_REJECTED;
+    };
+    match registry::local_backend().write_paths(PathScope::Machine, &payload.machine_paths) {
+        Ok(()) => 0,
+        Err(_) => HELPER_WRITE_FAILED,
+    }
+}
+
+/// Writes MACHINE through a short-lived elevated copy of pc, leaving this session unelevated
+pub struct HelperRegistry;
+
+impl RegistryBackend for HelperRegistry {
+    fn read_paths(&self, scope: PathScope) -> Result<Vec<String>> {
+        // Reading needs no elevation
+        registry::local_backend().read_paths(scope)
+    }
+
+    fn write_paths(&mut self, scope: PathScope, paths: &[String]) -> Result<()> {
+        if scope != PathScope::Machine {
+            anyhow::bail!("The elevated helper only writes MACHINE");
+        }
+        let (payload, sha256) = MachinePayload {
+            machine_paths: paths.to_vec(),
         }
+        .save()?;
+        let result = run_helper_elevated(&payload, &sha256);
+        // The helper normally removes it, not if UAC was declined
+        std::fs::remove_file(&payload).ok();
+        result
+    }
+}
+
+/// Start `pc --apply-machine FILE --payload-sha256 HEX` through UAC and wait for it
+#[cfg(windows)]
+fn run_helper_elevated(payload: &Path, sha256: &str) -> Result<()> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{GetExitCodeProcess, WaitForSingleObject, INFINITE};
+    use windows::Win32::UI::Shell::{ShellExecuteExW, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW};
+    use windows::Win32::UI::WindowsAndMessaging::SW_HIDE;
 
-        // Read and parse file
-        let json = std::fs::read_to_string(path).context("Failed to read elevation state file")?;
+    let current_exe = std::env::current_exe().context("Failed to get current executable path")?;
+    let to_wide = |s: &str| -> Vec<u16> { s.encode_utf16().chain(std::iter::once(0)).collect() };
+    let exe_wide = to_wide(&current_exe.to_string_lossy());
+    let params_wide = to_wide(&format!(
+        "--apply-machine \"{}\" --payload-sha256 {}",
+        payload.display(),
+        sha256
+    ));
+    let verb_wide = to_wide("runas");
 
-        let state: Self =
-            serde_json::from_str(&json).context("Failed to deserialize elevation state")?;
+    let mut info = SHELLEXECUTEINFOW {
+        cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+        fMask: SEE_MASK_NOCLOSEPROCESS,
+        lpVerb: PCWSTR(verb_wide.as_ptr()),
+        lpFile: PCWSTR(exe_wide.as_ptr()),
+        lpParameters: PCWSTR(params_wide.as_ptr()),
+        nShow: SW_HIDE.0,
+        ..Default::default()
+    };
 
-        // Clean up temp file
-        std::fs::remove_file(path).ok();
+    let mut exit_code = 0u32;
+    unsafe {
+        info.hwnd = GetForegroundWindow();
+        ShellExecuteExW(&mut info).context("UAC elevation was cancelled or failed")?;
+        if info.hProcess.is_invalid() {
+            anyhow::bail!("The elevated helper did not start");
+        }
+        WaitForSingleObject(info.hProcess, INFINITE);
+        let status = GetExitCodeProcess(info.hProcess, &mut exit_code);
+        let _ = CloseHandle(info.hProcess);
+        status.context("Failed to read the elevated helper's exit code")?;
+    }
 
-        Ok(state)
+    match exit_code as i32 {
+        0 => Ok(()),
+        HELPER_REJECTED => anyhow::bail!(
+            "Elevated MACHINE write failed: the payload was missing, expired or changed before the helper read it"
+        ),
+        HELPER_WRITE_FAILED => {
+            anyhow::bail!("Elevated MACHINE write failed: the registry refused the write")
+        }
+        code => anyhow::bail!("Elevated MACHINE write failed: exit code {}", code),
     }
 }
 
+#[cfg(not(windows))]
+fn run_helper_elevated(_payload: &Path, _sha256: &str) -> Result<()> {
+    anyhow::bail!(
+        "The elevated helper is not supported on this platform. Run pc with sudo to edit MACHINE paths."
+    )
+}
+
 /// Request UAC elevation by restarting the application with administrator privileges
+#[cfg(windows)]
 pub fn request_elevation(state: &ElevationState, current_exe: &str) -> Result<()> {
     // Save state to temp file
     let state_file = state.save()?;
@@ -139,6 +290,14 @@ pub fn request_elevation(state: &ElevationState, current_exe: &str) -> Result<()
     Ok(())
 }
 
+/// There is no UAC prompt to restart through, the user has to rerun with sudo
+#[cfg(not(windows))]
+pub fn request_elevation(_state: &ElevationState, _current_exe: &str) -> Result<()> {
+    anyhow::bail!(
+        "Elevation is not supported on this platform. Run pc with sudo to edit MACHINE paths."
+    )
+}
+
 /// Check if there are MACHINE path changes that require elevation
 pub fn needs_elevation_for_changes(
     is_admin: bool,
@@ -163,3 +322,41 @@ pub fn needs_elevation_for_changes(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Hashing is only built on Windows, the one platform with the helper
+    #[cfg(windows)]
+    #[test]
+    fn test_machine_payload_roundtrip() {
+        let payload = MachinePayload {
+            machine_paths: vec![r"C:\Windows".to_string(), r"C:\Tools".to_string()],
+        };
+        let (file, sha256) = payload.save().unwrap();
+        assert_eq!(MachinePayload::load(&file, &sha256).unwrap(), payload);
+        // Read once, then gone
+        assert!(!file.exists());
+        assert!(MachinePayload::load(&file, &sha256).is_err());
+
+        // Swapped after it was approved
+        let (file, sha256) = payload.save().unwrap();
+        std::fs::write(&file, r#"{"machine_paths":["C:\\Planted"]}"#).unwrap();
+        assert!(MachinePayload::load(&file, &sha256).is_err());
+    }
+
+    #[test]
+    fn test_machine_helper_rejects_unapproved_payloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("pc_apply_machine.json");
+        std::fs::write(&file, r#"{"machine_paths":["C:\\Planted"]}"#).unwrap();
+        assert_eq!(run_machine_helper(&file, &"00".repeat(32)), HELPER_REJECTED);
+        assert_eq!(
+            run_machine_helper(&dir.path().join("missing.json"), ""),
+            HELPER_REJECTED
+        );
+        // Nothing is left behind for the unelevated side to read
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,184 @@
+use anyhow::Result;
+
+use crate::history::HistoryEntry;
+
+/// Event source shown in the Application log
+#[cfg_attr(not(windows), allow(dead_code))]
+const SOURCE_NAME: &str = "PathCommander";
+
+/// Event ID of an apply, within the range the EventCreate message file defines
+#[cfg(windows)]
+const APPLY_EVENT_ID: u32 = 1000;
+
+/// Registry key where event sources of the Application log are registered
+#[cfg(windows)]
+const SOURCE_KEY: &str =
+    "SYSTEM\\CurrentControlSet\\Services\\EventLog\\Application\\PathCommander";
+
+/// Message file whose messages are just "%1", so our text is shown as is
+#[cfg(windows)]
+const MESSAGE_FILE: &str = "%SystemRoot%\\System32\\EventCreate.exe";
+
+/// Build the event text for an apply
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn format_message(entry: &HistoryEntry, user: &str) -> String {
+    let mut message = format!(
+        "{} changed PATH on {} as {}.\r\n",
+        SOURCE_NAME, entry.computer, user
+    );
+    for change in &entry.changes {
+        message.push_str(&format!("\r\n{}\r\n", change.summary()));
+        for added in &change.added {
+            message.push_str(&format!("  + {}\r\n", added));
+        }
+        for removed in &change.removed {
+            message.push_str(&format!("  - {}\r\n", removed));
+        }
+    }
+    message
+}
+
+/// Name of the account doing the apply
+pub fn current_user() -> String {
+    std::env::var("USERNAME")
+        .or_else(|_| std::env::var("USER"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Write an information event for an apply to the Application log
+#[cfg(windows)]
+pub fn report_apply(entry: &HistoryEntry) -> Result<()> {
+    use crate::registry::to_wide_string;
+    use anyhow::Context;
+    use windows::core::PCWSTR;
+    use windows::Win32::Security::PSID;
+    use windows::Win32::System::EventLog::{
+        DeregisterEventSource, RegisterEventSourceW, ReportEventW, EVENTLOG_INFORMATION_TYPE,
+    };
+
+    // Without a registered source the event still shows up, just with a canned description
+    let _ = register_source();
+
+    let message = to_wide_string(&format_message(entry, &current_user()));
+    let source = to_wide_string(SOURCE_NAME);
+    unsafe {
+        let handle = RegisterEventSourceW(PCWSTR::null(), PCWSTR(source.as_ptr()))
+            .context("Failed to open the Application event log")?;
+        let result = ReportEventW(
+            handle,
+            EVENTLOG_INFORMATION_TYPE,
+            0,
+            APPLY_EVENT_ID,
+            PSID::default(),
+            0,
+            Some(&[PCWSTR(message.as_ptr())]),
+            None,
+        );
+        let _ = DeregisterEventSource(handle);
+        result.context("Failed to write to the Application event log")
+    }
+}
+
+/// Register the event source once, needs administrator rights
+#[cfg(windows)]
+fn register_source() -> Result<()> {
+    use crate::registry::to_wide_string;
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::ERROR_SUCCESS;
+    use windows::Win32::System::Registry::{
+        RegCloseKey, RegCreateKeyExW, RegOpenKeyExW, RegSetValueExW, HKEY, HKEY_LOCAL_MACHINE,
+        KEY_READ, KEY_WRITE, REG_DWORD, REG_EXPAND_SZ, REG_OPTION_NON_VOLATILE,
+    };
+
+    let subkey = to_wide_string(SOURCE_KEY);
+    unsafe {
+        // Already registered
+        let mut hkey = HKEY::default();
+        if RegOpenKeyExW(
+            HKEY_LOCAL_MACHINE,
+            PCWSTR(subkey.as_ptr()),
+            0,
+            KEY_READ,
+            &mut hkey,
+        ) == ERROR_SUCCESS
+        {
+            let _ = RegCloseKey(hkey);
+            return Ok(());
+        }
+
+        let result = RegCreateKeyExW(
+            HKEY_LOCAL_MACHINE,
+            PCWSTR(subkey.as_ptr()),
+            0,
+            PCWSTR::null(),
+            REG_OPTION_NON_VOLATILE,
+            KEY_WRITE,
+            None,
+            &mut hkey,
+            None,
+        );
+        if result != ERROR_SUCCESS {
+            return Err(anyhow::anyhow!(
+                "Failed to register event source: {:?}",
+                result
+            ));
+        }
+
+        let message_file = to_wide_string(MESSAGE_FILE);
+        let message_bytes =
+            std::slice::from_raw_parts(message_file.as_ptr() as *const u8, message_file.len() * 2);
+        let name = to_wide_string("EventMessageFile");
+        let _ = RegSetValueExW(
+            hkey,
+            PCWSTR(name.as_ptr()),
+            0,
+            REG_EXPAND_SZ,
+            Some(message_bytes),
+        );
+
+        // Error, warning and information events
+        let types: u32 = 7;
+        let name = to_wide_string("TypesSupported");
+        let _ = RegSetValueExW(
+            hkey,
+            PCWSTR(name.as_ptr()),
+            0,
+            REG_DWORD,
+            Some(&types.to_le_bytes()),
+        );
+
+        let _ = RegCloseKey(hkey);
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+pub fn report_apply(_entry: &HistoryEntry) -> Result<()> {
+    Err(anyhow::anyhow!(
+        "the Windows event log is not available on this platform"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::ScopeChange;
+    use crate::registry::PathScope;
+
+    #[test]
+    fn test_format_message() {
+        let entry = HistoryEntry::new(
+            "SERVER01",
+            vec![ScopeChange::between(
+                PathScope::Machine,
+                &[r"C:\Old".to_string()],
+                &[r"C:\Tools".to_string()],
+            )
+            .unwrap()],
+        );
+
+        let message = format_message(&entry, "alice");
+        assert!(message.starts_with("PathCommander changed PATH on SERVER01 as alice."));
+        assert!(message.contains("MACHINE +1 -1\r\n  + C:\\Tools\r\n  - C:\\Old\r\n"));
+    }
+}
@@ -0,0 +1,301 @@
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::drive_info::{self, MappedDrive};
+use crate::security_scan::{SecurityProbe, SystemProbe};
+
+/// Filesystem queries used by the analyzer and the app's existence checks
+/// Abstracted so tests can run against an in-memory fake instead of the real disk
+pub trait FileSystem {
+    /// Check if a path exists (file or directory)
+    fn exists(&self, path: &str) -> bool;
+
+    /// Resolve a path to its canonical long form (8.3 short names expanded)
+    fn canonicalize(&self, path: &str) -> Option<String>;
+
+    /// Check if a path is a junction or symlink
+    fn is_symlink(&self, path: &str) -> bool;
+
+    /// Network mapping state of a drive letter
+    fn mapped_drive(&self, letter: char) -> MappedDrive;
+
+    /// Check if a drive letter belongs to removable media
+    fn is_removable_drive(&self, letter: char) -> bool;
+
+    /// Check if any local user can create files in a directory
+    fn is_writable_by_all_users(&self, path: &str) -> bool;
+
+    /// Last time a directory's contents changed, None if it can't be read
+    fn modified(&self, path: &str) -> Option<SystemTime>;
+
+    /// False when nothing is read from the disk, so no entry can be judged
+    fn is_checked(&self) -> bool {
+        true
+    }
+
+    /// Check if the root of a drive is currently accessible (media inserted)
+    fn is_drive_ready(&self, letter: char) -> bool {
+        self.exists(&format!("{}:\\", letter))
+    }
+
+    /// Check if a drive letter is a mapped network drive that can't be reached right now
+    /// (either a disconnected remembered mapping, or connected but the share is down)
+    fn is_offline_mapped_drive(&self, letter: char) -> bool {
+        match self.mapped_drive(letter) {
+            MappedDrive::Disconnected(_) => true,
+            MappedDrive::Connected(_) => !self.is_drive_ready(letter),
+            MappedDrive::NotMapped => false,
+        }
+    }
+}
+
+/// The real filesystem and drive APIs
+pub struct RealFileSystem;
+
+impl FileSystem for RealFileSystem {
+    fn exists(&self, path: &str) -> bool {
+        Path::new(path).exists()
+    }
+
+    fn canonicalize(&self, path: &str) -> Option<String> {
+        std::fs::canonicalize(path)
+            .ok()
+            .and_then(|p| p.to_str().map(|s| s.to_string()))
+    }
+
+    fn is_symlink(&self, path: &str) -> bool {
+        // Junctions are name-surrogate reparse points, which std reports as symlinks
+        std::fs::symlink_metadata(path)
+            .map(|m| m.file_type().is_symlink())
+            .unwrap_or(false)
+    }
+
+    fn mapped_drive(&self, letter: char) -> MappedDrive {
+        drive_info::mapped_drive(letter)
+    }
+
+    fn is_removable_drive(&self, letter: char) -> bool {
+        drive_info::is_removable_drive(letter)
+    }
+
+    fn is_writable_by_all_users(&self, path: &str) -> bool {
+        SystemProbe
+            .writable_by(Path::new(path))
+            .is_ok_and(|groups| !groups.is_empty())
+    }
+
+    fn modified(&self, path: &str) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+}
+
+/// Answers without touching the disk, for `--no-fs-checks` on machines where a hung
+/// network drive would block every check
+/// Everything counts as present and plain, so PATH is edited purely as strings
+pub struct UncheckedFileSystem;
+
+impl FileSystem for UncheckedFileSystem {
+    fn exists(&self, _path: &str) -> bool {
+        true
+    }
+
+    fn canonicalize(&self, _path: &str) -> Option<String> {
+        None
+    }
+
+    fn is_symlink(&self, _path: &str) -> bool {
+        false
+    }
+
+    fn mapped_drive(&self, _letter: char) -> MappedDrive {
+        MappedDrive::NotMapped
+    }
+
+    fn is_removable_drive(&self, _letter: char) -> bool {
+        false
+    }
+
+    fn is_writable_by_all_users(&self, _path: &str) -> bool {
+        false
+    }
+
+    fn modified(&self, _path: &str) -> Option<SystemTime> {
+        None
+    }
+
+    fn is_checked(&self) -> bool {
+        false
+    }
+
+    fn is_drive_ready(&self, _letter: char) -> bool {
+        true
+    }
+}
+
+/// The real disk, or answers that never touch it with --no-fs-checks
+pub fn for_session(no_fs_checks: bool) -> Box<dyn FileSystem> {
+    if no_fs_checks {
+        Box::new(UncheckedFileSystem)
+    } else {
+        Box::new(RealFileSystem)
+    }
+}
+
+/// In-memory filesystem for tests
+/// Paths are compared case-insensitively, like on Windows
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MemoryFileSystem {
+    dirs: Vec<String>,
+    symlinks: Vec<String>,
+    mapped: std::collections::HashMap<char, MappedDrive>,
+    removable: Vec<char>,
+    insecure: Vec<String>,
+    modified: Vec<(String, SystemTime)>,
+}
+
+#[cfg(test)]
+impl MemoryFileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a directory (its parents exist implicitly)
+    pub fn with_dir(mut self, path: &str) -> Self {
+        self.dirs.push(path.trim_end_matches('\\').to_string());
+        self
+    }
+
+    /// Add a directory that is a junction or symlink
+    pub fn with_symlink(mut self, path: &str) -> Self {
+        let path = path.trim_end_matches('\\').to_string();
+        self.dirs.push(path.clone());
+        self.symlinks.push(path);
+        self
+    }
+
+    /// Map a drive letter to a network share
+    pub fn with_mapped_drive(mut self, letter: char, state: MappedDrive) -> Self {
+        self.mapped.insert(letter.to_ascii_uppercase(), state);
+        self
+    }
+
+    /// Mark a drive letter as removable media
+    pub fn with_removable_drive(mut self, letter: char) -> Self {
+        self.removable.push(letter.to_ascii_uppercase());
+        self
+    }
+
+    /// Add a directory that any local user can write to
+    pub fn with_insecure_dir(mut self, path: &str) -> Self {
+        let path = path.trim_end_matches('\\').to_string();
+        self.dirs.push(path.clone());
+        self.insecure.push(path);
+        self
+    }
+
+    /// Add a directory last changed at the given time
+    pub fn with_modified_dir(mut self, path: &str, time: SystemTime) -> Self {
+        let path = path.trim_end_matches('\\').to_string();
+        self.dirs.push(path.clone());
+        self.modified.push((path, time));
+        self
+    }
+
+    /// Find the stored spelling of a directory, or of a directory below it
+    fn find(&self, path: &str) -> Option<&String> {
+        let wanted = path.trim_end_matches('\\').to_lowercase();
+        if wanted.is_empty() {
+            return None;
+        }
+        self.dirs.iter().find(|dir| {
+            let dir = dir.to_lowercase();
+            dir == wanted || dir.starts_with(&format!("{}\\", wanted))
+        })
+    }
+}
+
+#[cfg(test)]
+impl FileSystem for MemoryFileSystem {
+    fn exists(&self, path: &str) -> bool {
+        self.find(path).is_some()
+    }
+
+    fn canonicalize(&self, path: &str) -> Option<String> {
+        // Return the stored spelling, cut to the requested depth
+        let depth = path.trim_end_matches('\\').split('\\').count();
+        self.find(path)
+            .map(|dir| dir.split('\\').take(depth).collect::<Vec<_>>().join("\\"))
+    }
+
+    fn is_symlink(&self, path: &str) -> bool {
+        let wanted = path.trim_end_matches('\\');
+        self.symlinks.iter().any(|s| s.eq_ignore_ascii_case(wanted))
+    }
+
+    fn mapped_drive(&self, letter: char) -> MappedDrive {
+        self.mapped
+            .get(&letter.to_ascii_uppercase())
+            .cloned()
+            .unwrap_or(MappedDrive::NotMapped)
+    }
+
+    fn is_removable_drive(&self, letter: char) -> bool {
+        self.removable.contains(&letter.to_ascii_uppercase())
+    }
+
+    fn is_writable_by_all_users(&self, path: &str) -> bool {
+        let wanted = path.trim_end_matches('\\');
+        self.insecure.iter().any(|s| s.eq_ignore_ascii_case(wanted))
+    }
+
+    fn modified(&self, path: &str) -> Option<SystemTime> {
+        let wanted = path.trim_end_matches('\\');
+        self.modified
+            .iter()
+            .find(|(dir, _)| dir.eq_ignore_ascii_case(wanted))
+            .map(|(_, time)| *time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_fs_exists() {
+        let fs = MemoryFileSystem::new().with_dir(r"C:\Tools\Bin");
+        assert!(fs.exists(r"C:\Tools\Bin"));
+        assert!(fs.exists(r"c:\tools\bin\"));
+        assert!(fs.exists(r"C:\Tools"));
+        assert!(fs.exists(r"C:\"));
+        assert!(!fs.exists(r"C:\Tool"));
+        assert!(!fs.exists(r"D:\"));
+        assert!(!fs.exists(""));
+    }
+
+    #[test]
+    fn test_memory_fs_canonicalize() {
+        let fs = MemoryFileSystem::new().with_dir(r"C:\Program Files\App");
+        assert_eq!(
+            fs.canonicalize(r"c:\program files").as_deref(),
+            Some(r"C:\Program Files")
+        );
+        assert_eq!(fs.canonicalize(r"C:\Missing"), None);
+    }
+
+    #[test]
+    fn test_offline_mapped_drive() {
+        let fs = MemoryFileSystem::new()
+            .with_dir(r"Y:\tools")
+            .with_mapped_drive('X', MappedDrive::Disconnected(r"\\nas\x".to_string()))
+            .with_mapped_drive('Y', MappedDrive::Connected(r"\\nas\y".to_string()))
+            .with_mapped_drive('Z', MappedDrive::Connected(r"\\nas\z".to_string()));
+
+        assert!(fs.is_offline_mapped_drive('X'));
+        assert!(!fs.is_offline_mapped_drive('Y'));
+        // Connected, but the share is unreachable
+        assert!(fs.is_offline_mapped_drive('Z'));
+        assert!(!fs.is_offline_mapped_drive('C'));
+    }
+}
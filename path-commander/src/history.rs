@@ -0,0 +1,195 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use crate::registry::PathScope;
+
+/// What one apply changed in a single scope
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScopeChange {
+    pub scope: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Entries kept but in a different order
+    pub reordered: bool,
+}
+
+impl ScopeChange {
+    /// Diff a scope before and after an apply, None if nothing changed
+    pub fn between(scope: PathScope, before: &[String], after: &[String]) -> Option<Self> {
+        if before == after {
+            return None;
+        }
+
+        let added: Vec<String> = after
+            .iter()
+            .filter(|entry| !before.contains(entry))
+            .cloned()
+            .collect();
+        let removed: Vec<String> = before
+            .iter()
+            .filter(|entry| !after.contains(entry))
+            .cloned()
+            .collect();
+
+        // Same entries left over on both sides but not in the same order
+        let kept_before: Vec<&String> = before.iter().filter(|e| after.contains(e)).collect();
+        let kept_after: Vec<&String> = after.iter().filter(|e| before.contains(e)).collect();
+
+        Some(Self {
+            scope: scope.as_str().to_string(),
+            added,
+            removed,
+            reordered: kept_before != kept_after,
+        })
+    }
+
+    /// Short summary like "USER +2 -1 (reordered)"
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} +{} -{}",
+            self.scope,
+            self.added.len(),
+            self.removed.len()
+        );
+        if self.reordered {
+            summary.push_str(" (reordered)");
+        }
+        summary
+    }
+}
+
+/// One successful apply on one computer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub timestamp: String,
+    pub computer: String,
+    pub changes: Vec<ScopeChange>,
+}
+
+impl HistoryEntry {
+    pub fn new(computer: &str, changes: Vec<ScopeChange>) -> Self {
+        Self {
+            timestamp: Local::now().to_rfc3339(),
+            computer: computer.to_string(),
+            changes,
+        }
+    }
+
+    /// Timestamp formatted for display
+    pub fn display_time(&self) -> String {
+        DateTime::parse_from_rfc3339(&self.timestamp)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|_| self.timestamp.clone())
+    }
+}
+
+/// Name of this computer as recorded in the history
+pub fn local_computer_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "localhost".to_string())
+}
+
+/// Append an entry to the history file, one JSON object per line
+pub fn append(path: &Path, entry: &HistoryEntry) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open history file: {:?}", path))?;
+    let line = serde_json::to_string(entry).context("Failed to serialize history entry")?;
+    writeln!(file, "{}", line).with_context(|| format!("Failed to write history file: {:?}", path))
+}
+
+/// Load the history, newest first
+/// Lines that cannot be parsed (e.g. from an interrupted write) are skipped
+pub fn load(path: &Path) -> Result<Vec<HistoryEntry>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read history file: {:?}", path))
+        }
+    };
+
+    let mut entries: Vec<HistoryEntry> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    entries.reverse();
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(entries: &[&str]) -> Vec<String> {
+        entries.iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn test_scope_change_between() {
+        let before = paths(&[r"C:\A", r"C:\B", r"C:\C"]);
+
+        assert!(ScopeChange::between(PathScope::User, &before, &before).is_none());
+
+        let change = ScopeChange::between(
+            PathScope::User,
+            &before,
+            &paths(&[r"C:\A", r"C:\C", r"C:\D"]),
+        )
+        .unwrap();
+        assert_eq!(change.scope, "USER");
+        assert_eq!(change.added, paths(&[r"C:\D"]));
+        assert_eq!(change.removed, paths(&[r"C:\B"]));
+        assert!(!change.reordered);
+        assert_eq!(change.summary(), "USER +1 -1");
+
+        let moved = ScopeChange::between(
+            PathScope::Machine,
+            &before,
+            &paths(&[r"C:\C", r"C:\A", r"C:\B"]),
+        )
+        .unwrap();
+        assert!(moved.added.is_empty() && moved.removed.is_empty());
+        assert!(moved.reordered);
+        assert_eq!(moved.summary(), "MACHINE +0 -0 (reordered)");
+    }
+
+    #[test]
+    fn test_append_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("nested").join("history.jsonl");
+
+        // Nothing recorded yet
+        assert!(load(&file).unwrap().is_empty());
+
+        let first = HistoryEntry::new(
+            "WS01",
+            vec![ScopeChange::between(PathScope::User, &[], &paths(&[r"C:\Tools"])).unwrap()],
+        );
+        let second = HistoryEntry::new(
+            "SERVER01",
+            vec![ScopeChange::between(PathScope::Machine, &paths(&[r"C:\Old"]), &[]).unwrap()],
+        );
+        append(&file, &first).unwrap();
+        append(&file, &second).unwrap();
+
+        // A damaged line does not hide the rest of the history
+        let mut content = fs::read_to_string(&file).unwrap();
+        content.push_str("{not json\n");
+        fs::write(&file, content).unwrap();
+
+        let entries = load(&file).unwrap();
+        assert_eq!(entries, vec![second, first]);
+    }
+}
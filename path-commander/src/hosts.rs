@@ -0,0 +1,298 @@
+use anyhow::{anyhow, Context, Result};
+use std::path::Path;
+
+use crate::path_analyzer::PathInfo;
+use crate::policy::strip_comment;
+
+/// A remote computer saved in the host inventory
+///
+/// ```toml
+/// [[host]]
+/// name = "Build agent"
+/// address = "build-01.corp.local"
+/// # Which account to use, never a password
+/// credential = 'CORP\svc-build'
+/// notes = "Restart the agent service after PATH changes"
+/// last_connected = "2026-10-15 14:30"
+/// last_health = "38 entries, 2 dead"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Host {
+    /// Shown in the picker, the address if not given
+    pub name: String,
+    /// What is connected to
+    pub address: String,
+    pub credential: String,
+    pub notes: String,
+    /// Local time of the last successful connect
+    pub last_connected: Option<String>,
+    /// Summary of the remote MACHINE PATH when it was last read or written
+    pub last_health: Option<String>,
+}
+
+impl Host {
+    pub fn new(address: &str) -> Self {
+        Self {
+            name: address.to_string(),
+            address: address.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether a typed computer name means this host
+    pub fn matches(&self, computer_name: &str) -> bool {
+        self.address.eq_ignore_ascii_case(computer_name)
+            || self.name.eq_ignore_ascii_case(computer_name)
+    }
+}
+
+/// The hosts in ~/.pc/hosts.toml, in file order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostInventory {
+    pub hosts: Vec<Host>,
+}
+
+impl HostInventory {
+    /// Load the inventory, a missing file means no hosts yet
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read hosts: {}", path.display()))
+            }
+        };
+        Self::parse(&content).with_context(|| format!("Invalid hosts file: {}", path.display()))
+    }
+
+    /// Parse the subset of TOML the inventory uses: `[[host]]` tables of string keys
+    fn parse(content: &str) -> Result<Self> {
+        let mut inventory = Self::default();
+        for (index, line) in content.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line == "[[host]]" {
+                inventory.hosts.push(Host::default());
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow!("line {}: expected `key = \"...\"`", index + 1))?;
+            let host = inventory
+                .hosts
+                .last_mut()
+                .ok_or_else(|| anyhow!("line {}: expected [[host]] first", index + 1))?;
+            let value = parse_string(value.trim())
+                .with_context(|| format!("line {}: `{}`", index + 1, key.trim()))?;
+            match key.trim() {
+                "name" => host.name = value,
+                "address" => host.address = value,
+                "credential" => host.credential = value,
+                "notes" => host.notes = value,
+                "last_connected" => host.last_connected = Some(value),
+                "last_health" => host.last_health = Some(value),
+                // Unknown keys are tolerated so newer files still load
+                _ => {}
+            }
+        }
+
+        // A host given only by name is connected to by that name, and the other way round
+        inventory
+            .hosts
+            .retain(|host| !host.name.trim().is_empty() || !host.address.trim().is_empty());
+        for host in inventory.hosts.iter_mut() {
+            if host.address.trim().is_empty() {
+                host.address = host.name.clone();
+            } else if host.name.trim().is_empty() {
+                host.name = host.address.clone();
+            }
+        }
+        Ok(inventory)
+    }
+
+    /// The inventory as TOML, empty values left out
+    pub fn to_toml(&self) -> String {
+        let mut out = String::from(
+            "# Remote computers for File > Connect to...\n\
+             # last_connected and last_health are updated by Path Commander\n",
+        );
+        for host in &self.hosts {
+            out.push_str("\n[[host]]\n");
+            let values = [
+                ("name", Some(&host.name)),
+                ("address", Some(&host.address)),
+                ("credential", Some(&host.credential)),
+                ("notes", Some(&host.notes)),
+                ("last_connected", host.last_connected.as_ref()),
+                ("last_health", host.last_health.as_ref()),
+            ];
+            for (key, value) in values {
+                if let Some(value) = value.filter(|value| !value.is_empty()) {
+                    out.push_str(&format!("{} = {}\n", key, quote(value)));
+                }
+            }
+        }
+        out
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(path, self.to_toml())
+            .with_context(|| format!("Failed to write hosts: {}", path.display()))
+    }
+
+    /// Note a successful session with a host, adding it if it is not in the inventory yet
+    pub fn record_session(&mut self, computer_name: &str, health: String, when: String) {
+        let index = match self
+            .hosts
+            .iter()
+            .position(|host| host.matches(computer_name))
+        {
+            Some(index) => index,
+            None => {
+                self.hosts.push(Host::new(computer_name));
+                self.hosts.len() - 1
+            }
+        };
+        let host = &mut self.hosts[index];
+        host.last_connected = Some(when);
+        host.last_health = Some(health);
+    }
+}
+
+/// "38 entries, 2 dead, 1 duplicate", or "12 entries, no problems"
+pub fn path_health(infos: &[PathInfo]) -> String {
+    let entries = format!(
+        "{} entr{}",
+        infos.len(),
+        if infos.len() == 1 { "y" } else { "ies" }
+    );
+    let dead = infos.iter().filter(|info| !info.exists).count();
+    let duplicates = infos.iter().filter(|info| info.is_duplicate).count();
+    let mut parts = vec![entries];
+    if dead > 0 {
+        parts.push(format!("{} dead", dead));
+    }
+    if duplicates > 0 {
+        parts.push(format!(
+            "{} duplicate{}",
+            duplicates,
+            if duplicates == 1 { "" } else { "s" }
+        ));
+    }
+    if parts.len() == 1 {
+        parts.push("no problems".to_string());
+    }
+    parts.join(", ")
+}
+
+/// Parse a TOML basic ("...") or literal ('...') string
+fn parse_string(value: &str) -> Result<String> {
+    let mut chars = value.chars();
+    let quote = chars
+        .next()
+        .filter(|c| *c == '"' || *c == '\'')
+        .ok_or_else(|| anyhow!("expected a quoted string"))?;
+
+    let mut parsed = String::new();
+    loop {
+        match chars.next() {
+            None => return Err(anyhow!("unterminated string")),
+            Some(c) if c == quote => break,
+            // Only basic strings have escapes
+            Some('\\') if quote == '"' => match chars.next() {
+                Some('\\') => parsed.push('\\'),
+                Some('"') => parsed.push('"'),
+                Some('n') => parsed.push('\n'),
+                Some('t') => parsed.push('\t'),
+                Some(other) => return Err(anyhow!("unsupported escape \\{}", other)),
+                None => return Err(anyhow!("unterminated string")),
+            },
+            Some(c) => parsed.push(c),
+        }
+    }
+    if chars.as_str().trim().is_empty() {
+        Ok(parsed)
+    } else {
+        Err(anyhow!("unexpected text after the string"))
+    }
+}
+
+/// Write a value as a basic string the parser reads back
+fn quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t");
+    format!("\"{}\"", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hosts() {
+        let inventory = HostInventory::parse(
+            r#"
+# Lab machines
+[[host]]
+name = "Build agent"   # shown in the picker
+address = "build-01.corp.local"
+credential = 'CORP\svc-build'
+notes = "Restart the agent\nafter PATH changes"
+color = "blue"
+
+[[host]]
+address = "SRV01"
+"#,
+        )
+        .unwrap();
+        assert_eq!(inventory.hosts.len(), 2);
+        let build = &inventory.hosts[0];
+        assert_eq!(build.credential, r"CORP\svc-build");
+        assert_eq!(build.notes, "Restart the agent\nafter PATH changes");
+        assert_eq!(build.last_connected, None);
+        assert_eq!(inventory.hosts[1].name, "SRV01");
+        assert!(inventory.hosts[1].matches("srv01"));
+
+        assert_eq!(
+            HostInventory::parse("name = \"x\"")
+                .unwrap_err()
+                .to_string(),
+            "line 1: expected [[host]] first"
+        );
+        assert!(HostInventory::parse("[[host]]\nname = \"x").is_err());
+    }
+
+    #[test]
+    fn test_sessions_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hosts.toml");
+        let mut inventory = HostInventory::load(&path).unwrap();
+        assert!(inventory.hosts.is_empty());
+
+        inventory.hosts.push(Host {
+            notes: "Quote \"this\"\tand C:\\that".to_string(),
+            ..Host::new("SRV01")
+        });
+        inventory.record_session(
+            "srv01",
+            "3 entries, 1 dead".to_string(),
+            "today".to_string(),
+        );
+        inventory.record_session("SRV02", "no problems".to_string(), "now".to_string());
+        assert_eq!(inventory.hosts.len(), 2);
+        assert_eq!(inventory.hosts[0].last_connected.as_deref(), Some("today"));
+        assert_eq!(inventory.hosts[1].name, "SRV02");
+
+        inventory.save(&path).unwrap();
+        assert_eq!(HostInventory::load(&path).unwrap(), inventory);
+    }
+}
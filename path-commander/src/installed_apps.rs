@@ -0,0 +1,139 @@
+use anyhow::Result;
+
+use crate::path_analyzer::{comparable, expand_environment_variables};
+
+/// Where Windows keeps the uninstall data of installed programs
+#[cfg(windows)]
+const UNINSTALL_KEYS: [&str; 2] = [
+    "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall",
+    "SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall",
+];
+
+/// An installed program that recorded where it was installed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstalledApp {
+    pub name: String,
+    pub version: Option<String>,
+    /// InstallLocation from the uninstall data, expanded
+    pub location: String,
+}
+
+impl InstalledApp {
+    /// Name with the version, unless the name already carries it
+    pub fn label(&self) -> String {
+        match &self.version {
+            Some(version) if !version.is_empty() && !self.name.contains(version.as_str()) => {
+                format!("{} {}", self.name, version)
+            }
+            _ => self.name.clone(),
+        }
+    }
+}
+
+/// Programs installed in the entry's directory or one of its parents
+pub fn owners<'a>(apps: &'a [InstalledApp], entry: &str) -> Vec<&'a InstalledApp> {
+    let dir = comparable(&expand_environment_variables(entry));
+    if dir.is_empty() {
+        return Vec::new();
+    }
+
+    apps.iter()
+        .filter(|app| {
+            let location = comparable(&app.location);
+            // A drive root would claim every entry on the drive
+            !location.is_empty()
+                && !location.ends_with(':')
+                && (dir == location
+                    || dir
+                        .strip_prefix(location.as_str())
+                        .is_some_and(|rest| rest.starts_with('\\')))
+        })
+        .collect()
+}
+
+/// Installed programs with an InstallLocation, from the machine and user uninstall keys
+#[cfg(windows)]
+pub fn installed_apps() -> Result<Vec<InstalledApp>> {
+    use crate::registry::{string_value, subkey_names};
+    use windows::Win32::System::Registry::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE};
+
+    let mut apps: Vec<InstalledApp> = Vec::new();
+    let roots = [
+        (HKEY_LOCAL_MACHINE, UNINSTALL_KEYS[0]),
+        (HKEY_LOCAL_MACHINE, UNINSTALL_KEYS[1]),
+        (HKEY_CURRENT_USER, UNINSTALL_KEYS[0]),
+    ];
+    for (root, uninstall) in roots {
+        // The WOW6432Node key is missing on 32-bit Windows
+        let Ok(names) = subkey_names(root, uninstall) else {
+            continue;
+        };
+        for name in names {
+            let key = format!("{}\\{}", uninstall, name);
+            let Some(location) = string_value(root, &key, "InstallLocation") else {
+                continue;
+            };
+            let location = expand_environment_variables(location.trim().trim_matches('"'));
+            if location.is_empty() {
+                continue;
+            }
+            let app = InstalledApp {
+                name: string_value(root, &key, "DisplayName").unwrap_or(name),
+                version: string_value(root, &key, "DisplayVersion"),
+                location,
+            };
+            // 32- and 64-bit keys often describe the same program
+            if !apps.contains(&app) {
+                apps.push(app);
+            }
+        }
+    }
+    Ok(apps)
+}
+
+/// Only Windows keeps uninstall data in the registry
+#[cfg(not(windows))]
+pub fn installed_apps() -> Result<Vec<InstalledApp>> {
+    Ok(Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(name: &str, version: Option<&str>, location: &str) -> InstalledApp {
+        InstalledApp {
+            name: name.to_string(),
+            version: version.map(str::to_string),
+            location: location.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_owners_of_entry() {
+        let apps = vec![
+            app("Node.js", Some("20.1.0"), "C:\\Program Files\\nodejs\\"),
+            app("Git", Some("2.45.1"), "C:\\Program Files\\Git"),
+            app("Git LFS", None, "C:\\Program Files\\Git LFS"),
+            app("Everything on C", None, "C:\\"),
+        ];
+
+        let names =
+            |entry| -> Vec<String> { owners(&apps, entry).iter().map(|a| a.label()).collect() };
+        assert_eq!(names("c:\\program files\\nodejs"), ["Node.js 20.1.0"]);
+        // Entries below the install location belong to the program too
+        assert_eq!(names("C:\\Program Files\\Git\\cmd\\"), ["Git 2.45.1"]);
+        assert_eq!(names("C:\\Program Files\\Git LFS"), ["Git LFS"]);
+        assert!(names("C:\\Tools").is_empty());
+        assert!(names("").is_empty());
+    }
+
+    #[test]
+    fn test_label_does_not_repeat_version() {
+        assert_eq!(
+            app("Python 3.12.1 (64-bit)", Some("3.12.1"), "C:\\Python312").label(),
+            "Python 3.12.1 (64-bit)"
+        );
+        assert_eq!(app("Go", Some(""), "C:\\Go").label(), "Go");
+    }
+}
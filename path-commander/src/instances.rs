@@ -0,0 +1,337 @@
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// What a running Path Commander tells the others, one `<pid>.json` per instance
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Instance {
+    pub pid: u32,
+    /// RFC 3339, sorts in start order
+    pub started: String,
+    pub elevated: bool,
+    /// Computer the instance is connected to, None for a local session
+    #[serde(default)]
+    pub remote: Option<String>,
+    /// Edits staged but not applied yet
+    #[serde(default)]
+    pub pending: bool,
+}
+
+impl Instance {
+    /// "PID 4120 (elevated, connected to SRV01)"
+    pub fn describe(&self) -> String {
+        let mut notes = Vec::new();
+        if self.elevated {
+            notes.push("elevated".to_string());
+        }
+        if let Some(ref host) = self.remote {
+            notes.push(format!("connected to {}", host));
+        }
+        if notes.is_empty() {
+            format!("PID {}", self.pid)
+        } else {
+            format!("PID {} ({})", self.pid, notes.join(", "))
+        }
+    }
+}
+
+/// This instance's entry in the instances folder, removed again when dropped
+pub struct Registration {
+    dir: PathBuf,
+    current: Instance,
+}
+
+impl Registration {
+    /// Announce this process to other instances sharing the folder
+    pub fn register(dir: &Path, elevated: bool) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let registration = Self {
+            dir: dir.to_path_buf(),
+            current: Instance {
+                pid: std::process::id(),
+                started: Local::now().to_rfc3339(),
+                elevated,
+                remote: None,
+                pending: false,
+            },
+        };
+        registration.write()?;
+        Ok(registration)
+    }
+
+    fn file(&self) -> PathBuf {
+        self.dir.join(format!("{}.json", self.current.pid))
+    }
+
+    fn write(&self) -> Result<()> {
+        let json = serde_json::to_string(&self.current)?;
+        std::fs::write(self.file(), json)
+            .with_context(|| format!("Failed to write {}", self.file().display()))
+    }
+
+    /// Publish whether edits are pending and where we are connected, only writes on change
+    pub fn update(&mut self, pending: bool, remote: Option<&str>) {
+        let remote = remote.map(str::to_string);
+        if self.current.pending == pending && self.current.remote == remote {
+            return;
+        }
+        self.current.pending = pending;
+        self.current.remote = remote;
+        // Advisory only, the next change tries again
+        let _ = self.write();
+    }
+
+    /// Other instances still running, files left behind by crashed ones are removed
+    pub fn others(&self) -> Vec<Instance> {
+        self.others_where(is_running)
+    }
+
+    fn others_where(&self, alive: fn(u32) -> bool) -> Vec<Instance> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut others: Vec<Instance> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                let instance: Instance =
+                    serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+                if instance.pid == self.current.pid {
+                    return None;
+                }
+                if !alive(instance.pid) {
+                    let _ = std::fs::remove_file(&path);
+                    return None;
+                }
+                Some(instance)
+            })
+            .collect();
+        others.sort_by(|a, b| a.started.cmp(&b.started));
+        others
+    }
+
+    /// Hold the apply lock while writing PATH, fails if another running instance holds it
+    /// or the lock file can't be created
+    pub fn lock_apply(&self) -> Result<ApplyLock> {
+        ApplyLock::acquire(&self.dir, self.current.pid, is_running)
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(self.file());
+    }
+}
+
+/// How long a lock file without a pid counts as held, its owner may not have written it yet
+const UNWRITTEN_LOCK_GRACE: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// `apply.lock` holding the pid of the instance writing PATH right now
+pub struct ApplyLock {
+    path: PathBuf,
+}
+
+impl ApplyLock {
+    fn acquire(dir: &Path, pid: u32, alive: fn(u32) -> bool) -> Result<Self> {
+        let path = dir.join("apply.lock");
+        // Once for a free lock, once more after clearing a stale one
+        for _ in 0..2 {
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(mut file) => {
+                    let _ = write!(file, "{}", pid);
+                    return Ok(Self { path });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let holder = std::fs::read_to_string(&path)
+                        .ok()
+                        .and_then(|text| text.trim().parse::<u32>().ok());
+                    match holder {
+                        Some(holder) if holder != pid && alive(holder) => {
+                            bail!(
+                                "Path Commander PID {} is applying changes right now",
+                                holder
+                            )
+                        }
+                        // Just created, the owner is about to write its pid
+                        None if !Self::is_old(&path) => {
+                            bail!("Another Path Commander is applying changes right now")
+                        }
+                        // Left behind by a crash, or unreadable for longer than the grace
+                        _ => {
+                            let _ = std::fs::remove_file(&path);
+                        }
+                    }
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Can't create {}", path.display()))
+                }
+            }
+        }
+        bail!("Another Path Commander keeps taking the apply lock")
+    }
+
+    /// Whether the lock file was last written longer ago than the grace,
+    /// a file that can't be checked counts as old so it can't block applies for good
+    fn is_old(path: &Path) -> bool {
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_none_or(|age| age >= UNWRITTEN_LOCK_GRACE)
+    }
+}
+
+impl Drop for ApplyLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Whether a process is still running
+#[cfg(windows)]
+pub fn is_running(pid: u32) -> bool {
+    use windows::Win32::Foundation::{CloseHandle, ERROR_ACCESS_DENIED};
+    use windows::Win32::System::Threading::{
+        GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    const STILL_ACTIVE: u32 = 259;
+
+    unsafe {
+        match OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) {
+            Ok(process) => {
+                let mut code = 0u32;
+                let running =
+                    GetExitCodeProcess(process, &mut code).is_ok() && code == STILL_ACTIVE;
+                let _ = CloseHandle(process);
+                running
+            }
+            // An instance running under another account can't be opened, but it exists
+            Err(e) => e.code() == ERROR_ACCESS_DENIED.to_hresult(),
+        }
+    }
+}
+
+/// Whether a process is still running
+#[cfg(target_os = "linux")]
+pub fn is_running(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+/// Whether a process is still running, other platforms can't tell and keep the file
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn is_running(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OTHER: u32 = 4_000_000;
+    const CRASHED: u32 = 4_000_001;
+
+    fn alive(pid: u32) -> bool {
+        pid != CRASHED
+    }
+
+    fn instance(pid: u32, pending: bool) -> Instance {
+        Instance {
+            pid,
+            started: Local::now().to_rfc3339(),
+            elevated: true,
+            remote: None,
+            pending,
+        }
+    }
+
+    #[test]
+    fn test_registration_lists_live_others() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut registration = Registration::register(dir.path(), false).unwrap();
+        let own = dir.path().join(format!("{}.json", std::process::id()));
+        assert!(own.exists());
+
+        for other in [instance(OTHER, true), instance(CRASHED, false)] {
+            std::fs::write(
+                dir.path().join(format!("{}.json", other.pid)),
+                serde_json::to_string(&other).unwrap(),
+            )
+            .unwrap();
+        }
+        let others = registration.others_where(alive);
+        assert_eq!(others.len(), 1);
+        assert!(others[0].pending);
+        assert_eq!(others[0].describe(), format!("PID {} (elevated)", OTHER));
+        // The crashed instance's file is cleaned up
+        assert!(!dir.path().join(format!("{}.json", CRASHED)).exists());
+
+        registration.update(true, Some("SRV01"));
+        let saved: Instance =
+            serde_json::from_str(&std::fs::read_to_string(&own).unwrap()).unwrap();
+        assert!(saved.pending);
+        assert_eq!(saved.remote.as_deref(), Some("SRV01"));
+
+        drop(registration);
+        assert!(!own.exists());
+    }
+
+    #[test]
+    fn test_apply_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("apply.lock");
+
+        let lock = ApplyLock::acquire(dir.path(), 1, alive).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "1");
+        let error = ApplyLock::acquire(dir.path(), 2, alive).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Path Commander PID 1 is applying changes right now"
+        );
+        drop(lock);
+        assert!(!path.exists());
+
+        // A lock left behind by a crashed instance is taken over
+        std::fs::write(&path, CRASHED.to_string()).unwrap();
+        let lock = ApplyLock::acquire(dir.path(), 2, alive).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "2");
+        drop(lock);
+    }
+
+    #[test]
+    fn test_apply_lock_without_pid() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("apply.lock");
+
+        // Another instance created the file and hasn't written its pid yet
+        std::fs::write(&path, "").unwrap();
+        let error = ApplyLock::acquire(dir.path(), 2, alive).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Another Path Commander is applying changes right now"
+        );
+        assert!(path.exists());
+
+        // Still without a pid after the grace, the owner is gone
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(std::time::SystemTime::now() - UNWRITTEN_LOCK_GRACE)
+            .unwrap();
+        drop(file);
+        let _lock = ApplyLock::acquire(dir.path(), 2, alive).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "2");
+    }
+
+    #[test]
+    fn test_apply_lock_refuses_when_it_cant_be_created() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing");
+        assert!(ApplyLock::acquire(&missing, 2, alive).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use crate::path_analyzer::{comparable, expand_environment_variables};
+use crate::registry;
+
+/// PATH as running programs see it, as opposed to what is saved in the registry
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LiveEnvironment {
+    /// PATH this pc process inherited from the terminal that started it
+    pub process: Vec<String>,
+    /// HKCU\Volatile Environment PATH, None when the logon session sets none
+    pub volatile: Option<Vec<String>>,
+}
+
+impl LiveEnvironment {
+    /// Read the PATH of this process and of the logon session
+    pub fn capture() -> Self {
+        let process = std::env::var_os("PATH")
+            .map(|path| {
+                std::env::split_paths(&path)
+                    .map(|entry| entry.to_string_lossy().into_owned())
+                    .filter(|entry| !entry.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        // An unreadable key is treated like a session without a volatile PATH
+        let volatile = registry::read_volatile_path()
+            .ok()
+            .flatten()
+            .map(|path| registry::parse_path(&path));
+        Self { process, volatile }
+    }
+}
+
+/// Entries of `live` that none of the `saved` entries expand to
+pub fn missing_from<'a>(live: &'a [String], saved: &[&String]) -> Vec<&'a String> {
+    let saved: Vec<String> = saved.iter().map(|entry| expanded(entry)).collect();
+    let mut missing: Vec<&String> = Vec::new();
+    for entry in live {
+        let key = expanded(entry);
+        if !saved.contains(&key) && !missing.iter().any(|m| expanded(m) == key) {
+            missing.push(entry);
+        }
+    }
+    missing
+}
+
+/// Saved entries that a live PATH does not contain
+pub fn not_in<'a>(saved: &[&'a String], live: &[String]) -> Vec<&'a String> {
+    let live: Vec<String> = live.iter().map(|entry| expanded(entry)).collect();
+    saved
+        .iter()
+        .copied()
+        .filter(|entry| !live.contains(&expanded(entry)))
+        .collect()
+}
+
+/// Expanded form that compares by value
+/// Live PATH values are already expanded while saved ones usually are not
+fn expanded(entry: &str) -> String {
+    comparable(&expand_environment_variables(entry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_from_saved() {
+        let saved_tools = r#""C:\Tools\bin""#.to_string();
+        let saved_windows = r"C:\Windows".to_string();
+        let saved = [&saved_tools, &saved_windows];
+        let live = vec![
+            r"c:\windows\".to_string(),
+            r"C:\Tools\bin".to_string(),
+            r"C:\Terminal\bin".to_string(),
+            r"C:\TERMINAL\bin".to_string(),
+        ];
+
+        // Quoted, differently cased and repeated entries still match
+        assert_eq!(missing_from(&live, &saved), vec![r"C:\Terminal\bin"]);
+        assert!(not_in(&saved, &live).is_empty());
+        assert_eq!(not_in(&saved, &live[..1]), vec![&saved_tools]);
+    }
+}
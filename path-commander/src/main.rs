@@ -1,20 +1,60 @@
+mod aging;
 mod app;
 mod backup;
+mod change_report;
+mod cheat_sheet;
+mod cli;
+mod command_lookup;
+mod completions;
 mod config;
+mod control;
+mod digest;
+mod disk_usage;
+mod drive_info;
 mod elevation;
+mod event_log;
+mod filesystem;
+mod history;
+mod hosts;
+mod installed_apps;
+mod instances;
+mod live_env;
 mod menu;
+mod onedrive;
 mod path_analyzer;
+#[cfg(not(windows))]
+mod path_files;
+mod path_limits;
 mod permissions;
+mod policy;
+mod preflight;
 mod process_detector;
+mod profile;
+mod raw_editor;
 mod registry;
+mod release_notes;
+mod remote_queue;
+mod scheduler;
+mod script;
+mod security_scan;
+mod services;
+mod shell_integration;
+mod shims;
+mod task;
+mod template;
 mod theme;
+mod toolchains;
 mod ui;
+mod updates;
+mod usage;
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{CommandFactory, Parser, Subcommand, ValueHint};
+use clap_complete::engine::ArgValueCandidates;
+use clap_complete::CompleteEnv;
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
     },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
@@ -24,6 +64,8 @@ use std::io;
 use std::path::PathBuf;
 
 use app::App;
+use registry::PathScope;
+use scheduler::RenderScheduler;
 use theme::Theme;
 use ui::UI;
 
@@ -32,34 +74,211 @@ use ui::UI;
 #[command(name = "pc")]
 #[command(about = "A TUI for managing Windows PATH environment variables", long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Print plain lines without colors instead of starting the TUI
+    /// (automatic when output is piped or TERM=dumb)
+    #[arg(long, global = true)]
+    plain: bool,
+
     /// Theme to use (built-in: default, dracula) or path to .ini skin file
-    #[arg(short, long)]
+    #[arg(short, long, add = ArgValueCandidates::new(completions::themes))]
     theme: Option<String>,
 
     /// Connect to remote computer (hostname or IP address)
     #[arg(short, long)]
     remote: Option<String>,
 
+    /// Start on this scope and add new paths to it: user or machine (overrides config.ini)
+    #[arg(long, value_name = "SCOPE", value_parser = parse_scope, add = ArgValueCandidates::new(completions::scopes))]
+    scope: Option<PathScope>,
+
+    /// Expand %USERPROFILE%, %APPDATA% and other per-user variables for this profile
+    /// directory instead of your own, e.g. C:\Users\alice (overrides config.ini)
+    #[arg(long, value_name = "DIR", value_hint = ValueHint::DirPath)]
+    profile_root: Option<String>,
+
+    /// Limit redraws to this many frames per second (0 = no limit), e.g. 15 over slow RDP links
+    #[arg(long, value_name = "FPS")]
+    max_fps: Option<u32>,
+
+    /// Edit PATH as plain text without looking at any directory, for machines where a hung
+    /// network drive keeps pc from starting (every entry is shown as not checked)
+    #[arg(long, global = true)]
+    no_fs_checks: bool,
+
+    /// Open with the Add Path dialog filled in with this directory, e.g. from an installer
+    #[arg(long, value_name = "DIR", value_hint = ValueHint::DirPath, conflicts_with_all = ["remote", "prune_dead", "dedupe"])]
+    add: Option<String>,
+
+    /// Open with the dead entries marked and their delete confirmation shown
+    #[arg(long, conflicts_with_all = ["remote", "dedupe"])]
+    prune_dead: bool,
+
+    /// Open with the duplicate entries marked and their delete confirmation shown
+    #[arg(long, conflicts_with = "remote")]
+    dedupe: bool,
+
+    /// Limit this session to a profile's features: helpdesk (read-only, diagnostics),
+    /// auditor (read-only, reports), admin (everything) or one from config.ini
+    #[arg(long, value_name = "NAME", add = ArgValueCandidates::new(completions::profiles))]
+    profile: Option<String>,
+
+    /// Run the actions in a script file instead of reading the keyboard, then exit
+    /// (for end-to-end tests and demo recordings)
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    script: Option<PathBuf>,
+
+    /// Write the MACHINE entries in a payload file and exit (internal use only, runs elevated)
+    #[arg(long, hide = true)]
+    apply_machine: Option<PathBuf>,
+
+    /// SHA-256 the payload of --apply-machine must have (internal use only)
+    #[arg(long, hide = true, requires = "apply_machine")]
+    payload_sha256: Option<String>,
+
     /// Restore from elevated state file (internal use only)
     #[arg(long, hide = true)]
     restore_state: Option<PathBuf>,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print every PATH entry with its position and status, then exit
+    List {
+        /// Only this scope: user or machine
+        #[arg(long, value_name = "SCOPE", value_parser = parse_scope, add = ArgValueCandidates::new(completions::scopes))]
+        scope: Option<PathScope>,
+    },
+    /// Print the PATH a new terminal would get as a statement for your shell, to refresh
+    /// the current session after an apply, e.g. pc env | Invoke-Expression
+    Env {
+        /// Shell to write for, bash if SHELL is set and powershell otherwise
+        #[arg(value_enum)]
+        shell: Option<cli::EnvShell>,
+    },
+    /// Print a shell wrapper that starts pc and reloads PATH into the current session
+    /// after an apply
+    RefreshHook {
+        #[arg(value_enum)]
+        shell: cli::EnvShell,
+    },
+    /// Print a tab-completion script for a shell
+    Completions {
+        #[arg(value_enum)]
+        shell: completions::Shell,
+    },
+    /// Add or remove "Add to PATH with Path Commander" in Explorer's folder context menu
+    ContextMenu {
+        #[arg(value_enum)]
+        action: shell_integration::Action,
+        /// user (default) for you only, machine for all users (needs administrator rights)
+        #[arg(long, value_name = "SCOPE", value_parser = parse_scope, add = ArgValueCandidates::new(completions::scopes))]
+        scope: Option<PathScope>,
+    },
+    /// Print a PowerShell profile hook that logs which PATH directory each command runs
+    /// from, for the reorder suggestions (nothing is recorded without it)
+    UsageHook,
+}
+
+fn parse_scope(value: &str) -> Result<PathScope, String> {
+    PathScope::parse(value).ok_or_else(|| format!("expected user or machine, got '{}'", value))
+}
+
 fn main() -> Result<()> {
-    // Check if running on Windows
-    #[cfg(not(target_os = "windows"))]
-    {
-        eprintln!("This application only runs on Windows.");
-        std::process::exit(1);
-    }
+    // Tab completion: the shell scripts run pc with COMPLETE set and read the candidates
+    CompleteEnv::with_factory(Args::command)
+        .var(completions::COMPLETE_VAR)
+        .complete();
 
     // Parse command-line arguments
     let args = Args::parse();
 
+    // Elevated write helper started by an unelevated session, no TUI
+    if let Some(ref payload) = args.apply_machine {
+        std::process::exit(elevation::run_machine_helper(
+            payload,
+            args.payload_sha256.as_deref().unwrap_or_default(),
+        ));
+    }
+
+    match args.command {
+        Some(Command::Completions { shell }) => {
+            print!("{}", completions::generate(shell)?);
+            return Ok(());
+        }
+        Some(Command::Env { shell }) => {
+            let shell = shell.unwrap_or_else(cli::EnvShell::detect);
+            return cli::run_env(registry::local_backend().as_ref(), shell);
+        }
+        Some(Command::RefreshHook { shell }) => {
+            print!("{}", cli::refresh_hook(shell));
+            return Ok(());
+        }
+        Some(Command::UsageHook) => {
+            print!("{}", usage::powershell_hook(&config::get_usage_file()?));
+            return Ok(());
+        }
+        Some(Command::ContextMenu { action, scope }) => {
+            return shell_integration::run(action, scope.unwrap_or(PathScope::User));
+        }
+        _ => {}
+    }
+
+    // Line-oriented output for pipes, CI logs and dumb terminals, no TUI
+    let list_scope = match args.command {
+        Some(Command::List { scope }) => Some(scope),
+        Some(_) => None,
+        None if args.script.is_none()
+            && args.restore_state.is_none()
+            && (args.plain || !cli::rich_terminal()) =>
+        {
+            Some(args.scope)
+        }
+        None => None,
+    };
+    if let Some(scope) = list_scope {
+        let mut settings = config::Settings::load_default();
+        if args.profile_root.is_some() {
+            settings.profile_root = args.profile_root.clone();
+        }
+        let style = cli::OutputStyle::detect(args.plain);
+        return cli::run_list(
+            registry::local_backend().as_ref(),
+            scope,
+            &settings,
+            &*filesystem::for_session(args.no_fs_checks),
+            style,
+        );
+    }
+
     // Initialize config directories
     config::ensure_config_dirs()?;
     config::migrate_backups().ok(); // Don't fail if migration fails
 
+    // An update downloaded last session replaces this executable, which then runs it instead
+    // Never from an elevated process: whatever is staged in the user's profile would then be
+    // written where only administrators can write, it waits for the next normal start
+    if args.restore_state.is_none()
+        && args.script.is_none()
+        && config::Settings::load_default().check_updates
+        && !permissions::is_admin()
+    {
+        let exe = std::env::current_exe()?;
+        match config::get_update_dir().and_then(|staging| updates::install_staged(&staging, &exe)) {
+            Ok(Some(version)) => {
+                eprintln!("Updated Path Commander to v{}", version);
+                let status = std::process::Command::new(&exe)
+                    .args(std::env::args_os().skip(1))
+                    .status()?;
+                std::process::exit(status.code().unwrap_or(1));
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Could not install the downloaded update: {}", e),
+        }
+    }
+
     // Check if restoring from elevation state
     let elevation_state = if let Some(ref state_file) = args.restore_state {
         Some(elevation::ElevationState::load(state_file)?)
@@ -67,6 +286,30 @@ fn main() -> Result<()> {
         None
     };
 
+    // An unknown profile or a flag it does not allow ends here, before taking over the terminal
+    let profile = args
+        .profile
+        .as_deref()
+        .map(|name| profile::Profile::resolve(name, &config::Settings::load_default()))
+        .transpose()?;
+    if let Some(ref profile) = profile {
+        if args.remote.is_some() && !profile.allows(profile::Feature::Remote) {
+            anyhow::bail!("{}", profile.denied(profile::Feature::Remote));
+        }
+        if (args.add.is_some() || args.prune_dead || args.dedupe)
+            && !profile.allows(profile::Feature::Edit)
+        {
+            anyhow::bail!("{}", profile.denied(profile::Feature::Edit));
+        }
+    }
+
+    // Parse the script up front so mistakes are reported before taking over the terminal
+    let script = args
+        .script
+        .as_deref()
+        .map(script::load_script)
+        .transpose()?;
+
     // Load theme
     let theme = if let Some(theme_name) = args.theme.as_ref() {
         // Check if it's a file path
@@ -90,6 +333,8 @@ fn main() -> Result<()> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    // Pastes arrive as one event instead of keystrokes; the legacy Windows console has no such mode
+    let _ = execute!(stdout, EnableBracketedPaste);
 
     // Small delay to ensure terminal is fully ready on Windows
     std::thread::sleep(std::time::Duration::from_millis(50));
@@ -98,32 +343,79 @@ fn main() -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // Create app state
+    let restarted_elevated = elevation_state.is_some();
     let mut app = if let Some(state) = elevation_state {
         // Restore from elevation state
         App::from_elevation_state(theme, state)?
-    } else if let Some(remote) = args.remote {
-        // Connect to remote computer
-        match App::new_with_remote(theme, args.theme.clone(), &remote) {
-            Ok(app) => app,
-            Err(e) => {
-                // Restore terminal before showing error
-                disable_raw_mode()?;
-                execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
-                eprintln!("Failed to connect to remote computer '{}': {:?}", remote, e);
-                std::process::exit(1);
-            }
-        }
+    } else if args.remote.is_some() {
+        // Remote mode starts local, the connection is made once the screen is up
+        App::new(
+            theme,
+            args.theme.clone(),
+            filesystem::for_session(args.no_fs_checks),
+        )?
     } else {
         // Normal local mode
-        App::new(theme, args.theme.clone())?
+        let mut app = App::new(
+            theme,
+            args.theme.clone(),
+            filesystem::for_session(args.no_fs_checks),
+        )?;
+        if let Some(scope) = args.scope {
+            app.use_scope(scope);
+        }
+        app
     };
+    if let Some(ref profile_root) = args.profile_root {
+        app.use_profile_root(profile_root);
+    }
+    if let Some(profile) = profile {
+        app.profile = profile;
+    }
+    // A failed connection offers to retry instead of ending the program
+    if let Some(ref remote) = args.remote {
+        app.start_remote_connect(remote);
+    }
+    let flow = match (args.add, args.prune_dead, args.dedupe) {
+        (Some(dir), _, _) => Some(app::StartupFlow::Add(dir)),
+        (None, true, _) => Some(app::StartupFlow::PruneDead),
+        (None, false, true) => Some(app::StartupFlow::Dedupe),
+        (None, false, false) => None,
+    };
+    // Scripts expect the main screen, and an elevated restart already saw the notes
+    if script.is_none() && !restarted_elevated {
+        // The notes wait for another start when a flag opened a workflow
+        if flow.is_none() {
+            app.show_whats_new_once();
+        }
+        // Opt-in, nothing goes over the network unless [updates] check is set
+        if app.settings.check_updates {
+            app.check_for_updates(true);
+        }
+    }
+    if let Some(flow) = flow {
+        app.start_flow(flow);
+    }
+    if script.is_none() && app.settings.control_pipe {
+        app.start_control_pipe();
+    }
+    // Scripts run unattended, only interactive sessions tell each other about their edits
+    if script.is_none() {
+        app.register_instance();
+    }
     let mut ui = UI::new();
 
     // Main loop
-    let result = run_app(&mut terminal, &mut app, &mut ui);
+    let result = match script {
+        Some(steps) => script::run_script(&mut terminal, &mut app, &ui, &steps),
+        None => run_app(&mut terminal, &mut app, &mut ui, args.max_fps),
+    };
+    // Leave a RemoteRegistry service started only for this session stopped again
+    app.restore_remote_registry();
 
     // Restore terminal
     disable_raw_mode()?;
+    let _ = execute!(terminal.backend_mut(), DisableBracketedPaste);
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
@@ -133,15 +425,23 @@ fn main() -> Result<()> {
 
     if let Err(err) = result {
         eprintln!("Error: {:?}", err);
+        // Let scripted runs fail a CI job
+        if args.script.is_some() {
+            std::process::exit(1);
+        }
     }
 
     Ok(())
 }
 
+/// How often to re-check entries on removable media while idle
+const MEDIA_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
 fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
     ui: &mut UI,
+    max_fps: Option<u32>,
 ) -> Result<()> {
     // Initial render to show the UI immediately
     terminal.draw(|f| ui.render(f, app))?;
@@ -155,8 +455,14 @@ fn run_app<B: ratatui::backend::Backend>(
         }
     }
 
+    let mut scheduler = RenderScheduler::new(max_fps);
     loop {
-        terminal.draw(|f| ui.render(f, app))?;
+        // Only redraw when something changed (and the frame cap allows it)
+        let now = std::time::Instant::now();
+        if scheduler.should_draw(now) {
+            terminal.draw(|f| ui.render(f, app))?;
+            scheduler.frame_drawn(now);
+        }
 
         // Check if app wants to exit
         if app.should_exit {
@@ -164,43 +470,29 @@ fn run_app<B: ratatui::backend::Backend>(
         }
 
         // Update viewport height for PGUP/PGDOWN navigation
-        let terminal_height = terminal.size()?.height;
-        app.update_viewport_height(terminal_height);
-
-        match event::read()? {
-            Event::Key(key) => {
-                // Filter duplicate events using KeyEventKind (Windows sends both Press and Release)
-                // Only process Press events to avoid double-handling the same keystroke
-                // This is the standard approach recommended by ratatui/crossterm documentation
-                if key.kind == KeyEventKind::Press {
-                    // Global shortcuts
-                    match (key.code, key.modifiers) {
-                        (KeyCode::Char('c'), KeyModifiers::CONTROL) => break,
-                        (KeyCode::F(10), _) => {
-                            // F10 with double-tap detection for quick exit
-                            // Only handle in Normal mode; let confirm dialog handle it when in Exit confirmation
-                            if !matches!(app.mode, app::Mode::Confirm(app::ConfirmAction::Exit)) {
-                                app.handle_f10_press();
-                            } else {
-                                // In Exit confirmation dialog - pass to dialog handler
-                                app.handle_input(key)?;
-                            }
-                        }
-                        _ => {
-                            // Handle input in app
-                            app.handle_input(key)?;
-                        }
-                    }
-                }
-            }
-            Event::Mouse(mouse) => {
-                // Handle mouse events (clicks, scrolling)
-                let size = terminal.size()?;
-                let rect = ratatui::layout::Rect::new(0, 0, size.width, size.height);
-                app.handle_mouse(mouse, rect)?;
-            }
-            _ => {}
+        let size = terminal.size()?;
+        app.update_viewport_height(size.height);
+
+        // Tick fast while a spinner is animating, otherwise just often enough to
+        // pick up inserted/ejected removable media without a keypress
+        let tick = if app.busy_task().is_some() {
+            task::SPINNER_INTERVAL
+        } else {
+            MEDIA_CHECK_INTERVAL
+        };
+        let now = std::time::Instant::now();
+        if scheduler.tick_due(now, tick) && app.on_tick() {
+            scheduler.mark_dirty();
+        }
+
+        if !event::poll(scheduler.poll_timeout(std::time::Instant::now(), tick))? {
+            continue;
         }
+
+        let rect = ratatui::layout::Rect::new(0, 0, size.width, size.height);
+        app.handle_event(event::read()?, rect)?;
+        // Any event (including a resize) may change what's on screen
+        scheduler.mark_dirty();
     }
 
     Ok(())
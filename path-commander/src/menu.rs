@@ -1,3 +1,7 @@
+use ratatui::layout::Rect;
+
+use crate::profile::{Feature, Profile};
+
 /// Menu system for Path Commander
 /// Provides drop-down menus similar to Midnight Commander
 
@@ -7,26 +11,80 @@ pub struct MenuItem {
     pub shortcut: Option<String>,
     pub action: MenuAction,
     pub enabled: bool,
+    /// Items of the submenu this item opens, empty for a plain item
+    pub submenu: Vec<MenuItem>,
+    /// Underlined letter that runs the item while its dropdown is open
+    pub accelerator: Option<char>,
+}
+
+impl MenuItem {
+    pub fn new(label: &str, shortcut: Option<&str>, action: MenuAction) -> Self {
+        Self {
+            label: label.to_string(),
+            shortcut: shortcut.map(|s| s.to_string()),
+            action,
+            enabled: true,
+            submenu: Vec::new(),
+            accelerator: None,
+        }
+    }
+
+    /// Byte range of the accelerator in the label, for underlining it
+    pub fn accelerator_range(&self) -> Option<std::ops::Range<usize>> {
+        let accelerator = self.accelerator?;
+        accelerator_candidates(&self.label)
+            .find(|(_, c)| c.to_ascii_lowercase() == accelerator)
+            .map(|(pos, c)| pos..pos + c.len_utf8())
+    }
+
+    pub fn has_submenu(&self) -> bool {
+        !self.submenu.is_empty()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MenuAction {
+    /// Opens the item's submenu
+    Submenu,
+
     // File menu
     RunAsAdministrator,
+    /// Connect to the host at this position in the recent remotes
+    ConnectRecent(usize),
+    /// Placeholder shown while there are no recent remotes
+    NoRecentRemotes,
     Exit,
 
     // Command menu
     AddPath,
     EditPath,
+    PathDetails,
     DeleteMarked,
     MarkItem,
+    MarkAllVisible,
+    MarkAllDead,
+    MarkAllDuplicates,
+    MarkAllNonNormalized,
     UnmarkAll,
     MoveMarked,
+    CopyMarked,
+    PromoteEntry,
     MoveItemUp,
     NormalizeSelected,
+    CleanMalformed,
     DeleteAllDead,
     DeleteAllDuplicates,
     CreateMarkedDirectories,
+    SecurityScan,
+    ShimCheck,
+    Toolchains,
+    AgingReport,
+    UsageSuggestions,
+    MergeDuplicates,
+    NestedEntries,
+    BatchReplace,
+    EditRawValue,
+    DriveMigration,
 
     // Options menu
     SelectTheme,
@@ -35,12 +93,65 @@ pub enum MenuAction {
     DisconnectRemote,
     CreateBackup,
     RestoreBackup,
+    RevertLastApply,
+    ApplyHistory,
+    ChangeReport,
+    IgnoreList,
+    ToggleQuickActions,
 
     // Help menu
     KeyboardShortcuts,
+    ExportShortcuts,
+    PathPrecedence,
+    PathLengths,
+    WhereCommand,
+    LiveEnvironment,
+    MessageLog,
+    WhatsNew,
+    CheckUpdates,
+    InstallUpdate,
     About,
 }
 
+impl MenuAction {
+    /// Feature of a launch profile the item belongs to, None if every profile has it
+    pub fn feature(self) -> Option<Feature> {
+        match self {
+            MenuAction::RunAsAdministrator
+            | MenuAction::AddPath
+            | MenuAction::EditPath
+            | MenuAction::DeleteMarked
+            | MenuAction::MoveMarked
+            | MenuAction::CopyMarked
+            | MenuAction::PromoteEntry
+            | MenuAction::MoveItemUp
+            | MenuAction::NormalizeSelected
+            | MenuAction::CleanMalformed
+            | MenuAction::DeleteAllDead
+            | MenuAction::DeleteAllDuplicates
+            | MenuAction::CreateMarkedDirectories
+            | MenuAction::MergeDuplicates
+            | MenuAction::NestedEntries
+            | MenuAction::BatchReplace
+            | MenuAction::EditRawValue
+            | MenuAction::DriveMigration
+            | MenuAction::RevertLastApply
+            | MenuAction::IgnoreList => Some(Feature::Edit),
+            MenuAction::SecurityScan
+            | MenuAction::ShimCheck
+            | MenuAction::Toolchains
+            | MenuAction::AgingReport
+            | MenuAction::UsageSuggestions
+            | MenuAction::WhereCommand
+            | MenuAction::LiveEnvironment => Some(Feature::Doctor),
+            MenuAction::ApplyHistory | MenuAction::ChangeReport => Some(Feature::Reports),
+            MenuAction::ConnectRemote | MenuAction::ConnectRecent(_) => Some(Feature::Remote),
+            MenuAction::CreateBackup | MenuAction::RestoreBackup => Some(Feature::Backup),
+            _ => None,
+        }
+    }
+}
+
 pub struct Menu {
     pub name: String,
     pub accelerator: char, // The letter that activates this menu with Alt
@@ -57,17 +168,22 @@ impl Menu {
     }
 
     pub fn add_item(&mut self, label: &str, shortcut: Option<&str>, action: MenuAction) {
-        self.items.push(MenuItem {
-            label: label.to_string(),
-            shortcut: shortcut.map(|s| s.to_string()),
-            action,
-            enabled: true,
-        });
+        self.items.push(MenuItem::new(label, shortcut, action));
+    }
+
+    pub fn add_submenu(&mut self, label: &str, items: Vec<MenuItem>) {
+        let mut item = MenuItem::new(label, Some("▶"), MenuAction::Submenu);
+        item.submenu = items;
+        self.items.push(item);
     }
 }
 
 /// Get all menus for the application
-pub fn get_menus(connection_mode: crate::app::ConnectionMode) -> Vec<Menu> {
+/// `recent_remotes` fills File → Recent Remotes, most recent first
+pub fn get_menus(
+    connection_mode: crate::app::ConnectionMode,
+    recent_remotes: &[String],
+) -> Vec<Menu> {
     let mut menus = Vec::new();
 
     // File menu
@@ -77,6 +193,20 @@ pub fn get_menus(connection_mode: crate::app::ConnectionMode) -> Vec<Menu> {
         Some("Ctrl+E"),
         MenuAction::RunAsAdministrator,
     );
+    let mut recent: Vec<MenuItem> = recent_remotes
+        .iter()
+        .enumerate()
+        .map(|(i, host)| MenuItem::new(host, None, MenuAction::ConnectRecent(i)))
+        .collect();
+    if recent.is_empty() {
+        recent.push(MenuItem::new(
+            "(none yet)",
+            None,
+            MenuAction::NoRecentRemotes,
+        ));
+    }
+    file_menu.add_item("Connect to...", Some("Ctrl+O"), MenuAction::ConnectRemote);
+    file_menu.add_submenu("Recent Remotes", recent);
     file_menu.add_item("Exit", Some("F10"), MenuAction::Exit);
     menus.push(file_menu);
 
@@ -84,9 +214,27 @@ pub fn get_menus(connection_mode: crate::app::ConnectionMode) -> Vec<Menu> {
     let mut command_menu = Menu::new("Command", 'c');
     command_menu.add_item("Add Path", Some("F4"), MenuAction::AddPath);
     command_menu.add_item("Edit Path", Some("Enter"), MenuAction::EditPath);
+    command_menu.add_item("Path Details", Some("i"), MenuAction::PathDetails);
     command_menu.add_item("Delete Marked", Some("F3/Del"), MenuAction::DeleteMarked);
-    command_menu.add_item("Mark/Unmark", Some("F2/Space"), MenuAction::MarkItem);
-    command_menu.add_item("Unmark All", Some("Ctrl+Shift+U"), MenuAction::UnmarkAll);
+    command_menu.add_submenu(
+        "Mark",
+        vec![
+            MenuItem::new("Mark/Unmark", Some("F2/Space"), MenuAction::MarkItem),
+            MenuItem::new("All Visible", Some("Ctrl+A"), MenuAction::MarkAllVisible),
+            MenuItem::new("All Dead", Some("Ctrl+Shift+D"), MenuAction::MarkAllDead),
+            MenuItem::new(
+                "All Duplicates",
+                Some("Ctrl+D"),
+                MenuAction::MarkAllDuplicates,
+            ),
+            MenuItem::new(
+                "All Non-Normalized",
+                Some("Ctrl+N"),
+                MenuAction::MarkAllNonNormalized,
+            ),
+            MenuItem::new("Unmark All", Some("Ctrl+Shift+U"), MenuAction::UnmarkAll),
+        ],
+    );
 
     // Dynamic label based on connection mode
     let f5_label = if connection_mode == crate::app::ConnectionMode::Remote {
@@ -95,12 +243,30 @@ pub fn get_menus(connection_mode: crate::app::ConnectionMode) -> Vec<Menu> {
         "Move Marked to Other Panel"
     };
     command_menu.add_item(f5_label, Some("F5"), MenuAction::MoveMarked);
+    // Remote mode F5 already copies
+    if connection_mode == crate::app::ConnectionMode::Local {
+        command_menu.add_item(
+            "Copy Marked to Other Panel",
+            Some("Shift+F5"),
+            MenuAction::CopyMarked,
+        );
+        command_menu.add_item(
+            "Promote/Demote to Other Scope",
+            Some("p"),
+            MenuAction::PromoteEntry,
+        );
+    }
     command_menu.add_item("Move Item Up", Some("F6"), MenuAction::MoveItemUp);
     command_menu.add_item(
         "Normalize Selected",
-        Some("F9"),
+        Some("Shift+F9"),
         MenuAction::NormalizeSelected,
     );
+    command_menu.add_item(
+        "Clean Up Malformed Entries",
+        Some("Ctrl+L"),
+        MenuAction::CleanMalformed,
+    );
     command_menu.add_item(
         "Delete All Dead Paths",
         Some("F8"),
@@ -116,6 +282,18 @@ pub fn get_menus(connection_mode: crate::app::ConnectionMode) -> Vec<Menu> {
         Some("F10"),
         MenuAction::CreateMarkedDirectories,
     );
+    command_menu.add_item("Security Scan", None, MenuAction::SecurityScan);
+    command_menu.add_item("Shims and Version Managers", None, MenuAction::ShimCheck);
+    command_menu.add_item("Toolchains on PATH", None, MenuAction::Toolchains);
+    command_menu.add_item(
+        "Merge Cross-Scope Duplicates",
+        None,
+        MenuAction::MergeDuplicates,
+    );
+    command_menu.add_item("Nested Subdirectories", None, MenuAction::NestedEntries);
+    command_menu.add_item("Find and Replace", Some("R"), MenuAction::BatchReplace);
+    command_menu.add_item("Edit Raw Value", Some("E"), MenuAction::EditRawValue);
+    command_menu.add_item("Drive Migration Wizard", None, MenuAction::DriveMigration);
     menus.push(command_menu);
 
     // Options menu
@@ -134,17 +312,91 @@ pub fn get_menus(connection_mode: crate::app::ConnectionMode) -> Vec<Menu> {
     );
     options_menu.add_item("Create Backup", Some("Ctrl+B"), MenuAction::CreateBackup);
     options_menu.add_item("Restore Backup", Some("Ctrl+R"), MenuAction::RestoreBackup);
+    options_menu.add_item("Revert Last Apply", Some("Z"), MenuAction::RevertLastApply);
+    options_menu.add_item("Apply History", Some("h"), MenuAction::ApplyHistory);
+    options_menu.add_item("Change Summary Report", None, MenuAction::ChangeReport);
+    options_menu.add_item("Ignore List", None, MenuAction::IgnoreList);
+    options_menu.add_item("Quick Actions Row", None, MenuAction::ToggleQuickActions);
     menus.push(options_menu);
 
     // Help menu
     let mut help_menu = Menu::new("Help", 'h');
     help_menu.add_item("Help", Some("F1"), MenuAction::KeyboardShortcuts);
+    help_menu.add_item(
+        "Export Keyboard Shortcuts",
+        None,
+        MenuAction::ExportShortcuts,
+    );
+    help_menu.add_item("How PATH Is Searched", None, MenuAction::PathPrecedence);
+    help_menu.add_item("Where Is Command", Some("w"), MenuAction::WhereCommand);
+    help_menu.add_item("PATH Length and Limits", Some("L"), MenuAction::PathLengths);
+    help_menu.add_item("Entry Aging Report", None, MenuAction::AgingReport);
+    help_menu.add_item(
+        "Reorder by Command Usage",
+        None,
+        MenuAction::UsageSuggestions,
+    );
+    help_menu.add_item(
+        "Compare Live Environment",
+        Some("v"),
+        MenuAction::LiveEnvironment,
+    );
+    help_menu.add_item("Message Log", Some("l"), MenuAction::MessageLog);
+    help_menu.add_item("What's New", None, MenuAction::WhatsNew);
+    help_menu.add_item("Check for Updates", None, MenuAction::CheckUpdates);
+    help_menu.add_item("Install Update", None, MenuAction::InstallUpdate);
     help_menu.add_item("About", None, MenuAction::About);
     menus.push(help_menu);
 
+    for menu in &mut menus {
+        assign_accelerators(&mut menu.items);
+    }
     menus
 }
 
+/// Show shortcuts as remapped under [keys] in config.ini
+pub fn apply_key_settings(menus: &mut [Menu], settings: &crate::config::Settings) {
+    if !settings.f9_normalizes {
+        return;
+    }
+    for item in menus.iter_mut().flat_map(|menu| menu.items.iter_mut()) {
+        if item.action == MenuAction::NormalizeSelected {
+            item.shortcut = Some("F9/Shift+F9".to_string());
+        }
+    }
+}
+
+/// Letters of a label in the order they are tried as accelerator: word starts first
+fn accelerator_candidates(label: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let word_starts = label.char_indices().filter(|&(pos, _)| {
+        label[..pos]
+            .chars()
+            .next_back()
+            .is_none_or(|before| !before.is_ascii_alphanumeric())
+    });
+    word_starts
+        .chain(label.char_indices())
+        .filter(|(_, c)| c.is_ascii_alphanumeric())
+}
+
+/// Give each item the first candidate letter of its label no earlier item of the list took
+fn assign_accelerators(items: &mut [MenuItem]) {
+    let mut taken = Vec::new();
+    for item in items {
+        item.accelerator = accelerator_candidates(&item.label)
+            .map(|(_, c)| c.to_ascii_lowercase())
+            .find(|c| !taken.contains(c));
+        taken.extend(item.accelerator);
+        assign_accelerators(&mut item.submenu);
+    }
+}
+
+/// Item whose accelerator is this key, matched case-insensitively
+pub fn item_by_accelerator(items: &[MenuItem], key: char) -> Option<usize> {
+    let key = key.to_ascii_lowercase();
+    items.iter().position(|item| item.accelerator == Some(key))
+}
+
 /// Update menu item enabled states based on app state
 pub fn update_menu_enabled_states(
     menus: &mut [Menu],
@@ -155,21 +407,134 @@ pub fn update_menu_enabled_states(
     is_remote: bool,
     _has_changes: bool,
 ) {
+    let enabled = |action: MenuAction| -> bool {
+        match action {
+            MenuAction::RunAsAdministrator => !is_admin,
+            MenuAction::DeleteMarked
+            | MenuAction::MoveMarked
+            | MenuAction::CopyMarked
+            | MenuAction::UnmarkAll => has_marked,
+            MenuAction::CreateMarkedDirectories => has_marked_dead,
+            MenuAction::EditPath
+            | MenuAction::PathDetails
+            | MenuAction::PromoteEntry
+            | MenuAction::NormalizeSelected
+            | MenuAction::MoveItemUp => has_selection,
+            MenuAction::DisconnectRemote => is_remote,
+            MenuAction::PathPrecedence
+            | MenuAction::WhereCommand
+            | MenuAction::LiveEnvironment
+            | MenuAction::SecurityScan
+            | MenuAction::ShimCheck
+            | MenuAction::Toolchains
+            | MenuAction::MergeDuplicates
+            | MenuAction::NestedEntries
+            | MenuAction::BatchReplace
+            | MenuAction::EditRawValue
+            | MenuAction::DriveMigration
+            | MenuAction::RevertLastApply
+            | MenuAction::UsageSuggestions
+            | MenuAction::ChangeReport => !is_remote,
+            MenuAction::ConnectRemote | MenuAction::ConnectRecent(_) => !is_remote,
+            MenuAction::NoRecentRemotes => false,
+            _ => true,
+        }
+    };
     for menu in menus.iter_mut() {
         for item in menu.items.iter_mut() {
-            item.enabled = match item.action {
-                MenuAction::RunAsAdministrator => !is_admin,
-                MenuAction::DeleteMarked | MenuAction::MoveMarked | MenuAction::UnmarkAll => {
-                    has_marked
-                }
-                MenuAction::CreateMarkedDirectories => has_marked_dead,
-                MenuAction::EditPath | MenuAction::NormalizeSelected | MenuAction::MoveItemUp => {
-                    has_selection
-                }
-                MenuAction::DisconnectRemote => is_remote,
-                MenuAction::ConnectRemote => !is_remote,
-                _ => true,
+            for sub in item.submenu.iter_mut() {
+                sub.enabled = enabled(sub.action);
+            }
+            // A submenu opens as long as something in it can be used
+            item.enabled = if item.has_submenu() {
+                item.submenu.iter().any(|sub| sub.enabled)
+            } else {
+                enabled(item.action)
             };
         }
     }
 }
+
+/// Disable the items of features the launch profile turns off
+pub fn apply_profile(menus: &mut [Menu], profile: &Profile) {
+    let allowed = |action: MenuAction| action.feature().is_none_or(|f| profile.allows(f));
+    for item in menus.iter_mut().flat_map(|menu| menu.items.iter_mut()) {
+        for sub in item.submenu.iter_mut() {
+            sub.enabled &= allowed(sub.action);
+        }
+        item.enabled &= if item.has_submenu() {
+            item.submenu.iter().any(|sub| sub.enabled)
+        } else {
+            allowed(item.action)
+        };
+    }
+}
+
+/// Width of a dropdown: longest label and shortcut, plus padding
+fn dropdown_width(title: &str, items: &[MenuItem]) -> u16 {
+    let widest = items
+        .iter()
+        .map(|item| {
+            item.label.chars().count()
+                + item
+                    .shortcut
+                    .as_ref()
+                    .map(|s| s.chars().count() + 2)
+                    .unwrap_or(0)
+        })
+        .fold(title.len(), usize::max);
+    widest as u16 + 4
+}
+
+/// Where a menu's dropdown is drawn: below its name in the menu bar, kept on screen
+/// Rendering and mouse handling both use it, so clicks hit what is drawn
+pub fn dropdown_area(menus: &[Menu], active_menu: usize, screen_width: u16) -> Rect {
+    let Some(menu) = menus.get(active_menu) else {
+        return Rect::default();
+    };
+    // Menu names start after one space and are two spaces apart
+    let x = 1 + menus
+        .iter()
+        .take(active_menu)
+        .map(|m| m.name.len() as u16 + 2)
+        .sum::<u16>();
+    let width = dropdown_width(&menu.name, &menu.items);
+    Rect {
+        x: x.min(screen_width.saturating_sub(width)),
+        y: 1,
+        width,
+        height: menu.items.len() as u16 + 2,
+    }
+}
+
+/// Where the submenu of a dropdown item is drawn: right of the dropdown, level with the
+/// item, or left of it when there is no room on the right
+pub fn submenu_area(menus: &[Menu], active_menu: usize, item: usize, screen_width: u16) -> Rect {
+    let parent = dropdown_area(menus, active_menu, screen_width);
+    let Some(entry) = menus.get(active_menu).and_then(|m| m.items.get(item)) else {
+        return Rect::default();
+    };
+    let width = dropdown_width("", &entry.submenu);
+    let right = parent.x + parent.width;
+    let x = if right + width <= screen_width {
+        right
+    } else {
+        parent.x.saturating_sub(width)
+    };
+    Rect {
+        x,
+        y: parent.y + 1 + item as u16,
+        width,
+        height: entry.submenu.len() as u16 + 2,
+    }
+}
+
+/// Item row under a click inside a menu area, if any
+pub fn item_at(area: Rect, items: usize, column: u16, row: u16) -> Option<usize> {
+    let inside = column >= area.x
+        && column < area.x + area.width
+        && row > area.y
+        && row < area.y + area.height - 1;
+    let item = (row.saturating_sub(area.y + 1)) as usize;
+    (inside && item < items).then_some(item)
+}
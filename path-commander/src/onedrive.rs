@@ -0,0 +1,130 @@
+use anyhow::{Context, Result};
+#[cfg(windows)]
+use std::os::windows::fs::MetadataExt;
+use std::path::Path;
+
+// File attributes set on cloud placeholders (not exposed by std)
+const FILE_ATTRIBUTE_OFFLINE: u32 = 0x0000_1000;
+const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x0004_0000;
+const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x0040_0000;
+
+/// Environment variables OneDrive sets to its sync roots
+const ONEDRIVE_VARS: [&str; 3] = ["OneDrive", "OneDriveConsumer", "OneDriveCommercial"];
+
+/// How many files in a directory are stored locally vs. online-only
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Availability {
+    pub files: usize,
+    pub online_only: usize,
+}
+
+/// Get the OneDrive sync roots of the current user (personal and work/school)
+pub fn onedrive_roots() -> Vec<String> {
+    let mut roots: Vec<String> = Vec::new();
+    for var in ONEDRIVE_VARS {
+        if let Ok(root) = std::env::var(var) {
+            let root = root.trim_end_matches('\\').to_string();
+            if !root.is_empty() && !roots.iter().any(|r| r.eq_ignore_ascii_case(&root)) {
+                roots.push(root);
+            }
+        }
+    }
+    roots
+}
+
+/// Check if an expanded path lives inside one of the given sync roots
+pub fn is_under_onedrive(expanded_path: &str, roots: &[String]) -> bool {
+    let path = expanded_path.to_lowercase();
+    roots.iter().any(|root| {
+        let root = root.to_lowercase();
+        path == root || path.starts_with(&format!("{}\\", root))
+    })
+}
+
+/// Check if a file is an online-only placeholder that has to be downloaded before use
+fn is_online_only(attributes: u32) -> bool {
+    attributes
+        & (FILE_ATTRIBUTE_OFFLINE
+            | FILE_ATTRIBUTE_RECALL_ON_OPEN
+            | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS)
+        != 0
+}
+
+/// Raw file attributes of a directory entry
+#[cfg(windows)]
+fn file_attributes(metadata: &std::fs::Metadata) -> u32 {
+    metadata.file_attributes()
+}
+
+/// OneDrive placeholders only exist on Windows
+#[cfg(not(windows))]
+fn file_attributes(_metadata: &std::fs::Metadata) -> u32 {
+    0
+}
+
+/// Count the files in a directory that are online-only placeholders
+/// Reading attributes does not trigger a download
+pub fn check_availability(dir: &Path) -> Result<Availability> {
+    let mut availability = Availability {
+        files: 0,
+        online_only: 0,
+    };
+
+    let entries = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read directory {}", dir.display()))?;
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_file() {
+            availability.files += 1;
+            if is_online_only(file_attributes(&metadata)) {
+                availability.online_only += 1;
+            }
+        }
+    }
+
+    Ok(availability)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_under_onedrive() {
+        let roots = vec![r"C:\Users\user\OneDrive".to_string()];
+        assert!(is_under_onedrive(r"C:\Users\user\OneDrive\bin", &roots));
+        assert!(is_under_onedrive(r"c:\users\USER\onedrive\Tools\x", &roots));
+        assert!(is_under_onedrive(r"C:\Users\user\OneDrive", &roots));
+        assert!(!is_under_onedrive(r"C:\Users\user\OneDriveBackup", &roots));
+        assert!(!is_under_onedrive(r"C:\Windows", &roots));
+        assert!(!is_under_onedrive(r"C:\Windows", &[]));
+    }
+
+    #[test]
+    fn test_is_online_only() {
+        assert!(!is_online_only(0x20)); // FILE_ATTRIBUTE_ARCHIVE
+        assert!(is_online_only(0x20 | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS));
+        assert!(is_online_only(FILE_ATTRIBUTE_OFFLINE));
+    }
+
+    #[test]
+    fn test_check_availability_local_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("tool.exe"), b"MZ").unwrap();
+        std::fs::write(dir.path().join("script.cmd"), b"@echo off").unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+
+        let availability = check_availability(dir.path()).unwrap();
+        assert_eq!(
+            availability,
+            Availability {
+                files: 2,
+                online_only: 0
+            }
+        );
+
+        assert!(check_availability(&dir.path().join("missing")).is_err());
+    }
+}
@@ -1,5 +1,16 @@
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::path::Path;
+use std::time::SystemTime;
+
+use crate::drive_info::MappedDrive;
+use crate::filesystem::{FileSystem, RealFileSystem};
+
+/// Classic Win32 MAX_PATH limit (including the terminating NUL)
+/// Longer entries only work for applications that opt into long path support
+pub const MAX_PATH: usize = 260;
+
+/// Extended-length path prefix that bypasses Win32 path normalization
+const EXTENDED_PREFIX: &str = r"\\?\";
 
 /// Convert a local path to UNC format for remote access
 /// Example: `C:\Program Files` on computer `SERVER` becomes `\\SERVER\C$\Program Files`
@@ -35,6 +46,11 @@ pub enum PathStatus {
     Duplicate,     // Duplicate within same scope or across scopes
     NonNormalized, // Contains short names, env vars, or can be expanded
     DeadDuplicate, // Both dead and duplicate
+    Malformed,     // Empty segment, stray whitespace, or unneeded quotes
+    OfflineDrive,  // On a mapped network drive that is currently disconnected
+    Removable,     // On removable media (USB stick, card reader)
+    Insecure,      // Writable by all users and searched before system directories
+    Unchecked,     // Filesystem checks are off (--no-fs-checks), nothing is known
 }
 
 impl PathStatus {
@@ -43,12 +59,14 @@ impl PathStatus {
     pub fn is_problematic(&self) -> bool {
         matches!(
             self,
-            PathStatus::Dead | PathStatus::Duplicate | PathStatus::DeadDuplicate
+            PathStatus::Dead
+                | PathStatus::Duplicate
+                | PathStatus::DeadDuplicate
+                | PathStatus::Malformed
         )
     }
 
     /// Get a human-readable description
-    #[cfg(test)]
     pub fn description(&self) -> &'static str {
         match self {
             PathStatus::Valid => "Valid",
@@ -56,6 +74,43 @@ impl PathStatus {
             PathStatus::Duplicate => "Duplicate",
             PathStatus::NonNormalized => "Can be normalized",
             PathStatus::DeadDuplicate => "Dead & Duplicate",
+            PathStatus::Malformed => "Malformed (empty, padded, or quoted)",
+            PathStatus::OfflineDrive => "Offline (mapped drive not connected)",
+            PathStatus::Removable => "On removable media",
+            PathStatus::Insecure => "Insecure (writable by all users)",
+            PathStatus::Unchecked => "Not checked (--no-fs-checks)",
+        }
+    }
+}
+
+/// How a path entry refers to its directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PathKind {
+    Local,       // Directory on a local drive
+    Unc,         // \\server\share path
+    MappedDrive, // Drive letter mapped to a network share
+    Junction,    // Junction or symlink pointing somewhere else
+    EnvVar,      // Built from environment variables (%VAR%)
+}
+
+impl PathKind {
+    /// All kinds, in the order they are listed in the filter menu
+    pub const ALL: [PathKind; 5] = [
+        PathKind::Local,
+        PathKind::Unc,
+        PathKind::MappedDrive,
+        PathKind::Junction,
+        PathKind::EnvVar,
+    ];
+
+    /// Get a human-readable description
+    pub fn label(&self) -> &'static str {
+        match self {
+            PathKind::Local => "Local directory",
+            PathKind::Unc => "UNC share",
+            PathKind::MappedDrive => "Mapped drive",
+            PathKind::Junction => "Junction/symlink",
+            PathKind::EnvVar => "Environment variable",
         }
     }
 }
@@ -71,77 +126,414 @@ pub struct PathInfo {
     pub exists: bool,
     pub is_duplicate: bool,
     pub needs_normalization: bool,
+    pub is_malformed: bool,
+    /// Entry uses the `\\?\` extended-length prefix
+    pub has_extended_prefix: bool,
+    /// Expanded entry is at or beyond MAX_PATH characters
+    pub exceeds_max_path: bool,
+    /// Missing because its mapped network drive is disconnected (not necessarily dead)
+    pub is_offline_drive: bool,
+    /// Lives on a removable drive (USB stick, card reader, optical drive)
+    pub is_removable_drive: bool,
+    /// Lives on a removable drive whose media is not inserted right now
+    pub is_media_missing: bool,
+    /// Lives inside a OneDrive-synced folder (files may be online-only placeholders)
+    pub is_onedrive: bool,
+    /// Writable by all users and searched before a system directory (PATH hijack risk)
+    pub is_insecure: bool,
+    /// The WindowsApps folder of app execution aliases, counted as existing
+    pub is_app_aliases: bool,
+    /// On the ignore list, never reported as dead or duplicate
+    pub is_ignored: bool,
+    /// How the entry refers to its directory
+    pub kind: PathKind,
+    /// When the directory last changed, None if it is missing or unreadable
+    pub modified: Option<SystemTime>,
+}
+
+impl PathInfo {
+    /// Check if the entry is really gone, as opposed to temporarily unreachable
+    pub fn is_dead(&self) -> bool {
+        !self.exists && !self.is_offline_drive && !self.is_media_missing && !self.is_ignored
+    }
+
+    /// Check if the entry is missing only because its drive is disconnected or ejected
+    pub fn is_unavailable(&self) -> bool {
+        !self.exists && (self.is_offline_drive || self.is_media_missing) && !self.is_ignored
+    }
 }
 
 /// Analyze a list of path entries
+#[cfg(test)]
 pub fn analyze_paths(paths: &[String], other_scope_paths: &[String]) -> Vec<PathInfo> {
     analyze_paths_with_remote(paths, other_scope_paths, None)
 }
 
 /// Analyze a list of path entries with optional remote computer support
+#[cfg(test)]
 pub fn analyze_paths_with_remote(
     paths: &[String],
     other_scope_paths: &[String],
     remote_computer: Option<&str>,
 ) -> Vec<PathInfo> {
-    let mut results: Vec<PathInfo> = Vec::new();
-    let mut seen_normalized: HashMap<String, usize> = HashMap::new();
+    analyze_paths_with_fs(paths, other_scope_paths, remote_computer, &RealFileSystem)
+}
 
-    // First pass: normalize and check existence
-    for (idx, path) in paths.iter().enumerate() {
-        let normalized = normalize_path(path);
-        let exists = path_exists_with_remote(&normalized, remote_computer);
-        let needs_normalization = path != &normalized;
+/// Analyze a list of path entries against the given filesystem
+#[cfg(test)]
+pub fn analyze_paths_with_fs(
+    paths: &[String],
+    other_scope_paths: &[String],
+    remote_computer: Option<&str>,
+    fs: &dyn FileSystem,
+) -> Vec<PathInfo> {
+    analyze_paths_in(
+        paths,
+        other_scope_paths,
+        remote_computer,
+        fs,
+        &ExpansionContext::default(),
+    )
+}
+
+/// Analyze a list of path entries, expanding per-user variables for the given profile
+pub fn analyze_paths_in(
+    paths: &[String],
+    other_scope_paths: &[String],
+    remote_computer: Option<&str>,
+    fs: &dyn FileSystem,
+    context: &ExpansionContext,
+) -> Vec<PathInfo> {
+    let env = EnvVars::capture_for(context);
+    let normalized = normalize_all(paths, fs, &env);
+    let other_keys = duplicate_keys(&normalize_all(other_scope_paths, fs, &env));
+    analyze_normalized(
+        paths,
+        normalized,
+        &other_keys.iter().map(|k| k.as_str()).collect(),
+        remote_computer,
+        fs,
+        &env,
+        &expand_rules(&context.ignored, &env),
+    )
+}
 
-        // Track normalized paths for duplicate detection
-        if let Some(&first_idx) = seen_normalized.get(&normalized.to_lowercase()) {
-            // Mark the first occurrence as duplicate too
-            if first_idx < results.len() {
+/// Analyze the local MACHINE and USER scopes against each other
+#[cfg(test)]
+pub fn analyze_scopes_with_fs(
+    machine_paths: &[String],
+    user_paths: &[String],
+    fs: &dyn FileSystem,
+) -> (Vec<PathInfo>, Vec<PathInfo>) {
+    analyze_scopes_in(machine_paths, user_paths, fs, &ExpansionContext::default())
+}
+
+/// Analyze MACHINE and USER, expanding per-user variables for the given profile
+/// Every entry is normalized only once, instead of once per scope it is compared with
+pub fn analyze_scopes_in(
+    machine_paths: &[String],
+    user_paths: &[String],
+    fs: &dyn FileSystem,
+    context: &ExpansionContext,
+) -> (Vec<PathInfo>, Vec<PathInfo>) {
+    let env = EnvVars::capture_for(context);
+    let machine_normalized = normalize_all(machine_paths, fs, &env);
+    let user_normalized = normalize_all(user_paths, fs, &env);
+    let machine_keys = duplicate_keys(&machine_normalized);
+    let user_keys = duplicate_keys(&user_normalized);
+    let ignored = expand_rules(&context.ignored, &env);
+
+    let machine_info = analyze_normalized(
+        machine_paths,
+        machine_normalized,
+        &user_keys.iter().map(|k| k.as_str()).collect(),
+        None,
+        fs,
+        &env,
+        &ignored,
+    );
+    let user_info = analyze_normalized(
+        user_paths,
+        user_normalized,
+        &machine_keys.iter().map(|k| k.as_str()).collect(),
+        None,
+        fs,
+        &env,
+        &ignored,
+    );
+    (machine_info, user_info)
+}
+
+fn normalize_all(paths: &[String], fs: &dyn FileSystem, env: &EnvVars) -> Vec<String> {
+    paths.iter().map(|p| normalize_with(p, fs, env)).collect()
+}
+
+/// Keys that identify duplicates: normalized entries compared case-insensitively
+fn duplicate_keys(normalized: &[String]) -> Vec<String> {
+    normalized.iter().map(|n| n.to_lowercase()).collect()
+}
+
+/// Analyze entries whose normalized forms are already known
+fn analyze_normalized(
+    paths: &[String],
+    normalized: Vec<String>,
+    other_keys: &HashSet<&str>,
+    remote_computer: Option<&str>,
+    fs: &dyn FileSystem,
+    env: &EnvVars,
+    ignored: &[String],
+) -> Vec<PathInfo> {
+    let keys = duplicate_keys(&normalized);
+    let mut results: Vec<PathInfo> = Vec::with_capacity(paths.len());
+    let mut seen_normalized: HashMap<&str, usize> = HashMap::with_capacity(paths.len());
+    let mut drives = DriveCache::default();
+    let onedrive_roots = if remote_computer.is_none() {
+        crate::onedrive::onedrive_roots()
+    } else {
+        Vec::new()
+    };
+    let last_system_dir = last_system_dir(&normalized, remote_computer, env);
+
+    // First pass: check existence and the drive each entry lives on
+    for ((idx, path), normalized) in paths.iter().enumerate().zip(normalized) {
+        let mut info = check_entry(
+            path,
+            normalized,
+            last_system_dir.is_some_and(|last| idx < last),
+            remote_computer,
+            fs,
+            env,
+            &onedrive_roots,
+            &mut drives,
+        );
+
+        info.is_ignored = is_ignored(&env.expand(&info.normalized), ignored);
+        if info.is_ignored {
+            results.push(info);
+            continue;
+        }
+
+        // Track normalized paths for duplicate detection (the first occurrence is a duplicate too)
+        let key = keys[idx].as_str();
+        info.is_duplicate = match seen_normalized.get(key) {
+            Some(&first_idx) => {
                 results[first_idx].is_duplicate = true;
+                true
             }
-            results.push(PathInfo {
-                original: path.clone(),
-                normalized: normalized.clone(),
-                status: PathStatus::Valid, // Will be updated
-                exists,
-                is_duplicate: true,
-                needs_normalization,
-            });
-        } else {
-            seen_normalized.insert(normalized.to_lowercase(), idx);
-            results.push(PathInfo {
-                original: path.clone(),
-                normalized: normalized.clone(),
-                status: PathStatus::Valid, // Will be updated
-                exists,
-                is_duplicate: false,
-                needs_normalization,
-            });
-        }
+            None => {
+                seen_normalized.insert(key, idx);
+                false
+            }
+        } || other_keys.contains(key);
+
+        results.push(info);
     }
 
-    // Check for duplicates across scopes
-    let other_normalized: HashSet<String> = other_scope_paths
+    // Second pass: determine final status
+    for info in &mut results {
+        info.status = status_on(fs, info);
+    }
+
+    results
+}
+
+/// Re-run the filesystem checks for one entry, e.g. after its directory was created or
+/// its share reconnected, and update its status in place
+/// Duplicates depend on the other entries and are left as they were
+pub fn recheck_entry(
+    infos: &mut [PathInfo],
+    idx: usize,
+    remote_computer: Option<&str>,
+    fs: &dyn FileSystem,
+    context: &ExpansionContext,
+) {
+    let env = EnvVars::capture_for(context);
+    let normalized: Vec<String> = infos.iter().map(|info| info.normalized.clone()).collect();
+    let last_system_dir = last_system_dir(&normalized, remote_computer, &env);
+    let onedrive_roots = if remote_computer.is_none() {
+        crate::onedrive::onedrive_roots()
+    } else {
+        Vec::new()
+    };
+    let Some(old) = infos.get(idx) else {
+        return;
+    };
+
+    let mut info = check_entry(
+        &old.original,
+        old.normalized.clone(),
+        last_system_dir.is_some_and(|last| idx < last),
+        remote_computer,
+        fs,
+        &env,
+        &onedrive_roots,
+        &mut DriveCache::default(),
+    );
+    info.is_duplicate = old.is_duplicate;
+    info.is_ignored = old.is_ignored;
+    info.status = status_on(fs, &info);
+    infos[idx] = info;
+}
+
+/// Drive lookups can go over the network, so they are only asked once per letter
+#[derive(Default)]
+struct DriveCache {
+    offline: HashMap<char, bool>,
+    /// (is_removable, is_media_missing)
+    removable: HashMap<char, (bool, bool)>,
+    mapped: HashMap<char, bool>,
+}
+
+/// Position of the last system directory: only entries searched before one can shadow
+/// system commands, and permissions can only be read for local directories
+fn last_system_dir(
+    normalized: &[String],
+    remote_computer: Option<&str>,
+    env: &EnvVars,
+) -> Option<usize> {
+    if remote_computer.is_some() {
+        return None;
+    }
+    normalized
         .iter()
-        .map(|p| normalize_path(p).to_lowercase())
-        .collect();
+        .rposition(|n| is_system_dir(&env.expand(n), env))
+}
 
-    for info in &mut results {
-        if other_normalized.contains(&info.normalized.to_lowercase()) {
-            info.is_duplicate = true;
+/// Check existence and the drive of one entry, not yet compared with the others
+/// (is_duplicate is false and the status still has to be determined)
+#[allow(clippy::too_many_arguments)]
+fn check_entry(
+    path: &str,
+    normalized: String,
+    before_system_dir: bool,
+    remote_computer: Option<&str>,
+    fs: &dyn FileSystem,
+    env: &EnvVars,
+    onedrive_roots: &[String],
+    drives: &mut DriveCache,
+) -> PathInfo {
+    let expanded = env.expand(&normalized);
+    // The alias folder denies listing to elevated and other accounts,
+    // so a failed check says nothing about whether it is there
+    let is_app_aliases = crate::shims::is_alias_dir(&expanded);
+    let exists = is_app_aliases || expanded_path_exists(&expanded, remote_computer, fs);
+    let modified = if exists && !is_app_aliases {
+        fs.modified(&lookup_path(&expanded, remote_computer))
+    } else {
+        None
+    };
+    let needs_normalization = strip_required_quotes(path) != normalized;
+
+    // Drive mappings and drive types belong to the local machine, so they only apply locally
+    let letter = if remote_computer.is_none() {
+        crate::drive_info::drive_letter(&expanded)
+    } else {
+        None
+    };
+    let is_offline_drive = !exists
+        && letter
+            .map(|letter| {
+                *drives
+                    .offline
+                    .entry(letter)
+                    .or_insert_with(|| fs.is_offline_mapped_drive(letter))
+            })
+            .unwrap_or(false);
+    let (is_removable_drive, is_media_missing) = letter
+        .map(|letter| {
+            *drives.removable.entry(letter).or_insert_with(|| {
+                let removable = fs.is_removable_drive(letter);
+                (removable, removable && !fs.is_drive_ready(letter))
+            })
+        })
+        .unwrap_or((false, false));
+
+    let is_onedrive = crate::onedrive::is_under_onedrive(&expanded, onedrive_roots);
+    let is_insecure = exists
+        && !is_app_aliases
+        && before_system_dir
+        && !matches!(classify_path(path), PathKind::Unc)
+        && !is_offline_drive
+        && fs.is_writable_by_all_users(&expanded);
+
+    // Refine local entries: drive mappings and reparse points only make sense locally
+    let mut kind = classify_path(path);
+    if kind == PathKind::Local && remote_computer.is_none() {
+        let is_mapped = is_offline_drive
+            || letter
+                .map(|letter| {
+                    *drives
+                        .mapped
+                        .entry(letter)
+                        .or_insert_with(|| fs.mapped_drive(letter) != MappedDrive::NotMapped)
+                })
+                .unwrap_or(false);
+        if is_mapped {
+            kind = PathKind::MappedDrive;
+        } else if exists && fs.is_symlink(&expanded) {
+            kind = PathKind::Junction;
         }
     }
 
-    // Second pass: determine final status
-    for info in &mut results {
-        info.status = determine_status(info);
+    PathInfo {
+        original: path.to_string(),
+        normalized,
+        status: PathStatus::Valid, // Will be updated
+        exists,
+        is_duplicate: false,
+        needs_normalization,
+        is_malformed: is_malformed_entry(path),
+        has_extended_prefix: has_extended_prefix(path),
+        exceeds_max_path: exceeds_max_path(path),
+        is_offline_drive,
+        is_removable_drive,
+        is_media_missing,
+        is_onedrive,
+        is_insecure,
+        is_app_aliases,
+        is_ignored: false,
+        kind,
+        modified,
     }
+}
 
-    results
+/// Final status of a path, neutral when the filesystem was never asked
+fn status_on(fs: &dyn FileSystem, info: &PathInfo) -> PathStatus {
+    if fs.is_checked() {
+        determine_status(info)
+    } else {
+        PathStatus::Unchecked
+    }
 }
 
 /// Determine the final status of a path
 fn determine_status(info: &PathInfo) -> PathStatus {
+    // Structural problems take precedence - the entry can't be judged until it's cleaned up
+    if info.is_malformed {
+        return PathStatus::Malformed;
+    }
+
+    // A hijack risk matters more than anything cosmetic about the entry
+    if info.is_insecure {
+        return PathStatus::Insecure;
+    }
+
+    // The user knows about it and asked not to be told
+    if info.is_ignored {
+        return PathStatus::Valid;
+    }
+
+    // Not dead - the drive just isn't connected right now
+    if info.is_offline_drive {
+        return PathStatus::OfflineDrive;
+    }
+
+    // Media can come and go, so flag it whether or not it's inserted right now
+    // (a missing directory on inserted media is still dead)
+    if info.is_removable_drive && !info.is_duplicate && (info.exists || info.is_media_missing) {
+        return PathStatus::Removable;
+    }
+
     match (info.exists, info.is_duplicate, info.needs_normalization) {
         (false, true, _) => PathStatus::DeadDuplicate,
         (false, false, _) => PathStatus::Dead,
@@ -151,30 +543,166 @@ fn determine_status(info: &PathInfo) -> PathStatus {
     }
 }
 
+/// Ignore rules expanded and lowercased once, for comparing with expanded entries
+fn expand_rules(rules: &[String], env: &EnvVars) -> Vec<String> {
+    rules
+        .iter()
+        .map(|rule| env.expand(rule.trim()).to_lowercase())
+        .filter(|rule| !rule.is_empty())
+        .collect()
+}
+
+/// Whether an expanded entry is on the ignore list: the same directory, or anything
+/// below a rule ending in `*`
+fn is_ignored(expanded: &str, rules: &[String]) -> bool {
+    let entry = expanded.trim_end_matches(['\\', '/']).to_lowercase();
+    rules.iter().any(|rule| match rule.strip_suffix('*') {
+        Some(prefix) => entry.starts_with(prefix),
+        None => entry == rule.trim_end_matches(['\\', '/']),
+    })
+}
+
+/// Whether an entry is one of the operating system's own directories
+pub fn is_system_entry(entry: &str) -> bool {
+    let env = EnvVars::capture();
+    is_system_dir(&env.expand(entry), &env)
+}
+
+/// Directories holding the operating system's own commands
+fn is_system_dir(expanded: &str, env: &EnvVars) -> bool {
+    let dir = expanded.trim_end_matches(['\\', '/']).to_lowercase();
+    if matches!(dir.as_str(), "/bin" | "/sbin" | "/usr/bin" | "/usr/sbin") {
+        return true;
+    }
+
+    // Entries are not always expanded (and variable names match in any case)
+    let mut roots = vec!["%systemroot%".to_string(), "%windir%".to_string()];
+    let expanded_root = env.expand("%SYSTEMROOT%");
+    if !expanded_root.contains('%') {
+        roots.push(expanded_root.trim_end_matches('\\').to_lowercase());
+    }
+    roots
+        .iter()
+        .any(|root| dir == *root || dir.starts_with(&format!("{}\\", root)))
+}
+
+/// Classify an entry by how it is written: env-var based, UNC share, or local
+/// (mapped drives and junctions need a filesystem lookup, see analyze_paths_with_remote)
+pub fn classify_path(path: &str) -> PathKind {
+    let path = path.trim().trim_matches('"');
+    if path.contains('%') {
+        return PathKind::EnvVar;
+    }
+    // Shell profile entries like $HOME/bin or ~/bin
+    #[cfg(not(windows))]
+    if path.contains('$') || path.starts_with('~') {
+        return PathKind::EnvVar;
+    }
+
+    let upper = path.to_uppercase();
+    if let Some(rest) = upper.strip_prefix(EXTENDED_PREFIX) {
+        // \\?\UNC\server\share is a UNC path, \\?\C:\ is local
+        if rest.starts_with(r"UNC\") {
+            return PathKind::Unc;
+        }
+        return PathKind::Local;
+    }
+    if path.starts_with(r"\\") || path.starts_with("//") {
+        return PathKind::Unc;
+    }
+
+    PathKind::Local
+}
+
+/// Check if a raw PATH segment is malformed: empty, padded with whitespace,
+/// or wrapped in quotes it doesn't need (quotes are only required around a semicolon)
+pub fn is_malformed_entry(entry: &str) -> bool {
+    let trimmed = entry.trim();
+    trimmed.is_empty() || trimmed != entry || (entry.contains('"') && !entry.contains(';'))
+}
+
+/// Clean up a malformed entry by trimming whitespace and removing unneeded quotes
+/// Returns an empty string for empty segments, which callers should drop
+pub fn clean_entry(entry: &str) -> String {
+    let trimmed = entry.trim();
+    if trimmed.contains(';') {
+        // Quotes are what keeps this entry in one piece
+        return trimmed.to_string();
+    }
+    trimmed.replace('"', "").trim().to_string()
+}
+
+/// Lowercase a directory and drop quotes and trailing separators so paths compare by value
+/// Variables are left alone, so expand entries that may hold them first
+pub fn comparable(dir: &str) -> String {
+    dir.trim()
+        .trim_matches('"')
+        .trim_end_matches(['\\', '/'])
+        .replace('/', "\\")
+        .to_lowercase()
+}
+
+/// Strip quotes that are required to protect a semicolon inside an entry
+fn strip_required_quotes(entry: &str) -> &str {
+    if entry.contains(';') && entry.len() >= 2 && entry.starts_with('"') && entry.ends_with('"') {
+        &entry[1..entry.len() - 1]
+    } else {
+        entry
+    }
+}
+
+/// Check if an entry uses the `\\?\` extended-length prefix
+pub fn has_extended_prefix(path: &str) -> bool {
+    path.trim().trim_matches('"').starts_with(EXTENDED_PREFIX)
+}
+
+/// Check if the expanded entry reaches MAX_PATH (counted in UTF-16 units, like Windows does)
+pub fn exceeds_max_path(path: &str) -> bool {
+    expand_environment_variables(path).encode_utf16().count() >= MAX_PATH
+}
+
 /// Check if a path exists (file or directory)
+#[cfg(test)]
 pub fn path_exists(path: &str) -> bool {
     path_exists_with_remote(path, None)
 }
 
 /// Check if a path exists with optional remote computer support
+#[cfg(test)]
 pub fn path_exists_with_remote(path: &str, remote_computer: Option<&str>) -> bool {
-    if path.is_empty() {
-        return false;
-    }
+    path_exists_with_fs(path, remote_computer, &RealFileSystem)
+}
 
+/// Check if a path exists on the given filesystem, with optional remote computer support
+pub fn path_exists_with_fs(path: &str, remote_computer: Option<&str>, fs: &dyn FileSystem) -> bool {
     // Expand environment variables first
-    let expanded = expand_environment_variables(path);
+    expanded_path_exists(&expand_environment_variables(path), remote_computer, fs)
+}
+
+/// Check if an already expanded path exists
+fn expanded_path_exists(
+    expanded: &str,
+    remote_computer: Option<&str>,
+    fs: &dyn FileSystem,
+) -> bool {
+    if expanded.is_empty() {
+        return false;
+    }
+    fs.exists(&lookup_path(expanded, remote_computer))
+}
 
-    // If checking a remote path, convert to UNC
+/// Where an expanded entry is looked up, its UNC path when it is on a remote computer
+pub fn lookup_path(expanded: &str, remote_computer: Option<&str>) -> String {
+    // The extended-length prefix has to come off first, otherwise the entry looks like a UNC path
     if let Some(computer_name) = remote_computer {
-        if let Some(unc_path) = to_unc_path(&expanded, computer_name) {
-            // Try to access the UNC path
-            return Path::new(&unc_path).exists();
+        let local_form = expanded.strip_prefix(EXTENDED_PREFIX).unwrap_or(expanded);
+        if let Some(unc_path) = to_unc_path(local_form, computer_name) {
+            return unc_path;
         }
     }
 
     // Local path or UNC conversion failed - check locally
-    Path::new(&expanded).exists()
+    expanded.to_string()
 }
 
 /// Normalize a path by:
@@ -186,6 +714,15 @@ pub fn path_exists_with_remote(path: &str, remote_computer: Option<&str>) -> boo
 ///
 /// This is the opposite of the previous behavior - we want portable env vars, not expanded paths.
 pub fn normalize_path(path: &str) -> String {
+    normalize_path_with_fs(path, &RealFileSystem)
+}
+
+/// Normalize a path, resolving short names against the given filesystem
+pub fn normalize_path_with_fs(path: &str, fs: &dyn FileSystem) -> String {
+    normalize_with(path, fs, &EnvVars::capture())
+}
+
+fn normalize_with(path: &str, fs: &dyn FileSystem, env: &EnvVars) -> String {
     if path.is_empty() {
         return path.to_string();
     }
@@ -208,22 +745,20 @@ pub fn normalize_path(path: &str) -> String {
     cleaned = cleaned.trim().to_string();
 
     // First get the absolute expanded path for comparison
-    let mut expanded = expand_environment_variables(&cleaned);
+    let mut expanded = env.expand(&cleaned);
 
     // Remove \?\ prefix if present (this shouldn't be in PATH variables)
-    if let Some(stripped) = expanded.strip_prefix(r"\\?\") {
+    if let Some(stripped) = expanded.strip_prefix(EXTENDED_PREFIX) {
         expanded = stripped.to_string();
     }
 
     // Try to canonicalize to expand short names (8.3 format like PROGRA~1)
-    if let Ok(canonical) = std::fs::canonicalize(&expanded) {
-        if let Some(path_str) = canonical.to_str() {
-            // Canonicalize adds \\?\ prefix, remove it
-            if let Some(stripped) = path_str.strip_prefix(r"\\?\") {
-                expanded = stripped.to_string();
-            } else {
-                expanded = path_str.to_string();
-            }
+    if let Some(canonical) = fs.canonicalize(&expanded) {
+        // Canonicalize adds \\?\ prefix, remove it
+        if let Some(stripped) = canonical.strip_prefix(EXTENDED_PREFIX) {
+            expanded = stripped.to_string();
+        } else {
+            expanded = canonical;
         }
     }
 
@@ -234,107 +769,276 @@ pub fn normalize_path(path: &str) -> String {
         .to_string();
 
     // Now collapse to environment variables where possible
-    collapse_to_env_vars(&expanded)
-}
-
-/// Collapse an absolute path to use environment variables where possible
-/// Matches longest prefixes first and performs case-insensitive matching
-fn collapse_to_env_vars(path: &str) -> String {
-    // Environment variable mappings in priority order (longest/most specific first)
-    // This ensures we prefer %LOCALAPPDATA% over %USERPROFILE% for paths in AppData\Local
-    let env_var_mappings: Vec<(&str, String)> = vec![
-        (
-            "LOCALAPPDATA",
-            std::env::var("LOCALAPPDATA").unwrap_or_default(),
-        ),
-        ("APPDATA", std::env::var("APPDATA").unwrap_or_default()),
-        (
-            "PROGRAMFILES(X86)",
-            std::env::var("PROGRAMFILES(X86)").unwrap_or_default(),
-        ),
-        (
-            "PROGRAMFILES",
-            std::env::var("PROGRAMFILES").unwrap_or_default(),
-        ),
-        (
-            "PROGRAMDATA",
-            std::env::var("PROGRAMDATA").unwrap_or_default(),
-        ),
-        (
-            "USERPROFILE",
-            std::env::var("USERPROFILE").unwrap_or_default(),
-        ),
-        (
-            "SYSTEMROOT",
-            std::env::var("SYSTEMROOT").unwrap_or_default(),
-        ),
-        ("WINDIR", std::env::var("WINDIR").unwrap_or_default()),
-        ("TEMP", std::env::var("TEMP").unwrap_or_default()),
-        ("TMP", std::env::var("TMP").unwrap_or_default()),
-    ];
+    env.collapse(&expanded)
+}
 
-    let path_lower = path.to_lowercase();
+/// Whose profile the per-user variables (%USERPROFILE%, %APPDATA%, ...) are expanded for
+/// By default that is the account running pc, which is wrong for another user's or computer's PATH
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExpansionContext {
+    /// Profile directory standing in for %USERPROFILE%, e.g. C:\Users\alice
+    pub profile_root: Option<String>,
+    /// Entries never reported as dead or duplicate, `*` at the end covers everything below
+    pub ignored: Vec<String>,
+}
 
-    // Try to match against each environment variable (case-insensitive)
-    for (var_name, var_value) in env_var_mappings {
-        if var_value.is_empty() {
-            continue;
+impl ExpansionContext {
+    pub fn for_profile(profile_root: &str) -> Self {
+        Self {
+            profile_root: Some(profile_root.trim_end_matches(['\\', '/']).to_string()),
+            ignored: Vec::new(),
         }
+    }
 
-        let var_value_lower = var_value.to_lowercase();
-
-        // Check if the path starts with this env var value
-        if path_lower.starts_with(&var_value_lower) {
-            // Get the remaining part of the path
-            let remaining = &path[var_value.len()..];
+    /// The same account's profile at the standard location, for a remote computer
+    pub fn remote_default() -> Self {
+        let user = crate::event_log::current_user();
+        Self::for_profile(&format!(r"C:\Users\{}", user))
+    }
 
-            // If there's a remaining part, it should start with a separator
-            if remaining.is_empty() {
-                return format!("%{}%", var_name);
-            } else if remaining.starts_with('\\') || remaining.starts_with('/') {
-                return format!("%{}%{}", var_name, remaining);
-            }
+    /// Values of the per-user variables below the profile
+    fn overrides(&self) -> Vec<(&'static str, String)> {
+        let Some(root) = &self.profile_root else {
+            return Vec::new();
+        };
+        let local = format!(r"{}\AppData\Local", root);
+        let mut overrides = vec![
+            ("USERPROFILE", root.clone()),
+            ("APPDATA", format!(r"{}\AppData\Roaming", root)),
+            ("TEMP", format!(r"{}\Temp", local)),
+            ("TMP", format!(r"{}\Temp", local)),
+            ("LOCALAPPDATA", local),
+        ];
+        // HOMEDRIVE + HOMEPATH is the profile too, when it is on a drive
+        if let Some((drive, path)) = root.split_at_checked(2).filter(|(d, _)| d.ends_with(':')) {
+            overrides.push(("HOMEDRIVE", drive.to_string()));
+            overrides.push(("HOMEPATH", path.to_string()));
         }
+        overrides
     }
+}
 
-    // No matching environment variable found, return the path as-is
-    path.to_string()
+/// Environment variables that PATH entries are expanded with and collapsed to
+/// Read once per analysis instead of once per entry
+struct EnvVars {
+    /// (variable, value) in expansion order
+    expansions: Vec<(&'static str, String)>,
+    /// (variable, value, lowercase value) in priority order (longest/most specific first)
+    collapses: Vec<(&'static str, String, String)>,
+    #[cfg(not(windows))]
+    home: Option<String>,
 }
 
-/// Expand environment variables in a path string
-pub fn expand_environment_variables(path: &str) -> String {
-    let mut result = path.to_string();
-
-    // Common environment variables to expand
-    let vars = [
-        "USERPROFILE",
-        "PROGRAMFILES",
-        "PROGRAMFILES(X86)",
-        "PROGRAMDATA",
-        "APPDATA",
-        "LOCALAPPDATA",
-        "SYSTEMROOT",
-        "WINDIR",
-        "TEMP",
-        "TMP",
-        "HOMEDRIVE",
-        "HOMEPATH",
-    ];
+/// Common environment variables to expand
+const EXPAND_VARS: [&str; 12] = [
+    "USERPROFILE",
+    "PROGRAMFILES",
+    "PROGRAMFILES(X86)",
+    "PROGRAMDATA",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "SYSTEMROOT",
+    "WINDIR",
+    "TEMP",
+    "TMP",
+    "HOMEDRIVE",
+    "HOMEPATH",
+];
+
+/// Environment variable mappings in priority order (longest/most specific first)
+/// This ensures we prefer %LOCALAPPDATA% over %USERPROFILE% for paths in AppData\Local
+const COLLAPSE_VARS: [&str; 10] = [
+    "LOCALAPPDATA",
+    "APPDATA",
+    "PROGRAMFILES(X86)",
+    "PROGRAMFILES",
+    "PROGRAMDATA",
+    "USERPROFILE",
+    "SYSTEMROOT",
+    "WINDIR",
+    "TEMP",
+    "TMP",
+];
+
+impl EnvVars {
+    fn capture() -> Self {
+        Self::capture_for(&ExpansionContext::default())
+    }
+
+    fn capture_for(context: &ExpansionContext) -> Self {
+        let overrides = context.overrides();
+        let lookup = |var: &str| {
+            overrides
+                .iter()
+                .find(|(name, _)| *name == var)
+                .map(|(_, value)| value.clone())
+                .or_else(|| std::env::var(var).ok())
+        };
+        let expansions = EXPAND_VARS
+            .iter()
+            .filter_map(|&var| lookup(var).map(|value| (var, value)))
+            .collect();
+        let collapses = COLLAPSE_VARS
+            .iter()
+            .filter_map(|&var| {
+                let value = lookup(var).filter(|v| !v.is_empty())?;
+                let lower = value.to_lowercase();
+                Some((var, value, lower))
+            })
+            .collect();
+
+        Self {
+            expansions,
+            collapses,
+            #[cfg(not(windows))]
+            home: std::env::var("HOME").ok(),
+        }
+    }
+
+    fn expand(&self, path: &str) -> String {
+        // Most entries are plain directories, skip the replacements for them
+        if !path.contains(['%', '$', '~']) {
+            return path.to_string();
+        }
 
-    for var in &vars {
-        if let Ok(value) = std::env::var(var) {
+        let mut result = path.to_string();
+        for (var, value) in &self.expansions {
             // Try both %VAR% and ${VAR} formats
-            result = result.replace(&format!("%{}%", var), &value);
-            result = result.replace(&format!("${{{}}}", var), &value);
+            result = result.replace(&format!("%{}%", var), value);
+            result = result.replace(&format!("${{{}}}", var), value);
             // Case-insensitive replacement
             let var_lower = var.to_lowercase();
-            result = result.replace(&format!("%{}%", var_lower), &value);
+            result = result.replace(&format!("%{}%", var_lower), value);
+        }
+
+        // Shell profiles refer to the home directory as $HOME or ~
+        #[cfg(not(windows))]
+        if let Some(home) = &self.home {
+            result = result.replace("${HOME}", home).replace("$HOME", home);
+            if result == "~" || result.starts_with("~/") {
+                result = format!("{}{}", home, &result[1..]);
+            }
+        }
+
+        result
+    }
+
+    /// Collapse an absolute path to use environment variables where possible
+    /// Matches longest prefixes first and performs case-insensitive matching
+    fn collapse(&self, path: &str) -> String {
+        #[cfg(not(windows))]
+        if let Some(home) = &self.home {
+            let home = home.trim_end_matches('/');
+            if let Some(remaining) = path.strip_prefix(home) {
+                if !home.is_empty() && (remaining.is_empty() || remaining.starts_with('/')) {
+                    return format!("$HOME{}", remaining);
+                }
+            }
+        }
+
+        let path_lower = path.to_lowercase();
+
+        // Try to match against each environment variable (case-insensitive)
+        for (var_name, var_value, var_value_lower) in &self.collapses {
+            // Check if the path starts with this env var value
+            if path_lower.starts_with(var_value_lower.as_str()) {
+                // Get the remaining part of the path
+                let remaining = &path[var_value.len()..];
+
+                // If there's a remaining part, it should start with a separator
+                if remaining.is_empty() {
+                    return format!("%{}%", var_name);
+                } else if remaining.starts_with('\\') || remaining.starts_with('/') {
+                    return format!("%{}%{}", var_name, remaining);
+                }
+            }
         }
+
+        // No matching environment variable found, return the path as-is
+        path.to_string()
     }
+}
+
+/// Expand environment variables in a path string
+pub fn expand_environment_variables(path: &str) -> String {
+    EnvVars::capture().expand(path)
+}
 
+/// Expand entries the way Windows expands a REG_EXPAND_SZ value for a new process:
+/// per-user variables for the context's profile, any other %NAME% from this process,
+/// and names that are not set left as they are
+pub fn expand_entries(paths: &[String], context: &ExpansionContext) -> Vec<String> {
+    let env = EnvVars::capture_for(context);
+    paths
+        .iter()
+        .map(|path| expand_remaining_variables(&env.expand(path)))
+        .collect()
+}
+
+/// Replace the %NAME% references the common variables did not cover
+fn expand_remaining_variables(path: &str) -> String {
+    let mut result = String::with_capacity(path.len());
+    let mut rest = path;
+    while let Some(start) = rest.find('%') {
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('%') else {
+            break;
+        };
+        let name = &after[..end];
+        match std::env::var(name).ok().filter(|_| !name.is_empty()) {
+            Some(value) => {
+                result.push_str(&rest[..start]);
+                result.push_str(&value);
+                rest = &after[end + 1..];
+            }
+            // Like Windows, the closing % may open the next reference
+            None => {
+                result.push_str(&rest[..=start]);
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
     result
 }
 
+/// Entries inside another entry's directory, e.g. C:\Tools and C:\Tools\bin, as
+/// (ancestor, descendant) positions in `dirs` (expanded directories in search order)
+/// Entries naming the same directory are duplicates, not nested
+pub fn find_nested(dirs: &[String]) -> Vec<(usize, usize)> {
+    let keys: Vec<String> = dirs.iter().map(|dir| nesting_key(dir)).collect();
+    let mut nested = Vec::new();
+    for (outer, ancestor) in keys.iter().enumerate() {
+        for (inner, descendant) in keys.iter().enumerate() {
+            if key_is_inside(descendant, ancestor) {
+                nested.push((outer, inner));
+            }
+        }
+    }
+    nested
+}
+
+/// Whether an expanded directory is somewhere below another one
+pub fn is_inside(dir: &str, ancestor: &str) -> bool {
+    key_is_inside(&nesting_key(dir), &nesting_key(ancestor))
+}
+
+/// Directory compared case-insensitively with one kind of separator and no trailing one
+fn nesting_key(dir: &str) -> String {
+    dir.trim()
+        .trim_matches('"')
+        .replace('/', "\\")
+        .trim_end_matches('\\')
+        .to_lowercase()
+}
+
+fn key_is_inside(key: &str, ancestor: &str) -> bool {
+    !ancestor.is_empty()
+        && key.len() > ancestor.len() + 1
+        && key.starts_with(ancestor)
+        && key.as_bytes()[ancestor.len()] == b'\\'
+}
+
 /// Find all duplicate paths across both scopes
 #[cfg(test)]
 pub fn find_all_duplicates(user_paths: &[String], machine_paths: &[String]) -> HashSet<String> {
@@ -364,7 +1068,7 @@ pub fn find_all_duplicates(user_paths: &[String], machine_paths: &[String]) -> H
 }
 
 /// Find all dead paths in a list
-#[cfg(test)]
+#[cfg(all(test, windows))]
 pub fn find_dead_paths(paths: &[String]) -> Vec<usize> {
     paths
         .iter()
@@ -377,6 +1081,7 @@ pub fn find_dead_paths(paths: &[String]) -> Vec<usize> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::filesystem::MemoryFileSystem;
 
     #[test]
     fn test_normalize_path_env_vars() {
@@ -404,6 +1109,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(windows)]
     fn test_expand_environment_variables() {
         // Test %VAR% format
         let userprofile = std::env::var("USERPROFILE").unwrap();
@@ -418,6 +1124,256 @@ mod tests {
     }
 
     #[test]
+    fn test_analyze_with_memory_fs() {
+        let fs = MemoryFileSystem::new()
+            .with_dir(r"D:\Tools\Bin")
+            .with_dir(r"D:\Shared");
+        let paths = vec![
+            r"D:\Tools\Bin".to_string(),
+            r"d:\tools\bin\".to_string(),
+            r"D:\Gone".to_string(),
+            r"d:\tools".to_string(),
+        ];
+        let other = vec![r"D:\Shared".to_string(), r"D:\Gone".to_string()];
+
+        let results = analyze_paths_with_fs(&paths, &other, None, &fs);
+
+        // Same directory twice (case and trailing slash differ)
+        assert_eq!(results[0].status, PathStatus::Duplicate);
+        assert_eq!(results[1].status, PathStatus::Duplicate);
+        assert_eq!(results[1].normalized, r"D:\Tools\Bin");
+
+        // Missing, and also in the other scope
+        assert!(results[2].is_dead());
+        assert_eq!(results[2].status, PathStatus::DeadDuplicate);
+
+        // Exists, but the spelling differs from the canonical one
+        assert_eq!(results[3].normalized, r"D:\Tools");
+        assert_eq!(results[3].status, PathStatus::NonNormalized);
+    }
+
+    #[test]
+    fn test_analyze_without_fs_checks() {
+        let paths = vec![
+            r"Z:\Hung\Share".to_string(),
+            r"z:\hung\share\".to_string(),
+            r"D:\Gone".to_string(),
+        ];
+
+        let results =
+            analyze_paths_with_fs(&paths, &[], None, &crate::filesystem::UncheckedFileSystem);
+
+        // Nothing was looked up, so nothing is judged, but duplicates are still found as text
+        assert!(results
+            .iter()
+            .all(|info| info.status == PathStatus::Unchecked && !info.is_dead()));
+        assert!(results[0].is_duplicate && results[1].is_duplicate);
+        assert!(!results[2].is_duplicate);
+        assert_eq!(results[1].normalized, r"z:\hung\share");
+    }
+
+    #[test]
+    fn test_analyze_scopes_matches_per_scope_analysis() {
+        let fs = MemoryFileSystem::new()
+            .with_dir(r"C:\Tools")
+            .with_dir(r"C:\Shared\bin");
+        let machine = vec![r"C:\Shared\bin".to_string(), r"C:\Gone".to_string()];
+        let user = vec![
+            r"C:\Tools".to_string(),
+            r"c:\shared\BIN\".to_string(),
+            r"C:\Tools".to_string(),
+        ];
+
+        let (machine_info, user_info) = analyze_scopes_with_fs(&machine, &user, &fs);
+        let summary = |info: &[PathInfo]| {
+            info.iter()
+                .map(|i| (i.normalized.clone(), i.status, i.is_duplicate))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            summary(&machine_info),
+            summary(&analyze_paths_with_fs(&machine, &user, None, &fs))
+        );
+        assert_eq!(
+            summary(&user_info),
+            summary(&analyze_paths_with_fs(&user, &machine, None, &fs))
+        );
+        assert_eq!(machine_info[0].status, PathStatus::Duplicate);
+        assert_eq!(machine_info[1].status, PathStatus::Dead);
+        assert!(user_info.iter().all(|i| i.is_duplicate));
+    }
+
+    #[test]
+    fn test_expansion_context_for_another_profile() {
+        let fs = MemoryFileSystem::new()
+            .with_dir(r"\\SERVER01\C$\Users\alice\bin")
+            .with_dir(r"\\SERVER01\C$\Users\alice\AppData\Local\Tools");
+        let paths = vec![
+            r"%USERPROFILE%\bin".to_string(),
+            r"%LOCALAPPDATA%\Tools".to_string(),
+            r"%APPDATA%\Missing".to_string(),
+        ];
+        let context = ExpansionContext::for_profile(r"C:\Users\alice\");
+
+        let info = analyze_paths_in(&paths, &[], Some("SERVER01"), &fs, &context);
+        assert!(!info[0].is_dead());
+        assert!(!info[1].is_dead());
+        assert!(info[2].is_dead());
+
+        let env = EnvVars::capture_for(&context);
+        assert_eq!(env.expand(r"%HOMEDRIVE%%HOMEPATH%\x"), r"C:\Users\alice\x");
+        assert_eq!(env.expand(r"%TEMP%"), r"C:\Users\alice\AppData\Local\Temp");
+    }
+
+    #[test]
+    fn test_expand_entries_like_windows() {
+        let context = ExpansionContext::for_profile(r"C:\Users\alice");
+        let path = std::env::var("PATH").unwrap();
+        let expanded = expand_entries(
+            &[
+                r"%USERPROFILE%\bin".to_string(),
+                r"%PC_NOT_SET%\bin".to_string(),
+                "%PC_NOT_SET%PATH%".to_string(),
+                "100%".to_string(),
+            ],
+            &context,
+        );
+        assert_eq!(expanded[0], r"C:\Users\alice\bin");
+        assert_eq!(expanded[1], r"%PC_NOT_SET%\bin");
+        assert_eq!(expanded[2], format!("%PC_NOT_SET{}", path));
+        assert_eq!(expanded[3], "100%");
+    }
+
+    #[test]
+    fn test_insecure_before_system_dirs() {
+        let fs = MemoryFileSystem::new()
+            .with_dir(r"%SystemRoot%\System32")
+            .with_dir(r"C:\Program Files\App")
+            .with_insecure_dir(r"C:\Python27")
+            .with_insecure_dir(r"C:\Late");
+        let paths = vec![
+            r"C:\Python27".to_string(),
+            r"C:\Program Files\App".to_string(),
+            r"%SystemRoot%\System32".to_string(),
+            r"C:\Late".to_string(),
+        ];
+
+        let info = analyze_paths_with_fs(&paths, &[], None, &fs);
+        assert_eq!(info[0].status, PathStatus::Insecure);
+        assert!(!info[1].is_insecure);
+        // Searched after the system directories, so it cannot shadow system commands
+        assert!(!info[3].is_insecure);
+        assert_eq!(info[3].status, PathStatus::Valid);
+
+        // Without a system directory in the list nothing is shadowed
+        let info = analyze_paths_with_fs(&paths[..2], &[], None, &fs);
+        assert!(info.iter().all(|i| !i.is_insecure));
+
+        // Remote permissions cannot be read
+        let info = analyze_paths_with_fs(&paths, &[], Some("SERVER01"), &fs);
+        assert!(info.iter().all(|i| !i.is_insecure));
+    }
+
+    #[test]
+    fn test_app_alias_folder_is_never_dead() {
+        let fs = MemoryFileSystem::new()
+            .with_dir(r"%SystemRoot%\System32")
+            .with_insecure_dir(r"C:\Users\alice\AppData\Local\Microsoft\WindowsApps");
+        let paths = vec![
+            r"%LOCALAPPDATA%\Microsoft\WindowsApps".to_string(),
+            r"C:\Users\alice\AppData\Local\Microsoft\WindowsApps".to_string(),
+            r"%SystemRoot%\System32".to_string(),
+            r"C:\Gone\Microsoft\WindowsApps\Sub".to_string(),
+        ];
+
+        let info = analyze_paths_with_fs(&paths, &[], None, &fs);
+        // Not listable in the memory filesystem, still not dead
+        assert!(info[0].is_app_aliases);
+        assert!(info[0].exists);
+        assert_eq!(info[0].status, PathStatus::Valid);
+        // Its permissions are not a hijack risk to report
+        assert!(info[1].is_app_aliases);
+        assert!(!info[1].is_insecure);
+        assert!(!info[3].is_app_aliases);
+        assert_eq!(info[3].status, PathStatus::Dead);
+    }
+
+    #[test]
+    fn test_ignored_entries_are_never_flagged() {
+        let fs = MemoryFileSystem::new().with_dir(r"C:\Tools");
+        let paths = vec![
+            r"C:\agent\_work\1\bin".to_string(),
+            r"C:\agent\_work\1\bin".to_string(),
+            r"C:\Ephemeral\".to_string(),
+            r"C:\Tools".to_string(),
+            r"C:\agent\other".to_string(),
+        ];
+        let context = ExpansionContext {
+            ignored: vec![r"c:\agent\_work\*".to_string(), r"C:\Ephemeral".to_string()],
+            ..ExpansionContext::default()
+        };
+
+        let (machine, user) = analyze_scopes_in(&[r"C:\Tools".to_string()], &paths, &fs, &context);
+        for info in &user[..3] {
+            assert!(info.is_ignored);
+            assert!(!info.is_dead());
+            assert!(!info.is_duplicate);
+            assert_eq!(info.status, PathStatus::Valid);
+        }
+        // Entries off the list are judged as usual
+        assert_eq!(user[3].status, PathStatus::Duplicate);
+        assert!(machine[0].is_duplicate);
+        assert_eq!(user[4].status, PathStatus::Dead);
+    }
+
+    #[test]
+    fn test_analyze_drive_kinds_with_memory_fs() {
+        let fs = MemoryFileSystem::new()
+            .with_dir(r"D:\Tools")
+            .with_symlink(r"D:\Linked")
+            .with_removable_drive('E')
+            .with_mapped_drive('X', MappedDrive::Disconnected(r"\\nas\tools".to_string()));
+        let paths = vec![
+            r"D:\Tools".to_string(),
+            r"D:\Linked".to_string(),
+            r"E:\PortableApps".to_string(),
+            r"X:\bin".to_string(),
+        ];
+
+        let results = analyze_paths_with_fs(&paths, &[], None, &fs);
+
+        assert_eq!(results[0].kind, PathKind::Local);
+        assert_eq!(results[1].kind, PathKind::Junction);
+
+        // Ejected USB stick: not dead
+        assert!(results[2].is_media_missing);
+        assert_eq!(results[2].status, PathStatus::Removable);
+        assert!(!results[2].is_dead());
+
+        // Disconnected mapped drive: not dead either
+        assert_eq!(results[3].kind, PathKind::MappedDrive);
+        assert_eq!(results[3].status, PathStatus::OfflineDrive);
+        assert!(!results[3].is_dead());
+
+        // Drive lookups are skipped for remote entries
+        let remote = analyze_paths_with_fs(&paths, &[], Some("SERVER"), &fs);
+        assert!(!remote[3].is_offline_drive);
+        assert!(remote[3].is_dead());
+    }
+
+    #[test]
+    fn test_classify_path() {
+        assert_eq!(classify_path(r"C:\Tools"), PathKind::Local);
+        assert_eq!(classify_path(r"%SystemRoot%\System32"), PathKind::EnvVar);
+        assert_eq!(classify_path(r#""%ProgramFiles%\App""#), PathKind::EnvVar);
+        assert_eq!(classify_path(r"\\server\share\bin"), PathKind::Unc);
+        assert_eq!(classify_path(r"\\?\UNC\server\share"), PathKind::Unc);
+        assert_eq!(classify_path(r"\\?\C:\Tools"), PathKind::Local);
+        assert_eq!(classify_path(""), PathKind::Local);
+    }
+
+    #[test]
+    #[cfg(windows)]
     fn test_path_exists() {
         assert!(path_exists(r"C:\Windows"));
         assert!(!path_exists(r"C:\ThisPathDoesNotExist123456"));
@@ -429,6 +1385,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(windows)]
     fn test_path_exists_with_env_var() {
         // Test that environment variables are expanded before checking
         assert!(path_exists(r"%SYSTEMROOT%"));
@@ -523,6 +1480,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(windows)]
     fn test_find_dead_paths() {
         let paths = vec![
             r"C:\Windows".to_string(),
@@ -542,6 +1500,7 @@ mod tests {
         assert!(PathStatus::Dead.is_problematic());
         assert!(PathStatus::Duplicate.is_problematic());
         assert!(PathStatus::DeadDuplicate.is_problematic());
+        assert!(PathStatus::Malformed.is_problematic());
         assert!(!PathStatus::Valid.is_problematic());
         assert!(!PathStatus::NonNormalized.is_problematic());
     }
@@ -553,6 +1512,10 @@ mod tests {
         assert_eq!(PathStatus::Duplicate.description(), "Duplicate");
         assert_eq!(PathStatus::NonNormalized.description(), "Can be normalized");
         assert_eq!(PathStatus::DeadDuplicate.description(), "Dead & Duplicate");
+        assert_eq!(
+            PathStatus::Malformed.description(),
+            "Malformed (empty, padded, or quoted)"
+        );
     }
 
     #[test]
@@ -565,6 +1528,18 @@ mod tests {
             exists: true,
             is_duplicate: false,
             needs_normalization: false,
+            is_malformed: false,
+            has_extended_prefix: false,
+            exceeds_max_path: false,
+            is_offline_drive: false,
+            is_removable_drive: false,
+            is_media_missing: false,
+            is_onedrive: false,
+            is_insecure: false,
+            is_app_aliases: false,
+            is_ignored: false,
+            kind: PathKind::Local,
+            modified: None,
         };
         assert_eq!(determine_status(&info), PathStatus::Valid);
 
@@ -576,6 +1551,18 @@ mod tests {
             exists: false,
             is_duplicate: false,
             needs_normalization: false,
+            is_malformed: false,
+            has_extended_prefix: false,
+            exceeds_max_path: false,
+            is_offline_drive: false,
+            is_removable_drive: false,
+            is_media_missing: false,
+            is_onedrive: false,
+            is_insecure: false,
+            is_app_aliases: false,
+            is_ignored: false,
+            kind: PathKind::Local,
+            modified: None,
         };
         assert_eq!(determine_status(&info), PathStatus::Dead);
 
@@ -587,6 +1574,18 @@ mod tests {
             exists: true,
             is_duplicate: true,
             needs_normalization: false,
+            is_malformed: false,
+            has_extended_prefix: false,
+            exceeds_max_path: false,
+            is_offline_drive: false,
+            is_removable_drive: false,
+            is_media_missing: false,
+            is_onedrive: false,
+            is_insecure: false,
+            is_app_aliases: false,
+            is_ignored: false,
+            kind: PathKind::Local,
+            modified: None,
         };
         assert_eq!(determine_status(&info), PathStatus::Duplicate);
 
@@ -598,6 +1597,18 @@ mod tests {
             exists: true,
             is_duplicate: false,
             needs_normalization: true,
+            is_malformed: false,
+            has_extended_prefix: false,
+            exceeds_max_path: false,
+            is_offline_drive: false,
+            is_removable_drive: false,
+            is_media_missing: false,
+            is_onedrive: false,
+            is_insecure: false,
+            is_app_aliases: false,
+            is_ignored: false,
+            kind: PathKind::Local,
+            modified: None,
         };
         assert_eq!(determine_status(&info), PathStatus::NonNormalized);
 
@@ -609,8 +1620,172 @@ mod tests {
             exists: false,
             is_duplicate: true,
             needs_normalization: false,
+            is_malformed: false,
+            has_extended_prefix: false,
+            exceeds_max_path: false,
+            is_offline_drive: false,
+            is_removable_drive: false,
+            is_media_missing: false,
+            is_onedrive: false,
+            is_insecure: false,
+            is_app_aliases: false,
+            is_ignored: false,
+            kind: PathKind::Local,
+            modified: None,
         };
         assert_eq!(determine_status(&info), PathStatus::DeadDuplicate);
+
+        // Malformed wins over everything else
+        let info = PathInfo {
+            original: "".to_string(),
+            normalized: "".to_string(),
+            status: PathStatus::Valid,
+            exists: false,
+            is_duplicate: true,
+            needs_normalization: false,
+            is_malformed: true,
+            has_extended_prefix: false,
+            exceeds_max_path: false,
+            is_offline_drive: false,
+            is_removable_drive: false,
+            is_media_missing: false,
+            is_onedrive: false,
+            is_insecure: false,
+            is_app_aliases: false,
+            is_ignored: false,
+            kind: PathKind::Local,
+            modified: None,
+        };
+        assert_eq!(determine_status(&info), PathStatus::Malformed);
+
+        // Missing entry on a disconnected mapped drive
+        let info = PathInfo {
+            original: "X:\\Tools".to_string(),
+            normalized: "X:\\Tools".to_string(),
+            status: PathStatus::Valid,
+            exists: false,
+            is_duplicate: false,
+            needs_normalization: false,
+            is_malformed: false,
+            has_extended_prefix: false,
+            exceeds_max_path: false,
+            is_offline_drive: true,
+            is_removable_drive: false,
+            is_media_missing: false,
+            is_onedrive: false,
+            is_insecure: false,
+            is_app_aliases: false,
+            is_ignored: false,
+            kind: PathKind::Local,
+            modified: None,
+        };
+        assert_eq!(determine_status(&info), PathStatus::OfflineDrive);
+        assert!(!info.is_dead());
+
+        // Entry on a USB stick that has been ejected
+        let info = PathInfo {
+            original: "E:\\PortableApps".to_string(),
+            normalized: "E:\\PortableApps".to_string(),
+            status: PathStatus::Valid,
+            exists: false,
+            is_duplicate: false,
+            needs_normalization: false,
+            is_malformed: false,
+            has_extended_prefix: false,
+            exceeds_max_path: false,
+            is_offline_drive: false,
+            is_removable_drive: true,
+            is_media_missing: true,
+            is_onedrive: false,
+            is_insecure: false,
+            is_app_aliases: false,
+            is_ignored: false,
+            kind: PathKind::Local,
+            modified: None,
+        };
+        assert_eq!(determine_status(&info), PathStatus::Removable);
+        assert!(!info.is_dead());
+
+        // Media inserted but the directory is gone
+        let info = PathInfo {
+            exists: false,
+            is_media_missing: false,
+            ..info
+        };
+        assert_eq!(determine_status(&info), PathStatus::Dead);
+        assert!(info.is_dead());
+    }
+
+    #[test]
+    fn test_has_extended_prefix() {
+        assert!(has_extended_prefix(r"\\?\C:\Tools"));
+        assert!(has_extended_prefix(r#""\\?\C:\Tools""#));
+        assert!(!has_extended_prefix(r"C:\Tools"));
+        assert!(!has_extended_prefix(r"\\server\share"));
+    }
+
+    #[test]
+    fn test_exceeds_max_path() {
+        assert!(!exceeds_max_path(r"C:\Windows"));
+        let long_path = format!(r"C:\{}", "a".repeat(MAX_PATH));
+        assert!(exceeds_max_path(&long_path));
+
+        let info = analyze_paths(&[long_path], &[]);
+        assert!(info[0].exceeds_max_path);
+        assert!(!info[0].has_extended_prefix);
+    }
+
+    #[test]
+    fn test_path_exists_with_extended_prefix() {
+        let systemroot = std::env::var("SYSTEMROOT").unwrap_or_default();
+        if !systemroot.is_empty() {
+            let prefixed = format!(r"\\?\{}", systemroot);
+            assert!(path_exists(&prefixed));
+
+            let info = analyze_paths(&[prefixed], &[]);
+            assert!(info[0].has_extended_prefix);
+            assert!(info[0].exists);
+        }
+    }
+
+    #[test]
+    fn test_is_malformed_entry() {
+        assert!(is_malformed_entry(""));
+        assert!(is_malformed_entry("   "));
+        assert!(is_malformed_entry(r" C:\Windows"));
+        assert!(is_malformed_entry(r"C:\Windows "));
+        assert!(is_malformed_entry(r#""C:\Program Files\App""#));
+        assert!(!is_malformed_entry(r"C:\Windows"));
+        // Quotes protecting a semicolon are legitimate
+        assert!(!is_malformed_entry(r#""C:\Odd;Name""#));
+    }
+
+    #[test]
+    fn test_comparable() {
+        assert_eq!(comparable(r#" "C:\Tools\Bin\" "#), r"c:\tools\bin");
+        assert_eq!(comparable("C:/Tools/bin/"), r"c:\tools\bin");
+        assert_eq!(comparable(r"%ProgramFiles%\Git"), r"%programfiles%\git");
+        assert_eq!(comparable(r"\\"), "");
+    }
+
+    #[test]
+    fn test_clean_entry() {
+        assert_eq!(clean_entry(""), "");
+        assert_eq!(clean_entry("  "), "");
+        assert_eq!(clean_entry(r" C:\Windows "), r"C:\Windows");
+        assert_eq!(
+            clean_entry(r#""C:\Program Files\App""#),
+            r"C:\Program Files\App"
+        );
+        assert_eq!(clean_entry(r#" "C:\Odd;Name" "#), r#""C:\Odd;Name""#);
+    }
+
+    #[test]
+    fn test_required_quotes_not_flagged_for_normalization() {
+        let paths = vec![r#""C:\NonExistent\Odd;Name""#.to_string()];
+        let info = analyze_paths(&paths, &[]);
+        assert!(!info[0].is_malformed);
+        assert!(!info[0].needs_normalization);
     }
 
     #[test]
@@ -826,7 +2001,7 @@ mod tests {
                 info[0].original,
                 info[0].normalized
             );
-            assert_eq!(info[0].status, PathStatus::NonNormalized);
+            assert_eq!(info[0].status, PathStatus::Malformed);
 
             // Test unbalanced quote (leading)
             let unbalanced_path = format!(r#""{}\System32"#, systemroot);
@@ -838,7 +2013,7 @@ mod tests {
                 info[0].needs_normalization,
                 "Unbalanced quoted path should be flagged as needing normalization"
             );
-            assert_eq!(info[0].status, PathStatus::NonNormalized);
+            assert_eq!(info[0].status, PathStatus::Malformed);
         }
     }
 
@@ -879,6 +2054,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(windows)]
     fn test_path_exists_with_remote() {
         // Test local path without remote computer
         assert!(path_exists_with_remote(r"C:\Windows", None));
@@ -924,4 +2100,105 @@ mod tests {
             }
         }
     }
+
+    // Filesystem where every directory exists, so benchmarks measure the analyzer itself
+    struct EverythingExists;
+
+    impl FileSystem for EverythingExists {
+        fn exists(&self, _path: &str) -> bool {
+            true
+        }
+        fn canonicalize(&self, _path: &str) -> Option<String> {
+            None
+        }
+        fn is_symlink(&self, _path: &str) -> bool {
+            false
+        }
+        fn mapped_drive(&self, _letter: char) -> MappedDrive {
+            MappedDrive::NotMapped
+        }
+        fn is_removable_drive(&self, _letter: char) -> bool {
+            false
+        }
+        fn is_writable_by_all_users(&self, _path: &str) -> bool {
+            false
+        }
+        fn modified(&self, _path: &str) -> Option<SystemTime> {
+            None
+        }
+    }
+
+    // A realistic mix of env-var, absolute and duplicate entries
+    fn large_path_set(count: usize, offset: usize) -> Vec<String> {
+        (0..count)
+            .map(|i| match i % 4 {
+                0 => format!(r"%PROGRAMFILES%\Vendor{}\bin", i + offset),
+                1 => format!(r"C:\Tools\Tool{}", i + offset),
+                2 => format!(r"%USERPROFILE%\.local\app{}\bin\", i / 2),
+                _ => format!(r#""D:\SDK {}\bin""#, i / 8),
+            })
+            .collect()
+    }
+
+    fn bench<F: FnMut()>(label: &str, iterations: u32, mut f: F) {
+        f(); // Warm up
+        let start = std::time::Instant::now();
+        for _ in 0..iterations {
+            f();
+        }
+        println!("{}: {:?} per run", label, start.elapsed() / iterations);
+    }
+
+    // Benchmarks, run with: cargo test --release bench_ -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_analyze_large_path_set() {
+        let user = large_path_set(500, 0);
+        let machine = large_path_set(500, 250);
+        bench("analyze 500 USER vs 500 MACHINE", 20, || {
+            std::hint::black_box(analyze_paths_with_fs(
+                &user,
+                &machine,
+                None,
+                &EverythingExists,
+            ));
+        });
+        bench("analyze both scopes (reanalyze)", 20, || {
+            std::hint::black_box(analyze_scopes_with_fs(&machine, &user, &EverythingExists));
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_find_duplicates_large_path_set() {
+        let user = large_path_set(500, 0);
+        let machine = large_path_set(500, 250);
+        bench("find_all_duplicates 500 + 500", 20, || {
+            std::hint::black_box(find_all_duplicates(&user, &machine));
+        });
+        bench("normalize 500 entries", 20, || {
+            for path in &user {
+                std::hint::black_box(normalize_path_with_fs(path, &EverythingExists));
+            }
+        });
+    }
+
+    #[test]
+    fn test_find_nested() {
+        let dirs: Vec<String> = [
+            r"C:\Tools\bin",
+            r"C:\Tools\",
+            r"C:\ToolsExtra",
+            r"c:/tools/bin/x64",
+            r"C:\Tools",
+            "",
+        ]
+        .iter()
+        .map(|dir| dir.to_string())
+        .collect();
+        assert_eq!(
+            find_nested(&dirs),
+            vec![(0, 3), (1, 0), (1, 3), (4, 0), (4, 3)]
+        );
+    }
 }
@@ -0,0 +1,394 @@
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::registry::{PathScope, RegistryBackend};
+
+/// Markers around the block pc owns inside a shell profile
+const BLOCK_START: &str = "# >>> path-commander >>>";
+const BLOCK_END: &str = "# <<< path-commander <<<";
+
+/// How a PATH file lists its entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    /// Shell script with a managed `export PATH=...` block
+    Shell,
+    /// One directory per line, as in macOS /etc/paths.d
+    Lines,
+}
+
+/// A file that PATH entries are declared in
+#[derive(Debug, Clone)]
+pub struct PathFile {
+    pub path: PathBuf,
+    pub format: FileFormat,
+}
+
+/// PATH entries declared in shell startup files (experimental, macOS and Linux)
+/// USER entries live in a managed block of the user's shell profile, MACHINE
+/// entries in a file under /etc that only root can write
+pub struct ShellProfiles {
+    user: PathFile,
+    machine: PathFile,
+}
+
+impl ShellProfiles {
+    /// Pick the profile of the login shell and the system-wide file for this OS
+    pub fn new() -> Self {
+        let home = std::env::var("HOME").unwrap_or_default();
+        let shell = std::env::var("SHELL").unwrap_or_default();
+        let profile = if shell.ends_with("zsh") {
+            ".zshrc"
+        } else if shell.ends_with("bash") {
+            ".bashrc"
+        } else {
+            ".profile"
+        };
+
+        let machine = if cfg!(target_os = "macos") {
+            PathFile {
+                path: PathBuf::from("/etc/paths.d/path-commander"),
+                format: FileFormat::Lines,
+            }
+        } else {
+            PathFile {
+                path: PathBuf::from("/etc/profile.d/path-commander.sh"),
+                format: FileFormat::Shell,
+            }
+        };
+
+        Self {
+            user: PathFile {
+                path: Path::new(&home).join(profile),
+                format: FileFormat::Shell,
+            },
+            machine,
+        }
+    }
+
+    fn file(&self, scope: PathScope) -> &PathFile {
+        match scope {
+            PathScope::User => &self.user,
+            PathScope::Machine => &self.machine,
+        }
+    }
+}
+
+impl RegistryBackend for ShellProfiles {
+    fn read_paths(&self, scope: PathScope) -> Result<Vec<String>> {
+        let file = self.file(scope);
+
+        // A missing file just means nothing has been declared yet
+        let content = match std::fs::read_to_string(&file.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!(
+                        "Failed to read {} paths from {}",
+                        scope.as_str(),
+                        file.path.display()
+                    )
+                })
+            }
+        };
+
+        Ok(match file.format {
+            FileFormat::Shell => parse_block(&content),
+            FileFormat::Lines => parse_lines(&content),
+        })
+    }
+
+    fn write_paths(&mut self, scope: PathScope, paths: &[String]) -> Result<()> {
+        let file = self.file(scope);
+
+        // Only the managed block of a shell file is ours, keep everything around it
+        let content = match file.format {
+            FileFormat::Shell => {
+                let existing = std::fs::read_to_string(&file.path).unwrap_or_default();
+                replace_block(&existing, paths)?
+            }
+            FileFormat::Lines => format_lines(paths),
+        };
+
+        std::fs::write(&file.path, content).with_context(|| {
+            let hint = match scope {
+                PathScope::Machine => " Run pc with sudo to edit MACHINE paths.",
+                PathScope::User => "",
+            };
+            format!(
+                "Failed to write {} paths to {}.{}",
+                scope.as_str(),
+                file.path.display(),
+                hint
+            )
+        })
+    }
+
+    fn last_modified(&self, scope: PathScope) -> Option<std::time::SystemTime> {
+        std::fs::metadata(&self.file(scope).path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+}
+
+/// Extract the entries of the managed `export PATH=` line
+fn parse_block(content: &str) -> Vec<String> {
+    let mut in_block = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if line == BLOCK_START {
+            in_block = true;
+        } else if line == BLOCK_END {
+            break;
+        } else if in_block {
+            if let Some(value) = line.strip_prefix("export PATH=") {
+                let value = value.strip_prefix('"').unwrap_or(value);
+                let value = value.strip_suffix('"').unwrap_or(value);
+                return value
+                    .split(':')
+                    .filter(|entry| !entry.is_empty() && *entry != "$PATH" && *entry != "${PATH}")
+                    .map(unescape_entry)
+                    .collect();
+            }
+        }
+    }
+    Vec::new()
+}
+
+/// Build the managed block that prepends the entries to PATH
+fn format_block(paths: &[String]) -> Result<String> {
+    let mut entries = Vec::new();
+    for entry in paths {
+        if entry.contains(':') {
+            bail!(
+                "{} contains ':', which separates entries in a shell PATH",
+                entry
+            );
+        }
+        entries.push(escape_entry(entry));
+    }
+    entries.push("$PATH".to_string());
+    Ok(format!(
+        "{}\n# Managed by Path Commander, edits inside this block are overwritten\nexport PATH=\"{}\"\n{}\n",
+        BLOCK_START,
+        entries.join(":"),
+        BLOCK_END
+    ))
+}
+
+/// Escape an entry for the double-quoted PATH line, so sourcing the profile runs nothing
+/// $NAME and ${NAME} stay variable references like $HOME/bin, any other $ is literal
+fn escape_entry(entry: &str) -> String {
+    let mut escaped = String::new();
+    for (i, c) in entry.char_indices() {
+        match c {
+            '\\' | '"' | '`' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '$' if !is_variable_reference(&entry[i + 1..]) => escaped.push_str("\\$"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Whether the text after a `$` is a plain NAME or {NAME}
+fn is_variable_reference(rest: &str) -> bool {
+    let name = |text: &str| {
+        let len = text
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(text.len());
+        (len > 0 && !text.starts_with(|c: char| c.is_ascii_digit())).then_some(len)
+    };
+    match rest.strip_prefix('{') {
+        Some(braced) => name(braced).is_some_and(|len| braced[len..].starts_with('}')),
+        None => name(rest).is_some(),
+    }
+}
+
+/// Undo escape_entry, a backslash before anything else stays as the shell keeps it
+fn unescape_entry(entry: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = entry.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(&next) if c == '\\' && matches!(next, '\\' | '"' | '$' | '`') => {
+                unescaped.push(next);
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Replace the managed block in a shell file, appending it if there is none
+/// and dropping it when there are no entries left
+fn replace_block(content: &str, paths: &[String]) -> Result<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let start = lines.iter().position(|l| l.trim() == BLOCK_START);
+    let end = lines.iter().position(|l| l.trim() == BLOCK_END);
+    let block = if paths.is_empty() {
+        String::new()
+    } else {
+        format_block(paths)?
+    };
+
+    let mut result = String::new();
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => {
+            for line in &lines[..start] {
+                result.push_str(line);
+                result.push('\n');
+            }
+            result.push_str(&block);
+            for line in &lines[end + 1..] {
+                result.push_str(line);
+                result.push('\n');
+            }
+        }
+        _ => {
+            result.push_str(content);
+            if !block.is_empty() {
+                if !result.is_empty() && !result.ends_with('\n') {
+                    result.push('\n');
+                }
+                if !result.is_empty() && !result.ends_with("\n\n") {
+                    result.push('\n');
+                }
+                result.push_str(&block);
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Read a one-directory-per-line file, skipping blanks and comments
+fn parse_lines(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.to_string())
+        .collect()
+}
+
+fn format_lines(paths: &[String]) -> String {
+    paths.iter().map(|path| format!("{}\n", path)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(entries: &[&str]) -> Vec<String> {
+        entries.iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_block() {
+        let content = format!(
+            "alias ll='ls -l'\nexport PATH=\"/ignored:$PATH\"\n{}\nexport PATH=\"$HOME/bin:/opt/tools:$PATH\"\n{}\n",
+            BLOCK_START, BLOCK_END
+        );
+        assert_eq!(parse_block(&content), paths(&["$HOME/bin", "/opt/tools"]));
+        assert!(parse_block("export PATH=\"/usr/local/bin:$PATH\"\n").is_empty());
+    }
+
+    #[test]
+    fn test_replace_block() {
+        // Appended after the existing profile when there is no block yet
+        let profile = "alias ll='ls -l'\n";
+        let updated = replace_block(profile, &paths(&["/opt/a"])).unwrap();
+        assert!(updated.starts_with("alias ll='ls -l'\n\n# >>> path-commander >>>\n"));
+        assert_eq!(parse_block(&updated), paths(&["/opt/a"]));
+
+        // Replaced in place, keeping the lines after it
+        let with_tail = format!("{}export EDITOR=vim\n", updated);
+        let replaced = replace_block(&with_tail, &paths(&["/opt/b", "/opt/c"])).unwrap();
+        assert_eq!(parse_block(&replaced), paths(&["/opt/b", "/opt/c"]));
+        assert_eq!(replaced.matches(BLOCK_START).count(), 1);
+        assert!(replaced.starts_with("alias ll"));
+        assert!(replaced.ends_with("export EDITOR=vim\n"));
+
+        // Removed when no entries are left
+        let removed = replace_block(&replaced, &[]).unwrap();
+        assert!(!removed.contains(BLOCK_START));
+        assert!(removed.contains("export EDITOR=vim"));
+    }
+
+    #[test]
+    fn test_block_escapes_shell_syntax() {
+        let entries = paths(&[
+            r#"/opt/say "hi"/bin"#,
+            "/opt/$(touch pwned)/bin",
+            "/opt/`id`/bin",
+            r"/opt/back\slash",
+            "/opt/cost$5",
+            "$HOME/bin",
+            "${XDG_DATA_HOME}/bin",
+        ]);
+        let block = format_block(&entries).unwrap();
+        assert!(block.contains(r#"/opt/say \"hi\"/bin"#));
+        assert!(block.contains(r"/opt/\$(touch pwned)/bin"));
+        assert!(block.contains(r"/opt/\`id\`/bin"));
+        assert!(block.contains(r"/opt/back\\slash"));
+        assert!(block.contains(r"/opt/cost\$5"));
+        // Variable references are left for the shell to expand
+        assert!(block.contains(":$HOME/bin:${XDG_DATA_HOME}/bin:$PATH"));
+        assert_eq!(parse_block(&block), entries);
+
+        assert!(format_block(&paths(&["/opt/a:/opt/b"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let content = "# comment\n/opt/x/bin\n\n  /usr/local/go/bin \n";
+        assert_eq!(
+            parse_lines(content),
+            paths(&["/opt/x/bin", "/usr/local/go/bin"])
+        );
+        assert_eq!(
+            parse_lines(&format_lines(&paths(&["/a", "/b"]))),
+            paths(&["/a", "/b"])
+        );
+    }
+
+    #[test]
+    fn test_shell_profiles_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut profiles = ShellProfiles {
+            user: PathFile {
+                path: dir.path().join(".zshrc"),
+                format: FileFormat::Shell,
+            },
+            machine: PathFile {
+                path: dir.path().join("path-commander"),
+                format: FileFormat::Lines,
+            },
+        };
+
+        // Nothing declared yet
+        assert!(profiles.read_paths(PathScope::User).unwrap().is_empty());
+
+        std::fs::write(dir.path().join(".zshrc"), "setopt autocd\n").unwrap();
+        profiles
+            .write_paths(PathScope::User, &paths(&["$HOME/.cargo/bin"]))
+            .unwrap();
+        profiles
+            .write_paths(PathScope::Machine, &paths(&["/opt/tools"]))
+            .unwrap();
+
+        assert_eq!(
+            profiles.read_paths(PathScope::User).unwrap(),
+            paths(&["$HOME/.cargo/bin"])
+        );
+        assert_eq!(
+            profiles.read_paths(PathScope::Machine).unwrap(),
+            paths(&["/opt/tools"])
+        );
+        let zshrc = std::fs::read_to_string(dir.path().join(".zshrc")).unwrap();
+        assert!(zshrc.starts_with("setopt autocd\n"));
+    }
+}
@@ -0,0 +1,408 @@
+use crate::config::{LengthMeasure, Settings};
+use crate::path_analyzer::{expand_entries, ExpansionContext, PathInfo};
+use crate::registry::join_paths;
+
+/// Longest PATH the Environment Variables dialog and many installers handle
+pub const DIALOG_LIMIT: usize = 2047;
+
+/// `setx` silently truncates values to this many characters
+pub const SETX_LIMIT: usize = 1024;
+
+/// Longest expanded command line cmd.exe accepts, so `%PATH%` beyond it breaks batch files
+pub const CMD_LIMIT: usize = 8191;
+
+/// Largest environment variable Win32 allows, new processes get a truncated PATH beyond it
+pub const VARIABLE_LIMIT: usize = 32767;
+
+/// Warn once a value reaches this share of a limit
+const APPROACHING: f64 = 0.9;
+
+/// A limit some part of Windows runs into, listed in the PATH Length dialog
+pub struct KnownLimit {
+    pub length: usize,
+    pub measure: LengthMeasure,
+    /// Checked against MACHINE and USER expanded into one value, not each scope
+    pub combined: bool,
+    pub what: &'static str,
+}
+
+pub const KNOWN_LIMITS: [KnownLimit; 4] = [
+    KnownLimit {
+        length: SETX_LIMIT,
+        measure: LengthMeasure::Stored,
+        combined: false,
+        what: "setx cuts the value it writes short",
+    },
+    KnownLimit {
+        length: DIALOG_LIMIT,
+        measure: LengthMeasure::Stored,
+        combined: false,
+        what: "Older Environment Variables dialogs and installers truncate it",
+    },
+    KnownLimit {
+        length: CMD_LIMIT,
+        measure: LengthMeasure::Expanded,
+        combined: true,
+        what: "cmd.exe command line, batch files using %PATH% fail",
+    },
+    KnownLimit {
+        length: VARIABLE_LIMIT,
+        measure: LengthMeasure::Expanded,
+        combined: true,
+        what: "Largest Win32 variable, new processes get PATH truncated",
+    },
+];
+
+/// Stored and expanded size of a PATH value, in UTF-16 characters like Windows counts them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Lengths {
+    /// As written to the registry, PATH is a REG_EXPAND_SZ so %VARIABLES% stay unexpanded
+    pub stored: usize,
+    /// As a new process gets it, after Windows expands the variables
+    pub expanded: usize,
+}
+
+impl Lengths {
+    pub fn of(entries: &[String], context: &ExpansionContext) -> Self {
+        Self {
+            stored: serialized_length(entries),
+            expanded: serialized_length(&expand_entries(entries, context)),
+        }
+    }
+
+    pub fn measured(&self, measure: LengthMeasure) -> usize {
+        match measure {
+            LengthMeasure::Stored => self.stored,
+            LengthMeasure::Expanded => self.expanded,
+        }
+    }
+}
+
+/// The length PATH values are warned about, `[limits]` in config.ini
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Threshold {
+    pub limit: usize,
+    pub measure: LengthMeasure,
+}
+
+impl Default for Threshold {
+    fn default() -> Self {
+        Self {
+            limit: DIALOG_LIMIT,
+            measure: LengthMeasure::Stored,
+        }
+    }
+}
+
+impl Threshold {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            limit: settings.length_limit.unwrap_or(DIALOG_LIMIT),
+            measure: settings.length_measure,
+        }
+    }
+
+    /// The limit other tools trip over, as opposed to one set in config.ini
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Expanded length of several values that make up one environment, MACHINE first
+pub fn combined_length(values: &[(&[String], &ExpansionContext)]) -> usize {
+    let entries: Vec<String> = values
+        .iter()
+        .flat_map(|(entries, context)| expand_entries(entries, context))
+        .collect();
+    serialized_length(&entries)
+}
+
+/// One PATH value about to be written
+pub struct ScopeValue<'a> {
+    pub label: &'a str,
+    pub before: &'a [String],
+    pub after: &'a [String],
+    pub info: &'a [PathInfo],
+    /// Whose profile per-user variables are expanded for
+    pub context: &'a ExpansionContext,
+}
+
+impl ScopeValue<'_> {
+    fn length(&self) -> usize {
+        serialized_length(self.after)
+    }
+
+    fn lengths(&self) -> Lengths {
+        Lengths::of(self.after, self.context)
+    }
+
+    /// Only values that grow are worth a warning, shrinking one is the fix
+    fn grows(&self) -> bool {
+        self.length() > serialized_length(self.before)
+    }
+}
+
+/// What the apply would run into and how to avoid it
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Advisory {
+    pub warnings: Vec<String>,
+    pub suggestions: Vec<String>,
+}
+
+impl Advisory {
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// Characters the registry value takes, as UTF-16 like Windows counts them
+pub fn serialized_length(entries: &[String]) -> usize {
+    join_paths(entries).encode_utf16().count()
+}
+
+/// Check values about to be written against the registry and Win32 limits
+/// `combined` means the scopes make up one environment, like local MACHINE and USER
+pub fn advise(scopes: &[ScopeValue], combined: bool, threshold: Threshold) -> Advisory {
+    let mut advisory = Advisory::default();
+    let mut crowded = Vec::new();
+    let measured = match threshold.measure {
+        LengthMeasure::Stored => "",
+        LengthMeasure::Expanded => " expanded",
+    };
+
+    for scope in scopes.iter().filter(|scope| scope.grows()) {
+        let length = scope.lengths().measured(threshold.measure);
+        if length > threshold.limit {
+            advisory.warnings.push(if threshold.is_default() {
+                format!(
+                    "{} PATH is {} characters, over the {} the Environment Variables dialog and many installers handle; they truncate it the next time they save PATH",
+                    scope.label, length, threshold.limit
+                )
+            } else {
+                format!(
+                    "{} PATH is {} characters{}, over the limit of {} set in config.ini",
+                    scope.label, length, measured, threshold.limit
+                )
+            });
+            crowded.push(scope);
+        } else if length as f64 >= threshold.limit as f64 * APPROACHING {
+            advisory.warnings.push(if threshold.is_default() {
+                format!(
+                    "{} PATH is {} of {} characters, close to where the Environment Variables dialog and many installers truncate it",
+                    scope.label, length, threshold.limit
+                )
+            } else {
+                format!(
+                    "{} PATH is {} of {} characters{}, close to the limit set in config.ini",
+                    scope.label, length, threshold.limit, measured
+                )
+            });
+            crowded.push(scope);
+        }
+        let length = scope.length();
+        if length > SETX_LIMIT && serialized_length(scope.before) <= SETX_LIMIT {
+            advisory.warnings.push(format!(
+                "{} PATH grows past {} characters: a script that runs `setx PATH ...` now cuts it short",
+                scope.label, SETX_LIMIT
+            ));
+        }
+    }
+
+    // New processes get every scope expanded into one variable
+    if combined && scopes.iter().any(|scope| scope.grows()) {
+        let values: Vec<_> = scopes
+            .iter()
+            .map(|scope| (scope.after, scope.context))
+            .collect();
+        let expanded = combined_length(&values);
+        if expanded > VARIABLE_LIMIT {
+            advisory.warnings.push(format!(
+                "The expanded PATH is {} characters, over the Win32 limit of {}: new processes get it truncated",
+                expanded, VARIABLE_LIMIT
+            ));
+        } else if expanded > CMD_LIMIT {
+            advisory.warnings.push(format!(
+                "The expanded PATH is {} characters, over the {} cmd.exe accepts: batch files that use %PATH% fail",
+                expanded, CMD_LIMIT
+            ));
+        }
+    }
+
+    if advisory.is_empty() {
+        return advisory;
+    }
+    let crowded = if crowded.is_empty() {
+        scopes.iter().collect()
+    } else {
+        crowded
+    };
+
+    // Room in the other scope
+    if combined {
+        for scope in &crowded {
+            for other in scopes.iter().filter(|other| other.label != scope.label) {
+                let room = threshold
+                    .limit
+                    .saturating_sub(other.lengths().measured(threshold.measure));
+                if room > 0 {
+                    advisory.suggestions.push(format!(
+                        "Move entries from {} to {} (F5), {} has {} characters to spare",
+                        scope.label, other.label, other.label, room
+                    ));
+                }
+            }
+        }
+    }
+
+    for scope in &crowded {
+        // Normalizing collapses prefixes like C:\Program Files to %PROGRAMFILES%
+        let (shorter, saved) = scope
+            .after
+            .iter()
+            .zip(scope.info)
+            .filter(|(entry, info)| info.normalized.len() < entry.len())
+            .fold((0, 0), |(count, saved), (entry, info)| {
+                (count + 1, saved + entry.len() - info.normalized.len())
+            });
+        if shorter > 0 {
+            advisory.suggestions.push(format!(
+                "Normalize {} (Shift+F9): {} entries get {} characters shorter using variables like %PROGRAMFILES%",
+                scope.label, shorter, saved
+            ));
+        }
+
+        let (removable, saved) = scope
+            .after
+            .iter()
+            .zip(scope.info)
+            .filter(|(_, info)| info.is_duplicate || info.is_dead())
+            .fold((0, 0), |(count, saved), (entry, _)| {
+                (count + 1, saved + entry.len() + 1)
+            });
+        if removable > 0 {
+            advisory.suggestions.push(format!(
+                "Remove duplicate and dead entries from {} (F7, F8): {} entries, up to {} characters",
+                scope.label, removable, saved
+            ));
+        }
+    }
+    advisory.suggestions.push(
+        "Define a variable for a long prefix shared by several entries and write them as %NAME%\\..."
+            .to_string(),
+    );
+    advisory
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::MemoryFileSystem;
+    use crate::path_analyzer::analyze_scopes_with_fs;
+
+    fn entries(prefix: &str, count: usize) -> Vec<String> {
+        (0..count)
+            .map(|i| format!(r"C:\{}\{:03}", prefix, i))
+            .collect()
+    }
+
+    #[test]
+    fn test_advise_on_growing_values_only() {
+        let fs = MemoryFileSystem::new();
+        let user_before = entries("Tools", 100);
+        let mut user_after = user_before.clone();
+        user_after.extend(entries("More", 100));
+        let machine = entries("Sys", 10);
+        let (machine_info, user_info) = analyze_scopes_with_fs(&machine, &user_after, &fs);
+        let context = ExpansionContext::default();
+
+        let scopes = [
+            ScopeValue {
+                label: "MACHINE",
+                before: &machine,
+                after: &machine,
+                info: &machine_info,
+                context: &context,
+            },
+            ScopeValue {
+                label: "USER",
+                before: &user_before,
+                after: &user_after,
+                info: &user_info,
+                context: &context,
+            },
+        ];
+        let advisory = advise(&scopes, true, Threshold::default());
+        assert_eq!(serialized_length(&user_after), 2499);
+        assert!(advisory.warnings[0].starts_with("USER PATH is 2499 characters, over the 2047"));
+        assert_eq!(advisory.warnings.len(), 1);
+        assert!(advisory.suggestions[0].starts_with("Move entries from USER to MACHINE (F5)"));
+        // Nothing exists in the fake filesystem, so every entry counts as dead
+        assert!(advisory
+            .suggestions
+            .iter()
+            .any(|s| s.contains("(F7, F8): 200 entries")));
+
+        // Shrinking a long value is fine
+        let shrinking = [ScopeValue {
+            label: "USER",
+            before: &user_after,
+            after: &user_before,
+            info: &user_info,
+            context: &context,
+        }];
+        assert!(advise(&shrinking, true, Threshold::default()).is_empty());
+    }
+
+    #[test]
+    fn test_advise_on_crossing_setx_limit() {
+        let before = entries("Tools", 70);
+        let after = entries("Tools", 80);
+        let info = analyze_scopes_with_fs(&after, &[], &MemoryFileSystem::new()).0;
+        let context = ExpansionContext::default();
+        let scopes = [ScopeValue {
+            label: "USER",
+            before: &before,
+            after: &after,
+            info: &info,
+            context: &context,
+        }];
+        let advisory = advise(&scopes, false, Threshold::default());
+        assert_eq!(advisory.warnings.len(), 1);
+        assert!(advisory.warnings[0].contains("past 1024 characters"));
+    }
+
+    #[test]
+    fn test_advise_on_configured_threshold() {
+        let before = entries("Tools", 10);
+        let mut after = before.clone();
+        after.push(r"%USERPROFILE%\bin".to_string());
+        let info = analyze_scopes_with_fs(&after, &[], &MemoryFileSystem::new()).0;
+        let context = ExpansionContext::for_profile(r"C:\Users\alexandria");
+        let scopes = [ScopeValue {
+            label: "USER",
+            before: &before,
+            after: &after,
+            info: &info,
+            context: &context,
+        }];
+
+        let lengths = Lengths::of(&after, &context);
+        assert_eq!(lengths.stored, 10 * 13 + 17);
+        assert_eq!(lengths.expanded, 10 * 13 + 23);
+
+        // Only the expanded value is over 150
+        let stored = Threshold {
+            limit: 150,
+            measure: LengthMeasure::Stored,
+        };
+        assert!(advise(&scopes, false, stored).warnings[0].contains("147 of 150 characters"));
+        let expanded = Threshold {
+            limit: 150,
+            measure: LengthMeasure::Expanded,
+        };
+        assert_eq!(
+            advise(&scopes, false, expanded).warnings[0],
+            "USER PATH is 153 characters expanded, over the limit of 150 set in config.ini"
+        );
+    }
+}
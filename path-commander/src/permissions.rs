@@ -1,7 +1,10 @@
+#[cfg(windows)]
 use windows::Win32::Security::{GetTokenInformation, TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY};
+#[cfg(windows)]
 use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
 
 /// Check if the current process is running with administrator privileges
+#[cfg(windows)]
 pub fn is_admin() -> bool {
     unsafe {
         let mut token = Default::default();
@@ -31,6 +34,12 @@ pub fn is_admin() -> bool {
     }
 }
 
+/// Check if the current process is running as root
+#[cfg(unix)]
+pub fn is_admin() -> bool {
+    unsafe { libc::geteuid() == 0 }
+}
+
 /// Get a message about admin privileges
 pub fn get_privilege_message() -> String {
     if is_admin() {
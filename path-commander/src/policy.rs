@@ -0,0 +1,263 @@
+use anyhow::{anyhow, Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::path_analyzer::{comparable, expand_environment_variables};
+
+/// Machine-wide rules set by an administrator
+///
+/// ```toml
+/// # Entries under these prefixes cannot be removed
+/// locked = ["%SystemRoot%", "C:\\Program Files\\Corp"]
+/// # Entries under these prefixes cannot be added
+/// denied = ['C:\Users\Public', "D:\\Temp"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Policy {
+    pub locked: Vec<String>,
+    pub denied: Vec<String>,
+}
+
+/// Where the machine-wide policy file lives
+pub fn default_path() -> PathBuf {
+    if cfg!(windows) {
+        let program_data =
+            std::env::var("ProgramData").unwrap_or_else(|_| r"C:\ProgramData".to_string());
+        PathBuf::from(program_data)
+            .join("PathCommander")
+            .join("policy.toml")
+    } else {
+        PathBuf::from("/etc/path-commander/policy.toml")
+    }
+}
+
+impl Policy {
+    /// Load a policy file, a missing file means no restrictions
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read policy: {}", path.display()))
+            }
+        };
+        Self::parse(&content).with_context(|| format!("Invalid policy file: {}", path.display()))
+    }
+
+    /// Parse the subset of TOML a policy uses: string arrays assigned to keys
+    fn parse(content: &str) -> Result<Self> {
+        let mut policy = Self::default();
+        let mut lines = content.lines().enumerate();
+
+        while let Some((index, line)) = lines.next() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow!("line {}: expected `key = [...]`", index + 1))?;
+
+            // Arrays may span several lines
+            let mut value = value.trim().to_string();
+            while value.starts_with('[') && !value.ends_with(']') {
+                let (_, next) = lines
+                    .next()
+                    .ok_or_else(|| anyhow!("line {}: unterminated array", index + 1))?;
+                value.push(' ');
+                value.push_str(strip_comment(next).trim());
+            }
+
+            let entries = parse_string_array(&value)
+                .with_context(|| format!("line {}: `{}`", index + 1, key.trim()))?;
+            match key.trim() {
+                "locked" => policy.locked = entries,
+                "denied" => policy.denied = entries,
+                // Unknown keys are tolerated so newer policies still load
+                _ => {}
+            }
+        }
+
+        Ok(policy)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locked.is_empty() && self.denied.is_empty()
+    }
+
+    /// Entry cannot be removed from PATH
+    pub fn is_locked(&self, entry: &str) -> bool {
+        self.locked.iter().any(|prefix| has_prefix(entry, prefix))
+    }
+
+    /// Entry cannot be added to PATH
+    pub fn is_denied(&self, entry: &str) -> bool {
+        self.denied.iter().any(|prefix| has_prefix(entry, prefix))
+    }
+
+    /// Describe the edits between two states of PATH that break this policy
+    pub fn violations(&self, before: &[&String], after: &[&String]) -> Vec<String> {
+        let key = |entry: &String| expanded(entry);
+        let before_keys: Vec<String> = before.iter().map(|e| key(e)).collect();
+        let after_keys: Vec<String> = after.iter().map(|e| key(e)).collect();
+
+        let mut violations = Vec::new();
+        for (entry, entry_key) in before.iter().zip(&before_keys) {
+            if self.is_locked(entry) && !after_keys.contains(entry_key) {
+                violations.push(format!("{} is locked and cannot be removed", entry));
+            }
+        }
+        for (entry, entry_key) in after.iter().zip(&after_keys) {
+            if self.is_denied(entry) && !before_keys.contains(entry_key) {
+                violations.push(format!("{} is not allowed to be added", entry));
+            }
+        }
+        violations
+    }
+}
+
+/// Expanded form that compares by value
+fn expanded(entry: &str) -> String {
+    comparable(&expand_environment_variables(entry))
+}
+
+/// Whether an entry is the prefix directory or lies below it
+fn has_prefix(entry: &str, prefix: &str) -> bool {
+    let entry = expanded(entry);
+    let prefix = expanded(prefix);
+    if prefix.is_empty() {
+        // "\\" (all UNC paths) or "/" trims down to nothing
+        return false;
+    }
+    entry == prefix
+        || entry
+            .strip_prefix(&prefix)
+            .is_some_and(|rest| rest.starts_with(['\\', '/']))
+}
+
+/// Drop a `#` comment that is not inside a string
+pub fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '#') => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Parse `["a", 'b']` with TOML basic ("...") and literal ('...') strings
+fn parse_string_array(value: &str) -> Result<Vec<String>> {
+    let inner = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .ok_or_else(|| anyhow!("expected an array of strings"))?;
+
+    let mut entries = Vec::new();
+    let mut chars = inner.chars().peekable();
+    loop {
+        while chars.peek().is_some_and(|c| c.is_whitespace() || *c == ',') {
+            chars.next();
+        }
+        let Some(quote) = chars.next() else {
+            break;
+        };
+        if quote != '"' && quote != '\'' {
+            return Err(anyhow!("expected a quoted string"));
+        }
+
+        let mut entry = String::new();
+        loop {
+            match chars.next() {
+                None => return Err(anyhow!("unterminated string")),
+                Some(c) if c == quote => break,
+                // Only basic strings have escapes
+                Some('\\') if quote == '"' => match chars.next() {
+                    Some('\\') => entry.push('\\'),
+                    Some('"') => entry.push('"'),
+                    Some(other) => return Err(anyhow!("unsupported escape \\{}", other)),
+                    None => return Err(anyhow!("unterminated string")),
+                },
+                Some(c) => entry.push(c),
+            }
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_policy() {
+        let policy = Policy::parse(
+            r#"
+# Corporate PATH policy
+locked = ["C:\\Windows", 'C:\Program Files\Corp']  # never remove
+denied = [
+    'C:\Users\Public',   # world-writable
+    "D:\\Temp",
+]
+future_key = []
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(policy.locked, vec![r"C:\Windows", r"C:\Program Files\Corp"]);
+        assert_eq!(policy.denied, vec![r"C:\Users\Public", r"D:\Temp"]);
+        assert!(Policy::parse("").unwrap().is_empty());
+
+        // Mistakes are reported with their line
+        let err = Policy::parse("locked = ['C:\\Windows']\ndenied = C:\\Temp\n").unwrap_err();
+        assert!(format!("{:#}", err).contains("line 2"));
+        assert!(Policy::parse("locked = ['C:\\Windows]").is_err());
+        // Backslashes in basic strings must be escaped
+        assert!(Policy::parse("locked = [\"C:\\Windows\"]").is_err());
+    }
+
+    #[test]
+    fn test_prefix_matching() {
+        let policy = Policy {
+            locked: vec![r"C:\Windows\".to_string()],
+            denied: vec![r"C:\Users\Public".to_string()],
+        };
+
+        assert!(policy.is_locked(r"C:\Windows"));
+        assert!(policy.is_locked(r"c:\windows\System32"));
+        assert!(!policy.is_locked(r"C:\WindowsApps"));
+        assert!(policy.is_denied(r"C:\Users\Public\bin\"));
+        assert!(!policy.is_denied(r"C:\Users\PublicTools"));
+    }
+
+    #[test]
+    fn test_violations() {
+        let policy = Policy {
+            locked: vec![r"C:\Windows".to_string()],
+            denied: vec![r"C:\Users\Public".to_string()],
+        };
+        let windows = r"C:\Windows".to_string();
+        let system32 = r"C:\Windows\System32".to_string();
+        let public = r"C:\Users\Public\bin".to_string();
+        let tools = r"C:\Tools".to_string();
+
+        // Reordering, moving and unrelated edits are fine
+        assert!(policy
+            .violations(&[&windows, &system32], &[&system32, &windows, &tools])
+            .is_empty());
+        // A denied entry that was already there may stay
+        assert!(policy.violations(&[&public], &[&public]).is_empty());
+
+        let violations = policy.violations(&[&windows, &system32], &[&windows, &public]);
+        assert_eq!(
+            violations,
+            vec![
+                r"C:\Windows\System32 is locked and cannot be removed".to_string(),
+                r"C:\Users\Public\bin is not allowed to be added".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,488 @@
+use anyhow::{anyhow, Result};
+use std::fmt;
+
+/// Port the remote registry is reached through, as a named pipe over SMB
+pub const SMB_PORT: u16 = 445;
+
+/// How long each probe waits for an answer
+#[cfg(windows)]
+const PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// How long a started RemoteRegistry service gets to report it is running
+#[cfg(windows)]
+const SERVICE_START_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// The RemoteRegistry service on the remote computer, as its service manager reports it
+#[cfg_attr(not(windows), allow(dead_code))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServiceState {
+    Running,
+    /// Stopped services that aren't disabled start when the registry is first accessed
+    Stopped {
+        disabled: bool,
+        /// The service manager lets us start it
+        can_start: bool,
+    },
+    /// Not installed, Home editions don't have it
+    Missing,
+    /// The service manager turned us away
+    AccessDenied,
+    /// Not asked, or the query failed some other way, connecting will tell
+    Unknown,
+}
+
+/// What the probes found out about a computer before connecting to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Observations {
+    /// Name lookup, the error if it failed
+    pub resolved: Result<(), String>,
+    /// Answered an ICMP echo, None where one couldn't be sent
+    pub ping: Option<bool>,
+    /// TCP connect to port 445, the error if it failed
+    pub smb: Result<(), String>,
+    pub service: ServiceState,
+}
+
+/// The precondition a connection would fail on, and what to do about it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub what: String,
+    pub fix: String,
+    /// RemoteRegistry is only stopped, and may be started from here
+    pub startable: bool,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}. {}", self.what.trim_end_matches('.'), self.fix)
+    }
+}
+
+impl std::error::Error for Problem {}
+
+/// Probe a computer and fail with the first precondition it doesn't meet
+/// The error is a `Problem`, so callers can tell whether starting the service would help
+pub fn check(computer_name: &str) -> Result<()> {
+    match diagnose(computer_name, &observe(computer_name)) {
+        Some(problem) => Err(problem.into()),
+        None => Ok(()),
+    }
+}
+
+/// The first precondition the observations show is missing, in the order a connection needs them
+pub fn diagnose(computer_name: &str, seen: &Observations) -> Option<Problem> {
+    let problem = |what: String, fix: String| Problem {
+        what,
+        fix,
+        startable: false,
+    };
+    if let Err(e) = &seen.resolved {
+        return Some(problem(
+            format!("The name {} can't be resolved: {}", computer_name, e),
+            "Check the spelling, or use the full DNS name or the IP address".to_string(),
+        ));
+    }
+    if let Err(e) = &seen.smb {
+        return Some(if seen.ping == Some(false) {
+            problem(
+                format!(
+                    "{} doesn't answer ping or port {}: {}",
+                    computer_name, SMB_PORT, e
+                ),
+                "Check that it is switched on and on this network, a VPN may be needed".to_string(),
+            )
+        } else {
+            problem(
+                format!(
+                    "{} is up but port {} (SMB) doesn't answer: {}",
+                    computer_name, SMB_PORT, e
+                ),
+                format!(
+                    "Allow \"File and Printer Sharing\" in the firewall on {}",
+                    computer_name
+                ),
+            )
+        });
+    }
+    match seen.service {
+        ServiceState::Stopped { disabled: true, .. } => Some(problem(
+            format!(
+                "The RemoteRegistry service is disabled on {}",
+                computer_name
+            ),
+            format!(
+                "Run sc \\\\{} config RemoteRegistry start= demand as an administrator",
+                computer_name
+            ),
+        )),
+        ServiceState::Stopped {
+            disabled: false,
+            can_start: true,
+        } => Some(Problem {
+            startable: true,
+            ..problem(
+                format!("The RemoteRegistry service is stopped on {}", computer_name),
+                "It can be started from here".to_string(),
+            )
+        }),
+        ServiceState::Missing => Some(problem(
+            format!("{} has no RemoteRegistry service", computer_name),
+            "Home editions of Windows can't be managed remotely".to_string(),
+        )),
+        ServiceState::AccessDenied => Some(problem(
+            format!(
+                "Access to the service manager on {} was denied",
+                computer_name
+            ),
+            format!(
+                "Use an account in the Administrators group on {}, for example with runas /netonly",
+                computer_name
+            ),
+        )),
+        // Without the right to start it, connecting may still start it on demand
+        ServiceState::Running | ServiceState::Stopped { .. } | ServiceState::Unknown => None,
+    }
+}
+
+/// Run every probe, later ones are skipped once the computer is known to be unreachable
+#[cfg(windows)]
+pub fn observe(computer_name: &str) -> Observations {
+    use std::net::{TcpStream, ToSocketAddrs};
+
+    let address = match (computer_name, SMB_PORT).to_socket_addrs() {
+        Ok(mut addresses) => addresses.next(),
+        Err(e) => {
+            return Observations {
+                resolved: Err(e.to_string()),
+                ping: None,
+                smb: Err("not tried".to_string()),
+                service: ServiceState::Unknown,
+            }
+        }
+    };
+    let Some(address) = address else {
+        return Observations {
+            resolved: Err("no addresses found".to_string()),
+            ping: None,
+            smb: Err("not tried".to_string()),
+            service: ServiceState::Unknown,
+        };
+    };
+
+    let ping = ping(address.ip());
+    let smb = TcpStream::connect_timeout(&address, PROBE_TIMEOUT)
+        .map(|_| ())
+        .map_err(|e| e.to_string());
+    let service = if smb.is_ok() {
+        remote_registry_state(computer_name)
+    } else {
+        ServiceState::Unknown
+    };
+    Observations {
+        resolved: Ok(()),
+        ping,
+        smb,
+        service,
+    }
+}
+
+/// Other platforms can't connect at all, RemoteConnection::connect says so
+#[cfg(not(windows))]
+pub fn observe(_computer_name: &str) -> Observations {
+    Observations {
+        resolved: Ok(()),
+        ping: None,
+        smb: Ok(()),
+        service: ServiceState::Unknown,
+    }
+}
+
+/// Send one ICMP echo, IPv6 addresses aren't pinged
+#[cfg(windows)]
+fn ping(ip: std::net::IpAddr) -> Option<bool> {
+    use windows::Win32::NetworkManagement::IpHelper::{
+        IcmpCloseHandle, IcmpCreateFile, IcmpSendEcho, ICMP_ECHO_REPLY,
+    };
+
+    let std::net::IpAddr::V4(ip) = ip else {
+        return None;
+    };
+    let request = *b"PathCommander";
+    let mut reply = vec![0u8; std::mem::size_of::<ICMP_ECHO_REPLY>() + request.len() + 8];
+    unsafe {
+        let handle = IcmpCreateFile().ok()?;
+        let replies = IcmpSendEcho(
+            handle,
+            u32::from_ne_bytes(ip.octets()),
+            request.as_ptr() as *const _,
+            request.len() as u16,
+            None,
+            reply.as_mut_ptr() as *mut _,
+            reply.len() as u32,
+            PROBE_TIMEOUT.as_millis() as u32,
+        );
+        let _ = IcmpCloseHandle(handle);
+        Some(replies > 0)
+    }
+}
+
+/// Service manager and RemoteRegistry handles on a remote computer, closed when dropped
+#[cfg(windows)]
+struct RemoteService {
+    manager: windows::Win32::System::Services::SC_HANDLE,
+    service: windows::Win32::System::Services::SC_HANDLE,
+}
+
+#[cfg(windows)]
+impl RemoteService {
+    fn open(computer_name: &str, access: u32) -> windows::core::Result<Self> {
+        use windows::core::PCWSTR;
+        use windows::Win32::System::Services::{
+            CloseServiceHandle, OpenSCManagerW, OpenServiceW, SC_MANAGER_CONNECT,
+        };
+
+        let machine = crate::registry::to_wide_string(&format!("\\\\{}", computer_name));
+        let name = crate::registry::to_wide_string("RemoteRegistry");
+        unsafe {
+            let manager =
+                OpenSCManagerW(PCWSTR(machine.as_ptr()), PCWSTR::null(), SC_MANAGER_CONNECT)?;
+            match OpenServiceW(manager, PCWSTR(name.as_ptr()), access) {
+                Ok(service) => Ok(Self { manager, service }),
+                Err(e) => {
+                    let _ = CloseServiceHandle(manager);
+                    Err(e)
+                }
+            }
+        }
+    }
+
+    fn status(&self) -> windows::core::Result<windows::Win32::System::Services::SERVICE_STATUS> {
+        let mut status = Default::default();
+        unsafe { windows::Win32::System::Services::QueryServiceStatus(self.service, &mut status)? };
+        Ok(status)
+    }
+}
+
+#[cfg(windows)]
+impl Drop for RemoteService {
+    fn drop(&mut self) {
+        use windows::Win32::System::Services::CloseServiceHandle;
+        unsafe {
+            let _ = CloseServiceHandle(self.service);
+            let _ = CloseServiceHandle(self.manager);
+        }
+    }
+}
+
+/// Ask the remote service manager about RemoteRegistry
+#[cfg(windows)]
+fn remote_registry_state(computer_name: &str) -> ServiceState {
+    use windows::Win32::Foundation::{ERROR_ACCESS_DENIED, ERROR_SERVICE_DOES_NOT_EXIST};
+    use windows::Win32::System::Services::{
+        QueryServiceConfigW, QUERY_SERVICE_CONFIGW, SERVICE_DISABLED, SERVICE_QUERY_CONFIG,
+        SERVICE_QUERY_STATUS, SERVICE_RUNNING, SERVICE_START,
+    };
+
+    let state_of = |e: windows::core::Error| {
+        if e.code() == ERROR_ACCESS_DENIED.to_hresult() {
+            ServiceState::AccessDenied
+        } else if e.code() == ERROR_SERVICE_DOES_NOT_EXIST.to_hresult() {
+            ServiceState::Missing
+        } else {
+            ServiceState::Unknown
+        }
+    };
+    let service =
+        match RemoteService::open(computer_name, SERVICE_QUERY_STATUS | SERVICE_QUERY_CONFIG) {
+            Ok(service) => service,
+            Err(e) => return state_of(e),
+        };
+    match service.status() {
+        Ok(status) if status.dwCurrentState == SERVICE_RUNNING => ServiceState::Running,
+        Ok(_) => unsafe {
+            // First call asks for the size, the strings follow the struct
+            let mut needed = 0u32;
+            let _ = QueryServiceConfigW(service.service, None, 0, &mut needed);
+            let mut buffer = vec![0u64; (needed as usize).div_ceil(8)];
+            let config = buffer.as_mut_ptr() as *mut QUERY_SERVICE_CONFIGW;
+            let disabled = needed > 0
+                && QueryServiceConfigW(service.service, Some(config), needed, &mut needed).is_ok()
+                && (*config).dwStartType == SERVICE_DISABLED;
+            // Querying is allowed to more accounts than starting
+            let can_start = RemoteService::open(computer_name, SERVICE_START).is_ok();
+            ServiceState::Stopped {
+                disabled,
+                can_start,
+            }
+        },
+        Err(e) => state_of(e),
+    }
+}
+
+/// Start RemoteRegistry on a remote computer and wait until it runs
+#[cfg(windows)]
+pub fn start_remote_registry(computer_name: &str) -> Result<()> {
+    use windows::Win32::Foundation::ERROR_SERVICE_ALREADY_RUNNING;
+    use windows::Win32::System::Services::{
+        StartServiceW, SERVICE_QUERY_STATUS, SERVICE_RUNNING, SERVICE_START,
+    };
+
+    let service = RemoteService::open(computer_name, SERVICE_START | SERVICE_QUERY_STATUS)
+        .map_err(|e| {
+            anyhow!(
+                "Can't start RemoteRegistry on {}: {}",
+                computer_name,
+                e.message()
+            )
+        })?;
+    if let Err(e) = unsafe { StartServiceW(service.service, None) } {
+        if e.code() != ERROR_SERVICE_ALREADY_RUNNING.to_hresult() {
+            return Err(anyhow!(
+                "Can't start RemoteRegistry on {}: {}",
+                computer_name,
+                e.message()
+            ));
+        }
+    }
+    let deadline = std::time::Instant::now() + SERVICE_START_TIMEOUT;
+    while std::time::Instant::now() < deadline {
+        if service
+            .status()
+            .is_ok_and(|status| status.dwCurrentState == SERVICE_RUNNING)
+        {
+            return Ok(());
+        }
+        std::thread::sleep(std::time::Duration::from_millis(250));
+    }
+    Err(anyhow!(
+        "RemoteRegistry on {} did not start within {} seconds",
+        computer_name,
+        SERVICE_START_TIMEOUT.as_secs()
+    ))
+}
+
+/// Ask RemoteRegistry on a remote computer to stop, without waiting for it
+#[cfg(windows)]
+pub fn stop_remote_registry(computer_name: &str) -> Result<()> {
+    use windows::Win32::System::Services::{ControlService, SERVICE_CONTROL_STOP, SERVICE_STOP};
+
+    let service =
+        RemoteService::open(computer_name, SERVICE_STOP).map_err(|e| anyhow!("{}", e.message()))?;
+    let mut status = Default::default();
+    unsafe { ControlService(service.service, SERVICE_CONTROL_STOP, &mut status) }
+        .map_err(|e| anyhow!("{}", e.message()))
+}
+
+#[cfg(not(windows))]
+pub fn start_remote_registry(computer_name: &str) -> Result<()> {
+    Err(anyhow!(
+        "Can't start RemoteRegistry on {}: remote mode is only available on Windows",
+        computer_name
+    ))
+}
+
+#[cfg(not(windows))]
+pub fn stop_remote_registry(_computer_name: &str) -> Result<()> {
+    Err(anyhow!("remote mode is only available on Windows"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reachable() -> Observations {
+        Observations {
+            resolved: Ok(()),
+            ping: Some(true),
+            smb: Ok(()),
+            service: ServiceState::Running,
+        }
+    }
+
+    #[test]
+    fn test_reachable_computer_passes() {
+        assert_eq!(diagnose("SRV01", &reachable()), None);
+        // Without the right to start it, the first registry access may still start it
+        let on_demand = Observations {
+            service: ServiceState::Stopped {
+                disabled: false,
+                can_start: false,
+            },
+            ..reachable()
+        };
+        assert_eq!(diagnose("SRV01", &on_demand), None);
+        let unknown = Observations {
+            service: ServiceState::Unknown,
+            ..reachable()
+        };
+        assert_eq!(diagnose("SRV01", &unknown), None);
+    }
+
+    #[test]
+    fn test_first_missing_precondition_is_reported() {
+        let unresolved = Observations {
+            resolved: Err("No such host is known.".to_string()),
+            ..reachable()
+        };
+        assert_eq!(
+            diagnose("SRV01", &unresolved).unwrap().to_string(),
+            "The name SRV01 can't be resolved: No such host is known. \
+             Check the spelling, or use the full DNS name or the IP address"
+        );
+
+        // Ping only decides whether the computer or its firewall is to blame
+        let timed_out = Err("connection timed out".to_string());
+        let down = Observations {
+            ping: Some(false),
+            smb: timed_out.clone(),
+            ..reachable()
+        };
+        assert!(diagnose("SRV01", &down)
+            .unwrap()
+            .what
+            .starts_with("SRV01 doesn't answer ping or port 445"));
+        let firewalled = Observations {
+            smb: timed_out,
+            ..reachable()
+        };
+        assert_eq!(
+            diagnose("SRV01", &firewalled).unwrap().fix,
+            "Allow \"File and Printer Sharing\" in the firewall on SRV01"
+        );
+
+        let disabled = Observations {
+            service: ServiceState::Stopped {
+                disabled: true,
+                can_start: true,
+            },
+            ..reachable()
+        };
+        assert_eq!(
+            diagnose("SRV01", &disabled).unwrap().fix,
+            r"Run sc \\SRV01 config RemoteRegistry start= demand as an administrator"
+        );
+        // Only a stopped service that may be started is offered to start
+        assert!(!diagnose("SRV01", &disabled).unwrap().startable);
+        let stopped = Observations {
+            service: ServiceState::Stopped {
+                disabled: false,
+                can_start: true,
+            },
+            ..reachable()
+        };
+        let problem = diagnose("SRV01", &stopped).unwrap();
+        assert!(problem.startable);
+        assert_eq!(
+            problem.what,
+            "The RemoteRegistry service is stopped on SRV01"
+        );
+
+        for service in [ServiceState::Missing, ServiceState::AccessDenied] {
+            let seen = Observations {
+                service,
+                ..reachable()
+            };
+            assert!(diagnose("SRV01", &seen).is_some());
+        }
+    }
+}
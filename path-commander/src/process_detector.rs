@@ -1,12 +1,29 @@
 use anyhow::Result;
+use chrono::{DateTime, Local};
+#[cfg(windows)]
 use std::collections::HashSet;
-use windows::Win32::Foundation::{CloseHandle, ERROR_NO_MORE_FILES, HANDLE};
+#[cfg(windows)]
+use windows::Win32::Foundation::{
+    CloseHandle, BOOL, ERROR_NO_MORE_FILES, FILETIME, HANDLE, HWND, LPARAM,
+};
+#[cfg(windows)]
 use windows::Win32::System::Diagnostics::ToolHelp::{
     CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS,
 };
+#[cfg(windows)]
+use windows::Win32::System::RemoteDesktop::ProcessIdToSessionId;
+#[cfg(windows)]
+use windows::Win32::System::Threading::{
+    GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+#[cfg(windows)]
+use windows::Win32::UI::WindowsAndMessaging::{
+    EnumWindows, GetWindowThreadProcessId, IsWindowVisible,
+};
 
 /// List of known processes that don't respond to WM_SETTINGCHANGE
 /// These processes load environment variables at startup and don't refresh them
+#[cfg(windows)]
 const NON_RESPONSIVE_PROCESSES: &[&str] = &[
     "cmd.exe",             // Command Prompt
     "powershell.exe",      // Windows PowerShell
@@ -26,8 +43,37 @@ const NON_RESPONSIVE_PROCESSES: &[&str] = &[
     "atom.exe",            // Atom Editor
 ];
 
-/// Detect which known non-responsive processes are currently running
-pub fn detect_running_processes() -> Result<Vec<String>> {
+/// A running process that keeps the PATH it was started with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessInfo {
+    pub name: String,
+    pub pid: u32,
+    /// None if the process could not be opened, e.g. one of another user
+    pub started: Option<DateTime<Local>>,
+    /// Owns a visible top-level window
+    pub has_window: bool,
+    /// Runs in session 0, where services live
+    pub is_service: bool,
+}
+
+impl ProcessInfo {
+    /// Why this process does not see the new PATH, and what to do about it
+    pub fn reason(&self) -> &'static str {
+        if self.is_service {
+            "Runs as part of a service, which gets the new PATH when the service restarts"
+        } else if self.has_window {
+            "Read its environment when it started and ignores WM_SETTINGCHANGE; close and reopen it"
+        } else {
+            "Has no window of its own (a console tab or background helper); it keeps the PATH of the program that started it"
+        }
+    }
+}
+
+/// Detect which known non-responsive processes are currently running, one entry per process
+#[cfg(windows)]
+pub fn detect_running_processes() -> Result<Vec<ProcessInfo>> {
+    let windowed = processes_with_visible_windows();
+
     unsafe {
         // Create snapshot of all processes
         let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0)?;
@@ -48,7 +94,7 @@ pub fn detect_running_processes() -> Result<Vec<String>> {
             return Err(anyhow::anyhow!("Failed to get first process"));
         }
 
-        let mut running_processes = HashSet::new();
+        let mut running_processes = Vec::new();
 
         // Iterate through all processes
         loop {
@@ -61,12 +107,19 @@ pub fn detect_running_processes() -> Result<Vec<String>> {
             let exe_name = String::from_utf16_lossy(&entry.szExeFile[..exe_len]);
 
             // Check if this is a non-responsive process
-            let exe_lower = exe_name.to_lowercase();
-            for &known_process in NON_RESPONSIVE_PROCESSES {
-                if exe_lower == known_process.to_lowercase() {
-                    running_processes.insert(exe_name.clone());
-                    break;
-                }
+            if NON_RESPONSIVE_PROCESSES
+                .iter()
+                .any(|known| exe_name.eq_ignore_ascii_case(known))
+            {
+                let pid = entry.th32ProcessID;
+                let mut session = 0;
+                running_processes.push(ProcessInfo {
+                    name: exe_name,
+                    pid,
+                    started: start_time(pid),
+                    has_window: windowed.contains(&pid),
+                    is_service: ProcessIdToSessionId(pid, &mut session).is_ok() && session == 0,
+                });
             }
 
             // Move to next process
@@ -82,17 +135,63 @@ pub fn detect_running_processes() -> Result<Vec<String>> {
             }
         }
 
-        // Convert to sorted vector for consistent display
-        let mut result: Vec<String> = running_processes.into_iter().collect();
-        result.sort_by_key(|s| s.to_lowercase());
+        // Sort for consistent display
+        running_processes.sort_by_key(|p| (p.name.to_lowercase(), p.pid));
 
-        Ok(result)
+        Ok(running_processes)
     }
 }
 
+/// When a process started, None if it cannot be opened
+#[cfg(windows)]
+fn start_time(pid: u32) -> Option<DateTime<Local>> {
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let _guard = HandleGuard(process);
+        let (mut created, mut exited, mut kernel, mut user) = (
+            FILETIME::default(),
+            FILETIME::default(),
+            FILETIME::default(),
+            FILETIME::default(),
+        );
+        GetProcessTimes(process, &mut created, &mut exited, &mut kernel, &mut user).ok()?;
+        let intervals = ((created.dwHighDateTime as u64) << 32) | created.dwLowDateTime as u64;
+        Some(crate::registry::filetime_to_system_time(intervals).into())
+    }
+}
+
+/// IDs of the processes that own a visible top-level window
+#[cfg(windows)]
+fn processes_with_visible_windows() -> HashSet<u32> {
+    unsafe extern "system" fn collect(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let pids = &mut *(lparam.0 as *mut HashSet<u32>);
+        if IsWindowVisible(hwnd).as_bool() {
+            let mut pid = 0;
+            GetWindowThreadProcessId(hwnd, Some(&mut pid as *mut u32));
+            pids.insert(pid);
+        }
+        true.into()
+    }
+
+    let mut pids = HashSet::new();
+    unsafe {
+        let _ = EnumWindows(Some(collect), LPARAM(&mut pids as *mut _ as isize));
+    }
+    pids
+}
+
+/// Shells re-read their profile on startup, so every open shell is stale and
+/// there is no point singling out processes
+#[cfg(not(windows))]
+pub fn detect_running_processes() -> Result<Vec<ProcessInfo>> {
+    Ok(Vec::new())
+}
+
 /// RAII guard to ensure handle is closed
+#[cfg(windows)]
 struct HandleGuard(HANDLE);
 
+#[cfg(windows)]
 impl Drop for HandleGuard {
     fn drop(&mut self) {
         unsafe {
@@ -117,4 +216,20 @@ mod tests {
             println!("Detected processes: {:?}", processes);
         }
     }
+
+    #[test]
+    fn test_reason_by_kind_of_process() {
+        let mut process = ProcessInfo {
+            name: "cmd.exe".to_string(),
+            pid: 42,
+            started: None,
+            has_window: true,
+            is_service: false,
+        };
+        assert!(process.reason().contains("close and reopen it"));
+        process.has_window = false;
+        assert!(process.reason().contains("no window of its own"));
+        process.is_service = true;
+        assert!(process.reason().contains("service restarts"));
+    }
 }
@@ -0,0 +1,207 @@
+use anyhow::{bail, Result};
+
+use crate::config::Settings;
+
+/// A group of features a launch profile turns on or off
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// Changing entries, applying, restoring backups and elevating
+    Edit,
+    /// Diagnostics: security scan, shims, toolchains, live environment, where is command
+    Doctor,
+    /// Apply history and change summary reports
+    Reports,
+    /// Connecting to other computers
+    Remote,
+    /// Creating and browsing backups
+    Backup,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 5] = [
+        Feature::Edit,
+        Feature::Doctor,
+        Feature::Reports,
+        Feature::Remote,
+        Feature::Backup,
+    ];
+
+    /// Name in config.ini
+    pub fn key(self) -> &'static str {
+        match self {
+            Feature::Edit => "edit",
+            Feature::Doctor => "doctor",
+            Feature::Reports => "reports",
+            Feature::Remote => "remote",
+            Feature::Backup => "backup",
+        }
+    }
+
+    /// What is turned off, for the status bar
+    pub fn label(self) -> &'static str {
+        match self {
+            Feature::Edit => "editing",
+            Feature::Doctor => "diagnostics",
+            Feature::Reports => "reports",
+            Feature::Remote => "remote computers",
+            Feature::Backup => "backups",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase();
+        Self::ALL.into_iter().find(|feature| feature.key() == value)
+    }
+}
+
+/// Features turned on by a `features = ...` list, unknown names are ignored
+/// `all` turns everything on
+pub fn parse_features(value: &str) -> Vec<Feature> {
+    if value.trim().eq_ignore_ascii_case("all") {
+        return Feature::ALL.to_vec();
+    }
+    let mut features = Vec::new();
+    for feature in value.split(',').filter_map(Feature::parse) {
+        if !features.contains(&feature) {
+            features.push(feature);
+        }
+    }
+    features
+}
+
+/// Features a `--profile` launch is limited to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    /// Name given on the command line, empty when started without one
+    pub name: String,
+    features: Vec<Feature>,
+}
+
+impl Default for Profile {
+    /// Started without `--profile`: everything is on
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            features: Feature::ALL.to_vec(),
+        }
+    }
+}
+
+/// Profiles every installation knows, `[profile.NAME]` in config.ini replaces them
+const BUILTIN: [(&str, &[Feature]); 3] = [
+    ("admin", &Feature::ALL),
+    ("auditor", &[Feature::Reports]),
+    ("helpdesk", &[Feature::Doctor]),
+];
+
+impl Profile {
+    /// The profile named on the command line, from config.ini or built in
+    pub fn resolve(name: &str, settings: &Settings) -> Result<Self> {
+        let name = name.trim().to_lowercase();
+        let features = match settings.profiles.get(&name) {
+            Some(features) => features.clone(),
+            None => match BUILTIN.iter().find(|(builtin, _)| *builtin == name) {
+                Some((_, features)) => features.to_vec(),
+                None => bail!(
+                    "unknown profile '{}', expected one of: {}",
+                    name,
+                    Self::names(settings).join(", ")
+                ),
+            },
+        };
+        Ok(Self { name, features })
+    }
+
+    /// Built-in and configured profile names, sorted
+    pub fn names(settings: &Settings) -> Vec<String> {
+        let mut names: Vec<String> = BUILTIN
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .chain(settings.profiles.keys().cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    pub fn allows(&self, feature: Feature) -> bool {
+        self.features.contains(&feature)
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        !self.allows(Feature::Edit)
+    }
+
+    /// Shown in the status bar, None without `--profile`
+    pub fn badge(&self) -> Option<String> {
+        if self.name.is_empty() {
+            None
+        } else if self.is_read_only() {
+            Some(format!("{} (read-only)", self.name))
+        } else {
+            Some(self.name.clone())
+        }
+    }
+
+    /// Status message for something the profile turns off
+    pub fn denied(&self, feature: Feature) -> String {
+        format!(
+            "Not available in the {} profile: {}",
+            self.name,
+            feature.label()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_profiles() {
+        let settings = Settings::default();
+        let helpdesk = Profile::resolve("HelpDesk", &settings).unwrap();
+        assert!(helpdesk.is_read_only());
+        assert!(helpdesk.allows(Feature::Doctor));
+        assert!(!helpdesk.allows(Feature::Reports));
+        assert_eq!(helpdesk.badge(), Some("helpdesk (read-only)".to_string()));
+
+        let auditor = Profile::resolve("auditor", &settings).unwrap();
+        assert!(auditor.is_read_only());
+        assert!(auditor.allows(Feature::Reports));
+
+        let admin = Profile::resolve("admin", &settings).unwrap();
+        assert!(Feature::ALL.iter().all(|f| admin.allows(*f)));
+        assert_eq!(admin.badge(), Some("admin".to_string()));
+        assert_eq!(Profile::default().badge(), None);
+
+        let error = Profile::resolve("intern", &settings).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unknown profile 'intern', expected one of: admin, auditor, helpdesk"
+        );
+    }
+
+    #[test]
+    fn test_configured_profiles() {
+        let mut settings = Settings::default();
+        settings
+            .profiles
+            .insert("helpdesk".to_string(), parse_features("doctor, remote"));
+        settings
+            .profiles
+            .insert("packaging".to_string(), parse_features("edit,backup,bogus"));
+
+        // A section replaces the built-in profile of the same name
+        let helpdesk = Profile::resolve("helpdesk", &settings).unwrap();
+        assert!(helpdesk.allows(Feature::Remote));
+        let packaging = Profile::resolve("packaging", &settings).unwrap();
+        assert!(!packaging.is_read_only());
+        assert!(!packaging.allows(Feature::Remote));
+        assert_eq!(
+            Profile::names(&settings),
+            vec!["admin", "auditor", "helpdesk", "packaging"]
+        );
+        assert_eq!(parse_features("ALL"), Feature::ALL.to_vec());
+    }
+}
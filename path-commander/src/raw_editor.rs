@@ -0,0 +1,132 @@
+use crate::path_analyzer::is_malformed_entry;
+use crate::registry::{join_paths, parse_path};
+use crate::ui::TextArea;
+
+/// Characters no Windows directory name can contain, quotes aside
+const INVALID_CHARS: [char; 5] = ['<', '>', '|', '?', '*'];
+
+/// The whole PATH value of one scope as text, one entry per line
+/// Lines are joined with `;` again, so a pasted `a;b;c` works as well as one entry per line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawEditor {
+    pub text: TextArea,
+    /// Showing the diff before the entries are put back in the panel
+    pub reviewing: bool,
+    before: Vec<String>,
+}
+
+impl RawEditor {
+    pub fn new(entries: &[String]) -> Self {
+        // One entry per line, no separator after the last one, like the registry value
+        let raw = join_paths(entries);
+        Self {
+            text: TextArea::new(&parse_path(&raw).join(";\n")),
+            reviewing: false,
+            before: entries.to_vec(),
+        }
+    }
+
+    /// Entries as they were when the editor opened
+    pub fn before(&self) -> &[String] {
+        &self.before
+    }
+
+    /// The text parsed the way the registry value is, empty segments dropped
+    pub fn entries(&self) -> Vec<String> {
+        parse_path(&self.text.lines.join(";"))
+            .into_iter()
+            .filter(|entry| !entry.is_empty())
+            .collect()
+    }
+
+    pub fn is_changed(&self) -> bool {
+        self.entries() != self.before
+    }
+
+    /// What is wrong with the text right now, checked again on every key
+    pub fn problems(&self) -> Vec<String> {
+        let entries = self.entries();
+        let mut problems = Vec::new();
+        if self
+            .text
+            .lines
+            .iter()
+            .map(|line| line.matches('"').count())
+            .sum::<usize>()
+            % 2
+            == 1
+        {
+            problems.push("A quote is never closed, everything after it is one entry".to_string());
+        }
+        for (i, entry) in entries.iter().enumerate() {
+            let number = i + 1;
+            if entry.trim().is_empty() {
+                problems.push(format!("Entry {} is only spaces", number));
+            } else if let Some(c) = entry.chars().find(|c| INVALID_CHARS.contains(c)) {
+                problems.push(format!(
+                    "Entry {} contains {}, no directory name can",
+                    number, c
+                ));
+            } else if is_malformed_entry(entry) {
+                problems.push(format!("Entry {} has stray spaces or quotes", number));
+            }
+            let key = |entry: &str| entry.trim().trim_end_matches('\\').to_lowercase();
+            if let Some(first) = entries[..i].iter().position(|e| key(e) == key(entry)) {
+                problems.push(format!("Entry {} repeats entry {}", number, first + 1));
+            }
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(list: &[&str]) -> Vec<String> {
+        list.iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        // Quotes keep the semicolon inside an entry, in the registry and here
+        let before = entries(&[r"C:\Tools", r#""C:\a;b""#, r"C:\Go\bin"]);
+        let mut editor = RawEditor::new(&before);
+        assert_eq!(
+            editor.text.lines,
+            [r"C:\Tools;", r#""C:\a;b";"#, r"C:\Go\bin"]
+        );
+        assert_eq!(editor.entries(), before);
+        assert!(!editor.is_changed());
+
+        // Type a new entry on its own line, the missing ; is implied by the line break
+        editor.text.move_lines(2, false);
+        editor.text.end(false);
+        editor.text.insert_str("\nD:\\New");
+        assert_eq!(editor.entries().last().unwrap(), r"D:\New");
+        assert!(editor.is_changed());
+
+        // Pasting a whole value on one line works too
+        let mut pasted = RawEditor::new(&[]);
+        pasted.text.insert_str(r"C:\a;;C:\b;");
+        assert_eq!(pasted.entries(), entries(&[r"C:\a", r"C:\b"]));
+    }
+
+    #[test]
+    fn test_problems() {
+        let mut editor = RawEditor::new(&entries(&[r"C:\Tools", r"c:\tools\", " ", r"C:\a|b"]));
+        assert_eq!(
+            editor.problems(),
+            [
+                "Entry 2 repeats entry 1",
+                "Entry 3 is only spaces",
+                "Entry 4 contains |, no directory name can",
+            ]
+        );
+        editor.text.insert('"');
+        assert_eq!(
+            editor.problems()[0],
+            "A quote is never closed, everything after it is one entry"
+        );
+    }
+}
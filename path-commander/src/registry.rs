@@ -1,21 +1,32 @@
 use anyhow::Result;
+#[cfg(windows)]
 use std::ffi::OsString;
+#[cfg(windows)]
 use std::os::windows::ffi::OsStringExt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+#[cfg(windows)]
 use windows::core::PCWSTR;
+#[cfg(windows)]
 use windows::Win32::Foundation::ERROR_SUCCESS;
+#[cfg(windows)]
 use windows::Win32::System::Registry::{
-    RegCloseKey, RegConnectRegistryW, RegGetValueW, RegOpenKeyExW, RegSetValueExW, HKEY,
-    HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, KEY_READ, KEY_WRITE, REG_EXPAND_SZ, REG_VALUE_TYPE,
-    RRF_RT_REG_EXPAND_SZ, RRF_RT_REG_SZ,
+    RegCloseKey, RegConnectRegistryW, RegGetValueW, RegOpenKeyExW, RegQueryInfoKeyW,
+    RegSetValueExW, HKEY, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, KEY_READ, KEY_WRITE,
+    REG_EXPAND_SZ, REG_VALUE_TYPE, RRF_RT_REG_EXPAND_SZ, RRF_RT_REG_SZ,
 };
 
+#[cfg(windows)]
 const ENVIRONMENT_KEY: &str = "Environment";
+#[cfg(windows)]
 const SYSTEM_ENVIRONMENT_KEY: &str =
     "SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Environment";
+#[cfg(windows)]
+const VOLATILE_ENVIRONMENT_KEY: &str = "Volatile Environment";
+#[cfg(windows)]
 const PATH_VALUE: &str = "Path";
 
 /// Represents whether we're working with USER or MACHINE (SYSTEM) paths
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PathScope {
     User,
     Machine,
@@ -28,15 +39,144 @@ impl PathScope {
             PathScope::Machine => "MACHINE",
         }
     }
+
+    /// Parse a scope name as written in config files and on the command line
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "user" => Some(PathScope::User),
+            "machine" | "system" => Some(PathScope::Machine),
+            _ => None,
+        }
+    }
+}
+
+/// Where the local PATH values are read from and written to
+/// Abstracted so the app can be tested without touching the Windows registry
+pub trait RegistryBackend {
+    /// Read the PATH entries of a scope
+    fn read_paths(&self, scope: PathScope) -> Result<Vec<String>>;
+
+    /// Replace the PATH entries of a scope
+    fn write_paths(&mut self, scope: PathScope, paths: &[String]) -> Result<()>;
+
+    /// When the storage of a scope was last written, if the backend can tell
+    fn last_modified(&self, _scope: PathScope) -> Option<SystemTime> {
+        None
+    }
+}
+
+/// Where PATH is stored on this platform
+pub fn local_backend() -> Box<dyn RegistryBackend> {
+    #[cfg(windows)]
+    {
+        Box::new(WindowsRegistry)
+    }
+    // Elsewhere PATH is declared in shell profiles
+    #[cfg(not(windows))]
+    {
+        Box::new(crate::path_files::ShellProfiles::new())
+    }
+}
+
+/// The local Windows registry
+#[cfg(windows)]
+pub struct WindowsRegistry;
+
+#[cfg(windows)]
+impl RegistryBackend for WindowsRegistry {
+    fn read_paths(&self, scope: PathScope) -> Result<Vec<String>> {
+        Ok(parse_path(&read_path(scope)?))
+    }
+
+    fn write_paths(&mut self, scope: PathScope, paths: &[String]) -> Result<()> {
+        write_path(scope, &join_paths(paths))
+    }
+
+    fn last_modified(&self, scope: PathScope) -> Option<SystemTime> {
+        last_write_time(scope).ok()
+    }
+}
+
+/// In-memory registry for tests, with optional access-denied failures
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MockRegistry {
+    values: std::collections::HashMap<PathScope, Vec<String>>,
+    modified: std::collections::HashMap<PathScope, SystemTime>,
+    denied_reads: Vec<PathScope>,
+    denied_writes: Vec<PathScope>,
+}
+
+#[cfg(test)]
+impl MockRegistry {
+    pub fn new(user_path: &str, machine_path: &str) -> Self {
+        let mut registry = Self::default();
+        registry
+            .values
+            .insert(PathScope::User, parse_path(user_path));
+        registry
+            .values
+            .insert(PathScope::Machine, parse_path(machine_path));
+        registry
+    }
+
+    /// Make reads of a scope fail like an inaccessible key
+    pub fn deny_read(mut self, scope: PathScope) -> Self {
+        self.denied_reads.push(scope);
+        self
+    }
+
+    /// Make writes to a scope fail like a non-admin writing MACHINE
+    pub fn deny_write(mut self, scope: PathScope) -> Self {
+        self.denied_writes.push(scope);
+        self
+    }
+
+    /// Pretend a scope was last written at a given time
+    pub fn modified_at(mut self, scope: PathScope, time: SystemTime) -> Self {
+        self.modified.insert(scope, time);
+        self
+    }
+}
+
+#[cfg(test)]
+impl RegistryBackend for MockRegistry {
+    fn read_paths(&self, scope: PathScope) -> Result<Vec<String>> {
+        if self.denied_reads.contains(&scope) {
+            return Err(anyhow::anyhow!(
+                "Failed to open registry key for {} paths",
+                scope.as_str()
+            ));
+        }
+        Ok(self.values.get(&scope).cloned().unwrap_or_default())
+    }
+
+    fn write_paths(&mut self, scope: PathScope, paths: &[String]) -> Result<()> {
+        if self.denied_writes.contains(&scope) {
+            return Err(anyhow::anyhow!(
+                "Failed to open registry key for writing {} paths. Do you have admin rights?",
+                scope.as_str()
+            ));
+        }
+        self.values.insert(scope, paths.to_vec());
+        self.modified.insert(scope, SystemTime::now());
+        Ok(())
+    }
+
+    fn last_modified(&self, scope: PathScope) -> Option<SystemTime> {
+        self.modified.get(&scope).copied()
+    }
 }
 
 /// Represents a connection to a remote computer's registry
+#[cfg(windows)]
 pub struct RemoteConnection {
     computer_name: String,
     hkey_local_machine: HKEY,
     hkey_current_user: HKEY,
 }
 
+#[cfg(windows)]
 impl RemoteConnection {
     /// Connect to a remote computer's registry
     pub fn connect(computer_name: &str) -> Result<Self> {
@@ -101,6 +241,12 @@ impl RemoteConnection {
     }
 }
 
+// Registry handles aren't tied to the thread that opened them, so a connection
+// made on a worker thread can be handed to the UI thread
+#[cfg(windows)]
+unsafe impl Send for RemoteConnection {}
+
+#[cfg(windows)]
 impl Drop for RemoteConnection {
     fn drop(&mut self) {
         unsafe {
@@ -111,17 +257,55 @@ impl Drop for RemoteConnection {
     }
 }
 
+/// Remote registry access needs Windows, so on other platforms connecting always fails
+#[cfg(not(windows))]
+#[allow(dead_code)] // Never constructed
+pub struct RemoteConnection {
+    computer_name: String,
+}
+
+#[cfg(not(windows))]
+impl RemoteConnection {
+    pub fn connect(computer_name: &str) -> Result<Self> {
+        Err(anyhow::anyhow!(
+            "Cannot connect to '{}': remote mode is only available on Windows",
+            computer_name
+        ))
+    }
+
+    pub fn computer_name(&self) -> &str {
+        &self.computer_name
+    }
+}
+
+#[cfg(not(windows))]
+pub fn read_path_remote(_scope: PathScope, connection: &RemoteConnection) -> Result<String> {
+    RemoteConnection::connect(connection.computer_name()).map(|_| String::new())
+}
+
+#[cfg(not(windows))]
+pub fn write_path_remote(
+    _scope: PathScope,
+    _value: &str,
+    connection: &RemoteConnection,
+) -> Result<()> {
+    RemoteConnection::connect(connection.computer_name()).map(|_| ())
+}
+
 /// Read the PATH environment variable from the registry
+#[cfg(windows)]
 pub fn read_path(scope: PathScope) -> Result<String> {
     read_path_with_connection(scope, None)
 }
 
 /// Read the PATH environment variable from a remote registry
+#[cfg(windows)]
 pub fn read_path_remote(scope: PathScope, connection: &RemoteConnection) -> Result<String> {
     read_path_with_connection(scope, Some(connection))
 }
 
 /// Internal function to read PATH with optional remote connection
+#[cfg(windows)]
 fn read_path_with_connection(
     scope: PathScope,
     connection: Option<&RemoteConnection>,
@@ -215,12 +399,179 @@ fn read_path_with_connection(
     }
 }
 
+/// Read PATH from HKCU\Volatile Environment, None when this logon session does not set one
+#[cfg(windows)]
+pub fn read_volatile_path() -> Result<Option<String>> {
+    use windows::Win32::Foundation::ERROR_FILE_NOT_FOUND;
+
+    let subkey_wide = to_wide_string(VOLATILE_ENVIRONMENT_KEY);
+    let value_name_wide = to_wide_string(PATH_VALUE);
+    let mut buffer_size = 0u32;
+    unsafe {
+        let result = RegGetValueW(
+            HKEY_CURRENT_USER,
+            PCWSTR(subkey_wide.as_ptr()),
+            PCWSTR(value_name_wide.as_ptr()),
+            RRF_RT_REG_SZ | RRF_RT_REG_EXPAND_SZ,
+            None,
+            None,
+            Some(&mut buffer_size),
+        );
+        if result == ERROR_FILE_NOT_FOUND {
+            return Ok(None);
+        }
+        if result != ERROR_SUCCESS {
+            return Err(anyhow::anyhow!("Failed to query volatile PATH size"));
+        }
+
+        let mut buffer = vec![0u16; (buffer_size / 2) as usize];
+        let result = RegGetValueW(
+            HKEY_CURRENT_USER,
+            PCWSTR(subkey_wide.as_ptr()),
+            PCWSTR(value_name_wide.as_ptr()),
+            RRF_RT_REG_SZ | RRF_RT_REG_EXPAND_SZ,
+            None,
+            Some(buffer.as_mut_ptr() as *mut _),
+            Some(&mut buffer_size),
+        );
+        if result != ERROR_SUCCESS {
+            return Err(anyhow::anyhow!("Failed to read volatile PATH"));
+        }
+
+        let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+        let path_string = OsString::from_wide(&buffer[..len])
+            .into_string()
+            .map_err(|_| anyhow::anyhow!("Invalid UTF-8 in volatile PATH"))?;
+        Ok(Some(path_string))
+    }
+}
+
+/// Only Windows has a volatile environment
+#[cfg(not(windows))]
+pub fn read_volatile_path() -> Result<Option<String>> {
+    Ok(None)
+}
+
+/// Names of the subkeys directly under a key
+#[cfg(windows)]
+pub(crate) fn subkey_names(root: HKEY, key: &str) -> Result<Vec<String>> {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::ERROR_NO_MORE_ITEMS;
+    use windows::Win32::System::Registry::RegEnumKeyExW;
+
+    let key_wide = to_wide_string(key);
+    let mut hkey = HKEY::default();
+    unsafe {
+        if RegOpenKeyExW(root, PCWSTR(key_wide.as_ptr()), 0, KEY_READ, &mut hkey) != ERROR_SUCCESS {
+            return Err(anyhow::anyhow!("Failed to open registry key {}", key));
+        }
+
+        let mut names = Vec::new();
+        let mut index = 0;
+        loop {
+            // Key names are limited to 255 characters
+            let mut name = [0u16; 256];
+            let mut len = name.len() as u32;
+            let result = RegEnumKeyExW(
+                hkey,
+                index,
+                PWSTR(name.as_mut_ptr()),
+                &mut len,
+                None,
+                PWSTR::null(),
+                None,
+                None,
+            );
+            if result == ERROR_NO_MORE_ITEMS {
+                break;
+            }
+            if result == ERROR_SUCCESS {
+                names.push(String::from_utf16_lossy(&name[..len as usize]));
+            }
+            index += 1;
+        }
+
+        let _ = RegCloseKey(hkey).ok();
+        Ok(names)
+    }
+}
+
+/// A REG_SZ or REG_EXPAND_SZ value under a key, unexpanded, None when missing
+#[cfg(windows)]
+pub(crate) fn string_value(root: HKEY, key: &str, value: &str) -> Option<String> {
+    use windows::Win32::System::Registry::RRF_NOEXPAND;
+
+    let key_wide = to_wide_string(key);
+    let value_wide = to_wide_string(value);
+    let flags = RRF_RT_REG_SZ | RRF_RT_REG_EXPAND_SZ | RRF_NOEXPAND;
+    let mut buffer_size = 0u32;
+    unsafe {
+        if RegGetValueW(
+            root,
+            PCWSTR(key_wide.as_ptr()),
+            PCWSTR(value_wide.as_ptr()),
+            flags,
+            None,
+            None,
+            Some(&mut buffer_size),
+        ) != ERROR_SUCCESS
+        {
+            return None;
+        }
+
+        let mut buffer = vec![0u16; (buffer_size / 2) as usize];
+        if RegGetValueW(
+            root,
+            PCWSTR(key_wide.as_ptr()),
+            PCWSTR(value_wide.as_ptr()),
+            flags,
+            None,
+            Some(buffer.as_mut_ptr() as *mut _),
+            Some(&mut buffer_size),
+        ) != ERROR_SUCCESS
+        {
+            return None;
+        }
+
+        let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+        Some(String::from_utf16_lossy(&buffer[..len]))
+    }
+}
+
+/// A REG_DWORD value under a key, None when missing
+#[cfg(windows)]
+pub(crate) fn dword_value(root: HKEY, key: &str, value: &str) -> Option<u32> {
+    use windows::Win32::System::Registry::RRF_RT_REG_DWORD;
+
+    let key_wide = to_wide_string(key);
+    let value_wide = to_wide_string(value);
+    let mut data = 0u32;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    unsafe {
+        if RegGetValueW(
+            root,
+            PCWSTR(key_wide.as_ptr()),
+            PCWSTR(value_wide.as_ptr()),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut data as *mut u32 as *mut _),
+            Some(&mut size),
+        ) != ERROR_SUCCESS
+        {
+            return None;
+        }
+    }
+    Some(data)
+}
+
 /// Write the PATH environment variable to the registry
+#[cfg(windows)]
 pub fn write_path(scope: PathScope, value: &str) -> Result<()> {
     write_path_with_connection(scope, value, None)
 }
 
 /// Write the PATH environment variable to a remote registry
+#[cfg(windows)]
 pub fn write_path_remote(
     scope: PathScope,
     value: &str,
@@ -230,6 +581,7 @@ pub fn write_path_remote(
 }
 
 /// Internal function to write PATH with optional remote connection
+#[cfg(windows)]
 fn write_path_with_connection(
     scope: PathScope,
     value: &str,
@@ -302,22 +654,152 @@ fn write_path_with_connection(
     }
 }
 
+/// Last write time of the local Environment key of a scope
+#[cfg(windows)]
+pub fn last_write_time(scope: PathScope) -> Result<SystemTime> {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::FILETIME;
+
+    let (hkey_root, subkey) = match scope {
+        PathScope::User => (HKEY_CURRENT_USER, ENVIRONMENT_KEY),
+        PathScope::Machine => (HKEY_LOCAL_MACHINE, SYSTEM_ENVIRONMENT_KEY),
+    };
+    let subkey_wide = to_wide_string(subkey);
+    let mut written = FILETIME::default();
+
+    unsafe {
+        let mut hkey = HKEY::default();
+        let result = RegOpenKeyExW(
+            hkey_root,
+            PCWSTR(subkey_wide.as_ptr()),
+            0,
+            KEY_READ,
+            &mut hkey,
+        );
+        if result != ERROR_SUCCESS {
+            return Err(anyhow::anyhow!(
+                "Failed to open registry key for {} paths",
+                scope.as_str()
+            ));
+        }
+
+        let result = RegQueryInfoKeyW(
+            hkey,
+            PWSTR::null(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(&mut written),
+        );
+        let _ = RegCloseKey(hkey);
+        if result != ERROR_SUCCESS {
+            return Err(anyhow::anyhow!(
+                "Failed to query registry key for {} paths: {:?}",
+                scope.as_str(),
+                result
+            ));
+        }
+    }
+
+    Ok(filetime_to_system_time(
+        ((written.dwHighDateTime as u64) << 32) | written.dwLowDateTime as u64,
+    ))
+}
+
+/// Convert a FILETIME (100ns intervals since 1601) to a SystemTime
+#[cfg_attr(not(windows), allow(dead_code))]
+pub(crate) fn filetime_to_system_time(intervals: u64) -> SystemTime {
+    // Seconds between 1601-01-01 and 1970-01-01
+    const UNIX_EPOCH_OFFSET: u64 = 11_644_473_600;
+    let since_1601 = Duration::from_nanos(intervals.saturating_mul(100));
+    let since_1970 = since_1601
+        .checked_sub(Duration::from_secs(UNIX_EPOCH_OFFSET))
+        .unwrap_or_default();
+    UNIX_EPOCH + since_1970
+}
+
 /// Parse a PATH string into individual entries
+///
+/// Segments are kept verbatim (empty segments and stray whitespace included) so that
+/// `join_paths(&parse_path(s)) == s`. A semicolon inside double quotes does not split
+/// the entry, matching how cmd.exe reads quoted PATH entries. A quote that is never
+/// closed protects nothing, the rest is split on every semicolon and the entry with the
+/// stray quote is left for the analyzer to flag as malformed.
 pub fn parse_path(path_string: &str) -> Vec<String> {
-    path_string
-        .split(';')
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty())
-        .collect()
+    if path_string.is_empty() {
+        return Vec::new();
+    }
+
+    let mut entries = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    // Where the open quote sits in `current`
+    let mut opened_at = 0;
+
+    for c in path_string.chars() {
+        match c {
+            '"' => {
+                if !in_quotes {
+                    opened_at = current.len();
+                }
+                in_quotes = !in_quotes;
+                current.push(c);
+            }
+            ';' if !in_quotes => entries.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    if in_quotes {
+        let unclosed = current.split_off(opened_at);
+        let mut segments = unclosed.split(';');
+        current.push_str(segments.next().unwrap_or_default());
+        entries.push(current);
+        entries.extend(segments.map(str::to_string));
+    } else {
+        entries.push(current);
+    }
+
+    entries
 }
 
 /// Join path entries into a PATH string
+/// Entries containing an unquoted semicolon are wrapped in quotes so they survive a re-parse
 pub fn join_paths(paths: &[String]) -> String {
-    paths.join(";")
+    paths
+        .iter()
+        .map(|p| {
+            if has_unquoted_semicolon(p) {
+                format!("\"{}\"", p)
+            } else {
+                p.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Check if an entry contains a semicolon outside of double quotes
+fn has_unquoted_semicolon(entry: &str) -> bool {
+    let mut in_quotes = false;
+    for c in entry.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => return true,
+            _ => {}
+        }
+    }
+    false
 }
 
 /// Convert a Rust string to a null-terminated wide string
-fn to_wide_string(s: &str) -> Vec<u16> {
+#[cfg(windows)]
+pub(crate) fn to_wide_string(s: &str) -> Vec<u16> {
     use std::os::windows::ffi::OsStrExt;
     std::ffi::OsStr::new(s)
         .encode_wide()
@@ -326,6 +808,7 @@ fn to_wide_string(s: &str) -> Vec<u16> {
 }
 
 /// Broadcast environment change notification
+#[cfg(windows)]
 fn broadcast_environment_change() -> Result<()> {
     use windows::Win32::Foundation::{LPARAM, WPARAM};
     use windows::Win32::UI::WindowsAndMessaging::{
@@ -371,4 +854,63 @@ mod tests {
         let joined = join_paths(&paths);
         assert_eq!(joined, r"C:\Windows;C:\Windows\System32");
     }
+
+    #[test]
+    fn test_parse_path_keeps_empty_segments() {
+        let entries = parse_path(r"C:\Windows;;C:\Tools;");
+        assert_eq!(entries, vec![r"C:\Windows", "", r"C:\Tools", ""]);
+        assert!(parse_path("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_path_quoted_semicolon() {
+        let entries = parse_path(r#"C:\Windows;"C:\Odd;Name";C:\Tools"#);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1], r#""C:\Odd;Name""#);
+    }
+
+    #[test]
+    fn test_parse_path_unclosed_quote() {
+        let value = r#"C:\A;"C:\B;C:\C;C:\D"#;
+        let entries = parse_path(value);
+        assert_eq!(entries, vec![r"C:\A", r#""C:\B"#, r"C:\C", r"C:\D"]);
+        assert!(crate::path_analyzer::is_malformed_entry(&entries[1]));
+        assert_eq!(join_paths(&entries), value);
+
+        // Balanced quotes before the stray one still protect their semicolon
+        let entries = parse_path(r#""C:\Odd;Name";C:\X"y;C:\Z"#);
+        assert_eq!(entries, vec![r#""C:\Odd;Name""#, r#"C:\X"y"#, r"C:\Z"]);
+    }
+
+    #[test]
+    fn test_parse_join_round_trip() {
+        let values = [
+            r"C:\Windows;C:\Windows\System32",
+            r"C:\Windows;;C:\Tools;",
+            r#" C:\Windows ;"C:\Program Files\App";"D:\a;b""#,
+        ];
+        for value in values {
+            assert_eq!(join_paths(&parse_path(value)), value);
+        }
+    }
+
+    #[test]
+    fn test_join_paths_quotes_semicolon_entries() {
+        let paths = vec![r"C:\Odd;Name".to_string(), r"C:\Tools".to_string()];
+        let joined = join_paths(&paths);
+        assert_eq!(joined, r#""C:\Odd;Name";C:\Tools"#);
+        assert_eq!(parse_path(&joined)[0], r#""C:\Odd;Name""#);
+    }
+
+    #[test]
+    fn test_filetime_to_system_time() {
+        // 1970-01-01 in FILETIME intervals
+        assert_eq!(filetime_to_system_time(116_444_736_000_000_000), UNIX_EPOCH);
+        assert_eq!(
+            filetime_to_system_time(116_444_736_000_000_000 + 10_000_000),
+            UNIX_EPOCH + Duration::from_secs(1)
+        );
+        // Before 1970 is clamped
+        assert_eq!(filetime_to_system_time(0), UNIX_EPOCH);
+    }
 }
@@ -0,0 +1,117 @@
+/// Version of this build, as released
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The changelog shipped inside the executable, so release notes work offline
+const CHANGELOG: &str = include_str!("../docs/development/CHANGELOG.md");
+
+/// Notes of one version from the changelog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    /// "0.6.2", or "Unreleased" for changes not in a release yet
+    pub version: String,
+    pub date: Option<String>,
+    /// Headings like "Added" or "Fixed" with their bullet lines, indentation kept
+    pub sections: Vec<(String, Vec<String>)>,
+}
+
+impl Release {
+    /// Title for the dialog, "v0.6.2 (2025-01-17)"
+    pub fn title(&self) -> String {
+        let version = if self
+            .version
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_digit())
+        {
+            format!("v{}", self.version)
+        } else {
+            self.version.clone()
+        };
+        match &self.date {
+            Some(date) => format!("{} ({})", version, date),
+            None => version,
+        }
+    }
+}
+
+/// Releases of the embedded changelog, newest first
+pub fn releases() -> Vec<Release> {
+    parse(CHANGELOG)
+}
+
+/// Index of the running version among the releases, the newest if it is not listed
+pub fn current_index(releases: &[Release]) -> usize {
+    releases
+        .iter()
+        .position(|release| release.version == CURRENT_VERSION)
+        .unwrap_or(0)
+}
+
+/// Split a Keep a Changelog document into releases
+/// `## [version] - date` starts a release, `### Heading` a section within it
+pub fn parse(changelog: &str) -> Vec<Release> {
+    let mut releases: Vec<Release> = Vec::new();
+    for line in changelog.lines() {
+        if let Some(heading) = line.strip_prefix("## ") {
+            let (version, date) = match heading.split_once(" - ") {
+                Some((version, date)) => (version, Some(date.trim().to_string())),
+                None => (heading, None),
+            };
+            releases.push(Release {
+                version: version
+                    .trim()
+                    .trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .to_string(),
+                date,
+                sections: Vec::new(),
+            });
+        } else if let Some(heading) = line.strip_prefix("### ") {
+            if let Some(release) = releases.last_mut() {
+                release
+                    .sections
+                    .push((heading.trim().to_string(), Vec::new()));
+            }
+        } else if !line.trim().is_empty() {
+            // Text before the first section heading is the document's own introduction
+            if let Some((_, lines)) = releases
+                .last_mut()
+                .and_then(|release| release.sections.last_mut())
+            {
+                lines.push(line.trim_end().replace("**", ""));
+            }
+        }
+    }
+    releases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_changelog() {
+        let releases = parse(
+            "# Changelog\n\nIntro text.\n\n## [Unreleased]\n\n### Added\n- **Bold** feature\n  - Detail\n\n\
+             ## [0.6.2] - 2025-01-17\n\n### Fixed\n- A fix\n\n### Changed\n- A change\n",
+        );
+        assert_eq!(releases.len(), 2);
+        assert_eq!(releases[0].title(), "Unreleased");
+        assert_eq!(
+            releases[0].sections,
+            vec![(
+                "Added".to_string(),
+                vec!["- Bold feature".to_string(), "  - Detail".to_string()]
+            )]
+        );
+        assert_eq!(releases[1].title(), "v0.6.2 (2025-01-17)");
+        assert_eq!(releases[1].sections.len(), 2);
+        assert_eq!(current_index(&releases[..1]), 0);
+    }
+
+    #[test]
+    fn test_embedded_changelog_lists_this_version() {
+        let releases = releases();
+        assert_eq!(releases[current_index(&releases)].version, CURRENT_VERSION);
+    }
+}
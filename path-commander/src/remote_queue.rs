@@ -0,0 +1,199 @@
+use chrono::Local;
+use std::time::{Duration, Instant};
+
+/// Attempts before automatic retries stop, the failed apply being the first
+pub const MAX_ATTEMPTS: u32 = 5;
+
+/// Registry key the remote MACHINE PATH lives under, as a .reg file names it
+const MACHINE_ENVIRONMENT_KEY: &str =
+    r"HKEY_LOCAL_MACHINE\SYSTEM\CurrentControlSet\Control\Session Manager\Environment";
+
+/// Bytes per line of a hex value in a .reg file, regedit wraps about as often
+const REG_BYTES_PER_LINE: usize = 24;
+
+/// A remote MACHINE PATH write that failed and waits to be tried again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedWrite {
+    pub host: String,
+    /// The remote PATH the apply started from, a retry only writes over this value
+    pub base: Vec<String>,
+    pub paths: Vec<String>,
+    /// Why the last attempt failed
+    pub error: String,
+    pub attempts: u32,
+    /// When the next automatic attempt is due, None once they are used up
+    pub next_retry: Option<Instant>,
+}
+
+impl QueuedWrite {
+    /// Queue the entries a failed apply could not write over `base`
+    pub fn new(
+        host: &str,
+        base: Vec<String>,
+        paths: Vec<String>,
+        error: String,
+        now: Instant,
+    ) -> Self {
+        Self {
+            host: host.to_string(),
+            base,
+            paths,
+            error,
+            attempts: 1,
+            next_retry: Some(now + backoff(1)),
+        }
+    }
+
+    /// Record another failed attempt and schedule the next one, if any are left
+    pub fn failed_again(&mut self, error: String, now: Instant) {
+        self.attempts += 1;
+        self.error = error;
+        self.next_retry = (self.attempts < MAX_ATTEMPTS).then(|| now + backoff(self.attempts));
+    }
+
+    /// The remote PATH changed during the outage: writing would throw that change away,
+    /// so retrying stops
+    pub fn conflicted(&mut self) {
+        self.attempts += 1;
+        self.error = format!(
+            "PATH on {} was changed there since the apply started, it was not overwritten",
+            self.host
+        );
+        self.next_retry = None;
+    }
+
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.next_retry.is_some_and(|due| now >= due)
+    }
+
+    /// "attempt 3 of 5 in 8s", or that retrying is now up to the user
+    pub fn describe_next(&self, now: Instant) -> String {
+        match self.next_retry {
+            Some(due) => format!(
+                "attempt {} of {} in {}s",
+                self.attempts + 1,
+                MAX_ATTEMPTS,
+                due.saturating_duration_since(now).as_secs()
+            ),
+            None => "no automatic retries left".to_string(),
+        }
+    }
+
+    /// A .reg file that writes the queued value when imported on the remote computer
+    pub fn to_reg(&self) -> String {
+        let value = crate::registry::join_paths(&self.paths);
+        // REG_EXPAND_SZ is written as UTF-16LE bytes with the terminating null
+        let bytes: Vec<String> = value
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(u16::to_le_bytes)
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        let hex = bytes
+            .chunks(REG_BYTES_PER_LINE)
+            .map(|line| line.join(","))
+            .collect::<Vec<_>>()
+            .join(",\\\r\n  ");
+        format!(
+            "Windows Registry Editor Version 5.00\r\n\r\n\
+             ; PATH for {} saved by Path Commander on {}\r\n\
+             [{}]\r\n\"Path\"=hex(2):{}\r\n",
+            self.host,
+            Local::now().format("%Y-%m-%d %H:%M"),
+            MACHINE_ENVIRONMENT_KEY,
+            hex
+        )
+    }
+
+    /// path_SRV01_20261015_143000.reg
+    pub fn file_name(&self) -> String {
+        let host: String = self
+            .host
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("path_{}_{}.reg", host, Local::now().format("%Y%m%d_%H%M%S"))
+    }
+}
+
+/// How a retry on a fresh connection ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RetryOutcome {
+    Written,
+    /// The remote PATH is no longer the one the apply started from, this is what it holds now
+    Changed(Vec<String>),
+}
+
+/// Wait after the given number of failed attempts: 2, 4, 8 and 16 seconds
+pub fn backoff(attempts: u32) -> Duration {
+    Duration::from_secs(2u64.pow(attempts.min(6)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retries_back_off_then_stop() {
+        let now = Instant::now();
+        let mut queued = QueuedWrite::new("SRV01", vec![], vec![], "timeout".to_string(), now);
+        assert!(!queued.is_due(now));
+        assert!(queued.is_due(now + Duration::from_secs(2)));
+        assert_eq!(queued.describe_next(now), "attempt 2 of 5 in 2s");
+
+        queued.failed_again("timeout".to_string(), now);
+        assert_eq!(queued.next_retry, Some(now + Duration::from_secs(4)));
+        for _ in 0..3 {
+            queued.failed_again("still down".to_string(), now);
+        }
+        assert_eq!(queued.attempts, MAX_ATTEMPTS);
+        assert!(!queued.is_due(now + Duration::from_secs(3600)));
+        assert_eq!(queued.describe_next(now), "no automatic retries left");
+        assert_eq!(queued.error, "still down");
+
+        // A change on the remote computer ends the retries for good
+        let mut queued = QueuedWrite::new("SRV01", vec![], vec![], "timeout".to_string(), now);
+        queued.conflicted();
+        assert_eq!(queued.attempts, 2);
+        assert!(!queued.is_due(now + Duration::from_secs(3600)));
+        assert!(queued
+            .error
+            .contains("was changed there since the apply started"));
+    }
+
+    #[test]
+    fn test_reg_file() {
+        let queued = QueuedWrite::new(
+            r"srv01.corp",
+            vec![],
+            vec![r"C:\A".to_string(), "%B%".to_string()],
+            String::new(),
+            Instant::now(),
+        );
+        let reg = queued.to_reg();
+        assert!(reg.starts_with("Windows Registry Editor Version 5.00\r\n"));
+        assert!(reg.contains(
+            "[HKEY_LOCAL_MACHINE\\SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Environment]"
+        ));
+        // "C:\A;%B%" and the null, 18 bytes fit on one line
+        assert!(reg.ends_with(
+            "\"Path\"=hex(2):43,00,3a,00,5c,00,41,00,3b,00,25,00,42,00,25,00,00,00\r\n"
+        ));
+        assert!(queued.file_name().starts_with("path_srv01_corp_"));
+
+        let long = QueuedWrite::new(
+            "SRV01",
+            vec![],
+            vec!["x".repeat(20)],
+            String::new(),
+            Instant::now(),
+        );
+        assert_eq!(long.to_reg().matches(",\\\r\n  ").count(), 1);
+    }
+}
@@ -0,0 +1,122 @@
+use std::time::{Duration, Instant};
+
+/// Decides when the event loop redraws: only after something changed, and at most
+/// `max_fps` times per second. Idle sessions don't repaint at all, which avoids
+/// flicker and needless traffic over RDP.
+pub struct RenderScheduler {
+    dirty: bool,
+    frame_interval: Duration,
+    last_frame: Option<Instant>,
+    last_tick: Instant,
+}
+
+impl RenderScheduler {
+    /// `max_fps` of None or 0 disables the cap
+    pub fn new(max_fps: Option<u32>) -> Self {
+        let frame_interval = match max_fps {
+            Some(fps) if fps > 0 => Duration::from_secs(1) / fps,
+            _ => Duration::ZERO,
+        };
+        Self {
+            dirty: true, // The first frame is always drawn
+            frame_interval,
+            last_frame: None,
+            last_tick: Instant::now(),
+        }
+    }
+
+    /// Request a redraw
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Check if a frame should be drawn now
+    pub fn should_draw(&self, now: Instant) -> bool {
+        self.dirty && self.until_next_frame(now).is_zero()
+    }
+
+    pub fn frame_drawn(&mut self, now: Instant) {
+        self.dirty = false;
+        self.last_frame = Some(now);
+    }
+
+    /// Check if periodic work is due, restarting the tick interval if so
+    pub fn tick_due(&mut self, now: Instant, tick: Duration) -> bool {
+        if now.duration_since(self.last_tick) >= tick {
+            self.last_tick = now;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// How long to wait for input: until the next tick, or sooner if a
+    /// pending redraw is only held back by the frame cap
+    pub fn poll_timeout(&self, now: Instant, tick: Duration) -> Duration {
+        let until_tick = tick.saturating_sub(now.duration_since(self.last_tick));
+        if self.dirty {
+            until_tick.min(self.until_next_frame(now))
+        } else {
+            until_tick
+        }
+    }
+
+    fn until_next_frame(&self, now: Instant) -> Duration {
+        match self.last_frame {
+            Some(last) => self.frame_interval.saturating_sub(now.duration_since(last)),
+            None => Duration::ZERO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICK: Duration = Duration::from_secs(2);
+
+    #[test]
+    fn test_draws_only_when_dirty() {
+        let mut scheduler = RenderScheduler::new(None);
+        let now = Instant::now();
+        assert!(scheduler.should_draw(now));
+
+        scheduler.frame_drawn(now);
+        assert!(!scheduler.should_draw(now));
+
+        scheduler.mark_dirty();
+        assert!(scheduler.should_draw(now));
+    }
+
+    #[test]
+    fn test_frame_cap() {
+        let mut scheduler = RenderScheduler::new(Some(10));
+        let start = Instant::now();
+        scheduler.frame_drawn(start);
+        scheduler.mark_dirty();
+
+        // Held back until 100ms after the last frame
+        let soon = start + Duration::from_millis(40);
+        assert!(!scheduler.should_draw(soon));
+        assert_eq!(
+            scheduler.poll_timeout(soon, TICK),
+            Duration::from_millis(60)
+        );
+        assert!(scheduler.should_draw(start + Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn test_tick_due() {
+        let mut scheduler = RenderScheduler::new(Some(0));
+        let start = Instant::now();
+        assert!(!scheduler.tick_due(start, TICK));
+        assert!(scheduler.tick_due(start + TICK, TICK));
+        // Restarted from the last tick
+        assert!(!scheduler.tick_due(start + TICK + Duration::from_millis(10), TICK));
+
+        // Idle and clean: sleep until the next tick
+        scheduler.frame_drawn(start + TICK);
+        let timeout = scheduler.poll_timeout(start + TICK + Duration::from_secs(1), TICK);
+        assert_eq!(timeout, Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,392 @@
+use anyhow::{Context, Result};
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use ratatui::{backend::Backend, buffer::Buffer, layout::Rect, Terminal};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::app::App;
+use crate::ui::UI;
+
+/// A single scripted action
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Inject an event (key press, click, scroll)
+    Event(Event),
+    /// Type text as individual key presses
+    Type(String),
+    /// Pause, so recordings can be followed (and input-buffering guards expire)
+    Wait(Duration),
+    /// Fail unless the condition holds
+    Expect(Expectation),
+    /// Write the current screen text to a file
+    Dump(PathBuf),
+}
+
+/// Conditions a script can check against the app and the rendered screen
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expectation {
+    /// The current mode, as shown by `{:?}` (e.g. `Normal`, `Confirm(Exit)`)
+    Mode(String),
+    /// The status bar message contains the text
+    Status(String),
+    /// The rendered screen contains the text
+    Screen(String),
+    /// Number of entries in the USER panel
+    UserCount(usize),
+    /// Number of entries in the MACHINE panel
+    MachineCount(usize),
+}
+
+/// An action with the script line it came from, for error messages
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub line: usize,
+    pub action: Action,
+}
+
+/// Parse a script: one action per line, blank lines and `#` comments are skipped
+///
+/// ```text
+/// key Down            # key names: Enter, Esc, Tab, F1-F12, Ctrl+A, Shift+Tab, ...
+/// type C:\Tools
+/// click 10 4          # column row
+/// scroll down 10 4
+/// wait 200            # milliseconds
+/// expect mode Normal
+/// expect status Added
+/// expect screen C:\Tools
+/// expect user 3
+/// dump screen.txt
+/// ```
+pub fn parse_script(content: &str) -> Result<Vec<Step>> {
+    let mut steps = Vec::new();
+    for (index, raw) in content.lines().enumerate() {
+        let line = index + 1;
+        let text = raw.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let action = parse_action(text).with_context(|| format!("Script line {}", line))?;
+        steps.push(Step { line, action });
+    }
+    Ok(steps)
+}
+
+fn parse_action(text: &str) -> Result<Action> {
+    let (command, rest) = text.split_once(' ').unwrap_or((text, ""));
+    // Only `type` keeps its argument verbatim (paths may contain '#')
+    let args = match command {
+        "type" => rest,
+        _ => rest.split(" #").next().unwrap_or("").trim(),
+    };
+
+    match command {
+        "key" => Ok(Action::Event(Event::Key(parse_key(args)?))),
+        "type" => Ok(Action::Type(args.to_string())),
+        "click" => {
+            let (column, row) = parse_position(args)?;
+            Ok(Action::Event(mouse_event(
+                MouseEventKind::Down(MouseButton::Left),
+                column,
+                row,
+            )))
+        }
+        "scroll" => {
+            let (direction, position) = args.split_once(' ').unwrap_or((args, ""));
+            let kind = match direction {
+                "up" => MouseEventKind::ScrollUp,
+                "down" => MouseEventKind::ScrollDown,
+                _ => anyhow::bail!("Expected 'scroll up|down <column> <row>'"),
+            };
+            let (column, row) = parse_position(position)?;
+            Ok(Action::Event(mouse_event(kind, column, row)))
+        }
+        "wait" => {
+            let ms: u64 = args
+                .parse()
+                .with_context(|| format!("Invalid wait time '{}', expected milliseconds", args))?;
+            Ok(Action::Wait(Duration::from_millis(ms)))
+        }
+        "expect" => parse_expectation(args).map(Action::Expect),
+        "dump" if !args.is_empty() => Ok(Action::Dump(PathBuf::from(args))),
+        "dump" => anyhow::bail!("Expected 'dump <file>'"),
+        _ => anyhow::bail!("Unknown action '{}'", command),
+    }
+}
+
+fn parse_expectation(args: &str) -> Result<Expectation> {
+    let (what, value) = args.split_once(' ').unwrap_or((args, ""));
+    let value = value.trim();
+    let count = || {
+        value
+            .parse::<usize>()
+            .with_context(|| format!("Invalid count '{}'", value))
+    };
+
+    match what {
+        "mode" => Ok(Expectation::Mode(value.to_string())),
+        "status" => Ok(Expectation::Status(value.to_string())),
+        "screen" => Ok(Expectation::Screen(value.to_string())),
+        "user" => Ok(Expectation::UserCount(count()?)),
+        "machine" => Ok(Expectation::MachineCount(count()?)),
+        _ => anyhow::bail!(
+            "Unknown expectation '{}' (mode, status, screen, user, machine)",
+            what
+        ),
+    }
+}
+
+/// Parse a key like `Enter`, `a`, `F5`, `Ctrl+S` or `Ctrl+Shift+A`
+pub fn parse_key(text: &str) -> Result<KeyEvent> {
+    if text.is_empty() {
+        anyhow::bail!("Expected 'key <name>'");
+    }
+
+    let mut modifiers = KeyModifiers::NONE;
+    let mut name = text;
+    // A lone "+" is the plus key, not a separator
+    while let Some((modifier, rest)) = name.split_once('+').filter(|(_, rest)| !rest.is_empty()) {
+        modifiers |= match modifier.to_lowercase().as_str() {
+            "ctrl" | "control" => KeyModifiers::CONTROL,
+            "shift" => KeyModifiers::SHIFT,
+            "alt" => KeyModifiers::ALT,
+            _ => anyhow::bail!("Unknown modifier '{}'", modifier),
+        };
+        name = rest;
+    }
+
+    let code = match name.to_lowercase().as_str() {
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" | "ins" => KeyCode::Insert,
+        "space" => KeyCode::Char(' '),
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" | "pgup" => KeyCode::PageUp,
+        "pagedown" | "pgdn" => KeyCode::PageDown,
+        lower => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => {
+                    // Shifted letters arrive as uppercase characters
+                    if modifiers.contains(KeyModifiers::SHIFT) {
+                        KeyCode::Char(c.to_ascii_uppercase())
+                    } else {
+                        KeyCode::Char(c)
+                    }
+                }
+                _ => match lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => anyhow::bail!("Unknown key '{}'", name),
+                },
+            }
+        }
+    };
+
+    Ok(KeyEvent::new(code, modifiers))
+}
+
+fn parse_position(text: &str) -> Result<(u16, u16)> {
+    let mut parts = text.split_whitespace().map(|p| p.parse::<u16>());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(column)), Some(Ok(row)), None) => Ok((column, row)),
+        _ => anyhow::bail!("Expected '<column> <row>', got '{}'", text),
+    }
+}
+
+fn mouse_event(kind: MouseEventKind, column: u16, row: u16) -> Event {
+    Event::Mouse(MouseEvent {
+        kind,
+        column,
+        row,
+        modifiers: KeyModifiers::NONE,
+    })
+}
+
+/// Text content of a rendered screen, one line per row with trailing spaces trimmed
+pub fn screen_text(buffer: &Buffer) -> String {
+    let width = buffer.area.width as usize;
+    buffer
+        .content
+        .chunks(width)
+        .map(|row| {
+            let line: String = row.iter().map(|cell| cell.symbol()).collect();
+            line.trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        + "\n"
+}
+
+/// Read and parse a script file
+pub fn load_script(path: &Path) -> Result<Vec<Step>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read script {}", path.display()))?;
+    parse_script(&content)
+}
+
+/// Run a script against the app, rendering after every action
+/// Stops early if the app exits, fails on the first unmet expectation
+pub fn run_script<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    ui: &UI,
+    steps: &[Step],
+) -> Result<()> {
+    let mut screen = screen_text(terminal.draw(|f| ui.render(f, app))?.buffer);
+
+    for step in steps {
+        if app.should_exit {
+            break;
+        }
+
+        let size = terminal.size()?;
+        let area = Rect::new(0, 0, size.width, size.height);
+        app.update_viewport_height(size.height);
+
+        match &step.action {
+            Action::Event(event) => app.handle_event(event.clone(), area)?,
+            Action::Type(text) => {
+                for c in text.chars() {
+                    let key = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+                    app.handle_event(Event::Key(key), area)?;
+                }
+            }
+            Action::Wait(duration) => {
+                std::thread::sleep(*duration);
+                // Work finished meanwhile shows up, such as a remote connection
+                app.on_tick();
+            }
+            Action::Expect(expectation) => check(expectation, app, &screen)
+                .with_context(|| format!("Script line {}", step.line))?,
+            Action::Dump(path) => std::fs::write(path, &screen)
+                .with_context(|| format!("Failed to write screen to {}", path.display()))?,
+        }
+
+        screen = screen_text(terminal.draw(|f| ui.render(f, app))?.buffer);
+    }
+
+    Ok(())
+}
+
+fn check(expectation: &Expectation, app: &App, screen: &str) -> Result<()> {
+    let passed = match expectation {
+        Expectation::Mode(mode) => format!("{:?}", app.mode) == *mode,
+        Expectation::Status(text) => app.status_message.contains(text.as_str()),
+        Expectation::Screen(text) => screen.contains(text.as_str()),
+        Expectation::UserCount(count) => app.user_paths.len() == *count,
+        Expectation::MachineCount(count) => app.machine_paths.len() == *count,
+    };
+
+    if passed {
+        return Ok(());
+    }
+    let actual = match expectation {
+        Expectation::Mode(_) => format!("mode is {:?}", app.mode),
+        Expectation::Status(_) => format!("status is '{}'", app.status_message),
+        Expectation::Screen(_) => format!("screen is\n{}", screen),
+        Expectation::UserCount(_) => format!("USER has {} entries", app.user_paths.len()),
+        Expectation::MachineCount(_) => {
+            format!("MACHINE has {} entries", app.machine_paths.len())
+        }
+    };
+    anyhow::bail!("Expected {:?}, but {}", expectation, actual)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::Mode;
+    use crate::filesystem::MemoryFileSystem;
+    use ratatui::backend::TestBackend;
+
+    fn test_app() -> App {
+        App::for_test(
+            vec![r"C:\Dev\Python".to_string()],
+            vec![r"C:\Tools".to_string(), r"C:\Missing".to_string()],
+            Box::new(
+                MemoryFileSystem::new()
+                    .with_dir(r"C:\Dev\Python")
+                    .with_dir(r"C:\Tools"),
+            ),
+        )
+    }
+
+    fn run(script: &str, app: &mut App) -> Result<()> {
+        let mut terminal = Terminal::new(TestBackend::new(120, 36)).unwrap();
+        run_script(&mut terminal, app, &UI::new(), &parse_script(script)?)
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key("Enter").unwrap().code, KeyCode::Enter);
+        assert_eq!(parse_key("a").unwrap().code, KeyCode::Char('a'));
+        assert_eq!(parse_key("F10").unwrap().code, KeyCode::F(10));
+        assert_eq!(parse_key("+").unwrap().code, KeyCode::Char('+'));
+
+        let key = parse_key("Ctrl+Shift+a").unwrap();
+        assert_eq!(key.code, KeyCode::Char('A'));
+        assert_eq!(key.modifiers, KeyModifiers::CONTROL | KeyModifiers::SHIFT);
+        assert_eq!(parse_key("Shift+Tab").unwrap().code, KeyCode::BackTab);
+
+        assert!(parse_key("F13").is_err());
+        assert!(parse_key("Hyper+x").is_err());
+        assert!(parse_key("").is_err());
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let steps = parse_script(
+            "# comment\n\nkey Down  # move\ntype C:\\A #1\nclick 3 4\nwait 10\nexpect user 2\n",
+        )
+        .unwrap();
+        assert_eq!(steps.len(), 5);
+        assert_eq!(steps[0].line, 3);
+        assert_eq!(steps[1].action, Action::Type(r"C:\A #1".to_string()));
+        assert_eq!(steps[4].action, Action::Expect(Expectation::UserCount(2)));
+
+        let err = parse_script("key Down\nfly away\n").unwrap_err();
+        assert!(format!("{:#}", err).contains("Script line 2"));
+        assert!(parse_script("click 3").is_err());
+    }
+
+    #[test]
+    fn test_run_script_deletes_marked_path() {
+        let mut app = test_app();
+        run(
+            "key Down\nkey F2\nexpect screen [X]\nkey F3\nexpect mode Confirm(DeleteSelected)\nkey y\nexpect user 1\nexpect screen C:\\Tools\n",
+            &mut app,
+        )
+        .unwrap();
+        assert_eq!(app.user_paths, vec![r"C:\Tools".to_string()]);
+        assert!(app.has_changes);
+    }
+
+    #[test]
+    fn test_run_script_failed_expectation() {
+        let mut app = test_app();
+        let err = run("key Down\nexpect user 5\n", &mut app).unwrap_err();
+        let message = format!("{:#}", err);
+        assert!(message.contains("Script line 2"));
+        assert!(message.contains("USER has 2 entries"));
+    }
+
+    #[test]
+    fn test_run_script_stops_on_exit() {
+        let mut app = test_app();
+        run("key Ctrl+c\nexpect user 99\n", &mut app).unwrap();
+        assert!(app.should_exit);
+        assert_eq!(app.mode, Mode::Normal);
+    }
+}
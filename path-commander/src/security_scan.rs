@@ -0,0 +1,390 @@
+use anyhow::Result;
+use std::path::Path;
+
+use crate::path_analyzer::expand_environment_variables;
+use crate::registry::PathScope;
+
+/// Extensions of files that can carry an Authenticode signature
+const SIGNABLE_EXTENSIONS: [&str; 3] = ["exe", "com", "dll"];
+
+/// Reads directory permissions and executable signatures from the system
+pub trait SecurityProbe {
+    /// Broad groups (e.g. "Everyone") that can create files in the directory
+    fn writable_by(&self, dir: &Path) -> Result<Vec<String>>;
+
+    /// Whether a file has a valid signature, None where signatures are not checked
+    fn is_signed(&self, file: &Path) -> Option<bool>;
+
+    /// Signatures are only checked on Windows, elsewhere only permissions are reported
+    fn checks_signatures(&self) -> bool;
+}
+
+/// A PATH directory any local user can plant programs in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub scope: PathScope,
+    pub index: usize,
+    pub path: String,
+    pub writable_by: Vec<String>,
+    /// Executables in the directory without a valid signature
+    pub unsigned: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanReport {
+    /// Directories that exist and were checked
+    pub scanned: usize,
+    pub findings: Vec<Finding>,
+    /// Directories whose permissions could not be read
+    pub errors: Vec<String>,
+    pub signatures_checked: bool,
+}
+
+/// Check every directory in PATH, entries are (scope, index, path as stored)
+pub fn scan(entries: &[(PathScope, usize, String)], probe: &dyn SecurityProbe) -> ScanReport {
+    let mut report = ScanReport {
+        signatures_checked: probe.checks_signatures(),
+        ..Default::default()
+    };
+
+    for (scope, index, path) in entries {
+        let expanded = expand_environment_variables(path.trim().trim_matches('"'));
+        let dir = Path::new(&expanded);
+        // Alias reparse points are neither signed files nor a place users can write to
+        if !dir.is_dir() || crate::shims::is_alias_dir(&expanded) {
+            continue;
+        }
+        report.scanned += 1;
+
+        let writable_by = match probe.writable_by(dir) {
+            Ok(groups) => groups,
+            Err(e) => {
+                report.errors.push(format!("{}: {:#}", path, e));
+                continue;
+            }
+        };
+        if writable_by.is_empty() {
+            continue;
+        }
+
+        // Anything in a writable directory could have been planted, so list what is unsigned
+        report.findings.push(Finding {
+            scope: *scope,
+            index: *index,
+            path: path.clone(),
+            writable_by,
+            unsigned: unsigned_executables(dir, probe),
+        });
+    }
+
+    report
+}
+
+/// Names of signable files in the directory that fail the signature check
+fn unsigned_executables(dir: &Path, probe: &dyn SecurityProbe) -> Vec<String> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut unsigned: Vec<String> = read_dir
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|file| file.is_file() && is_signable(file))
+        .filter(|file| probe.is_signed(file) == Some(false))
+        .filter_map(|file| file.file_name().map(|n| n.to_string_lossy().to_string()))
+        .collect();
+    unsigned.sort_by_key(|name| name.to_lowercase());
+    unsigned
+}
+
+fn is_signable(file: &Path) -> bool {
+    file.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| SIGNABLE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Probe backed by the operating system
+pub struct SystemProbe;
+
+#[cfg(windows)]
+impl SecurityProbe for SystemProbe {
+    fn writable_by(&self, dir: &Path) -> Result<Vec<String>> {
+        windows_acl::writable_by(dir)
+    }
+
+    fn is_signed(&self, file: &Path) -> Option<bool> {
+        Some(windows_acl::is_signed(file))
+    }
+
+    fn checks_signatures(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(not(windows))]
+impl SecurityProbe for SystemProbe {
+    fn writable_by(&self, dir: &Path) -> Result<Vec<String>> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(dir)?.permissions().mode();
+        Ok(if mode & 0o002 != 0 {
+            vec!["Everyone".to_string()]
+        } else {
+            Vec::new()
+        })
+    }
+
+    fn is_signed(&self, _file: &Path) -> Option<bool> {
+        None
+    }
+
+    fn checks_signatures(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(windows)]
+mod windows_acl {
+    use anyhow::{anyhow, Result};
+    use std::path::Path;
+    use windows::core::{PCWSTR, PWSTR};
+    use windows::Win32::Foundation::{LocalFree, ERROR_SUCCESS, HANDLE, HLOCAL, HWND};
+    use windows::Win32::Security::Authorization::{
+        ConvertSidToStringSidW, GetNamedSecurityInfoW, SE_FILE_OBJECT,
+    };
+    use windows::Win32::Security::WinTrust::{
+        WinVerifyTrust, WINTRUST_ACTION_GENERIC_VERIFY_V2, WINTRUST_DATA, WINTRUST_FILE_INFO,
+        WTD_CHOICE_FILE, WTD_REVOKE_NONE, WTD_STATEACTION_CLOSE, WTD_STATEACTION_VERIFY,
+        WTD_UI_NONE,
+    };
+    use windows::Win32::Security::{
+        AclSizeInformation, GetAce, GetAclInformation, ACCESS_ALLOWED_ACE, ACL,
+        ACL_SIZE_INFORMATION, DACL_SECURITY_INFORMATION, INHERIT_ONLY_ACE, PSECURITY_DESCRIPTOR,
+        PSID,
+    };
+
+    use crate::registry::to_wide_string;
+
+    const ACCESS_ALLOWED_ACE_TYPE: u8 = 0;
+
+    /// Rights that let someone drop a file into a directory or take it over
+    const FILE_ADD_FILE: u32 = 0x0002;
+    const WRITE_DAC: u32 = 0x0004_0000;
+    const WRITE_OWNER: u32 = 0x0008_0000;
+    const GENERIC_ALL: u32 = 0x1000_0000;
+    const GENERIC_WRITE: u32 = 0x4000_0000;
+    const WRITE_RIGHTS: u32 = FILE_ADD_FILE | WRITE_DAC | WRITE_OWNER | GENERIC_ALL | GENERIC_WRITE;
+
+    /// Well-known SIDs that include every local user
+    const BROAD_GROUPS: [(&str, &str); 4] = [
+        ("S-1-1-0", "Everyone"),
+        ("S-1-5-4", "INTERACTIVE"),
+        ("S-1-5-11", "Authenticated Users"),
+        ("S-1-5-32-545", "Users"),
+    ];
+
+    /// Broad groups granted write access by the directory's DACL
+    /// Deny entries are not weighed, they are rare on directories and only narrow the finding
+    pub fn writable_by(dir: &Path) -> Result<Vec<String>> {
+        let wide = to_wide_string(&dir.to_string_lossy());
+        let mut dacl: *mut ACL = std::ptr::null_mut();
+        let mut descriptor = PSECURITY_DESCRIPTOR::default();
+
+        unsafe {
+            let result = GetNamedSecurityInfoW(
+                PCWSTR(wide.as_ptr()),
+                SE_FILE_OBJECT,
+                DACL_SECURITY_INFORMATION,
+                None,
+                None,
+                Some(&mut dacl),
+                None,
+                &mut descriptor,
+            );
+            if result != ERROR_SUCCESS {
+                return Err(anyhow!("Failed to read permissions: {:?}", result));
+            }
+
+            // A missing DACL grants everyone full access
+            let groups = if dacl.is_null() {
+                vec!["Everyone".to_string()]
+            } else {
+                broad_writers(dacl)
+            };
+
+            let _ = LocalFree(HLOCAL(descriptor.0));
+            Ok(groups)
+        }
+    }
+
+    unsafe fn broad_writers(dacl: *const ACL) -> Vec<String> {
+        let mut size = ACL_SIZE_INFORMATION::default();
+        if GetAclInformation(
+            dacl,
+            &mut size as *mut _ as *mut core::ffi::c_void,
+            std::mem::size_of::<ACL_SIZE_INFORMATION>() as u32,
+            AclSizeInformation,
+        )
+        .is_err()
+        {
+            return Vec::new();
+        }
+
+        let mut groups = Vec::new();
+        for i in 0..size.AceCount {
+            let mut ace: *mut core::ffi::c_void = std::ptr::null_mut();
+            if GetAce(dacl, i, &mut ace).is_err() {
+                continue;
+            }
+            let ace = &*(ace as *const ACCESS_ALLOWED_ACE);
+            // Inherit-only entries apply to children, not to the directory itself
+            if ace.Header.AceType != ACCESS_ALLOWED_ACE_TYPE
+                || u32::from(ace.Header.AceFlags) & INHERIT_ONLY_ACE.0 != 0
+                || ace.Mask & WRITE_RIGHTS == 0
+            {
+                continue;
+            }
+
+            let sid = PSID(&ace.SidStart as *const u32 as *mut core::ffi::c_void);
+            let Some(sid) = sid_string(sid) else {
+                continue;
+            };
+            if let Some((_, name)) = BROAD_GROUPS.iter().find(|(s, _)| *s == sid) {
+                if !groups.iter().any(|g| g == name) {
+                    groups.push(name.to_string());
+                }
+            }
+        }
+        groups
+    }
+
+    unsafe fn sid_string(sid: PSID) -> Option<String> {
+        let mut string = PWSTR::null();
+        ConvertSidToStringSidW(sid, &mut string).ok()?;
+        let result = string.to_string().ok();
+        let _ = LocalFree(HLOCAL(string.0 as *mut core::ffi::c_void));
+        result
+    }
+
+    /// Authenticode check of the file's embedded signature
+    /// Files signed only through a system catalog count as unsigned here
+    pub fn is_signed(file: &Path) -> bool {
+        let wide = to_wide_string(&file.to_string_lossy());
+        let mut file_info = WINTRUST_FILE_INFO {
+            cbStruct: std::mem::size_of::<WINTRUST_FILE_INFO>() as u32,
+            pcwszFilePath: PCWSTR(wide.as_ptr()),
+            hFile: HANDLE::default(),
+            pgKnownSubject: std::ptr::null_mut(),
+        };
+        let mut data = WINTRUST_DATA {
+            cbStruct: std::mem::size_of::<WINTRUST_DATA>() as u32,
+            dwUIChoice: WTD_UI_NONE,
+            // Revocation needs the network, which would stall the scan
+            fdwRevocationChecks: WTD_REVOKE_NONE,
+            dwUnionChoice: WTD_CHOICE_FILE,
+            dwStateAction: WTD_STATEACTION_VERIFY,
+            ..Default::default()
+        };
+        data.Anonymous.pFile = &mut file_info;
+
+        let mut action = WINTRUST_ACTION_GENERIC_VERIFY_V2;
+        unsafe {
+            let status = WinVerifyTrust(
+                HWND::default(),
+                &mut action,
+                &mut data as *mut _ as *mut core::ffi::c_void,
+            );
+            data.dwStateAction = WTD_STATEACTION_CLOSE;
+            let _ = WinVerifyTrust(
+                HWND::default(),
+                &mut action,
+                &mut data as *mut _ as *mut core::ffi::c_void,
+            );
+            status == 0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Probe that treats directories ending in "open" as world-writable
+    /// and files starting with "signed" as signed
+    struct MockProbe;
+
+    impl SecurityProbe for MockProbe {
+        fn writable_by(&self, dir: &Path) -> Result<Vec<String>> {
+            let name = dir.file_name().unwrap().to_string_lossy();
+            if name == "broken" {
+                return Err(anyhow::anyhow!("access denied"));
+            }
+            Ok(if name.ends_with("open") {
+                vec!["Everyone".to_string()]
+            } else {
+                Vec::new()
+            })
+        }
+
+        fn is_signed(&self, file: &Path) -> Option<bool> {
+            Some(
+                file.file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .starts_with("signed"),
+            )
+        }
+
+        fn checks_signatures(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_scan_flags_writable_directories() {
+        let root = tempfile::tempdir().unwrap();
+        let open = root.path().join("open");
+        let closed = root.path().join("closed");
+        let broken = root.path().join("broken");
+        for dir in [&open, &closed, &broken] {
+            fs::create_dir(dir).unwrap();
+        }
+        for file in [
+            "tool.EXE",
+            "signed.exe",
+            "helper.dll",
+            "run.bat",
+            "readme.txt",
+        ] {
+            fs::write(open.join(file), "").unwrap();
+        }
+        fs::write(closed.join("tool.exe"), "").unwrap();
+
+        let as_entry = |scope, index, dir: &Path| (scope, index, dir.display().to_string());
+        let entries = vec![
+            as_entry(PathScope::Machine, 0, &closed),
+            as_entry(PathScope::User, 0, &open),
+            as_entry(PathScope::User, 1, &broken),
+            as_entry(PathScope::User, 2, &root.path().join("missing")),
+        ];
+
+        let report = scan(&entries, &MockProbe);
+        assert_eq!(report.scanned, 3);
+        assert!(report.signatures_checked);
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].ends_with("access denied"));
+
+        // Only signable files are checked, scripts and data files are not
+        assert_eq!(
+            report.findings,
+            vec![Finding {
+                scope: PathScope::User,
+                index: 0,
+                path: open.display().to_string(),
+                writable_by: vec!["Everyone".to_string()],
+                unsigned: vec!["helper.dll".to_string(), "tool.EXE".to_string()],
+            }]
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use anyhow::Result;
+
+use crate::path_analyzer::{comparable, expand_environment_variables};
+
+/// Registry key every installed service has a subkey under
+#[cfg(windows)]
+const SERVICES_KEY: &str = "SYSTEM\\CurrentControlSet\\Services";
+
+/// SERVICE_WIN32_OWN_PROCESS | SERVICE_WIN32_SHARE_PROCESS, drivers have neither bit
+#[cfg(windows)]
+const WIN32_SERVICE_TYPES: u32 = 0x30;
+
+/// An installed Windows service and the program it runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Service {
+    pub name: String,
+    pub display_name: String,
+    /// Expanded path of the service binary, without arguments
+    pub executable: String,
+}
+
+/// A service whose binary lives in a directory that was just removed from PATH
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceAtRisk {
+    pub service: Service,
+    /// The removed PATH entry, as it was stored
+    pub entry: String,
+}
+
+/// Pull the program out of a service ImagePath, which may be quoted and carry arguments
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn executable_of(image_path: &str) -> String {
+    let image = image_path.trim();
+    let program = if let Some(rest) = image.strip_prefix('"') {
+        rest.split('"').next().unwrap_or(rest)
+    } else if let Some(end) = image.to_ascii_lowercase().find(".exe") {
+        // Unquoted paths with spaces are still common, the service manager
+        // resolves them up to the first .exe
+        &image[..end + 4]
+    } else {
+        image.split_whitespace().next().unwrap_or(image)
+    };
+
+    let program = program.strip_prefix("\\??\\").unwrap_or(program);
+    let program = match program.get(..12) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("\\SystemRoot\\") => {
+            format!("%SystemRoot%\\{}", &program[12..])
+        }
+        _ => program.to_string(),
+    };
+    expand_environment_variables(&program)
+}
+
+/// Services whose binary sits in, or below, one of the removed PATH entries
+pub fn at_risk(services: &[Service], removed: &[String]) -> Vec<ServiceAtRisk> {
+    let removed: Vec<(&String, String)> = removed
+        .iter()
+        .map(|entry| (entry, comparable(&expand_environment_variables(entry))))
+        .filter(|(_, dir)| !dir.is_empty())
+        .collect();
+
+    services
+        .iter()
+        .filter_map(|service| {
+            let executable = comparable(&service.executable);
+            let dir = executable.rsplit_once('\\')?.0;
+            removed
+                .iter()
+                .find(|(_, removed_dir)| {
+                    dir == removed_dir
+                        || dir
+                            .strip_prefix(removed_dir.as_str())
+                            .is_some_and(|rest| rest.starts_with('\\'))
+                })
+                .map(|(entry, _)| ServiceAtRisk {
+                    service: service.clone(),
+                    entry: (*entry).clone(),
+                })
+        })
+        .collect()
+}
+
+/// Installed Win32 services, read from the service database in the registry
+#[cfg(windows)]
+pub fn installed_services() -> Result<Vec<Service>> {
+    use crate::registry::{dword_value, string_value, subkey_names};
+    use windows::Win32::System::Registry::HKEY_LOCAL_MACHINE;
+
+    let mut services = Vec::new();
+    for name in subkey_names(HKEY_LOCAL_MACHINE, SERVICES_KEY)? {
+        let key = format!("{}\\{}", SERVICES_KEY, name);
+        let is_win32 = dword_value(HKEY_LOCAL_MACHINE, &key, "Type")
+            .is_some_and(|kind| kind & WIN32_SERVICE_TYPES != 0);
+        let Some(image_path) = string_value(HKEY_LOCAL_MACHINE, &key, "ImagePath") else {
+            continue;
+        };
+        if !is_win32 {
+            continue;
+        }
+
+        // Display names are often resource references like "@%SystemRoot%\x.dll,-100"
+        let display_name = string_value(HKEY_LOCAL_MACHINE, &key, "DisplayName")
+            .filter(|display| !display.starts_with('@'))
+            .unwrap_or_else(|| name.clone());
+        services.push(Service {
+            name,
+            display_name,
+            executable: executable_of(&image_path),
+        });
+    }
+    Ok(services)
+}
+
+/// There are no Windows services to check elsewhere
+#[cfg(not(windows))]
+pub fn installed_services() -> Result<Vec<Service>> {
+    Ok(Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(name: &str, executable: &str) -> Service {
+        Service {
+            name: name.to_string(),
+            display_name: format!("{} Service", name),
+            executable: executable.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_executable_of_image_paths() {
+        assert_eq!(
+            executable_of("\"C:\\Program Files\\Acme\\agent.exe\" --service"),
+            "C:\\Program Files\\Acme\\agent.exe"
+        );
+        assert_eq!(
+            executable_of("C:\\Program Files\\Acme\\agent.exe -k netsvcs"),
+            "C:\\Program Files\\Acme\\agent.exe"
+        );
+        assert_eq!(
+            executable_of("\\??\\C:\\Tools\\watch.EXE"),
+            "C:\\Tools\\watch.EXE"
+        );
+        assert_eq!(
+            executable_of("  C:\\Tools\\runner /background"),
+            "C:\\Tools\\runner"
+        );
+    }
+
+    #[test]
+    fn test_at_risk_matches_removed_directories() {
+        let services = vec![
+            service("AcmeAgent", "C:\\Program Files\\Acme\\bin\\agent.exe"),
+            service(
+                "AcmeHelper",
+                "C:\\Program Files\\Acme\\bin\\x64\\helper.exe",
+            ),
+            service("AcmeTools", "C:\\Program Files\\AcmeTools\\tool.exe"),
+            service("Other", "D:\\Other\\other.exe"),
+        ];
+        let removed = vec!["c:\\program files\\acme\\bin\\".to_string()];
+
+        let risky = at_risk(&services, &removed);
+        let names: Vec<&str> = risky.iter().map(|r| r.service.name.as_str()).collect();
+        // Same directory or below it, but not a sibling that shares a prefix
+        assert_eq!(names, vec!["AcmeAgent", "AcmeHelper"]);
+        assert_eq!(risky[0].entry, "c:\\program files\\acme\\bin\\");
+
+        assert!(at_risk(&services, &[]).is_empty());
+    }
+}
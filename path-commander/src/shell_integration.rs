@@ -0,0 +1,248 @@
+use anyhow::Result;
+
+use crate::registry::PathScope;
+
+/// Name of the verb's keys, shared by every place it is registered
+#[cfg_attr(not(windows), allow(dead_code))]
+const VERB: &str = "PathCommander.AddToPath";
+
+/// Text of the context menu item
+pub const MENU_TEXT: &str = "Add to PATH with Path Commander";
+
+/// Keys under Software\Classes the verb is registered in: right-clicking a folder, and
+/// right-clicking the background of an open folder
+#[cfg_attr(not(windows), allow(dead_code))]
+const SHELL_KEYS: [&str; 2] = [r"Directory\shell", r"Directory\Background\shell"];
+
+/// What `pc context-menu` does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Action {
+    Install,
+    Uninstall,
+}
+
+/// A string value the verb needs, an empty name is the key's default value
+#[cfg_attr(not(windows), allow(dead_code))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistryValue {
+    /// Key under Software\Classes
+    pub key: String,
+    pub name: &'static str,
+    pub data: String,
+}
+
+/// Keys of the verb, relative to Software\Classes, removed as a whole on uninstall
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn verb_keys() -> Vec<String> {
+    SHELL_KEYS
+        .iter()
+        .map(|shell| format!(r"{}\{}", shell, VERB))
+        .collect()
+}
+
+/// Values that make Explorer run `pc --add "<folder>"` from the folder context menu
+/// %V is the folder clicked, or the open folder for the background menu
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn registry_values(exe: &str) -> Vec<RegistryValue> {
+    let mut values = Vec::new();
+    for key in verb_keys() {
+        values.push(RegistryValue {
+            key: key.clone(),
+            name: "",
+            data: MENU_TEXT.to_string(),
+        });
+        values.push(RegistryValue {
+            key: key.clone(),
+            name: "Icon",
+            data: format!("\"{}\",0", exe),
+        });
+        values.push(RegistryValue {
+            key: format!(r"{}\command", key),
+            name: "",
+            data: format!("\"{}\" --add \"%V\"", exe),
+        });
+    }
+    values
+}
+
+/// `pc context-menu`: add or remove the folder context menu item for this user or all users
+pub fn run(action: Action, scope: PathScope) -> Result<()> {
+    let (who, flag) = match scope {
+        PathScope::User => ("you", ""),
+        PathScope::Machine => ("all users", " --scope machine"),
+    };
+    match action {
+        Action::Install => {
+            let exe = std::env::current_exe()?;
+            install(&exe.to_string_lossy(), scope)?;
+            println!(
+                "Added \"{}\" to the folder context menu for {}",
+                MENU_TEXT, who
+            );
+            println!("Run pc context-menu uninstall{} to remove it again", flag);
+        }
+        Action::Uninstall => {
+            if uninstall(scope)? {
+                println!(
+                    "Removed \"{}\" from the folder context menu for {}",
+                    MENU_TEXT, who
+                );
+            } else {
+                println!("The folder context menu item was not installed for {}", who);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn install(exe: &str, scope: PathScope) -> Result<()> {
+    // Start clean so nothing of an older registration is left behind
+    uninstall(scope)?;
+    for value in registry_values(exe) {
+        windows_registry::set_string(scope, &value.key, value.name, &value.data)?;
+    }
+    Ok(())
+}
+
+/// Remove every key of the verb, false if none was there
+#[cfg(windows)]
+fn uninstall(scope: PathScope) -> Result<bool> {
+    let mut removed = false;
+    for key in verb_keys() {
+        removed |= windows_registry::delete_tree(scope, &key)?;
+    }
+    Ok(removed)
+}
+
+#[cfg(not(windows))]
+fn install(_exe: &str, _scope: PathScope) -> Result<()> {
+    anyhow::bail!("the Explorer context menu is only available on Windows")
+}
+
+#[cfg(not(windows))]
+fn uninstall(_scope: PathScope) -> Result<bool> {
+    anyhow::bail!("the Explorer context menu is only available on Windows")
+}
+
+#[cfg(windows)]
+mod windows_registry {
+    use anyhow::{anyhow, Result};
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{ERROR_ACCESS_DENIED, ERROR_FILE_NOT_FOUND, ERROR_SUCCESS};
+    use windows::Win32::System::Registry::{
+        RegCloseKey, RegCreateKeyExW, RegDeleteTreeW, RegSetValueExW, HKEY, HKEY_CURRENT_USER,
+        HKEY_LOCAL_MACHINE, KEY_WRITE, REG_OPTION_NON_VOLATILE, REG_SZ,
+    };
+
+    use crate::registry::{to_wide_string, PathScope};
+
+    /// Software\Classes of the scope: HKCU for this user, HKLM for all users
+    fn classes(scope: PathScope, key: &str) -> (HKEY, Vec<u16>) {
+        let root = match scope {
+            PathScope::User => HKEY_CURRENT_USER,
+            PathScope::Machine => HKEY_LOCAL_MACHINE,
+        };
+        (root, to_wide_string(&format!(r"Software\Classes\{}", key)))
+    }
+
+    fn denied(scope: PathScope) -> anyhow::Error {
+        match scope {
+            PathScope::Machine => anyhow!(
+                "registering for all users needs administrator rights, run pc from an elevated terminal or use --scope user"
+            ),
+            PathScope::User => anyhow!("access to HKEY_CURRENT_USER\\Software\\Classes was denied"),
+        }
+    }
+
+    /// Create the key if needed and set a REG_SZ value, "" for the default value
+    pub fn set_string(scope: PathScope, key: &str, name: &str, data: &str) -> Result<()> {
+        let (root, key_wide) = classes(scope, key);
+        let name_wide = to_wide_string(name);
+        let data_wide = to_wide_string(data);
+        unsafe {
+            let mut hkey = HKEY::default();
+            let result = RegCreateKeyExW(
+                root,
+                PCWSTR(key_wide.as_ptr()),
+                0,
+                PCWSTR::null(),
+                REG_OPTION_NON_VOLATILE,
+                KEY_WRITE,
+                None,
+                &mut hkey,
+                None,
+            );
+            if result == ERROR_ACCESS_DENIED {
+                return Err(denied(scope));
+            }
+            if result != ERROR_SUCCESS {
+                return Err(anyhow!("Failed to create {}: {:?}", key, result));
+            }
+
+            let bytes =
+                std::slice::from_raw_parts(data_wide.as_ptr() as *const u8, data_wide.len() * 2);
+            let name = if name.is_empty() {
+                PCWSTR::null()
+            } else {
+                PCWSTR(name_wide.as_ptr())
+            };
+            let result = RegSetValueExW(hkey, name, 0, REG_SZ, Some(bytes));
+            let _ = RegCloseKey(hkey).ok();
+            if result != ERROR_SUCCESS {
+                return Err(anyhow!("Failed to write {}: {:?}", key, result));
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete a key with everything under it, false if it did not exist
+    pub fn delete_tree(scope: PathScope, key: &str) -> Result<bool> {
+        let (root, key_wide) = classes(scope, key);
+        let result = unsafe { RegDeleteTreeW(root, PCWSTR(key_wide.as_ptr())) };
+        if result == ERROR_SUCCESS {
+            Ok(true)
+        } else if result == ERROR_FILE_NOT_FOUND {
+            Ok(false)
+        } else if result == ERROR_ACCESS_DENIED {
+            Err(denied(scope))
+        } else {
+            Err(anyhow!("Failed to delete {}: {:?}", key, result))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_values() {
+        let values = registry_values(r"C:\Tools\pc.exe");
+        assert_eq!(values.len(), 6);
+        assert_eq!(
+            values[0],
+            RegistryValue {
+                key: r"Directory\shell\PathCommander.AddToPath".to_string(),
+                name: "",
+                data: MENU_TEXT.to_string(),
+            }
+        );
+        assert_eq!(
+            values[2],
+            RegistryValue {
+                key: r"Directory\shell\PathCommander.AddToPath\command".to_string(),
+                name: "",
+                data: r#""C:\Tools\pc.exe" --add "%V""#.to_string(),
+            }
+        );
+        // Every value lives under a key uninstall removes
+        assert!(values.iter().all(|value| verb_keys()
+            .iter()
+            .any(|key| value.key.starts_with(key.as_str()))));
+        assert_eq!(
+            verb_keys()[1],
+            r"Directory\Background\shell\PathCommander.AddToPath"
+        );
+    }
+}
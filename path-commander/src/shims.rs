@@ -0,0 +1,425 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::path_analyzer::{comparable, expand_environment_variables};
+use crate::registry::PathScope;
+
+/// File types shim directories hold their launchers as
+const COMMAND_EXTENSIONS: [&str; 5] = ["exe", "cmd", "bat", "com", "ps1"];
+
+/// Package and version managers that put one directory of launchers on PATH
+/// for every tool or tool version they install
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShimManager {
+    Chocolatey,
+    Scoop,
+    WindowsApps,
+    NvmWindows,
+    PyenvWin,
+    Rustup,
+}
+
+impl ShimManager {
+    pub const ALL: [ShimManager; 6] = [
+        ShimManager::Chocolatey,
+        ShimManager::Scoop,
+        ShimManager::WindowsApps,
+        ShimManager::NvmWindows,
+        ShimManager::PyenvWin,
+        ShimManager::Rustup,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ShimManager::Chocolatey => "Chocolatey shims",
+            ShimManager::Scoop => "Scoop shims",
+            ShimManager::WindowsApps => "App execution aliases",
+            ShimManager::NvmWindows => "nvm-windows active Node.js",
+            ShimManager::PyenvWin => "pyenv-win shims",
+            ShimManager::Rustup => "rustup proxies",
+        }
+    }
+
+    /// Explanation for the details dialog
+    pub fn note(&self) -> &'static str {
+        match self {
+            ShimManager::Chocolatey | ShimManager::Scoop => "Shim directory of a package manager: each command here launches a tool it installed. Directories searched before it with the same commands win over the managed versions.",
+            ShimManager::WindowsApps => "App execution aliases of Store apps and winget installs. Real installs of the same command should be searched before it.",
+            ShimManager::NvmWindows => "Link nvm-windows points at the Node.js version picked with 'nvm use'. A Node.js directory searched before it pins one version, whatever nvm says.",
+            ShimManager::PyenvWin => "Shims of pyenv-win that run the Python version picked with 'pyenv global' or 'pyenv local'. A Python directory searched before it pins one version.",
+            ShimManager::Rustup => "Proxies of rustup that run the toolchain picked with 'rustup default' or rust-toolchain.toml. A Rust directory searched before it pins one toolchain.",
+        }
+    }
+
+    /// Switches between versions of a tool rather than installing tools
+    pub fn is_version_manager(&self) -> bool {
+        matches!(
+            self,
+            ShimManager::NvmWindows | ShimManager::PyenvWin | ShimManager::Rustup
+        )
+    }
+
+    /// Ending every install of the manager shares, compared lowercase
+    /// nvm-windows links any directory the user picked, so it is only known by its variable
+    fn suffix(&self) -> Option<&'static str> {
+        match self {
+            ShimManager::Chocolatey => Some(r"\chocolatey\bin"),
+            ShimManager::Scoop => Some(r"\scoop\shims"),
+            ShimManager::WindowsApps => Some(r"\microsoft\windowsapps"),
+            ShimManager::NvmWindows => None,
+            ShimManager::PyenvWin => Some(r"\pyenv-win\shims"),
+            ShimManager::Rustup => Some(r"\.cargo\bin"),
+        }
+    }
+
+    /// Where the manager's directory is, following its install location variable if set
+    /// None for nvm-windows when it is not installed
+    pub fn default_dir(&self) -> Option<String> {
+        let custom = |var: &str, sub: &str| {
+            std::env::var(var)
+                .ok()
+                .filter(|root| !root.is_empty())
+                .map(|root| {
+                    let root = root.trim_end_matches(['\\', '/']);
+                    if sub.is_empty() {
+                        root.to_string()
+                    } else {
+                        format!(r"{}\{}", root, sub)
+                    }
+                })
+        };
+        let dir = match self {
+            ShimManager::Chocolatey => custom("ChocolateyInstall", "bin")
+                .unwrap_or_else(|| r"%ProgramData%\chocolatey\bin".to_string()),
+            ShimManager::Scoop => {
+                custom("SCOOP", "shims").unwrap_or_else(|| r"%USERPROFILE%\scoop\shims".to_string())
+            }
+            ShimManager::WindowsApps => r"%LOCALAPPDATA%\Microsoft\WindowsApps".to_string(),
+            ShimManager::NvmWindows => return custom("NVM_SYMLINK", ""),
+            ShimManager::PyenvWin => custom("PYENV", "shims")
+                .unwrap_or_else(|| r"%USERPROFILE%\.pyenv\pyenv-win\shims".to_string()),
+            ShimManager::Rustup => custom("CARGO_HOME", "bin")
+                .unwrap_or_else(|| r"%USERPROFILE%\.cargo\bin".to_string()),
+        };
+        Some(dir)
+    }
+
+    /// Scope the manager's installer adds its directory to
+    pub fn scope(&self) -> PathScope {
+        match self {
+            ShimManager::Chocolatey | ShimManager::NvmWindows => PathScope::Machine,
+            ShimManager::Scoop
+            | ShimManager::WindowsApps
+            | ShimManager::PyenvWin
+            | ShimManager::Rustup => PathScope::User,
+        }
+    }
+
+    /// Shims should win over tools installed by hand, aliases should lose to real installs
+    pub fn belongs_in_front(&self) -> bool {
+        !matches!(self, ShimManager::WindowsApps)
+    }
+}
+
+/// The package manager whose shim directory the entry is
+pub fn manager_of(entry: &str) -> Option<ShimManager> {
+    let dir = comparable(&expand_environment_variables(entry));
+    if dir.is_empty() {
+        return None;
+    }
+    ShimManager::ALL.into_iter().find(|manager| {
+        manager.suffix().is_some_and(|suffix| dir.ends_with(suffix))
+            || manager
+                .default_dir()
+                .is_some_and(|default| dir == comparable(&expand_environment_variables(&default)))
+    })
+}
+
+/// Whether the directory is the per-user folder of app execution aliases
+pub fn is_alias_dir(dir: &str) -> bool {
+    ShimManager::WindowsApps
+        .suffix()
+        .is_some_and(|suffix| comparable(dir).ends_with(suffix))
+}
+
+/// Commands a directory provides, lowercase and without extension
+fn commands(dir: &str) -> BTreeSet<String> {
+    let Ok(files) = std::fs::read_dir(expand_environment_variables(dir)) else {
+        return BTreeSet::new();
+    };
+    files
+        .flatten()
+        .filter_map(|file| {
+            let path = file.path();
+            let extension = path.extension()?.to_str()?.to_lowercase();
+            if !COMMAND_EXTENSIONS.contains(&extension.as_str()) {
+                return None;
+            }
+            Some(path.file_stem()?.to_str()?.to_lowercase())
+        })
+        .collect()
+}
+
+/// A directory searched before a shim directory that has some of the same commands
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub scope: PathScope,
+    pub index: usize,
+    pub path: String,
+    pub commands: Vec<String>,
+}
+
+/// What the shim check found for one package manager
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShimFinding {
+    pub manager: ShimManager,
+    /// The entry and where it is, None if the manager is installed but missing from PATH
+    pub entry: Option<(PathScope, usize, String)>,
+    /// Directory of the manager, as looked for on disk
+    pub dir: String,
+    /// Directories searched earlier whose commands win over the shims
+    pub shadowed_by: Vec<Conflict>,
+    /// Directories searched later whose commands the aliases hide, for app execution aliases
+    pub shadows: Vec<Conflict>,
+}
+
+impl ShimFinding {
+    /// The conflicts worth fixing: shims losing to earlier directories, or aliases
+    /// winning over real installs searched after them
+    pub fn problems(&self) -> &[Conflict] {
+        if self.manager.belongs_in_front() {
+            &self.shadowed_by
+        } else {
+            &self.shadows
+        }
+    }
+
+    /// Moving the entry within its scope takes it past a problem conflict
+    /// MACHINE is searched before all of USER, so conflicts across scopes cannot be fixed this way
+    pub fn is_fixable(&self) -> bool {
+        self.entry.as_ref().is_some_and(|(scope, _, _)| {
+            self.problems()
+                .iter()
+                .any(|conflict| conflict.scope == *scope)
+        })
+    }
+}
+
+/// Entries among `others` with commands in common with `shims`
+fn conflicts(others: &[(PathScope, usize, String)], shims: &BTreeSet<String>) -> Vec<Conflict> {
+    others
+        .iter()
+        .filter_map(|(scope, index, path)| {
+            let shared: Vec<String> = commands(path).intersection(shims).cloned().collect();
+            (!shared.is_empty()).then(|| Conflict {
+                scope: *scope,
+                index: *index,
+                path: path.clone(),
+                commands: shared,
+            })
+        })
+        .collect()
+}
+
+/// Check the shim directories of installed package and version managers against PATH,
+/// in search order
+pub fn scan(entries: &[(PathScope, usize, String)]) -> Vec<ShimFinding> {
+    let mut findings = Vec::new();
+    for manager in ShimManager::ALL {
+        let Some(position) = entries
+            .iter()
+            .position(|(_, _, entry)| manager_of(entry) == Some(manager))
+        else {
+            // Only worth mentioning if the manager is installed
+            let dir = manager
+                .default_dir()
+                .map(|dir| expand_environment_variables(&dir))
+                .filter(|dir| Path::new(dir).is_dir());
+            if let Some(dir) = dir {
+                findings.push(ShimFinding {
+                    manager,
+                    entry: None,
+                    dir,
+                    shadowed_by: Vec::new(),
+                    shadows: Vec::new(),
+                });
+            }
+            continue;
+        };
+
+        let (scope, index, entry) = &entries[position];
+        let shims = commands(entry);
+        // Aliases such as the python.exe stub open the Store instead of the real install
+        let shadows = if manager.belongs_in_front() {
+            Vec::new()
+        } else {
+            conflicts(&entries[position + 1..], &shims)
+        };
+        findings.push(ShimFinding {
+            manager,
+            entry: Some((*scope, *index, entry.clone())),
+            dir: expand_environment_variables(entry),
+            shadowed_by: conflicts(&entries[..position], &shims),
+            shadows,
+        });
+    }
+    findings
+}
+
+/// Move each shim entry of `scope` up to just before the first entry of that scope
+/// shadowing it, and each alias entry down to just after the last real install it
+/// hides; the order of everything else is kept
+pub fn restore_order(paths: &[String], scope: PathScope, findings: &[ShimFinding]) -> Vec<String> {
+    let mut order = paths.to_vec();
+    for finding in findings {
+        let Some((shim_scope, _, shim)) = &finding.entry else {
+            continue;
+        };
+        if *shim_scope != scope {
+            continue;
+        }
+        let Some(from) = order.iter().position(|p| p == shim) else {
+            continue;
+        };
+        let positions = finding
+            .problems()
+            .iter()
+            .filter(|conflict| conflict.scope == scope)
+            .filter_map(|conflict| order.iter().position(|p| *p == conflict.path));
+        let to = if finding.manager.belongs_in_front() {
+            positions.min().filter(|to| *to < from)
+        } else {
+            // Once the entry is taken out, inserting at the conflict's index lands after it
+            positions.max().filter(|to| *to > from)
+        };
+        if let Some(to) = to {
+            let entry = order.remove(from);
+            order.insert(to, entry);
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_manager_of_entries() {
+        assert_eq!(
+            manager_of(r"C:\ProgramData\chocolatey\bin\"),
+            Some(ShimManager::Chocolatey)
+        );
+        assert_eq!(
+            manager_of(r"C:\Users\alice\scoop\shims"),
+            Some(ShimManager::Scoop)
+        );
+        assert_eq!(
+            manager_of(r"C:\Users\alice\AppData\Local\Microsoft\WindowsApps"),
+            Some(ShimManager::WindowsApps)
+        );
+        assert_eq!(
+            manager_of(r"C:\Users\alice\.pyenv\pyenv-win\shims"),
+            Some(ShimManager::PyenvWin)
+        );
+        assert_eq!(
+            manager_of(r"C:\Users\alice\.cargo\bin"),
+            Some(ShimManager::Rustup)
+        );
+        assert!(ShimManager::Rustup.is_version_manager());
+        assert_eq!(manager_of(r"C:\Tools\scoop\shims\extra"), None);
+        assert_eq!(manager_of(""), None);
+        assert!(is_alias_dir(r"%LOCALAPPDATA%\Microsoft\WindowsApps\"));
+        assert!(!is_alias_dir(r"C:\Program Files\WindowsApps"));
+    }
+
+    #[test]
+    fn test_scan_finds_shadowed_shims_and_restores_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = dir.path().join("git").join("cmd");
+        let shims = dir.path().join("scoop").join("shims");
+        let other = dir.path().join("other");
+        for (path, files) in [
+            (&git, vec!["git.exe", "readme.txt"]),
+            (&shims, vec!["git.exe", "git.shim", "node.cmd"]),
+            (&other, vec!["make.exe"]),
+        ] {
+            fs::create_dir_all(path).unwrap();
+            for file in files {
+                fs::write(path.join(file), "").unwrap();
+            }
+        }
+        let text = |path: &std::path::PathBuf| path.to_string_lossy().to_string();
+        let entries = vec![
+            (PathScope::User, 0, text(&other)),
+            (PathScope::User, 1, text(&git)),
+            (PathScope::User, 2, text(&shims)),
+        ];
+
+        let findings = scan(&entries);
+        let scoop = findings
+            .iter()
+            .find(|finding| finding.manager == ShimManager::Scoop)
+            .unwrap();
+        assert_eq!(scoop.entry, Some(entries[2].clone()));
+        assert_eq!(scoop.shadowed_by.len(), 1);
+        assert_eq!(scoop.shadowed_by[0].path, text(&git));
+        assert_eq!(scoop.shadowed_by[0].commands, vec!["git"]);
+        assert!(scoop.is_fixable());
+
+        let paths: Vec<String> = entries.iter().map(|(_, _, p)| p.clone()).collect();
+        assert_eq!(
+            restore_order(&paths, PathScope::User, &findings),
+            vec![text(&other), text(&shims), text(&git)]
+        );
+        // Nothing of MACHINE moves
+        assert_eq!(restore_order(&paths, PathScope::Machine, &findings), paths);
+    }
+
+    #[test]
+    fn test_aliases_hiding_real_installs_move_after_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let aliases = dir.path().join("Microsoft").join("WindowsApps");
+        let python = dir.path().join("Python312");
+        let node = dir.path().join("node");
+        for (path, files) in [
+            (
+                &aliases,
+                vec!["python.exe", "python3.exe", "winget.exe", "node.exe"],
+            ),
+            (&python, vec!["python.exe", "pip.exe"]),
+            (&node, vec!["node.exe"]),
+        ] {
+            fs::create_dir_all(path).unwrap();
+            for file in files {
+                fs::write(path.join(file), "").unwrap();
+            }
+        }
+        let text = |path: &std::path::PathBuf| path.to_string_lossy().to_string();
+        let entries = vec![
+            (PathScope::Machine, 0, text(&node)),
+            (PathScope::User, 0, text(&aliases)),
+            (PathScope::User, 1, text(&python)),
+        ];
+
+        let findings = scan(&entries);
+        let alias = findings
+            .iter()
+            .find(|finding| finding.manager == ShimManager::WindowsApps)
+            .unwrap();
+        // The real node comes first, which is how it should be
+        assert_eq!(alias.shadowed_by.len(), 1);
+        assert_eq!(alias.shadowed_by[0].commands, vec!["node"]);
+        assert_eq!(alias.problems().len(), 1);
+        assert_eq!(alias.problems()[0].path, text(&python));
+        assert_eq!(alias.problems()[0].commands, vec!["python"]);
+        assert!(alias.is_fixable());
+
+        let user = vec![text(&aliases), text(&python)];
+        assert_eq!(
+            restore_order(&user, PathScope::User, &findings),
+            vec![text(&python), text(&aliases)]
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::{Duration, Instant};
+
+/// Braille spinner shown in the status bar while a task runs
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Shown instead of the spinner when animations are turned off
+pub const STILL_SPINNER: char = '•';
+
+/// How long each spinner frame is shown
+pub const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Work running on a worker thread so the UI stays responsive
+pub struct BackgroundTask<T> {
+    pub label: String,
+    started: Instant,
+    receiver: Receiver<T>,
+}
+
+impl<T: Send + 'static> BackgroundTask<T> {
+    /// Start `work` on a new thread
+    pub fn spawn(label: &str, work: impl FnOnce() -> T + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            // The receiver is gone if the task was cancelled, nothing left to do then
+            let _ = sender.send(work());
+        });
+
+        Self {
+            label: label.to_string(),
+            started: Instant::now(),
+            receiver,
+        }
+    }
+
+    /// The result, once the worker has finished
+    /// A worker that panicked yields None forever, so callers should also check `is_abandoned`
+    pub fn try_finish(&self) -> Option<T> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Check if the worker died without sending a result
+    pub fn is_abandoned(&self) -> bool {
+        matches!(self.receiver.try_recv(), Err(TryRecvError::Disconnected))
+    }
+
+    /// Current spinner frame, based on how long the task has been running
+    pub fn spinner(&self) -> char {
+        let step = self.started.elapsed().as_millis() / SPINNER_INTERVAL.as_millis();
+        SPINNER_FRAMES[step as usize % SPINNER_FRAMES.len()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wait_for<T: Send + 'static>(task: &BackgroundTask<T>) -> T {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            if let Some(result) = task.try_finish() {
+                return result;
+            }
+            assert!(Instant::now() < deadline, "task did not finish");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_background_task_result() {
+        let task = BackgroundTask::spawn("Adding", || 2 + 2);
+        assert_eq!(task.label, "Adding");
+        assert!(SPINNER_FRAMES.contains(&task.spinner()));
+        assert_eq!(wait_for(&task), 4);
+        // The result is only handed out once
+        assert_eq!(task.try_finish(), None);
+    }
+}
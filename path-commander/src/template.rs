@@ -0,0 +1,207 @@
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::config::parse_ini;
+
+/// Machine-specific values for `${NAME}` placeholders in a shared backup
+///
+/// ```ini
+/// [variables]
+/// TOOLS_ROOT = D:\Tools
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Variables {
+    /// Values by uppercase name, placeholders are matched ignoring case
+    values: HashMap<String, String>,
+}
+
+impl Variables {
+    /// Load a variables file, a missing file defines nothing
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read variables: {}", path.display()))
+            }
+        };
+        Ok(Self::parse(&content))
+    }
+
+    fn parse(content: &str) -> Self {
+        let values = parse_ini(content)
+            .unwrap_or_default()
+            .remove("variables")
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, value)| (name.to_uppercase(), value))
+            .collect();
+        Self { values }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(&name.to_uppercase()).map(|v| v.as_str())
+    }
+
+    #[cfg(test)]
+    pub fn set(&mut self, name: &str, value: &str) {
+        self.values.insert(name.to_uppercase(), value.to_string());
+    }
+}
+
+/// Piece of an entry: literal text or a placeholder name
+enum Part<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+/// Split an entry into text and `${NAME}` placeholders, `$${` is a literal `${`
+fn parts(entry: &str) -> Result<Vec<Part<'_>>> {
+    let mut parts = Vec::new();
+    let mut rest = entry;
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('$') {
+            parts.push(Part::Text(&rest[..start]));
+            parts.push(Part::Text("{"));
+            rest = &rest[start + 2..];
+            continue;
+        }
+        parts.push(Part::Text(&rest[..start]));
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| anyhow!("unterminated placeholder in {}", entry))?;
+        let name = &after[..end];
+        let valid = name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(anyhow!("invalid placeholder ${{{}}} in {}", name, entry));
+        }
+        parts.push(Part::Placeholder(name));
+        rest = &after[end + 1..];
+    }
+    parts.push(Part::Text(rest));
+    Ok(parts)
+}
+
+/// Replace the placeholders of one entry
+/// Fails with the names that have no value on this machine
+pub fn substitute(entry: &str, variables: &Variables) -> Result<String> {
+    let mut resolved = String::new();
+    let mut missing = Vec::new();
+    for part in parts(entry)? {
+        match part {
+            Part::Text(text) => resolved.push_str(text),
+            Part::Placeholder(name) => match variables.get(name) {
+                Some(value) => resolved.push_str(value),
+                None => missing.push(name),
+            },
+        }
+    }
+    if !missing.is_empty() {
+        return Err(anyhow!("unresolved {}", braces(&missing)));
+    }
+    Ok(resolved)
+}
+
+/// Replace the placeholders of every entry, or report everything that could not be resolved
+pub fn resolve_all(entries: &[String], variables: &Variables) -> Result<Vec<String>> {
+    let mut missing: Vec<&str> = Vec::new();
+    for entry in entries {
+        for part in parts(entry)? {
+            if let Part::Placeholder(name) = part {
+                if variables.get(name).is_none()
+                    && !missing.iter().any(|m| m.eq_ignore_ascii_case(name))
+                {
+                    missing.push(name);
+                }
+            }
+        }
+    }
+    if !missing.is_empty() {
+        return Err(anyhow!("unresolved placeholders: {}", braces(&missing)));
+    }
+    entries
+        .iter()
+        .map(|entry| substitute(entry, variables))
+        .collect()
+}
+
+fn braces(names: &[&str]) -> String {
+    names
+        .iter()
+        .map(|name| format!("${{{}}}", name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_variables() {
+        let variables = Variables::parse(
+            "[variables]\ntools_root = D:\\Tools\n; comment\nSDK = C:\\SDK\n\n[other]\nX = 1\n",
+        );
+        assert_eq!(variables.get("TOOLS_ROOT"), Some(r"D:\Tools"));
+        assert_eq!(variables.get("sdk"), Some(r"C:\SDK"));
+        assert_eq!(variables.get("X"), None);
+    }
+
+    #[test]
+    fn test_substitute() {
+        let mut variables = Variables::default();
+        variables.set("TOOLS_ROOT", r"D:\Tools");
+
+        assert_eq!(
+            substitute(r"${TOOLS_ROOT}\bin", &variables).unwrap(),
+            r"D:\Tools\bin"
+        );
+        assert_eq!(
+            substitute(r"${tools_root}\a;${TOOLS_ROOT}\b", &variables).unwrap(),
+            r"D:\Tools\a;D:\Tools\b"
+        );
+        // Plain entries and Windows variables are left alone
+        assert_eq!(
+            substitute(r"%USERPROFILE%\bin", &variables).unwrap(),
+            r"%USERPROFILE%\bin"
+        );
+        assert_eq!(
+            substitute("$${HOME}/bin", &variables).unwrap(),
+            "${HOME}/bin"
+        );
+
+        let err = substitute(r"${SDK}\bin", &variables).unwrap_err();
+        assert_eq!(err.to_string(), "unresolved ${SDK}");
+        assert!(substitute(r"${TOOLS_ROOT\bin", &variables).is_err());
+        assert!(substitute(r"${1X}\bin", &variables).is_err());
+    }
+
+    #[test]
+    fn test_resolve_all_reports_every_missing_name() {
+        let mut variables = Variables::default();
+        variables.set("TOOLS_ROOT", r"D:\Tools");
+        let entries = vec![
+            r"${TOOLS_ROOT}\bin".to_string(),
+            r"${SDK}\bin".to_string(),
+            r"${sdk}\lib".to_string(),
+            r"${GO}\bin".to_string(),
+        ];
+
+        let err = resolve_all(&entries, &variables).unwrap_err();
+        assert_eq!(err.to_string(), "unresolved placeholders: ${SDK}, ${GO}");
+
+        variables.set("SDK", r"C:\SDK");
+        variables.set("GO", r"C:\Go");
+        assert_eq!(
+            resolve_all(&entries, &variables).unwrap(),
+            vec![r"D:\Tools\bin", r"C:\SDK\bin", r"C:\SDK\lib", r"C:\Go\bin"]
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use anyhow::{anyhow, Context, Result};
 use ratatui::style::Color;
+
+use crate::path_analyzer::PathKind;
 use std::collections::HashMap;
 use std::path::Path;
 
@@ -69,6 +71,14 @@ pub struct Theme {
     pub path_duplicate_bg: Color,
     pub path_nonnormalized_fg: Color,
     pub path_nonnormalized_bg: Color,
+    pub path_malformed_fg: Color,
+    pub path_malformed_bg: Color,
+    pub path_offline_fg: Color,
+    pub path_offline_bg: Color,
+    pub path_removable_fg: Color,
+    pub path_removable_bg: Color,
+    pub path_insecure_fg: Color,
+    pub path_insecure_bg: Color,
 
     // UI element colors
     pub scrollbar_fg: Color,
@@ -95,6 +105,13 @@ pub struct Theme {
     pub menu_selected_bg: Color,
     pub menu_hotkey_fg: Color,
     pub menu_hotkey_selected_fg: Color,
+
+    // Entry kind glyphs ([glyphs] section)
+    pub glyph_local: String,
+    pub glyph_unc: String,
+    pub glyph_mapped: String,
+    pub glyph_junction: String,
+    pub glyph_envvar: String,
 }
 
 impl Theme {
@@ -104,7 +121,7 @@ impl Theme {
             .with_context(|| format!("Failed to read INI file: {}", path.display()))?;
 
         // Parse INI file into sections
-        let ini_data = Self::parse_ini(&content)?;
+        let ini_data = crate::config::parse_ini(&content)?;
 
         // Get theme name from metadata or filename
         let name = ini_data
@@ -197,6 +214,21 @@ impl Theme {
         let menu_hotkey_selected_fg =
             get_color_pair("menu", "menuhotsel", Color::White, menu_selected_bg).0;
 
+        // Parse [glyphs] section (Path Commander specific, like MC's [Lines] section)
+        let get_glyph = |key: &str, default: &str| -> String {
+            ini_data
+                .get("glyphs")
+                .and_then(|s| s.get(key))
+                .filter(|v| !v.is_empty())
+                .cloned()
+                .unwrap_or_else(|| default.to_string())
+        };
+        let glyph_local = get_glyph("local", DEFAULT_GLYPH_LOCAL);
+        let glyph_unc = get_glyph("unc", DEFAULT_GLYPH_UNC);
+        let glyph_mapped = get_glyph("mapped", DEFAULT_GLYPH_MAPPED);
+        let glyph_junction = get_glyph("junction", DEFAULT_GLYPH_JUNCTION);
+        let glyph_envvar = get_glyph("envvar", DEFAULT_GLYPH_ENVVAR);
+
         // Parse [pathcommander] section (Path Commander specific)
         // If not present, fall back to MC's [filehighlight] and [error] sections for intelligent defaults
 
@@ -278,6 +310,38 @@ impl Theme {
             panel_normal_bg,
         );
 
+        // Path malformed (empty segment, stray whitespace, quotes): PC-specific, default magenta
+        let (path_malformed_fg, path_malformed_bg) = get_color_pair(
+            "pathcommander",
+            "path_malformed",
+            Color::Magenta,
+            panel_normal_bg,
+        );
+
+        // Path offline (temporarily unreachable drive): PC-specific, default gray
+        let (path_offline_fg, path_offline_bg) = get_color_pair(
+            "pathcommander",
+            "path_offline",
+            Color::Gray,
+            panel_normal_bg,
+        );
+
+        // Path on removable media: PC-specific, default light blue
+        let (path_removable_fg, path_removable_bg) = get_color_pair(
+            "pathcommander",
+            "path_removable",
+            Color::LightBlue,
+            panel_normal_bg,
+        );
+
+        // Path writable by all users ahead of system directories: PC-specific, default light red
+        let (path_insecure_fg, path_insecure_bg) = get_color_pair(
+            "pathcommander",
+            "path_insecure",
+            Color::LightRed,
+            panel_normal_bg,
+        );
+
         let (warning_fg, warning_bg) =
             get_color_pair("pathcommander", "warning", Color::Red, dialog_bg);
         let (info_fg, info_bg) = get_color_pair("pathcommander", "info", dialog_fg, dialog_bg);
@@ -399,6 +463,14 @@ impl Theme {
             path_duplicate_bg,
             path_nonnormalized_fg,
             path_nonnormalized_bg,
+            path_malformed_fg,
+            path_malformed_bg,
+            path_offline_fg,
+            path_offline_bg,
+            path_removable_fg,
+            path_removable_bg,
+            path_insecure_fg,
+            path_insecure_bg,
 
             // UI element colors
             scrollbar_fg,
@@ -425,43 +497,14 @@ impl Theme {
             menu_selected_bg,
             menu_hotkey_fg,
             menu_hotkey_selected_fg,
-        }
-    }
 
-    /// Parse INI file content into sections and key-value pairs
-    fn parse_ini(content: &str) -> Result<HashMap<String, HashMap<String, String>>> {
-        let mut result: HashMap<String, HashMap<String, String>> = HashMap::new();
-        let mut current_section = String::new();
-
-        for line in content.lines() {
-            let line = line.trim();
-
-            // Skip empty lines and comments
-            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
-                continue;
-            }
-
-            // Section header
-            if line.starts_with('[') && line.ends_with(']') {
-                current_section = line[1..line.len() - 1].trim().to_string();
-                result.entry(current_section.clone()).or_default();
-                continue;
-            }
-
-            // Key-value pair
-            if let Some(eq_pos) = line.find('=') {
-                let key = line[..eq_pos].trim().to_string();
-                let value = line[eq_pos + 1..].trim().to_string();
-                if !current_section.is_empty() {
-                    result
-                        .entry(current_section.clone())
-                        .or_default()
-                        .insert(key, value);
-                }
-            }
+            // Entry kind glyphs
+            glyph_local,
+            glyph_unc,
+            glyph_mapped,
+            glyph_junction,
+            glyph_envvar,
         }
-
-        Ok(result)
     }
 
     /// Load a built-in theme by name
@@ -538,6 +581,14 @@ impl Theme {
             path_duplicate_bg: Color::Blue,
             path_nonnormalized_fg: Color::Cyan,
             path_nonnormalized_bg: Color::Blue,
+            path_malformed_fg: Color::Magenta,
+            path_malformed_bg: Color::Blue,
+            path_offline_fg: Color::Gray,
+            path_offline_bg: Color::Blue,
+            path_removable_fg: Color::LightBlue,
+            path_removable_bg: Color::Blue,
+            path_insecure_fg: Color::LightRed,
+            path_insecure_bg: Color::Blue,
 
             // UI element colors
             scrollbar_fg: Color::DarkGray,
@@ -564,10 +615,35 @@ impl Theme {
             menu_selected_bg: Color::Cyan,
             menu_hotkey_fg: Color::LightCyan,
             menu_hotkey_selected_fg: Color::White,
+
+            // Entry kind glyphs
+            glyph_local: DEFAULT_GLYPH_LOCAL.to_string(),
+            glyph_unc: DEFAULT_GLYPH_UNC.to_string(),
+            glyph_mapped: DEFAULT_GLYPH_MAPPED.to_string(),
+            glyph_junction: DEFAULT_GLYPH_JUNCTION.to_string(),
+            glyph_envvar: DEFAULT_GLYPH_ENVVAR.to_string(),
+        }
+    }
+
+    /// Get the glyph shown in front of entries of the given kind
+    pub fn kind_glyph(&self, kind: PathKind) -> &str {
+        match kind {
+            PathKind::Local => &self.glyph_local,
+            PathKind::Unc => &self.glyph_unc,
+            PathKind::MappedDrive => &self.glyph_mapped,
+            PathKind::Junction => &self.glyph_junction,
+            PathKind::EnvVar => &self.glyph_envvar,
         }
     }
 }
 
+// Default entry kind glyphs (all present in the standard Windows console fonts)
+const DEFAULT_GLYPH_LOCAL: &str = "·";
+const DEFAULT_GLYPH_UNC: &str = "≡";
+const DEFAULT_GLYPH_MAPPED: &str = "→";
+const DEFAULT_GLYPH_JUNCTION: &str = "»";
+const DEFAULT_GLYPH_ENVVAR: &str = "%";
+
 /// Parse MC color pair notation (fg;bg) supporting rgb, named colors, and color indices
 fn parse_mc_color_pair(
     value: &str,
@@ -0,0 +1,373 @@
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::path_analyzer::expand_environment_variables;
+use crate::registry::PathScope;
+
+/// How long a tool gets to print its version before it is killed
+const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A well-known tool and how to ask it for its version
+pub struct Tool {
+    /// Executable name without extension
+    pub name: &'static str,
+    pub label: &'static str,
+    pub version_args: &'static [&'static str],
+}
+
+/// Tools people commonly end up with several versions of
+pub const TOOLS: [Tool; 13] = [
+    Tool {
+        name: "java",
+        label: "Java",
+        version_args: &["-version"],
+    },
+    Tool {
+        name: "python",
+        label: "Python",
+        version_args: &["--version"],
+    },
+    Tool {
+        name: "node",
+        label: "Node.js",
+        version_args: &["--version"],
+    },
+    Tool {
+        name: "go",
+        label: "Go",
+        version_args: &["version"],
+    },
+    Tool {
+        name: "dotnet",
+        label: ".NET",
+        version_args: &["--version"],
+    },
+    Tool {
+        name: "ruby",
+        label: "Ruby",
+        version_args: &["--version"],
+    },
+    Tool {
+        name: "perl",
+        label: "Perl",
+        version_args: &["--version"],
+    },
+    Tool {
+        name: "php",
+        label: "PHP",
+        version_args: &["--version"],
+    },
+    Tool {
+        name: "rustc",
+        label: "Rust",
+        version_args: &["--version"],
+    },
+    Tool {
+        name: "gcc",
+        label: "GCC",
+        version_args: &["--version"],
+    },
+    Tool {
+        name: "cmake",
+        label: "CMake",
+        version_args: &["--version"],
+    },
+    Tool {
+        name: "git",
+        label: "Git",
+        version_args: &["--version"],
+    },
+    Tool {
+        name: "pwsh",
+        label: "PowerShell",
+        version_args: &["--version"],
+    },
+];
+
+/// One copy of a tool found in a PATH directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Install {
+    pub scope: PathScope,
+    pub index: usize,
+    /// The entry as stored
+    pub path: String,
+    /// None if neither the file nor the tool told
+    pub version: Option<String>,
+}
+
+/// Every copy of one tool on PATH, in search order: the first one is the one that runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Toolchain {
+    pub label: &'static str,
+    pub installs: Vec<Install>,
+}
+
+impl Toolchain {
+    /// Distinct versions found, unknown ones counted once
+    pub fn version_count(&self) -> usize {
+        self.installs
+            .iter()
+            .map(|install| install.version.as_deref())
+            .collect::<HashSet<_>>()
+            .len()
+    }
+}
+
+/// Find the known tools in PATH directories (scope, index, path as stored, in search order)
+/// and the version of each copy
+pub fn scan(entries: &[(PathScope, usize, String)]) -> Vec<Toolchain> {
+    let mut toolchains: Vec<Toolchain> = Vec::new();
+    let mut seen = HashSet::new();
+    for (scope, index, path) in entries {
+        let expanded = expand_environment_variables(path.trim().trim_matches('"'));
+        let dir = Path::new(&expanded);
+        // A directory listed twice is only searched once, and running the
+        // WindowsApps aliases would open the Store instead of reporting a version
+        if !seen.insert(expanded.to_lowercase())
+            || !dir.is_dir()
+            || crate::shims::is_alias_dir(&expanded)
+        {
+            continue;
+        }
+
+        for tool in &TOOLS {
+            let Some(exe) = executable(dir, tool.name) else {
+                continue;
+            };
+            let install = Install {
+                scope: *scope,
+                index: *index,
+                path: path.clone(),
+                version: version_of(&exe, tool.version_args),
+            };
+            match toolchains.iter_mut().find(|t| t.label == tool.label) {
+                Some(toolchain) => toolchain.installs.push(install),
+                None => toolchains.push(Toolchain {
+                    label: tool.label,
+                    installs: vec![install],
+                }),
+            }
+        }
+    }
+    // Tools found more than once first, the rest in the order of TOOLS
+    toolchains.sort_by_key(|toolchain| toolchain.installs.len() == 1);
+    toolchains
+}
+
+/// The tool's executable in the directory, if it is there
+fn executable(dir: &Path, name: &str) -> Option<PathBuf> {
+    let file = if cfg!(windows) {
+        dir.join(format!("{}.exe", name))
+    } else {
+        dir.join(name)
+    };
+    file.is_file().then_some(file)
+}
+
+/// Version of an executable, from its version resource where there is one so
+/// nothing has to run, otherwise from what it prints
+fn version_of(exe: &Path, args: &[&str]) -> Option<String> {
+    #[cfg(windows)]
+    {
+        if let Some(version) = windows_version::product_version(exe) {
+            return Some(version);
+        }
+    }
+    run_for_version(exe, args)
+}
+
+/// Run the tool directly (no shell, no input, no console window) and read its version
+/// It is killed if it does not finish in time
+fn run_for_version(exe: &Path, args: &[&str]) -> Option<String> {
+    let mut command = Command::new(exe);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let mut child = command.spawn().ok()?;
+
+    let deadline = Instant::now() + VERSION_TIMEOUT;
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(20)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    }
+
+    // Some tools (java, older python) print their version to stderr
+    let mut output = String::new();
+    if let Some(mut stdout) = child.stdout.take() {
+        let _ = stdout.read_to_string(&mut output);
+    }
+    if let Some(mut stderr) = child.stderr.take() {
+        let _ = stderr.read_to_string(&mut output);
+    }
+    parse_version(&output)
+}
+
+/// First dotted version number in a tool's output, e.g. "17.0.8" from
+/// `openjdk version "17.0.8" 2023-07-18`
+fn parse_version(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .map(|word| {
+            word.trim_start_matches(|c: char| !c.is_ascii_digit())
+                .trim_end_matches(|c: char| !c.is_ascii_alphanumeric())
+        })
+        .find(|word| {
+            word.starts_with(|c: char| c.is_ascii_digit())
+                && word.contains('.')
+                && !word.contains('-')
+        })
+        .map(str::to_string)
+}
+
+#[cfg(windows)]
+mod windows_version {
+    use std::ffi::c_void;
+    use std::path::Path;
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::{
+        GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW,
+    };
+
+    use crate::registry::to_wide_string;
+
+    /// ProductVersion string of the file's version resource
+    pub fn product_version(exe: &Path) -> Option<String> {
+        let name = to_wide_string(&exe.to_string_lossy());
+        unsafe {
+            let size = GetFileVersionInfoSizeW(PCWSTR(name.as_ptr()), None);
+            if size == 0 {
+                return None;
+            }
+            let mut data = vec![0u8; size as usize];
+            GetFileVersionInfoW(
+                PCWSTR(name.as_ptr()),
+                0,
+                size,
+                data.as_mut_ptr() as *mut c_void,
+            )
+            .ok()?;
+
+            // The first language and code page the resource has strings for
+            let translation = query(&data, r"\VarFileInfo\Translation")?;
+            if translation.1 < 4 {
+                return None;
+            }
+            let codes = translation.0 as *const u16;
+            let key = format!(
+                r"\StringFileInfo\{:04x}{:04x}\ProductVersion",
+                *codes,
+                *codes.add(1)
+            );
+            let (text, chars) = query(&data, &key)?;
+            let text = std::slice::from_raw_parts(text as *const u16, chars as usize);
+            let version = String::from_utf16_lossy(text)
+                .trim_end_matches('\0')
+                .trim()
+                .to_string();
+            (!version.is_empty()).then_some(version)
+        }
+    }
+
+    /// Pointer into the resource and its length (bytes, or characters for strings)
+    unsafe fn query(data: &[u8], key: &str) -> Option<(*const c_void, u32)> {
+        let key = to_wide_string(key);
+        let mut value: *mut c_void = std::ptr::null_mut();
+        let mut len = 0u32;
+        VerQueryValueW(
+            data.as_ptr() as *const c_void,
+            PCWSTR(key.as_ptr()),
+            &mut value,
+            &mut len,
+        )
+        .as_bool()
+        .then_some((value as *const c_void, len))
+        .filter(|(value, _)| !value.is_null())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            parse_version("openjdk version \"17.0.8\" 2023-07-18\nOpenJDK Runtime"),
+            Some("17.0.8".to_string())
+        );
+        assert_eq!(parse_version("Python 3.12.1\n"), Some("3.12.1".to_string()));
+        assert_eq!(parse_version("v20.10.0\n"), Some("20.10.0".to_string()));
+        assert_eq!(
+            parse_version("go version go1.21.5 windows/amd64"),
+            Some("1.21.5".to_string())
+        );
+        assert_eq!(
+            parse_version("This is perl 5, version 32, subversion 1 (v5.32.1) built"),
+            Some("5.32.1".to_string())
+        );
+        assert_eq!(parse_version("no version here"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_groups_copies_in_search_order() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = tempfile::tempdir().unwrap();
+        let tool = |dir: &str, name: &str, output: &str| {
+            let dir = root.path().join(dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            let file = dir.join(name);
+            std::fs::write(&file, format!("#!/bin/sh\necho '{}'\n", output)).unwrap();
+            std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o755)).unwrap();
+            dir.to_string_lossy().to_string()
+        };
+        let node18 = tool("node18", "node", "v18.19.0");
+        let node20 = tool("node20", "node", "v20.10.0");
+        let git = tool("git", "git", "git version 2.43.0");
+        let entries = vec![
+            (PathScope::Machine, 0, node18.clone()),
+            (PathScope::Machine, 1, git.clone()),
+            (PathScope::User, 0, node20.clone()),
+            // Listed twice, searched once
+            (PathScope::User, 1, node18.clone()),
+        ];
+
+        let toolchains = scan(&entries);
+        assert_eq!(toolchains.len(), 2);
+        assert_eq!(toolchains[0].label, "Node.js");
+        assert_eq!(toolchains[0].version_count(), 2);
+        let node: Vec<_> = toolchains[0]
+            .installs
+            .iter()
+            .map(|install| (install.scope, install.version.as_deref()))
+            .collect();
+        assert_eq!(
+            node,
+            vec![
+                (PathScope::Machine, Some("18.19.0")),
+                (PathScope::User, Some("20.10.0"))
+            ]
+        );
+        assert_eq!(toolchains[1].label, "Git");
+        assert_eq!(toolchains[1].installs[0].path, git);
+    }
+}
@@ -1,15 +1,29 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation, Wrap},
+    widgets::{
+        Block, Borders, List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarOrientation, Wrap,
+    },
     Frame,
 };
 
-use crate::app::{App, ConfirmAction, InputMode, Mode, Panel};
+use crate::aging;
+use crate::app::{
+    terminal_too_small, App, ConfirmAction, Dialog, InputMode, MergeKeep, Mode, NestedFix, Panel,
+    MIN_TERMINAL_HEIGHT, MIN_TERMINAL_WIDTH,
+};
+use crate::disk_usage;
+use crate::live_env;
 use crate::menu;
-use crate::path_analyzer::PathStatus;
+use crate::path_analyzer::{PathKind, PathStatus};
+use crate::path_limits;
+use crate::registry::PathScope;
+use crate::release_notes;
+use crate::shims;
 use crate::theme::Theme;
+use crate::usage;
 
 pub struct UI;
 
@@ -19,6 +33,11 @@ impl UI {
     }
 
     pub fn render(&self, f: &mut Frame, app: &App) {
+        // Panels and dialogs overlap below the minimum, wait for a bigger terminal instead
+        if terminal_too_small(f.area()) {
+            self.render_too_small(f, app);
+            return;
+        }
         match app.mode {
             Mode::Help => {
                 self.render_main(f, app);
@@ -48,6 +67,95 @@ impl UI {
                 self.render_main(f, app);
                 self.render_theme_selection(f, app);
             }
+            Mode::PathDetails => {
+                self.render_main(f, app);
+                self.render_path_details(f, app);
+            }
+            Mode::PathPrecedence => {
+                self.render_main(f, app);
+                self.render_path_precedence(f, app);
+            }
+            Mode::PathLengths => {
+                self.render_main(f, app);
+                self.render_path_lengths(f, app);
+            }
+            Mode::LiveEnvironment => {
+                self.render_main(f, app);
+                self.render_live_environment(f, app);
+            }
+            Mode::History => {
+                self.render_main(f, app);
+                self.render_history(f, app);
+            }
+            Mode::MessageLog => {
+                self.render_main(f, app);
+                self.render_message_log(f, app);
+            }
+            Mode::SecurityReport => {
+                self.render_main(f, app);
+                self.render_security_report(f, app);
+            }
+            Mode::ShimReport => {
+                self.render_main(f, app);
+                self.render_shim_report(f, app);
+            }
+            Mode::Toolchains => {
+                self.render_main(f, app);
+                self.render_toolchains(f, app);
+            }
+            Mode::WhereResult => {
+                self.render_main(f, app);
+                self.render_where_result(f, app);
+            }
+            Mode::AgingReport => {
+                self.render_main(f, app);
+                self.render_aging_report(f, app);
+            }
+            Mode::UsageSuggestions => {
+                self.render_main(f, app);
+                self.render_usage_suggestions(f, app);
+            }
+            Mode::MergeDuplicates => {
+                self.render_main(f, app);
+                self.render_merge_duplicates(f, app);
+            }
+            Mode::NestedEntries => {
+                self.render_main(f, app);
+                self.render_nested_entries(f, app);
+            }
+            Mode::BatchReplace => {
+                self.render_main(f, app);
+                self.render_batch_replace(f, app);
+            }
+            Mode::RawEditor => {
+                self.render_main(f, app);
+                self.render_raw_editor(f, app);
+            }
+            Mode::WhatsNew => {
+                self.render_main(f, app);
+                self.render_whats_new(f, app);
+            }
+            Mode::HostPicker => {
+                self.render_main(f, app);
+                self.render_host_picker(f, app);
+            }
+            Mode::HostNotes => {
+                self.render_main(f, app);
+                self.render_host_picker(f, app);
+                self.render_host_notes(f, app);
+            }
+            Mode::BulkReview => {
+                self.render_main(f, app);
+                self.render_bulk_review(f, app);
+            }
+            Mode::IgnoreList => {
+                self.render_main(f, app);
+                self.render_ignore_list(f, app);
+            }
+            Mode::Locked => {
+                self.render_main(f, app);
+                self.render_locked(f, app);
+            }
             Mode::Menu {
                 active_menu,
                 selected_item,
@@ -71,11 +179,12 @@ impl UI {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length(1), // Menu bar
-                Constraint::Length(1), // Header (statistics only)
-                Constraint::Min(0),    // Main content
-                Constraint::Length(3), // Status bar
-                Constraint::Length(2), // Key hints
+                Constraint::Length(1),                          // Menu bar
+                Constraint::Length(1),                          // Header (statistics only)
+                Constraint::Min(0),                             // Main content
+                Constraint::Length(app.quick_actions_height()), // Quick actions, if turned on
+                Constraint::Length(3),                          // Status bar
+                Constraint::Length(2),                          // Key hints
             ])
             .split(f.area());
 
@@ -95,11 +204,16 @@ impl UI {
         self.render_panel(f, panels[0], app, Panel::Machine);
         self.render_panel(f, panels[1], app, Panel::User);
 
+        // Render quick actions for the selected entry
+        if app.settings.quick_actions {
+            self.render_quick_actions(f, chunks[3], app);
+        }
+
         // Render status bar
-        self.render_status(f, chunks[3], app);
+        self.render_status(f, chunks[4], app);
 
         // Render key hints
-        self.render_key_hints(f, chunks[4], app);
+        self.render_key_hints(f, chunks[5], app);
 
         // Render input overlay if in input mode
         if let Mode::Input(input_mode) = app.mode {
@@ -113,102 +227,71 @@ impl UI {
     }
 
     fn render_header(&self, f: &mut Frame, area: Rect, app: &App) {
-        let stats = app.get_statistics();
-
-        // Build filter status text
-        let mut second_line_spans = vec![
-            Span::raw("Total: "),
-            Span::styled(
-                format!("M:{} ", stats.machine_total),
-                Style::default().fg(app.theme.panel_normal_fg),
-            ),
-            Span::styled(
-                format!("U:{} ", stats.user_total),
-                Style::default().fg(app.theme.panel_normal_fg),
-            ),
-            Span::raw("│ Dead: "),
-            Span::styled(
-                format!("M:{} ", stats.machine_dead),
-                Style::default().fg(app.theme.path_dead_fg),
-            ),
-            Span::styled(
-                format!("U:{} ", stats.user_dead),
-                Style::default().fg(app.theme.path_dead_fg),
-            ),
-            Span::raw("│ Duplicates: "),
-            Span::styled(
-                format!("M:{} ", stats.machine_duplicates),
-                Style::default().fg(app.theme.path_duplicate_fg),
-            ),
-            Span::styled(
-                format!("U:{} ", stats.user_duplicates),
-                Style::default().fg(app.theme.path_duplicate_fg),
-            ),
-            Span::raw("│ Non-norm: "),
-            Span::styled(
-                format!("M:{} ", stats.machine_non_normalized),
-                Style::default().fg(app.theme.path_nonnormalized_fg),
-            ),
-            Span::styled(
-                format!("U:{}", stats.user_non_normalized),
-                Style::default().fg(app.theme.path_nonnormalized_fg),
-            ),
-        ];
+        use crate::app::{FilterMode, HeaderRegion};
+        let bold = |color| Style::default().fg(color).add_modifier(Modifier::BOLD);
+        let mut second_line_spans: Vec<Span> = app
+            .header_stats()
+            .into_iter()
+            .map(|(region, text)| {
+                let style = match region {
+                    HeaderRegion::Label => Style::default(),
+                    HeaderRegion::Remote => bold(app.theme.path_duplicate_fg),
+                    HeaderRegion::Host => bold(app.theme.path_valid_fg),
+                    HeaderRegion::Total(_) => Style::default().fg(app.theme.panel_normal_fg),
+                    HeaderRegion::Dead(_) => Style::default().fg(app.theme.path_dead_fg),
+                    HeaderRegion::Duplicates(_) => Style::default().fg(app.theme.path_duplicate_fg),
+                    HeaderRegion::NonNormalized(_) => {
+                        Style::default().fg(app.theme.path_nonnormalized_fg)
+                    }
+                    HeaderRegion::Malformed(_) => Style::default().fg(app.theme.path_malformed_fg),
+                    HeaderRegion::Offline(_) => Style::default().fg(app.theme.path_offline_fg),
+                };
+                // The count a panel is filtered by stands out
+                let style = match region.filter() {
+                    Some((filter, panel))
+                        if filter != FilterMode::None
+                            && app.filter_mode == filter
+                            && app.filter_panel == Some(panel) =>
+                    {
+                        style.add_modifier(Modifier::REVERSED)
+                    }
+                    _ => style,
+                };
+                Span::styled(text, style)
+            })
+            .collect();
 
-        // Add filter status if active
-        use crate::app::FilterMode;
+        // Add filter status if active, naming the panel it is limited to
         if app.filter_mode != FilterMode::None {
-            let filter_text = match app.filter_mode {
-                FilterMode::Dead => "Dead",
-                FilterMode::Duplicates => "Duplicates",
-                FilterMode::NonNormalized => "Non-normalized",
-                FilterMode::Valid => "Valid",
-                FilterMode::None => "",
-            };
+            let filter_text = app.filter_mode.short_label();
             second_line_spans.push(Span::raw(" │ Filter: "));
             second_line_spans.push(Span::styled(
-                filter_text,
-                Style::default()
-                    .fg(app.theme.filter_indicator_fg)
-                    .add_modifier(Modifier::BOLD),
+                match app.filter_panel {
+                    Some(panel) => {
+                        format!("{} ({})", filter_text, app.panel_descriptor(panel).label)
+                    }
+                    None => filter_text.to_string(),
+                },
+                bold(app.theme.filter_indicator_fg),
             ));
         }
 
+        // Every entry looks fine without checks, so keep saying why
+        if !app.fs.is_checked() {
+            second_line_spans.push(Span::raw(" │ "));
+            second_line_spans.push(Span::styled("No FS checks", bold(app.theme.warning_fg)));
+        }
+
         second_line_spans.push(Span::raw(" │ "));
         second_line_spans.push(Span::styled(
             if app.has_changes { "MODIFIED" } else { "Clean" },
             if app.has_changes {
-                Style::default()
-                    .fg(app.theme.path_duplicate_fg)
-                    .add_modifier(Modifier::BOLD)
+                bold(app.theme.path_duplicate_fg)
             } else {
                 Style::default().fg(app.theme.path_valid_fg)
             },
         ));
 
-        // Add connection mode indicator if in remote mode
-        if let Some(ref connection) = app.remote_connection {
-            second_line_spans.insert(0, Span::raw(" "));
-            second_line_spans.insert(
-                0,
-                Span::styled(
-                    connection.computer_name(),
-                    Style::default()
-                        .fg(app.theme.path_valid_fg)
-                        .add_modifier(Modifier::BOLD),
-                ),
-            );
-            second_line_spans.insert(
-                0,
-                Span::styled(
-                    "REMOTE: ",
-                    Style::default()
-                        .fg(app.theme.path_duplicate_fg)
-                        .add_modifier(Modifier::BOLD),
-                ),
-            );
-        }
-
         let header_line = Line::from(second_line_spans);
 
         let header = Paragraph::new(header_line)
@@ -223,7 +306,7 @@ impl UI {
     }
 
     fn render_panel(&self, f: &mut Frame, area: Rect, app: &App, panel: Panel) {
-        use crate::app::ConnectionMode;
+        use crate::app::{ConnectionMode, PanelAccess};
 
         let is_active = app.active_panel == panel;
 
@@ -259,8 +342,11 @@ impl UI {
             ),
         };
 
-        // Get filtered indices
-        let filtered_indices = app.get_filtered_indices(info);
+        // Filtered and sorted entries
+        let filtered_indices = app.display_indices(panel);
+
+        // Entries equivalent to the selection in the other panel
+        let counterparts = app.counterpart_indices(panel);
 
         // Split area: List (left) and Scrollbar (right 1 column)
         let chunks = Layout::default()
@@ -271,34 +357,12 @@ impl UI {
             ])
             .split(area);
 
-        // Build panel title based on connection mode
-        let scope_label = match (app.connection_mode, panel) {
-            (ConnectionMode::Local, Panel::Machine) => "MACHINE".to_string(),
-            (ConnectionMode::Local, Panel::User) => "USER".to_string(),
-            (ConnectionMode::Remote, Panel::Machine) => "LOCAL MACHINE".to_string(),
-            (ConnectionMode::Remote, Panel::User) => {
-                if let Some(ref conn) = app.remote_connection {
-                    format!("REMOTE MACHINE ({})", conn.computer_name())
-                } else {
-                    "REMOTE MACHINE".to_string()
-                }
-            }
-        };
-
-        let title = format!(
-            " {} {} {}",
-            scope_label,
-            if !app.is_admin && panel == Panel::Machine {
-                "[READ-ONLY]"
-            } else {
-                ""
-            },
-            if !filtered_indices.is_empty() && filtered_indices.len() != paths.len() {
-                format!("[{}/{}]", filtered_indices.len(), paths.len())
-            } else {
-                String::new()
-            }
-        );
+        // Same parts the mouse handler hit-tests
+        let title: String = app
+            .panel_title(panel)
+            .into_iter()
+            .map(|(_, text)| text)
+            .collect();
 
         let border_style = if is_active {
             Style::default()
@@ -308,7 +372,7 @@ impl UI {
             Style::default().fg(app.theme.panel_border_fg)
         };
 
-        let block = Block::default()
+        let mut block = Block::default()
             .title(title)
             .borders(Borders::ALL)
             .border_style(border_style)
@@ -317,10 +381,40 @@ impl UI {
                     .fg(app.theme.panel_normal_fg)
                     .bg(app.theme.panel_normal_bg),
             );
+        // Pending MACHINE edits that Ctrl+S cannot write yet
+        if app.panel_descriptor(panel).access == PanelAccess::Staged {
+            block = block.title_bottom(Line::from(Span::styled(
+                if app.uses_machine_helper() {
+                    " Staged changes (requires elevation, Ctrl+S prompts) "
+                } else {
+                    " Staged changes (requires elevation, Ctrl+E) "
+                },
+                Style::default()
+                    .fg(app.theme.warning_fg)
+                    .add_modifier(Modifier::BOLD),
+            )));
+        }
+        // The remote MACHINE takes this panel's place once connected
+        if let (Panel::User, Some(task), Some(host)) = (panel, &app.remote_task, &app.connecting_to)
+        {
+            block = block.title_bottom(Line::from(Span::styled(
+                format!(" {} Connecting to {} ", app.spinner_of(task), host),
+                Style::default()
+                    .fg(app.theme.info_fg)
+                    .add_modifier(Modifier::BOLD),
+            )));
+        }
+        if let Some((_, written)) = app.last_written_for(panel) {
+            block = block.title_bottom(
+                Line::from(format!(" Written {} ", written.format("%Y-%m-%d %H:%M")))
+                    .right_aligned(),
+            );
+        }
 
-        // Only show filtered paths
+        // Only show filtered paths, from the first row scrolled to
         let items: Vec<ListItem> = filtered_indices
             .iter()
+            .skip(app.panel_position(panel).scroll)
             .map(|&idx| {
                 let is_selected = idx == selected && is_active;
                 let is_marked = marked.contains(&idx);
@@ -330,7 +424,27 @@ impl UI {
                 let color = self.get_status_color(status, &app.theme);
 
                 let checkbox = if is_marked { "[X] " } else { "[ ] " };
-                let display = format!("{}{}", checkbox, path);
+                let glyph = info
+                    .get(idx)
+                    .map(|i| app.theme.kind_glyph(i.kind))
+                    .unwrap_or(" ");
+                // Entries the machine policy keeps from being removed
+                let lock = if app.policy.is_locked(path) {
+                    "🔒 "
+                } else {
+                    ""
+                };
+                let display = if path.trim().is_empty() {
+                    // Make empty segments (";;" or a trailing ";") visible
+                    format!("{}{} <empty segment>", checkbox, glyph)
+                } else {
+                    format!("{}{} {}{}", checkbox, glyph, lock, path)
+                };
+                // Name the package manager a shim directory belongs to
+                let display = match shims::manager_of(path) {
+                    Some(manager) => format!("{}  ({})", display, manager.label()),
+                    None => display,
+                };
 
                 let style = if is_selected {
                     // Use theme colors for selection
@@ -338,6 +452,12 @@ impl UI {
                         .fg(app.theme.panel_selected_fg)
                         .bg(app.theme.panel_selected_bg)
                         .add_modifier(Modifier::BOLD)
+                } else if counterparts.contains(&idx) {
+                    // Dimmed selection bar on duplicates of the other panel's selection
+                    Style::default()
+                        .fg(app.theme.panel_selected_fg)
+                        .bg(app.theme.panel_selected_bg)
+                        .add_modifier(Modifier::DIM)
                 } else {
                     // Use status color for normal items
                     Style::default().fg(color).bg(app.theme.panel_normal_bg)
@@ -366,87 +486,169 @@ impl UI {
     }
 
     fn render_status(&self, f: &mut Frame, area: Rect, app: &App) {
-        let mut status_spans = vec![];
+        let mut segments = vec![];
 
         // Show privilege level with helpful context
         if app.is_admin {
-            status_spans.push(Span::styled(
-                "ADMIN ",
+            segments.push(StatusSegment::new(
+                "ADMIN",
+                1,
                 Style::default().fg(app.theme.path_valid_fg),
             ));
-        } else {
-            status_spans.push(Span::styled(
-                "USER ",
-                Style::default().fg(app.theme.path_duplicate_fg),
-            ));
-            status_spans.push(Span::styled(
-                "(MACHINE read-only, press Ctrl+E to elevate)",
+        } else if app.profile.is_read_only() {
+            // Elevating would not help
+            segments.push(StatusSegment::new(
+                "USER",
+                1,
                 Style::default().fg(app.theme.path_duplicate_fg),
             ));
+        } else {
+            segments.push(
+                StatusSegment::new(
+                    "USER (MACHINE read-only, press Ctrl+E to elevate)",
+                    1,
+                    Style::default().fg(app.theme.path_duplicate_fg),
+                )
+                .or_short("USER"),
+            );
+        }
+
+        // Launch profile, so a limited session is not mistaken for a broken one
+        if let Some(badge) = app.profile.badge() {
+            segments.push(
+                StatusSegment::new(
+                    format!("Profile: {}", badge),
+                    1,
+                    Style::default().fg(app.theme.path_duplicate_fg),
+                )
+                .or_short(app.profile.name.clone()),
+            );
         }
-        status_spans.push(Span::raw(" │ "));
 
         // Add marked items count if any are marked
         let total_marked = app.machine_marked.len() + app.user_marked.len();
         if total_marked > 0 {
-            status_spans.push(Span::styled(
-                format!("{} marked", total_marked),
-                Style::default().fg(app.theme.panel_marked_fg),
-            ));
-            status_spans.push(Span::raw(" │ "));
+            segments.push(
+                StatusSegment::new(
+                    format!("{} marked", total_marked),
+                    2,
+                    Style::default().fg(app.theme.panel_marked_fg),
+                )
+                .or_short(format!("{}✓", total_marked)),
+            );
         }
 
-        // Add PATH length indicators for both panels
-        const PATH_LIMIT: usize = 2047;
+        // Undo history depth, and what can be redone
+        if app.can_undo() || app.can_redo() {
+            let mut text = format!("Undo {}/{}", app.undo_stack.len(), app.undo_depth());
+            let mut short = format!("↶{}", app.undo_stack.len());
+            if app.can_redo() {
+                text.push_str(&format!(" Redo {}", app.redo_stack.len()));
+                short.push_str(&format!(" ↷{}", app.redo_stack.len()));
+            }
+            segments.push(
+                StatusSegment::new(text, 3, Style::default().fg(app.theme.status_fg))
+                    .or_short(short),
+            );
+        }
 
-        // Machine/Left panel PATH length
-        let machine_length = app.calculate_path_length(Panel::Machine);
-        let machine_label = match app.connection_mode {
-            crate::app::ConnectionMode::Local => "MACHINE",
-            crate::app::ConnectionMode::Remote => "LOCAL",
+        // Add PATH length indicators for both panels, measured the way the threshold is
+        let threshold = app.length_threshold();
+        let (machine_label, user_label) = match app.connection_mode {
+            crate::app::ConnectionMode::Local => ("MACHINE", "USER"),
+            crate::app::ConnectionMode::Remote => ("LOCAL", "REMOTE"),
         };
+        for (panel, label) in [(Panel::Machine, machine_label), (Panel::User, user_label)] {
+            let length = app.path_lengths(panel).measured(threshold.measure);
+            let (warning, style) = if length > threshold.limit {
+                (
+                    " ⚠",
+                    Style::default()
+                        .fg(app.theme.path_dead_fg)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                ("", Style::default().fg(app.theme.status_fg))
+            };
+            segments.push(
+                StatusSegment::new(
+                    format!("{}: {}/{}{}", label, length, threshold.limit, warning),
+                    3,
+                    style,
+                )
+                .or_short(format!("{}:{}{}", &label[..1], length, warning)),
+            );
+        }
 
-        if machine_length > PATH_LIMIT {
-            status_spans.push(Span::styled(
-                format!("{}: {}/{} ⚠", machine_label, machine_length, PATH_LIMIT),
-                Style::default()
-                    .fg(app.theme.path_dead_fg)
-                    .add_modifier(Modifier::BOLD),
-            ));
-        } else {
-            status_spans.push(Span::styled(
-                format!("{}: {}/{}", machine_label, machine_length, PATH_LIMIT),
-                Style::default().fg(app.theme.status_fg),
-            ));
+        // Newer release found by the update check
+        if let Some(version) = &app.update_staged {
+            segments.push(
+                StatusSegment::new(
+                    format!("v{} at next start", version),
+                    3,
+                    Style::default().fg(app.theme.info_fg),
+                )
+                .or_short(format!("↑v{}", version)),
+            );
+        } else if let Some(update) = &app.update {
+            segments.push(
+                StatusSegment::new(
+                    format!("Update v{} available", update.version),
+                    3,
+                    Style::default().fg(app.theme.info_fg),
+                )
+                .or_short(format!("↑v{}", update.version)),
+            );
         }
-        status_spans.push(Span::raw(" │ "));
 
-        // User/Right panel PATH length
-        let user_length = app.calculate_path_length(Panel::User);
-        let user_label = match app.connection_mode {
-            crate::app::ConnectionMode::Local => "USER",
-            crate::app::ConnectionMode::Remote => "REMOTE",
-        };
+        // A remote write waiting for its next attempt
+        if let (Some(queued), None) = (&app.queued_remote_write, &app.remote_write_task) {
+            segments.push(
+                StatusSegment::new(
+                    format!("Write to {} queued", queued.host),
+                    2,
+                    Style::default().fg(app.theme.warning_fg),
+                )
+                .or_short(format!("⟳{}", queued.host)),
+            );
+        }
 
-        if user_length > PATH_LIMIT {
-            status_spans.push(Span::styled(
-                format!("{}: {}/{} ⚠", user_label, user_length, PATH_LIMIT),
-                Style::default()
-                    .fg(app.theme.path_dead_fg)
-                    .add_modifier(Modifier::BOLD),
+        // Spinner for work running in the background
+        if let Some((label, spinner)) = app.busy_task() {
+            segments.push(
+                StatusSegment::new(
+                    format!("{} {}...", spinner, label),
+                    2,
+                    Style::default().fg(app.theme.info_fg),
+                )
+                .or_short(spinner.to_string()),
+            );
+        }
+
+        // The message is cut short rather than dropped, the message log has it in full
+        // The entry under the mouse pointer takes its place while hovering
+        if let Some(hover) = app.hover_text() {
+            segments.push(StatusSegment::new(
+                hover,
+                0,
+                Style::default().fg(app.theme.info_fg),
             ));
-        } else {
-            status_spans.push(Span::styled(
-                format!("{}: {}/{}", user_label, user_length, PATH_LIMIT),
+        } else if !app.status_message.is_empty() {
+            segments.push(StatusSegment::new(
+                app.status_message.as_str(),
+                0,
                 Style::default().fg(app.theme.status_fg),
             ));
         }
-        status_spans.push(Span::raw(" │ "));
 
-        status_spans.push(Span::styled(
-            &app.status_message,
-            Style::default().fg(app.theme.status_fg),
-        ));
+        let mut status_spans = vec![];
+        let segments = fit_status_segments(segments, area.width.saturating_sub(2) as usize);
+        for (i, segment) in segments.into_iter().enumerate() {
+            if i > 0 {
+                status_spans.push(Span::raw(STATUS_SEPARATOR));
+            }
+            status_spans.push(Span::styled(segment.text, segment.style));
+        }
 
         let status_text = vec![Line::from(status_spans)];
 
@@ -464,162 +666,67 @@ impl UI {
     }
 
     /// Render function keys with even spacing across terminal width (MC-style)
-    fn render_evenly_spaced_keys(
-        &self,
-        keys: Vec<(&str, &str)>,
-        area: Rect,
-        theme: &Theme,
-    ) -> Line<'static> {
-        let available_width = area.width as usize;
-        let num_keys = keys.len();
-
-        if num_keys == 0 {
-            return Line::from(vec![]);
-        }
-
-        // Calculate total content width (without spacing)
-        let total_content_width: usize = keys
-            .iter()
-            .map(|(num, label)| num.len() + label.len())
-            .sum();
-
-        // Calculate total spacing to distribute
-        let total_spacing = available_width.saturating_sub(total_content_width);
-
-        // Distribute spacing evenly between keys
-        let spacing_per_gap = if num_keys > 1 {
-            total_spacing / (num_keys - 1)
-        } else {
-            0
-        };
-
+    fn render_key_hints(&self, f: &mut Frame, area: Rect, app: &App) {
+        // Laid out by the app, so its click handler sees the same columns
         let mut spans = Vec::new();
-        for (idx, (key_num, label)) in keys.iter().enumerate() {
-            // Add the key number span
+        let mut x = 0;
+        for hint in app.key_hints(area.width) {
+            spans.push(Span::raw(" ".repeat(hint.x.saturating_sub(x) as usize)));
             spans.push(Span::styled(
-                key_num.to_string(),
+                hint.key.clone(),
                 Style::default()
-                    .fg(theme.function_key_number_fg)
-                    .bg(theme.function_key_number_bg),
+                    .fg(app.theme.function_key_number_fg)
+                    .bg(app.theme.function_key_number_bg),
             ));
-            // Add the label span
             spans.push(Span::styled(
-                label.to_string(),
+                hint.label,
                 Style::default()
-                    .fg(theme.function_key_label_fg)
-                    .bg(theme.function_key_label_bg),
+                    .fg(app.theme.function_key_label_fg)
+                    .bg(app.theme.function_key_label_bg),
             ));
-
-            // Add spacing between keys (but not after the last key)
-            if idx < num_keys - 1 {
-                spans.push(Span::raw(" ".repeat(spacing_per_gap)));
-            }
+            x = hint.x + hint.width();
         }
 
-        Line::from(spans)
-    }
+        let paragraph = Paragraph::new(Line::from(spans))
+            .alignment(Alignment::Left)
+            .style(
+                Style::default()
+                    .fg(app.theme.function_key_label_fg)
+                    .bg(app.theme.function_key_label_bg),
+            );
 
-    fn render_key_hints(&self, f: &mut Frame, area: Rect, app: &App) {
-        let hints_line = match app.mode {
-            Mode::Normal => {
-                use crate::app::FilterMode;
-
-                // Count total marked items across both panels
-                let total_marked = app.machine_marked.len() + app.user_marked.len();
-                let filter_active = app.filter_mode != FilterMode::None;
-
-                // Context-sensitive hints based on application state
-                if filter_active {
-                    // When filter is active - show filter-related operations
-                    let mut key_pairs = vec![
-                        ("1", "Help"),
-                        ("/", "Clear"),
-                        ("Ctrl+A", "MarkAll"),
-                        ("3", "Del"),
-                    ];
-                    if app.can_undo() {
-                        key_pairs.push(("Ctrl+Z", "Undo"));
-                    }
-                    if app.can_redo() {
-                        key_pairs.push(("Ctrl+Y", "Redo"));
-                    }
-                    key_pairs.push(("Ctrl+S", "Save"));
-                    if !app.is_admin {
-                        key_pairs.push(("Ctrl+E", "Elevate"));
-                    }
-                    key_pairs.push(("10", "Quit"));
-                    self.render_evenly_spaced_keys(key_pairs, area, &app.theme)
-                } else if total_marked > 0 {
-                    // When items are marked - show bulk operations
-                    let mut key_pairs = vec![
-                        ("1", "Help"),
-                        ("3", "Delete"),
-                        ("5", "Move"),
-                        ("9", "Normalize"),
-                    ];
-                    if app.can_undo() {
-                        key_pairs.push(("Ctrl+Z", "Undo"));
-                    }
-                    if app.can_redo() {
-                        key_pairs.push(("Ctrl+Y", "Redo"));
-                    }
-                    key_pairs.push(("Ctrl+Shift+U", "Unmark"));
-                    key_pairs.push(("Ctrl+S", "Save"));
-                    if !app.is_admin {
-                        key_pairs.push(("Ctrl+E", "Elevate"));
-                    }
-                    key_pairs.push(("10", "Quit"));
-                    self.render_evenly_spaced_keys(key_pairs, area, &app.theme)
-                } else {
-                    // Normal mode - default hints with more discoverable features
-                    let mut key_pairs = vec![
-                        ("1", "Help"),
-                        ("2", "Mark"),
-                        ("3", "Del"),
-                        ("4", "Add"),
-                        ("/", "Filter"),
-                    ];
-                    if app.can_undo() {
-                        key_pairs.push(("Ctrl+Z", "Undo"));
-                    }
-                    if app.can_redo() {
-                        key_pairs.push(("Ctrl+Y", "Redo"));
-                    }
-                    key_pairs.push(("Ctrl+S", "Save"));
-                    // Show Ctrl+E hint when not admin
-                    if !app.is_admin {
-                        key_pairs.push(("Ctrl+E", "Elevate"));
-                    }
-                    // Show Ctrl+O hint when in remote mode
-                    if app.remote_connection.is_some() {
-                        key_pairs.push(("Ctrl+O", "Disconnect"));
-                    }
-                    key_pairs.push(("10", "Quit"));
+        f.render_widget(paragraph, area);
+    }
 
-                    self.render_evenly_spaced_keys(key_pairs, area, &app.theme)
-                }
-            }
-            _ => {
-                let key_pairs = vec![("ESC", "Cancel")];
-                self.render_evenly_spaced_keys(key_pairs, area, &app.theme)
-            }
-        };
+    fn render_quick_actions(&self, f: &mut Frame, area: Rect, app: &App) {
+        // Laid out by the app, so its click handler sees the same columns
+        let mut spans = Vec::new();
+        let mut x = 0;
+        for button in app.quick_buttons() {
+            spans.push(Span::raw(" ".repeat(button.x.saturating_sub(x) as usize)));
+            spans.push(Span::styled(
+                format!("[{}]", button.action.label()),
+                Style::default()
+                    .fg(app.theme.function_key_number_fg)
+                    .bg(app.theme.function_key_number_bg),
+            ));
+            x = button.x + button.width();
+        }
 
-        let paragraph = Paragraph::new(hints_line).alignment(Alignment::Left).style(
+        let paragraph = Paragraph::new(Line::from(spans)).style(
             Style::default()
-                .fg(app.theme.function_key_label_fg)
-                .bg(app.theme.function_key_label_bg),
+                .fg(app.theme.panel_normal_fg)
+                .bg(app.theme.panel_normal_bg),
         );
-
         f.render_widget(paragraph, area);
     }
 
     fn render_help(&self, f: &mut Frame, app: &App) {
         // Create a centered dialog area
-        let area = centered_rect(55, 50, f.area());
+        let area = app.dialog_area(Dialog::Help, f.area());
 
         // Render shadow effect
-        render_dialog_shadow(f, area, &app.theme);
+        render_dialog_shadow(f, area, app);
 
         f.render_widget(ratatui::widgets::Clear, area);
 
@@ -655,7 +762,21 @@ impl UI {
             Line::from("  ↑/↓, j/k        Move selection up/down"),
             Line::from("  PgUp/PgDn       Move by screen height"),
             Line::from("  Home/End        Jump to first/last item"),
+            Line::from("  } / {           Next/previous problem entry"),
             Line::from("  Tab, ←/→        Switch between panels"),
+            Line::from("  m 1-9, Alt+1-9  Set a bookmark, jump to it"),
+            Line::from("  Ctrl+arrows     Resize this dialog"),
+            Line::from("  F9              Focus the menu bar"),
+            Line::from("  i               Show details of selected path"),
+            Line::from("  o               Open selected directory in Explorer"),
+            Line::from("  r               Check the selected path on disk again"),
+            Line::from("  h               Show history of applied changes"),
+            Line::from("  v               Compare with the live (process) PATH"),
+            Line::from("  l               Show the message log"),
+            Line::from("  L               Show PATH lengths and which limits apply"),
+            Line::from("  w               Find which file a command runs, with your edits"),
+            Line::from("  R               Find and replace text in entries of both panels"),
+            Line::from("  E               Edit the panel's whole PATH value as text"),
             Line::from(""),
             Line::from(vec![Span::styled(
                 "Marking Paths:",
@@ -663,11 +784,13 @@ impl UI {
                     .fg(app.theme.help_bold_fg)
                     .add_modifier(Modifier::BOLD),
             )]),
+            Line::from("  V               Mark a range: move to extend, Esc cancels"),
             Line::from("  Ctrl+A          Mark all in current panel"),
             Line::from("  Ctrl+Shift+A    Mark all in both panels"),
             Line::from("  Ctrl+D          Mark all duplicates"),
             Line::from("  Ctrl+Shift+D    Mark all dead paths"),
             Line::from("  Ctrl+N          Mark non-normalized paths"),
+            Line::from("  Ctrl+L          Clean up malformed entries"),
             Line::from(""),
             Line::from(vec![Span::styled(
                 "Color Legend:",
@@ -690,6 +813,29 @@ impl UI {
                 Span::styled("Cyan", Style::default().fg(app.theme.path_nonnormalized_fg)),
                 Span::raw(" - Non-normalized"),
             ]),
+            Line::from(vec![
+                Span::raw("  "),
+                Span::styled("Magenta", Style::default().fg(app.theme.path_malformed_fg)),
+                Span::raw(" - Malformed (empty/padded/quoted)"),
+            ]),
+            Line::from(vec![
+                Span::raw("  "),
+                Span::styled("Gray", Style::default().fg(app.theme.path_offline_fg)),
+                Span::raw(" - On a disconnected mapped drive"),
+            ]),
+            Line::from(vec![
+                Span::raw("  "),
+                Span::styled(
+                    "Light Blue",
+                    Style::default().fg(app.theme.path_removable_fg),
+                ),
+                Span::raw(" - On removable media (USB, card reader)"),
+            ]),
+            Line::from(vec![
+                Span::raw("  "),
+                Span::styled("Light Red", Style::default().fg(app.theme.path_insecure_fg)),
+                Span::raw(" - Writable by all users (hijack risk)"),
+            ]),
             Line::from(vec![
                 Span::raw("  "),
                 Span::styled("Green", Style::default().fg(app.theme.path_valid_fg)),
@@ -707,19 +853,44 @@ impl UI {
             )]),
             Line::from("  Ctrl+Z          Undo last operation"),
             Line::from("  Ctrl+Y          Redo last undone operation"),
+            Line::from("  Z               Revert the last apply"),
             Line::from(""),
             Line::from(vec![Span::styled(
-                "Privileges:",
+                "Panel Titles:",
                 Style::default()
                     .fg(app.theme.help_bold_fg)
                     .add_modifier(Modifier::BOLD),
             )]),
-            Line::from(vec![
-                Span::raw("  "),
-                Span::styled(
-                    "USER mode:",
-                    Style::default().fg(app.theme.path_duplicate_fg),
-                ),
+            Line::from("  Click name      Sort by PATH order/name/status"),
+            Line::from("  Click [n/m]     Switch the filter off and on"),
+            Line::from("  Click a count   Filter its panel (header line)"),
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                "Entry Kinds:",
+                Style::default()
+                    .fg(app.theme.help_bold_fg)
+                    .add_modifier(Modifier::BOLD),
+            )]),
+            Line::from(
+                PathKind::ALL
+                    .iter()
+                    .map(|kind| format!("  {} {}", app.theme.kind_glyph(*kind), kind.label()))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                "Privileges:",
+                Style::default()
+                    .fg(app.theme.help_bold_fg)
+                    .add_modifier(Modifier::BOLD),
+            )]),
+            Line::from(vec![
+                Span::raw("  "),
+                Span::styled(
+                    "USER mode:",
+                    Style::default().fg(app.theme.path_duplicate_fg),
+                ),
                 Span::raw(" MACHINE paths read-only"),
             ]),
             Line::from(vec![
@@ -777,10 +948,2117 @@ impl UI {
         let footer = Paragraph::new(Line::from(vec![Span::styled(
             "Press ESC or F1 to close this help",
             Style::default().fg(app.theme.warning_fg),
-        )]))
-        .alignment(Alignment::Center);
+        )]))
+        .alignment(Alignment::Center);
+
+        f.render_widget(footer, footer_area);
+    }
+
+    fn render_path_details(&self, f: &mut Frame, app: &App) {
+        let Some((path, info)) = app.selected_path_info() else {
+            return;
+        };
+
+        let label_style = Style::default()
+            .fg(app.theme.dialog_fg)
+            .add_modifier(Modifier::BOLD);
+        let value_style = Style::default().fg(app.theme.dialog_fg);
+        let expanded = crate::path_analyzer::expand_environment_variables(path);
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled("Entry:      ", label_style),
+                Span::styled(path.clone(), value_style),
+            ]),
+            Line::from(vec![
+                Span::styled("Normalized: ", label_style),
+                Span::styled(info.normalized.clone(), value_style),
+            ]),
+            Line::from(vec![
+                Span::styled("Expanded:   ", label_style),
+                Span::styled(expanded.clone(), value_style),
+            ]),
+            Line::from(vec![
+                Span::styled("Status:     ", label_style),
+                Span::styled(
+                    info.status.description(),
+                    Style::default()
+                        .fg(self.get_status_color(info.status, &app.theme))
+                        .bg(app.theme.panel_normal_bg),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("Length:     ", label_style),
+                Span::styled(
+                    format!(
+                        "{} characters expanded (MAX_PATH is {})",
+                        expanded.encode_utf16().count(),
+                        crate::path_analyzer::MAX_PATH
+                    ),
+                    value_style,
+                ),
+            ]),
+        ];
+        // Measured on request, or when the dialog opens with directory_sizes on
+        let size_text = match (app.selected_directory_size(), &app.size_task) {
+            (Some(Ok(size)), _) => Some(format!("{} (top level only)", size.describe())),
+            (Some(Err(e)), _) => Some(format!("Could not measure: {}", e)),
+            (None, Some(task)) => Some(format!("{} measuring...", app.spinner_of(task))),
+            (None, None) if info.exists && !info.is_app_aliases && app.fs.is_checked() => {
+                Some("press S to measure the files in it".to_string())
+            }
+            (None, None) => None,
+        };
+        if let Some(size_text) = size_text {
+            lines.push(Line::from(vec![
+                Span::styled("Size:       ", label_style),
+                Span::styled(size_text, value_style),
+            ]));
+        }
+
+        if let Some((scope, written)) = app.last_written_for(app.active_panel) {
+            lines.push(Line::from(vec![
+                Span::styled("Written:    ", label_style),
+                Span::styled(
+                    format!(
+                        "{} PATH last written {}",
+                        scope.as_str(),
+                        written.format("%Y-%m-%d %H:%M:%S")
+                    ),
+                    value_style,
+                ),
+            ]));
+        }
+
+        // Explain anything unusual about this entry
+        let mut notes: Vec<&str> = Vec::new();
+        if info.is_malformed {
+            notes.push(
+                "Malformed entry (empty, padded with spaces, or quoted). Press Ctrl+L to clean up.",
+            );
+        }
+        if info.is_dead() {
+            notes.push("The directory does not exist, so nothing in it can be found via PATH.");
+            if app.selected_creatable_dead().is_some() {
+                notes.push("Press Shift+F10 to create it.");
+            }
+        }
+        if app.connection_mode == crate::app::ConnectionMode::Local {
+            let (has_parent, has_child) = app.nesting_of(&expanded);
+            if has_parent {
+                notes.push("Another PATH entry is a parent of this directory. Command → Nested Subdirectories suggests which one to keep.");
+            }
+            if has_child {
+                notes.push("Another PATH entry is inside this directory. Command → Nested Subdirectories suggests which one to keep.");
+            }
+        }
+        if info.is_offline_drive {
+            notes.push(
+                "The directory is on a mapped network drive that is not connected right now.",
+            );
+            notes.push("Delete All Dead keeps it; reconnect the drive or delete it manually.");
+        }
+        if info.is_media_missing {
+            notes.push("The directory is on removable media that is not inserted right now.");
+            notes
+                .push("Delete All Dead keeps it; the status refreshes when the media is inserted.");
+        } else if info.is_removable_drive {
+            notes.push("The directory is on removable media and disappears when it is ejected.");
+        }
+        if info.is_insecure {
+            notes.push("Any user can create files in this directory, and it is searched before the system directories. A program planted here runs in place of a system command (PATH hijacking).");
+            notes.push("Restrict write access to administrators, or move the entry after the system directories.");
+        }
+        if let Some(manager) = shims::manager_of(path) {
+            notes.push(manager.note());
+            notes.push(
+                "Command → Shims and Version Managers checks it against the other directories in PATH.",
+            );
+        }
+        if info.is_app_aliases {
+            notes.push("Its files are zero-byte reparse points that only Windows can launch, and the folder denies listing to elevated and other accounts, so it is never reported as dead.");
+            notes.push("Turn aliases on or off in Settings → Apps → Advanced app settings → App execution aliases.");
+            notes.push("About aliases: https://learn.microsoft.com/windows/apps/desktop/modernize/desktop-to-uwp-extensions#start-your-application-by-using-an-alias");
+        }
+        if info.is_ignored {
+            notes.push("On the ignore list (Options → Ignore List): never reported as dead or duplicate, and left alone by bulk cleanups.");
+        }
+        if let Some(Ok(size)) = app.selected_directory_size() {
+            if size.bytes >= disk_usage::LARGE_DIRECTORY {
+                notes.push("Removing the entry from PATH leaves these files on disk. If the program is no longer needed, uninstall it too (Settings → Apps → Installed apps).");
+            }
+        }
+        if info.is_onedrive {
+            notes.push("The directory is inside a OneDrive-synced folder. Online-only executables download on first use, which is slow and fails when offline.");
+            notes.push("Press A to check which files are available offline.");
+        }
+        if info.has_extended_prefix {
+            notes.push(r"Uses the \\?\ extended-length prefix: Windows skips path parsing, so '/' separators, '.' and '..' are not resolved.");
+            notes.push("Many programs (including cmd.exe) ignore prefixed PATH entries when searching for executables.");
+            notes.push(r"Normalizing (Shift+F9) removes the \\?\ prefix.");
+        }
+        if info.exceeds_max_path {
+            notes.push("Longer than MAX_PATH: programs without long path support cannot launch executables from here.");
+            notes.push(
+                "Enable LongPathsEnabled, or shorten the entry using an environment variable.",
+            );
+        }
+
+        if !notes.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![Span::styled(
+                "Notes:",
+                Style::default()
+                    .fg(app.theme.warning_fg)
+                    .add_modifier(Modifier::BOLD),
+            )]));
+            for note in notes {
+                lines.push(Line::from(vec![
+                    Span::styled("  • ", value_style),
+                    Span::styled(note, Style::default().fg(app.theme.info_fg)),
+                ]));
+            }
+        }
+
+        if let Some(report) = &app.availability_report {
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                Span::styled("Availability: ", label_style),
+                Span::styled(report.clone(), value_style),
+            ]));
+        } else if let Some(task) = &app.availability_task {
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                Span::styled("Availability: ", label_style),
+                Span::styled(format!("{} checking...", app.spinner_of(task)), value_style),
+            ]));
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![Span::styled(
+            "Press ENTER or ESC to close",
+            Style::default().fg(app.theme.warning_fg),
+        )]));
+
+        let title = vec![Span::styled(
+            " Path Details ",
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let details = Paragraph::new(lines)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false });
+
+        let area = app.dialog_area(Dialog::PathDetails, f.area());
+
+        // Render shadow effect
+        render_dialog_shadow(f, area, app);
+
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(details, area);
+    }
+
+    fn render_live_environment(&self, f: &mut Frame, app: &App) {
+        let label_style = Style::default()
+            .fg(app.theme.dialog_fg)
+            .add_modifier(Modifier::BOLD);
+        let value_style = Style::default().fg(app.theme.dialog_fg);
+        let dim_style = Style::default().fg(app.theme.info_fg);
+        let warning_style = Style::default().fg(app.theme.warning_fg);
+        let saved = app.saved_entries();
+
+        let mut lines = vec![
+            Line::from(Span::styled(
+                "Programs keep the PATH they were started with; new ones read it from the registry.",
+                value_style,
+            )),
+            Line::from(Span::styled(
+                "An entry that is only live works in this terminal, but not in new ones.",
+                value_style,
+            )),
+        ];
+
+        // Each live PATH, with the entries the registry does not have
+        let live = [
+            (
+                format!(
+                    "This process ({} entries):",
+                    app.live_environment.process.len()
+                ),
+                Some(&app.live_environment.process),
+            ),
+            (
+                "HKCU\\Volatile Environment:".to_string(),
+                app.live_environment.volatile.as_ref(),
+            ),
+        ];
+        for (heading, entries) in live {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(heading, label_style)));
+            let Some(entries) = entries else {
+                lines.push(Line::from(Span::styled(
+                    "  No PATH is set for this logon session",
+                    dim_style,
+                )));
+                continue;
+            };
+            let missing = live_env::missing_from(entries, &saved);
+            for entry in entries {
+                let mut spans = vec![Span::styled(format!("  {}", entry), value_style)];
+                if missing.contains(&entry) {
+                    spans.push(Span::styled("  ◀── not in the registry", warning_style));
+                }
+                lines.push(Line::from(spans));
+            }
+        }
+
+        let not_live = live_env::not_in(&saved, &app.live_environment.process);
+        if !not_live.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                "Saved but not in this process (restart the terminal to pick them up):",
+                label_style,
+            )));
+            for entry in not_live {
+                lines.push(Line::from(Span::styled(format!("  {}", entry), dim_style)));
+            }
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "u add unsaved entries to USER, ↑/↓ PgUp/PgDn to scroll, ENTER or ESC to close",
+            warning_style,
+        )));
+
+        let title = vec![Span::styled(
+            " Live Environment ",
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let overlay = Paragraph::new(lines)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .alignment(Alignment::Left)
+            .scroll((app.live_scroll, 0));
+
+        let area = app.dialog_area(Dialog::LiveEnvironment, f.area());
+
+        // Render shadow effect
+        render_dialog_shadow(f, area, app);
+
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(overlay, area);
+    }
+
+    fn render_path_precedence(&self, f: &mut Frame, app: &App) {
+        let label_style = Style::default()
+            .fg(app.theme.dialog_fg)
+            .add_modifier(Modifier::BOLD);
+        let value_style = Style::default().fg(app.theme.dialog_fg);
+        let dim_style = Style::default().fg(app.theme.info_fg);
+        let entries = app.effective_path();
+
+        let scope_info = |scope: PathScope| match scope {
+            PathScope::Machine => (&app.machine_paths, &app.machine_info, "M"),
+            PathScope::User => (&app.user_paths, &app.user_info, "U"),
+        };
+        let name = |scope: PathScope, index: usize| {
+            let (_, _, letter) = scope_info(scope);
+            format!("{}{}", letter, index + 1)
+        };
+
+        let mut lines = vec![
+            Line::from(Span::styled("How Windows builds PATH", label_style)),
+            Line::from(Span::styled(
+                "New processes get the MACHINE entries first, then the USER entries:",
+                value_style,
+            )),
+            Line::from(vec![
+                Span::styled("  PATH = ", value_style),
+                Span::styled(
+                    format!("MACHINE ({})", app.machine_paths.len()),
+                    label_style,
+                ),
+                Span::styled(" ; ", value_style),
+                Span::styled(format!("USER ({})", app.user_paths.len()), label_style),
+            ]),
+            Line::from(Span::styled(
+                "Directories are searched top to bottom and the first match of a program name wins,",
+                value_style,
+            )),
+            Line::from(Span::styled(
+                "so a USER entry never overrides a MACHINE entry for the same directory.",
+                value_style,
+            )),
+            Line::from(""),
+            Line::from(Span::styled("Search order:", label_style)),
+        ];
+
+        for (position, entry) in entries.iter().enumerate() {
+            let (paths, infos, _) = scope_info(entry.scope);
+            let info = &infos[entry.index];
+            let mut spans = vec![
+                Span::styled(format!("{:>4}  ", position + 1), dim_style),
+                Span::styled(
+                    format!("{:<5}", name(entry.scope, entry.index)),
+                    label_style,
+                ),
+                Span::styled(
+                    paths[entry.index].clone(),
+                    Style::default().fg(self.get_status_color(info.status, &app.theme)),
+                ),
+            ];
+            if let Some((scope, index)) = entry.shadowed_by {
+                spans.push(Span::styled(
+                    format!(
+                        "  ──▶ no effect, {} {} is searched first",
+                        scope.as_str(),
+                        name(scope, index)
+                    ),
+                    Style::default().fg(app.theme.warning_fg),
+                ));
+            } else if info.is_dead() {
+                spans.push(Span::styled("  (missing, skipped)", dim_style));
+            }
+            lines.push(Line::from(spans));
+        }
+
+        // Summarize which copy of each cross-scope duplicate wins
+        let cross_scope: Vec<_> = entries
+            .iter()
+            .filter_map(|e| e.shadowed_by.map(|winner| (winner, e)))
+            .filter(|((scope, _), e)| *scope != e.scope)
+            .collect();
+        if !cross_scope.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                "Duplicated between panels:",
+                label_style,
+            )));
+            for ((scope, index), loser) in cross_scope {
+                let (paths, _, _) = scope_info(scope);
+                lines.push(Line::from(vec![
+                    Span::styled(format!("  {} ", name(scope, index)), label_style),
+                    Span::styled(paths[index].clone(), value_style),
+                    Span::styled(
+                        " takes effect  ◀──  ",
+                        Style::default().fg(app.theme.path_valid_fg),
+                    ),
+                    Span::styled(
+                        format!("{} is ignored", name(loser.scope, loser.index)),
+                        Style::default().fg(app.theme.warning_fg),
+                    ),
+                ]));
+            }
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "↑/↓ PgUp/PgDn to scroll, ENTER or ESC to close",
+            Style::default().fg(app.theme.warning_fg),
+        )));
+
+        let title = vec![Span::styled(
+            " How PATH Is Searched ",
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let overlay = Paragraph::new(lines)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .alignment(Alignment::Left)
+            .scroll((app.precedence_scroll, 0));
+
+        let area = app.dialog_area(Dialog::PathPrecedence, f.area());
+
+        // Render shadow effect
+        render_dialog_shadow(f, area, app);
+
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(overlay, area);
+    }
+
+    fn render_path_lengths(&self, f: &mut Frame, app: &App) {
+        let label_style = Style::default()
+            .fg(app.theme.dialog_fg)
+            .add_modifier(Modifier::BOLD);
+        let value_style = Style::default().fg(app.theme.dialog_fg);
+        let dim_style = Style::default().fg(app.theme.info_fg);
+        let over_style = Style::default()
+            .fg(app.theme.path_dead_fg)
+            .add_modifier(Modifier::BOLD);
+        let threshold = app.length_threshold();
+
+        let scopes = match app.connection_mode {
+            crate::app::ConnectionMode::Local => {
+                [("MACHINE", Panel::Machine), ("USER", Panel::User)]
+            }
+            crate::app::ConnectionMode::Remote => [
+                ("LOCAL MACHINE", Panel::Machine),
+                ("REMOTE MACHINE", Panel::User),
+            ],
+        };
+        let lengths: Vec<_> = scopes
+            .iter()
+            .map(|&(label, panel)| (label, app.path_lengths(panel)))
+            .collect();
+        let combined = app.combined_expanded_length();
+
+        let mut lines = vec![
+            Line::from(Span::styled(
+                "Characters, counted in UTF-16 like Windows counts them",
+                dim_style,
+            )),
+            Line::from(Span::styled(
+                format!("  {:<16}{:>9}{:>11}", "", "Stored", "Expanded"),
+                label_style,
+            )),
+        ];
+        for (label, lengths) in &lengths {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {:<16}", label), label_style),
+                Span::styled(
+                    format!("{:>9}{:>11}", lengths.stored, lengths.expanded),
+                    value_style,
+                ),
+            ]));
+        }
+        if let Some(combined) = combined {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {:<16}", "New processes"), label_style),
+                Span::styled(format!("{:>9}{:>11}", "", combined), value_style),
+                Span::styled("  MACHINE;USER", dim_style),
+            ]));
+        }
+        lines.extend([
+            Line::from(""),
+            Line::from(Span::styled(
+                "PATH is stored as REG_EXPAND_SZ: %VARIABLES% stay as written in the registry",
+                value_style,
+            )),
+            Line::from(Span::styled(
+                "and are expanded when a process starts, so the two lengths differ.",
+                value_style,
+            )),
+            Line::from(""),
+            Line::from(Span::styled("Warnings", label_style)),
+        ]);
+        let mut warning = vec![Span::styled(
+            format!(
+                "  Over {} characters, {} length ([limits] in config.ini)",
+                threshold.limit,
+                threshold.measure.label()
+            ),
+            value_style,
+        )];
+        if lengths
+            .iter()
+            .any(|(_, lengths)| lengths.measured(threshold.measure) > threshold.limit)
+        {
+            warning.push(Span::styled("  ⚠ over", over_style));
+        }
+        lines.extend([
+            Line::from(warning),
+            Line::from(""),
+            Line::from(Span::styled("Limits in Windows", label_style)),
+        ]);
+
+        for limit in &path_limits::KNOWN_LIMITS {
+            // Combined limits apply per computer when the panels are two computers
+            let exceeded = match combined.filter(|_| limit.combined) {
+                Some(combined) => combined > limit.length,
+                None => lengths
+                    .iter()
+                    .any(|(_, lengths)| lengths.measured(limit.measure) > limit.length),
+            };
+            let applies_to = match (limit.measure, limit.combined) {
+                (crate::config::LengthMeasure::Stored, _) => "stored, per scope",
+                (crate::config::LengthMeasure::Expanded, true) => "expanded, combined",
+                (crate::config::LengthMeasure::Expanded, false) => "expanded, per scope",
+            };
+            let mut spans = vec![
+                Span::styled(
+                    format!("  {:>6}  ", limit.length),
+                    if exceeded { over_style } else { label_style },
+                ),
+                Span::styled(format!("{:<20}", applies_to), dim_style),
+                Span::styled(limit.what, value_style),
+            ];
+            if exceeded {
+                spans.push(Span::styled("  ⚠ over", over_style));
+            }
+            lines.push(Line::from(spans));
+        }
+
+        lines.extend([
+            Line::from(""),
+            Line::from(Span::styled(
+                "The Windows 10 editor no longer stops at 2047, but older tools and installers do.",
+                value_style,
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                "↑/↓ PgUp/PgDn to scroll, ENTER or ESC to close",
+                Style::default().fg(app.theme.warning_fg),
+            )),
+        ]);
+
+        let title = vec![Span::styled(
+            " PATH Length and Limits ",
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let overlay = Paragraph::new(lines)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false })
+            .scroll((app.lengths_scroll, 0));
+
+        let area = app.dialog_area(Dialog::PathLengths, f.area());
+
+        // Render shadow effect
+        render_dialog_shadow(f, area, app);
+
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(overlay, area);
+    }
+
+    fn render_history(&self, f: &mut Frame, app: &App) {
+        let label_style = Style::default()
+            .fg(app.theme.dialog_fg)
+            .add_modifier(Modifier::BOLD);
+        let value_style = Style::default().fg(app.theme.dialog_fg);
+
+        let mut lines = Vec::new();
+        for entry in &app.history {
+            lines.push(Line::from(vec![
+                Span::styled(entry.display_time(), label_style),
+                Span::styled(format!("  {}", entry.computer), value_style),
+            ]));
+            for change in &entry.changes {
+                lines.push(Line::from(Span::styled(
+                    format!("  {}", change.summary()),
+                    Style::default().fg(app.theme.info_fg),
+                )));
+                for added in &change.added {
+                    lines.push(Line::from(Span::styled(
+                        format!("    + {}", added),
+                        Style::default().fg(app.theme.path_valid_fg),
+                    )));
+                }
+                for removed in &change.removed {
+                    lines.push(Line::from(Span::styled(
+                        format!("    - {}", removed),
+                        Style::default().fg(app.theme.path_dead_fg),
+                    )));
+                }
+            }
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(
+            "↑/↓ PgUp/PgDn to scroll, ESC to close",
+            Style::default().fg(app.theme.warning_fg),
+        )));
+
+        let title = vec![Span::styled(
+            " Apply History ",
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let dialog = Paragraph::new(lines)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .alignment(Alignment::Left)
+            .scroll((app.history_scroll, 0));
+
+        let area = app.dialog_area(Dialog::History, f.area());
+
+        // Render shadow effect
+        render_dialog_shadow(f, area, app);
+
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(dialog, area);
+    }
+
+    fn render_message_log(&self, f: &mut Frame, app: &App) {
+        let time_style = Style::default().fg(app.theme.info_fg);
+        let message_style = Style::default().fg(app.theme.dialog_fg);
+
+        let mut lines: Vec<Line> = app
+            .status_log
+            .iter()
+            .rev()
+            .map(|(time, message)| {
+                Line::from(vec![
+                    Span::styled(format!("{}  ", time), time_style),
+                    Span::styled(message.as_str(), message_style),
+                ])
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push(Line::from(Span::styled("No messages yet", message_style)));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "↑/↓ PgUp/PgDn to scroll, ESC to close",
+            Style::default().fg(app.theme.warning_fg),
+        )));
+
+        let title = vec![Span::styled(
+            " Message Log ",
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let dialog = Paragraph::new(lines)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false })
+            .scroll((app.message_log_scroll, 0));
+
+        let area = app.dialog_area(Dialog::MessageLog, f.area());
+
+        // Render shadow effect
+        render_dialog_shadow(f, area, app);
+
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(dialog, area);
+    }
+
+    fn render_whats_new(&self, f: &mut Frame, app: &App) {
+        let heading_style = Style::default()
+            .fg(app.theme.dialog_title_fg)
+            .add_modifier(Modifier::BOLD);
+        let text_style = Style::default().fg(app.theme.dialog_fg);
+
+        let mut lines = Vec::new();
+        let release = app.releases.get(app.release_selected);
+        match release {
+            Some(release) if !release.sections.is_empty() => {
+                for (heading, items) in &release.sections {
+                    lines.push(Line::from(Span::styled(heading.clone(), heading_style)));
+                    for item in items {
+                        lines.push(Line::from(Span::styled(item.clone(), text_style)));
+                    }
+                    lines.push(Line::from(""));
+                }
+            }
+            _ => {
+                lines.push(Line::from(Span::styled("No release notes", text_style)));
+                lines.push(Line::from(""));
+            }
+        }
+        if !app.releases.is_empty() {
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "Release {} of {}",
+                    app.release_selected + 1,
+                    app.releases.len()
+                ),
+                Style::default().fg(app.theme.info_fg),
+            )));
+        }
+        lines.push(Line::from(Span::styled(
+            "←/→ newer/older release, ↑/↓ PgUp/PgDn to scroll, ESC to close",
+            Style::default().fg(app.theme.warning_fg),
+        )));
+
+        let title = vec![Span::styled(
+            match release {
+                Some(release) => format!(" What's New: {} ", release.title()),
+                None => " What's New ".to_string(),
+            },
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let dialog = Paragraph::new(lines)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false })
+            .scroll((app.release_scroll, 0));
+
+        let area = app.dialog_area(Dialog::WhatsNew, f.area());
+
+        // Render shadow effect
+        render_dialog_shadow(f, area, app);
+
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(dialog, area);
+    }
+
+    fn render_toolchains(&self, f: &mut Frame, app: &App) {
+        let label_style = Style::default()
+            .fg(app.theme.dialog_fg)
+            .add_modifier(Modifier::BOLD);
+        let value_style = Style::default().fg(app.theme.dialog_fg);
+        let dim_style = Style::default()
+            .fg(app.theme.dialog_fg)
+            .add_modifier(Modifier::DIM);
+
+        let mut lines = Vec::new();
+        let repeated = app
+            .toolchains
+            .iter()
+            .filter(|toolchain| toolchain.installs.len() > 1)
+            .count();
+        for (position, toolchain) in app.toolchains.iter().enumerate() {
+            if position == 0 && repeated > 0 {
+                lines.push(Line::from(Span::styled(
+                    "Found more than once (only the first copy runs):",
+                    label_style.add_modifier(Modifier::UNDERLINED),
+                )));
+            } else if position == repeated {
+                if repeated > 0 {
+                    lines.push(Line::from(""));
+                }
+                lines.push(Line::from(Span::styled(
+                    "Found once:",
+                    label_style.add_modifier(Modifier::UNDERLINED),
+                )));
+            }
+
+            if toolchain.installs.len() > 1 {
+                lines.push(Line::from(""));
+                lines.push(Line::from(vec![
+                    Span::styled(toolchain.label, label_style),
+                    Span::styled(
+                        format!(
+                            "  {} copies, {} version{}",
+                            toolchain.installs.len(),
+                            toolchain.version_count(),
+                            if toolchain.version_count() == 1 {
+                                ""
+                            } else {
+                                "s"
+                            }
+                        ),
+                        value_style,
+                    ),
+                ]));
+            }
+            for (copy, install) in toolchain.installs.iter().enumerate() {
+                let version = install.version.as_deref().unwrap_or("unknown");
+                let mut spans = if toolchain.installs.len() > 1 {
+                    vec![
+                        Span::styled(if copy == 0 { "  ▶ " } else { "    " }, label_style),
+                        Span::styled(format!("{:<14}", version), value_style),
+                    ]
+                } else {
+                    vec![
+                        Span::styled(format!("{:<12}", toolchain.label), label_style),
+                        Span::styled(format!("{:<14}", version), value_style),
+                    ]
+                };
+                spans.push(Span::styled(
+                    format!(
+                        "{:<13}",
+                        format!("{} #{}", install.scope.as_str(), install.index + 1)
+                    ),
+                    dim_style,
+                ));
+                spans.push(Span::styled(
+                    install.path.clone(),
+                    if copy == 0 {
+                        value_style
+                    } else {
+                        Style::default().fg(app.theme.warning_fg)
+                    },
+                ));
+                lines.push(Line::from(spans));
+            }
+        }
+
+        lines.push(Line::from(""));
+        if repeated > 0 {
+            lines.push(Line::from(vec![
+                Span::styled("Press M ", label_style),
+                Span::styled(
+                    "to mark the directories whose copies never run, except directories that provide the copy of another tool that does. Review the marks, then press F3 or Del to delete the marked entries.",
+                    value_style,
+                ),
+            ]));
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(
+            "↑/↓ PgUp/PgDn to scroll, M to mark unused copies, ESC to close",
+            Style::default().fg(app.theme.warning_fg),
+        )));
+
+        let title = vec![Span::styled(
+            " Toolchains on PATH ",
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let dialog = Paragraph::new(lines)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false })
+            .scroll((app.toolchain_scroll, 0));
+
+        let area = app.dialog_area(Dialog::Toolchains, f.area());
+
+        // Render shadow effect
+        render_dialog_shadow(f, area, app);
+
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(dialog, area);
+    }
+
+    fn render_aging_report(&self, f: &mut Frame, app: &App) {
+        let label_style = Style::default()
+            .fg(app.theme.dialog_fg)
+            .add_modifier(Modifier::BOLD);
+        let value_style = Style::default().fg(app.theme.dialog_fg);
+        let dim_style = Style::default()
+            .fg(app.theme.dialog_fg)
+            .add_modifier(Modifier::DIM);
+        let now = std::time::SystemTime::now();
+        let stale = app
+            .aging_rows
+            .iter()
+            .filter(|row| aging::is_stale(row.modified, now))
+            .count();
+
+        let mut lines =
+            vec![
+            Line::from(Span::styled(
+                format!(
+                    "{} director{} untouched for over a year, oldest first.",
+                    stale,
+                    if stale == 1 { "y has gone" } else { "ies have gone" }
+                ),
+                value_style,
+            )),
+            Line::from(Span::styled(
+                "An install that was removed or replaced often leaves its old directory behind.",
+                value_style,
+            )),
+            Line::from(""),
+        ];
+        let header_lines = lines.len();
+
+        for (idx, row) in app.aging_rows.iter().enumerate() {
+            let (paths, _, _) = app.panel_entries(row.panel);
+            let is_stale = aging::is_stale(row.modified, now);
+            let row_style = if idx == app.aging_selected {
+                Style::default()
+                    .fg(app.theme.panel_selected_fg)
+                    .bg(app.theme.panel_selected_bg)
+            } else {
+                Style::default()
+            };
+            let age_style = if is_stale {
+                Style::default()
+                    .fg(app.theme.warning_fg)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                value_style
+            };
+            let location = format!(
+                "{} #{}",
+                app.panel_descriptor(row.panel).label,
+                row.index + 1
+            );
+            lines.push(Line::from(vec![
+                Span::styled(
+                    if app.is_marked(row.panel, row.index) {
+                        "[X] "
+                    } else {
+                        "[ ] "
+                    },
+                    label_style.patch(row_style),
+                ),
+                Span::styled(
+                    format!("{:<11}", aging::describe_age(row.modified, now)),
+                    age_style.patch(row_style),
+                ),
+                Span::styled(format!("{:<20}", location), dim_style.patch(row_style)),
+                Span::styled(
+                    paths.get(row.index).cloned().unwrap_or_default(),
+                    value_style.patch(row_style),
+                ),
+            ]));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "↑/↓ select, Space to mark, S to mark all over a year, Enter to go to the entry, ESC to close",
+            Style::default().fg(app.theme.warning_fg),
+        )));
+
+        let title = vec![Span::styled(
+            " Entry Aging Report ",
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let area = app.dialog_area(Dialog::AgingReport, f.area());
+
+        // Keep the selected row in view
+        let visible = area.height.saturating_sub(2) as usize;
+        let selected_end = header_lines + app.aging_selected + 1;
+        let scroll = selected_end.saturating_sub(visible) as u16;
+        let dialog = Paragraph::new(lines)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .alignment(Alignment::Left)
+            .scroll((scroll, 0));
+
+        // Render shadow effect
+        render_dialog_shadow(f, area, app);
+
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(dialog, area);
+    }
+
+    fn render_usage_suggestions(&self, f: &mut Frame, app: &App) {
+        let label_style = Style::default()
+            .fg(app.theme.dialog_fg)
+            .add_modifier(Modifier::BOLD);
+        let value_style = Style::default().fg(app.theme.dialog_fg);
+        let dim_style = Style::default()
+            .fg(app.theme.dialog_fg)
+            .add_modifier(Modifier::DIM);
+
+        let mut lines = vec![
+            Line::from(Span::styled(
+                format!(
+                    "Based on {} days of commands run in PowerShell with the usage hook.",
+                    app.usage_days
+                ),
+                value_style,
+            )),
+            Line::from(Span::styled(
+                "Only unused entries are jumped over, so every recorded command finds the same file.",
+                value_style,
+            )),
+            Line::from(""),
+        ];
+        let header_lines = lines.len();
+
+        for (idx, row) in app.usage_rows.iter().enumerate() {
+            let (paths, _, _) = app.panel_entries(row.panel);
+            let suggestion = row.suggestion;
+            let row_style = if idx == app.usage_selected {
+                Style::default()
+                    .fg(app.theme.panel_selected_fg)
+                    .bg(app.theme.panel_selected_bg)
+            } else {
+                Style::default()
+            };
+            let label = app.panel_descriptor(row.panel).label;
+            let (action, action_style) = match suggestion.action {
+                usage::Move::Promote { to } => (
+                    format!("{} runs, up to {} #{}", suggestion.runs, label, to + 1),
+                    Style::default().fg(app.theme.path_valid_fg),
+                ),
+                usage::Move::Demote => (
+                    format!("never ran, to the end of {}", label),
+                    Style::default().fg(app.theme.warning_fg),
+                ),
+            };
+            lines.push(Line::from(vec![
+                Span::styled(
+                    if row.include { "[X] " } else { "[ ] " },
+                    label_style.patch(row_style),
+                ),
+                Span::styled(
+                    format!("{:<20}", format!("{} #{}", label, suggestion.index + 1)),
+                    dim_style.patch(row_style),
+                ),
+                Span::styled(
+                    format!(
+                        "{:<40}",
+                        paths.get(suggestion.index).cloned().unwrap_or_default()
+                    ),
+                    value_style.patch(row_style),
+                ),
+                Span::styled(action, action_style.patch(row_style)),
+            ]));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "↑/↓ select, Space to include or leave out, A for all, Enter to move, ESC to close",
+            Style::default().fg(app.theme.warning_fg),
+        )));
+
+        let title = vec![Span::styled(
+            " Reorder by Command Usage ",
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let area = app.dialog_area(Dialog::UsageSuggestions, f.area());
+
+        // Keep the selected row in view
+        let visible = area.height.saturating_sub(2) as usize;
+        let selected_end = header_lines + app.usage_selected + 1;
+        let scroll = selected_end.saturating_sub(visible) as u16;
+        let dialog = Paragraph::new(lines)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .alignment(Alignment::Left)
+            .scroll((scroll, 0));
+
+        // Render shadow effect
+        render_dialog_shadow(f, area, app);
+
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(dialog, area);
+    }
+
+    fn render_where_result(&self, f: &mut Frame, app: &App) {
+        let label_style = Style::default()
+            .fg(app.theme.dialog_fg)
+            .add_modifier(Modifier::BOLD);
+        let value_style = Style::default().fg(app.theme.dialog_fg);
+        let dim_style = Style::default()
+            .fg(app.theme.dialog_fg)
+            .add_modifier(Modifier::DIM);
+        let lookup = &app.where_lookup;
+
+        let mut lines = vec![Line::from(Span::styled(
+            "With your edits (not applied yet):",
+            label_style.add_modifier(Modifier::UNDERLINED),
+        ))];
+        if lookup.edited.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "  Not found in any PATH directory",
+                Style::default().fg(app.theme.warning_fg),
+            )));
+        }
+        for (position, found) in lookup.edited.iter().enumerate() {
+            lines.push(Line::from(vec![
+                Span::styled(if position == 0 { "  ▶ " } else { "    " }, label_style),
+                Span::styled(
+                    format!(
+                        "{:<13}",
+                        format!("{} #{}", found.scope.as_str(), found.index + 1)
+                    ),
+                    dim_style,
+                ),
+                Span::styled(
+                    found.file.clone(),
+                    if position == 0 {
+                        value_style
+                    } else {
+                        dim_style
+                    },
+                ),
+            ]));
+        }
+
+        lines.push(Line::from(""));
+        let applied = lookup
+            .applied
+            .first()
+            .map(|found| found.file.as_str())
+            .unwrap_or("nothing, not found");
+        lines.push(Line::from(vec![
+            Span::styled("Runs now: ", label_style),
+            Span::styled(applied.to_string(), value_style),
+        ]));
+        if lookup.changes_winner() {
+            lines.push(Line::from(Span::styled(
+                "Applying your edits changes which file runs",
+                Style::default()
+                    .fg(app.theme.warning_fg)
+                    .add_modifier(Modifier::BOLD),
+            )));
+        } else {
+            lines.push(Line::from(Span::styled(
+                "Applying your edits does not change which file runs",
+                value_style,
+            )));
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "↑/↓ PgUp/PgDn to scroll, W to look up another command, ESC to close",
+            Style::default().fg(app.theme.warning_fg),
+        )));
+
+        let title = vec![Span::styled(
+            format!(" Where Is {} ", lookup.name),
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let dialog = Paragraph::new(lines)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false })
+            .scroll((app.where_scroll, 0));
+
+        let area = app.dialog_area(Dialog::WhereResult, f.area());
+
+        // Render shadow effect
+        render_dialog_shadow(f, area, app);
+
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(dialog, area);
+    }
+
+    fn render_shim_report(&self, f: &mut Frame, app: &App) {
+        let label_style = Style::default()
+            .fg(app.theme.dialog_fg)
+            .add_modifier(Modifier::BOLD);
+        let value_style = Style::default().fg(app.theme.dialog_fg);
+
+        let mut lines = Vec::new();
+        let mut group = None;
+        for finding in &app.shim_findings {
+            // Findings come package managers first, then version managers
+            let versions = finding.manager.is_version_manager();
+            if group != Some(versions) {
+                group = Some(versions);
+                lines.push(Line::from(Span::styled(
+                    if versions {
+                        "Version managers"
+                    } else {
+                        "Package managers"
+                    },
+                    label_style.add_modifier(Modifier::UNDERLINED),
+                )));
+            }
+            lines.push(Line::from(Span::styled(
+                finding.manager.label(),
+                label_style,
+            )));
+            match &finding.entry {
+                Some((scope, index, _)) => lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("  {} #{}  ", scope.as_str(), index + 1),
+                        label_style,
+                    ),
+                    Span::styled(finding.dir.clone(), value_style),
+                ])),
+                None => {
+                    lines.push(Line::from(vec![
+                        Span::styled("  Missing from PATH  ", label_style),
+                        Span::styled(finding.dir.clone(), value_style),
+                    ]));
+                    lines.push(Line::from(Span::styled(
+                        format!(
+                            "  Installed, but its commands are not found. Add the directory to {} PATH.",
+                            finding.manager.scope().as_str()
+                        ),
+                        Style::default().fg(app.theme.warning_fg),
+                    )));
+                }
+            }
+            // Aliases should lose to real installs, shims should win over them
+            let aliases = !finding.manager.belongs_in_front();
+            if finding.entry.is_some()
+                && finding.shadowed_by.is_empty()
+                && finding.shadows.is_empty()
+            {
+                lines.push(Line::from(Span::styled(
+                    if aliases {
+                        "  No other directory in PATH has the same commands"
+                    } else {
+                        "  Nothing searched before it has the same commands"
+                    },
+                    Style::default().fg(app.theme.success_fg),
+                )));
+            }
+            let conflicts = finding
+                .shadowed_by
+                .iter()
+                .map(|conflict| {
+                    if aliases {
+                        (
+                            "Real install searched first",
+                            app.theme.success_fg,
+                            conflict,
+                        )
+                    } else {
+                        ("Shadowed by", app.theme.warning_fg, conflict)
+                    }
+                })
+                .chain(
+                    finding
+                        .shadows
+                        .iter()
+                        .map(|conflict| ("Hides", app.theme.warning_fg, conflict)),
+                );
+            for (label, color, conflict) in conflicts {
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!(
+                            "  {} {} #{}  ",
+                            label,
+                            conflict.scope.as_str(),
+                            conflict.index + 1
+                        ),
+                        Style::default().fg(color),
+                    ),
+                    Span::styled(conflict.path.clone(), value_style),
+                ]));
+                lines.push(Line::from(Span::styled(
+                    format!("    {}", conflict.commands.join(", ")),
+                    Style::default().fg(app.theme.info_fg),
+                )));
+            }
+            if !finding.shadows.is_empty() {
+                lines.push(Line::from(Span::styled(
+                    "  These commands start the alias (a Store stub for python) instead of the real install. Move the folder after them, or turn the aliases off in Settings → Apps → Advanced app settings → App execution aliases.",
+                    value_style,
+                )));
+            }
+            lines.push(Line::from(""));
+        }
+
+        if app.shim_findings.iter().any(|finding| finding.is_fixable()) {
+            lines.push(Line::from(vec![
+                Span::styled("Press R ", label_style),
+                Span::styled(
+                    "to move each shim directory just ahead of the first entry of its panel that shadows it, and the alias folder just after the last install of its panel it hides (Ctrl+Z undoes it).",
+                    value_style,
+                ),
+            ]));
+        }
+        if app.shim_findings.iter().any(|finding| {
+            finding.entry.as_ref().is_some_and(|(scope, _, _)| {
+                finding
+                    .problems()
+                    .iter()
+                    .any(|conflict| conflict.scope != *scope)
+            })
+        }) {
+            lines.push(Line::from(Span::styled(
+                "MACHINE entries are searched before all USER entries, so a conflict between the panels cannot be fixed by reordering.",
+                value_style,
+            )));
+        }
+        if lines.last().is_some_and(|line| line.width() > 0) {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(
+            "↑/↓ PgUp/PgDn to scroll, R to restore recommended positions, ESC to close",
+            Style::default().fg(app.theme.warning_fg),
+        )));
+
+        let title = vec![Span::styled(
+            " Shims and Version Managers ",
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let dialog = Paragraph::new(lines)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false })
+            .scroll((app.shim_scroll, 0));
+
+        let area = app.dialog_area(Dialog::ShimReport, f.area());
+
+        // Render shadow effect
+        render_dialog_shadow(f, area, app);
+
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(dialog, area);
+    }
+
+    fn render_security_report(&self, f: &mut Frame, app: &App) {
+        let label_style = Style::default()
+            .fg(app.theme.dialog_fg)
+            .add_modifier(Modifier::BOLD);
+        let value_style = Style::default().fg(app.theme.dialog_fg);
+
+        let mut lines = Vec::new();
+        if let Some(report) = &app.security_report {
+            lines.push(Line::from(Span::styled(
+                format!("Checked {} director(ies) in PATH", report.scanned),
+                value_style,
+            )));
+            lines.push(Line::from(""));
+
+            if report.findings.is_empty() {
+                lines.push(Line::from(Span::styled(
+                    "No PATH directory is writable by all users",
+                    Style::default().fg(app.theme.success_fg),
+                )));
+            } else {
+                lines.push(Line::from(Span::styled(
+                    "Any user can plant programs in these directories that run in place of trusted commands:",
+                    Style::default()
+                        .fg(app.theme.warning_fg)
+                        .add_modifier(Modifier::BOLD),
+                )));
+                for finding in &report.findings {
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(vec![
+                        Span::styled(
+                            format!("{} #{}  ", finding.scope.as_str(), finding.index + 1),
+                            label_style,
+                        ),
+                        Span::styled(
+                            finding.path.clone(),
+                            Style::default().fg(app.theme.path_dead_fg),
+                        ),
+                    ]));
+                    lines.push(Line::from(vec![
+                        Span::styled("  Writable by: ", label_style),
+                        Span::styled(finding.writable_by.join(", "), value_style),
+                    ]));
+                    if !report.signatures_checked {
+                        continue;
+                    }
+                    if finding.unsigned.is_empty() {
+                        lines.push(Line::from(Span::styled(
+                            "  No unsigned executables",
+                            value_style,
+                        )));
+                    } else {
+                        lines.push(Line::from(Span::styled(
+                            "  Unsigned executables:",
+                            label_style,
+                        )));
+                        for name in &finding.unsigned {
+                            lines.push(Line::from(Span::styled(
+                                format!("    {}", name),
+                                Style::default().fg(app.theme.info_fg),
+                            )));
+                        }
+                    }
+                }
+            }
+
+            if !report.findings.is_empty() {
+                lines.push(Line::from(""));
+                lines.push(Line::from(vec![
+                    Span::styled("Press D ", label_style),
+                    Span::styled(
+                        "to move these entries to the end of their panel instead of deleting them (Ctrl+Z undoes it).",
+                        value_style,
+                    ),
+                ]));
+                lines.push(Line::from(Span::styled(
+                    "PATH is searched top to bottom and the first match wins, so at the end they can no longer shadow commands in the directories above them. MACHINE entries are still searched before all USER entries.",
+                    value_style,
+                )));
+            }
+
+            if !report.errors.is_empty() {
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    "Could not read permissions:",
+                    label_style,
+                )));
+                for error in &report.errors {
+                    lines.push(Line::from(Span::styled(
+                        format!("  {}", error),
+                        Style::default().fg(app.theme.warning_fg),
+                    )));
+                }
+            }
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(
+            "↑/↓ PgUp/PgDn to scroll, D to demote, ESC to close",
+            Style::default().fg(app.theme.warning_fg),
+        )));
+
+        let title = vec![Span::styled(
+            " Security Scan ",
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let dialog = Paragraph::new(lines)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false })
+            .scroll((app.security_scroll, 0));
+
+        let area = app.dialog_area(Dialog::SecurityReport, f.area());
+
+        // Render shadow effect
+        render_dialog_shadow(f, area, app);
+
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(dialog, area);
+    }
+
+    fn render_merge_duplicates(&self, f: &mut Frame, app: &App) {
+        let label_style = Style::default()
+            .fg(app.theme.dialog_fg)
+            .add_modifier(Modifier::BOLD);
+        let value_style = Style::default().fg(app.theme.dialog_fg);
+
+        let mut lines =
+            vec![
+            Line::from(Span::styled(
+                format!(
+                    "{} entr{} listed in both MACHINE and USER.",
+                    app.merge_pairs.len(),
+                    if app.merge_pairs.len() == 1 { "y is" } else { "ies are" }
+                ),
+                value_style,
+            )),
+            Line::from(Span::styled(
+                "MACHINE is searched first, so a USER copy has no effect. Choose the copy to keep:",
+                value_style,
+            )),
+            Line::from(""),
+        ];
+        let header_lines = lines.len();
+
+        for (idx, pair) in app.merge_pairs.iter().enumerate() {
+            let choice_style = if idx == app.merge_selected {
+                Style::default()
+                    .fg(app.theme.panel_selected_fg)
+                    .bg(app.theme.panel_selected_bg)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                label_style
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("{:>3}. ", idx + 1), label_style),
+                Span::styled(format!("< {} >", pair.keep.label()), choice_style),
+            ]));
+            let copies = [
+                (
+                    PathScope::Machine,
+                    pair.machine,
+                    &app.machine_paths,
+                    MergeKeep::User,
+                ),
+                (
+                    PathScope::User,
+                    pair.user,
+                    &app.user_paths,
+                    MergeKeep::Machine,
+                ),
+            ];
+            for (scope, index, paths, dropped_by) in copies {
+                let (outcome, style) = if pair.keep == dropped_by {
+                    ("removed", Style::default().fg(app.theme.path_dead_fg))
+                } else {
+                    ("kept", Style::default().fg(app.theme.path_valid_fg))
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!(
+                            "     {:<7} #{:<3} {:<8} ",
+                            scope.as_str(),
+                            index + 1,
+                            outcome
+                        ),
+                        style,
+                    ),
+                    Span::styled(paths.get(index).cloned().unwrap_or_default(), value_style),
+                ]));
+            }
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "↑/↓ select, ←/→ or M/U/B to choose, Enter to merge, ESC to cancel",
+            Style::default().fg(app.theme.warning_fg),
+        )));
+
+        let title = vec![Span::styled(
+            " Merge Cross-Scope Duplicates ",
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let area = app.dialog_area(Dialog::MergeDuplicates, f.area());
+
+        // Keep the selected pair's three lines in view
+        let visible = area.height.saturating_sub(2) as usize;
+        let selected_end = header_lines + (app.merge_selected + 1) * 3;
+        let scroll = selected_end.saturating_sub(visible) as u16;
+        let dialog = Paragraph::new(lines)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .alignment(Alignment::Left)
+            .scroll((scroll, 0));
+
+        // Render shadow effect
+        render_dialog_shadow(f, area, app);
+
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(dialog, area);
+    }
+
+    fn render_nested_entries(&self, f: &mut Frame, app: &App) {
+        let label_style = Style::default()
+            .fg(app.theme.dialog_fg)
+            .add_modifier(Modifier::BOLD);
+        let value_style = Style::default().fg(app.theme.dialog_fg);
+
+        let mut lines = vec![
+            Line::from(Span::styled(
+                format!(
+                    "{} entr{} inside another entry's directory.",
+                    app.nested_pairs.len(),
+                    if app.nested_pairs.len() == 1 { "y is" } else { "ies are" }
+                ),
+                value_style,
+            )),
+            Line::from(Span::styled(
+                "Installers often add a folder and its bin; usually only one of them has anything to run.",
+                value_style,
+            )),
+            Line::from(""),
+        ];
+        let header_lines = lines.len();
+
+        for (idx, pair) in app.nested_pairs.iter().enumerate() {
+            let choice_style = if idx == app.nested_selected {
+                Style::default()
+                    .fg(app.theme.panel_selected_fg)
+                    .bg(app.theme.panel_selected_bg)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                label_style
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("{:>3}. ", idx + 1), label_style),
+                Span::styled(format!("< {} >", pair.fix.label()), choice_style),
+            ]));
+            let entries = [
+                (
+                    "parent",
+                    pair.parent,
+                    pair.parent_has_commands,
+                    NestedFix::RemoveParent,
+                ),
+                (
+                    "child",
+                    pair.child,
+                    pair.child_has_commands,
+                    NestedFix::RemoveChild,
+                ),
+            ];
+            for (role, (scope, index), has_commands, removed_by) in entries {
+                let (outcome, style) = if pair.fix == removed_by {
+                    ("removed", Style::default().fg(app.theme.path_dead_fg))
+                } else {
+                    ("kept", Style::default().fg(app.theme.path_valid_fg))
+                };
+                let paths = match scope {
+                    PathScope::Machine => &app.machine_paths,
+                    PathScope::User => &app.user_paths,
+                };
+                let mut spans = vec![
+                    Span::styled(
+                        format!(
+                            "     {:<6} {:<7} #{:<3} {:<8} ",
+                            role,
+                            scope.as_str(),
+                            index + 1,
+                            outcome
+                        ),
+                        style,
+                    ),
+                    Span::styled(paths.get(index).cloned().unwrap_or_default(), value_style),
+                ];
+                if !has_commands {
+                    spans.push(Span::styled(
+                        "  (nothing to run)",
+                        Style::default().fg(app.theme.info_fg),
+                    ));
+                }
+                lines.push(Line::from(spans));
+            }
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "↑/↓ select, ←/→ or B/P/C to choose, Enter to apply, ESC to cancel",
+            Style::default().fg(app.theme.warning_fg),
+        )));
+
+        let title = vec![Span::styled(
+            " Nested Subdirectories ",
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let area = app.dialog_area(Dialog::NestedEntries, f.area());
+
+        // Keep the selected pair's three lines in view
+        let visible = area.height.saturating_sub(2) as usize;
+        let selected_end = header_lines + (app.nested_selected + 1) * 3;
+        let scroll = selected_end.saturating_sub(visible) as u16;
+        let dialog = Paragraph::new(lines)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .alignment(Alignment::Left)
+            .scroll((scroll, 0));
+
+        // Render shadow effect
+        render_dialog_shadow(f, area, app);
+
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(dialog, area);
+    }
+
+    fn render_batch_replace(&self, f: &mut Frame, app: &App) {
+        let label_style = Style::default()
+            .fg(app.theme.dialog_fg)
+            .add_modifier(Modifier::BOLD);
+        let value_style = Style::default().fg(app.theme.dialog_fg);
+
+        let rows = &app.replace_rows;
+        let missing = rows.iter().filter(|row| !row.exists).count();
+        let plural = |count: usize| if count == 1 { "y" } else { "ies" };
+        let mut lines = if app.replace_migration {
+            vec![
+                Line::from(Span::styled(
+                    format!(
+                        "{} entr{} under {}, {} found under {}.",
+                        rows.len(),
+                        plural(rows.len()),
+                        app.replace_find,
+                        rows.len() - missing,
+                        app.replace_with
+                    ),
+                    value_style,
+                )),
+                Line::from(Span::styled(
+                    if missing == 0 {
+                        "Every entry moves to its new location.".to_string()
+                    } else {
+                        format!(
+                            "{} entr{} not there and stay{} unchanged (missing below), fix {} by hand.",
+                            missing,
+                            if missing == 1 { "y is" } else { "ies are" },
+                            if missing == 1 { "s" } else { "" },
+                            if missing == 1 { "it" } else { "them" }
+                        )
+                    },
+                    value_style,
+                )),
+                Line::from(""),
+            ]
+        } else {
+            vec![
+                Line::from(Span::styled(
+                    format!(
+                        "Replace \"{}\" with \"{}\" in {} entr{}.",
+                        app.replace_find,
+                        app.replace_with,
+                        rows.len(),
+                        plural(rows.len())
+                    ),
+                    value_style,
+                )),
+                Line::from(Span::styled(
+                    if missing == 0 {
+                        "Every new directory exists.".to_string()
+                    } else {
+                        format!(
+                            "{} new director{} missing, press X to leave {} out.",
+                            missing,
+                            if missing == 1 { "y is" } else { "ies are" },
+                            if missing == 1 { "it" } else { "them" }
+                        )
+                    },
+                    value_style,
+                )),
+                Line::from(""),
+            ]
+        };
+        let header_lines = lines.len();
+
+        for (idx, row) in rows.iter().enumerate() {
+            let check_style = if idx == app.replace_selected {
+                Style::default()
+                    .fg(app.theme.panel_selected_fg)
+                    .bg(app.theme.panel_selected_bg)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                label_style
+            };
+            lines.push(Line::from(vec![
+                Span::styled(if row.include { "[x]" } else { "[ ]" }, check_style),
+                Span::styled(
+                    format!(" {:<7} #{:<3} ", row.scope.as_str(), row.index + 1),
+                    label_style,
+                ),
+                Span::styled(row.before.clone(), value_style),
+            ]));
+            let (outcome, style) = if row.exists {
+                ("exists ", Style::default().fg(app.theme.path_valid_fg))
+            } else {
+                ("missing", Style::default().fg(app.theme.path_dead_fg))
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("     → {}  ", outcome), style),
+                Span::styled(row.after.clone(), value_style),
+            ]));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "↑/↓ select, Space to include, X to skip missing, Enter to replace, ESC to cancel",
+            Style::default().fg(app.theme.warning_fg),
+        )));
+
+        let title = vec![Span::styled(
+            if app.replace_migration {
+                " Drive Migration "
+            } else {
+                " Find and Replace "
+            },
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let area = app.dialog_area(Dialog::BatchReplace, f.area());
+
+        // Keep the selected row's two lines in view
+        let visible = area.height.saturating_sub(2) as usize;
+        let selected_end = header_lines + (app.replace_selected + 1) * 2;
+        let scroll = selected_end.saturating_sub(visible) as u16;
+        let dialog = Paragraph::new(lines)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .alignment(Alignment::Left)
+            .scroll((scroll, 0));
+
+        // Render shadow effect
+        render_dialog_shadow(f, area, app);
+
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(dialog, area);
+    }
+
+    fn render_raw_editor(&self, f: &mut Frame, app: &App) {
+        let Some(editor) = &app.raw_editor else {
+            return;
+        };
+        let value_style = Style::default().fg(app.theme.dialog_fg);
+        let warning_style = Style::default().fg(app.theme.warning_fg);
+        let area = app.dialog_area(Dialog::RawEditor, f.area());
+        let title = vec![Span::styled(
+            format!(
+                " Raw PATH Value: {} ",
+                app.raw_editor_panel.scope().as_str()
+            ),
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let block = create_floating_dialog_block(title, &app.theme);
+        let inner = block.inner(area);
+
+        render_dialog_shadow(f, area, app);
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(block, area);
+
+        // Parsed again on every key: count, length, what changes and what looks wrong
+        let entries = editor.entries();
+        let change = app.raw_editor_change();
+        let mut footer = vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                format!(
+                    "{} entr{}, {} characters, {}",
+                    entries.len(),
+                    if entries.len() == 1 { "y" } else { "ies" },
+                    app.raw_editor_length().unwrap_or_default(),
+                    change
+                        .as_ref()
+                        .map_or("no changes".to_string(), |change| change.summary())
+                ),
+                value_style,
+            )),
+        ];
+        let problems = editor.problems();
+        for problem in problems.iter().take(3) {
+            footer.push(Line::from(Span::styled(
+                format!("! {}", problem),
+                warning_style,
+            )));
+        }
+        if problems.len() > 3 {
+            footer.push(Line::from(Span::styled(
+                format!("! {} more", problems.len() - 3),
+                warning_style,
+            )));
+        }
+        footer.push(Line::from(Span::styled(
+            if editor.reviewing {
+                "Enter or Y to put these entries in the panel, Esc or N to keep editing"
+            } else {
+                "One entry per line or separated by ;, Ctrl+S to review the changes, Esc to discard"
+            },
+            Style::default().fg(app.theme.info_fg),
+        )));
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(footer.len() as u16)])
+            .split(inner);
+
+        if let (true, Some(change)) = (editor.reviewing, &change) {
+            // Only what changes, so a long PATH still fits
+            let added = Style::default().fg(app.theme.path_valid_fg);
+            let removed = Style::default().fg(app.theme.path_dead_fg);
+            let mut lines: Vec<Line> = change
+                .added
+                .iter()
+                .map(|entry| Line::from(Span::styled(format!("+ {}", entry), added)))
+                .chain(
+                    change
+                        .removed
+                        .iter()
+                        .map(|entry| Line::from(Span::styled(format!("- {}", entry), removed))),
+                )
+                .collect();
+            if change.reordered {
+                lines.push(Line::from(Span::styled(
+                    "The remaining entries are reordered.",
+                    value_style,
+                )));
+            }
+            f.render_widget(Paragraph::new(lines), chunks[0]);
+        } else {
+            render_text_area(f, chunks[0], &editor.text, &app.theme);
+        }
+        f.render_widget(Paragraph::new(footer), chunks[1]);
+    }
+
+    fn render_host_picker(&self, f: &mut Frame, app: &App) {
+        let items: Vec<ListItem> = app
+            .host_list
+            .iter()
+            .enumerate()
+            .map(|(idx, host)| {
+                let mut name = host.name.clone();
+                if !host.address.eq_ignore_ascii_case(&host.name) {
+                    name.push_str(&format!(" ({})", host.address));
+                }
+                if !host.credential.is_empty() {
+                    name.push_str(&format!(" as {}", host.credential));
+                }
+                let mut details = vec![host
+                    .last_connected
+                    .as_ref()
+                    .map_or("never connected".to_string(), |when| {
+                        format!("last connected {}", when)
+                    })];
+                details.extend(host.last_health.clone());
+                details.extend(host.notes.lines().next().map(str::to_string));
+
+                let style = if idx == app.host_selected {
+                    Style::default()
+                        .fg(app.theme.panel_selected_fg)
+                        .bg(app.theme.panel_selected_bg)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                        .fg(app.theme.dialog_fg)
+                        .bg(app.theme.dialog_bg)
+                };
+                ListItem::new(format!("{}\n  {}", name, details.join(" · "))).style(style)
+            })
+            .collect();
+
+        let title = vec![Span::styled(
+            " Connect to ",
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let block =
+            create_floating_dialog_block(title, &app.theme).title_bottom(Line::from(Span::styled(
+                " Enter connect · T type a name · N notes · Del remove ",
+                Style::default().fg(app.theme.info_fg),
+            )));
+        render_list_dialog(f, app, List::new(items).block(block));
+    }
+
+    fn render_host_notes(&self, f: &mut Frame, app: &App) {
+        let Some(notes) = &app.host_notes else {
+            return;
+        };
+        let name = app
+            .host_list
+            .get(app.host_selected)
+            .map_or("", |host| host.name.as_str());
+        let area = app.dialog_area(Dialog::HostNotes, f.area());
+        let title = vec![Span::styled(
+            format!(" Notes: {} ", name),
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let block = create_floating_dialog_block(title, &app.theme);
+        let inner = block.inner(area);
+
+        render_dialog_shadow(f, area, app);
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(2)])
+            .split(inner);
+        render_text_area(f, chunks[0], notes, &app.theme);
+        let hint = Line::from(Span::styled(
+            "Ctrl+S to save the notes, Esc to discard",
+            Style::default().fg(app.theme.info_fg),
+        ));
+        f.render_widget(Paragraph::new(vec![Line::from(""), hint]), chunks[1]);
+    }
+
+    fn render_bulk_review(&self, f: &mut Frame, app: &App) {
+        let Some(plan) = &app.pending_bulk_delete else {
+            return;
+        };
+        let value_style = Style::default().fg(app.theme.dialog_fg);
+        let what = match plan.action {
+            ConfirmAction::DeleteAllDead => "dead ",
+            ConfirmAction::DeleteAllDuplicates => "duplicate ",
+            _ => "marked ",
+        };
+        let checked = app.review_checked.iter().filter(|&&c| c).count();
+
+        let mut lines = vec![
+            Line::from(Span::styled(
+                format!(
+                    "{} {}path(s) to delete: {} in MACHINE, {} in USER. Unchecked entries are kept:",
+                    plan.len(),
+                    what,
+                    plan.machine.len(),
+                    plan.user.len()
+                ),
+                value_style,
+            )),
+            Line::from(""),
+        ];
+        let header_lines = lines.len();
+
+        for (idx, (scope, entry)) in plan.entries().enumerate() {
+            let is_checked = app.review_checked.get(idx).copied().unwrap_or(false);
+            let style = if idx == app.review_selected {
+                Style::default()
+                    .fg(app.theme.panel_selected_fg)
+                    .bg(app.theme.panel_selected_bg)
+                    .add_modifier(Modifier::BOLD)
+            } else if is_checked {
+                value_style
+            } else {
+                Style::default().fg(app.theme.info_fg)
+            };
+            let mut spans = vec![
+                Span::styled(if is_checked { "[X] " } else { "[ ] " }, style),
+                Span::styled(format!("{:<8}", scope.as_str()), style),
+                Span::styled(entry.clone(), style),
+            ];
+            if let Some((_, owner)) = app.delete_owners.iter().find(|(e, _)| e == entry) {
+                spans.push(Span::styled(
+                    format!("  belongs to '{}'", owner),
+                    Style::default().fg(app.theme.warning_fg),
+                ));
+            }
+            lines.push(Line::from(spans));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!(
+                "{} of {} checked. ↑/↓ select, Space toggles, A toggles all, Enter deletes, ESC cancels",
+                checked,
+                plan.len()
+            ),
+            Style::default().fg(app.theme.warning_fg),
+        )));
+
+        let title = vec![Span::styled(
+            " Review Delete ",
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let area = app.dialog_area(Dialog::BulkReview, f.area());
+
+        // Keep the selected entry in view
+        let visible = area.height.saturating_sub(2) as usize;
+        let selected_end = header_lines + app.review_selected + 1;
+        let scroll = selected_end.saturating_sub(visible) as u16;
+        let dialog = Paragraph::new(lines)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .alignment(Alignment::Left)
+            .scroll((scroll, 0));
+
+        // Render shadow effect
+        render_dialog_shadow(f, area, app);
+
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(dialog, area);
+    }
+
+    fn render_ignore_list(&self, f: &mut Frame, app: &App) {
+        let value_style = Style::default().fg(app.theme.dialog_fg);
+        let ignored = app
+            .machine_info
+            .iter()
+            .chain(app.user_info.iter())
+            .filter(|info| info.is_ignored)
+            .count();
+
+        let mut lines = vec![
+            Line::from(Span::styled(
+                "Entries here are never reported as dead or duplicate, and bulk cleanups leave them alone.",
+                value_style,
+            )),
+            Line::from(Span::styled(
+                format!("Entries in PATH ignored right now: {}", ignored),
+                value_style,
+            )),
+            Line::from(""),
+        ];
+        let header_lines = lines.len();
+
+        if app.settings.ignored.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "Nothing is ignored yet.",
+                Style::default().fg(app.theme.info_fg),
+            )));
+        }
+        for (idx, rule) in app.settings.ignored.iter().enumerate() {
+            let style = if idx == app.ignore_selected {
+                Style::default()
+                    .fg(app.theme.panel_selected_fg)
+                    .bg(app.theme.panel_selected_bg)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                value_style
+            };
+            lines.push(Line::from(Span::styled(rule.clone(), style)));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "A/Ins adds the selected entry or a prefix, D/Del removes, ESC closes",
+            Style::default().fg(app.theme.warning_fg),
+        )));
 
-        f.render_widget(footer, footer_area);
+        let title = vec![Span::styled(
+            " Ignore List ",
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let area = app.dialog_area(Dialog::IgnoreList, f.area());
+
+        // Keep the selected rule in view
+        let visible = area.height.saturating_sub(2) as usize;
+        let selected_end = header_lines + app.ignore_selected + 1;
+        let scroll = selected_end.saturating_sub(visible) as u16;
+        let dialog = Paragraph::new(lines)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false })
+            .scroll((scroll, 0));
+
+        // Render shadow effect
+        render_dialog_shadow(f, area, app);
+
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(dialog, area);
     }
 
     fn render_process_restart_info(&self, f: &mut Frame, app: &App) {
@@ -792,47 +3070,132 @@ impl UI {
                     .add_modifier(Modifier::BOLD),
             )]),
             Line::from(""),
-            Line::from(vec![Span::styled(
+        ];
+
+        if !app.services_at_risk.is_empty() {
+            lines.push(Line::from(vec![Span::styled(
+                "Warning: Services may depend on removed directories",
+                Style::default()
+                    .fg(app.theme.warning_fg)
+                    .add_modifier(Modifier::BOLD),
+            )]));
+            lines.push(Line::from(""));
+            for risk in &app.services_at_risk {
+                lines.push(Line::from(vec![
+                    Span::styled("  • ", Style::default().fg(app.theme.dialog_fg)),
+                    Span::styled(
+                        format!("{} ({})", risk.service.display_name, risk.service.name),
+                        Style::default().fg(app.theme.info_fg),
+                    ),
+                ]));
+                lines.push(Line::from(Span::styled(
+                    format!("    runs {}", risk.service.executable),
+                    Style::default().fg(app.theme.dialog_fg),
+                )));
+                lines.push(Line::from(Span::styled(
+                    format!("    removed {}", risk.entry),
+                    Style::default().fg(app.theme.dialog_fg),
+                )));
+            }
+            if !app.processes_to_restart.is_empty() {
+                lines.push(Line::from(""));
+            }
+        }
+
+        if !app.processes_to_restart.is_empty() {
+            lines.push(Line::from(vec![Span::styled(
                 "Important: Some running processes need to be restarted",
                 Style::default()
                     .fg(app.theme.warning_fg)
                     .add_modifier(Modifier::BOLD),
-            )]),
-            Line::from(""),
-            Line::from(vec![Span::styled(
+            )]));
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![Span::styled(
                 "The following processes won't pick up the new PATH until restarted:",
                 Style::default().fg(app.theme.dialog_fg),
-            )]),
-            Line::from(""),
-        ];
+            )]));
+            lines.push(Line::from(""));
+        }
 
-        // Add each process to the list
-        for process in &app.processes_to_restart {
-            lines.push(Line::from(vec![
+        // Add each process to the list, the selected one gets its details below
+        let listed = app.listed_processes();
+        for (idx, process) in listed.iter().enumerate() {
+            let style = if idx == app.process_selected {
+                Style::default()
+                    .fg(app.theme.panel_selected_fg)
+                    .bg(app.theme.panel_selected_bg)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(app.theme.info_fg)
+            };
+            let mut spans = vec![
                 Span::styled("  • ", Style::default().fg(app.theme.dialog_fg)),
-                Span::styled(process, Style::default().fg(app.theme.info_fg)),
-            ]));
+                Span::styled(format!("{} (PID {})", process.name, process.pid), style),
+            ];
+            if process.is_service {
+                spans.push(Span::styled(
+                    "  service",
+                    Style::default().fg(app.theme.dialog_fg),
+                ));
+            }
+            lines.push(Line::from(spans));
+        }
+        let hidden = app.processes_to_restart.len() - listed.len();
+        if hidden > 0 {
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "  ({} service process(es) hidden, press S to show them)",
+                    hidden
+                ),
+                Style::default().fg(app.theme.dialog_fg),
+            )));
         }
 
-        lines.push(Line::from(""));
-        lines.push(Line::from(vec![Span::styled(
-            "Why restart?",
-            Style::default()
+        if let Some(process) = listed.get(app.process_selected) {
+            let label_style = Style::default()
                 .fg(app.theme.dialog_fg)
-                .add_modifier(Modifier::BOLD),
-        )]));
-        lines.push(Line::from(vec![Span::styled(
-            "These processes load environment variables at startup and don't respond to",
-            Style::default().fg(app.theme.dialog_fg),
-        )]));
-        lines.push(Line::from(vec![Span::styled(
-            "WM_SETTINGCHANGE notifications. You'll need to close and reopen them to see",
-            Style::default().fg(app.theme.dialog_fg),
-        )]));
-        lines.push(Line::from(vec![Span::styled(
-            "the updated PATH.",
-            Style::default().fg(app.theme.dialog_fg),
-        )]));
+                .add_modifier(Modifier::BOLD);
+            let value_style = Style::default().fg(app.theme.dialog_fg);
+            let started = process
+                .started
+                .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|| "unknown (no access to the process)".to_string());
+            let details = [
+                ("PID", process.pid.to_string()),
+                ("Started", started),
+                (
+                    "Window",
+                    if process.has_window {
+                        "visible"
+                    } else {
+                        "none"
+                    }
+                    .to_string(),
+                ),
+                (
+                    "Service",
+                    if process.is_service {
+                        "yes (session 0)"
+                    } else {
+                        "no"
+                    }
+                    .to_string(),
+                ),
+                ("Why", process.reason().to_string()),
+            ];
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                format!("{}:", process.name),
+                label_style,
+            )));
+            for (label, value) in details {
+                lines.push(Line::from(vec![
+                    Span::styled(format!("  {:<9}", label), label_style),
+                    Span::styled(value, value_style),
+                ]));
+            }
+        }
+
         lines.push(Line::from(""));
         lines.push(Line::from(vec![
             Span::styled(
@@ -847,8 +3210,16 @@ impl UI {
             ),
         ]));
         lines.push(Line::from(""));
+        let hint = if app.processes_to_restart.is_empty() {
+            "ENTER or ESC to continue".to_string()
+        } else {
+            format!(
+                "↑/↓ to see why a process keeps the old PATH, S to {} services, ENTER or ESC to continue",
+                if app.hide_services { "show" } else { "hide" }
+            )
+        };
         lines.push(Line::from(vec![Span::styled(
-            "Press ENTER or ESC to continue",
+            hint,
             Style::default().fg(app.theme.warning_fg),
         )]));
 
@@ -863,21 +3234,114 @@ impl UI {
             .alignment(Alignment::Left)
             .wrap(Wrap { trim: false });
 
-        let area = centered_rect(55, 50, f.area());
+        let area = app.dialog_area(Dialog::ProcessRestartInfo, f.area());
 
         // Render shadow effect
-        render_dialog_shadow(f, area, &app.theme);
+        render_dialog_shadow(f, area, app);
 
         f.render_widget(ratatui::widgets::Clear, area);
         f.render_widget(info, area);
     }
 
+    fn render_too_small(&self, f: &mut Frame, app: &App) {
+        let area = f.area();
+        let content = vec![
+            Line::from(Span::styled(
+                "Terminal too small",
+                Style::default()
+                    .fg(app.theme.warning_fg)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(format!(
+                "need {}x{}, now {}x{}",
+                MIN_TERMINAL_WIDTH, MIN_TERMINAL_HEIGHT, area.width, area.height
+            )),
+            Line::from("Enlarge the window, or press Ctrl+C to quit"),
+        ];
+        let top = area.height.saturating_sub(content.len() as u16) / 2;
+        let placeholder = Paragraph::new(content)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .style(
+                Style::default()
+                    .fg(app.theme.panel_normal_fg)
+                    .bg(app.theme.panel_normal_bg),
+            );
+        f.render_widget(
+            placeholder,
+            Rect {
+                y: area.y + top,
+                height: area.height - top,
+                ..area
+            },
+        );
+    }
+
+    fn render_locked(&self, f: &mut Frame, app: &App) {
+        let area = content_sized_rect(56, 7, f.area());
+        render_dialog_shadow(f, area, app);
+        f.render_widget(ratatui::widgets::Clear, area);
+
+        let title = vec![Span::styled(
+            " Session Locked ",
+            Style::default()
+                .fg(app.theme.dialog_title_fg)
+                .add_modifier(Modifier::BOLD),
+        )];
+        let minutes = app
+            .settings
+            .lock_after
+            .map(|limit| limit.as_secs() / 60)
+            .unwrap_or_default();
+        let content = vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                "RUNNING AS ADMINISTRATOR",
+                Style::default()
+                    .fg(app.theme.warning_fg)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from("Edits to MACHINE affect every user of this computer."),
+            Line::from(""),
+            Line::from(format!("Locked after {} minute(s) without input.", minutes)),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("Press ", Style::default().fg(app.theme.dialog_fg)),
+                Span::styled(
+                    "Enter",
+                    Style::default()
+                        .fg(app.theme.button_focused_fg)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" to unlock", Style::default().fg(app.theme.dialog_fg)),
+            ]),
+        ];
+
+        let dialog = Paragraph::new(content)
+            .block(create_floating_dialog_block(title, &app.theme))
+            .style(
+                Style::default()
+                    .fg(app.theme.dialog_fg)
+                    .bg(app.theme.dialog_bg),
+            )
+            .alignment(Alignment::Center);
+        f.render_widget(dialog, area);
+    }
+
     fn render_about(&self, f: &mut Frame, app: &App) {
+        // Update found by the opt-in check, or downloaded and waiting for the next start
+        let update = match (&app.update_staged, &app.update) {
+            (Some(version), _) => Some(format!("v{} installs at next start", version)),
+            (None, Some(update)) => Some(format!("Update available: v{}", update.version)),
+            (None, None) => None,
+        };
+
         // Auto-size dialog to fit ASCII logo (24 chars wide) + content
-        let area = content_sized_rect(30, 16, f.area());
+        let height = if update.is_some() { 17 } else { 16 };
+        let area = content_sized_rect(30, height, f.area());
 
         // Render shadow effect
-        render_dialog_shadow(f, area, &app.theme);
+        render_dialog_shadow(f, area, app);
 
         f.render_widget(ratatui::widgets::Clear, area);
 
@@ -894,7 +3358,7 @@ impl UI {
         f.render_widget(outer_block, area);
 
         // Build content with ASCII logo and info
-        let content = vec![
+        let mut content = vec![
             Line::from("    ____        __  __  "),
             Line::from("   / __ \\____ _/ /_/ /_ "),
             Line::from("  / /_/ / __ `/ __/ __ \\"),
@@ -907,7 +3371,15 @@ impl UI {
                     .add_modifier(Modifier::BOLD),
             )),
             Line::from(""),
-            Line::from("v0.3.0"),
+            Line::from(format!("v{}", release_notes::CURRENT_VERSION)),
+        ];
+        if let Some(update) = update {
+            content.push(Line::from(Span::styled(
+                update,
+                Style::default().fg(app.theme.info_fg),
+            )));
+        }
+        content.extend([
             Line::from(""),
             Line::from("Windows PATH Environment Manager"),
             Line::from(""),
@@ -918,7 +3390,7 @@ impl UI {
                 "Press Esc or Enter to close",
                 Style::default().fg(app.theme.dialog_fg),
             )),
-        ];
+        ]);
 
         let paragraph = Paragraph::new(content)
             .style(
@@ -931,6 +3403,42 @@ impl UI {
         f.render_widget(paragraph, inner_area);
     }
 
+    /// Per-scope counts and the first entries of the delete-all awaiting confirmation
+    fn push_bulk_delete_preview(&self, lines: &mut Vec<Line>, app: &App) {
+        const PREVIEW_ENTRIES: usize = 5;
+
+        let Some(plan) = &app.pending_bulk_delete else {
+            return;
+        };
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![Span::styled(
+            format!(
+                "{} path(s): {} from MACHINE, {} from USER",
+                plan.len(),
+                plan.machine.len(),
+                plan.user.len()
+            ),
+            Style::default().fg(app.theme.warning_fg),
+        )]));
+        lines.push(Line::from(""));
+
+        for (scope, entry) in plan.entries().take(PREVIEW_ENTRIES) {
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("{:<8}", scope.as_str()),
+                    Style::default().fg(app.theme.info_fg),
+                ),
+                Span::styled(entry.clone(), Style::default().fg(app.theme.dialog_fg)),
+            ]));
+        }
+        if plan.len() > PREVIEW_ENTRIES {
+            lines.push(Line::from(format!(
+                "...and {} more",
+                plan.len() - PREVIEW_ENTRIES
+            )));
+        }
+    }
+
     fn render_confirm(&self, f: &mut Frame, app: &App, action: ConfirmAction) {
         // Build the message lines based on action
         let mut message_lines = vec![Line::from("")]; // Start with blank line
@@ -960,6 +3468,16 @@ impl UI {
                         .fg(app.theme.dialog_fg)
                         .add_modifier(Modifier::BOLD),
                 )]));
+                for (entry, owner) in &app.delete_owners {
+                    message_lines.push(Line::from(""));
+                    message_lines.push(Line::from(vec![Span::styled(
+                        format!(
+                            "{} belongs to '{}' - deleting it from PATH may break that app",
+                            entry, owner
+                        ),
+                        Style::default().fg(app.theme.warning_fg),
+                    )]));
+                }
             }
             ConfirmAction::DeleteAllDead => {
                 message_lines.push(Line::from(vec![Span::styled(
@@ -968,6 +3486,40 @@ impl UI {
                         .fg(app.theme.dialog_fg)
                         .add_modifier(Modifier::BOLD),
                 )]));
+                self.push_bulk_delete_preview(&mut message_lines, app);
+                let unavailable = app.unavailable_count();
+                if unavailable > 0 {
+                    let key_style = Style::default()
+                        .fg(app.theme.button_focused_fg)
+                        .add_modifier(Modifier::BOLD);
+                    message_lines.push(Line::from(""));
+                    message_lines.push(Line::from(vec![
+                        Span::styled(
+                            if app.delete_unavailable {
+                                "[X] "
+                            } else {
+                                "[ ] "
+                            },
+                            Style::default().fg(app.theme.dialog_fg),
+                        ),
+                        Span::styled("O", key_style),
+                        Span::styled(
+                            format!(
+                                "ffline too: {} path(s) on disconnected or ejected drives",
+                                unavailable
+                            ),
+                            Style::default().fg(app.theme.dialog_fg),
+                        ),
+                    ]));
+                }
+                let recent = app.recently_dead_count();
+                if recent > 0 {
+                    message_lines.push(Line::from(""));
+                    message_lines.push(Line::from(format!(
+                        "{} will be kept.",
+                        app.recently_dead_note(recent)
+                    )));
+                }
             }
             ConfirmAction::DeleteAllDuplicates => {
                 message_lines.push(Line::from(vec![Span::styled(
@@ -976,6 +3528,7 @@ impl UI {
                         .fg(app.theme.dialog_fg)
                         .add_modifier(Modifier::BOLD),
                 )]));
+                self.push_bulk_delete_preview(&mut message_lines, app);
             }
             ConfirmAction::ApplyChanges => {
                 message_lines.push(Line::from(vec![Span::styled(
@@ -985,23 +3538,52 @@ impl UI {
                         .add_modifier(Modifier::BOLD),
                 )]));
                 message_lines.push(Line::from(""));
-                // Show which scopes will be modified
-                let mut scopes = Vec::new();
-                if app.user_paths != app.user_original {
-                    scopes.push("USER");
-                }
-                if app.machine_paths != app.machine_original {
-                    scopes.push("MACHINE");
+                // Checkboxes for the scopes (or hosts) that will be written
+                let key_style = Style::default()
+                    .fg(app.theme.button_focused_fg)
+                    .add_modifier(Modifier::BOLD);
+                let choices = app.apply_choices();
+                for choice in &choices {
+                    let note = if !choice.changed {
+                        " (no changes)"
+                    } else if !choice.writable {
+                        " (needs admin)"
+                    } else {
+                        ""
+                    };
+                    let (key, rest) = choice.label.split_at(1);
+                    message_lines.push(Line::from(vec![
+                        Span::styled(
+                            if choice.checked { "[X] " } else { "[ ] " },
+                            Style::default().fg(app.theme.dialog_fg),
+                        ),
+                        Span::styled(key.to_string(), key_style),
+                        Span::styled(rest.to_string(), Style::default().fg(app.theme.dialog_fg)),
+                        Span::styled(note, Style::default().fg(app.theme.info_fg)),
+                    ]));
                 }
-                let scope_text = if scopes.is_empty() {
-                    "No changes detected".to_string()
-                } else {
-                    format!("This will modify: {}", scopes.join(" and "))
-                };
+                let keys: Vec<String> = choices.iter().map(|c| c.key.to_string()).collect();
                 message_lines.push(Line::from(vec![Span::styled(
-                    scope_text,
+                    if choices.iter().all(|c| c.checked) {
+                        format!("Press {} to apply only some changes", keys.join(" or "))
+                    } else {
+                        "Unchecked changes stay pending".to_string()
+                    },
                     Style::default().fg(app.theme.warning_fg),
                 )]));
+                // Someone else wrote PATH or is about to
+                let warnings = app.apply_warnings();
+                if !warnings.is_empty() {
+                    message_lines.push(Line::from(""));
+                }
+                for warning in warnings {
+                    message_lines.push(Line::from(vec![Span::styled(
+                        format!("! {}", warning),
+                        Style::default()
+                            .fg(app.theme.warning_fg)
+                            .add_modifier(Modifier::BOLD),
+                    )]));
+                }
             }
             ConfirmAction::RequestElevation => {
                 message_lines.push(Line::from(vec![Span::styled(
@@ -1058,23 +3640,291 @@ impl UI {
                 )]));
                 message_lines.push(Line::from(""));
                 message_lines.push(Line::from(vec![Span::styled(
-                    "Create directory and add to PATH?",
-                    Style::default().fg(app.theme.dialog_fg),
-                )]));
-            }
-            ConfirmAction::CreateMarkedDirectories => {
-                message_lines.push(Line::from(vec![Span::styled(
-                    "Create all marked dead directories?",
+                    "Create directory and add to PATH?",
+                    Style::default().fg(app.theme.dialog_fg),
+                )]));
+            }
+            ConfirmAction::CreateMarkedDirectories => {
+                message_lines.push(Line::from(vec![Span::styled(
+                    "Create all marked dead directories?",
+                    Style::default()
+                        .fg(app.theme.dialog_fg)
+                        .add_modifier(Modifier::BOLD),
+                )]));
+                message_lines.push(Line::from(""));
+                message_lines.push(Line::from(vec![Span::styled(
+                    "(Network paths and invalid paths will be skipped)",
+                    Style::default().fg(app.theme.info_fg),
+                )]));
+            }
+            ConfirmAction::CreateSelectedDirectory => {
+                message_lines.push(Line::from(vec![Span::styled(
+                    "Create the missing directory?",
+                    Style::default()
+                        .fg(app.theme.dialog_fg)
+                        .add_modifier(Modifier::BOLD),
+                )]));
+                message_lines.push(Line::from(""));
+                message_lines.push(Line::from(vec![Span::styled(
+                    app.selected_creatable_dead().cloned().unwrap_or_default(),
+                    Style::default().fg(app.theme.dialog_fg),
+                )]));
+            }
+            ConfirmAction::UseSubdirectory => {
+                if let Some((typed, suggested)) = &app.pending_subdirectory {
+                    message_lines.push(Line::from(vec![Span::styled(
+                        "Nothing to run in this directory",
+                        Style::default()
+                            .fg(app.theme.dialog_fg)
+                            .add_modifier(Modifier::BOLD),
+                    )]));
+                    message_lines.push(Line::from(vec![Span::styled(
+                        typed.clone(),
+                        Style::default().fg(app.theme.info_fg),
+                    )]));
+                    message_lines.push(Line::from(""));
+                    message_lines.push(Line::from(vec![Span::styled(
+                        "Add this subdirectory instead? (N adds it as typed)",
+                        Style::default().fg(app.theme.dialog_fg),
+                    )]));
+                    message_lines.push(Line::from(vec![Span::styled(
+                        suggested.clone(),
+                        Style::default()
+                            .fg(app.theme.path_valid_fg)
+                            .add_modifier(Modifier::BOLD),
+                    )]));
+                }
+            }
+            ConfirmAction::RevertLastApply => {
+                if let Some(backup) = &app.pending_revert {
+                    message_lines.push(Line::from(vec![Span::styled(
+                        "Revert the last apply?",
+                        Style::default()
+                            .fg(app.theme.dialog_fg)
+                            .add_modifier(Modifier::BOLD),
+                    )]));
+                    let last = app.last_apply.as_ref();
+                    message_lines.push(Line::from(format!(
+                        "Restores {} from the backup saved at {}",
+                        last.map_or("USER and MACHINE", |last| last.scopes()),
+                        backup.display_time()
+                    )));
+                    let counts = match last.map(|last| (last.user, last.machine)) {
+                        Some((true, false)) => {
+                            format!(
+                                "{} USER entries, MACHINE edits are kept",
+                                backup.user_paths.len()
+                            )
+                        }
+                        Some((false, true)) => format!(
+                            "{} MACHINE entries, USER edits are kept",
+                            backup.machine_paths.len()
+                        ),
+                        _ => format!(
+                            "{} USER and {} MACHINE entries",
+                            backup.user_paths.len(),
+                            backup.machine_paths.len()
+                        ),
+                    };
+                    message_lines.push(Line::from(vec![Span::styled(
+                        counts,
+                        Style::default().fg(app.theme.info_fg),
+                    )]));
+                    message_lines.push(Line::from(""));
+                    message_lines.push(Line::from(vec![Span::styled(
+                        "Write it to the registry? (N only restores the panels, Esc cancels)",
+                        Style::default().fg(app.theme.dialog_fg),
+                    )]));
+                }
+            }
+            ConfirmAction::PromoteEntry => {
+                if let Some(plan) = &app.pending_promotion {
+                    let (title, target) = match plan.from {
+                        Panel::User => ("Promote to MACHINE?", "MACHINE"),
+                        Panel::Machine => ("Demote to USER?", "USER"),
+                    };
+                    message_lines.push(Line::from(vec![Span::styled(
+                        title,
+                        Style::default()
+                            .fg(app.theme.dialog_fg)
+                            .add_modifier(Modifier::BOLD),
+                    )]));
+                    message_lines.push(Line::from(""));
+                    message_lines.push(Line::from(plan.entry.clone()));
+                    message_lines.push(Line::from(vec![Span::styled(
+                        match plan.kept_in_target {
+                            Some(idx) => {
+                                format!("Already in {} at #{}, kept there", target, idx + 1)
+                            }
+                            None => format!("Added at the end of {}", target),
+                        },
+                        Style::default().fg(app.theme.info_fg),
+                    )]));
+                    let source = plan.from.scope().as_str();
+                    let removed = plan
+                        .removed_from_source
+                        .iter()
+                        .map(|r| (source, r))
+                        .chain(plan.removed_from_target.iter().map(|r| (target, r)));
+                    message_lines.push(Line::from(""));
+                    message_lines.push(Line::from("Removed:"));
+                    for (scope, (idx, entry)) in removed {
+                        message_lines.push(Line::from(vec![Span::styled(
+                            format!("{} #{}: {}", scope, idx + 1, entry),
+                            Style::default().fg(app.theme.warning_fg),
+                        )]));
+                    }
+                    message_lines.push(Line::from(""));
+                    message_lines.push(Line::from(vec![Span::styled(
+                        "(One Ctrl+Z undoes all of it)",
+                        Style::default()
+                            .fg(app.theme.info_fg)
+                            .add_modifier(Modifier::ITALIC),
+                    )]));
+                }
+            }
+            ConfirmAction::SizeAdvisory => {
+                message_lines.push(Line::from(vec![Span::styled(
+                    "PATH Length Advisory",
+                    Style::default()
+                        .fg(app.theme.dialog_fg)
+                        .add_modifier(Modifier::BOLD),
+                )]));
+                message_lines.push(Line::from(""));
+                for warning in &app.size_advisory.warnings {
+                    message_lines.push(Line::from(vec![Span::styled(
+                        format!("⚠ {}", warning),
+                        Style::default().fg(app.theme.warning_fg),
+                    )]));
+                }
+                message_lines.push(Line::from(""));
+                message_lines.push(Line::from("To make room:"));
+                for suggestion in &app.size_advisory.suggestions {
+                    message_lines.push(Line::from(vec![Span::styled(
+                        format!("• {}", suggestion),
+                        Style::default().fg(app.theme.dialog_fg),
+                    )]));
+                }
+                message_lines.push(Line::from(""));
+                message_lines.push(Line::from(vec![Span::styled(
+                    "Continue to the apply dialog anyway?",
                     Style::default()
                         .fg(app.theme.dialog_fg)
                         .add_modifier(Modifier::BOLD),
                 )]));
+            }
+            ConfirmAction::InstallUpdate => {
+                if let Some(update) = &app.update {
+                    message_lines.push(Line::from(vec![Span::styled(
+                        format!(
+                            "Download Path Commander v{} and install it the next time pc starts?",
+                            update.version
+                        ),
+                        Style::default()
+                            .fg(app.theme.dialog_fg)
+                            .add_modifier(Modifier::BOLD),
+                    )]));
+                    message_lines.push(Line::from(""));
+                    message_lines.push(Line::from(format!("Release notes: {}", update.page_url)));
+                }
                 message_lines.push(Line::from(""));
                 message_lines.push(Line::from(vec![Span::styled(
-                    "(Network paths and invalid paths will be skipped)",
-                    Style::default().fg(app.theme.info_fg),
+                    "The download must match the SHA-256 published with the release",
+                    Style::default()
+                        .fg(app.theme.info_fg)
+                        .add_modifier(Modifier::ITALIC),
                 )]));
             }
+            ConfirmAction::RetryRemote => {
+                if let Some((host, error)) = &app.failed_remote {
+                    message_lines.push(Line::from(vec![Span::styled(
+                        format!("Could not connect to {}", host),
+                        Style::default()
+                            .fg(app.theme.dialog_fg)
+                            .add_modifier(Modifier::BOLD),
+                    )]));
+                    message_lines.push(Line::from(""));
+                    message_lines.push(Line::from(vec![Span::styled(
+                        error.clone(),
+                        Style::default().fg(app.theme.warning_fg),
+                    )]));
+                    message_lines.push(Line::from(""));
+                    message_lines.push(Line::from(vec![Span::styled(
+                        "Try again? The local panels stay usable either way",
+                        Style::default().fg(app.theme.dialog_fg),
+                    )]));
+                }
+            }
+            ConfirmAction::StartRemoteRegistry => {
+                if let Some((host, _)) = &app.failed_remote {
+                    message_lines.push(Line::from(vec![Span::styled(
+                        format!("RemoteRegistry is stopped on {}", host),
+                        Style::default()
+                            .fg(app.theme.dialog_fg)
+                            .add_modifier(Modifier::BOLD),
+                    )]));
+                    message_lines.push(Line::from(""));
+                    message_lines.push(Line::from(vec![Span::styled(
+                        "The remote registry can't be opened while it is stopped. \
+                         Your account is allowed to start it through the computer's service manager.",
+                        Style::default().fg(app.theme.dialog_fg),
+                    )]));
+                    message_lines.push(Line::from(""));
+                    message_lines.push(Line::from(vec![
+                        Span::styled(
+                            "Start it and connect? ",
+                            Style::default().fg(app.theme.dialog_fg),
+                        ),
+                        Span::styled(
+                            "T",
+                            Style::default()
+                                .fg(app.theme.button_focused_fg)
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        Span::styled(
+                            " starts it only until you disconnect",
+                            Style::default().fg(app.theme.dialog_fg),
+                        ),
+                    ]));
+                }
+            }
+            ConfirmAction::RemoteWriteQueued => {
+                if let Some(queued) = &app.queued_remote_write {
+                    message_lines.push(Line::from(vec![Span::styled(
+                        format!("Could not write PATH to {}", queued.host),
+                        Style::default()
+                            .fg(app.theme.dialog_fg)
+                            .add_modifier(Modifier::BOLD),
+                    )]));
+                    message_lines.push(Line::from(""));
+                    message_lines.push(Line::from(vec![Span::styled(
+                        queued.error.clone(),
+                        Style::default().fg(app.theme.warning_fg),
+                    )]));
+                    message_lines.push(Line::from(""));
+                    message_lines.push(Line::from(vec![Span::styled(
+                        format!(
+                            "The local changes are applied. The remote change is queued ({}).",
+                            queued.describe_next(std::time::Instant::now())
+                        ),
+                        Style::default().fg(app.theme.dialog_fg),
+                    )]));
+                    message_lines.push(Line::from(""));
+                    message_lines.push(Line::from(vec![
+                        Span::styled("Retry now? ", Style::default().fg(app.theme.dialog_fg)),
+                        Span::styled(
+                            "S",
+                            Style::default()
+                                .fg(app.theme.button_focused_fg)
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        Span::styled(
+                            " saves it as a .reg file for later instead",
+                            Style::default().fg(app.theme.dialog_fg),
+                        ),
+                    ]));
+                }
+            }
             ConfirmAction::DisconnectRemote => {
                 let computer_name = app
                     .remote_connection
@@ -1087,10 +3937,14 @@ impl UI {
                         .fg(app.theme.dialog_fg)
                         .add_modifier(Modifier::BOLD),
                 )]));
-                if app.has_changes {
+                // Only this host's edits are lost, local ones stay pending
+                if app.remote_changed() {
                     message_lines.push(Line::from(""));
                     message_lines.push(Line::from(vec![Span::styled(
-                        "Warning: Unsaved changes will be lost!",
+                        format!(
+                            "Warning: Unsaved changes on {} will be lost!",
+                            computer_name
+                        ),
                         Style::default().fg(app.theme.warning_fg),
                     )]));
                 }
@@ -1145,14 +3999,45 @@ impl UI {
                 .fg(app.theme.dialog_title_fg)
                 .add_modifier(Modifier::BOLD),
         )];
-        let dialog = Paragraph::new(text)
+        let mut dialog = Paragraph::new(text)
             .block(create_floating_dialog_block(title, &app.theme))
             .alignment(Alignment::Center);
 
-        let area = centered_rect(40, 20, f.area());
+        // The apply dialog needs room for its scope checkboxes
+        let (width, height) = match action {
+            ConfirmAction::ApplyChanges => {
+                // Warnings about other instances name them, let those wrap
+                dialog = dialog.wrap(Wrap { trim: true });
+                (40, 35)
+            }
+            ConfirmAction::PromoteEntry => (40, 35),
+            // Warnings and suggestions are whole sentences
+            ConfirmAction::SizeAdvisory => {
+                dialog = dialog.alignment(Alignment::Left).wrap(Wrap { trim: true });
+                (70, 60)
+            }
+            ConfirmAction::DeleteAllDead | ConfirmAction::DeleteAllDuplicates => (60, 40),
+            ConfirmAction::UseSubdirectory | ConfirmAction::RevertLastApply => {
+                dialog = dialog.wrap(Wrap { trim: true });
+                (60, 35)
+            }
+            ConfirmAction::InstallUpdate
+            | ConfirmAction::RetryRemote
+            | ConfirmAction::StartRemoteRegistry
+            | ConfirmAction::RemoteWriteQueued => {
+                dialog = dialog.wrap(Wrap { trim: true });
+                (60, 40)
+            }
+            ConfirmAction::DeleteSelected if !app.delete_owners.is_empty() => {
+                dialog = dialog.wrap(Wrap { trim: true });
+                (60, 40)
+            }
+            _ => (40, 20),
+        };
+        let area = centered_rect(width, height, f.area());
 
         // Render shadow effect
-        render_dialog_shadow(f, area, &app.theme);
+        render_dialog_shadow(f, area, app);
 
         f.render_widget(ratatui::widgets::Clear, area);
         f.render_widget(dialog, area);
@@ -1163,20 +4048,176 @@ impl UI {
             InputMode::AddPath => " Add Path ",
             InputMode::EditPath => " Edit Path ",
             InputMode::ConnectRemote => " Connect to Remote Computer ",
+            InputMode::WhereCommand => " Where Is Command ",
+            InputMode::ReplaceFind => " Find and Replace: Find ",
+            InputMode::ReplaceWith => " Find and Replace: Replace With ",
+            InputMode::MigrateFrom => " Drive Migration: Moved From ",
+            InputMode::MigrateTo => " Drive Migration: Moved To ",
+            InputMode::ChangeReport => " Change Summary Report ",
+            InputMode::IgnoreRule => " Ignore Entry ",
         };
 
-        let text = vec![
+        let mut text = vec![
             Line::from(""),
             Line::from(vec![Span::styled(
                 &app.input_buffer,
                 Style::default().fg(app.theme.dialog_fg),
             )]),
             Line::from(""),
-            Line::from(vec![Span::styled(
+        ];
+        let mut height = 15;
+        // Said while typing, analysis would only flag the copy after it is added
+        let conflict = app.input_conflict();
+        if let Some((panel, index)) = conflict {
+            text.insert(
+                2,
+                Line::from(Span::styled(
+                    format!(
+                        "Already in {} as entry {} - Ctrl+G goes to it",
+                        app.panel_descriptor(panel).label,
+                        index + 1
+                    ),
+                    Style::default()
+                        .fg(app.theme.warning_fg)
+                        .add_modifier(Modifier::BOLD),
+                )),
+            );
+        }
+        if input_mode == InputMode::AddPath {
+            // Position chooser, the current choice is bracketed
+            let mut spans = vec![Span::styled(
+                "Position: ",
+                Style::default()
+                    .fg(app.theme.dialog_fg)
+                    .add_modifier(Modifier::BOLD),
+            )];
+            for position in crate::config::AddPosition::ALL {
+                if position == app.add_position {
+                    spans.push(Span::styled(
+                        format!("[{}] ", position.label()),
+                        Style::default()
+                            .fg(app.theme.dialog_title_fg)
+                            .add_modifier(Modifier::BOLD),
+                    ));
+                } else {
+                    spans.push(Span::styled(
+                        format!(" {}  ", position.label()),
+                        Style::default().fg(app.theme.dialog_fg),
+                    ));
+                }
+            }
+            text.push(Line::from(spans));
+
+            // Scope chooser, only local panels are scopes
+            let mut keys = "Tab to change position";
+            if app.connection_mode == crate::app::ConnectionMode::Local {
+                let mut spans = vec![Span::styled(
+                    "Scope: ",
+                    Style::default()
+                        .fg(app.theme.dialog_fg)
+                        .add_modifier(Modifier::BOLD),
+                )];
+                for panel in [Panel::User, Panel::Machine] {
+                    let label = panel.scope().as_str();
+                    if panel == app.add_panel() {
+                        spans.push(Span::styled(
+                            format!("[{}] ", label),
+                            Style::default()
+                                .fg(app.theme.dialog_title_fg)
+                                .add_modifier(Modifier::BOLD),
+                        ));
+                    } else {
+                        spans.push(Span::styled(
+                            format!(" {}  ", label),
+                            Style::default().fg(app.theme.dialog_fg),
+                        ));
+                    }
+                }
+                text.push(Line::from(spans));
+                keys = "Tab to change position, Shift+Tab to change scope";
+            }
+            text.push(Line::from(""));
+            text.push(Line::from(vec![Span::styled(
                 "Enter to confirm, ESC to cancel",
                 Style::default().fg(app.theme.info_fg),
-            )]),
-        ];
+            )]));
+            text.push(Line::from(vec![Span::styled(
+                keys,
+                Style::default().fg(app.theme.info_fg),
+            )]));
+            height = 28;
+        } else if input_mode == InputMode::ChangeReport {
+            // Format chooser, the current choice is bracketed
+            let mut spans = vec![Span::styled(
+                "Format: ",
+                Style::default()
+                    .fg(app.theme.dialog_fg)
+                    .add_modifier(Modifier::BOLD),
+            )];
+            for format in crate::change_report::ReportFormat::ALL {
+                if format == app.report_format {
+                    spans.push(Span::styled(
+                        format!("[{}] ", format.label()),
+                        Style::default()
+                            .fg(app.theme.dialog_title_fg)
+                            .add_modifier(Modifier::BOLD),
+                    ));
+                } else {
+                    spans.push(Span::styled(
+                        format!(" {}  ", format.label()),
+                        Style::default().fg(app.theme.dialog_fg),
+                    ));
+                }
+            }
+            text.push(Line::from(spans));
+            text.push(Line::from(Span::styled(
+                "Folder the diff and health check are saved in",
+                Style::default().fg(app.theme.dialog_fg),
+            )));
+            text.push(Line::from(""));
+            text.push(Line::from(vec![Span::styled(
+                "Enter to save, ESC to cancel, Tab to change format",
+                Style::default().fg(app.theme.info_fg),
+            )]));
+            height = 28;
+        } else {
+            let hint = match input_mode {
+                InputMode::ReplaceFind => {
+                    Some("Text to find in the entries of both panels, in any case".to_string())
+                }
+                InputMode::ReplaceWith => Some(format!(
+                    "Replaces \"{}\", the changes are previewed first",
+                    app.replace_find
+                )),
+                InputMode::MigrateFrom => Some(format!(
+                    "Old drive or folder. In PATH: {}",
+                    app.entry_roots().join("  ")
+                )),
+                InputMode::MigrateTo => Some(format!(
+                    "New drive or folder for the entries under {}",
+                    app.replace_find
+                )),
+                InputMode::IgnoreRule => {
+                    Some(r"End with * to cover everything below, e.g. C:\agent\_work\*".to_string())
+                }
+                _ => None,
+            };
+            if let Some(hint) = hint {
+                text.push(Line::from(Span::styled(
+                    hint,
+                    Style::default().fg(app.theme.dialog_fg),
+                )));
+                text.push(Line::from(""));
+            }
+            text.push(Line::from(vec![Span::styled(
+                "Enter to confirm, ESC to cancel",
+                Style::default().fg(app.theme.info_fg),
+            )]));
+        }
+
+        if conflict.is_some() {
+            height += 5;
+        }
 
         let title_spans = vec![Span::styled(
             title,
@@ -1188,20 +4229,20 @@ impl UI {
             .block(create_floating_dialog_block(title_spans, &app.theme))
             .alignment(Alignment::Left);
 
-        let area = centered_rect(50, 15, f.area());
+        let area = centered_rect(50, height, f.area());
 
         // Render shadow effect
-        render_dialog_shadow(f, area, &app.theme);
+        render_dialog_shadow(f, area, app);
 
         f.render_widget(ratatui::widgets::Clear, area);
         f.render_widget(input, area);
     }
 
     fn render_file_browser(&self, f: &mut Frame, app: &App) {
-        let area = centered_rect(60, 60, f.area());
+        let area = app.dialog_area(Dialog::FileBrowser, f.area());
 
         // Render shadow effect
-        render_dialog_shadow(f, area, &app.theme);
+        render_dialog_shadow(f, area, app);
 
         // Clear the area and render the main block
         f.render_widget(ratatui::widgets::Clear, area);
@@ -1330,9 +4371,9 @@ impl UI {
             )
             .alignment(Alignment::Center);
 
-        // Render the inner widgets
+        // Render the inner widgets, the list scrolled the way the click handler expects
         f.render_widget(path_widget, chunks[0]);
-        f.render_widget(list, chunks[1]);
+        f.render_stateful_widget(list, chunks[1], &mut list_state(app, f.area()));
         f.render_widget(hints_widget, chunks[2]);
 
         // Render scrollbar
@@ -1379,23 +4420,14 @@ impl UI {
                 .add_modifier(Modifier::BOLD),
         )];
         let list = List::new(items).block(create_floating_dialog_block(title, &app.theme));
-
-        // Auto-size based on number of backups (max 15 visible, ~35 chars wide for filename)
-        let num_items = app.backup_list.len().min(15);
-        let area = content_sized_rect(40, num_items as u16 + 2, f.area());
-
-        // Render shadow effect
-        render_dialog_shadow(f, area, &app.theme);
-
-        f.render_widget(ratatui::widgets::Clear, area);
-        f.render_widget(list, area);
+        render_list_dialog(f, app, list);
     }
 
     fn render_filter_menu(&self, f: &mut Frame, app: &App) {
         use crate::app::FilterMode;
 
         // Filter options with descriptions
-        let filter_options = [
+        let mut filter_options = vec![
             ("Clear Filter", "Show all paths", FilterMode::None),
             (
                 "Dead Paths",
@@ -1418,6 +4450,16 @@ impl UI {
                 FilterMode::Valid,
             ),
         ];
+        for kind in PathKind::ALL {
+            let description = match kind {
+                PathKind::Local => "Plain directories on local drives",
+                PathKind::Unc => r"Network paths (\\server\share)",
+                PathKind::MappedDrive => "Drive letters mapped to network shares",
+                PathKind::Junction => "Junctions and symlinks",
+                PathKind::EnvVar => "Entries built from %VARIABLES%",
+            };
+            filter_options.push((kind.label(), description, FilterMode::Kind(kind)));
+        }
 
         let items: Vec<ListItem> = filter_options
             .iter()
@@ -1456,15 +4498,7 @@ impl UI {
                 .add_modifier(Modifier::BOLD),
         )];
         let list = List::new(items).block(create_floating_dialog_block(title, &app.theme));
-
-        // Auto-size: longest description is ~40 chars, 5 items × 2 lines each = 10 lines
-        let area = content_sized_rect(45, 11, f.area());
-
-        // Render shadow effect
-        render_dialog_shadow(f, area, &app.theme);
-
-        f.render_widget(ratatui::widgets::Clear, area);
-        f.render_widget(list, area);
+        render_list_dialog(f, app, list);
     }
 
     fn render_theme_selection(&self, f: &mut Frame, app: &App) {
@@ -1511,25 +4545,7 @@ impl UI {
                 .add_modifier(Modifier::BOLD),
         )];
         let list = List::new(items).block(create_floating_dialog_block(title, &app.theme));
-
-        // Auto-size based on number of themes and longest name
-        let num_themes = app.theme_list.len().min(15); // Max 15 visible themes
-        let max_name_len = app
-            .theme_list
-            .iter()
-            .map(|(name, _)| name.len())
-            .max()
-            .unwrap_or(20)
-            .min(40); // Cap at 40 chars
-
-        // Height: just the themes, no preview section
-        let area = content_sized_rect(max_name_len as u16 + 10, num_themes as u16 + 2, f.area());
-
-        // Render shadow effect
-        render_dialog_shadow(f, area, &app.theme);
-
-        f.render_widget(ratatui::widgets::Clear, area);
-        f.render_widget(list, area);
+        render_list_dialog(f, app, list);
     }
 
     fn get_status_color(&self, status: PathStatus, theme: &crate::theme::Theme) -> Color {
@@ -1539,12 +4555,23 @@ impl UI {
             PathStatus::Duplicate => theme.path_duplicate_fg,
             PathStatus::NonNormalized => theme.path_nonnormalized_fg,
             PathStatus::DeadDuplicate => theme.path_dead_fg,
+            PathStatus::Malformed => theme.path_malformed_fg,
+            PathStatus::OfflineDrive => theme.path_offline_fg,
+            PathStatus::Removable => theme.path_removable_fg,
+            PathStatus::Insecure => theme.path_insecure_fg,
+            PathStatus::Unchecked => theme.panel_normal_fg,
         }
     }
 }
 
 /// Helper function to render a shadow effect for floating dialogs
-fn render_dialog_shadow(f: &mut Frame, dialog_area: Rect, theme: &Theme) {
+fn render_dialog_shadow(f: &mut Frame, dialog_area: Rect, app: &App) {
+    // Reduced motion keeps dialogs to their plain border, the shadow smears in some recorders
+    if app.settings.reduced_motion {
+        return;
+    }
+    let theme = &app.theme;
+
     // Only render shadow if there's space (not at edges)
     if dialog_area.x + dialog_area.width < f.area().width
         && dialog_area.y + dialog_area.height < f.area().height
@@ -1579,39 +4606,436 @@ fn render_dialog_shadow(f: &mut Frame, dialog_area: Rect, theme: &Theme) {
     }
 }
 
-/// Helper function to create an enhanced dialog block with floating effect
-fn create_floating_dialog_block<'a>(title: Vec<Span<'a>>, theme: &Theme) -> Block<'a> {
-    Block::default()
-        .title(title)
-        .borders(Borders::ALL)
-        .border_style(
-            Style::default()
-                .fg(theme.dialog_border_fg)
-                .add_modifier(Modifier::BOLD),
-        )
-        .style(Style::default().fg(theme.dialog_fg).bg(theme.dialog_bg))
+/// Helper function to create an enhanced dialog block with floating effect
+fn create_floating_dialog_block<'a>(title: Vec<Span<'a>>, theme: &Theme) -> Block<'a> {
+    Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(
+            Style::default()
+                .fg(theme.dialog_border_fg)
+                .add_modifier(Modifier::BOLD),
+        )
+        .style(Style::default().fg(theme.dialog_fg).bg(theme.dialog_bg))
+}
+
+/// Scroll position of the open list dialog, as App::list_layout places its items
+fn list_state(app: &App, area: Rect) -> ListState {
+    let offset = app.list_layout(area).map_or(0, |layout| layout.offset);
+    ListState::default().with_offset(offset)
+}
+
+/// Backup list, filter menu and theme selection: sized to their items by App::list_layout
+fn render_list_dialog(f: &mut Frame, app: &App, list: List) {
+    let Some(layout) = app.list_layout(f.area()) else {
+        return;
+    };
+    render_dialog_shadow(f, layout.area, app);
+    f.render_widget(ratatui::widgets::Clear, layout.area);
+    let mut state = ListState::default().with_offset(layout.offset);
+    f.render_stateful_widget(list, layout.area, &mut state);
+}
+
+/// Helper function to create a rectangle sized to fit content with padding
+pub fn content_sized_rect(content_width: u16, content_height: u16, max_area: Rect) -> Rect {
+    // Add padding for borders and margins (2 for borders, 2 for internal padding)
+    let width = (content_width + 4).min(max_area.width);
+    let height = (content_height + 4).min(max_area.height);
+
+    // Center the rectangle
+    let x = max_area.x + (max_area.width.saturating_sub(width)) / 2;
+    let y = max_area.y + (max_area.height.saturating_sub(height)) / 2;
+
+    Rect {
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+/// Split pasted or typed text into lines, whatever line breaks it came with
+fn split_lines(text: &str) -> Vec<String> {
+    text.replace("\r\n", "\n")
+        .split(['\n', '\r'])
+        .map(str::to_string)
+        .collect()
+}
+
+/// Characters `from..to` of a line
+fn char_slice(line: &str, from: usize, to: usize) -> String {
+    line.chars()
+        .skip(from)
+        .take(to.saturating_sub(from))
+        .collect()
+}
+
+/// Multi-line text with a cursor and a selection, drawn wrapped by render_text_area
+/// For the editors that need more than the single-line input buffer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextArea {
+    pub lines: Vec<String>,
+    /// Cursor line
+    pub row: usize,
+    /// Cursor position in the line, in characters
+    pub col: usize,
+    /// Where the selection started, the cursor is its other end
+    pub anchor: Option<(usize, usize)>,
+}
+
+impl TextArea {
+    pub fn new(text: &str) -> Self {
+        Self {
+            lines: split_lines(text),
+            row: 0,
+            col: 0,
+            anchor: None,
+        }
+    }
+
+    /// Start and end of the selection in text order, None when nothing is selected
+    pub fn selection(&self) -> Option<((usize, usize), (usize, usize))> {
+        let anchor = self.anchor?;
+        let cursor = (self.row, self.col);
+        match anchor.cmp(&cursor) {
+            std::cmp::Ordering::Less => Some((anchor, cursor)),
+            std::cmp::Ordering::Greater => Some((cursor, anchor)),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    pub fn selected_text(&self) -> Option<String> {
+        let ((start_row, start_col), (end_row, end_col)) = self.selection()?;
+        if start_row == end_row {
+            return Some(char_slice(&self.lines[start_row], start_col, end_col));
+        }
+        let mut text = char_slice(&self.lines[start_row], start_col, usize::MAX);
+        for line in &self.lines[start_row + 1..end_row] {
+            text.push('\n');
+            text.push_str(line);
+        }
+        text.push('\n');
+        text.push_str(&char_slice(&self.lines[end_row], 0, end_col));
+        Some(text)
+    }
+
+    fn line_len(&self) -> usize {
+        self.lines[self.row].chars().count()
+    }
+
+    /// Byte offset of a character position
+    fn byte_at(&self, row: usize, col: usize) -> usize {
+        let line = &self.lines[row];
+        line.char_indices().nth(col).map_or(line.len(), |(i, _)| i)
+    }
+
+    /// Remove the selected text and put the cursor where it was, false if nothing was selected
+    fn delete_selection(&mut self) -> bool {
+        let Some((start, end)) = self.selection() else {
+            self.anchor = None;
+            return false;
+        };
+        let tail = self.lines[end.0][self.byte_at(end.0, end.1)..].to_string();
+        let head = self.byte_at(start.0, start.1);
+        self.lines[start.0].truncate(head);
+        self.lines[start.0].push_str(&tail);
+        self.lines.drain(start.0 + 1..=end.0);
+        (self.row, self.col) = start;
+        self.anchor = None;
+        true
+    }
+
+    /// Type or paste text over the selection, line breaks start new lines
+    pub fn insert_str(&mut self, text: &str) {
+        self.delete_selection();
+        let at = self.byte_at(self.row, self.col);
+        let rest = self.lines[self.row].split_off(at);
+        for (i, piece) in split_lines(text).iter().enumerate() {
+            if i > 0 {
+                self.row += 1;
+                self.lines.insert(self.row, String::new());
+                self.col = 0;
+            }
+            self.lines[self.row].push_str(piece);
+            self.col += piece.chars().count();
+        }
+        self.lines[self.row].push_str(&rest);
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.insert_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    pub fn newline(&mut self) {
+        self.insert_str("\n");
+    }
+
+    pub fn backspace(&mut self) {
+        if self.delete_selection() {
+            return;
+        }
+        if self.col > 0 {
+            self.col -= 1;
+            let at = self.byte_at(self.row, self.col);
+            self.lines[self.row].remove(at);
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.line_len();
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.delete_selection() {
+            return;
+        }
+        if self.col < self.line_len() {
+            let at = self.byte_at(self.row, self.col);
+            self.lines[self.row].remove(at);
+        } else if self.row + 1 < self.lines.len() {
+            let line = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    /// Before the cursor moves: keep or start the selection when selecting, drop it otherwise
+    fn start_move(&mut self, select: bool) {
+        if !select {
+            self.anchor = None;
+        } else if self.anchor.is_none() {
+            self.anchor = Some((self.row, self.col));
+        }
+    }
+
+    pub fn left(&mut self, select: bool) {
+        self.start_move(select);
+        if self.col > 0 {
+            self.col -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.line_len();
+        }
+    }
+
+    pub fn right(&mut self, select: bool) {
+        self.start_move(select);
+        if self.col < self.line_len() {
+            self.col += 1;
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    /// Move by lines, up for negative counts, keeping the column where the line allows
+    pub fn move_lines(&mut self, by: isize, select: bool) {
+        self.start_move(select);
+        self.row = self.row.saturating_add_signed(by).min(self.lines.len() - 1);
+        self.col = self.col.min(self.line_len());
+    }
+
+    pub fn home(&mut self, select: bool) {
+        self.start_move(select);
+        self.col = 0;
+    }
+
+    pub fn end(&mut self, select: bool) {
+        self.start_move(select);
+        self.col = self.line_len();
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = Some((0, 0));
+        self.row = self.lines.len() - 1;
+        self.col = self.line_len();
+    }
+
+    /// Editing keys, false for keys left to the dialog (Esc, Ctrl+S and the like)
+    /// Ctrl+C quits everywhere, so copy is Ctrl+Insert as in other console editors
+    pub fn handle_key(&mut self, key: KeyEvent, page: usize, clipboard: &mut String) -> bool {
+        let select = key.modifiers.contains(KeyModifiers::SHIFT);
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let page = page.max(1) as isize;
+        match key.code {
+            KeyCode::Char('a') if ctrl => self.select_all(),
+            KeyCode::Insert if ctrl => {
+                if let Some(text) = self.selected_text() {
+                    *clipboard = text;
+                }
+            }
+            KeyCode::Char('x') if ctrl => {
+                if let Some(text) = self.selected_text() {
+                    *clipboard = text;
+                    self.delete_selection();
+                }
+            }
+            KeyCode::Delete if select => {
+                if let Some(text) = self.selected_text() {
+                    *clipboard = text;
+                    self.delete_selection();
+                }
+            }
+            KeyCode::Char('v') if ctrl => self.insert_str(&clipboard.clone()),
+            KeyCode::Insert if select => self.insert_str(&clipboard.clone()),
+            // AltGr arrives as Ctrl+Alt, and types \ or | on many layouts
+            KeyCode::Char(c) if !ctrl || key.modifiers.contains(KeyModifiers::ALT) => {
+                self.insert(c)
+            }
+            KeyCode::Enter => self.newline(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left => self.left(select),
+            KeyCode::Right => self.right(select),
+            KeyCode::Up => self.move_lines(-1, select),
+            KeyCode::Down => self.move_lines(1, select),
+            KeyCode::PageUp => self.move_lines(-page, select),
+            KeyCode::PageDown => self.move_lines(page, select),
+            KeyCode::Home if ctrl => {
+                self.start_move(select);
+                (self.row, self.col) = (0, 0);
+            }
+            KeyCode::End if ctrl => {
+                self.move_lines(isize::MAX, select);
+                self.col = self.line_len();
+            }
+            KeyCode::Home => self.home(select),
+            KeyCode::End => self.end(select),
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Draw a TextArea wrapped at the width of the area, scrolled to keep the cursor in view
+fn render_text_area(f: &mut Frame, area: Rect, text: &TextArea, theme: &Theme) {
+    let width = area.width.max(1) as usize;
+    let height = area.height.max(1) as usize;
+    let style = Style::default().fg(theme.dialog_fg);
+    let selected_style = Style::default()
+        .fg(theme.panel_selected_fg)
+        .bg(theme.panel_selected_bg);
+    let selection = text.selection();
+
+    let mut rows: Vec<Line> = Vec::new();
+    let mut cursor_row = 0;
+    for (row, line) in text.lines.iter().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        // One cell past the text, where the cursor sits at the end of the line
+        for start in (0..=chars.len()).step_by(width) {
+            let end = (start + width).min(chars.len() + 1);
+            let mut spans: Vec<Span> = Vec::new();
+            let mut run = String::new();
+            let mut run_style = style;
+            for col in start..end {
+                let at_cursor = row == text.row && col == text.col;
+                if at_cursor {
+                    cursor_row = rows.len();
+                }
+                let Some(c) = chars.get(col).copied().or(at_cursor.then_some(' ')) else {
+                    continue;
+                };
+                let cell_style = if at_cursor {
+                    style.add_modifier(Modifier::REVERSED)
+                } else if selection.is_some_and(|(from, to)| (row, col) >= from && (row, col) < to)
+                {
+                    selected_style
+                } else {
+                    style
+                };
+                if cell_style != run_style && !run.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut run), run_style));
+                }
+                run_style = cell_style;
+                run.push(c);
+            }
+            if !run.is_empty() {
+                spans.push(Span::styled(run, run_style));
+            }
+            rows.push(Line::from(spans));
+        }
+    }
+    let top = cursor_row.saturating_sub(height - 1);
+    let visible: Vec<Line> = rows.into_iter().skip(top).take(height).collect();
+    f.render_widget(Paragraph::new(visible), area);
+}
+
+/// Between status bar segments
+const STATUS_SEPARATOR: &str = " │ ";
+
+/// A piece of the status bar, shortened or dropped by priority when the bar is too narrow
+struct StatusSegment {
+    text: String,
+    /// Shorter form tried before the segment is dropped
+    short: Option<String>,
+    /// Higher numbers are shortened and dropped first, 0 is never dropped, only cut short
+    priority: u8,
+    style: Style,
+}
+
+impl StatusSegment {
+    fn new(text: impl Into<String>, priority: u8, style: Style) -> Self {
+        Self {
+            text: text.into(),
+            short: None,
+            priority,
+            style,
+        }
+    }
+
+    fn or_short(mut self, short: impl Into<String>) -> Self {
+        self.short = Some(short.into());
+        self
+    }
 }
 
-/// Helper function to create a rectangle sized to fit content with padding
-fn content_sized_rect(content_width: u16, content_height: u16, max_area: Rect) -> Rect {
-    // Add padding for borders and margins (2 for borders, 2 for internal padding)
-    let width = (content_width + 4).min(max_area.width);
-    let height = (content_height + 4).min(max_area.height);
+/// Fit segments into `width` columns: shorten the least important first, then drop them,
+/// and cut the last one that has to stay with … if it still does not fit
+fn fit_status_segments(mut segments: Vec<StatusSegment>, width: usize) -> Vec<StatusSegment> {
+    let total = |segments: &[StatusSegment]| {
+        segments
+            .iter()
+            .map(|segment| segment.text.chars().count())
+            .sum::<usize>()
+            + STATUS_SEPARATOR.chars().count() * segments.len().saturating_sub(1)
+    };
+
+    let mut least_important: Vec<usize> = (0..segments.len()).collect();
+    least_important.sort_by_key(|&i| std::cmp::Reverse(segments[i].priority));
+    for i in least_important {
+        if total(&segments) <= width {
+            return segments;
+        }
+        if let Some(short) = segments[i].short.take() {
+            segments[i].text = short;
+        }
+    }
 
-    // Center the rectangle
-    let x = max_area.x + (max_area.width.saturating_sub(width)) / 2;
-    let y = max_area.y + (max_area.height.saturating_sub(height)) / 2;
+    while total(&segments) > width {
+        let droppable = segments
+            .iter()
+            .enumerate()
+            .filter(|(_, segment)| segment.priority > 0)
+            .max_by_key(|(_, segment)| segment.priority)
+            .map(|(i, _)| i);
+        match droppable {
+            Some(i) => {
+                segments.remove(i);
+            }
+            None => break,
+        }
+    }
 
-    Rect {
-        x,
-        y,
-        width,
-        height,
+    let over = total(&segments).saturating_sub(width);
+    if let Some(segment) = segments.last_mut().filter(|_| over > 0) {
+        let keep = segment.text.chars().count().saturating_sub(over + 1);
+        segment.text = segment.text.chars().take(keep).chain(['…']).collect();
     }
+    segments
 }
 
 /// Helper function to create a centered rectangle
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+pub(crate) fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -1634,15 +5058,14 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 impl UI {
     /// Render the menu bar (top row with menu names)
     fn render_menu_bar(&self, f: &mut Frame, area: Rect, app: &App) {
-        let menus = menu::get_menus(app.connection_mode);
+        let menus = app.menus();
         let mut spans = Vec::new();
 
         for (i, menu_item) in menus.iter().enumerate() {
             // Determine if this menu is active (only in Menu mode)
-            let is_active = if let Mode::Menu { active_menu, .. } = app.mode {
-                active_menu == i
-            } else {
-                false
+            let is_active = match app.mode {
+                Mode::Menu { active_menu, .. } | Mode::MenuBar { active_menu } => active_menu == i,
+                _ => false,
             };
 
             // Style based on whether menu is active
@@ -1698,7 +5121,7 @@ impl UI {
         f.render_widget(menu_bar, area);
     }
 
-    /// Render the drop-down menu overlay
+    /// Render the drop-down menu overlay, and the open submenu beside it
     fn render_menu_dropdown(
         &self,
         f: &mut Frame,
@@ -1706,72 +5129,31 @@ impl UI {
         active_menu: usize,
         selected_item: usize,
     ) {
-        let mut menus = menu::get_menus(app.connection_mode);
-
-        // Update enabled states based on app state
-        let has_marked = app.has_marked_items();
-        let has_marked_dead = app.has_marked_dead_paths();
-        let has_selection = match app.active_panel {
-            Panel::Machine => !app.machine_paths.is_empty(),
-            Panel::User => !app.user_paths.is_empty(),
-        };
-        let is_remote = app.connection_mode == crate::app::ConnectionMode::Remote;
-
-        menu::update_menu_enabled_states(
-            &mut menus,
-            app.is_admin,
-            has_marked,
-            has_marked_dead,
-            has_selection,
-            is_remote,
-            app.has_changes,
-        );
-
-        if active_menu >= menus.len() {
+        let menus = app.menus();
+        let Some(menu) = menus.get(active_menu) else {
             return;
-        }
-
-        let menu = &menus[active_menu];
+        };
 
-        // Calculate menu position (under the menu name in menu bar)
-        let mut x_offset = 1; // Start with 1 for initial space
-        for menu_item in menus.iter().take(active_menu) {
-            x_offset += menu_item.name.len() as u16 + 2; // name + 2 spaces
-        }
+        let area = menu::dropdown_area(&menus, active_menu, f.area().width);
+        self.render_menu_items(f, app, area, &menu.items, selected_item);
 
-        // Calculate menu width (longest item + padding)
-        let mut menu_width = menu.name.len();
-        for item in &menu.items {
-            let item_text_len =
-                item.label.len() + item.shortcut.as_ref().map(|s| s.len() + 2).unwrap_or(0);
-            menu_width = menu_width.max(item_text_len);
+        if let (Some(sub), Some(item)) = (app.menu_submenu, menu.items.get(selected_item)) {
+            let area = menu::submenu_area(&menus, active_menu, selected_item, f.area().width);
+            self.render_menu_items(f, app, area, &item.submenu, sub);
         }
-        menu_width += 4; // Add padding
-
-        let menu_height = menu.items.len() as u16 + 2; // +2 for borders
-
-        // Create menu area (positioned below menu bar)
-        let area = Rect {
-            x: x_offset,
-            y: 1, // Below menu bar
-            width: menu_width as u16,
-            height: menu_height,
-        };
-
-        // Ensure menu fits on screen
-        let terminal_width = f.area().width;
-        let area = if area.x + area.width > terminal_width {
-            Rect {
-                x: terminal_width.saturating_sub(area.width),
-                ..area
-            }
-        } else {
-            area
-        };
+    }
 
-        // Build menu items
-        let items: Vec<ListItem> = menu
-            .items
+    /// Draw a bordered list of menu items with their shortcuts right-aligned
+    fn render_menu_items(
+        &self,
+        f: &mut Frame,
+        app: &App,
+        area: Rect,
+        items: &[menu::MenuItem],
+        selected_item: usize,
+    ) {
+        let menu_width = area.width as usize;
+        let items: Vec<ListItem> = items
             .iter()
             .enumerate()
             .map(|(i, item)| {
@@ -1792,14 +5174,28 @@ impl UI {
                     app.theme.menu_active_bg
                 };
 
-                // Format: "Label          Shortcut"
+                // Format: "Label          Shortcut", with the accelerator underlined
                 let label = &item.label;
                 let shortcut = item.shortcut.as_deref().unwrap_or("");
-                let spacing =
-                    " ".repeat(menu_width.saturating_sub(label.len() + shortcut.len() + 4));
-                let text = format!(" {}{} {} ", label, spacing, shortcut);
+                let spacing = " ".repeat(
+                    menu_width.saturating_sub(label.chars().count() + shortcut.chars().count() + 4),
+                );
+                let style = Style::default().fg(fg).bg(bg);
+                let mut spans = vec![Span::raw(" ")];
+                match item.accelerator_range() {
+                    Some(range) => {
+                        spans.push(Span::raw(&label[..range.start]));
+                        spans.push(Span::styled(
+                            &label[range.clone()],
+                            style.add_modifier(Modifier::UNDERLINED),
+                        ));
+                        spans.push(Span::raw(&label[range.end..]));
+                    }
+                    None => spans.push(Span::raw(label.as_str())),
+                }
+                spans.push(Span::raw(format!("{} {} ", spacing, shortcut)));
 
-                ListItem::new(text).style(Style::default().fg(fg).bg(bg))
+                ListItem::new(Line::from(spans)).style(style)
             })
             .collect();
 
@@ -1830,3 +5226,1021 @@ impl UI {
         f.render_widget(menu_list, area);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::FilterMode;
+    use crate::filesystem::MemoryFileSystem;
+    use crate::live_env::LiveEnvironment;
+    use crate::raw_editor::RawEditor;
+    use crate::script::screen_text;
+    use chrono::{Local, TimeZone};
+    use ratatui::{backend::TestBackend, buffer::Buffer, Terminal};
+    use std::path::Path;
+
+    const WIDTH: u16 = 120;
+    const HEIGHT: u16 = 36;
+
+    // App with one entry of each common status, checked against a fake filesystem
+    // (no paths under Windows env vars, so normalization is the same on every machine)
+    fn sample_app() -> App {
+        let fs = MemoryFileSystem::new()
+            .with_dir(r"C:\Dev\Python")
+            .with_dir(r"C:\Dev\Go\bin")
+            .with_dir(r"C:\Tools\bin");
+        App::for_test(
+            vec![r"C:\Dev\Python".to_string(), r"C:\Dev\Go\bin".to_string()],
+            vec![
+                r"C:\Tools\bin".to_string(),
+                r"C:\Missing".to_string(),
+                r"c:\dev\python".to_string(),
+            ],
+            Box::new(fs),
+        )
+    }
+
+    fn render(app: &App) -> Buffer {
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        terminal.draw(|f| UI::new().render(f, app)).unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    /// Compare the rendered screen with tests/snapshots/<name>.txt
+    /// Run with UPDATE_SNAPSHOTS=1 to accept intended UI changes
+    fn assert_snapshot(name: &str, app: &App) {
+        let actual = screen_text(&render(app));
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("snapshots")
+            .join(format!("{}.txt", name));
+
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, &actual).unwrap();
+            return;
+        }
+
+        let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+            panic!(
+                "Missing snapshot {}, run with UPDATE_SNAPSHOTS=1 to create it",
+                path.display()
+            )
+        });
+        assert!(
+            actual == expected.replace("\r\n", "\n"),
+            "Snapshot '{}' changed (rerun with UPDATE_SNAPSHOTS=1 if intended)\n--- expected\n{}\n--- actual\n{}",
+            name,
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_snapshot_main_screen() {
+        assert_snapshot("main_screen", &sample_app());
+    }
+
+    #[test]
+    fn test_snapshot_main_screen_filtered() {
+        let mut app = sample_app();
+        app.filter_mode = FilterMode::Dead;
+        assert_snapshot("main_screen_filtered", &app);
+    }
+
+    #[test]
+    fn test_snapshot_main_screen_panel_filter() {
+        let mut app = sample_app();
+        app.filter_mode = FilterMode::Duplicates;
+        app.filter_panel = Some(Panel::User);
+        assert_snapshot("main_screen_panel_filter", &app);
+    }
+
+    #[test]
+    fn test_snapshot_main_screen_profile() {
+        let mut app = sample_app();
+        app.profile =
+            crate::profile::Profile::resolve("helpdesk", &crate::config::Settings::default())
+                .unwrap();
+        assert_snapshot("main_screen_profile", &app);
+    }
+
+    #[test]
+    fn test_snapshot_main_screen_no_fs_checks() {
+        let app = App::for_test(
+            vec![r"C:\Dev\Python".to_string(), r"C:\Dev\Go\bin".to_string()],
+            vec![
+                r"C:\Tools\bin".to_string(),
+                r"C:\Missing".to_string(),
+                r"c:\dev\python".to_string(),
+            ],
+            Box::new(crate::filesystem::UncheckedFileSystem),
+        );
+        assert_snapshot("main_screen_no_fs_checks", &app);
+    }
+
+    #[test]
+    fn test_snapshot_help() {
+        let mut app = sample_app();
+        app.mode = Mode::Help;
+        assert_snapshot("help", &app);
+    }
+
+    #[test]
+    fn test_snapshot_confirm_exit() {
+        let mut app = sample_app();
+        app.has_changes = true;
+        app.mode = Mode::Confirm(ConfirmAction::Exit);
+        assert_snapshot("confirm_exit", &app);
+    }
+
+    #[test]
+    fn test_reduced_motion_drops_dialog_shadow() {
+        let mut app = sample_app();
+        app.mode = Mode::Confirm(ConfirmAction::Exit);
+        let area = centered_rect(40, 20, Rect::new(0, 0, WIDTH, HEIGHT));
+        let shadow_fg = |app: &App| render(app)[(area.right(), area.y + 1)].fg;
+
+        assert_eq!(shadow_fg(&app), Color::DarkGray);
+        app.settings.reduced_motion = true;
+        assert_ne!(shadow_fg(&app), Color::DarkGray);
+    }
+
+    #[test]
+    fn test_snapshot_path_details() {
+        let mut app = sample_app();
+        app.user_selected = 1;
+        app.mode = Mode::PathDetails;
+        let written = Local.with_ymd_and_hms(2026, 10, 14, 9, 12, 3).unwrap();
+        app.last_written.insert(PathScope::User, written);
+        app.last_written.insert(PathScope::Machine, written);
+        assert_snapshot("path_details", &app);
+    }
+
+    #[test]
+    fn test_snapshot_filter_menu() {
+        let mut app = sample_app();
+        app.mode = Mode::FilterMenu;
+        assert_snapshot("filter_menu", &app);
+    }
+
+    #[test]
+    fn test_snapshot_menu_dropdown() {
+        let mut app = sample_app();
+        app.mode = Mode::Menu {
+            active_menu: 0,
+            selected_item: 0,
+        };
+        assert_snapshot("menu_dropdown", &app);
+    }
+
+    #[test]
+    fn test_snapshot_menu_submenu() {
+        let mut app = sample_app();
+        app.settings.recent_remotes = vec!["server01".to_string(), "build-agent".to_string()];
+        app.mode = Mode::Menu {
+            active_menu: 0,
+            selected_item: 2,
+        };
+        app.menu_submenu = Some(1);
+        assert_snapshot("menu_submenu", &app);
+    }
+
+    #[test]
+    fn test_snapshot_live_environment() {
+        let mut app = sample_app();
+        app.mode = Mode::LiveEnvironment;
+        app.live_environment = LiveEnvironment {
+            process: vec![
+                r"c:\dev\python".to_string(),
+                r"C:\Tools\bin\".to_string(),
+                r"C:\Program Files\Terminal".to_string(),
+            ],
+            volatile: None,
+        };
+        assert_snapshot("live_environment", &app);
+    }
+
+    #[test]
+    fn test_snapshot_size_advisory() {
+        let mut app = sample_app();
+        for i in 0..100 {
+            app.user_paths
+                .push(format!(r"C:\Program Files\Vendor\Tool{:03}\bin", i));
+        }
+        app.has_changes = true;
+        app.size_advisory = app.check_path_sizes();
+        app.mode = Mode::Confirm(ConfirmAction::SizeAdvisory);
+        assert_snapshot("size_advisory", &app);
+    }
+
+    #[test]
+    fn test_snapshot_path_precedence() {
+        let mut app = sample_app();
+        app.mode = Mode::PathPrecedence;
+        assert_snapshot("path_precedence", &app);
+    }
+
+    #[test]
+    fn test_snapshot_process_restart() {
+        let mut app = sample_app();
+        let process =
+            |name: &str, pid, has_window, is_service| crate::process_detector::ProcessInfo {
+                name: name.to_string(),
+                pid,
+                started: Some(Local.with_ymd_and_hms(2026, 10, 14, 9, 12, 3).unwrap()),
+                has_window,
+                is_service,
+            };
+        app.processes_to_restart = vec![
+            process("Code.exe", 4120, true, false),
+            process("cmd.exe", 812, false, true),
+            process("pwsh.exe", 9300, false, false),
+        ];
+        app.process_selected = 1;
+        app.mode = Mode::ProcessRestartInfo;
+        assert_snapshot("process_restart", &app);
+    }
+
+    #[test]
+    fn test_snapshot_confirm_delete_all_dead() {
+        let mut app = sample_app();
+        app.pending_bulk_delete = Some(crate::app::BulkDelete {
+            action: ConfirmAction::DeleteAllDead,
+            machine: vec![],
+            user: vec![(1, r"C:\Missing".to_string())],
+        });
+        app.mode = Mode::Confirm(ConfirmAction::DeleteAllDead);
+        assert_snapshot("confirm_delete_all_dead", &app);
+    }
+
+    #[test]
+    fn test_snapshot_bulk_review() {
+        let mut app = sample_app();
+        app.pending_bulk_delete = Some(crate::app::BulkDelete {
+            action: ConfirmAction::DeleteSelected,
+            machine: vec![(1, r"C:\Dev\Go\bin".to_string())],
+            user: vec![
+                (0, r"C:\Tools\bin".to_string()),
+                (1, r"C:\Missing".to_string()),
+            ],
+        });
+        app.review_checked = vec![true, false, true];
+        app.review_selected = 1;
+        app.delete_owners = vec![(r"C:\Dev\Go\bin".to_string(), "Go 1.22.4".to_string())];
+        app.mode = Mode::BulkReview;
+        assert_snapshot("bulk_review", &app);
+    }
+
+    #[test]
+    fn test_snapshot_confirm_install_update() {
+        let mut app = sample_app();
+        app.update = Some(crate::updates::Update {
+            version: "9.0.0".to_string(),
+            page_url: "https://github.com/jesse-slaton/cli-tools/releases/tag/v9.0.0".to_string(),
+            executable_url: "https://example.com/pc.exe".to_string(),
+            checksum_url: Some("https://example.com/pc.exe.sha256".to_string()),
+        });
+        app.mode = Mode::Confirm(ConfirmAction::InstallUpdate);
+        assert_snapshot("confirm_install_update", &app);
+    }
+
+    #[test]
+    fn test_snapshot_confirm_use_subdirectory() {
+        let mut app = sample_app();
+        app.pending_subdirectory = Some((
+            r"C:\Program Files\SomeTool".to_string(),
+            r"C:\Program Files\SomeTool\bin".to_string(),
+        ));
+        app.mode = Mode::Confirm(ConfirmAction::UseSubdirectory);
+        assert_snapshot("confirm_use_subdirectory", &app);
+    }
+
+    #[test]
+    fn test_snapshot_confirm_revert_last_apply() {
+        let mut app = sample_app();
+        app.pending_revert = Some(crate::backup::PathBackup {
+            timestamp: "2026-03-10T14:05:00+00:00".to_string(),
+            user_path: String::new(),
+            machine_path: String::new(),
+            user_paths: vec![r"C:\Users\alice\bin".to_string()],
+            machine_paths: vec![
+                r"C:\Windows".to_string(),
+                r"C:\Windows\System32".to_string(),
+            ],
+        });
+        app.mode = Mode::Confirm(ConfirmAction::RevertLastApply);
+        assert_snapshot("confirm_revert_last_apply", &app);
+    }
+
+    #[test]
+    fn test_snapshot_whats_new() {
+        let mut app = sample_app();
+        // Fixed notes, the embedded changelog changes with every release
+        app.releases = release_notes::parse(
+            "## [Unreleased]\n\n### Added\n- Next feature\n\n\
+             ## [0.6.2] - 2025-01-17\n\n### Fixed\n- **Crash** on empty PATH\n  - Shown on startup\n\n\
+             ### Changed\n- Faster scans\n",
+        );
+        app.release_selected = 1;
+        app.mode = Mode::WhatsNew;
+        assert_snapshot("whats_new", &app);
+    }
+
+    #[test]
+    fn test_snapshot_services_at_risk() {
+        let mut app = sample_app();
+        app.services_at_risk = vec![crate::services::ServiceAtRisk {
+            service: crate::services::Service {
+                name: "AcmeAgent".to_string(),
+                display_name: "Acme Update Agent".to_string(),
+                executable: "C:\\Program Files\\Acme\\bin\\agent.exe".to_string(),
+            },
+            entry: "C:\\Program Files\\Acme\\bin".to_string(),
+        }];
+        app.mode = Mode::ProcessRestartInfo;
+        assert_snapshot("services_at_risk", &app);
+    }
+
+    #[test]
+    fn test_snapshot_path_lengths() {
+        let mut app = sample_app();
+        app.settings.length_limit = Some(30);
+        app.mode = Mode::PathLengths;
+        assert_snapshot("path_lengths", &app);
+    }
+
+    /// Whether the row showing `text` is drawn with the dimmed counterpart highlight
+    fn row_is_dimmed(buffer: &Buffer, text: &str) -> bool {
+        let screen = screen_text(buffer);
+        let (y, line) = screen
+            .lines()
+            .enumerate()
+            .find(|(_, line)| line.contains(text))
+            .unwrap();
+        let x = line[..line.find(text).unwrap()].chars().count();
+        buffer[(x as u16, y as u16)]
+            .modifier
+            .contains(Modifier::DIM)
+    }
+
+    #[test]
+    fn test_counterpart_highlight_follows_selection() {
+        let mut app = sample_app();
+        app.active_panel = Panel::Machine;
+        app.machine_selected = 0;
+        assert!(row_is_dimmed(&render(&app), r"c:\dev\python"));
+
+        app.machine_selected = 1;
+        assert!(!row_is_dimmed(&render(&app), r"c:\dev\python"));
+    }
+
+    #[test]
+    fn test_snapshot_apply_changes() {
+        let mut app = sample_app();
+        app.is_admin = false;
+        // Pretend both scopes were edited since loading
+        app.user_original.pop();
+        app.machine_original.pop();
+        app.has_changes = true;
+        app.apply_user = true;
+        app.apply_machine = false;
+        app.mode = Mode::Confirm(ConfirmAction::ApplyChanges);
+        assert_snapshot("apply_changes", &app);
+    }
+
+    #[test]
+    fn test_snapshot_history() {
+        use crate::history::{HistoryEntry, ScopeChange};
+        use crate::registry::PathScope;
+
+        let mut app = sample_app();
+        let change = |scope, before: &[&str], after: &[&str]| {
+            let before: Vec<String> = before.iter().map(|s| s.to_string()).collect();
+            let after: Vec<String> = after.iter().map(|s| s.to_string()).collect();
+            ScopeChange::between(scope, &before, &after).unwrap()
+        };
+        app.history = vec![
+            HistoryEntry {
+                timestamp: "2026-03-10T14:05:00+00:00".to_string(),
+                computer: "SERVER01".to_string(),
+                changes: vec![change(
+                    PathScope::Machine,
+                    &[r"C:\Old", r"C:\A", r"C:\B"],
+                    &[r"C:\B", r"C:\A"],
+                )],
+            },
+            HistoryEntry {
+                timestamp: "2026-03-03T09:30:00+00:00".to_string(),
+                computer: "WS01".to_string(),
+                changes: vec![
+                    change(PathScope::User, &[], &[r"C:\Tools\bin"]),
+                    change(PathScope::Machine, &[], &[r"C:\Dev\Go\bin"]),
+                ],
+            },
+        ];
+        app.mode = Mode::History;
+        assert_snapshot("history", &app);
+    }
+
+    #[test]
+    fn test_snapshot_add_path() {
+        let mut app = sample_app();
+        app.input_buffer = r"C:\Tools\new".to_string();
+        app.add_position = crate::config::AddPosition::BeforeSelection;
+        app.mode = Mode::Input(crate::app::InputMode::AddPath);
+        assert_snapshot("add_path", &app);
+    }
+
+    #[test]
+    fn test_snapshot_add_path_duplicate() {
+        let mut app = sample_app();
+        app.input_buffer = r"c:\tools\bin\".to_string();
+        app.mode = Mode::Input(crate::app::InputMode::AddPath);
+        assert_snapshot("add_path_duplicate", &app);
+    }
+
+    #[test]
+    fn test_snapshot_change_report() {
+        let mut app = sample_app();
+        app.input_buffer = r"\\fileserver\changes".to_string();
+        app.mode = Mode::Input(crate::app::InputMode::ChangeReport);
+        assert_snapshot("change_report", &app);
+    }
+
+    #[test]
+    fn test_snapshot_ignore_list() {
+        let mut app = sample_app();
+        app.settings.ignored = vec![r"C:\Missing".to_string(), r"C:\agent\_work\*".to_string()];
+        app.user_info[1].is_ignored = true;
+        app.ignore_selected = 1;
+        app.mode = Mode::IgnoreList;
+        assert_snapshot("ignore_list", &app);
+    }
+
+    #[test]
+    fn test_snapshot_raw_editor() {
+        let mut app = sample_app();
+        app.active_panel = Panel::User;
+        let mut editor = RawEditor::new(&app.user_paths);
+        editor.text.move_lines(1, false);
+        editor.text.end(false);
+        editor.text.insert_str("\nD:\\New|bin");
+        app.raw_editor = Some(editor);
+        app.raw_editor_panel = Panel::User;
+        app.mode = Mode::RawEditor;
+        assert_snapshot("raw_editor", &app);
+    }
+
+    #[test]
+    fn test_snapshot_remote_connecting() {
+        let mut app = sample_app();
+        app.settings.reduced_motion = true;
+        app.remote_task = Some(crate::task::BackgroundTask::spawn(
+            "Connecting to SRV01",
+            || Err(anyhow::anyhow!("unreachable")),
+        ));
+        app.connecting_to = Some("SRV01".to_string());
+        assert_snapshot("remote_connecting", &app);
+    }
+
+    #[test]
+    fn test_snapshot_retry_remote() {
+        let mut app = sample_app();
+        app.failed_remote = Some((
+            "SRV01".to_string(),
+            "The network path was not found.".to_string(),
+        ));
+        app.mode = Mode::Confirm(ConfirmAction::RetryRemote);
+        assert_snapshot("retry_remote", &app);
+    }
+
+    #[test]
+    fn test_snapshot_start_remote_registry() {
+        let mut app = sample_app();
+        app.failed_remote = Some((
+            "SRV01".to_string(),
+            "The RemoteRegistry service is stopped on SRV01. It can be started from here"
+                .to_string(),
+        ));
+        app.mode = Mode::Confirm(ConfirmAction::StartRemoteRegistry);
+        assert_snapshot("start_remote_registry", &app);
+    }
+
+    #[test]
+    fn test_snapshot_remote_write_queued() {
+        let mut app = sample_app();
+        let mut queued = crate::remote_queue::QueuedWrite::new(
+            "SRV01",
+            vec![],
+            vec![r"C:\Tools".to_string()],
+            "The network path was not found.".to_string(),
+            std::time::Instant::now(),
+        );
+        // A countdown would change between runs
+        queued.next_retry = None;
+        app.queued_remote_write = Some(queued);
+        app.mode = Mode::Confirm(ConfirmAction::RemoteWriteQueued);
+        assert_snapshot("remote_write_queued", &app);
+    }
+
+    #[test]
+    fn test_snapshot_host_picker() {
+        use crate::hosts::Host;
+        let mut app = sample_app();
+        app.host_list = vec![
+            Host {
+                name: "Build agent".to_string(),
+                credential: r"CORP\svc-build".to_string(),
+                notes: "Restart the agent after PATH changes".to_string(),
+                last_connected: Some("2026-10-15 14:30".to_string()),
+                last_health: Some("38 entries, 2 dead".to_string()),
+                ..Host::new("build-01")
+            },
+            Host::new("SRV01"),
+        ];
+        app.host_selected = 1;
+        app.mode = Mode::HostPicker;
+        assert_snapshot("host_picker", &app);
+    }
+
+    #[test]
+    fn test_snapshot_quick_actions() {
+        let mut app = sample_app();
+        app.settings.quick_actions = true;
+        app.user_selected = 2;
+        assert_snapshot("quick_actions", &app);
+    }
+
+    #[test]
+    fn test_snapshot_security_report() {
+        use crate::registry::PathScope;
+        use crate::security_scan::{Finding, ScanReport};
+
+        let mut app = sample_app();
+        app.security_report = Some(ScanReport {
+            scanned: 6,
+            findings: vec![
+                Finding {
+                    scope: PathScope::Machine,
+                    index: 2,
+                    path: r"C:\Python27".to_string(),
+                    writable_by: vec!["Authenticated Users".to_string()],
+                    unsigned: vec!["python.exe".to_string(), "pythonw.exe".to_string()],
+                },
+                Finding {
+                    scope: PathScope::User,
+                    index: 0,
+                    path: r"C:\Users\Public\bin".to_string(),
+                    writable_by: vec!["Everyone".to_string(), "Users".to_string()],
+                    unsigned: vec![],
+                },
+            ],
+            errors: vec![r"D:\Share: Failed to read permissions".to_string()],
+            signatures_checked: true,
+        });
+        app.mode = Mode::SecurityReport;
+        assert_snapshot("security_report", &app);
+    }
+
+    #[test]
+    fn test_snapshot_toolchains() {
+        use crate::registry::PathScope;
+        use crate::toolchains::{Install, Toolchain};
+
+        let install = |scope, index, path: &str, version: Option<&str>| Install {
+            scope,
+            index,
+            path: path.to_string(),
+            version: version.map(str::to_string),
+        };
+        let mut app = sample_app();
+        app.toolchains = vec![
+            Toolchain {
+                label: "Java",
+                installs: vec![
+                    install(
+                        PathScope::Machine,
+                        0,
+                        r"C:\Program Files\Java\jdk-17\bin",
+                        Some("17.0.8"),
+                    ),
+                    install(PathScope::User, 1, r"C:\jdk8\bin", Some("1.8.0_392")),
+                ],
+            },
+            Toolchain {
+                label: "Git",
+                installs: vec![install(
+                    PathScope::Machine,
+                    1,
+                    r"C:\Program Files\Git\cmd",
+                    None,
+                )],
+            },
+        ];
+        app.mode = Mode::Toolchains;
+        assert_snapshot("toolchains", &app);
+    }
+
+    #[test]
+    fn test_snapshot_where_result() {
+        use crate::command_lookup::{Lookup, Match};
+        use crate::registry::PathScope;
+
+        let found = |scope, index, file: &str| Match {
+            scope,
+            index,
+            file: file.to_string(),
+        };
+        let mut app = sample_app();
+        app.where_lookup = Lookup {
+            name: "python".to_string(),
+            edited: vec![
+                found(PathScope::User, 0, r"C:\Python312\python.exe"),
+                found(
+                    PathScope::User,
+                    2,
+                    r"C:\Users\alice\AppData\Local\Microsoft\WindowsApps\python.exe",
+                ),
+            ],
+            applied: vec![found(
+                PathScope::User,
+                1,
+                r"C:\Users\alice\AppData\Local\Microsoft\WindowsApps\python.exe",
+            )],
+        };
+        app.mode = Mode::WhereResult;
+        assert_snapshot("where_result", &app);
+    }
+
+    #[test]
+    fn test_snapshot_shim_report() {
+        use crate::registry::PathScope;
+        use crate::shims::{Conflict, ShimFinding, ShimManager};
+
+        let mut app = sample_app();
+        app.shim_findings = vec![
+            ShimFinding {
+                manager: ShimManager::Chocolatey,
+                entry: None,
+                dir: r"C:\ProgramData\chocolatey\bin".to_string(),
+                shadowed_by: vec![],
+                shadows: vec![],
+            },
+            ShimFinding {
+                manager: ShimManager::Scoop,
+                entry: Some((
+                    PathScope::User,
+                    2,
+                    r"C:\Users\alice\scoop\shims".to_string(),
+                )),
+                dir: r"C:\Users\alice\scoop\shims".to_string(),
+                shadowed_by: vec![
+                    Conflict {
+                        scope: PathScope::Machine,
+                        index: 1,
+                        path: r"C:\Program Files\Git\cmd".to_string(),
+                        commands: vec!["git".to_string()],
+                    },
+                    Conflict {
+                        scope: PathScope::User,
+                        index: 0,
+                        path: r"C:\Tools\node".to_string(),
+                        commands: vec!["node".to_string(), "npm".to_string()],
+                    },
+                ],
+                shadows: vec![],
+            },
+            ShimFinding {
+                manager: ShimManager::WindowsApps,
+                entry: Some((
+                    PathScope::User,
+                    3,
+                    r"%LOCALAPPDATA%\Microsoft\WindowsApps".to_string(),
+                )),
+                dir: r"C:\Users\alice\AppData\Local\Microsoft\WindowsApps".to_string(),
+                shadowed_by: vec![],
+                shadows: vec![Conflict {
+                    scope: PathScope::User,
+                    index: 4,
+                    path: r"C:\Python312".to_string(),
+                    commands: vec!["python".to_string()],
+                }],
+            },
+            ShimFinding {
+                manager: ShimManager::NvmWindows,
+                entry: Some((PathScope::Machine, 3, r"C:\nvm4w\nodejs".to_string())),
+                dir: r"C:\nvm4w\nodejs".to_string(),
+                shadowed_by: vec![Conflict {
+                    scope: PathScope::Machine,
+                    index: 0,
+                    path: r"C:\Program Files\nodejs-18".to_string(),
+                    commands: vec!["node".to_string(), "npm".to_string()],
+                }],
+                shadows: vec![],
+            },
+        ];
+        app.mode = Mode::ShimReport;
+        assert_snapshot("shim_report", &app);
+    }
+
+    #[test]
+    fn test_snapshot_merge_duplicates() {
+        let mut app = sample_app();
+        app.merge_pairs = app.cross_scope_duplicates();
+        app.merge_pairs[0].keep = MergeKeep::User;
+        app.mode = Mode::MergeDuplicates;
+        assert_snapshot("merge_duplicates", &app);
+    }
+
+    #[test]
+    fn test_snapshot_nested_entries() {
+        use crate::app::NestedPair;
+        use crate::registry::PathScope;
+
+        let fs = MemoryFileSystem::new()
+            .with_dir(r"C:\Tools")
+            .with_dir(r"C:\Tools\bin")
+            .with_dir(r"C:\Dev\Go\bin");
+        let mut app = App::for_test(
+            vec![r"C:\Tools".to_string(), r"C:\Dev\Go\bin".to_string()],
+            vec![
+                r"C:\Tools\bin".to_string(),
+                r"C:\Dev\Go\bin\x64".to_string(),
+            ],
+            Box::new(fs),
+        );
+        app.nested_pairs = vec![
+            NestedPair {
+                parent: (PathScope::Machine, 0),
+                child: (PathScope::User, 0),
+                parent_has_commands: false,
+                child_has_commands: true,
+                fix: NestedFix::RemoveParent,
+            },
+            NestedPair {
+                parent: (PathScope::Machine, 1),
+                child: (PathScope::User, 1),
+                parent_has_commands: true,
+                child_has_commands: true,
+                fix: NestedFix::KeepBoth,
+            },
+        ];
+        app.nested_selected = 1;
+        app.mode = Mode::NestedEntries;
+        assert_snapshot("nested_entries", &app);
+    }
+
+    #[test]
+    fn test_snapshot_aging_report() {
+        use crate::app::AgingRow;
+
+        let mut app = sample_app();
+        let days_ago = |days: u64| {
+            std::time::SystemTime::now() - std::time::Duration::from_secs(days * 24 * 60 * 60)
+        };
+        app.aging_rows = vec![
+            AgingRow {
+                panel: Panel::Machine,
+                index: 1,
+                modified: days_ago(1000),
+            },
+            AgingRow {
+                panel: Panel::User,
+                index: 0,
+                modified: days_ago(400),
+            },
+            AgingRow {
+                panel: Panel::Machine,
+                index: 0,
+                modified: days_ago(20),
+            },
+        ];
+        app.machine_marked.insert(1);
+        app.aging_selected = 1;
+        app.mode = Mode::AgingReport;
+        assert_snapshot("aging_report", &app);
+    }
+
+    #[test]
+    fn test_snapshot_usage_suggestions() {
+        let mut app = sample_app();
+        app.usage_days = 30;
+        app.usage_rows = vec![
+            crate::app::UsageRow {
+                panel: Panel::User,
+                suggestion: usage::Suggestion {
+                    index: 2,
+                    runs: 48,
+                    action: usage::Move::Promote { to: 1 },
+                },
+                include: true,
+            },
+            crate::app::UsageRow {
+                panel: Panel::User,
+                suggestion: usage::Suggestion {
+                    index: 1,
+                    runs: 0,
+                    action: usage::Move::Demote,
+                },
+                include: false,
+            },
+        ];
+        app.usage_selected = 1;
+        app.mode = Mode::UsageSuggestions;
+        assert_snapshot("usage_suggestions", &app);
+    }
+
+    #[test]
+    fn test_snapshot_batch_replace() {
+        use crate::app::ReplaceRow;
+        use crate::registry::PathScope;
+
+        let row = |scope, index, before: &str, after: &str, exists| ReplaceRow {
+            scope,
+            index,
+            before: before.to_string(),
+            after: after.to_string(),
+            exists,
+            include: exists,
+        };
+        let mut app = sample_app();
+        app.replace_find = r"C:\Tools".to_string();
+        app.replace_with = r"E:\Tools".to_string();
+        app.replace_rows = vec![
+            row(
+                PathScope::Machine,
+                1,
+                r"C:\Tools\bin",
+                r"E:\Tools\bin",
+                true,
+            ),
+            row(
+                PathScope::User,
+                0,
+                r"C:\Tools\go\bin",
+                r"E:\Tools\go\bin",
+                false,
+            ),
+        ];
+        app.mode = Mode::BatchReplace;
+        assert_snapshot("batch_replace", &app);
+    }
+
+    #[test]
+    fn test_snapshot_drive_migration() {
+        use crate::app::ReplaceRow;
+        use crate::registry::PathScope;
+
+        let row = |scope, index, before: &str, after: &str, exists| ReplaceRow {
+            scope,
+            index,
+            before: before.to_string(),
+            after: after.to_string(),
+            exists,
+            include: exists,
+        };
+        let mut app = sample_app();
+        app.replace_find = r"D:\".to_string();
+        app.replace_with = r"E:\".to_string();
+        app.replace_migration = true;
+        app.replace_rows = vec![
+            row(
+                PathScope::Machine,
+                0,
+                r"D:\Tools\bin",
+                r"E:\Tools\bin",
+                true,
+            ),
+            row(PathScope::User, 2, r"D:\Go\bin", r"E:\Go\bin", false),
+        ];
+        app.mode = Mode::BatchReplace;
+        assert_snapshot("drive_migration", &app);
+    }
+
+    #[test]
+    fn test_snapshot_locked_session() {
+        let mut app = sample_app();
+        app.is_admin = true;
+        app.settings.lock_after = Some(std::time::Duration::from_secs(600));
+        app.mode = Mode::Locked;
+        assert_snapshot("locked_session", &app);
+    }
+
+    #[test]
+    fn test_too_small_placeholder() {
+        let app = sample_app();
+        let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+        terminal.draw(|f| UI::new().render(f, &app)).unwrap();
+        let screen = screen_text(terminal.backend().buffer());
+        assert!(screen.contains("Terminal too small"));
+        assert!(screen.contains("need 80x24, now 60x20"));
+        assert!(!screen.contains("MACHINE"));
+    }
+
+    #[test]
+    fn test_locked_entries_show_padlock() {
+        let mut app = sample_app();
+        app.policy = crate::policy::Policy {
+            locked: vec![r"C:\Dev".to_string()],
+            denied: vec![],
+        };
+        // The padlock is two cells wide, the second one renders as a space
+        let screen = screen_text(&render(&app)).replace("🔒  ", "🔒 ");
+
+        // Matched case-insensitively below the prefix, in both panels
+        for entry in [r"C:\Dev\Python", r"C:\Dev\Go\bin", r"c:\dev\python"] {
+            assert!(
+                screen.contains(&format!("🔒 {}", entry)),
+                "{} not locked",
+                entry
+            );
+        }
+        assert_eq!(screen.matches('🔒').count(), 3);
+    }
+
+    #[test]
+    fn test_text_area_selection_and_clipboard() {
+        let mut text = TextArea::new("C:\\Tools\r\nD:\\Go");
+        assert_eq!(text.lines, [r"C:\Tools", r"D:\Go"]);
+        let mut clipboard = String::new();
+        let mut key = |text: &mut TextArea, code, modifiers| {
+            text.handle_key(KeyEvent::new(code, modifiers), 10, &mut clipboard)
+        };
+
+        // Shift+Right twice from the end of the first line selects the line break and "D"
+        key(&mut text, KeyCode::End, KeyModifiers::NONE);
+        key(&mut text, KeyCode::Right, KeyModifiers::SHIFT);
+        key(&mut text, KeyCode::Right, KeyModifiers::SHIFT);
+        assert_eq!(text.selected_text().unwrap(), "\nD");
+        key(&mut text, KeyCode::Char('x'), KeyModifiers::CONTROL);
+        assert_eq!(text.lines, [r"C:\Tools:\Go"]);
+
+        // Paste puts the line break back, typing replaces a selection
+        key(&mut text, KeyCode::Char('v'), KeyModifiers::CONTROL);
+        assert_eq!(text.lines, [r"C:\Tools", r"D:\Go"]);
+        key(&mut text, KeyCode::Home, KeyModifiers::SHIFT);
+        key(&mut text, KeyCode::Char('E'), KeyModifiers::SHIFT);
+        assert_eq!(text.lines, [r"C:\Tools", r"E:\Go"]);
+
+        // Ctrl+Insert copies without changing the text, Esc is left to the dialog
+        key(&mut text, KeyCode::Char('a'), KeyModifiers::CONTROL);
+        key(&mut text, KeyCode::Insert, KeyModifiers::CONTROL);
+        assert!(!key(&mut text, KeyCode::Esc, KeyModifiers::NONE));
+        assert_eq!(clipboard, "C:\\Tools\nE:\\Go");
+        text.backspace();
+        assert_eq!(text.lines, [""]);
+    }
+
+    #[test]
+    fn test_text_area_wraps_at_the_width() {
+        let mut text = TextArea::new("0123456789AB\nshort");
+        text.move_lines(1, false);
+        text.end(false);
+        let theme = Theme::default();
+        let mut terminal = Terminal::new(TestBackend::new(5, 3)).unwrap();
+        terminal
+            .draw(|f| render_text_area(f, f.area(), &text, &theme))
+            .unwrap();
+        // Scrolled to the cursor after "short", which needs a row of its own
+        assert_eq!(screen_text(terminal.backend().buffer()), "AB\nshort\n\n");
+    }
+
+    #[test]
+    fn test_status_segments_shorten_then_drop_by_priority() {
+        let segments = || {
+            vec![
+                StatusSegment::new("USER (read-only)", 1, Style::default()).or_short("USER"),
+                StatusSegment::new("3 marked", 2, Style::default()).or_short("3✓"),
+                StatusSegment::new("MACHINE: 120/2047", 3, Style::default()).or_short("M:120"),
+                StatusSegment::new("Copied 3 path(s)", 0, Style::default()),
+            ]
+        };
+        let texts = |width| {
+            fit_status_segments(segments(), width)
+                .into_iter()
+                .map(|segment| segment.text)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            texts(80),
+            [
+                "USER (read-only)",
+                "3 marked",
+                "MACHINE: 120/2047",
+                "Copied 3 path(s)"
+            ]
+        );
+        assert_eq!(texts(40), ["USER", "3✓", "M:120", "Copied 3 path(s)"]);
+        assert_eq!(texts(25), ["USER", "Copied 3 path(s)"]);
+        // The message is cut short once nothing else can go
+        assert_eq!(texts(12), ["Copied 3 pa…"]);
+    }
+
+    #[test]
+    fn test_render_is_deterministic() {
+        let app = sample_app();
+        assert_eq!(render(&app), render(&app));
+    }
+}
@@ -0,0 +1,553 @@
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::digest::sha256_hex;
+use crate::release_notes::CURRENT_VERSION;
+use crate::security_scan::{SecurityProbe, SystemProbe};
+
+/// GitHub API listing of releases, newest first
+/// The repository holds several tools, so the latest release may not be this one's
+const RELEASES_URL: &str = "https://api.github.com/repos/jesse-slaton/cli-tools/releases";
+
+/// Release asset holding the executable, uploaded next to the zip by release.yml
+const EXECUTABLE_ASSET: &str = "pc.exe";
+
+/// Release asset holding the SHA-256 of the executable, as written by sha256sum
+const CHECKSUM_ASSET: &str = "pc.exe.sha256";
+
+/// Written last when staging, so a half-finished download is never installed
+const VERSION_FILE: &str = "version";
+
+/// SHA-256 and signature of the staged executable as checked after the download,
+/// checked again before installing it
+const VERIFIED_FILE: &str = "verified";
+
+/// A published release of Path Commander
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Update {
+    /// "0.7.0", without the tag's prefix
+    pub version: String,
+    pub page_url: String,
+    pub executable_url: String,
+    /// None if the release publishes no checksum, such a release is never installed
+    pub checksum_url: Option<String>,
+}
+
+impl Update {
+    /// Newer than the running version
+    pub fn is_newer(&self) -> bool {
+        is_newer(&self.version, CURRENT_VERSION)
+    }
+}
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    html_url: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Deserialize)]
+struct GithubAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// The newest stable release that ships pc.exe, from the GitHub releases listing
+pub fn parse_releases(json: &str) -> Result<Option<Update>> {
+    let releases: Vec<GithubRelease> = serde_json::from_str(json)?;
+    let asset = |release: &GithubRelease, name: &str| {
+        release
+            .assets
+            .iter()
+            .find(|asset| asset.name.eq_ignore_ascii_case(name))
+            .map(|asset| asset.browser_download_url.clone())
+    };
+
+    Ok(releases
+        .iter()
+        .filter(|release| !release.draft && !release.prerelease)
+        .find_map(|release| {
+            Some(Update {
+                // Tags look like "v0.7.0" or "path-commander-v0.7.0"
+                version: release
+                    .tag_name
+                    .trim_start_matches(|c: char| !c.is_ascii_digit())
+                    .to_string(),
+                page_url: release.html_url.clone(),
+                executable_url: asset(release, EXECUTABLE_ASSET)?,
+                checksum_url: asset(release, CHECKSUM_ASSET),
+            })
+        }))
+}
+
+/// Numeric parts of a version, pre-release and build suffixes ignored
+fn version_parts(version: &str) -> Vec<u64> {
+    version
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// Compare versions part by part, "0.10.0" is newer than "0.9.1"
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    version_parts(candidate) > version_parts(current)
+}
+
+/// The digest in a checksum file, either the bare hex or sha256sum's "<hex>  pc.exe"
+pub fn parse_checksum(text: &str) -> Option<String> {
+    let digest = text.split_whitespace().next()?;
+    (digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| digest.to_ascii_lowercase())
+}
+
+/// Ask GitHub for the newest release
+pub fn latest() -> Result<Option<Update>> {
+    let listing = http_get(RELEASES_URL)?;
+    parse_releases(&String::from_utf8_lossy(&listing))
+}
+
+/// Download the update, verify it and leave it in `staging` for the next start
+/// Returns the staged version
+pub fn download(update: &Update, staging: &Path) -> Result<String> {
+    // Nothing gets installed that cannot be checked against the release
+    let checksum_url = update.checksum_url.as_deref().ok_or_else(|| {
+        anyhow!(
+            "v{} publishes no {}, not installing it",
+            update.version,
+            CHECKSUM_ASSET
+        )
+    })?;
+    let expected = parse_checksum(&String::from_utf8_lossy(&http_get(checksum_url)?))
+        .ok_or_else(|| anyhow!("{} of v{} is unreadable", CHECKSUM_ASSET, update.version))?;
+    let executable = http_get(&update.executable_url)?;
+    if sha256_hex(&executable)? != expected {
+        bail!(
+            "Download of v{} does not match its checksum",
+            update.version
+        );
+    }
+
+    // A leftover staging directory may hold an older download
+    let _ = fs::remove_dir_all(staging);
+    fs::create_dir_all(staging)?;
+    let staged = staging.join(EXECUTABLE_ASSET);
+    fs::write(&staged, &executable)?;
+
+    // A signed build is only ever replaced by a signed build
+    let signed = SystemProbe.is_signed(&staged) == Some(true);
+    if running_signed() && !signed {
+        let _ = fs::remove_dir_all(staging);
+        bail!("Download of v{} has no valid signature", update.version);
+    }
+
+    fs::write(
+        staging.join(VERIFIED_FILE),
+        Verified {
+            sha256: expected,
+            signed,
+        }
+        .to_text(),
+    )?;
+    fs::write(staging.join(VERSION_FILE), &update.version)?;
+    Ok(update.version.clone())
+}
+
+/// What `download` checked, "sha256 <hex>" and "signed yes|no" lines
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Verified {
+    sha256: String,
+    signed: bool,
+}
+
+impl Verified {
+    fn to_text(&self) -> String {
+        format!(
+            "sha256 {}\nsigned {}\n",
+            self.sha256,
+            if self.signed { "yes" } else { "no" }
+        )
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let mut sha256 = None;
+        let mut signed = None;
+        for line in text.lines() {
+            match line.trim().split_once(' ') {
+                Some(("sha256", digest)) => sha256 = parse_checksum(digest),
+                Some(("signed", flag)) => signed = Some(flag.trim() == "yes"),
+                _ => {}
+            }
+        }
+        Some(Self {
+            sha256: sha256?,
+            signed: signed?,
+        })
+    }
+}
+
+fn running_signed() -> bool {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| SystemProbe.is_signed(&exe))
+        == Some(true)
+}
+
+/// Whether this user can create files next to `exe`
+/// An update never writes where only an administrator could
+pub fn folder_writable(exe: &Path) -> bool {
+    let Some(dir) = exe.parent() else {
+        return false;
+    };
+    let probe = dir.join(format!(".pc-update-{}", std::process::id()));
+    match fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+    {
+        Ok(_) => {
+            let _ = fs::remove_file(&probe);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Replace `exe` with the update staged by `download`, returns the installed version
+/// The staged file is checked again against what `download` verified, anything else that
+/// turned up in the staging directory is thrown away
+/// The replaced executable is kept next to it as <name>.old until the next update
+pub fn install_staged(staging: &Path, exe: &Path) -> Result<Option<String>> {
+    let Ok(version) = fs::read_to_string(staging.join(VERSION_FILE)) else {
+        return Ok(None);
+    };
+    let version = version.trim().to_string();
+    // Updated some other way in the meantime
+    if !is_newer(&version, CURRENT_VERSION) {
+        let _ = fs::remove_dir_all(staging);
+        return Ok(None);
+    }
+    if !folder_writable(exe) {
+        bail!(
+            "{} is not writable without administrator rights, install v{} from the release page",
+            exe.parent().unwrap_or(exe).display(),
+            version
+        );
+    }
+
+    let staged = staging.join(EXECUTABLE_ASSET);
+    let verified = fs::read_to_string(staging.join(VERIFIED_FILE))
+        .ok()
+        .and_then(|text| Verified::parse(&text));
+    let discard = |reason: &str| -> Result<Option<String>> {
+        let _ = fs::remove_dir_all(staging);
+        bail!("Staged v{} {}, not installing it", version, reason)
+    };
+    let Some(verified) = verified else {
+        return discard("was not verified when downloaded");
+    };
+    // Checked before reading the bytes, which are then hashed and written as read
+    if (verified.signed || running_signed()) && SystemProbe.is_signed(&staged) != Some(true) {
+        return discard("has no valid signature");
+    }
+    let executable = fs::read(&staged)?;
+    if sha256_hex(&executable).ok().as_deref() != Some(verified.sha256.as_str()) {
+        return discard("changed since it was downloaded");
+    }
+
+    let mut old = exe.as_os_str().to_owned();
+    old.push(".old");
+    let _ = fs::remove_file(&old);
+    // Windows cannot overwrite a running executable, but it can rename it
+    fs::rename(exe, &old)?;
+    if let Err(e) = fs::write(exe, &executable) {
+        let _ = fs::rename(&old, exe);
+        return Err(e.into());
+    }
+    let _ = fs::remove_dir_all(staging);
+    Ok(Some(version))
+}
+
+/// Fetch a URL over HTTPS, following redirects to the download host
+#[cfg(windows)]
+fn http_get(url: &str) -> Result<Vec<u8>> {
+    use crate::registry::to_wide_string;
+    use windows::core::PCWSTR;
+    use windows::Win32::Networking::WinHttp::{
+        WinHttpCloseHandle, WinHttpConnect, WinHttpOpen, WinHttpOpenRequest, WinHttpQueryHeaders,
+        WinHttpReadData, WinHttpReceiveResponse, WinHttpSendRequest, INTERNET_DEFAULT_HTTPS_PORT,
+        WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY, WINHTTP_FLAG_SECURE, WINHTTP_QUERY_FLAG_NUMBER,
+        WINHTTP_QUERY_STATUS_CODE,
+    };
+
+    let rest = url
+        .strip_prefix("https://")
+        .ok_or_else(|| anyhow!("Only HTTPS downloads are allowed: {}", url))?;
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let host = to_wide_string(host);
+    let path = to_wide_string(&format!("/{}", path));
+    // GitHub rejects API requests without a user agent
+    let agent = to_wide_string(&format!("PathCommander/{}", CURRENT_VERSION));
+    let verb = to_wide_string("GET");
+    let headers: Vec<u16> = "Accept: application/vnd.github+json\r\n"
+        .encode_utf16()
+        .collect();
+
+    unsafe {
+        let session = WinHttpOpen(
+            PCWSTR(agent.as_ptr()),
+            WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY,
+            PCWSTR::null(),
+            PCWSTR::null(),
+            0,
+        );
+        if session.is_null() {
+            bail!("Could not start an HTTP session");
+        }
+        let connection = WinHttpConnect(
+            session,
+            PCWSTR(host.as_ptr()),
+            INTERNET_DEFAULT_HTTPS_PORT,
+            0,
+        );
+        let request = if connection.is_null() {
+            std::ptr::null_mut()
+        } else {
+            WinHttpOpenRequest(
+                connection,
+                PCWSTR(verb.as_ptr()),
+                PCWSTR(path.as_ptr()),
+                PCWSTR::null(),
+                PCWSTR::null(),
+                std::ptr::null(),
+                WINHTTP_FLAG_SECURE,
+            )
+        };
+
+        let result = (|| -> Result<Vec<u8>> {
+            if request.is_null() {
+                bail!("Could not reach {}", url);
+            }
+            WinHttpSendRequest(request, Some(&headers), None, 0, 0, 0)?;
+            WinHttpReceiveResponse(request, std::ptr::null_mut())?;
+
+            let mut status: u32 = 0;
+            let mut size = std::mem::size_of::<u32>() as u32;
+            WinHttpQueryHeaders(
+                request,
+                WINHTTP_QUERY_STATUS_CODE | WINHTTP_QUERY_FLAG_NUMBER,
+                PCWSTR::null(),
+                Some(&mut status as *mut u32 as *mut core::ffi::c_void),
+                &mut size,
+                std::ptr::null_mut(),
+            )?;
+            if status != 200 {
+                bail!("{} answered HTTP {}", url, status);
+            }
+
+            let mut body = Vec::new();
+            let mut buffer = vec![0u8; 64 * 1024];
+            loop {
+                let mut read = 0u32;
+                WinHttpReadData(
+                    request,
+                    buffer.as_mut_ptr() as *mut core::ffi::c_void,
+                    buffer.len() as u32,
+                    &mut read,
+                )?;
+                if read == 0 {
+                    break;
+                }
+                body.extend_from_slice(&buffer[..read as usize]);
+            }
+            Ok(body)
+        })();
+
+        for handle in [request, connection, session] {
+            if !handle.is_null() {
+                let _ = WinHttpCloseHandle(handle);
+            }
+        }
+        result
+    }
+}
+
+/// Only the Windows build downloads updates
+#[cfg(not(windows))]
+fn http_get(_url: &str) -> Result<Vec<u8>> {
+    bail!("Update checks are only available on Windows")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_releases_skips_other_tools_and_prereleases() {
+        let json = r#"[
+            {"tag_name": "path-commander-v9.1.0-rc1", "html_url": "https://example.com/rc",
+             "prerelease": true,
+             "assets": [{"name": "pc.exe", "browser_download_url": "https://example.com/rc/pc.exe"}]},
+            {"tag_name": "other-tool-v2.0.0", "html_url": "https://example.com/other",
+             "assets": [{"name": "other.exe", "browser_download_url": "https://example.com/other.exe"}]},
+            {"tag_name": "v9.0.0", "html_url": "https://example.com/v9",
+             "assets": [
+                {"name": "PC.exe", "browser_download_url": "https://example.com/v9/pc.exe"},
+                {"name": "pc.exe.sha256", "browser_download_url": "https://example.com/v9/pc.exe.sha256"}
+             ]}
+        ]"#;
+        let update = parse_releases(json).unwrap().unwrap();
+        assert_eq!(update.version, "9.0.0");
+        assert_eq!(update.executable_url, "https://example.com/v9/pc.exe");
+        assert_eq!(
+            update.checksum_url.as_deref(),
+            Some("https://example.com/v9/pc.exe.sha256")
+        );
+        assert!(update.is_newer());
+
+        assert_eq!(parse_releases("[]").unwrap(), None);
+        assert!(parse_releases("{\"message\": \"rate limited\"}").is_err());
+    }
+
+    #[test]
+    fn test_parse_releases_finds_the_workflow_assets() {
+        // Asset names as .github/workflows/release.yml publishes them
+        let json = r#"[
+            {"tag_name": "v0.9.0", "html_url": "https://example.com/v0.9.0",
+             "assets": [
+                {"name": "path-commander-0.9.0-windows-x64.zip",
+                 "browser_download_url": "https://example.com/path-commander-0.9.0-windows-x64.zip"},
+                {"name": "path-commander-0.9.0-windows-x64.zip.sha256",
+                 "browser_download_url": "https://example.com/path-commander-0.9.0-windows-x64.zip.sha256"},
+                {"name": "pc.exe", "browser_download_url": "https://example.com/pc.exe"},
+                {"name": "pc.exe.sha256", "browser_download_url": "https://example.com/pc.exe.sha256"}
+             ]},
+            {"tag_name": "v0.8.0", "html_url": "https://example.com/v0.8.0",
+             "assets": [
+                {"name": "path-commander-0.8.0-windows-x64.zip",
+                 "browser_download_url": "https://example.com/path-commander-0.8.0-windows-x64.zip"}
+             ]}
+        ]"#;
+        let update = parse_releases(json).unwrap().unwrap();
+        assert_eq!(update.version, "0.9.0");
+        assert_eq!(update.executable_url, "https://example.com/pc.exe");
+        assert_eq!(
+            update.checksum_url.as_deref(),
+            Some("https://example.com/pc.exe.sha256")
+        );
+
+        // Releases from before the executable was published on its own are skipped
+        let older = json.replacen("pc.exe", "gone.exe", 2);
+        assert_eq!(parse_releases(&older).unwrap(), None);
+    }
+
+    #[test]
+    fn test_versions_and_checksums() {
+        assert!(is_newer("0.10.0", "0.9.1"));
+        assert!(is_newer("1.0", "0.6.2"));
+        assert!(!is_newer("0.6.2", "0.6.2"));
+        assert!(!is_newer("0.6.2-beta", "0.6.2"));
+
+        let digest = "AB".repeat(32);
+        assert_eq!(
+            parse_checksum(&format!("{}  pc.exe\n", digest)),
+            Some("ab".repeat(32))
+        );
+        assert_eq!(parse_checksum("not a checksum"), None);
+        assert_eq!(parse_checksum(""), None);
+    }
+
+    #[test]
+    fn test_verified_record_round_trips() {
+        let verified = Verified {
+            sha256: "ab".repeat(32),
+            signed: true,
+        };
+        assert_eq!(Verified::parse(&verified.to_text()), Some(verified));
+        assert_eq!(Verified::parse("signed no\n"), None);
+        assert_eq!(Verified::parse("sha256 1234\nsigned no\n"), None);
+    }
+
+    #[test]
+    fn test_install_staged_refuses_unverified_downloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("pc.exe");
+        let staging = dir.path().join("update");
+        fs::write(&exe, "old build").unwrap();
+        assert!(folder_writable(&exe));
+
+        // Dropped into the staging directory without going through download
+        fs::create_dir_all(&staging).unwrap();
+        fs::write(staging.join(EXECUTABLE_ASSET), "planted build").unwrap();
+        fs::write(staging.join(VERSION_FILE), "99.0.0").unwrap();
+        assert!(install_staged(&staging, &exe).is_err());
+        assert_eq!(fs::read_to_string(&exe).unwrap(), "old build");
+        assert!(!staging.exists());
+
+        // A record that does not match what is staged
+        fs::create_dir_all(&staging).unwrap();
+        fs::write(staging.join(EXECUTABLE_ASSET), "planted build").unwrap();
+        let verified = Verified {
+            sha256: "00".repeat(32),
+            signed: false,
+        };
+        fs::write(staging.join(VERIFIED_FILE), verified.to_text()).unwrap();
+        fs::write(staging.join(VERSION_FILE), "99.0.0").unwrap();
+        assert!(install_staged(&staging, &exe).is_err());
+        assert_eq!(fs::read_to_string(&exe).unwrap(), "old build");
+        assert!(!staging.exists());
+    }
+
+    // Hashing is only built on Windows, the one platform that downloads updates
+    #[cfg(windows)]
+    #[test]
+    fn test_install_staged_swaps_executable() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("pc.exe");
+        let staging = dir.path().join("update");
+        fs::write(&exe, "old build").unwrap();
+
+        // Nothing staged, nothing to do
+        assert_eq!(install_staged(&staging, &exe).unwrap(), None);
+
+        // An executable without the version marker is an unfinished download
+        fs::create_dir_all(&staging).unwrap();
+        fs::write(staging.join(EXECUTABLE_ASSET), "new build").unwrap();
+        assert_eq!(install_staged(&staging, &exe).unwrap(), None);
+
+        let verified = Verified {
+            sha256: sha256_hex(b"new build").unwrap(),
+            signed: false,
+        };
+        fs::write(staging.join(VERIFIED_FILE), verified.to_text()).unwrap();
+        fs::write(staging.join(VERSION_FILE), "99.0.0").unwrap();
+        assert_eq!(
+            install_staged(&staging, &exe).unwrap(),
+            Some("99.0.0".to_string())
+        );
+        assert_eq!(fs::read_to_string(&exe).unwrap(), "new build");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("pc.exe.old")).unwrap(),
+            "old build"
+        );
+        assert!(!staging.exists());
+
+        // A staged version that is not newer is thrown away
+        fs::create_dir_all(&staging).unwrap();
+        fs::write(staging.join(EXECUTABLE_ASSET), "older build").unwrap();
+        fs::write(staging.join(VERSION_FILE), CURRENT_VERSION).unwrap();
+        assert_eq!(install_staged(&staging, &exe).unwrap(), None);
+        assert_eq!(fs::read_to_string(&exe).unwrap(), "new build");
+        assert!(!staging.exists());
+    }
+}
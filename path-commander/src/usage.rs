@@ -0,0 +1,306 @@
+use chrono::NaiveDate;
+use std::collections::{BTreeSet, HashMap};
+use std::io;
+use std::path::Path;
+
+use crate::path_analyzer::comparable;
+
+/// Days the log has to cover before a directory without runs counts as unused
+pub const MIN_LOG_DAYS: i64 = 14;
+
+/// Runs that make a directory worth moving ahead of unused ones
+pub const HOT_RUNS: u32 = 10;
+
+/// Commands that ran from one directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirectoryUsage {
+    pub runs: u32,
+    pub last_run: Option<NaiveDate>,
+    pub commands: BTreeSet<String>,
+}
+
+/// What the usage hook recorded, one line per command run: date, directory and file name
+/// separated by tabs
+#[derive(Debug, Clone, Default)]
+pub struct UsageLog {
+    directories: HashMap<String, DirectoryUsage>,
+    pub first_day: Option<NaiveDate>,
+    pub last_day: Option<NaiveDate>,
+}
+
+impl UsageLog {
+    /// Lines that don't parse are skipped, a line cut off by a crash is no reason to fail
+    pub fn parse(text: &str) -> Self {
+        let mut log = Self::default();
+        for line in text.lines() {
+            let mut fields = line.trim_end_matches('\r').split('\t');
+            let (Some(date), Some(dir), Some(command)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let Ok(date) = NaiveDate::parse_from_str(date, "%Y-%m-%d") else {
+                continue;
+            };
+            if dir.trim().is_empty() {
+                continue;
+            }
+
+            let usage = log.directories.entry(comparable(dir)).or_default();
+            usage.runs += 1;
+            usage.last_run = usage.last_run.max(Some(date));
+            usage.commands.insert(command.to_lowercase());
+            log.first_day = Some(log.first_day.map_or(date, |first| first.min(date)));
+            log.last_day = log.last_day.max(Some(date));
+        }
+        log
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?))
+    }
+
+    /// Days from the first to the last recorded run, both included
+    pub fn days(&self) -> i64 {
+        match (self.first_day, self.last_day) {
+            (Some(first), Some(last)) => (last - first).num_days() + 1,
+            _ => 0,
+        }
+    }
+
+    /// Usage of an expanded directory, compared like Windows compares paths
+    pub fn usage(&self, dir: &str) -> Option<&DirectoryUsage> {
+        self.directories.get(&comparable(dir))
+    }
+
+    pub fn runs(&self, dir: &str) -> u32 {
+        self.usage(dir).map_or(0, |usage| usage.runs)
+    }
+}
+
+/// Where a suggestion moves an entry within its panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Move {
+    /// Ahead of the unused entries right above it, to this position
+    Promote { to: usize },
+    /// To the end of the panel
+    Demote,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Suggestion {
+    pub index: usize,
+    pub runs: u32,
+    pub action: Move,
+}
+
+/// Suggestions for one panel, given its entries expanded
+/// Only entries that ran nothing are jumped over, so no recorded command resolves to a
+/// different file afterwards; `pinned` entries (system directories) are neither demoted
+/// nor jumped over
+pub fn suggest(dirs: &[String], pinned: &[bool], log: &UsageLog) -> Vec<Suggestion> {
+    if log.days() < MIN_LOG_DAYS {
+        return Vec::new();
+    }
+    let runs: Vec<u32> = dirs.iter().map(|dir| log.runs(dir)).collect();
+    let movable = |index: usize| runs[index] == 0 && !pinned.get(index).copied().unwrap_or(false);
+    let mut suggestions = Vec::new();
+
+    for (index, &count) in runs.iter().enumerate() {
+        if count < HOT_RUNS {
+            continue;
+        }
+        let to = (0..index)
+            .rev()
+            .take_while(|&above| movable(above))
+            .last()
+            .unwrap_or(index);
+        if to < index {
+            suggestions.push(Suggestion {
+                index,
+                runs: count,
+                action: Move::Promote { to },
+            });
+        }
+    }
+
+    // Unused entries already at the end stay where they are
+    let last_used = (0..dirs.len()).rev().find(|&index| !movable(index));
+    for index in 0..dirs.len() {
+        if movable(index) && last_used.is_some_and(|last| index < last) {
+            suggestions.push(Suggestion {
+                index,
+                runs: 0,
+                action: Move::Demote,
+            });
+        }
+    }
+    suggestions
+}
+
+/// A panel's entries after the chosen suggestions: promoted entries first, then the demoted
+/// ones go to the end in their order
+pub fn reorder(paths: &[String], chosen: &[Suggestion]) -> Vec<String> {
+    let mut order: Vec<usize> = (0..paths.len()).collect();
+    let mut promotions: Vec<(usize, usize)> = chosen
+        .iter()
+        .filter_map(|suggestion| match suggestion.action {
+            Move::Promote { to } => Some((suggestion.index, to)),
+            Move::Demote => None,
+        })
+        .collect();
+    promotions.sort_unstable();
+    for (index, to) in promotions {
+        let (Some(from), Some(before)) = (
+            order.iter().position(|&i| i == index),
+            order.iter().position(|&i| i == to),
+        ) else {
+            continue;
+        };
+        if before < from {
+            let moved = order.remove(from);
+            order.insert(before, moved);
+        }
+    }
+
+    let demoted: BTreeSet<usize> = chosen
+        .iter()
+        .filter(|suggestion| suggestion.action == Move::Demote)
+        .map(|suggestion| suggestion.index)
+        .collect();
+    let (kept, moved): (Vec<usize>, Vec<usize>) = order
+        .into_iter()
+        .partition(|index| !demoted.contains(index));
+    kept.into_iter()
+        .chain(moved)
+        .filter_map(|index| paths.get(index).cloned())
+        .collect()
+}
+
+/// PowerShell profile snippet that appends a line to the log for each external command
+/// run at the prompt; nothing is recorded until it is added to $PROFILE
+pub fn powershell_hook(log: &Path) -> String {
+    r#"# Path Commander usage log for PowerShell: records which PATH directory each command runs from
+# Add to $PROFILE: pc usage-hook | Out-String | Invoke-Expression
+# Remove that line to stop recording, delete the log to forget what was recorded
+if (-not $global:PcUsageHook) {
+    $global:PcUsageHook = $ExecutionContext.InvokeCommand.PostCommandLookupAction
+    if (-not $global:PcUsageHook) { $global:PcUsageHook = {} }
+    $ExecutionContext.InvokeCommand.PostCommandLookupAction = {
+        param($name, $lookup)
+        & $global:PcUsageHook $name $lookup
+        $command = $lookup.Command
+        if ($lookup.CommandOrigin -eq 'Runspace' -and $command.CommandType -eq 'Application') {
+            $line = "{0:yyyy-MM-dd}`t{1}`t{2}" -f (Get-Date), (Split-Path -Parent $command.Source), $command.Name
+            Add-Content -LiteralPath 'LOG' -Value $line -ErrorAction SilentlyContinue
+        }
+    }
+}
+"#
+    .replace("'LOG'", &format!("'{}'", log.display().to_string().replace('\'', "''")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_over(days: i64, runs: &[(&str, u32)]) -> UsageLog {
+        let first = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        let last = first + chrono::Duration::days(days - 1);
+        let mut text = format!("{}\tC:\\Other\tother.exe\n", first.format("%Y-%m-%d"));
+        for (dir, count) in runs {
+            for _ in 0..*count {
+                text.push_str(&format!("{}\t{}\ttool.exe\n", last.format("%Y-%m-%d"), dir));
+            }
+        }
+        UsageLog::parse(&text)
+    }
+
+    #[test]
+    fn test_parse_skips_bad_lines() {
+        let log = UsageLog::parse(
+            "2026-03-01\tC:\\Tools\\bin\\\tgit.exe\r\n\
+             2026-03-04\tc:\\tools\\bin\tGIT.EXE\n\
+             not a line\n\
+             2026-13-40\tC:\\Tools\\bin\tgit.exe\n\
+             2026-03-02\t\tgit.exe\n",
+        );
+        let usage = log.usage(r"C:\TOOLS\BIN").unwrap();
+        assert_eq!(usage.runs, 2);
+        assert_eq!(usage.last_run, NaiveDate::from_ymd_opt(2026, 3, 4));
+        assert_eq!(usage.commands.len(), 1);
+        assert_eq!(log.days(), 4);
+        assert_eq!(log.runs(r"C:\Missing"), 0);
+        assert_eq!(UsageLog::default().days(), 0);
+    }
+
+    #[test]
+    fn test_suggest_and_reorder() {
+        let dirs: Vec<String> = [
+            "C:\\Windows",
+            "C:\\Old",
+            "C:\\Unused",
+            "C:\\Hot",
+            "C:\\Idle",
+        ]
+        .iter()
+        .map(|dir| dir.to_string())
+        .collect();
+        let pinned = [true, false, false, false, false];
+        let log = log_over(30, &[(r"C:\Hot", 12)]);
+
+        // The hot entry jumps the two unused ones, but not the system directory; the
+        // unused entry already at the end stays
+        let suggestions = suggest(&dirs, &pinned, &log);
+        assert_eq!(
+            suggestions,
+            vec![
+                Suggestion {
+                    index: 3,
+                    runs: 12,
+                    action: Move::Promote { to: 1 }
+                },
+                Suggestion {
+                    index: 1,
+                    runs: 0,
+                    action: Move::Demote
+                },
+                Suggestion {
+                    index: 2,
+                    runs: 0,
+                    action: Move::Demote
+                },
+            ]
+        );
+        assert_eq!(
+            reorder(&dirs, &suggestions),
+            vec![
+                "C:\\Windows",
+                "C:\\Hot",
+                "C:\\Idle",
+                "C:\\Old",
+                "C:\\Unused"
+            ]
+        );
+        assert_eq!(
+            reorder(&dirs, &suggestions[..1]),
+            vec![
+                "C:\\Windows",
+                "C:\\Hot",
+                "C:\\Old",
+                "C:\\Unused",
+                "C:\\Idle"
+            ]
+        );
+
+        // Two weeks of data are needed before anything counts as unused
+        assert!(suggest(&dirs, &pinned, &log_over(13, &[(r"C:\Hot", 12)])).is_empty());
+    }
+
+    #[test]
+    fn test_powershell_hook_names_the_log() {
+        let hook = powershell_hook(Path::new(r"C:\Users\o'neil\.pc\usage.log"));
+        assert!(hook.contains(r"Add-Content -LiteralPath 'C:\Users\o''neil\.pc\usage.log'"));
+        assert!(hook.contains("PostCommandLookupAction"));
+    }
+}